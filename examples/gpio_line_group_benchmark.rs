@@ -0,0 +1,154 @@
+// gpio_line_group_benchmark.rs - Measures how many times per second the
+// output states of an 8-bit parallel bus can be changed.
+//
+// Compares LineGroup::write_atomic, which writes directly to the GPIO set and
+// clear registers, against changing each OutputPin individually, and against
+// the kernel's gpiochip character device (GPIOHANDLE_SET_LINE_VALUES_IOCTL).
+//
+// Remember to disconnect anything that shouldn't be toggled at high speed from
+// the pins listed in GPIO_BUS before running this example.
+
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io;
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::time::{Duration, Instant};
+
+use rppal::gpio::{Gpio, LineGroup, OutputPin};
+
+// Gpio uses BCM pin numbering. BCM GPIO 5 through 12 are all located in the
+// first register bank on every model.
+const GPIO_BUS: [u8; 8] = [5, 6, 7, 8, 9, 10, 11, 12];
+
+// Minimum duration of each benchmark.
+const DURATION: Duration = Duration::from_secs(2);
+
+const PATH_GPIOCHIP: &str = "/dev/gpiochip0";
+const HANDLES_MAX: usize = 64;
+const HANDLE_REQUEST_OUTPUT: u32 = 1 << 1;
+
+// struct gpiohandle_request from linux/gpio.h
+#[repr(C)]
+struct HandleRequest {
+    line_offsets: [u32; HANDLES_MAX],
+    flags: u32,
+    default_values: [u8; HANDLES_MAX],
+    consumer_label: [u8; 32],
+    lines: u32,
+    fd: std::os::raw::c_int,
+}
+
+// struct gpiohandle_data from linux/gpio.h
+#[repr(C)]
+struct HandleData {
+    values: [u8; HANDLES_MAX],
+}
+
+// Equivalent of the _IOWR macro for the GPIO ioctl type.
+fn iowr(nr: u64, size: usize) -> u64 {
+    (3 << 30) | ((size as u64) << 16) | (0xb4 << 8) | nr
+}
+
+// Calls write repeatedly for at least DURATION, and prints the number of
+// writes per second.
+fn bench<F>(name: &str, mut write: F) -> io::Result<()>
+where
+    F: FnMut(u8) -> io::Result<()>,
+{
+    let start = Instant::now();
+    let mut count: u64 = 0;
+
+    while start.elapsed() < DURATION {
+        // Check the elapsed time in batches to limit the overhead of Instant
+        for _ in 0..1000 {
+            write(count as u8)?;
+            count += 1;
+        }
+    }
+
+    println!(
+        "{:<36} {:>12.0} writes/s",
+        name,
+        count as f64 / start.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
+
+fn bench_chardev() -> io::Result<()> {
+    let gpiochip = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(PATH_GPIOCHIP)?;
+
+    let mut request = HandleRequest {
+        line_offsets: [0; HANDLES_MAX],
+        flags: HANDLE_REQUEST_OUTPUT,
+        default_values: [0; HANDLES_MAX],
+        consumer_label: [0; 32],
+        lines: GPIO_BUS.len() as u32,
+        fd: 0,
+    };
+
+    for (offset, &pin) in request.line_offsets.iter_mut().zip(GPIO_BUS.iter()) {
+        *offset = u32::from(pin);
+    }
+
+    let req_get_line_handle = iowr(0x03, mem::size_of::<HandleRequest>());
+    if unsafe { libc::ioctl(gpiochip.as_raw_fd(), req_get_line_handle as _, &mut request) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let req_set_line_values = iowr(0x09, mem::size_of::<HandleData>());
+    let mut data = HandleData {
+        values: [0; HANDLES_MAX],
+    };
+
+    let result = bench("gpiochip (SET_LINE_VALUES ioctl)", |value| {
+        for (idx, level) in data.values[..GPIO_BUS.len()].iter_mut().enumerate() {
+            *level = (value >> idx) & 0b1;
+        }
+
+        if unsafe { libc::ioctl(request.fd, req_set_line_values as _, &mut data) } == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    });
+
+    unsafe {
+        libc::close(request.fd);
+    }
+
+    result
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // The line handle is released before the pins are retrieved through Gpio.
+    bench_chardev()?;
+
+    let gpio = Gpio::new()?;
+    let mut pins = GPIO_BUS
+        .iter()
+        .map(|&pin| Ok(gpio.get(pin)?.into_output_low()))
+        .collect::<rppal::gpio::Result<Vec<OutputPin>>>()?;
+
+    bench("OutputPin::write (one pin at a time)", |value| {
+        for (idx, pin) in pins.iter_mut().enumerate() {
+            pin.write(((value >> idx) & 0b1).into());
+        }
+
+        Ok(())
+    })?;
+
+    let mut group = LineGroup::new(pins);
+
+    bench("LineGroup::write_atomic", |value| {
+        group.write_atomic(u64::from(value), 0xff);
+
+        Ok(())
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+// gpio_multithreaded_shared.rs - Retrieves and releases overlapping sets of
+// pins from multiple threads, each using its own Gpio handle, and verifies
+// that a pin is never in use by more than one thread at the same time.
+//
+// The pins are only retrieved, and not reconfigured, so this example doesn't
+// change the state of any connected hardware.
+
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use rppal::gpio::{self, Gpio};
+
+// Each thread retrieves a window of pins that overlaps with its neighbors.
+const FIRST_PIN: u8 = 2;
+const PINS_PER_THREAD: u8 = 4;
+const NUM_THREADS: u8 = 8;
+const ITERATIONS: usize = 10_000;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Keeps track of the pins our threads believe they own.
+    let owned: Arc<Vec<AtomicBool>> =
+        Arc::new((0..u8::MAX).map(|_| AtomicBool::new(false)).collect());
+
+    let mut threads = Vec::with_capacity(NUM_THREADS as usize);
+    for thread_id in 0..NUM_THREADS {
+        let owned = Arc::clone(&owned);
+
+        threads.push(thread::spawn(move || -> gpio::Result<usize> {
+            // Every Gpio instance shares the same internal state.
+            let gpio = Gpio::new()?;
+            let start = FIRST_PIN + thread_id;
+            let mut acquired = 0;
+
+            for i in 0..ITERATIONS {
+                let pin_number = start + (i as u8 % PINS_PER_THREAD);
+
+                match gpio.get(pin_number) {
+                    Ok(pin) => {
+                        // If get() succeeded, no other thread may own the pin.
+                        assert!(!owned[pin_number as usize].swap(true, Ordering::SeqCst));
+                        acquired += 1;
+
+                        // Release our claim before the pin is dropped.
+                        owned[pin_number as usize].store(false, Ordering::SeqCst);
+                        drop(pin);
+                    }
+                    Err(gpio::Error::PinUsed(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            Ok(acquired)
+        }));
+    }
+
+    for (thread_id, thread) in threads.into_iter().enumerate() {
+        let acquired = thread.join().unwrap()?;
+        println!("Thread {} acquired a pin {} times.", thread_id, acquired);
+    }
+
+    // After all pins have been dropped, every pin should be available again.
+    let gpio = Gpio::new()?;
+    for pin_number in FIRST_PIN..FIRST_PIN + NUM_THREADS + PINS_PER_THREAD - 1 {
+        gpio.get(pin_number)?;
+    }
+
+    println!("All pins were released.");
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+// hal_compat.rs - Passes the same peripherals to a driver written for
+// embedded-hal v0.2.7 and a driver written for embedded-hal v1.0.0-alpha.9.
+//
+// Requires both the hal-0 and hal-1 features, which are enabled by the hal
+// feature: cargo run --example hal_compat --features hal
+//
+// Reads the chip ID of a Bosch BME280 sensor, and blinks an LED connected to
+// BCM GPIO 23.
+
+use std::error::Error;
+
+use rppal::gpio::Gpio;
+use rppal::hal::Delay;
+use rppal::i2c::I2c;
+
+// BME280 I2C default slave address and chip ID register.
+const ADDR_BME280: u8 = 0x76;
+const REG_CHIP_ID: u8 = 0xd0;
+
+// Gpio uses BCM pin numbering. BCM GPIO 23 is tied to physical pin 16.
+const GPIO_LED: u8 = 23;
+
+// Minimal driver written against the embedded-hal v0.2.7 traits.
+mod driver_eh0 {
+    use embedded_hal_0::blocking::delay::DelayMs;
+    use embedded_hal_0::blocking::i2c::WriteRead;
+    use embedded_hal_0::digital::v2::OutputPin;
+
+    pub fn read_register<I: WriteRead>(i2c: &mut I, address: u8, reg: u8) -> Result<u8, I::Error> {
+        let mut value = [0u8; 1];
+        i2c.write_read(address, &[reg], &mut value)?;
+
+        Ok(value[0])
+    }
+
+    pub fn blink<P: OutputPin, D: DelayMs<u16>>(
+        pin: &mut P,
+        delay: &mut D,
+    ) -> Result<(), P::Error> {
+        pin.set_high()?;
+        delay.delay_ms(250);
+        pin.set_low()?;
+        delay.delay_ms(250);
+
+        Ok(())
+    }
+}
+
+// Minimal driver written against the embedded-hal v1.0.0-alpha.9 traits.
+mod driver_eh1 {
+    use embedded_hal::delay::DelayUs;
+    use embedded_hal::digital::OutputPin;
+    use embedded_hal::i2c::I2c;
+
+    pub fn read_register<I: I2c>(i2c: &mut I, address: u8, reg: u8) -> Result<u8, I::Error> {
+        let mut value = [0u8; 1];
+        i2c.write_read(address, &[reg], &mut value)?;
+
+        Ok(value[0])
+    }
+
+    pub fn blink<P: OutputPin, D: DelayUs>(pin: &mut P, delay: &mut D) -> Result<(), P::Error> {
+        pin.set_high()?;
+        let _ = delay.delay_ms(250);
+        pin.set_low()?;
+        let _ = delay.delay_ms(250);
+
+        Ok(())
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut i2c = I2c::new()?;
+    let mut pin = Gpio::new()?.get(GPIO_LED)?.into_output();
+    let mut delay = Delay::new();
+
+    let id_eh0 = driver_eh0::read_register(&mut i2c, ADDR_BME280, REG_CHIP_ID)?;
+    let id_eh1 = driver_eh1::read_register(&mut i2c, ADDR_BME280, REG_CHIP_ID)?;
+
+    println!(
+        "Chip ID: {:#04x} (v0.2.7), {:#04x} (v1.0.0-alpha.9)",
+        id_eh0, id_eh1
+    );
+
+    driver_eh0::blink(&mut pin, &mut delay)?;
+    driver_eh1::blink(&mut pin, &mut delay)?;
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+// i2c_benchmark.rs - Measures the number of I2C transactions per second for
+// a few common access patterns.
+//
+// With the mock feature enabled, the transactions are sent to simulated
+// devices, which measures the overhead of RPPAL itself:
+// cargo run --release --example i2c_benchmark --features mock
+//
+// Without the mock feature, the transactions are sent to the slave devices
+// at the addresses listed in ADDR_DEVICES on I2C bus 1. Make sure the devices
+// don't change state when register 0x00 is read.
+
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use rppal::i2c::{self, I2c};
+
+// Slave addresses of the devices polled by the benchmark.
+const ADDR_DEVICES: [u16; 16] = [
+    0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d, 0x4e, 0x4f,
+];
+
+// Register read by each transaction.
+const REG_DATA: u8 = 0x00;
+
+// Minimum duration of each benchmark.
+const DURATION: Duration = Duration::from_secs(2);
+
+// Calls transaction repeatedly for at least DURATION, and prints the number
+// of transactions per second. clear_events is called after every batch.
+fn bench<F>(
+    name: &str,
+    i2c: &mut I2c,
+    clear_events: &dyn Fn(),
+    mut transaction: F,
+) -> i2c::Result<()>
+where
+    F: FnMut(&mut I2c, usize) -> i2c::Result<()>,
+{
+    let start = Instant::now();
+    let mut count = 0;
+
+    while start.elapsed() < DURATION {
+        // Check the elapsed time in batches to limit the overhead of Instant
+        for _ in 0..100 {
+            transaction(i2c, count)?;
+            count += 1;
+        }
+
+        clear_events();
+    }
+
+    println!(
+        "{:<44} {:>10.0} transactions/s",
+        name,
+        count as f64 / start.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    #[cfg(feature = "mock")]
+    let simulator = rppal::mock::Simulator::new();
+    #[cfg(feature = "mock")]
+    let mut i2c = {
+        for &address in ADDR_DEVICES.iter() {
+            simulator.add_i2c_device(1, address, rppal::mock::Registers::new());
+        }
+
+        I2c::with_simulator_bus(&simulator, 1)?
+    };
+    #[cfg(not(feature = "mock"))]
+    let mut i2c = I2c::with_bus(1)?;
+
+    // The simulator logs every transfer, so the log is cleared regularly to
+    // keep its memory usage from affecting the results
+    let clear_events = || {
+        #[cfg(feature = "mock")]
+        simulator.clear_events();
+    };
+
+    i2c.set_slave_address(ADDR_DEVICES[0])?;
+
+    bench("smbus_read_word", &mut i2c, &clear_events, |i2c, _| {
+        i2c.smbus_read_word(REG_DATA)?;
+        Ok(())
+    })?;
+
+    bench(
+        "write_read (1 byte out, 2 bytes in)",
+        &mut i2c,
+        &clear_events,
+        |i2c, _| {
+            let mut buffer = [0u8; 2];
+            i2c.write_read(&[REG_DATA], &mut buffer)
+        },
+    )?;
+
+    bench(
+        "set_slave_address + smbus_read_word (same)",
+        &mut i2c,
+        &clear_events,
+        |i2c, _| {
+            i2c.set_slave_address(ADDR_DEVICES[0])?;
+            i2c.smbus_read_word(REG_DATA)?;
+            Ok(())
+        },
+    )?;
+
+    bench(
+        "set_slave_address + smbus_read_word (16)",
+        &mut i2c,
+        &clear_events,
+        |i2c, count| {
+            i2c.set_slave_address(ADDR_DEVICES[count % ADDR_DEVICES.len()])?;
+            i2c.smbus_read_word(REG_DATA)?;
+            Ok(())
+        },
+    )?;
+
+    Ok(())
+}
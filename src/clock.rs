@@ -0,0 +1,492 @@
+//! Interface for the general purpose clock outputs (GPCLK).
+//!
+//! The BCM283x and BCM2711 SoCs contain three general purpose clocks,
+//! GPCLK0, GPCLK1 and GPCLK2, which output a square wave derived from one of
+//! the SoC's internal clock sources. The clock signal is generated in
+//! hardware, without any CPU involvement, which makes it suitable for clocking
+//! external ADCs, audio codecs or camera sensors.
+//!
+//! [`Clock`] configures the clock manager registers directly through
+//! `/dev/mem`, which requires superuser privileges. The Raspberry Pi 5
+//! isn't supported, because its GPCLK outputs are located on the RP1.
+//!
+//! ## Pins
+//!
+//! The clock signal is only output on a GPIO pin after that pin's mode is
+//! set to the corresponding alternate function, for instance through
+//! [`Pin::into_io`].
+//!
+//! | Clock  | Pin     | Mode   | Physical pin |
+//! |--------|---------|--------|--------------|
+//! | GPCLK0 | GPIO 4  | `Alt0` | 7            |
+//! | GPCLK0 | GPIO 20 | `Alt5` | 38           |
+//! | GPCLK1 | GPIO 5  | `Alt0` | 29           |
+//! | GPCLK1 | GPIO 21 | `Alt5` | 40           |
+//! | GPCLK2 | GPIO 6  | `Alt0` | 31           |
+//!
+//! GPCLK0 through GPCLK2 are also available on GPIO 32, GPIO 34, GPIO 42,
+//! GPIO 43 and GPIO 44, which aren't routed to the GPIO header. On some
+//! models, the firmware uses GPCLK1 internally. Use GPCLK0 or GPCLK2 unless
+//! you've verified GPCLK1 is unused.
+//!
+//! ## Frequency resolution
+//!
+//! The output frequency is the frequency of the selected [`Source`] divided
+//! by a divisor between 2 and 4095. Integer divisors produce a clean square
+//! wave. Fractional divisors are supported with a resolution of 1/4096,
+//! using a noise-shaping (MASH) divider that alternates between the two
+//! nearest integer divisors. The average frequency is accurate, but the
+//! individual periods jitter by one source clock cycle, which may not be
+//! acceptable for every application.
+//!
+//! The GPIO pads can't reliably drive frequencies above approximately
+//! 25 MHz, although higher frequencies can be configured.
+//!
+//! [`Clock`]: struct.Clock.html
+//! [`Source`]: enum.Source.html
+//! [`Pin::into_io`]: ../gpio/struct.Pin.html#method.into_io
+
+use std::error;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::result;
+use std::sync::atomic::{self, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use libc::{off_t, MAP_FAILED, MAP_SHARED, O_SYNC, PROT_READ, PROT_WRITE};
+
+use crate::system::{self, DeviceInfo, SoC};
+
+const PATH_DEV_MEM: &str = "/dev/mem";
+// Offset from the peripheral base address
+const CLOCK_OFFSET: u64 = 0x0010_1000;
+// The clock manager registers fit within a single page
+const CLOCK_SIZE: usize = 4096;
+// GPCLK0 control register. Each clock has a control register followed by a
+// divisor register.
+const CM_GP0CTL: usize = 0x70 / std::mem::size_of::<u32>();
+
+// Every write to a clock manager register must include the password
+const CM_PASSWORD: u32 = 0x5a00_0000;
+const CM_CTL_ENAB: u32 = 1 << 4;
+const CM_CTL_BUSY: u32 = 1 << 7;
+const CM_CTL_MASH_SHIFT: u32 = 9;
+const CM_DIV_DIVI_SHIFT: u32 = 12;
+
+const DIVISOR_MIN: u32 = 2;
+const DIVISOR_MAX: u32 = 4095;
+const DIVISOR_FRACTION: f64 = 4096.0;
+// Maximum time to wait for a clock generator to stop
+const BUSY_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Errors that can occur when accessing the general purpose clocks.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// I/O error.
+    Io(io::Error),
+    /// Unknown model.
+    ///
+    /// The Raspberry Pi model or SoC can't be identified.
+    UnknownModel,
+    /// Model not supported.
+    ///
+    /// The general purpose clocks aren't accessible through the BCM clock
+    /// manager on this model, such as the Raspberry Pi 5.
+    UnsupportedModel,
+    /// Invalid frequency.
+    ///
+    /// The frequency is NaN, or can't be generated from the selected clock
+    /// source with a divisor between 2 and 4095. Contains the rejected
+    /// value.
+    InvalidFrequency(f64),
+    /// Clock busy.
+    ///
+    /// The clock generator didn't stop before the timeout elapsed, so its
+    /// configuration couldn't be changed safely.
+    Busy,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::UnknownModel => write!(f, "Unknown Raspberry Pi model"),
+            Error::UnsupportedModel => write!(f, "Raspberry Pi model not supported"),
+            Error::InvalidFrequency(value) => write!(f, "Invalid frequency: {}", value),
+            Error::Busy => write!(f, "Clock busy"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => err.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<system::Error> for Error {
+    fn from(_err: system::Error) -> Error {
+        Error::UnknownModel
+    }
+}
+
+/// Result type returned from methods that can have `clock::Error`s.
+pub type Result<T> = result::Result<T, Error>;
+
+/// General purpose clocks.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Channel {
+    Gpclk0 = 0,
+    Gpclk1 = 1,
+    Gpclk2 = 2,
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Channel::Gpclk0 => write!(f, "GPCLK0"),
+            Channel::Gpclk1 => write!(f, "GPCLK1"),
+            Channel::Gpclk2 => write!(f, "GPCLK2"),
+        }
+    }
+}
+
+/// Clock sources.
+///
+/// Only sources with a fixed frequency are available. The frequency of
+/// PLLC depends on the current core clock speed, which changes with CPU
+/// frequency scaling.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Source {
+    /// The crystal oscillator, which runs at 19.2 MHz, or 54 MHz on the
+    /// BCM2711.
+    Oscillator,
+    /// PLLD, which runs at 500 MHz, or 750 MHz on the BCM2711.
+    PllD,
+}
+
+impl Source {
+    // Value for the SRC field in the control register
+    fn id(self) -> u32 {
+        match self {
+            Source::Oscillator => 1,
+            Source::PllD => 6,
+        }
+    }
+
+    // Source frequency in hertz (Hz)
+    fn frequency(self, soc: SoC) -> f64 {
+        match (self, soc) {
+            (Source::Oscillator, SoC::Bcm2711) => 54_000_000.0,
+            (Source::Oscillator, _) => 19_200_000.0,
+            (Source::PllD, SoC::Bcm2711) => 750_000_000.0,
+            (Source::PllD, _) => 500_000_000.0,
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Source::Oscillator => write!(f, "Oscillator"),
+            Source::PllD => write!(f, "PLLD"),
+        }
+    }
+}
+
+/// Provides access to a general purpose clock.
+///
+/// More information on the available pins and the frequency resolution can
+/// be found in the [module-level documentation].
+///
+/// ```no_run
+/// use rppal::clock::{Channel, Clock, Source};
+/// use rppal::gpio::{Gpio, Mode};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // Route GPCLK0 to GPIO 4 (physical pin 7)
+/// let _pin = Gpio::new()?.get(4)?.into_io(Mode::Alt0)?;
+///
+/// let mut clock = Clock::new(Channel::Gpclk0, Source::Oscillator)?;
+/// let frequency = clock.set_frequency(4_800_000.0)?;
+/// clock.enable()?;
+///
+/// println!("Generating {} Hz", frequency);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [module-level documentation]: index.html
+#[derive(Debug)]
+pub struct Clock {
+    mem_ptr: *mut u32,
+    channel: Channel,
+    source: Source,
+    soc: SoC,
+    frequency: Option<f64>,
+    reset_on_drop: bool,
+}
+
+impl Clock {
+    /// Constructs a new `Clock` for the specified channel and clock source.
+    ///
+    /// The clock generator is stopped if it's currently running. The clock
+    /// stays disabled until a frequency is set with [`set_frequency`], and
+    /// [`enable`] is called.
+    ///
+    /// [`set_frequency`]: #method.set_frequency
+    /// [`enable`]: #method.enable
+    pub fn new(channel: Channel, source: Source) -> Result<Clock> {
+        let device_info = DeviceInfo::new()?;
+        if device_info.capabilities().rp1() {
+            return Err(Error::UnsupportedModel);
+        }
+
+        let mem_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(O_SYNC)
+            .open(PATH_DEV_MEM)?;
+
+        let mem_ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                CLOCK_SIZE,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED,
+                mem_file.as_raw_fd(),
+                (device_info.peripheral_base() + CLOCK_OFFSET) as off_t,
+            )
+        };
+
+        if mem_ptr == MAP_FAILED {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+
+        let clock = Clock {
+            mem_ptr: mem_ptr as *mut u32,
+            channel,
+            source,
+            soc: device_info.soc(),
+            frequency: None,
+            reset_on_drop: true,
+        };
+
+        clock.stop()?;
+
+        log_debug!("Opened {} ({})", channel, source);
+
+        Ok(clock)
+    }
+
+    /// Returns the clock channel.
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    /// Returns the clock source.
+    pub fn source(&self) -> Source {
+        self.source
+    }
+
+    /// Returns the frequency in hertz (Hz) configured with
+    /// [`set_frequency`], or `None` if no frequency has been set.
+    ///
+    /// [`set_frequency`]: #method.set_frequency
+    pub fn frequency(&self) -> Option<f64> {
+        self.frequency
+    }
+
+    /// Sets the output frequency in hertz (Hz), and returns the average
+    /// frequency that's actually generated.
+    ///
+    /// The divisor is rounded to the nearest 1/4096. If the divisor doesn't
+    /// have a fractional part, the clock outputs a jitter-free square wave.
+    /// Otherwise, the MASH divider is used, as described in the
+    /// [module-level documentation].
+    ///
+    /// If the clock is enabled, it's briefly stopped while the new divisor
+    /// is applied. If `frequency` can't be generated from the clock source,
+    /// `set_frequency` returns `Err(`[`Error::InvalidFrequency`]`)`.
+    ///
+    /// [module-level documentation]: index.html#frequency-resolution
+    /// [`Error::InvalidFrequency`]: enum.Error.html#variant.InvalidFrequency
+    pub fn set_frequency(&mut self, frequency: f64) -> Result<f64> {
+        let source_frequency = self.source.frequency(self.soc);
+        let divisor = (source_frequency / frequency * DIVISOR_FRACTION).round() / DIVISOR_FRACTION;
+
+        if !(f64::from(DIVISOR_MIN)..=f64::from(DIVISOR_MAX)).contains(&divisor) {
+            return Err(Error::InvalidFrequency(frequency));
+        }
+
+        let divi = divisor.trunc() as u32;
+        let divf = ((divisor - divisor.trunc()) * DIVISOR_FRACTION) as u32;
+        let mash = if divf > 0 { 1 } else { 0 };
+
+        let enabled = self.is_enabled();
+        self.stop()?;
+
+        self.write(
+            CM_GP0CTL + 2 * self.channel as usize + 1,
+            CM_PASSWORD | (divi << CM_DIV_DIVI_SHIFT) | divf,
+        );
+        self.write(
+            CM_GP0CTL + 2 * self.channel as usize,
+            CM_PASSWORD | (mash << CM_CTL_MASH_SHIFT) | self.source.id(),
+        );
+
+        self.frequency = Some(source_frequency / divisor);
+
+        if enabled {
+            self.enable()?;
+        }
+
+        log_debug!(
+            "{} set to {:?} Hz (divisor {}.{}/4096)",
+            self.channel,
+            self.frequency,
+            divi,
+            divf
+        );
+
+        Ok(source_frequency / divisor)
+    }
+
+    /// Returns `true` if the clock generator is running.
+    pub fn is_enabled(&self) -> bool {
+        self.read(CM_GP0CTL + 2 * self.channel as usize) & CM_CTL_ENAB > 0
+    }
+
+    /// Starts the clock generator.
+    ///
+    /// A frequency must be set with [`set_frequency`] before the clock can
+    /// be enabled. Otherwise, `enable` returns
+    /// `Err(`[`Error::InvalidFrequency`]`)`.
+    ///
+    /// [`set_frequency`]: #method.set_frequency
+    /// [`Error::InvalidFrequency`]: enum.Error.html#variant.InvalidFrequency
+    pub fn enable(&self) -> Result<()> {
+        if self.frequency.is_none() {
+            return Err(Error::InvalidFrequency(0.0));
+        }
+
+        let offset = CM_GP0CTL + 2 * self.channel as usize;
+        // Only the ENAB bit may change while the other fields are applied
+        let ctl = self.read(offset) & 0x0000_0fff;
+        self.write(offset, CM_PASSWORD | ctl | CM_CTL_ENAB);
+
+        Ok(())
+    }
+
+    /// Stops the clock generator.
+    ///
+    /// The generator finishes its current cycle before it stops, so the
+    /// output never produces a truncated pulse.
+    pub fn disable(&self) -> Result<()> {
+        self.stop()
+    }
+
+    /// Returns the value of `reset_on_drop`.
+    pub fn reset_on_drop(&self) -> bool {
+        self.reset_on_drop
+    }
+
+    /// When enabled, stops the clock generator when the `Clock` goes out of
+    /// scope.
+    ///
+    /// By default, `reset_on_drop` is set to `true`.
+    pub fn set_reset_on_drop(&mut self, reset_on_drop: bool) {
+        self.reset_on_drop = reset_on_drop;
+    }
+
+    // Clears ENAB, and waits until the generator is no longer busy. The other
+    // control fields can only be changed safely while it's stopped.
+    fn stop(&self) -> Result<()> {
+        let offset = CM_GP0CTL + 2 * self.channel as usize;
+        let ctl = self.read(offset) & 0x0000_0fff & !CM_CTL_ENAB;
+        self.write(offset, CM_PASSWORD | ctl);
+
+        let start = Instant::now();
+        while self.read(offset) & CM_CTL_BUSY > 0 {
+            if start.elapsed() >= BUSY_TIMEOUT {
+                return Err(Error::Busy);
+            }
+
+            thread::sleep(Duration::from_micros(10));
+        }
+
+        Ok(())
+    }
+
+    // A SeqCst fence orders our accesses with respect to regular memory. On
+    // ARMv7 and AArch64 it's a DMB ISH, which is weaker than the barrier
+    // described in the BCM2835 ARM Peripherals manual (section 1.3).
+    #[inline(always)]
+    fn read(&self, offset: usize) -> u32 {
+        let value = unsafe { ptr::read_volatile(self.mem_ptr.add(offset)) };
+        atomic::fence(Ordering::SeqCst);
+
+        value
+    }
+
+    #[inline(always)]
+    fn write(&self, offset: usize, value: u32) {
+        atomic::fence(Ordering::SeqCst);
+        unsafe {
+            ptr::write_volatile(self.mem_ptr.add(offset), value);
+        }
+    }
+}
+
+impl Drop for Clock {
+    fn drop(&mut self) {
+        if self.reset_on_drop {
+            let _ = self.stop();
+        }
+
+        unsafe {
+            libc::munmap(self.mem_ptr as *mut libc::c_void, CLOCK_SIZE);
+        }
+    }
+}
+
+// Clock doesn't contain any thread-specific state, and the registers for
+// each channel are only accessed through the Clock that owns them.
+unsafe impl Send for Clock {}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn error_display_and_source() {
+        let err = Error::from(io::Error::new(
+            io::ErrorKind::Other,
+            "Operation not permitted",
+        ));
+        assert_eq!(err.to_string(), "I/O error: Operation not permitted");
+        assert!(err.source().is_none());
+
+        let err = Error::Busy;
+        assert_eq!(err.to_string(), "Clock busy");
+        assert!(err.source().is_none());
+    }
+}
@@ -0,0 +1,19 @@
+//! Reference drivers for common slave devices.
+//!
+//! The drivers in this module are built entirely on RPPAL's public API, and
+//! are intended both as ready-to-use drivers and as a template for writing
+//! your own. They're only available when the optional `drivers` feature is
+//! enabled.
+//!
+//! For a wider selection of drivers, consider using any of the platform-agnostic
+//! drivers built on `embedded-hal`, through RPPAL's `hal` feature.
+
+#[cfg(feature = "spi")]
+mod apa102;
+#[cfg(feature = "i2c")]
+mod bme280;
+
+#[cfg(feature = "spi")]
+pub use self::apa102::{Apa102, Error as Apa102Error};
+#[cfg(feature = "i2c")]
+pub use self::bme280::{Bme280, Error as Bme280Error, Measurements};
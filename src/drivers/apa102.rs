@@ -0,0 +1,360 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::result;
+
+use crate::spi::{self, Spi};
+
+// Start frame (APA102 datasheet). 32 zero bits mark the start of an update
+const START_FRAME: [u8; 4] = [0x00; 4];
+// The 3 most significant bits of each LED frame are always set
+const LED_FRAME_MARKER: u8 = 0b1110_0000;
+// Maximum value of the 5-bit global brightness field
+const MAX_BRIGHTNESS: u8 = 0b1_1111;
+// Reset frame required by SK9822 clones to latch the new data
+const RESET_FRAME_LEN: usize = 4;
+// spidev's default bufsiz value. Larger updates are split into multiple
+// writes, which is harmless because the LEDs don't use Slave Select
+const CHUNK_SIZE: usize = 4096;
+
+/// Errors that can occur when accessing an APA102 LED strip.
+///
+/// [`Spi`] is displayed without the underlying [`spi::Error`], which is
+/// available through [`source`] instead.
+///
+/// [`Spi`]: #variant.Spi
+/// [`spi::Error`]: ../spi/enum.Error.html
+/// [`source`]: https://doc.rust-lang.org/std/error/trait.Error.html#method.source
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// SPI error.
+    Spi(spi::Error),
+    /// Invalid pixel index.
+    ///
+    /// The specified index is equal to or greater than the number of LEDs on
+    /// the strip.
+    InvalidIndex(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::Spi(_) => write!(f, "SPI error"),
+            Error::InvalidIndex(index) => write!(f, "Invalid pixel index: {}", index),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Spi(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<spi::Error> for Error {
+    fn from(err: spi::Error) -> Error {
+        Error::Spi(err)
+    }
+}
+
+/// Result type returned from methods that can have `Apa102Error`s.
+pub type Result<T> = result::Result<T, Error>;
+
+#[derive(Debug, Default, Copy, Clone)]
+struct Pixel {
+    red: u8,
+    green: u8,
+    blue: u8,
+    brightness: u8,
+}
+
+/// Driver for APA102 and SK9822 addressable LED strips.
+///
+/// Unlike WS2812 LEDs, which rely on precise timing, APA102 LEDs have
+/// separate data and clock inputs, which makes them a good fit for [`Spi`].
+/// Connect the strip's data input to MOSI and its clock input to SCLK.
+/// Slave Select and MISO aren't used. Most strips work reliably at clock
+/// speeds up to several MHz, and [`Mode::Mode0`].
+///
+/// Pixel colors are stored in a buffer by [`set_pixel`], and sent to the
+/// strip when [`show`] is called.
+///
+/// ## Color order
+///
+/// Colors are specified as red, green and blue components. Each LED frame
+/// is sent as a brightness byte, followed by the blue, green and red
+/// components, which is the order used by genuine APA102 and SK9822 LEDs.
+/// Some clones expect a different order. For those strips, swap the color
+/// arguments passed to [`set_pixel`] accordingly.
+///
+/// ## Brightness and gamma correction
+///
+/// Each LED has a 5-bit global brightness value (`0`-`31`) that scales its
+/// color components. On genuine APA102 LEDs, the global brightness is
+/// applied using a slower PWM cycle, which can cause visible flicker at low
+/// brightness levels. For the smoothest result, keep the brightness at `31`,
+/// and scale the color components instead.
+///
+/// The human eye perceives brightness non-linearly. [`set_gamma`] enables
+/// gamma correction of the color components, so color fades appear even.
+///
+/// ## Example
+///
+/// ```no_run
+/// use rppal::drivers::Apa102;
+/// use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let spi = Spi::new(Bus::Spi0, SlaveSelect::Ss0, 4_000_000, Mode::Mode0)?;
+/// let mut strip = Apa102::new(spi, 60);
+/// strip.set_gamma(2.2);
+///
+/// for index in 0..strip.len() {
+///     strip.set_pixel(index, 255, 64, 0, 31)?;
+/// }
+///
+/// strip.show()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The data sent to the strip can be inspected with the [`mock`] simulator.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use rppal::drivers::Apa102;
+/// use rppal::mock::{Event, Registers, Simulator};
+/// use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
+///
+/// let simulator = Simulator::new();
+/// simulator.add_spi_device(Bus::Spi0, SlaveSelect::Ss0, Registers::new());
+///
+/// let spi = Spi::with_simulator(&simulator, Bus::Spi0, SlaveSelect::Ss0, 4_000_000, Mode::Mode0)?;
+/// let mut strip = Apa102::new(spi, 2);
+/// strip.set_pixel(0, 0x11, 0x22, 0x33, 31)?;
+/// strip.set_pixel(1, 0x44, 0x55, 0x66, 1)?;
+/// strip.show()?;
+///
+/// match &simulator.events()[..] {
+///     [Event::SpiTransfer { write, .. }] => assert_eq!(
+///         write[..],
+///         [
+///             0x00, 0x00, 0x00, 0x00, // Start frame
+///             0xFF, 0x33, 0x22, 0x11, // LED 0
+///             0xE1, 0x66, 0x55, 0x44, // LED 1
+///             0x00, 0x00, 0x00, 0x00, // Reset frame
+///             0x00, // End frame
+///         ]
+///     ),
+///     events => panic!("unexpected events: {:?}", events),
+/// }
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`Spi`]: ../spi/struct.Spi.html
+/// [`Mode::Mode0`]: ../spi/enum.Mode.html#variant.Mode0
+/// [`set_pixel`]: #method.set_pixel
+/// [`show`]: #method.show
+/// [`set_gamma`]: #method.set_gamma
+/// [`mock`]: ../mock/index.html
+#[derive(Debug)]
+pub struct Apa102 {
+    spi: Spi,
+    pixels: Vec<Pixel>,
+    gamma: f32,
+    gamma_table: [u8; 256],
+}
+
+impl Apa102 {
+    /// Constructs a new `Apa102` for a strip of `len` LEDs.
+    ///
+    /// All LEDs are initially off. Nothing is sent to the strip until
+    /// [`show`] is called.
+    ///
+    /// [`show`]: #method.show
+    pub fn new(spi: Spi, len: usize) -> Apa102 {
+        let mut apa102 = Apa102 {
+            spi,
+            pixels: vec![Pixel::default(); len],
+            gamma: 1.0,
+            gamma_table: [0u8; 256],
+        };
+
+        apa102.set_gamma(1.0);
+
+        apa102
+    }
+
+    /// Returns the number of LEDs on the strip.
+    pub fn len(&self) -> usize {
+        self.pixels.len()
+    }
+
+    /// Returns `true` if the strip doesn't contain any LEDs.
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// Sets the color and 5-bit global brightness of the LED at `index`.
+    ///
+    /// `brightness` ranges from `0` to `31`. Higher values are clamped to
+    /// `31`. The change takes effect when [`show`] is called.
+    ///
+    /// If `index` is out of range, `set_pixel` returns
+    /// `Err(`[`Apa102Error::InvalidIndex`]`)`.
+    ///
+    /// [`show`]: #method.show
+    /// [`Apa102Error::InvalidIndex`]: enum.Apa102Error.html#variant.InvalidIndex
+    pub fn set_pixel(
+        &mut self,
+        index: usize,
+        red: u8,
+        green: u8,
+        blue: u8,
+        brightness: u8,
+    ) -> Result<()> {
+        let pixel = self
+            .pixels
+            .get_mut(index)
+            .ok_or(Error::InvalidIndex(index))?;
+
+        *pixel = Pixel {
+            red,
+            green,
+            blue,
+            brightness: brightness.min(MAX_BRIGHTNESS),
+        };
+
+        Ok(())
+    }
+
+    /// Sets all LEDs to the specified color and 5-bit global brightness.
+    ///
+    /// The change takes effect when [`show`] is called.
+    ///
+    /// [`show`]: #method.show
+    pub fn fill(&mut self, red: u8, green: u8, blue: u8, brightness: u8) {
+        let pixel = Pixel {
+            red,
+            green,
+            blue,
+            brightness: brightness.min(MAX_BRIGHTNESS),
+        };
+
+        self.pixels.iter_mut().for_each(|p| *p = pixel);
+    }
+
+    /// Turns off all LEDs.
+    ///
+    /// The change takes effect when [`show`] is called.
+    ///
+    /// [`show`]: #method.show
+    pub fn clear(&mut self) {
+        self.fill(0, 0, 0, 0);
+    }
+
+    /// Returns the gamma value used to correct the color components.
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    /// Sets the gamma value used to correct the color components.
+    ///
+    /// Each color component is mapped to `255 * (value / 255)^gamma` when
+    /// the pixel data is sent to the strip. A `gamma` of `1.0` disables gamma
+    /// correction. Values between `2.2` and `2.8` are typical for LEDs.
+    ///
+    /// By default, gamma correction is disabled. Invalid values, such as
+    /// negative numbers or `NaN`, are replaced by `1.0`.
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.gamma = if gamma.is_finite() && gamma > 0.0 {
+            gamma
+        } else {
+            1.0
+        };
+
+        for (value, corrected) in self.gamma_table.iter_mut().enumerate() {
+            *corrected = ((value as f32 / 255.0).powf(self.gamma) * 255.0).round() as u8;
+        }
+    }
+
+    /// Sends the pixel data to the strip.
+    ///
+    /// The data consists of a start frame of 32 zero bits, an LED frame for
+    /// each LED, and an end frame. Because the LEDs delay the clock signal
+    /// by half a cycle as it's passed down the strip, the end frame provides
+    /// at least `len / 2` additional clock cycles. It's preceded by 32 zero
+    /// bits, which SK9822 clones require to latch the new data.
+    ///
+    /// Large strips are updated using multiple writes. The LEDs don't use
+    /// Slave Select, so this doesn't affect the result.
+    pub fn show(&mut self) -> Result<()> {
+        let end_frame_len = RESET_FRAME_LEN + (self.pixels.len() + 15) / 16;
+        let mut buffer =
+            Vec::with_capacity(START_FRAME.len() + self.pixels.len() * 4 + end_frame_len);
+
+        buffer.extend_from_slice(&START_FRAME);
+
+        for pixel in &self.pixels {
+            buffer.extend_from_slice(&[
+                LED_FRAME_MARKER | pixel.brightness,
+                self.gamma_table[pixel.blue as usize],
+                self.gamma_table[pixel.green as usize],
+                self.gamma_table[pixel.red as usize],
+            ]);
+        }
+
+        buffer.resize(buffer.len() + end_frame_len, 0);
+
+        for chunk in buffer.chunks(CHUNK_SIZE) {
+            let length = self.spi.write(chunk)?;
+
+            if length < chunk.len() {
+                return Err(Error::Spi(spi::Error::Io(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "not all pixel data was written",
+                ))));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Consumes the `Apa102` and returns the underlying [`Spi`] instance.
+    ///
+    /// [`Spi`]: ../spi/struct.Spi.html
+    pub fn release(self) -> Spi {
+        self.spi
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn error_display_and_source() {
+        let err = Error::from(spi::Error::from(io::Error::new(
+            io::ErrorKind::Other,
+            "Message too long",
+        )));
+        assert_eq!(err.to_string(), "SPI error");
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "I/O error: Message too long"
+        );
+
+        let err = Error::InvalidIndex(10);
+        assert_eq!(err.to_string(), "Invalid pixel index: 10");
+        assert!(err.source().is_none());
+    }
+}
@@ -0,0 +1,382 @@
+use std::error;
+use std::fmt;
+use std::result;
+use std::thread;
+use std::time::Duration;
+
+use crate::i2c::{self, I2c};
+
+// Register addresses (BME280 datasheet @ 5.3)
+const REG_CALIB_00: u8 = 0x88;
+const REG_CHIP_ID: u8 = 0xd0;
+const REG_RESET: u8 = 0xe0;
+const REG_CALIB_26: u8 = 0xe1;
+const REG_CTRL_HUM: u8 = 0xf2;
+const REG_STATUS: u8 = 0xf3;
+const REG_CTRL_MEAS: u8 = 0xf4;
+const REG_DATA: u8 = 0xf7;
+
+const CHIP_ID: u8 = 0x60;
+const RESET: u8 = 0xb6;
+const STATUS_MEASURING: u8 = 0b1000;
+const STATUS_IM_UPDATE: u8 = 0b0001;
+
+// Oversampling x1 for humidity, temperature and pressure, forced mode
+const CTRL_HUM: u8 = 0b001;
+const CTRL_MEAS: u8 = (0b001 << 5) | (0b001 << 2) | 0b01;
+
+// Interval between status checks while waiting for a measurement
+const POLL_INTERVAL: Duration = Duration::from_millis(2);
+// Maximum number of status checks before giving up
+const POLL_RETRIES: u32 = 50;
+
+/// Errors that can occur when accessing a BME280.
+///
+/// [`I2c`] is displayed without the underlying [`i2c::Error`], which is
+/// available through [`source`] instead.
+///
+/// [`I2c`]: #variant.I2c
+/// [`i2c::Error`]: ../i2c/enum.Error.html
+/// [`source`]: https://doc.rust-lang.org/std/error/trait.Error.html#method.source
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// I2C error.
+    I2c(i2c::Error),
+    /// Unexpected chip ID.
+    ///
+    /// The device at the specified address isn't a BME280. A BMP280, which
+    /// doesn't include a humidity sensor, reports chip ID `0x58`.
+    InvalidChipId(u8),
+    /// Timed out.
+    ///
+    /// The device didn't finish copying its calibration data or completing a
+    /// measurement within the expected time.
+    TimedOut,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::I2c(_) => write!(f, "I2C error"),
+            Error::InvalidChipId(id) => write!(f, "Invalid chip ID: 0x{:02X}", id),
+            Error::TimedOut => write!(f, "Timed out"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::I2c(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<i2c::Error> for Error {
+    fn from(err: i2c::Error) -> Error {
+        Error::I2c(err)
+    }
+}
+
+/// Result type returned from methods that can have `Bme280Error`s.
+pub type Result<T> = result::Result<T, Error>;
+
+/// Compensated measurements returned by [`Bme280::read_measurements`].
+///
+/// [`Bme280::read_measurements`]: struct.Bme280.html#method.read_measurements
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Measurements {
+    /// Temperature in degrees Celsius (°C).
+    pub temperature: f64,
+    /// Pressure in pascal (Pa).
+    pub pressure: f64,
+    /// Relative humidity in percent (%RH).
+    pub humidity: f64,
+}
+
+// Factory-programmed compensation parameters (BME280 datasheet @ 4.2.2)
+#[derive(Debug, Default, Copy, Clone)]
+struct Calibration {
+    t1: u16,
+    t2: i16,
+    t3: i16,
+    p1: u16,
+    p2: i16,
+    p3: i16,
+    p4: i16,
+    p5: i16,
+    p6: i16,
+    p7: i16,
+    p8: i16,
+    p9: i16,
+    h1: u8,
+    h2: i16,
+    h3: u8,
+    h4: i16,
+    h5: i16,
+    h6: i8,
+}
+
+impl Calibration {
+    // calib_00 contains registers 0x88 through 0xA1, and calib_26 registers
+    // 0xE1 through 0xE7.
+    fn parse(calib_00: &[u8; 26], calib_26: &[u8; 7]) -> Calibration {
+        let u16_le = |i: usize| u16::from_le_bytes([calib_00[i], calib_00[i + 1]]);
+        let i16_le = |i: usize| i16::from_le_bytes([calib_00[i], calib_00[i + 1]]);
+
+        Calibration {
+            t1: u16_le(0),
+            t2: i16_le(2),
+            t3: i16_le(4),
+            p1: u16_le(6),
+            p2: i16_le(8),
+            p3: i16_le(10),
+            p4: i16_le(12),
+            p5: i16_le(14),
+            p6: i16_le(16),
+            p7: i16_le(18),
+            p8: i16_le(20),
+            p9: i16_le(22),
+            h1: calib_00[25],
+            h2: i16::from_le_bytes([calib_26[0], calib_26[1]]),
+            h3: calib_26[2],
+            // dig_H4 and dig_H5 are 12-bit signed values that share register 0xE5
+            h4: (i16::from(calib_26[3] as i8) << 4) | i16::from(calib_26[4] & 0x0f),
+            h5: (i16::from(calib_26[5] as i8) << 4) | i16::from(calib_26[4] >> 4),
+            h6: calib_26[6] as i8,
+        }
+    }
+
+    // Returns the temperature in °C, and t_fine, which is used to compensate
+    // the pressure and humidity (BME280 datasheet @ 8.1).
+    fn temperature(&self, adc_t: i32) -> (f64, f64) {
+        let adc_t = f64::from(adc_t);
+        let t1 = f64::from(self.t1);
+
+        let var1 = (adc_t / 16384.0 - t1 / 1024.0) * f64::from(self.t2);
+        let var2 = (adc_t / 131_072.0 - t1 / 8192.0)
+            * (adc_t / 131_072.0 - t1 / 8192.0)
+            * f64::from(self.t3);
+        let t_fine = var1 + var2;
+
+        (t_fine / 5120.0, t_fine)
+    }
+
+    // Returns the pressure in Pa.
+    fn pressure(&self, adc_p: i32, t_fine: f64) -> f64 {
+        let mut var1 = t_fine / 2.0 - 64000.0;
+        let mut var2 = var1 * var1 * f64::from(self.p6) / 32768.0;
+        var2 += var1 * f64::from(self.p5) * 2.0;
+        var2 = var2 / 4.0 + f64::from(self.p4) * 65536.0;
+        var1 =
+            (f64::from(self.p3) * var1 * var1 / 524_288.0 + f64::from(self.p2) * var1) / 524_288.0;
+        var1 = (1.0 + var1 / 32768.0) * f64::from(self.p1);
+
+        // Avoid a division by zero
+        if var1 == 0.0 {
+            return 0.0;
+        }
+
+        let mut pressure = 1_048_576.0 - f64::from(adc_p);
+        pressure = (pressure - var2 / 4096.0) * 6250.0 / var1;
+        var1 = f64::from(self.p9) * pressure * pressure / 2_147_483_648.0;
+        var2 = pressure * f64::from(self.p8) / 32768.0;
+
+        pressure + (var1 + var2 + f64::from(self.p7)) / 16.0
+    }
+
+    // Returns the relative humidity in %RH.
+    fn humidity(&self, adc_h: i32, t_fine: f64) -> f64 {
+        let mut humidity = t_fine - 76800.0;
+        humidity = (f64::from(adc_h)
+            - (f64::from(self.h4) * 64.0 + f64::from(self.h5) / 16384.0 * humidity))
+            * (f64::from(self.h2) / 65536.0
+                * (1.0
+                    + f64::from(self.h6) / 67_108_864.0
+                        * humidity
+                        * (1.0 + f64::from(self.h3) / 67_108_864.0 * humidity)));
+        humidity *= 1.0 - f64::from(self.h1) * humidity / 524_288.0;
+
+        humidity.clamp(0.0, 100.0)
+    }
+}
+
+/// Bosch BME280 temperature, pressure and humidity sensor.
+///
+/// `Bme280` takes ownership of an [`I2c`] instance, and sets the slave address
+/// before every transaction. Call [`release`] to retrieve the `I2c` instance.
+///
+/// Measurements are performed in forced mode with 1x oversampling, and the IIR
+/// filter disabled, which is the recommended configuration for weather
+/// monitoring.
+///
+/// ## Example
+///
+/// ```no_run
+/// use rppal::drivers::Bme280;
+/// use rppal::i2c::I2c;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut bme280 = Bme280::new(I2c::new()?, Bme280::ADDRESS_PRIMARY)?;
+/// let measurements = bme280.read_measurements()?;
+///
+/// println!(
+///     "{:.1} °C, {:.1} hPa, {:.1} %RH",
+///     measurements.temperature,
+///     measurements.pressure / 100.0,
+///     measurements.humidity
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`I2c`]: ../i2c/struct.I2c.html
+/// [`release`]: #method.release
+#[derive(Debug)]
+pub struct Bme280 {
+    i2c: I2c,
+    address: u16,
+    calibration: Calibration,
+}
+
+impl Bme280 {
+    /// Slave address used when the SDO pin is connected to GND.
+    pub const ADDRESS_PRIMARY: u16 = 0x76;
+    /// Slave address used when the SDO pin is connected to VDDIO.
+    pub const ADDRESS_SECONDARY: u16 = 0x77;
+
+    /// Constructs a new `Bme280`.
+    ///
+    /// `new` verifies the device's chip ID, resets the device, and reads its
+    /// calibration data.
+    ///
+    /// If the device doesn't identify itself as a BME280, `new` returns
+    /// `Err(`[`Bme280Error::InvalidChipId`]`)`.
+    ///
+    /// [`Bme280Error::InvalidChipId`]: enum.Bme280Error.html#variant.InvalidChipId
+    pub fn new(i2c: I2c, address: u16) -> Result<Bme280> {
+        let mut bme280 = Bme280 {
+            i2c,
+            address,
+            calibration: Calibration::default(),
+        };
+
+        let chip_id = bme280.read_register(REG_CHIP_ID)?;
+        if chip_id != CHIP_ID {
+            return Err(Error::InvalidChipId(chip_id));
+        }
+
+        bme280.write_register(REG_RESET, RESET)?;
+
+        // Wait until the calibration data has been copied to the image registers
+        bme280.wait_for_status(STATUS_IM_UPDATE)?;
+
+        let mut calib_00 = [0u8; 26];
+        let mut calib_26 = [0u8; 7];
+        bme280.read_registers(REG_CALIB_00, &mut calib_00)?;
+        bme280.read_registers(REG_CALIB_26, &mut calib_26)?;
+        bme280.calibration = Calibration::parse(&calib_00, &calib_26);
+
+        Ok(bme280)
+    }
+
+    /// Returns the slave address.
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// Performs a single measurement, and returns the compensated temperature,
+    /// pressure and humidity.
+    ///
+    /// `read_measurements` blocks until the measurement is completed, which
+    /// typically takes about 10 ms.
+    pub fn read_measurements(&mut self) -> Result<Measurements> {
+        // ctrl_hum only takes effect after writing to ctrl_meas
+        self.write_register(REG_CTRL_HUM, CTRL_HUM)?;
+        self.write_register(REG_CTRL_MEAS, CTRL_MEAS)?;
+
+        self.wait_for_status(STATUS_MEASURING)?;
+
+        // Burst read all data registers, so they belong to the same measurement
+        let mut data = [0u8; 8];
+        self.read_registers(REG_DATA, &mut data)?;
+
+        let adc_p =
+            (i32::from(data[0]) << 12) | (i32::from(data[1]) << 4) | (i32::from(data[2]) >> 4);
+        let adc_t =
+            (i32::from(data[3]) << 12) | (i32::from(data[4]) << 4) | (i32::from(data[5]) >> 4);
+        let adc_h = (i32::from(data[6]) << 8) | i32::from(data[7]);
+
+        let (temperature, t_fine) = self.calibration.temperature(adc_t);
+
+        Ok(Measurements {
+            temperature,
+            pressure: self.calibration.pressure(adc_p, t_fine),
+            humidity: self.calibration.humidity(adc_h, t_fine),
+        })
+    }
+
+    /// Consumes the `Bme280` and returns the underlying [`I2c`] instance.
+    ///
+    /// [`I2c`]: ../i2c/struct.I2c.html
+    pub fn release(self) -> I2c {
+        self.i2c
+    }
+
+    // Waits until all bits in mask are cleared in the status register.
+    fn wait_for_status(&mut self, mask: u8) -> Result<()> {
+        for _ in 0..POLL_RETRIES {
+            thread::sleep(POLL_INTERVAL);
+
+            if self.read_register(REG_STATUS)? & mask == 0 {
+                return Ok(());
+            }
+        }
+
+        Err(Error::TimedOut)
+    }
+
+    fn read_register(&mut self, register: u8) -> Result<u8> {
+        let mut value = [0u8];
+        self.read_registers(register, &mut value)?;
+
+        Ok(value[0])
+    }
+
+    fn read_registers(&mut self, register: u8, buffer: &mut [u8]) -> Result<()> {
+        self.i2c.set_slave_address(self.address)?;
+        self.i2c.write_read(&[register], buffer)?;
+
+        Ok(())
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) -> Result<()> {
+        self.i2c.set_slave_address(self.address)?;
+        self.i2c.write(&[register, value])?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn error_display_and_source() {
+        let err = Error::from(i2c::Error::DeviceNotFound(0x76));
+        assert_eq!(err.to_string(), "I2C error");
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "Slave device not found at address 0x76"
+        );
+
+        let err = Error::InvalidChipId(0x58);
+        assert_eq!(err.to_string(), "Invalid chip ID: 0x58");
+        assert!(err.source().is_none());
+    }
+}
@@ -4,6 +4,15 @@
 //! accessing the registers through either `/dev/gpiomem` or `/dev/mem`. GPIO interrupts
 //! are configured using the `gpiochip` character device.
 //!
+//! On the Raspberry Pi 5, the GPIO pins are controlled by the RP1 I/O controller, which
+//! uses a different register layout. RPPAL automatically detects the RP1, and accesses its
+//! registers through `/dev/gpiomem0` or `/dev/mem` instead. The [`Pin`] API behaves
+//! identically on all models, with the exception of the additional pin modes listed
+//! under [`Mode`].
+//!
+//! If the registers can't be memory-mapped, RPPAL falls back to controlling the pins through
+//! the `gpiochip` character device, as described under [Containers](#containers).
+//!
 //! ## Pins
 //!
 //! GPIO pins are retrieved from a [`Gpio`] instance by their BCM GPIO number by calling
@@ -24,6 +33,14 @@
 //! Note that `drop` methods aren't called when a process is abnormally terminated (for
 //! instance when a `SIGINT` signal isn't caught).
 //!
+//! Multiple [`OutputPin`]s can be combined into a [`LineGroup`], which changes their output
+//! states simultaneously with a minimal number of register writes. [`ParallelBus`] builds on
+//! `LineGroup` to write commands and data to parallel bus devices, such as HD44780-compatible
+//! character LCDs.
+//!
+//! [`Gpio::capture`] samples the logic levels of a set of pins at a fixed rate, similar to a
+//! logic analyzer, which can be useful when debugging timing issues.
+//!
 //! ## Interrupts
 //!
 //! [`InputPin`] supports both synchronous and asynchronous interrupt handlers.
@@ -33,10 +50,17 @@
 //! which blocks the current thread until a trigger event occurs, or until the timeout period
 //! elapses. [`Gpio::poll_interrupts`] should be used when multiple pins have been configured
 //! for synchronous interrupt triggers, and need to be polled simultaneously.
+//! Alternatively, [`InputPin::events`] returns an iterator that blocks on each call
+//! to `next` until the next trigger event occurs.
+//! [`InputPin::glitch_filter`] returns a similar iterator, which discards any pulses shorter
+//! than a minimum width based on the timestamps assigned by the kernel.
 //!
 //! Asynchronous interrupt triggers are configured using [`InputPin::set_async_interrupt`]. The
 //! specified callback function will be executed on a separate thread when a trigger event occurs.
 //!
+//! [`RotaryEncoder`] builds on asynchronous interrupts to decode the signals of an incremental
+//! rotary encoder, and reports rotation and push button events through a callback.
+//!
 //! ## Software-based PWM
 //!
 //! [`OutputPin`] and [`IoPin`] feature a software-based PWM implementation. The PWM signal is
@@ -93,54 +117,127 @@
 //! manually update your `udev` rules to set the appropriate permissions. More information
 //! can be found at [raspberrypi/linux#1225] and [raspberrypi/linux#2289].
 //!
+//! ### Containers
+//!
+//! Containers usually don't have access to the GPIO device nodes unless they're explicitly
+//! mapped. With Docker, map the following device nodes using `--device`, instead of running
+//! the container with `--privileged`:
+//!
+//! * `/dev/gpiomem` (Raspberry Pi 4 and earlier) or `/dev/gpiomem0` (Raspberry Pi 5), used to
+//!   access the GPIO registers.
+//! * `/dev/gpiochip0` (or `/dev/gpiochip4` on the Raspberry Pi 5 with older kernels), used for
+//!   interrupts.
+//!
+//! `/dev/mem` is only used as an alternative to `/dev/gpiomem`, and can't be accessed
+//! without superuser privileges.
+//!
+//! If only the `gpiochip` device node is available, [`Gpio::new`] falls back to controlling
+//! the pins through the character device, and [`Gpio::backend`] returns
+//! [`Backend::CharacterDevice`]. The same binary works inside and outside a container,
+//! with the following limitations:
+//!
+//! * Changing the pin state is considerably slower, which affects software-based PWM and
+//!   [`Gpio::capture`] in particular.
+//! * Only the [`Input`] and [`Output`] modes are supported. Alternate function modes are
+//!   ignored, which is logged as a warning.
+//! * While an interrupt trigger is configured for a pin, [`InputPin::read`] reads its
+//!   logic level through the interrupt's line event. Pins that are in use by another
+//!   process can't be read, and return [`Low`], which is logged as a warning.
+//! * [`LineGroup::write_atomic`] changes the pins one at a time, so they no longer change
+//!   state simultaneously.
+//!
+//! The fallback is logged as a warning the first time it occurs, but only if the optional
+//! `log` feature is enabled. Otherwise, [`Gpio::backend`] is the only way to find out which
+//! backend is used.
+//!
 //! [`Error::PinNotAvailable`]: enum.Error.html#variant.PinNotAvailable
+//! [`LineGroup::write_atomic`]: struct.LineGroup.html#method.write_atomic
 //! [`PermissionDenied`]: enum.Error.html#variant.PermissionDenied
 //! [raspberrypi/linux#1225]: https://github.com/raspberrypi/linux/issues/1225
 //! [raspberrypi/linux#2289]: https://github.com/raspberrypi/linux/issues/2289
 //! [`Gpio`]: struct.Gpio.html
 //! [`Gpio::get`]: struct.Gpio.html#method.get
+//! [`Gpio::new`]: struct.Gpio.html#method.new
+//! [`Gpio::backend`]: struct.Gpio.html#method.backend
+//! [`Backend::CharacterDevice`]: enum.Backend.html#variant.CharacterDevice
+//! [`Input`]: enum.Mode.html#variant.Input
+//! [`Output`]: enum.Mode.html#variant.Output
+//! [`Low`]: enum.Level.html#variant.Low
+//! [`InputPin::read`]: struct.InputPin.html#method.read
 //! [`Gpio::poll_interrupts`]: struct.Gpio.html#method.poll_interrupts
+//! [`Gpio::capture`]: struct.Gpio.html#method.capture
 //! [`Pin`]: struct.Pin.html
+//! [`Mode`]: enum.Mode.html
 //! [`InputPin`]: struct.InputPin.html
 //! [`InputPin::set_reset_on_drop(false)`]: struct.InputPin.html#method.set_reset_on_drop
 //! [`InputPin::set_interrupt`]: struct.InputPin.html#method.set_interrupt
 //! [`InputPin::poll_interrupt`]: struct.InputPin.html#method.poll_interrupt
+//! [`InputPin::events`]: struct.InputPin.html#method.events
+//! [`InputPin::glitch_filter`]: struct.InputPin.html#method.glitch_filter
 //! [`InputPin::set_async_interrupt`]: struct.InputPin.html#method.set_async_interrupt
 //! [`OutputPin`]: struct.OutputPin.html
 //! [`OutputPin::set_reset_on_drop(false)`]: struct.OutputPin.html#method.set_reset_on_drop
+//! [`LineGroup`]: struct.LineGroup.html
+//! [`ParallelBus`]: struct.ParallelBus.html
+//! [`RotaryEncoder`]: struct.RotaryEncoder.html
 //! [`IoPin`]: struct.IoPin.html
 //! [`IoPin::set_reset_on_drop(false)`]: struct.IoPin.html#method.set_reset_on_drop
 //! [`Pwm`]: ../pwm/struct.Pwm.html
 
+use std::convert::Infallible;
 use std::error;
 use std::fmt;
 use std::io;
 use std::mem::MaybeUninit;
 use std::ops::Not;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::ptr;
 use std::result;
-use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, Once, Weak};
 use std::time::Duration;
 
-mod epoll;
-#[cfg(feature = "hal")]
+mod bcm_pin;
+mod capture;
+mod config;
+pub(crate) mod epoll;
+mod glitch_filter;
+mod gpiomem;
+#[cfg(any(feature = "hal-0", feature = "hal-1"))]
 mod hal;
 #[cfg(feature = "hal-unproven")]
 mod hal_unproven;
 mod interrupt;
 mod ioctl;
-mod mem;
+mod line_group;
+mod line_watch;
+mod parallel_bus;
 mod pin;
+mod pins_taken;
+mod rotary_encoder;
+mod schmitt;
 mod soft_pwm;
+mod stable;
 
+use crate::sys;
 use crate::system;
 use crate::system::DeviceInfo;
+use crate::units;
 
+pub use self::bcm_pin::BcmPin;
+pub use self::capture::{Capture, Sample};
+pub use self::config::PinConfig;
+pub use self::glitch_filter::GlitchFilter;
+pub use self::ioctl::Event;
+pub use self::line_group::LineGroup;
+pub use self::line_watch::{LineChange, LineChangeKind, LineWatch};
+pub use self::parallel_bus::{ParallelBus, ParallelTiming};
 pub use self::pin::{InputPin, IoPin, OutputPin, Pin};
+pub use self::rotary_encoder::{EncoderEvent, RotaryEncoder};
+pub use self::schmitt::Schmitt;
 
 /// Errors that can occur when accessing the GPIO peripheral.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Unknown model.
     ///
@@ -170,17 +267,64 @@ pub enum Error {
     /// addressed by their BCM GPIO numbers, rather than their physical location on the GPIO
     /// header.
     PinNotAvailable(u8),
+    /// Physical pin is not available.
+    ///
+    /// The GPIO header doesn't contain a GPIO pin at the specified physical location,
+    /// because the physical pin doesn't exist, it's a power or ground pin, or the current
+    /// Raspberry Pi model doesn't have a GPIO header.
+    PhysicalPinNotAvailable(u8),
+    /// Pin is not configured as input.
+    ///
+    /// Interrupt triggers can only be configured on an [`IoPin`] while its mode is
+    /// set to [`Input`].
+    ///
+    /// [`IoPin`]: struct.IoPin.html
+    /// [`Input`]: enum.Mode.html#variant.Input
+    PinNotInput(u8),
+    /// Mode is not supported.
+    ///
+    /// The GPIO peripheral doesn't support the requested mode for the pin.
+    /// `Alt6`, `Alt7`, `Alt8` and `Null` are only available on the RP1 I/O
+    /// controller used by the Raspberry Pi 5.
+    ModeNotSupported { pin: u8, mode: Mode },
+    /// Line is in use by another consumer.
+    ///
+    /// The kernel reports the pin's line as requested by another process or a
+    /// kernel driver, identified by `consumer`. Returned by
+    /// [`Pin::into_input_with`] when the line can't be shared.
+    ///
+    /// [`Pin::into_input_with`]: struct.Pin.html#method.into_input_with
+    LineBusy { pin: u8, consumer: String },
     /// Permission denied when opening `/dev/gpiomem`, `/dev/mem` or `/dev/gpiochipN` for
     /// read/write access.
     ///
+    /// `path` contains the device node that couldn't be accessed, and `required_group`
+    /// the group that conventionally grants access to it. `required_group` is `None` for
+    /// `/dev/mem`, which requires superuser privileges. `hint` describes how to resolve
+    /// the issue.
+    ///
     /// More information on possible causes for this error can be found [here].
     ///
     /// [here]: index.html#permission-denied
-    PermissionDenied(String),
+    PermissionDenied {
+        path: String,
+        required_group: Option<&'static str>,
+        hint: String,
+    },
     /// I/O error.
     Io(io::Error),
     /// Thread panicked.
     ThreadPanic,
+    /// Invalid software-based PWM duty cycle.
+    ///
+    /// The duty cycle is NaN, or outside the range `0.0..=1.0`. Contains the
+    /// rejected value.
+    InvalidDutyCycle(f64),
+    /// Invalid software-based PWM frequency.
+    ///
+    /// The frequency is NaN, infinite, or not greater than `0.0`. Contains the
+    /// rejected value.
+    InvalidFrequency(f64),
 }
 
 impl fmt::Display for Error {
@@ -189,21 +333,88 @@ impl fmt::Display for Error {
             Error::UnknownModel => write!(f, "Unknown Raspberry Pi model"),
             Error::PinUsed(pin) => write!(f, "Pin {} is already in use", pin),
             Error::PinNotAvailable(pin) => write!(f, "Pin {} is not available", pin),
-            Error::PermissionDenied(ref path) => write!(f, "Permission denied: {}", path),
+            Error::PhysicalPinNotAvailable(pin) => {
+                write!(f, "Physical pin {} is not available", pin)
+            }
+            Error::PinNotInput(pin) => write!(f, "Pin {} is not configured as input", pin),
+            Error::ModeNotSupported { pin, mode } => {
+                write!(f, "Pin {} doesn't support mode {}", pin, mode)
+            }
+            Error::LineBusy { pin, ref consumer } => {
+                write!(f, "Pin {} is in use by {}", pin, consumer)
+            }
+            Error::PermissionDenied {
+                ref path, ref hint, ..
+            } => write!(f, "Permission denied: {} ({})", path, hint),
             Error::Io(ref err) => write!(f, "I/O error: {}", err),
             Error::ThreadPanic => write!(f, "Thread panicked"),
+            Error::InvalidDutyCycle(value) => write!(f, "Invalid duty cycle: {}", value),
+            Error::InvalidFrequency(value) => write!(f, "Invalid frequency: {}", value),
         }
     }
 }
 
-impl error::Error for Error {}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => err.source(),
+            _ => None,
+        }
+    }
+}
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Error {
+        log_debug!("I/O error: {}", err);
+
         Error::Io(err)
     }
 }
 
+impl From<units::Error> for Error {
+    fn from(err: units::Error) -> Error {
+        match err {
+            units::Error::InvalidDutyCycle(value) => Error::InvalidDutyCycle(value),
+            units::Error::InvalidFrequency(value) => Error::InvalidFrequency(value),
+        }
+    }
+}
+
+// Converting a DutyCycle or Frequency into itself can't fail.
+impl From<Infallible> for Error {
+    fn from(err: Infallible) -> Error {
+        match err {}
+    }
+}
+
+impl Error {
+    // Converts an I/O error that occurred while accessing path, and classifies
+    // any permission errors.
+    pub(crate) fn with_path(err: io::Error, path: &str) -> Error {
+        match sys::permission_denied(&err, path) {
+            Some(denied) => Error::PermissionDenied {
+                path: denied.path,
+                required_group: denied.required_group,
+                hint: denied.hint,
+            },
+            None => Error::from(sys::io_error_with_path(err, path)),
+        }
+    }
+
+    // Converts an I/O error returned by an ioctl on fd, and classifies any
+    // permission errors.
+    pub(crate) fn with_fd(err: io::Error, fd: RawFd) -> Error {
+        match sys::permission_denied_fd(&err, fd) {
+            Some(denied) => Error::PermissionDenied {
+                path: denied.path,
+                required_group: denied.required_group,
+                hint: denied.hint,
+            },
+            None => Error::from(err),
+        }
+    }
+}
+
 impl From<system::Error> for Error {
     fn from(_err: system::Error) -> Error {
         Error::UnknownModel
@@ -214,7 +425,17 @@ impl From<system::Error> for Error {
 pub type Result<T> = result::Result<T, Error>;
 
 /// Pin modes.
+///
+/// `Alt6`, `Alt7`, `Alt8` and `Null` are only available on the RP1 I/O controller
+/// used by the Raspberry Pi 5. Setting these modes on other models returns
+/// [`Error::ModeNotSupported`].
+/// `Null` indicates the pin isn't connected to any function.
+///
+/// With the optional `serde` feature enabled, `Mode` is (de)serialized as
+/// `"input"`, `"output"`, `"alt0"` through `"alt8"`, or `"null"`.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 #[repr(u8)]
 pub enum Mode {
     Input = 0b000,
@@ -225,6 +446,10 @@ pub enum Mode {
     Alt3 = 0b111,
     Alt4 = 0b011,
     Alt5 = 0b010,
+    Alt6 = 0b1000,
+    Alt7 = 0b1001,
+    Alt8 = 0b1010,
+    Null = 0b1011,
 }
 
 impl fmt::Display for Mode {
@@ -238,12 +463,21 @@ impl fmt::Display for Mode {
             Mode::Alt3 => write!(f, "Alt3"),
             Mode::Alt4 => write!(f, "Alt4"),
             Mode::Alt5 => write!(f, "Alt5"),
+            Mode::Alt6 => write!(f, "Alt6"),
+            Mode::Alt7 => write!(f, "Alt7"),
+            Mode::Alt8 => write!(f, "Alt8"),
+            Mode::Null => write!(f, "Null"),
         }
     }
 }
 
 /// Pin logic levels.
+///
+/// With the optional `serde` feature enabled, `Level` is (de)serialized as
+/// `"low"` or `"high"`.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 #[repr(u8)]
 pub enum Level {
     Low = 0,
@@ -291,7 +525,12 @@ impl Not for Level {
 }
 
 /// Built-in pull-up/pull-down resistor states.
+///
+/// With the optional `serde` feature enabled, `PullUpDown` is (de)serialized as
+/// `"off"`, `"pull-down"` or `"pull-up"`.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum PullUpDown {
     Off = 0b00,
     PullDown = 0b01,
@@ -309,7 +548,12 @@ impl fmt::Display for PullUpDown {
 }
 
 /// Interrupt trigger conditions.
+///
+/// With the optional `serde` feature enabled, `Trigger` is (de)serialized as
+/// `"disabled"`, `"rising-edge"`, `"falling-edge"` or `"both"`.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum Trigger {
     Disabled = 0,
     RisingEdge = 1,
@@ -328,20 +572,88 @@ impl fmt::Display for Trigger {
     }
 }
 
+/// Result of [`InputPin::wait_for_either`].
+///
+/// [`InputPin::wait_for_either`]: struct.InputPin.html#method.wait_for_either
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum WhichPin {
+    /// An interrupt was triggered on the pin `wait_for_either` was called on.
+    /// Contains the logic level at the time of the trigger event.
+    This(Level),
+    /// An interrupt was triggered on the `other` pin. Contains the logic
+    /// level at the time of the trigger event.
+    Other(Level),
+    /// No interrupt was triggered before the timeout elapsed.
+    Timeout,
+}
+
+/// Interfaces used to control the GPIO pins.
+///
+/// `Backend` might be extended with additional variants in a minor or
+/// patch revision, and must not be exhaustively matched against.
+/// Instead, add a `_` catch-all arm to match future variants.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[non_exhaustive]
+pub enum Backend {
+    /// The GPIO registers are memory-mapped through `/dev/gpiomem`,
+    /// `/dev/gpiomem0` or `/dev/mem`.
+    Registers,
+    /// The GPIO pins are controlled through the `gpiochip` character device,
+    /// because the GPIO registers couldn't be memory-mapped. Pins are changed
+    /// one at a time, even when they're written through a [`LineGroup`].
+    ///
+    /// [`LineGroup`]: struct.LineGroup.html
+    CharacterDevice,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Backend::Registers => write!(f, "Registers"),
+            Backend::CharacterDevice => write!(f, "CharacterDevice"),
+        }
+    }
+}
+
+/// Determines how [`Pin::into_input_with`] acquires a pin.
+///
+/// [`Pin::into_input_with`]: struct.Pin.html#method.into_input_with
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum RequestMode {
+    /// Configure the pin as input, and fail if the line is in use by another
+    /// consumer.
+    Exclusive,
+    /// Leave the pin's mode and built-in pull-up/pull-down resistors
+    /// unchanged, so the pin can be monitored while it's controlled
+    /// elsewhere.
+    AsIs,
+}
+
+impl fmt::Display for RequestMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RequestMode::Exclusive => write!(f, "Exclusive"),
+            RequestMode::AsIs => write!(f, "AsIs"),
+        }
+    }
+}
+
 // Store Gpio's state separately, so we can conveniently share it through
 // a cloned Arc.
 pub(crate) struct GpioState {
-    gpio_mem: mem::GpioMem,
+    gpio_mem: Box<dyn gpiomem::GpioRegisters>,
+    backend: Backend,
     cdev: std::fs::File,
     sync_interrupts: Mutex<interrupt::EventLoop>,
-    pins_taken: [AtomicBool; u8::MAX as usize],
+    pins_taken: pins_taken::PinsTaken,
     gpio_lines: u8,
 }
 
 impl fmt::Debug for GpioState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("EventLoop")
+        f.debug_struct("GpioState")
             .field("gpio_mem", &self.gpio_mem)
+            .field("backend", &self.backend)
             .field("cdev", &self.cdev)
             .field("sync_interrupts", &self.sync_interrupts)
             .field("pins_taken", &format_args!("{{ .. }}"))
@@ -351,6 +663,22 @@ impl fmt::Debug for GpioState {
 }
 
 /// Provides access to the Raspberry Pi's GPIO peripheral.
+///
+/// All `Gpio` instances within a process share the same internal state,
+/// which contains the memory-mapped GPIO registers, the `gpiochip` file
+/// descriptor, the interrupt event loop and the bookkeeping for pins that are
+/// in use. The shared state is created by the first call to [`new`], and
+/// released after all `Gpio` and [`Pin`] instances have gone out of scope.
+///
+/// Calling [`new`] again, or cloning an existing `Gpio`, is cheap, and returns
+/// a new handle to the same state. `Gpio` is `Send` and `Sync`, so handles can
+/// be passed to other threads, and pins retrieved on one thread can be moved
+/// to another thread independently of the `Gpio` instance they were retrieved
+/// from. A pin can only be in use once, regardless of which `Gpio` handle
+/// was used to retrieve it.
+///
+/// [`new`]: #method.new
+/// [`Pin`]: struct.Pin.html
 #[derive(Clone, Debug)]
 pub struct Gpio {
     inner: Arc<GpioState>,
@@ -358,6 +686,13 @@ pub struct Gpio {
 
 impl Gpio {
     /// Constructs a new `Gpio`.
+    ///
+    /// `new` memory-maps the GPIO registers. If that fails, `new` falls back
+    /// to controlling the pins through the `gpiochip` character device, and
+    /// emits a warning through the optional `log` feature. The active
+    /// interface is returned by [`backend`].
+    ///
+    /// [`backend`]: #method.backend
     pub fn new() -> Result<Gpio> {
         // Replace this when std::sync::SyncLazy is stabilized. https://github.com/rust-lang/rust/issues/74465
 
@@ -371,11 +706,15 @@ impl Gpio {
         // by the closure can be observed by other threads after execution completes.
         let mut weak_state = unsafe {
             ONCE.call_once(|| {
-                GPIO_STATE.write(Mutex::new(Weak::new()));
+                (*ptr::addr_of_mut!(GPIO_STATE)).write(Mutex::new(Weak::new()));
             });
 
-            // GPIO_STATE will always be initialized at this point.
-            GPIO_STATE.assume_init_ref().lock().unwrap()
+            // GPIO_STATE will always be initialized at this point, and is never
+            // written to again.
+            (*ptr::addr_of!(GPIO_STATE))
+                .assume_init_ref()
+                .lock()
+                .unwrap()
         };
 
         // Clone a strong reference if a GpioState instance already exists, otherwise
@@ -385,19 +724,19 @@ impl Gpio {
                 inner: state.clone(),
             })
         } else {
-            let gpio_mem = mem::GpioMem::open()?;
+            let device_info = DeviceInfo::new().map_err(|_| Error::UnknownModel)?;
+            let (gpio_mem, backend) = gpiomem::open(&device_info)?;
             let cdev = ioctl::find_gpiochip()?;
             let sync_interrupts = Mutex::new(interrupt::EventLoop::new(
                 cdev.as_raw_fd(),
                 u8::MAX as usize,
             )?);
-            let pins_taken = init_array!(AtomicBool::new(false), u8::MAX as usize);
-            let gpio_lines = DeviceInfo::new()
-                .map_err(|_| Error::UnknownModel)?
-                .gpio_lines();
+            let pins_taken = pins_taken::PinsTaken::new();
+            let gpio_lines = device_info.gpio_lines();
 
             let gpio_state = Arc::new(GpioState {
                 gpio_mem,
+                backend,
                 cdev,
                 sync_interrupts,
                 pins_taken,
@@ -412,8 +751,28 @@ impl Gpio {
         }
     }
 
+    /// Returns the interface used to control the GPIO pins.
+    ///
+    /// If the GPIO registers can't be memory-mapped, for instance inside a
+    /// container that doesn't have access to `/dev/gpiomem`, `Gpio` falls back
+    /// to the `gpiochip` character device, and `backend` returns
+    /// [`Backend::CharacterDevice`]. More information can be found in the
+    /// [module-level documentation].
+    ///
+    /// The fallback is only logged when the optional `log` feature is
+    /// enabled, so check `backend` if your application depends on the
+    /// performance or the simultaneous pin changes of the register backend.
+    ///
+    /// [`Backend::CharacterDevice`]: enum.Backend.html#variant.CharacterDevice
+    /// [module-level documentation]: index.html#containers
+    pub fn backend(&self) -> Backend {
+        self.inner.backend
+    }
+
     /// Returns a [`Pin`] for the specified BCM GPIO number.
     ///
+    /// `pin` can either be a bare `u8`, or a [`BcmPin`].
+    ///
     /// Retrieving a GPIO pin grants access to the pin through an owned [`Pin`] instance.
     /// If the pin is already in use, `get` returns `Err(`[`Error::PinUsed`]`)`.
     /// After a [`Pin`] (or a derived [`InputPin`], [`OutputPin`] or [`IoPin`]) goes out
@@ -424,16 +783,16 @@ impl Gpio {
     /// [`OutputPin`]: struct.OutputPin.html
     /// [`IoPin`]: struct.IoPin.html
     /// [`Error::PinUsed`]: enum.Error.html#variant.PinUsed
-    pub fn get(&self, pin: u8) -> Result<Pin> {
+    /// [`BcmPin`]: struct.BcmPin.html
+    pub fn get<P: Into<u8>>(&self, pin: P) -> Result<Pin> {
+        let pin = pin.into();
+
         if pin >= self.inner.gpio_lines {
             return Err(Error::PinNotAvailable(pin));
         }
 
         // Returns an error if the pin is already taken, otherwise atomically sets it to true here
-        if self.inner.pins_taken[pin as usize]
-            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-            .is_err()
-        {
+        if !self.inner.pins_taken.take(pin) {
             // Pin is taken
             Err(Error::PinUsed(pin))
         } else {
@@ -442,6 +801,89 @@ impl Gpio {
         }
     }
 
+    /// Returns a [`Pin`] for the GPIO pin at the specified physical location on the GPIO header.
+    ///
+    /// `get_physical` converts the physical pin number to a BCM GPIO number based on the
+    /// header layout returned by [`DeviceInfo::header`], and then calls [`get`].
+    ///
+    /// If the physical pin isn't a GPIO pin, `get_physical` returns
+    /// `Err(`[`Error::PhysicalPinNotAvailable`]`)`.
+    ///
+    /// [`Pin`]: struct.Pin.html
+    /// [`DeviceInfo::header`]: ../system/struct.DeviceInfo.html#method.header
+    /// [`get`]: #method.get
+    /// [`Error::PhysicalPinNotAvailable`]: enum.Error.html#variant.PhysicalPinNotAvailable
+    pub fn get_physical(&self, physical: u8) -> Result<Pin> {
+        let pin = DeviceInfo::new()?
+            .header()
+            .and_then(|header| header.to_bcm(physical))
+            .ok_or(Error::PhysicalPinNotAvailable(physical))?;
+
+        self.get(pin)
+    }
+
+    /// Retrieves and configures multiple pins in a single pass, based on a
+    /// list of [`PinConfig`]s.
+    ///
+    /// `configure` is intended for board bring-up, where a HAT or custom board
+    /// requires a number of pins to be set to specific modes, alternate
+    /// functions, pull-up/pull-down resistors and initial output levels. The
+    /// pins are returned as [`IoPin`]s, in the same order as `configs`.
+    ///
+    /// All pins are retrieved before any of them are configured. If a pin is
+    /// already in use, listed more than once, or isn't available, `configure`
+    /// returns `Err(`[`Error::PinUsed`]`)` or `Err(`[`Error::PinNotAvailable`]`)`
+    /// for the first pin that fails, and releases any pins it retrieved
+    /// without changing their configuration.
+    ///
+    /// Each pin is then configured in order. If `level` is set, the logic
+    /// level is changed before the mode, so outputs don't briefly drive the
+    /// wrong level. When the returned `IoPin`s go out of scope, their mode
+    /// and pull-up/pull-down resistors are reset if [`reset_on_drop`] is set
+    /// to `true` (default).
+    ///
+    /// ```no_run
+    /// use rppal::gpio::{Gpio, Level, Mode, PinConfig, PullUpDown};
+    ///
+    /// # fn main() -> rppal::gpio::Result<()> {
+    /// let pins = Gpio::new()?.configure(&[
+    ///     PinConfig::new(17, Mode::Input, PullUpDown::PullUp, None),
+    ///     PinConfig::new(27, Mode::Output, PullUpDown::Off, Some(Level::High)),
+    ///     PinConfig::new(18, Mode::Alt5, PullUpDown::Off, None),
+    /// ])?;
+    ///
+    /// println!("GPIO 17 is {}", pins[0].read());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`PinConfig`]: struct.PinConfig.html
+    /// [`IoPin`]: struct.IoPin.html
+    /// [`Error::PinUsed`]: enum.Error.html#variant.PinUsed
+    /// [`Error::PinNotAvailable`]: enum.Error.html#variant.PinNotAvailable
+    /// [`reset_on_drop`]: struct.IoPin.html#method.set_reset_on_drop
+    pub fn configure(&self, configs: &[PinConfig]) -> Result<Vec<IoPin>> {
+        // Any pins retrieved so far are released when pins is dropped
+        let pins = configs
+            .iter()
+            .map(|config| self.get(config.pin))
+            .collect::<Result<Vec<Pin>>>()?;
+
+        pins.into_iter()
+            .zip(configs)
+            .map(|(mut pin, config)| {
+                if let Some(level) = config.level {
+                    pin.write(level);
+                }
+
+                let mut pin = pin.into_io(config.mode)?;
+                pin.set_pullupdown(config.pull_up_down);
+
+                Ok(pin)
+            })
+            .collect()
+    }
+
     /// Blocks until an interrupt is triggered on any of the specified pins, or until a timeout occurs.
     ///
     /// Only pins that have been previously configured for synchronous interrupts using [`InputPin::set_interrupt`]
@@ -474,6 +916,187 @@ impl Gpio {
         reset: bool,
         timeout: Option<Duration>,
     ) -> Result<Option<(&'a InputPin, Level)>> {
-        (*self.inner.sync_interrupts.lock().unwrap()).poll(pins, reset, timeout)
+        Ok((*self.inner.sync_interrupts.lock().unwrap())
+            .poll(pins, reset, timeout)?
+            .map(|(pin, event)| (pin, event.level())))
+    }
+
+    /// Starts monitoring the line of the specified pin for changes to its
+    /// request status and configuration, without requesting the line.
+    ///
+    /// `watch_line` is intended for monitoring pins that are controlled by
+    /// other processes or kernel drivers. The pin doesn't need to be
+    /// retrieved with [`get`] first, and the returned [`LineWatch`] doesn't
+    /// prevent other consumers from requesting the line. Only request and
+    /// configuration changes are reported, not changes to the logic level.
+    ///
+    /// Requires Linux kernel 5.10 or later. On older kernels, an
+    /// [`Error::Io`] is returned.
+    ///
+    /// If `pin` isn't available, `watch_line` returns
+    /// `Err(`[`Error::PinNotAvailable`]`)`.
+    ///
+    /// [`get`]: #method.get
+    /// [`LineWatch`]: struct.LineWatch.html
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    /// [`Error::PinNotAvailable`]: enum.Error.html#variant.PinNotAvailable
+    pub fn watch_line(&self, pin: u8) -> Result<LineWatch> {
+        if pin >= self.inner.gpio_lines {
+            return Err(Error::PinNotAvailable(pin));
+        }
+
+        LineWatch::new(pin)
+    }
+
+    /// Samples the logic levels of the specified pins at a fixed rate, and
+    /// returns the recorded samples.
+    ///
+    /// `capture` works like a simple logic analyzer. It reads the GPIO level
+    /// registers directly through the memory-mapped GPIO interface in a tight
+    /// loop, taking `sample_rate_hz` samples per second until `duration` has
+    /// elapsed. All pins are read at the same time. The pins don't need to be
+    /// retrieved with [`get`] first, and their configuration isn't changed,
+    /// so pins in use elsewhere in your application, or by other processes or
+    /// peripherals, can be captured as well. The resulting [`Capture`] can be
+    /// exported to a VCD file with [`Capture::write_vcd`].
+    ///
+    /// `capture` blocks until `duration` has elapsed, and keeps a CPU core
+    /// fully occupied while doing so. Memory for all samples is allocated
+    /// upfront, at 24 bytes per sample.
+    ///
+    /// Sampling is best-effort. Linux isn't a real-time OS, so the thread can
+    /// be preempted at any time, in which case any sample slots that passed in
+    /// the meantime are skipped. Each sample contains the time it was actually
+    /// taken, and the number of skipped samples is available through
+    /// [`Capture::missed_samples`]. The maximum sustainable sample rate is
+    /// limited by the time it takes to read the clock and the GPIO registers,
+    /// and is usually in the range of 1-5 MHz on a Raspberry Pi 4 B or 5, and
+    /// lower on older models. Running the thread with a real-time scheduling
+    /// policy on an isolated CPU core improves reliability.
+    ///
+    /// Returns [`Error::PinNotAvailable`] if any of the pins don't exist, and
+    /// an `io::ErrorKind::InvalidInput` error if `sample_rate_hz` is `0`.
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::time::Duration;
+    ///
+    /// use rppal::gpio::Gpio;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let capture = Gpio::new()?.capture(&[17, 27], 100_000, Duration::from_millis(500))?;
+    ///
+    /// println!("Missed samples: {}", capture.missed_samples());
+    /// capture.write_vcd(File::create("capture.vcd")?)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`get`]: #method.get
+    /// [`Capture`]: struct.Capture.html
+    /// [`Capture::write_vcd`]: struct.Capture.html#method.write_vcd
+    /// [`Capture::missed_samples`]: struct.Capture.html#method.missed_samples
+    /// [`Error::PinNotAvailable`]: enum.Error.html#variant.PinNotAvailable
+    pub fn capture(&self, pins: &[u8], sample_rate_hz: u32, duration: Duration) -> Result<Capture> {
+        if sample_rate_hz == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "sample rate must be greater than 0",
+            )));
+        }
+
+        let mut unique_pins = Vec::with_capacity(pins.len());
+        for &pin in pins {
+            if pin >= self.inner.gpio_lines {
+                return Err(Error::PinNotAvailable(pin));
+            }
+
+            if !unique_pins.contains(&pin) {
+                unique_pins.push(pin);
+            }
+        }
+
+        log_debug!(
+            "Capturing GPIO {:?} at {} Hz for {:?}",
+            unique_pins,
+            sample_rate_hz,
+            duration
+        );
+
+        Ok(capture::capture(
+            &*self.inner.gpio_mem,
+            &unique_pins,
+            sample_rate_hz,
+            duration,
+        ))
+    }
+
+    /// Issues a memory barrier, which orders any memory accesses before the
+    /// barrier relative to any memory accesses after it.
+    ///
+    /// The BCM2835 ARM Peripherals manual (section 1.3) states that
+    /// peripheral writes and reads may arrive out of order relative to
+    /// accesses to other peripherals or regular memory, and requires a memory
+    /// barrier before the first write to a peripheral, and after the last
+    /// read. RPPAL inserts a barrier whenever it accesses the GPIO
+    /// registers, and writes to the set and clear registers always take
+    /// effect in program order, and you don't need to call `memory_barrier`
+    /// when you only use RPPAL's GPIO interface.
+    ///
+    /// `memory_barrier` is useful when you combine RPPAL with your own
+    /// memory-mapped peripheral access, for instance through `/dev/mem`, or
+    /// with DMA buffers that are read by a peripheral, and need those
+    /// accesses to be ordered relative to RPPAL's GPIO register accesses.
+    ///
+    /// The barrier is implemented as a sequentially consistent fence, which
+    /// prevents the compiler and the CPU from reordering memory accesses across
+    /// the barrier. On ARMv6 it results in a full system `DMB` instruction. On
+    /// ARMv7 and AArch64, it results in `DMB ISH`, which only orders accesses
+    /// within the inner shareable domain, and is weaker than the barrier
+    /// described in the manual. If you need `DMB OSH` or `DSB SY`, issue them
+    /// with inline assembly.
+    #[inline]
+    pub fn memory_barrier() {
+        gpiomem::memory_barrier();
+    }
+}
+
+// Gpio and all pin types are shared between threads, which requires the
+// internal state to be Send and Sync.
+#[allow(dead_code)]
+fn assert_send_sync() {
+    fn assert<T: Send + Sync>() {}
+
+    assert::<Gpio>();
+    assert::<Pin>();
+    assert::<InputPin>();
+    assert::<OutputPin>();
+    assert::<IoPin>();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn error_display_and_source() {
+        let err = Error::from(io::Error::new(
+            io::ErrorKind::Other,
+            "Device or resource busy",
+        ));
+        assert_eq!(err.to_string(), "I/O error: Device or resource busy");
+        assert!(err.source().is_none());
+
+        let err = Error::with_path(io::Error::from_raw_os_error(libc::EIO), "/dev/gpiomem");
+        assert!(err.to_string().starts_with("I/O error: /dev/gpiomem: "));
+        let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.raw_os_error(), Some(libc::EIO));
+
+        let err = Error::PinUsed(17);
+        assert_eq!(err.to_string(), "Pin 17 is already in use");
+        assert!(err.source().is_none());
     }
 }
@@ -0,0 +1,122 @@
+use std::fmt;
+
+use crate::system::DeviceInfo;
+
+/// A BCM GPIO number.
+///
+/// `BcmPin` distinguishes BCM GPIO numbers from physical pin numbers and
+/// other values at the type level. A `BcmPin` is either validated against
+/// the GPIO lines available on the current Raspberry Pi model when it's
+/// constructed through [`new`], or selected from the associated constants,
+/// such as [`BcmPin::GPIO17`], which cover BCM GPIO 0-27 and are available
+/// on the GPIO header of every model with a 40-pin header.
+///
+/// [`Gpio::get`] accepts both a `BcmPin` and a bare `u8`.
+///
+/// ```no_run
+/// use rppal::gpio::{BcmPin, Gpio};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// const LED: BcmPin = BcmPin::GPIO23;
+///
+/// let mut pin = Gpio::new()?.get(LED)?.into_output();
+/// pin.set_high();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`new`]: #method.new
+/// [`BcmPin::GPIO17`]: #associatedconstant.GPIO17
+/// [`Gpio::get`]: struct.Gpio.html#method.get
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct BcmPin(u8);
+
+impl BcmPin {
+    /// BCM GPIO 0.
+    pub const GPIO0: BcmPin = BcmPin(0);
+    /// BCM GPIO 1.
+    pub const GPIO1: BcmPin = BcmPin(1);
+    /// BCM GPIO 2.
+    pub const GPIO2: BcmPin = BcmPin(2);
+    /// BCM GPIO 3.
+    pub const GPIO3: BcmPin = BcmPin(3);
+    /// BCM GPIO 4.
+    pub const GPIO4: BcmPin = BcmPin(4);
+    /// BCM GPIO 5.
+    pub const GPIO5: BcmPin = BcmPin(5);
+    /// BCM GPIO 6.
+    pub const GPIO6: BcmPin = BcmPin(6);
+    /// BCM GPIO 7.
+    pub const GPIO7: BcmPin = BcmPin(7);
+    /// BCM GPIO 8.
+    pub const GPIO8: BcmPin = BcmPin(8);
+    /// BCM GPIO 9.
+    pub const GPIO9: BcmPin = BcmPin(9);
+    /// BCM GPIO 10.
+    pub const GPIO10: BcmPin = BcmPin(10);
+    /// BCM GPIO 11.
+    pub const GPIO11: BcmPin = BcmPin(11);
+    /// BCM GPIO 12.
+    pub const GPIO12: BcmPin = BcmPin(12);
+    /// BCM GPIO 13.
+    pub const GPIO13: BcmPin = BcmPin(13);
+    /// BCM GPIO 14.
+    pub const GPIO14: BcmPin = BcmPin(14);
+    /// BCM GPIO 15.
+    pub const GPIO15: BcmPin = BcmPin(15);
+    /// BCM GPIO 16.
+    pub const GPIO16: BcmPin = BcmPin(16);
+    /// BCM GPIO 17.
+    pub const GPIO17: BcmPin = BcmPin(17);
+    /// BCM GPIO 18.
+    pub const GPIO18: BcmPin = BcmPin(18);
+    /// BCM GPIO 19.
+    pub const GPIO19: BcmPin = BcmPin(19);
+    /// BCM GPIO 20.
+    pub const GPIO20: BcmPin = BcmPin(20);
+    /// BCM GPIO 21.
+    pub const GPIO21: BcmPin = BcmPin(21);
+    /// BCM GPIO 22.
+    pub const GPIO22: BcmPin = BcmPin(22);
+    /// BCM GPIO 23.
+    pub const GPIO23: BcmPin = BcmPin(23);
+    /// BCM GPIO 24.
+    pub const GPIO24: BcmPin = BcmPin(24);
+    /// BCM GPIO 25.
+    pub const GPIO25: BcmPin = BcmPin(25);
+    /// BCM GPIO 26.
+    pub const GPIO26: BcmPin = BcmPin(26);
+    /// BCM GPIO 27.
+    pub const GPIO27: BcmPin = BcmPin(27);
+
+    /// Constructs a new `BcmPin` for the specified BCM GPIO number.
+    ///
+    /// Returns `None` if the GPIO number is out of range for the current
+    /// Raspberry Pi model, or if the model can't be identified.
+    pub fn new(pin: u8) -> Option<BcmPin> {
+        let device_info = DeviceInfo::new().ok()?;
+
+        if pin < device_info.gpio_lines() {
+            Some(BcmPin(pin))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the BCM GPIO number.
+    pub const fn number(self) -> u8 {
+        self.0
+    }
+}
+
+impl From<BcmPin> for u8 {
+    fn from(pin: BcmPin) -> u8 {
+        pin.0
+    }
+}
+
+impl fmt::Display for BcmPin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GPIO{}", self.0)
+    }
+}
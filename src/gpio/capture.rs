@@ -0,0 +1,184 @@
+use std::hint;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::gpio::gpiomem::GpioRegisters;
+use crate::gpio::Level;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// A single sample recorded by [`Gpio::capture`].
+///
+/// [`Gpio::capture`]: struct.Gpio.html#method.capture
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Sample {
+    timestamp: Duration,
+    levels: u64,
+}
+
+impl Sample {
+    /// Returns the time at which the sample was taken, relative to the start
+    /// of the capture.
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    /// Returns the logic level of the specified pin.
+    ///
+    /// Pins that weren't included in the capture are always reported as
+    /// [`Low`].
+    ///
+    /// [`Low`]: enum.Level.html#variant.Low
+    pub fn level(&self, pin: u8) -> Level {
+        if pin < 64 {
+            Level::from(((self.levels >> pin) & 0b1) as u8)
+        } else {
+            Level::Low
+        }
+    }
+
+    /// Returns the logic levels of all captured pins as a bit mask, where bit
+    /// n corresponds to BCM GPIO n.
+    pub fn levels(&self) -> u64 {
+        self.levels
+    }
+}
+
+/// A series of samples recorded by [`Gpio::capture`].
+///
+/// [`Gpio::capture`]: struct.Gpio.html#method.capture
+#[derive(Debug, Clone)]
+pub struct Capture {
+    pins: Vec<u8>,
+    sample_rate: u32,
+    samples: Vec<Sample>,
+    missed_samples: u64,
+}
+
+impl Capture {
+    /// Returns the BCM GPIO numbers of the captured pins.
+    pub fn pins(&self) -> &[u8] {
+        &self.pins
+    }
+
+    /// Returns the requested sample rate in hertz (Hz).
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Returns the recorded samples, in chronological order.
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    /// Returns the number of samples that were skipped because the sampling
+    /// loop fell behind schedule.
+    ///
+    /// A non-zero value indicates the requested sample rate couldn't be
+    /// sustained, or the thread was preempted during the capture.
+    pub fn missed_samples(&self) -> u64 {
+        self.missed_samples
+    }
+
+    /// Writes the samples to `writer` in the Value Change Dump (VCD) format,
+    /// which can be opened by waveform viewers such as GTKWave or PulseView.
+    ///
+    /// Each captured pin is listed as a separate 1-bit wire named after its
+    /// BCM GPIO number. Timestamps are written in nanoseconds.
+    pub fn write_vcd<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "$timescale 1 ns $end")?;
+        writeln!(writer, "$scope module gpio $end")?;
+        for (idx, pin) in self.pins.iter().enumerate() {
+            writeln!(writer, "$var wire 1 {} gpio{} $end", vcd_id(idx), pin)?;
+        }
+        writeln!(writer, "$upscope $end")?;
+        writeln!(writer, "$enddefinitions $end")?;
+
+        let mut prev_levels = None;
+        for sample in &self.samples {
+            let changed = match prev_levels {
+                Some(prev_levels) => sample.levels ^ prev_levels,
+                None => u64::MAX,
+            };
+
+            if changed == 0 {
+                continue;
+            }
+
+            writeln!(writer, "#{}", sample.timestamp.as_nanos())?;
+            if prev_levels.is_none() {
+                writeln!(writer, "$dumpvars")?;
+            }
+
+            for (idx, &pin) in self.pins.iter().enumerate() {
+                if (changed >> pin) & 0b1 == 1 {
+                    writeln!(writer, "{}{}", (sample.levels >> pin) & 0b1, vcd_id(idx))?;
+                }
+            }
+
+            if prev_levels.is_none() {
+                writeln!(writer, "$end")?;
+            }
+
+            prev_levels = Some(sample.levels);
+        }
+
+        writer.flush()
+    }
+}
+
+// Returns a VCD identifier code for the variable at idx. Every pin fits in
+// the range of single printable ASCII characters.
+fn vcd_id(idx: usize) -> char {
+    (b'!' + idx as u8) as char
+}
+
+// Reads the levels of the specified pins at a fixed rate, until duration has
+// elapsed.
+pub(crate) fn capture(
+    gpio_mem: &dyn GpioRegisters,
+    pins: &[u8],
+    sample_rate: u32,
+    duration: Duration,
+) -> Capture {
+    let mask = pins.iter().fold(0u64, |mask, &pin| mask | (1 << pin));
+
+    let period_ns = (NANOS_PER_SEC / u64::from(sample_rate)).max(1);
+    let total = (duration.as_nanos() / u128::from(period_ns)) as u64;
+
+    let mut samples = Vec::with_capacity(total as usize);
+    let mut missed_samples = 0;
+    let mut next = 0;
+
+    let start = Instant::now();
+    while next < total {
+        let target = start + Duration::from_nanos(next * period_ns);
+
+        let mut now = Instant::now();
+        while now < target {
+            hint::spin_loop();
+            now = Instant::now();
+        }
+
+        let levels = gpio_mem.levels() & mask;
+        let timestamp = now - start;
+
+        samples.push(Sample { timestamp, levels });
+
+        // Skip any sample slots that have already passed
+        let current = ((timestamp.as_nanos() / u128::from(period_ns)) as u64).min(total - 1);
+        if current > next {
+            missed_samples += current - next;
+            next = current;
+        }
+
+        next += 1;
+    }
+
+    Capture {
+        pins: pins.to_vec(),
+        sample_rate,
+        samples,
+        missed_samples,
+    }
+}
@@ -0,0 +1,69 @@
+use super::{Level, Mode, PullUpDown};
+
+/// Configuration for a single GPIO pin, applied by [`Gpio::configure`].
+///
+/// `PinConfig` describes a pin's mode, which covers both the direction and
+/// any alternate function, its built-in pull-up/pull-down resistors, and an
+/// optional initial logic level for outputs. This is useful when the pin
+/// assignments for a board are loaded from a file.
+///
+/// With the optional `serde` feature enabled, `PinConfig` is (de)serialized
+/// using kebab-case field names, and unknown fields are rejected. If
+/// `pull-up-down` is omitted, it defaults to `"off"`. If `level` is omitted,
+/// the logic level is left unchanged. For instance, in TOML:
+///
+/// ```toml
+/// [[pins]]
+/// pin = 17
+/// mode = "input"
+/// pull-up-down = "pull-up"
+///
+/// [[pins]]
+/// pin = 27
+/// mode = "output"
+/// level = "high"
+///
+/// [[pins]]
+/// pin = 18
+/// mode = "alt5"
+/// ```
+///
+/// [`Gpio::configure`]: struct.Gpio.html#method.configure
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(rename_all = "kebab-case", deny_unknown_fields)
+)]
+pub struct PinConfig {
+    /// The BCM GPIO number.
+    pub pin: u8,
+    /// The pin mode.
+    pub mode: Mode,
+    /// The built-in pull-up/pull-down resistor configuration.
+    #[cfg_attr(feature = "serde", serde(default = "default_pull_up_down"))]
+    pub pull_up_down: PullUpDown,
+    /// The logic level set before the mode is changed, or `None` to leave
+    /// the level unchanged. Only relevant when `mode` is [`Output`].
+    ///
+    /// [`Output`]: enum.Mode.html#variant.Output
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub level: Option<Level>,
+}
+
+impl PinConfig {
+    /// Constructs a new `PinConfig`.
+    pub fn new(pin: u8, mode: Mode, pull_up_down: PullUpDown, level: Option<Level>) -> PinConfig {
+        PinConfig {
+            pin,
+            mode,
+            pull_up_down,
+            level,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn default_pull_up_down() -> PullUpDown {
+    PullUpDown::Off
+}
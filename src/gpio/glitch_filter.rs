@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use crate::gpio::{Event, Level};
+
+/// Discards runt pulses from a stream of interrupt trigger events.
+///
+/// Mechanical contacts and long cables often produce short pulses that are
+/// too fast to be measured reliably in userspace. `GlitchFilter` uses the
+/// timestamps the kernel assigns to each [`Event`] to measure the width of
+/// every pulse, and discards both edges of any pulse shorter than the
+/// configured minimum width.
+///
+/// Because the filter can only decide whether an edge belongs to a runt pulse
+/// once the next edge arrives, or once the minimum width has passed without
+/// another edge, each edge is held back as pending until it's confirmed.
+/// [`InputPin::glitch_filter`] takes care of releasing pending edges after a
+/// timeout, and is the easiest way to use `GlitchFilter`.
+///
+/// When the kernel supports sequence numbers, gaps in [`Event::line_seqno`]
+/// are used to detect events the kernel dropped. The width of a pulse can't be
+/// determined when any of its edges were lost, so the pending edge is released
+/// unfiltered instead. The number of lost events is available through
+/// [`lost_events`].
+///
+/// [`Event`]: struct.Event.html
+/// [`Event::line_seqno`]: struct.Event.html#method.line_seqno
+/// [`InputPin::glitch_filter`]: struct.InputPin.html#method.glitch_filter
+/// [`lost_events`]: #method.lost_events
+#[derive(Debug, Copy, Clone)]
+pub struct GlitchFilter {
+    min_width: Duration,
+    level: Option<Level>,
+    pending: Option<Event>,
+    line_seqno: Option<u32>,
+    lost_events: u64,
+}
+
+impl GlitchFilter {
+    /// Constructs a new `GlitchFilter` that discards pulses shorter than
+    /// `min_width`.
+    pub fn new(min_width: Duration) -> GlitchFilter {
+        GlitchFilter {
+            min_width,
+            level: None,
+            pending: None,
+            line_seqno: None,
+            lost_events: 0,
+        }
+    }
+
+    /// Returns the minimum pulse width.
+    pub fn min_width(&self) -> Duration {
+        self.min_width
+    }
+
+    /// Returns the last confirmed logic level, or `None` if no edges have been
+    /// confirmed yet.
+    pub fn level(&self) -> Option<Level> {
+        self.level
+    }
+
+    /// Returns the edge that's waiting to be confirmed, if any.
+    pub fn pending(&self) -> Option<Event> {
+        self.pending
+    }
+
+    /// Returns the number of events the kernel dropped, based on gaps in the
+    /// line sequence numbers.
+    ///
+    /// Always returns `0` on kernels that don't provide sequence numbers.
+    pub fn lost_events(&self) -> u64 {
+        self.lost_events
+    }
+
+    /// Processes the next `event`, and returns the previously pending edge if
+    /// it's been confirmed.
+    ///
+    /// If `event` ends a pulse shorter than the minimum width, both `event`
+    /// and the pending edge are discarded. Otherwise, the pending edge is
+    /// confirmed, and `event` becomes the new pending edge. Events for the
+    /// same level as the last confirmed or pending edge are ignored.
+    pub fn push(&mut self, event: Event) -> Option<Event> {
+        let lost = self.check_seqno(&event);
+
+        let pending = match self.pending {
+            Some(pending) => pending,
+            None => {
+                if self.level != Some(event.level()) {
+                    self.pending = Some(event);
+                }
+
+                return None;
+            }
+        };
+
+        if event.level() == pending.level() {
+            return None;
+        }
+
+        let width = event.timestamp().saturating_sub(pending.timestamp());
+        if !lost && width < self.min_width {
+            // Runt pulse
+            self.pending = None;
+
+            return None;
+        }
+
+        self.level = Some(pending.level());
+        self.pending = if self.level != Some(event.level()) {
+            Some(event)
+        } else {
+            None
+        };
+
+        Some(pending)
+    }
+
+    /// Confirms and returns the pending edge, if any.
+    ///
+    /// Call `flush` once the minimum width has passed since the pending edge
+    /// occurred without any further events.
+    pub fn flush(&mut self) -> Option<Event> {
+        let pending = self.pending.take()?;
+        self.level = Some(pending.level());
+
+        Some(pending)
+    }
+
+    /// Discards the pending edge and the last confirmed level.
+    pub fn reset(&mut self) {
+        self.level = None;
+        self.pending = None;
+        self.line_seqno = None;
+    }
+
+    // Updates the lost event count, and returns true if any events were lost
+    // since the previous event.
+    fn check_seqno(&mut self, event: &Event) -> bool {
+        let line_seqno = match event.line_seqno() {
+            Some(line_seqno) => line_seqno,
+            None => return false,
+        };
+
+        let lost = match self.line_seqno.replace(line_seqno) {
+            Some(last) => line_seqno.wrapping_sub(last).wrapping_sub(1),
+            None => 0,
+        };
+
+        self.lost_events += u64::from(lost);
+
+        lost > 0
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+use std::sync::atomic::{self, AtomicBool, Ordering};
+
+use crate::gpio::{Backend, Level, Mode, PullUpDown, Result};
+use crate::system::DeviceInfo;
+
+pub mod bcm;
+pub mod cdev;
+pub mod rp1;
+
+// Register-based access to the GPIO pins. Implemented separately for the
+// BCM283x/BCM2711 GPIO peripheral and the RP1 I/O controller, which use
+// different register layouts.
+pub trait GpioRegisters: fmt::Debug + Send + Sync {
+    fn set_high(&self, pin: u8);
+    fn set_low(&self, pin: u8);
+    // Sets the pins in set to high, and then the pins in clear to low. Bit n
+    // corresponds to BCM GPIO n. Only writes to the registers for banks with
+    // at least one affected pin.
+    fn set_clear(&self, set: u64, clear: u64);
+    fn level(&self, pin: u8) -> Level;
+    // Returns the levels of all pins. Bit n corresponds to BCM GPIO n.
+    fn levels(&self) -> u64;
+    fn mode(&self, pin: u8) -> Mode;
+    // Returns false if the peripheral doesn't implement mode.
+    fn supports_mode(&self, _mode: Mode) -> bool {
+        true
+    }
+    fn set_mode(&self, pin: u8, mode: Mode);
+    fn set_pullupdown(&self, pin: u8, pud: PullUpDown);
+    // Releases any resources held for pin. Called when the pin goes out of
+    // scope, and before an interrupt trigger is configured.
+    fn release(&self, _pin: u8) {}
+}
+
+// Orders memory accesses before and after the barrier, for both the compiler
+// and the CPU. Register accesses are volatile, which keeps them in program
+// order relative to each other, but not relative to regular memory accesses.
+// The BCM2835 ARM Peripherals manual (section 1.3) requires a write barrier
+// before the first write to a peripheral, and a read barrier after the last
+// read. A SeqCst fence only partially satisfies this. On ARMv6 it results in a
+// full system DMB, but on ARMv7 and AArch64 it's a DMB ISH, which is limited
+// to the inner shareable domain and doesn't cover peripherals. DMB OSH or
+// DSB SY would require inline assembly, which isn't available in our minimum
+// supported Rust version.
+#[inline(always)]
+pub fn memory_barrier() {
+    atomic::fence(Ordering::SeqCst);
+}
+
+// Selects the appropriate register backend based on the Raspberry Pi model.
+fn open_registers(device_info: &DeviceInfo) -> Result<Box<dyn GpioRegisters>> {
+    if device_info.capabilities().rp1() {
+        Ok(Box::new(rp1::GpioMem::open(device_info)?))
+    } else {
+        Ok(Box::new(bcm::GpioMem::open(device_info)?))
+    }
+}
+
+// Memory-maps the GPIO registers. If neither /dev/gpiomem nor /dev/mem is
+// accessible, which is common inside containers, falls back to the gpiochip
+// character device. The register error is returned if the fallback fails as
+// well, since it's the more relevant one to fix. The fallback is only logged
+// with the log feature enabled, and is otherwise reported through
+// Gpio::backend().
+pub fn open(device_info: &DeviceInfo) -> Result<(Box<dyn GpioRegisters>, Backend)> {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    let err = match open_registers(device_info) {
+        Ok(registers) => return Ok((registers, Backend::Registers)),
+        Err(err) => err,
+    };
+
+    match cdev::GpioCdev::open() {
+        Ok(cdev) => {
+            if !WARNED.swap(true, Ordering::SeqCst) {
+                log_warn!(
+                    "Unable to access the GPIO registers ({}), falling back to the slower gpiochip backend",
+                    err
+                );
+            }
+
+            Ok((Box::new(cdev), Backend::CharacterDevice))
+        }
+        Err(_) => Err(err),
+    }
+}
@@ -10,8 +10,9 @@ use std::time::Duration;
 
 use libc::{self, c_void, off_t, size_t, MAP_FAILED, MAP_SHARED, O_SYNC, PROT_READ, PROT_WRITE};
 
+use crate::gpio::gpiomem::{memory_barrier, GpioRegisters};
 use crate::gpio::{Error, Level, Mode, PullUpDown, Result};
-use crate::system::{DeviceInfo, SoC};
+use crate::system::{DeviceInfo, RegisterBlock, SoC};
 
 const PATH_DEV_GPIOMEM: &str = "/dev/gpiomem";
 const PATH_DEV_MEM: &str = "/dev/mem";
@@ -45,38 +46,45 @@ impl fmt::Debug for GpioMem {
 }
 
 impl GpioMem {
-    pub fn open() -> Result<GpioMem> {
+    pub fn open(device_info: &DeviceInfo) -> Result<GpioMem> {
+        // Identify how we can access the SoC's registers.
+        let capabilities = device_info.capabilities();
+
         // Try /dev/gpiomem first. If that fails, try /dev/mem instead. If neither works,
         // report back the error that's the most relevant.
-        let mem_ptr = match Self::map_devgpiomem() {
+        let gpiomem = if capabilities.requires_devmem(RegisterBlock::Gpio) {
+            Err(Error::Io(io::Error::from(io::ErrorKind::NotFound)))
+        } else {
+            Self::map_devgpiomem()
+        };
+
+        let mem_ptr = match gpiomem {
             Ok(ptr) => ptr,
-            Err(gpiomem_err) => match Self::map_devmem() {
+            Err(gpiomem_err) => match Self::map_devmem(device_info) {
                 Ok(ptr) => ptr,
-                Err(Error::Io(ref e)) if e.kind() == io::ErrorKind::PermissionDenied => {
+                Err(Error::Io(e)) if e.kind() == io::ErrorKind::PermissionDenied => {
                     // Did /dev/gpiomem also give us a Permission Denied error? If so, return
                     // that path instead of /dev/mem. Solving /dev/gpiomem issues should be
                     // preferred (add user to gpio group) over /dev/mem (use sudo),
                     match gpiomem_err {
-                        Error::Io(ref e) if e.kind() == io::ErrorKind::PermissionDenied => {
-                            return Err(Error::PermissionDenied(String::from(PATH_DEV_GPIOMEM)));
+                        Error::Io(gpiomem_e)
+                            if gpiomem_e.kind() == io::ErrorKind::PermissionDenied =>
+                        {
+                            return Err(Error::with_path(gpiomem_e, PATH_DEV_GPIOMEM));
                         }
-                        _ => return Err(Error::PermissionDenied(String::from(PATH_DEV_MEM))),
+                        _ => return Err(Error::with_path(e, PATH_DEV_MEM)),
                     }
                 }
-                Err(Error::UnknownModel) => return Err(Error::UnknownModel),
                 _ => return Err(gpiomem_err),
             },
         };
 
         let locks = init_array!(AtomicBool::new(false), GPIO_MEM_REGISTERS);
 
-        // Identify which SoC we're using.
-        let soc = DeviceInfo::new().map_err(|_| Error::UnknownModel)?.soc();
-
         Ok(GpioMem {
             mem_ptr,
             locks,
-            soc,
+            soc: device_info.soc(),
         })
     }
 
@@ -110,10 +118,7 @@ impl GpioMem {
         Ok(gpiomem_ptr as *mut u32)
     }
 
-    fn map_devmem() -> Result<*mut u32> {
-        // Identify which SoC we're using, so we know what offset to start at
-        let device_info = DeviceInfo::new().map_err(|_| Error::UnknownModel)?;
-
+    fn map_devmem(device_info: &DeviceInfo) -> Result<*mut u32> {
         let mem_file = OpenOptions::new()
             .read(true)
             .write(true)
@@ -128,7 +133,7 @@ impl GpioMem {
                 PROT_READ | PROT_WRITE,
                 MAP_SHARED,
                 mem_file.as_raw_fd(),
-                (device_info.peripheral_base() + device_info.gpio_offset()) as off_t,
+                (device_info.peripheral_base() + u64::from(device_info.gpio_offset())) as off_t,
             )
         };
 
@@ -141,18 +146,24 @@ impl GpioMem {
 
     #[inline(always)]
     fn read(&self, offset: usize) -> u32 {
-        unsafe { ptr::read_volatile(self.mem_ptr.add(offset)) }
+        let value = unsafe { ptr::read_volatile(self.mem_ptr.add(offset)) };
+        memory_barrier();
+
+        value
     }
 
     #[inline(always)]
     fn write(&self, offset: usize, value: u32) {
+        memory_barrier();
         unsafe {
             ptr::write_volatile(self.mem_ptr.add(offset), value);
         }
     }
+}
 
+impl GpioRegisters for GpioMem {
     #[inline(always)]
-    pub(crate) fn set_high(&self, pin: u8) {
+    fn set_high(&self, pin: u8) {
         let offset = GPSET0 + pin as usize / 32;
         let shift = pin % 32;
 
@@ -160,7 +171,7 @@ impl GpioMem {
     }
 
     #[inline(always)]
-    pub(crate) fn set_low(&self, pin: u8) {
+    fn set_low(&self, pin: u8) {
         let offset = GPCLR0 + pin as usize / 32;
         let shift = pin % 32;
 
@@ -168,7 +179,22 @@ impl GpioMem {
     }
 
     #[inline(always)]
-    pub(crate) fn level(&self, pin: u8) -> Level {
+    fn set_clear(&self, set: u64, clear: u64) {
+        // GPSET0/GPCLR0 cover GPIO 0-31, GPSET1/GPCLR1 cover GPIO 32-53.
+        for (register, mask) in [
+            (GPSET0, set as u32),
+            (GPSET0 + 1, (set >> 32) as u32),
+            (GPCLR0, clear as u32),
+            (GPCLR0 + 1, (clear >> 32) as u32),
+        ] {
+            if mask != 0 {
+                self.write(register, mask);
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn level(&self, pin: u8) -> Level {
         let offset = GPLEV0 + pin as usize / 32;
         let shift = pin % 32;
         let reg_value = self.read(offset);
@@ -176,7 +202,13 @@ impl GpioMem {
         unsafe { std::mem::transmute((reg_value >> shift) as u8 & 0b1) }
     }
 
-    pub(crate) fn mode(&self, pin: u8) -> Mode {
+    #[inline(always)]
+    fn levels(&self) -> u64 {
+        // GPLEV0 covers GPIO 0-31, GPLEV1 covers GPIO 32-53.
+        u64::from(self.read(GPLEV0)) | (u64::from(self.read(GPLEV0 + 1)) << 32)
+    }
+
+    fn mode(&self, pin: u8) -> Mode {
         let offset = GPFSEL0 + pin as usize / 10;
         let shift = (pin % 10) * 3;
         let reg_value = self.read(offset);
@@ -184,7 +216,18 @@ impl GpioMem {
         unsafe { std::mem::transmute((reg_value >> shift) as u8 & 0b111) }
     }
 
-    pub(crate) fn set_mode(&self, pin: u8, mode: Mode) {
+    // Alt6 through Alt8 and Null are only available on the RP1.
+    fn supports_mode(&self, mode: Mode) -> bool {
+        !matches!(mode, Mode::Alt6 | Mode::Alt7 | Mode::Alt8 | Mode::Null)
+    }
+
+    fn set_mode(&self, pin: u8, mode: Mode) {
+        // Unsupported modes are rejected by Pin. They don't fit in the 3-bit
+        // FSEL fields, and would overwrite the next pin's mode.
+        if !self.supports_mode(mode) {
+            return;
+        }
+
         let offset = GPFSEL0 + pin as usize / 10;
         let shift = (pin % 10) * 3;
 
@@ -206,7 +249,7 @@ impl GpioMem {
         self.locks[offset].store(false, Ordering::SeqCst);
     }
 
-    pub(crate) fn set_pullupdown(&self, pin: u8, pud: PullUpDown) {
+    fn set_pullupdown(&self, pin: u8, pud: PullUpDown) {
         // Offset for register.
         let offset: usize;
         // Bit shift for pin position within register value.
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::os::unix::io::AsRawFd;
+use std::sync::Mutex;
+
+use libc::EINVAL;
+
+use crate::gpio::gpiomem::GpioRegisters;
+use crate::gpio::ioctl::{
+    self, HandleRequest, LineInfo, HANDLE_FLAG_BIAS_DISABLE, HANDLE_FLAG_BIAS_PULL_DOWN,
+    HANDLE_FLAG_BIAS_PULL_UP, HANDLE_FLAG_INPUT, HANDLE_FLAG_OUTPUT,
+};
+use crate::gpio::{Error, Level, Mode, PullUpDown, Result};
+
+// Pins that can be represented in the 64-bit masks used by set_clear() and
+// levels().
+const LINES_MAX: u8 = 64;
+
+// Configuration of a single line. The kernel only keeps a line's
+// configuration while it's requested, so it's stored here as well, and
+// applied to each new request.
+#[derive(Debug)]
+struct Line {
+    mode: Mode,
+    pud: Option<PullUpDown>,
+    level: Level,
+    handle: Option<HandleRequest>,
+}
+
+// Fallback for systems where the GPIO registers can't be memory-mapped, such
+// as containers that only have access to /dev/gpiochipN. Each pin is
+// controlled through a separate line handle, which is considerably slower
+// than accessing the registers directly. Alternate function modes can't be
+// selected through the character device.
+pub struct GpioCdev {
+    cdev: File,
+    lines: Mutex<HashMap<u8, Line>>,
+}
+
+impl fmt::Debug for GpioCdev {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GpioCdev")
+            .field("cdev", &self.cdev)
+            .field("lines", &format_args!("{{ .. }}"))
+            .finish()
+    }
+}
+
+impl GpioCdev {
+    pub fn open() -> Result<GpioCdev> {
+        Ok(GpioCdev {
+            cdev: ioctl::find_gpiochip()?,
+            lines: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // Calls f with the stored configuration for pin. Lines that haven't been
+    // accessed before are initialized with their current direction and level.
+    fn with_line<F, R>(&self, pin: u8, f: F) -> R
+    where
+        F: FnOnce(&mut Line) -> R,
+    {
+        let mut lines = self.lines.lock().unwrap();
+        let cdev_fd = self.cdev.as_raw_fd();
+
+        let line = lines.entry(pin).or_insert_with(|| {
+            let is_output = LineInfo::get(cdev_fd, pin).map_or(false, |info| info.is_output());
+
+            // Requesting the line without a direction leaves it unchanged
+            let level = HandleRequest::new(cdev_fd, &[pin], 0, &[])
+                .and_then(|handle| handle.levels())
+                .map_or(Level::Low, |data| Level::from(data.values[0]));
+
+            Line {
+                mode: if is_output { Mode::Output } else { Mode::Input },
+                pud: None,
+                level,
+                handle: None,
+            }
+        });
+
+        f(line)
+    }
+
+    // Requests the line for pin with its stored configuration, replacing any
+    // existing request.
+    fn request(&self, pin: u8, line: &mut Line) {
+        // The kernel doesn't allow multiple requests for the same line
+        line.handle = None;
+
+        let cdev_fd = self.cdev.as_raw_fd();
+        let (flags, bias) = match line.mode {
+            Mode::Output => (HANDLE_FLAG_OUTPUT, 0),
+            _ => (
+                HANDLE_FLAG_INPUT,
+                match line.pud {
+                    Some(PullUpDown::Off) => HANDLE_FLAG_BIAS_DISABLE,
+                    Some(PullUpDown::PullDown) => HANDLE_FLAG_BIAS_PULL_DOWN,
+                    Some(PullUpDown::PullUp) => HANDLE_FLAG_BIAS_PULL_UP,
+                    None => 0,
+                },
+            ),
+        };
+
+        let result = match HandleRequest::new(cdev_fd, &[pin], flags | bias, &[line.level]) {
+            // Kernels older than 5.5 don't support bias flags
+            Err(Error::Io(ref e)) if bias != 0 && e.raw_os_error() == Some(EINVAL) => {
+                log_debug!("Pin {} pull-up/pull-down not supported by the kernel", pin);
+
+                HandleRequest::new(cdev_fd, &[pin], flags, &[line.level])
+            }
+            result => result,
+        };
+
+        match result {
+            Ok(handle) => line.handle = Some(handle),
+            Err(_e) => log_debug!("Pin {} line request failed: {}", pin, _e),
+        }
+    }
+
+    fn write(&self, pin: u8, level: Level) {
+        self.with_line(pin, |line| {
+            line.level = level;
+
+            if line.mode != Mode::Output {
+                return;
+            }
+
+            match line.handle {
+                Some(ref mut handle) => {
+                    if let Err(_e) = handle.set_levels(&[level]) {
+                        log_debug!("Pin {} level change failed: {}", pin, _e);
+                    }
+                }
+                None => self.request(pin, line),
+            }
+        });
+    }
+}
+
+impl GpioRegisters for GpioCdev {
+    fn set_high(&self, pin: u8) {
+        self.write(pin, Level::High);
+    }
+
+    fn set_low(&self, pin: u8) {
+        self.write(pin, Level::Low);
+    }
+
+    // Each line has its own handle, so the pins change state one at a time.
+    // Requesting them together would fail while their handles are open.
+    fn set_clear(&self, set: u64, clear: u64) {
+        for pin in 0..LINES_MAX {
+            if set & (1 << pin) != 0 {
+                self.write(pin, Level::High);
+            }
+        }
+
+        for pin in 0..LINES_MAX {
+            if clear & (1 << pin) != 0 {
+                self.write(pin, Level::Low);
+            }
+        }
+    }
+
+    fn level(&self, pin: u8) -> Level {
+        self.with_line(pin, |line| {
+            if line.handle.is_none() {
+                self.request(pin, line);
+            }
+
+            // Lines with an interrupt trigger are read through the interrupt's
+            // event request instead. The request fails while the line is in
+            // use by another process, in which case its level is unknown.
+            match line.handle.as_ref().map(|handle| handle.levels()) {
+                Some(Ok(data)) => Level::from(data.values[0]),
+                Some(Err(_e)) => {
+                    log_warn!("Pin {} level read failed: {}", pin, _e);
+
+                    Level::Low
+                }
+                None => {
+                    log_warn!("Pin {} level unknown, line is in use", pin);
+
+                    Level::Low
+                }
+            }
+        })
+    }
+
+    fn levels(&self) -> u64 {
+        let pins: Vec<u8> = self.lines.lock().unwrap().keys().copied().collect();
+
+        pins.into_iter()
+            .filter(|&pin| pin < LINES_MAX)
+            .fold(0, |levels, pin| levels | ((self.level(pin) as u64) << pin))
+    }
+
+    fn mode(&self, pin: u8) -> Mode {
+        self.with_line(pin, |line| line.mode)
+    }
+
+    fn set_mode(&self, pin: u8, mode: Mode) {
+        if mode != Mode::Input && mode != Mode::Output {
+            log_warn!(
+                "Pin {} mode {} not supported by the gpiochip backend",
+                pin,
+                mode
+            );
+
+            return;
+        }
+
+        self.with_line(pin, |line| {
+            line.mode = mode;
+            self.request(pin, line);
+        });
+    }
+
+    fn set_pullupdown(&self, pin: u8, pud: PullUpDown) {
+        self.with_line(pin, |line| {
+            line.pud = Some(pud);
+
+            if line.mode == Mode::Input {
+                self.request(pin, line);
+            }
+        });
+    }
+
+    fn release(&self, pin: u8) {
+        self.lines.lock().unwrap().remove(&pin);
+    }
+}
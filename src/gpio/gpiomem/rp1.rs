@@ -0,0 +1,323 @@
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+
+use libc::{self, c_void, off_t, size_t, MAP_FAILED, MAP_SHARED, O_SYNC, PROT_READ, PROT_WRITE};
+
+use crate::gpio::gpiomem::{memory_barrier, GpioRegisters};
+use crate::gpio::{Error, Level, Mode, PullUpDown, Result};
+use crate::system::{DeviceInfo, RegisterBlock};
+
+const PATH_DEV_GPIOMEM: &str = "/dev/gpiomem0";
+const PATH_DEV_MEM: &str = "/dev/mem";
+
+// /dev/gpiomem0 maps the IO_BANK, SYS_RIO and PADS register blocks for all
+// three GPIO banks (RP1 datasheet @ 3.1).
+const MEM_SIZE: usize = 0x30000;
+const IO_BANK_OFFSET: usize = 0x0000;
+const RIO_OFFSET: usize = 0x10000;
+const PADS_OFFSET: usize = 0x20000;
+// Offset between the register blocks for each bank.
+const BANK_OFFSET: usize = 0x4000;
+
+// Atomic register access aliases (RP1 datasheet @ 2.4)
+const ATOMIC_XOR: usize = 0x1000;
+const ATOMIC_SET: usize = 0x2000;
+const ATOMIC_CLR: usize = 0x3000;
+
+// IO_BANK registers, 2 per GPIO (RP1 datasheet @ 3.3)
+const GPIO_CTRL: usize = 0x04;
+const GPIO_REG_SIZE: usize = 0x08;
+const CTRL_FUNCSEL_MASK: u32 = 0x1f;
+
+// SYS_RIO registers (RP1 datasheet @ 3.3.2)
+const RIO_OUT: usize = 0x00;
+const RIO_OE: usize = 0x04;
+const RIO_SYNC_IN: usize = 0x0c;
+
+// PADS registers. The first register contains the bank's voltage select
+// setting (RP1 datasheet @ 3.1.3).
+const PADS_GPIO: usize = 0x04;
+const PADS_PDE: u32 = 1 << 2;
+const PADS_PUE: u32 = 1 << 3;
+const PADS_IE: u32 = 1 << 6;
+const PADS_OD: u32 = 1 << 7;
+
+// Function select values
+const FSEL_SYS_RIO: u32 = 5;
+const FSEL_NULL: u32 = 0x1f;
+
+// Number of GPIO pins per bank
+const BANK_PINS: [u8; 3] = [28, 6, 20];
+
+pub struct GpioMem {
+    mem_ptr: *mut u32,
+}
+
+impl fmt::Debug for GpioMem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GpioMem")
+            .field("mem_ptr", &self.mem_ptr)
+            .finish()
+    }
+}
+
+impl GpioMem {
+    pub fn open(device_info: &DeviceInfo) -> Result<GpioMem> {
+        // Try /dev/gpiomem0 first. If that fails, try /dev/mem instead. If neither works,
+        // report back the error that's the most relevant.
+        let gpiomem = if device_info
+            .capabilities()
+            .requires_devmem(RegisterBlock::Gpio)
+        {
+            Err(Error::Io(io::Error::from(io::ErrorKind::NotFound)))
+        } else {
+            Self::map(PATH_DEV_GPIOMEM, 0)
+        };
+
+        let mem_ptr = match gpiomem {
+            Ok(ptr) => ptr,
+            Err(gpiomem_err) => match Self::map(
+                PATH_DEV_MEM,
+                device_info.peripheral_base() + u64::from(device_info.gpio_offset()),
+            ) {
+                Ok(ptr) => ptr,
+                Err(Error::Io(e)) if e.kind() == io::ErrorKind::PermissionDenied => {
+                    // Solving /dev/gpiomem0 issues should be preferred (add user to gpio
+                    // group) over /dev/mem (use sudo).
+                    match gpiomem_err {
+                        Error::Io(gpiomem_e)
+                            if gpiomem_e.kind() == io::ErrorKind::PermissionDenied =>
+                        {
+                            return Err(Error::with_path(gpiomem_e, PATH_DEV_GPIOMEM));
+                        }
+                        _ => return Err(Error::with_path(e, PATH_DEV_MEM)),
+                    }
+                }
+                _ => return Err(gpiomem_err),
+            },
+        };
+
+        Ok(GpioMem { mem_ptr })
+    }
+
+    fn map(path: &str, offset: u64) -> Result<*mut u32> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(O_SYNC)
+            .open(path)?;
+
+        let mem_ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                MEM_SIZE,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED,
+                file.as_raw_fd(),
+                offset as off_t,
+            )
+        };
+
+        if mem_ptr == MAP_FAILED {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+
+        Ok(mem_ptr as *mut u32)
+    }
+
+    // Returns the bank and the pin's index within that bank.
+    #[inline(always)]
+    fn bank(pin: u8) -> (usize, u8) {
+        let mut pin = pin;
+
+        for (bank, &pins) in BANK_PINS.iter().enumerate() {
+            if pin < pins {
+                return (bank, pin);
+            }
+
+            pin -= pins;
+        }
+
+        // Gpio::get already checks the pin number against gpio_lines.
+        unreachable!()
+    }
+
+    #[inline(always)]
+    fn read(&self, offset: usize) -> u32 {
+        let value =
+            unsafe { ptr::read_volatile(self.mem_ptr.add(offset / std::mem::size_of::<u32>())) };
+        memory_barrier();
+
+        value
+    }
+
+    #[inline(always)]
+    fn write(&self, offset: usize, value: u32) {
+        memory_barrier();
+        unsafe {
+            ptr::write_volatile(self.mem_ptr.add(offset / std::mem::size_of::<u32>()), value);
+        }
+    }
+
+    // Atomically changes the bits in mask to value by writing to the XOR alias,
+    // which leaves any other bits untouched.
+    #[inline(always)]
+    fn write_masked(&self, offset: usize, mask: u32, value: u32) {
+        let reg_value = self.read(offset);
+
+        self.write(offset + ATOMIC_XOR, (reg_value ^ value) & mask);
+    }
+
+    #[inline(always)]
+    fn rio(bank: usize, register: usize) -> usize {
+        RIO_OFFSET + bank * BANK_OFFSET + register
+    }
+
+    #[inline(always)]
+    fn ctrl(bank: usize, bank_pin: u8) -> usize {
+        IO_BANK_OFFSET + bank * BANK_OFFSET + bank_pin as usize * GPIO_REG_SIZE + GPIO_CTRL
+    }
+
+    #[inline(always)]
+    fn pads(bank: usize, bank_pin: u8) -> usize {
+        PADS_OFFSET + bank * BANK_OFFSET + PADS_GPIO + bank_pin as usize * 4
+    }
+}
+
+impl GpioRegisters for GpioMem {
+    #[inline(always)]
+    fn set_high(&self, pin: u8) {
+        let (bank, bank_pin) = Self::bank(pin);
+
+        self.write(Self::rio(bank, RIO_OUT) + ATOMIC_SET, 1 << bank_pin);
+    }
+
+    #[inline(always)]
+    fn set_low(&self, pin: u8) {
+        let (bank, bank_pin) = Self::bank(pin);
+
+        self.write(Self::rio(bank, RIO_OUT) + ATOMIC_CLR, 1 << bank_pin);
+    }
+
+    #[inline(always)]
+    fn set_clear(&self, set: u64, clear: u64) {
+        for (alias, mask) in [(ATOMIC_SET, set), (ATOMIC_CLR, clear)] {
+            let mut mask = mask;
+
+            for (bank, &pins) in BANK_PINS.iter().enumerate() {
+                let bank_mask = (mask & ((1 << pins) - 1)) as u32;
+                if bank_mask != 0 {
+                    self.write(Self::rio(bank, RIO_OUT) + alias, bank_mask);
+                }
+
+                mask >>= pins;
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn level(&self, pin: u8) -> Level {
+        let (bank, bank_pin) = Self::bank(pin);
+        let reg_value = self.read(Self::rio(bank, RIO_SYNC_IN));
+
+        Level::from(((reg_value >> bank_pin) & 0b1) as u8)
+    }
+
+    #[inline(always)]
+    fn levels(&self) -> u64 {
+        let mut levels = 0;
+        let mut shift = 0;
+
+        for (bank, &pins) in BANK_PINS.iter().enumerate() {
+            let bank_levels = self.read(Self::rio(bank, RIO_SYNC_IN)) & ((1 << pins) - 1);
+            levels |= u64::from(bank_levels) << shift;
+            shift += pins;
+        }
+
+        levels
+    }
+
+    fn mode(&self, pin: u8) -> Mode {
+        let (bank, bank_pin) = Self::bank(pin);
+        let fsel = self.read(Self::ctrl(bank, bank_pin)) & CTRL_FUNCSEL_MASK;
+
+        match fsel {
+            FSEL_SYS_RIO => {
+                if (self.read(Self::rio(bank, RIO_OE)) >> bank_pin) & 0b1 == 1 {
+                    Mode::Output
+                } else {
+                    Mode::Input
+                }
+            }
+            0 => Mode::Alt0,
+            1 => Mode::Alt1,
+            2 => Mode::Alt2,
+            3 => Mode::Alt3,
+            4 => Mode::Alt4,
+            6 => Mode::Alt6,
+            7 => Mode::Alt7,
+            8 => Mode::Alt8,
+            _ => Mode::Null,
+        }
+    }
+
+    fn set_mode(&self, pin: u8, mode: Mode) {
+        let (bank, bank_pin) = Self::bank(pin);
+
+        let fsel = match mode {
+            Mode::Input => {
+                self.write(Self::rio(bank, RIO_OE) + ATOMIC_CLR, 1 << bank_pin);
+                FSEL_SYS_RIO
+            }
+            Mode::Output => {
+                self.write(Self::rio(bank, RIO_OE) + ATOMIC_SET, 1 << bank_pin);
+                FSEL_SYS_RIO
+            }
+            Mode::Alt0 => 0,
+            Mode::Alt1 => 1,
+            Mode::Alt2 => 2,
+            Mode::Alt3 => 3,
+            Mode::Alt4 => 4,
+            Mode::Alt5 => 5,
+            Mode::Alt6 => 6,
+            Mode::Alt7 => 7,
+            Mode::Alt8 => 8,
+            Mode::Null => FSEL_NULL,
+        };
+
+        // Enable the input and output buffers, unless the pin is disconnected.
+        let pads = if mode == Mode::Null { PADS_OD } else { PADS_IE };
+        self.write_masked(Self::pads(bank, bank_pin), PADS_IE | PADS_OD, pads);
+
+        self.write_masked(Self::ctrl(bank, bank_pin), CTRL_FUNCSEL_MASK, fsel);
+    }
+
+    fn set_pullupdown(&self, pin: u8, pud: PullUpDown) {
+        let (bank, bank_pin) = Self::bank(pin);
+
+        let bias = match pud {
+            PullUpDown::Off => 0,
+            PullUpDown::PullDown => PADS_PDE,
+            PullUpDown::PullUp => PADS_PUE,
+        };
+
+        self.write_masked(Self::pads(bank, bank_pin), PADS_PDE | PADS_PUE, bias);
+    }
+}
+
+impl Drop for GpioMem {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.mem_ptr as *mut c_void, MEM_SIZE as size_t);
+        }
+    }
+}
+
+// Required because of the raw pointer to our memory-mapped file
+unsafe impl Send for GpioMem {}
+
+unsafe impl Sync for GpioMem {}
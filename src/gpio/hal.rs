@@ -1,18 +1,23 @@
 use core::convert::Infallible;
 
+#[cfg(feature = "hal-1")]
 use embedded_hal::digital::{
     ErrorType, InputPin as InputPinHal, OutputPin as OutputPinHal,
     StatefulOutputPin as StatefulOutputPinHal, ToggleableOutputPin as ToggleableOutputPinHal,
 };
 
-use super::{InputPin, IoPin, Level, OutputPin, Pin};
+#[cfg(feature = "hal-1")]
+use super::{InputPin, Level, Pin};
+use super::{IoPin, OutputPin};
 
 /// `ErrorType` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl ErrorType for Pin {
     type Error = Infallible;
 }
 
 /// `InputPin` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl InputPinHal for Pin {
     fn is_high(&self) -> Result<bool, Self::Error> {
         Ok(Self::read(self) == Level::High)
@@ -24,11 +29,13 @@ impl InputPinHal for Pin {
 }
 
 /// `ErrorType` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl ErrorType for InputPin {
     type Error = Infallible;
 }
 
 /// `InputPin` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl InputPinHal for InputPin {
     fn is_high(&self) -> Result<bool, Self::Error> {
         Ok(Self::is_high(self))
@@ -40,11 +47,13 @@ impl InputPinHal for InputPin {
 }
 
 /// `ErrorType` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl ErrorType for IoPin {
     type Error = Infallible;
 }
 
 /// `InputPin` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl InputPinHal for IoPin {
     fn is_high(&self) -> Result<bool, Self::Error> {
         Ok(Self::is_high(self))
@@ -56,11 +65,13 @@ impl InputPinHal for IoPin {
 }
 
 /// `ErrorType` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl ErrorType for OutputPin {
     type Error = Infallible;
 }
 
 /// `InputPin` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl InputPinHal for OutputPin {
     fn is_high(&self) -> Result<bool, Self::Error> {
         Ok(Self::is_set_high(self))
@@ -72,6 +83,7 @@ impl InputPinHal for OutputPin {
 }
 
 /// `OutputPin` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl OutputPinHal for OutputPin {
     fn set_low(&mut self) -> Result<(), Self::Error> {
         OutputPin::set_low(self);
@@ -87,19 +99,25 @@ impl OutputPinHal for OutputPin {
 }
 
 /// `OutputPin` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::digital::v2::OutputPin for OutputPin {
     type Error = Infallible;
 
     fn set_low(&mut self) -> Result<(), Self::Error> {
-        OutputPinHal::set_low(self)
+        OutputPin::set_low(self);
+
+        Ok(())
     }
 
     fn set_high(&mut self) -> Result<(), Self::Error> {
-        OutputPinHal::set_high(self)
+        OutputPin::set_high(self);
+
+        Ok(())
     }
 }
 
 /// `StatefulOutputPin` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl StatefulOutputPinHal for OutputPin {
     fn is_set_high(&self) -> Result<bool, Self::Error> {
         Ok(OutputPin::is_set_high(self))
@@ -111,6 +129,7 @@ impl StatefulOutputPinHal for OutputPin {
 }
 
 /// `ToggleableOutputPin` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl ToggleableOutputPinHal for OutputPin {
     fn toggle(&mut self) -> Result<(), Self::Error> {
         OutputPin::toggle(self);
@@ -120,6 +139,7 @@ impl ToggleableOutputPinHal for OutputPin {
 }
 
 /// `OutputPin` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl OutputPinHal for IoPin {
     fn set_low(&mut self) -> Result<(), Self::Error> {
         IoPin::set_low(self);
@@ -135,19 +155,25 @@ impl OutputPinHal for IoPin {
 }
 
 /// `OutputPin` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::digital::v2::OutputPin for IoPin {
     type Error = Infallible;
 
     fn set_low(&mut self) -> Result<(), Self::Error> {
-        OutputPinHal::set_low(self)
+        IoPin::set_low(self);
+
+        Ok(())
     }
 
     fn set_high(&mut self) -> Result<(), Self::Error> {
-        OutputPinHal::set_high(self)
+        IoPin::set_high(self);
+
+        Ok(())
     }
 }
 
 /// `StatefulOutputPin` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl StatefulOutputPinHal for IoPin {
     fn is_set_high(&self) -> Result<bool, Self::Error> {
         Ok(IoPin::is_high(self))
@@ -159,6 +185,7 @@ impl StatefulOutputPinHal for IoPin {
 }
 
 /// `ToggleableOutputPin` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl ToggleableOutputPinHal for IoPin {
     fn toggle(&mut self) -> Result<(), Self::Error> {
         IoPin::toggle(self);
@@ -168,6 +195,7 @@ impl ToggleableOutputPinHal for IoPin {
 }
 
 /// `PwmPin` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::PwmPin for OutputPin {
     type Duty = f64;
 
@@ -197,6 +225,7 @@ impl embedded_hal_0::PwmPin for OutputPin {
 }
 
 /// `PwmPin` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::PwmPin for IoPin {
     type Duty = f64;
 
@@ -1,5 +1,5 @@
 use core::convert::Infallible;
-use std::time::Duration;
+use core::time::Duration;
 
 use embedded_hal::digital::{
     InputPin as InputPinHal, StatefulOutputPin as StatefulOutputPinHal,
@@ -230,7 +230,7 @@ impl embedded_hal_0::digital::v2::IoPin<IoPin, IoPin> for IoPin {
     /// If the pin is already in input mode, this method should succeed.
     fn into_input_pin(mut self) -> Result<IoPin, Self::Error> {
         if self.mode() != Mode::Input {
-            self.set_mode(Mode::Input);
+            self.switch_mode(Mode::Input);
         }
 
         Ok(self)
@@ -250,7 +250,7 @@ impl embedded_hal_0::digital::v2::IoPin<IoPin, IoPin> for IoPin {
         }
 
         if self.mode() != Mode::Output {
-            self.set_mode(Mode::Output);
+            self.switch_mode(Mode::Output);
         }
 
         Ok(self)
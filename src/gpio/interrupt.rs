@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 use std::fmt;
+use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -45,9 +46,25 @@ impl Interrupt {
         self.reset()
     }
 
+    // Changes the trigger without requesting a new event fd. Returns false if
+    // the kernel doesn't support this, in which case nothing is changed.
+    fn reconfigure(&mut self, trigger: Trigger) -> Result<bool> {
+        if !self.event_request.set_trigger(trigger)? {
+            return Ok(false);
+        }
+
+        self.trigger = trigger;
+
+        Ok(true)
+    }
+
+    fn level(&self) -> Result<Level> {
+        self.event_request.level()
+    }
+
     fn event(&mut self) -> Result<ioctl::Event> {
         // This might block if there are no events waiting
-        ioctl::get_event(self.event_request.fd)
+        self.event_request.event()
     }
 
     fn reset(&mut self) -> Result<()> {
@@ -62,8 +79,7 @@ impl Interrupt {
 #[derive(Debug)]
 struct TriggerStatus {
     interrupt: Option<Interrupt>,
-    triggered: bool,
-    level: Level,
+    event: Option<ioctl::Event>,
 }
 
 pub struct EventLoop {
@@ -92,8 +108,7 @@ impl EventLoop {
         for _ in 0..trigger_status.capacity() {
             trigger_status.push(TriggerStatus {
                 interrupt: None,
-                triggered: false,
-                level: Level::Low,
+                event: None,
             });
         }
 
@@ -110,16 +125,29 @@ impl EventLoop {
         pins: &[&'a InputPin],
         reset: bool,
         timeout: Option<Duration>,
-    ) -> Result<Option<(&'a InputPin, Level)>> {
-        for pin in pins {
-            let trigger_status = &mut self.trigger_status[pin.pin() as usize];
+    ) -> Result<Option<(&'a InputPin, ioctl::Event)>> {
+        let pin_numbers: Vec<u8> = pins.iter().map(|pin| pin.pin()).collect();
 
-            // Did we cache any trigger events during the previous poll?
-            if trigger_status.triggered {
-                trigger_status.triggered = false;
+        Ok(self
+            .poll_pins(&pin_numbers, reset, timeout)?
+            .map(|(index, event)| (pins[index], event)))
+    }
 
+    // Polls the specified pins, and returns the index of the pin that triggered
+    // an interrupt together with its event.
+    pub fn poll_pins(
+        &mut self,
+        pins: &[u8],
+        reset: bool,
+        timeout: Option<Duration>,
+    ) -> Result<Option<(usize, ioctl::Event)>> {
+        for (index, &pin) in pins.iter().enumerate() {
+            let trigger_status = &mut self.trigger_status[pin as usize];
+
+            // Did we cache any trigger events during the previous poll?
+            if let Some(event) = trigger_status.event.take() {
                 if !reset {
-                    return Ok(Some((pin, trigger_status.level)));
+                    return Ok(Some((index, event)));
                 }
             }
 
@@ -153,19 +181,17 @@ impl EventLoop {
                 let trigger_status = &mut self.trigger_status[pin];
 
                 if let Some(ref mut interrupt) = trigger_status.interrupt {
-                    trigger_status.level = interrupt.event()?.level();
-                    trigger_status.triggered = true;
+                    trigger_status.event = Some(interrupt.event()?);
                 };
             }
 
             // Were any interrupts triggered? If so, return one. The rest
             // will be saved for the next poll.
-            for pin in pins {
-                let trigger_status = &mut self.trigger_status[pin.pin() as usize];
+            for (index, &pin) in pins.iter().enumerate() {
+                let trigger_status = &mut self.trigger_status[pin as usize];
 
-                if trigger_status.triggered {
-                    trigger_status.triggered = false;
-                    return Ok(Some((pin, trigger_status.level)));
+                if let Some(event) = trigger_status.event.take() {
+                    return Ok(Some((index, event)));
                 }
             }
 
@@ -184,7 +210,7 @@ impl EventLoop {
     pub fn set_interrupt(&mut self, pin: u8, trigger: Trigger) -> Result<()> {
         let trigger_status = &mut self.trigger_status[pin as usize];
 
-        trigger_status.triggered = false;
+        trigger_status.event = None;
 
         // Interrupt already exists. We just need to change the trigger.
         if let Some(ref mut interrupt) = trigger_status.interrupt {
@@ -208,10 +234,28 @@ impl EventLoop {
         Ok(())
     }
 
+    // Changes the trigger of an existing interrupt. Unlike set_interrupt, any
+    // cached events are kept, and the event fd is reused when supported by the
+    // kernel, so no events are lost.
+    pub fn set_trigger(&mut self, pin: u8, trigger: Trigger) -> Result<()> {
+        let trigger_status = &mut self.trigger_status[pin as usize];
+
+        if let Some(ref mut interrupt) = trigger_status.interrupt {
+            if interrupt.trigger != trigger && !interrupt.reconfigure(trigger)? {
+                self.poll.delete(interrupt.fd())?;
+                interrupt.set_trigger(trigger)?;
+                self.poll
+                    .add(interrupt.fd(), u64::from(pin), EPOLLIN | EPOLLPRI)?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn clear_interrupt(&mut self, pin: u8) -> Result<()> {
         let trigger_status = &mut self.trigger_status[pin as usize];
 
-        trigger_status.triggered = false;
+        trigger_status.event = None;
 
         if let Some(interrupt) = trigger_status.interrupt.take() {
             self.poll.delete(interrupt.fd())?;
@@ -219,30 +263,71 @@ impl EventLoop {
 
         Ok(())
     }
+
+    pub fn has_interrupt(&self, pin: u8) -> bool {
+        self.trigger_status[pin as usize].interrupt.is_some()
+    }
+
+    // Returns the event fd for pin, which becomes readable when a new
+    // trigger event is available.
+    pub fn interrupt_fd(&self, pin: u8) -> Option<i32> {
+        self.trigger_status[pin as usize]
+            .interrupt
+            .as_ref()
+            .map(|interrupt| interrupt.fd())
+    }
+
+    // Reads the current level of pin through its event request, if an
+    // interrupt is configured.
+    pub fn interrupt_level(&self, pin: u8) -> Option<Result<Level>> {
+        self.trigger_status[pin as usize]
+            .interrupt
+            .as_ref()
+            .map(|interrupt| interrupt.level())
+    }
 }
 
+type Callback = Box<dyn FnMut(Level) + Send>;
+
 #[derive(Debug)]
 pub struct AsyncInterrupt {
-    poll_thread: Option<thread::JoinHandle<Result<()>>>,
+    poll_thread: Option<thread::JoinHandle<Result<Callback>>>,
     tx: EventFd,
+    event_request: Arc<ioctl::EventRequest>,
+    cdev_fd: i32,
+    pin: u8,
 }
 
 impl AsyncInterrupt {
-    pub fn new<C>(fd: i32, pin: u8, trigger: Trigger, mut callback: C) -> Result<AsyncInterrupt>
+    pub fn new<C>(fd: i32, pin: u8, trigger: Trigger, callback: C) -> Result<AsyncInterrupt>
     where
         C: FnMut(Level) + Send + 'static,
     {
+        AsyncInterrupt::spawn(fd, pin, trigger, Box::new(callback))
+    }
+
+    fn spawn(
+        cdev_fd: i32,
+        pin: u8,
+        trigger: Trigger,
+        mut callback: Callback,
+    ) -> Result<AsyncInterrupt> {
         let tx = EventFd::new()?;
         let rx = tx.fd();
 
-        let poll_thread = thread::spawn(move || -> Result<()> {
+        // The event fd is shared with the poll thread, so the trigger can be
+        // changed from the main thread while the poll thread is running.
+        let event_request = Arc::new(ioctl::EventRequest::new(cdev_fd, pin, trigger)?);
+        let thread_event_request = event_request.clone();
+
+        let poll_thread = thread::spawn(move || -> Result<Callback> {
             let poll = Epoll::new()?;
 
             // rx becomes readable when the main thread calls notify()
             poll.add(rx, rx as u64, EPOLLERR | EPOLLET | EPOLLIN)?;
 
-            let mut interrupt = Interrupt::new(fd, pin, trigger)?;
-            poll.add(interrupt.fd(), interrupt.fd() as u64, EPOLLIN | EPOLLPRI)?;
+            let event_fd = thread_event_request.fd;
+            poll.add(event_fd, event_fd as u64, EPOLLIN | EPOLLPRI)?;
 
             let mut events = [epoll_event { events: 0, u64: 0 }; 2];
             loop {
@@ -251,9 +336,9 @@ impl AsyncInterrupt {
                     for event in &events[0..num_events] {
                         let fd = event.u64 as i32;
                         if fd == rx {
-                            return Ok(()); // The main thread asked us to stop
-                        } else if fd == interrupt.fd() {
-                            let level = interrupt.event()?.level();
+                            return Ok(callback); // The main thread asked us to stop
+                        } else if fd == event_fd {
+                            let level = thread_event_request.event()?.level();
                             callback(level);
                         }
                     }
@@ -264,20 +349,52 @@ impl AsyncInterrupt {
         Ok(AsyncInterrupt {
             poll_thread: Some(poll_thread),
             tx,
+            event_request,
+            cdev_fd,
+            pin,
         })
     }
 
+    // Changes the trigger in place when supported by the kernel. Otherwise,
+    // the poll thread is restarted with a new event request for the same
+    // callback.
+    pub fn set_trigger(&mut self, trigger: Trigger) -> Result<()> {
+        if self.event_request.set_trigger(trigger)? {
+            return Ok(());
+        }
+
+        if let Some(callback) = self.join()? {
+            *self = AsyncInterrupt::spawn(self.cdev_fd, self.pin, trigger, callback)?;
+        }
+
+        Ok(())
+    }
+
+    // Reads the current level through the event request used by the poll
+    // thread.
+    pub fn level(&self) -> Result<Level> {
+        self.event_request.level()
+    }
+
     pub fn stop(&mut self) -> Result<()> {
+        self.join()?;
+
+        Ok(())
+    }
+
+    // Stops the poll thread, and returns the callback if the thread was
+    // still running.
+    fn join(&mut self) -> Result<Option<Callback>> {
         self.tx.notify()?;
 
         if let Some(poll_thread) = self.poll_thread.take() {
             match poll_thread.join() {
-                Ok(r) => return r,
+                Ok(r) => return r.map(Some),
                 Err(_) => return Err(Error::ThreadPanic),
             }
         }
 
-        Ok(())
+        Ok(None)
     }
 }
 
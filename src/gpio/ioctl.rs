@@ -2,7 +2,7 @@
 #![allow(dead_code)]
 
 use crate::gpio::{Error, Level, Result, Trigger};
-use libc::{self, c_int, c_ulong, c_void, ENOENT};
+use libc::{self, c_int, c_ulong, c_void, EINVAL, ENOENT, ENOTTY};
 use std::ffi::CString;
 use std::fmt;
 use std::fs::{File, OpenOptions};
@@ -20,6 +20,7 @@ const PATH_GPIOCHIP: &str = "/dev/gpiochip";
 const CONSUMER_LABEL: &str = "RPPAL";
 const DRIVER_NAME: &[u8] = b"pinctrl-bcm2835\0";
 const DRIVER_NAME_CM4: &[u8] = b"pinctrl-bcm2711\0";
+const DRIVER_NAME_RP1: &[u8] = b"pinctrl-rp1\0";
 const NRBITS: u8 = 8;
 const TYPEBITS: u8 = 8;
 const SIZEBITS: u8 = 14;
@@ -34,13 +35,21 @@ const NR_GET_LINE_HANDLE: IoctlLong = 0x03 << NRSHIFT;
 const NR_GET_LINE_EVENT: IoctlLong = 0x04 << NRSHIFT;
 const NR_GET_LINE_VALUES: IoctlLong = 0x08 << NRSHIFT;
 const NR_SET_LINE_VALUES: IoctlLong = 0x09 << NRSHIFT;
+const NR_GET_LINE_INFO_WATCH_V2: IoctlLong = 0x06 << NRSHIFT;
+const NR_GET_LINE_V2: IoctlLong = 0x07 << NRSHIFT;
+const NR_SET_LINE_CONFIG_V2: IoctlLong = 0x0D << NRSHIFT;
+const NR_GET_LINE_VALUES_V2: IoctlLong = 0x0E << NRSHIFT;
 const TYPE_GPIO: IoctlLong = (0xB4 as IoctlLong) << TYPESHIFT;
 
 const SIZE_CHIP_INFO: IoctlLong = (mem::size_of::<ChipInfo>() as IoctlLong) << SIZESHIFT;
 const SIZE_LINE_INFO: IoctlLong = (mem::size_of::<LineInfo>() as IoctlLong) << SIZESHIFT;
 const SIZE_HANDLE_REQUEST: IoctlLong = (mem::size_of::<HandleRequest>() as IoctlLong) << SIZESHIFT;
-const SIZE_EVENT_REQUEST: IoctlLong = (mem::size_of::<EventRequest>() as IoctlLong) << SIZESHIFT;
+const SIZE_EVENT_REQUEST: IoctlLong = (mem::size_of::<EventRequestV1>() as IoctlLong) << SIZESHIFT;
 const SIZE_HANDLE_DATA: IoctlLong = (mem::size_of::<HandleData>() as IoctlLong) << SIZESHIFT;
+const SIZE_LINE_REQUEST_V2: IoctlLong = (mem::size_of::<LineRequestV2>() as IoctlLong) << SIZESHIFT;
+const SIZE_LINE_CONFIG_V2: IoctlLong = (mem::size_of::<LineConfigV2>() as IoctlLong) << SIZESHIFT;
+const SIZE_LINE_VALUES_V2: IoctlLong = (mem::size_of::<LineValuesV2>() as IoctlLong) << SIZESHIFT;
+const SIZE_LINE_INFO_V2: IoctlLong = (mem::size_of::<LineInfoV2>() as IoctlLong) << SIZESHIFT;
 
 const DIR_NONE: c_ulong = 0;
 const DIR_WRITE: IoctlLong = 1 << DIRSHIFT;
@@ -57,6 +66,14 @@ const REQ_GET_LINE_VALUES: IoctlLong =
     DIR_READ_WRITE | TYPE_GPIO | NR_GET_LINE_VALUES | SIZE_HANDLE_DATA;
 const REQ_SET_LINE_VALUES: IoctlLong =
     DIR_READ_WRITE | TYPE_GPIO | NR_SET_LINE_VALUES | SIZE_HANDLE_DATA;
+const REQ_GET_LINE_V2: IoctlLong =
+    DIR_READ_WRITE | TYPE_GPIO | NR_GET_LINE_V2 | SIZE_LINE_REQUEST_V2;
+const REQ_SET_LINE_CONFIG_V2: IoctlLong =
+    DIR_READ_WRITE | TYPE_GPIO | NR_SET_LINE_CONFIG_V2 | SIZE_LINE_CONFIG_V2;
+const REQ_GET_LINE_VALUES_V2: IoctlLong =
+    DIR_READ_WRITE | TYPE_GPIO | NR_GET_LINE_VALUES_V2 | SIZE_LINE_VALUES_V2;
+const REQ_GET_LINE_INFO_WATCH_V2: IoctlLong =
+    DIR_READ_WRITE | TYPE_GPIO | NR_GET_LINE_INFO_WATCH_V2 | SIZE_LINE_INFO_V2;
 
 const NAME_BUFSIZE: usize = 32;
 const LABEL_BUFSIZE: usize = 32;
@@ -77,7 +94,8 @@ impl ChipInfo {
             lines: 0,
         };
 
-        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_CHIP_INFO, &mut chip_info) })?;
+        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_CHIP_INFO, &mut chip_info) })
+            .map_err(|e| Error::with_fd(e, cdev_fd))?;
 
         Ok(chip_info)
     }
@@ -119,6 +137,34 @@ impl LineInfo {
     }
 }
 
+impl LineInfo {
+    pub fn get(cdev_fd: c_int, pin: u8) -> Result<LineInfo> {
+        let mut line_info = LineInfo::new();
+        line_info.line_offset = u32::from(pin);
+
+        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_INFO, &mut line_info) })
+            .map_err(|e| Error::with_fd(e, cdev_fd))?;
+
+        Ok(line_info)
+    }
+
+    pub fn is_output(&self) -> bool {
+        (self.flags & LINE_FLAG_IS_OUT) > 0
+    }
+
+    // Returns true if the line is requested by any consumer, including the
+    // kernel itself.
+    pub fn is_used(&self) -> bool {
+        (self.flags & LINE_FLAG_KERNEL) > 0
+    }
+
+    pub fn consumer(&self) -> String {
+        cbuf_to_cstring(&self.consumer)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
 impl fmt::Debug for LineInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("LineInfo")
@@ -131,11 +177,15 @@ impl fmt::Debug for LineInfo {
 }
 
 const HANDLES_MAX: usize = 64;
-const HANDLE_FLAG_INPUT: u32 = 0x01;
-const HANDLE_FLAG_OUTPUT: u32 = 0x02;
+pub const HANDLE_FLAG_INPUT: u32 = 0x01;
+pub const HANDLE_FLAG_OUTPUT: u32 = 0x02;
 const HANDLE_FLAG_ACTIVE_LOW: u32 = 0x04;
 const HANDLE_FLAG_OPEN_DRAIN: u32 = 0x08;
 const HANDLE_FLAG_OPEN_SOURCE: u32 = 0x10;
+// Bias flags are supported on kernel 5.5 and later
+pub const HANDLE_FLAG_BIAS_PULL_UP: u32 = 0x20;
+pub const HANDLE_FLAG_BIAS_PULL_DOWN: u32 = 0x40;
+pub const HANDLE_FLAG_BIAS_DISABLE: u32 = 0x80;
 
 #[repr(C)]
 pub struct HandleRequest {
@@ -148,10 +198,18 @@ pub struct HandleRequest {
 }
 
 impl HandleRequest {
-    pub fn new(cdev_fd: c_int, pins: &[u8]) -> Result<HandleRequest> {
+    // Requests the lines for pins. If flags doesn't contain a direction, the
+    // current direction is left unchanged. default_values is only used for
+    // outputs.
+    pub fn new(
+        cdev_fd: c_int,
+        pins: &[u8],
+        flags: u32,
+        default_values: &[Level],
+    ) -> Result<HandleRequest> {
         let mut handle_request = HandleRequest {
             line_offsets: [0u32; HANDLES_MAX],
-            flags: 0,
+            flags,
             default_values: [0u8; HANDLES_MAX],
             consumer_label: [0u8; LABEL_BUFSIZE],
             lines: 0,
@@ -170,11 +228,16 @@ impl HandleRequest {
             handle_request.line_offsets[idx] = u32::from(*pin);
         }
 
+        for (idx, level) in default_values.iter().take(HANDLES_MAX).enumerate() {
+            handle_request.default_values[idx] = *level as u8;
+        }
+
         // Set consumer label, so other processes know we're using these pins
         handle_request.consumer_label[0..CONSUMER_LABEL.len()]
             .copy_from_slice(CONSUMER_LABEL.as_bytes());
 
-        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_HANDLE, &mut handle_request) })?;
+        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_HANDLE, &mut handle_request) })
+            .map_err(|e| Error::with_fd(e, cdev_fd))?;
 
         // If the handle fd is zero or negative, an error occurred
         if handle_request.fd <= 0 {
@@ -187,7 +250,8 @@ impl HandleRequest {
     pub fn levels(&self) -> Result<HandleData> {
         let mut handle_data = HandleData::new();
 
-        parse_retval!(unsafe { libc::ioctl(self.fd, REQ_GET_LINE_VALUES, &mut handle_data) })?;
+        parse_retval!(unsafe { libc::ioctl(self.fd, REQ_GET_LINE_VALUES, &mut handle_data) })
+            .map_err(|e| Error::with_fd(e, self.fd))?;
 
         Ok(handle_data)
     }
@@ -204,7 +268,8 @@ impl HandleRequest {
             handle_data.values[idx] = *level as u8;
         }
 
-        parse_retval!(unsafe { libc::ioctl(self.fd, REQ_SET_LINE_VALUES, &mut handle_data) })?;
+        parse_retval!(unsafe { libc::ioctl(self.fd, REQ_SET_LINE_VALUES, &mut handle_data) })
+            .map_err(|e| Error::with_fd(e, self.fd))?;
 
         Ok(())
     }
@@ -271,18 +336,19 @@ const EVENT_FLAG_RISING_EDGE: u32 = 0x01;
 const EVENT_FLAG_FALLING_EDGE: u32 = 0x02;
 const EVENT_FLAG_BOTH_EDGES: u32 = EVENT_FLAG_RISING_EDGE | EVENT_FLAG_FALLING_EDGE;
 
+// The deprecated v1 event request, used on kernels older than 5.10
 #[repr(C)]
-pub struct EventRequest {
-    pub line_offset: u32,
-    pub handle_flags: u32,
-    pub event_flags: u32,
-    pub consumer_label: [u8; LABEL_BUFSIZE],
-    pub fd: c_int,
+struct EventRequestV1 {
+    line_offset: u32,
+    handle_flags: u32,
+    event_flags: u32,
+    consumer_label: [u8; LABEL_BUFSIZE],
+    fd: c_int,
 }
 
-impl EventRequest {
-    pub fn new(cdev_fd: c_int, pin: u8, trigger: Trigger) -> Result<EventRequest> {
-        let mut event_request = EventRequest {
+impl EventRequestV1 {
+    fn new(cdev_fd: c_int, pin: u8, trigger: Trigger) -> Result<EventRequestV1> {
+        let mut event_request = EventRequestV1 {
             line_offset: u32::from(pin),
             handle_flags: HANDLE_FLAG_INPUT,
             event_flags: trigger as u32,
@@ -294,13 +360,265 @@ impl EventRequest {
         event_request.consumer_label[0..CONSUMER_LABEL.len()]
             .copy_from_slice(CONSUMER_LABEL.as_bytes());
 
-        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_EVENT, &mut event_request) })?;
+        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_EVENT, &mut event_request) })
+            .map_err(|e| Error::with_fd(e, cdev_fd))?;
+
+        Ok(event_request)
+    }
+}
+
+const LINES_MAX_V2: usize = 64;
+const LINE_NUM_ATTRS_MAX_V2: usize = 10;
+const LINE_FLAG_V2_INPUT: u64 = 1 << 2;
+const LINE_FLAG_V2_EDGE_RISING: u64 = 1 << 4;
+const LINE_FLAG_V2_EDGE_FALLING: u64 = 1 << 5;
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct LineConfigAttributeV2 {
+    id: u32,
+    padding: u32,
+    value: u64,
+    mask: u64,
+}
+
+#[repr(C)]
+struct LineConfigV2 {
+    flags: u64,
+    num_attrs: u32,
+    padding: [u32; 5],
+    attrs: [LineConfigAttributeV2; LINE_NUM_ATTRS_MAX_V2],
+}
+
+impl LineConfigV2 {
+    fn new(trigger: Trigger) -> LineConfigV2 {
+        let mut flags = LINE_FLAG_V2_INPUT;
+        if trigger == Trigger::RisingEdge || trigger == Trigger::Both {
+            flags |= LINE_FLAG_V2_EDGE_RISING;
+        }
+        if trigger == Trigger::FallingEdge || trigger == Trigger::Both {
+            flags |= LINE_FLAG_V2_EDGE_FALLING;
+        }
+
+        LineConfigV2 {
+            flags,
+            num_attrs: 0,
+            padding: [0u32; 5],
+            attrs: [LineConfigAttributeV2 {
+                id: 0,
+                padding: 0,
+                value: 0,
+                mask: 0,
+            }; LINE_NUM_ATTRS_MAX_V2],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+struct LineValuesV2 {
+    bits: u64,
+    mask: u64,
+}
+
+#[repr(C)]
+struct LineRequestV2 {
+    offsets: [u32; LINES_MAX_V2],
+    consumer: [u8; LABEL_BUFSIZE],
+    config: LineConfigV2,
+    num_lines: u32,
+    event_buffer_size: u32,
+    padding: [u32; 5],
+    fd: c_int,
+}
+
+impl LineRequestV2 {
+    fn new(cdev_fd: c_int, pin: u8, trigger: Trigger) -> Result<LineRequestV2> {
+        let mut line_request = LineRequestV2 {
+            offsets: [0u32; LINES_MAX_V2],
+            consumer: [0u8; LABEL_BUFSIZE],
+            config: LineConfigV2::new(trigger),
+            num_lines: 1,
+            event_buffer_size: 0,
+            padding: [0u32; 5],
+            fd: 0,
+        };
+
+        line_request.offsets[0] = u32::from(pin);
+
+        // Set consumer label, so other processes know we're monitoring this event
+        line_request.consumer[0..CONSUMER_LABEL.len()].copy_from_slice(CONSUMER_LABEL.as_bytes());
+
+        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_V2, &mut line_request) })
+            .map_err(|e| Error::with_fd(e, cdev_fd))?;
+
+        Ok(line_request)
+    }
+}
+
+const LINE_FLAG_V2_USED: u64 = 1 << 0;
+const LINE_FLAG_V2_OUTPUT: u64 = 1 << 3;
+
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+struct LineAttributeV2 {
+    id: u32,
+    padding: u32,
+    value: u64,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+pub struct LineInfoV2 {
+    name: [u8; NAME_BUFSIZE],
+    consumer: [u8; LABEL_BUFSIZE],
+    offset: u32,
+    num_attrs: u32,
+    flags: u64,
+    attrs: [LineAttributeV2; LINE_NUM_ATTRS_MAX_V2],
+    padding: [u32; 4],
+}
+
+impl LineInfoV2 {
+    // Starts watching the line for changes to its request status and
+    // configuration, and returns its current info. Change events can be
+    // read from cdev_fd. The watch is removed when cdev_fd is closed.
+    pub fn watch(cdev_fd: c_int, pin: u8) -> Result<LineInfoV2> {
+        let mut line_info = LineInfoV2 {
+            offset: u32::from(pin),
+            ..LineInfoV2::default()
+        };
+
+        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_INFO_WATCH_V2, &mut line_info) })
+            .map_err(|e| Error::with_fd(e, cdev_fd))?;
+
+        Ok(line_info)
+    }
+
+    pub fn is_used(&self) -> bool {
+        (self.flags & LINE_FLAG_V2_USED) > 0
+    }
+
+    pub fn is_output(&self) -> bool {
+        (self.flags & LINE_FLAG_V2_OUTPUT) > 0
+    }
+
+    pub fn consumer(&self) -> String {
+        cbuf_to_cstring(&self.consumer)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+pub const LINE_CHANGED_REQUESTED: u32 = 1;
+pub const LINE_CHANGED_RELEASED: u32 = 2;
+pub const LINE_CHANGED_CONFIG: u32 = 3;
+
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+pub struct LineInfoChangedV2 {
+    pub info: LineInfoV2,
+    pub timestamp_ns: u64,
+    pub event_type: u32,
+    padding: [u32; 5],
+}
+
+impl LineInfoChangedV2 {
+    // Reads a line info change event. This blocks if there are no events
+    // waiting.
+    pub fn read(cdev_fd: c_int) -> Result<LineInfoChangedV2> {
+        read_event(cdev_fd)
+    }
+}
+
+// Character device ABI version used for an event request
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum Abi {
+    V1,
+    V2,
+}
+
+// Line event fd. The v2 ABI is used when the kernel supports it, because v2
+// events include sequence numbers. Otherwise, we fall back to v1.
+#[derive(Debug)]
+pub struct EventRequest {
+    pub fd: c_int,
+    abi: Abi,
+}
+
+impl EventRequest {
+    pub fn new(cdev_fd: c_int, pin: u8, trigger: Trigger) -> Result<EventRequest> {
+        let (fd, abi) = match LineRequestV2::new(cdev_fd, pin, trigger) {
+            Ok(line_request) => (line_request.fd, Abi::V2),
+            // Kernels older than 5.10 reject the unknown v2 ioctl with EINVAL
+            Err(Error::Io(ref e))
+                if e.raw_os_error() == Some(EINVAL) || e.raw_os_error() == Some(ENOTTY) =>
+            {
+                (EventRequestV1::new(cdev_fd, pin, trigger)?.fd, Abi::V1)
+            }
+            Err(e) => return Err(e),
+        };
 
         // If the event fd is zero or negative, an error occurred
-        if event_request.fd <= 0 {
+        if fd <= 0 {
             Err(Error::Io(std::io::Error::last_os_error()))
         } else {
-            Ok(event_request)
+            Ok(EventRequest { fd, abi })
+        }
+    }
+
+    // Changes the edges that trigger events on the existing request, which
+    // keeps the fd and any queued events. Returns false if the kernel only
+    // supports v1, in which case a new request is needed instead.
+    pub fn set_trigger(&self, trigger: Trigger) -> Result<bool> {
+        if self.abi == Abi::V1 {
+            return Ok(false);
+        }
+
+        let mut line_config = LineConfigV2::new(trigger);
+
+        parse_retval!(unsafe { libc::ioctl(self.fd, REQ_SET_LINE_CONFIG_V2, &mut line_config) })
+            .map_err(|e| Error::with_fd(e, self.fd))?;
+
+        Ok(true)
+    }
+
+    // Read interrupt event. This blocks if there are no events waiting. The
+    // current level is read immediately after the event is dequeued.
+    pub fn event(&self) -> Result<Event> {
+        let mut event = match self.abi {
+            Abi::V1 => Event::from_event_data(read_event(self.fd)?),
+            Abi::V2 => Event::from_line_event(read_event(self.fd)?),
+        };
+
+        event.current_level = self.level().ok();
+
+        Ok(event)
+    }
+
+    // Reads the current level of the requested line.
+    pub fn level(&self) -> Result<Level> {
+        match self.abi {
+            Abi::V1 => {
+                let mut handle_data = HandleData::new();
+
+                parse_retval!(unsafe {
+                    libc::ioctl(self.fd, REQ_GET_LINE_VALUES, &mut handle_data)
+                })
+                .map_err(|e| Error::with_fd(e, self.fd))?;
+
+                Ok(Level::from(handle_data.values[0]))
+            }
+            Abi::V2 => {
+                let mut line_values = LineValuesV2 { bits: 0, mask: 1 };
+
+                parse_retval!(unsafe {
+                    libc::ioctl(self.fd, REQ_GET_LINE_VALUES_V2, &mut line_values)
+                })
+                .map_err(|e| Error::with_fd(e, self.fd))?;
+
+                Ok(Level::from((line_values.bits & 1) as u8))
+            }
         }
     }
 
@@ -321,77 +639,145 @@ impl Drop for EventRequest {
     }
 }
 
-impl fmt::Debug for EventRequest {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("EventRequest")
-            .field("line_offset", &self.line_offset)
-            .field("handle_flags", &self.handle_flags)
-            .field("event_flags", &self.event_flags)
-            .field("consumer_label", &cbuf_to_cstring(&self.consumer_label))
-            .field("fd", &self.fd)
-            .finish()
-    }
-}
-
 const EVENT_TYPE_RISING_EDGE: u32 = 0x01;
 const EVENT_TYPE_FALLING_EDGE: u32 = 0x02;
 
+// Reads a single fixed-size event structure from event_fd
+fn read_event<T: Default>(event_fd: c_int) -> Result<T> {
+    let mut event_data = T::default();
+
+    let bytes_read = parse_retval!(unsafe {
+        libc::read(
+            event_fd,
+            &mut event_data as *mut T as *mut c_void,
+            mem::size_of::<T>(),
+        )
+    })?;
+
+    if bytes_read < mem::size_of::<T>() as isize {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "failed to fill whole buffer",
+        )
+        .into())
+    } else {
+        Ok(event_data)
+    }
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 #[repr(C)]
-struct EventData {
+struct EventDataV1 {
     timestamp: u64,
     id: u32,
 }
 
-impl EventData {
-    fn new(event_fd: c_int) -> Result<EventData> {
-        let mut event_data = EventData {
-            timestamp: 0,
-            id: 0,
-        };
-
-        let bytes_read = parse_retval!(unsafe {
-            libc::read(
-                event_fd,
-                &mut event_data as *mut EventData as *mut c_void,
-                mem::size_of::<EventData>(),
-            )
-        })?;
-
-        if bytes_read < mem::size_of::<EventData>() as isize {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::UnexpectedEof,
-                "failed to fill whole buffer",
-            )
-            .into())
-        } else {
-            Ok(event_data)
-        }
-    }
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+struct LineEventV2 {
+    timestamp_ns: u64,
+    id: u32,
+    offset: u32,
+    seqno: u32,
+    line_seqno: u32,
+    padding: [u32; 6],
 }
 
+/// Interrupt trigger event.
+///
+/// `Event`s are returned by the iterator created with [`InputPin::events`].
+///
+/// [`InputPin::events`]: struct.InputPin.html#method.events
 #[derive(Debug, Copy, Clone)]
 pub struct Event {
     trigger: Trigger,
     timestamp: Duration,
+    seqno: Option<u32>,
+    line_seqno: Option<u32>,
+    current_level: Option<Level>,
 }
 
 impl Event {
-    fn from_event_data(event_data: EventData) -> Event {
+    fn trigger_from_id(id: u32) -> Trigger {
+        match id {
+            EVENT_TYPE_RISING_EDGE => Trigger::RisingEdge,
+            EVENT_TYPE_FALLING_EDGE => Trigger::FallingEdge,
+            _ => unreachable!(),
+        }
+    }
+
+    fn from_event_data(event_data: EventDataV1) -> Event {
         Event {
-            trigger: match event_data.id {
-                EVENT_TYPE_RISING_EDGE => Trigger::RisingEdge,
-                EVENT_TYPE_FALLING_EDGE => Trigger::FallingEdge,
-                _ => unreachable!(),
-            },
+            trigger: Event::trigger_from_id(event_data.id),
             timestamp: Duration::from_nanos(event_data.timestamp),
+            seqno: None,
+            line_seqno: None,
+            current_level: None,
+        }
+    }
+
+    fn from_line_event(line_event: LineEventV2) -> Event {
+        Event {
+            trigger: Event::trigger_from_id(line_event.id),
+            timestamp: Duration::from_nanos(line_event.timestamp_ns),
+            seqno: Some(line_event.seqno),
+            line_seqno: Some(line_event.line_seqno),
+            current_level: None,
         }
     }
 
+    /// Returns the edge that triggered the event, either [`RisingEdge`] or [`FallingEdge`].
+    ///
+    /// [`RisingEdge`]: enum.Trigger.html#variant.RisingEdge
+    /// [`FallingEdge`]: enum.Trigger.html#variant.FallingEdge
     pub fn trigger(&self) -> Trigger {
         self.trigger
     }
 
+    /// Returns the time at which the event was registered by the kernel.
+    ///
+    /// On kernel 5.7 and later, the timestamp is based on `CLOCK_MONOTONIC`.
+    /// Older kernels use `CLOCK_REALTIME`.
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    /// Returns the sequence number of the event across all lines in the
+    /// kernel's line request.
+    ///
+    /// Each pin's interrupt trigger uses its own line request, so `seqno`
+    /// matches [`line_seqno`]. Sequence numbers start at `1`, and any gaps
+    /// indicate the kernel dropped events because they weren't read quickly
+    /// enough.
+    ///
+    /// Sequence numbers are only available on kernel 5.10 and later, which
+    /// support the v2 character device ABI. On older kernels, `seqno` returns
+    /// `None`.
+    ///
+    /// [`line_seqno`]: #method.line_seqno
+    pub fn seqno(&self) -> Option<u32> {
+        self.seqno
+    }
+
+    /// Returns the sequence number of the event for this pin.
+    ///
+    /// Sequence numbers start at `1`, and any gaps indicate the kernel
+    /// dropped events because they weren't read quickly enough.
+    ///
+    /// Sequence numbers are only available on kernel 5.10 and later, which
+    /// support the v2 character device ABI. On older kernels, `line_seqno`
+    /// returns `None`.
+    pub fn line_seqno(&self) -> Option<u32> {
+        self.line_seqno
+    }
+
+    /// Returns the pin's logic level after the edge occurred.
+    ///
+    /// The level is derived from the edge that triggered the event, and
+    /// reflects the state of the pin at the time of the event. Use
+    /// [`current_level`] to find out whether the pin has changed since.
+    ///
+    /// [`current_level`]: #method.current_level
     pub fn level(&self) -> Level {
         match self.trigger {
             Trigger::RisingEdge => Level::High,
@@ -402,32 +788,46 @@ impl Event {
             }
         }
     }
-}
 
-// Read interrupt event
-pub fn get_event(event_fd: c_int) -> Result<Event> {
-    let event_data = EventData::new(event_fd)?;
-    Ok(Event::from_event_data(event_data))
+    /// Returns the pin's logic level as read immediately after the event was
+    /// retrieved from the kernel.
+    ///
+    /// The kernel doesn't report the line level as part of an event, so
+    /// the level is read with a separate `ioctl` call on the same line
+    /// request right after the event is dequeued. This saves a separate call
+    /// to [`InputPin::read`], and keeps the delay between the event and the
+    /// level read as short as possible. However, the level isn't captured
+    /// atomically with the event. If the pin changes again in between, the
+    /// current level reflects the later change, and the corresponding event
+    /// is waiting in the queue. If more events are queued, the current level
+    /// may be several transitions ahead of this event.
+    ///
+    /// Comparing `current_level` to [`level`] shows whether the pin has
+    /// changed since the event occurred.
+    ///
+    /// Returns `None` if the level couldn't be read.
+    ///
+    /// [`InputPin::read`]: struct.InputPin.html#method.read
+    /// [`level`]: #method.level
+    pub fn current_level(&self) -> Option<Level> {
+        self.current_level
+    }
 }
 
 // Find the correct gpiochip device based on its label
 pub fn find_gpiochip() -> Result<File> {
     for id in 0..=255 {
-        let gpiochip = match OpenOptions::new()
+        let path = format!("{}{}", PATH_GPIOCHIP, id);
+        let gpiochip = OpenOptions::new()
             .read(true)
             .write(true)
-            .open(format!("{}{}", PATH_GPIOCHIP, id))
-        {
-            Ok(file) => file,
-            Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => {
-                return Err(Error::PermissionDenied(format!("{}{}", PATH_GPIOCHIP, id)));
-            }
-            Err(e) => return Err(Error::from(e)),
-        };
+            .open(&path)
+            .map_err(|e| Error::with_path(e, &path))?;
 
         let chip_info = ChipInfo::new(gpiochip.as_raw_fd())?;
         if chip_info.label[0..DRIVER_NAME.len()] == DRIVER_NAME[..]
             || chip_info.label[0..DRIVER_NAME_CM4.len()] == DRIVER_NAME_CM4[..]
+            || chip_info.label[0..DRIVER_NAME_RP1.len()] == DRIVER_NAME_RP1[..]
         {
             return Ok(gpiochip);
         }
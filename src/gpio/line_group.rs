@@ -0,0 +1,119 @@
+use super::OutputPin;
+
+/// A group of output pins that can be changed with a minimal number of register writes.
+///
+/// `LineGroup` takes ownership of a set of [`OutputPin`]s, and changes their
+/// output states by writing directly to the GPIO set and clear registers.
+/// Multiple pins within the same register bank change state simultaneously,
+/// which prevents the intermediate states (glitches) that occur when pins
+/// are changed one at a time, for instance when driving a parallel data bus.
+///
+/// ## Ordering guarantees
+///
+/// [`write_atomic`] first writes to the set registers, and then to the clear
+/// registers, so any pins that change to [`High`] do so before any pins that
+/// change to [`Low`]. Registers for banks that don't contain any affected pins
+/// aren't written to. No register is read, so pins outside the group aren't
+/// affected, even if they're changed concurrently by other threads or processes.
+///
+/// On the BCM283x/BCM2711, GPIO 0-31 and GPIO 32-53 are controlled through
+/// separate registers. On the Raspberry Pi 5, the RP1 splits its pins into
+/// banks containing GPIO 0-27, GPIO 28-33 and GPIO 34-53. All pins on the
+/// GPIO header belong to the first bank. A group that spans multiple banks
+/// requires a separate write for each bank. All set registers are written
+/// before any clear registers, each in ascending bank order, so pins in
+/// different banks don't change state simultaneously.
+///
+/// Output states changed through a `LineGroup` aren't synchronized with any
+/// software-based PWM configured on the individual pins.
+///
+/// These guarantees only apply when the GPIO registers are memory-mapped. If
+/// [`Gpio::backend`] returns [`Backend::CharacterDevice`], each pin is
+/// changed through a separate `gpiochip` line request, so pins change state
+/// one at a time, in ascending BCM GPIO order, with all set pins before any
+/// cleared pins.
+///
+/// [`OutputPin`]: struct.OutputPin.html
+/// [`write_atomic`]: #method.write_atomic
+/// [`Gpio::backend`]: struct.Gpio.html#method.backend
+/// [`Backend::CharacterDevice`]: enum.Backend.html#variant.CharacterDevice
+/// [`High`]: enum.Level.html#variant.High
+/// [`Low`]: enum.Level.html#variant.Low
+#[derive(Debug)]
+pub struct LineGroup {
+    pins: Vec<OutputPin>,
+    // Set and clear register bit for each pin in pins, based on its BCM GPIO number.
+    bits: Vec<u64>,
+}
+
+impl LineGroup {
+    /// Constructs a new `LineGroup` containing the specified pins.
+    ///
+    /// The order of `pins` determines which bit in the `value` and `mask`
+    /// arguments of [`write_atomic`] corresponds to each pin. Bit 0 maps to
+    /// the first pin. Only the first 64 pins can be addressed.
+    ///
+    /// [`write_atomic`]: #method.write_atomic
+    pub fn new(pins: Vec<OutputPin>) -> LineGroup {
+        let bits = pins.iter().map(|pin| 1u64 << pin.pin()).collect();
+
+        LineGroup { pins, bits }
+    }
+
+    /// Returns the pins in the group.
+    pub fn pins(&self) -> &[OutputPin] {
+        &self.pins
+    }
+
+    /// Consumes the `LineGroup` and returns the pins it contains.
+    pub fn into_pins(self) -> Vec<OutputPin> {
+        self.pins
+    }
+
+    /// Returns the number of pins in the group.
+    pub fn len(&self) -> usize {
+        self.pins.len()
+    }
+
+    /// Returns `true` if the group doesn't contain any pins.
+    pub fn is_empty(&self) -> bool {
+        self.pins.is_empty()
+    }
+
+    /// Changes the output states of the pins selected by `mask` to the
+    /// corresponding bits in `value`.
+    ///
+    /// Bit n of `mask` and `value` corresponds to the pin at index n in the
+    /// group. Pins with their `mask` bit set are changed to [`High`] if their
+    /// `value` bit is set, or to [`Low`] otherwise. Pins with their `mask`
+    /// bit cleared are left untouched.
+    ///
+    /// The new output states are applied with a single write to the set
+    /// register and a single write to the clear register for each affected
+    /// bank. More details can be found in the [ordering guarantees], which
+    /// don't apply to the `gpiochip` fallback backend.
+    ///
+    /// [`High`]: enum.Level.html#variant.High
+    /// [`Low`]: enum.Level.html#variant.Low
+    /// [ordering guarantees]: #ordering-guarantees
+    pub fn write_atomic(&mut self, value: u64, mask: u64) {
+        let mut set = 0;
+        let mut clear = 0;
+
+        for (idx, &bit) in self.bits.iter().take(64).enumerate() {
+            if (mask >> idx) & 0b1 == 0 {
+                continue;
+            }
+
+            if (value >> idx) & 0b1 == 1 {
+                set |= bit;
+            } else {
+                clear |= bit;
+            }
+        }
+
+        if let Some(pin) = self.pins.first() {
+            pin.gpio_state().gpio_mem.set_clear(set, clear);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+use std::fs::File;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+use libc::c_int;
+
+use crate::gpio::ioctl::{self, LineInfoChangedV2, LineInfoV2};
+use crate::gpio::{Error, Result};
+
+/// The type of change reported by [`LineWatch::poll`].
+///
+/// [`LineWatch::poll`]: struct.LineWatch.html#method.poll
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum LineChangeKind {
+    /// The line was requested by a consumer.
+    Requested,
+    /// The line was released by its consumer.
+    Released,
+    /// The configuration of the requested line was changed, for instance its
+    /// direction or bias.
+    Reconfigured,
+}
+
+impl fmt::Display for LineChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            LineChangeKind::Requested => write!(f, "Requested"),
+            LineChangeKind::Released => write!(f, "Released"),
+            LineChangeKind::Reconfigured => write!(f, "Reconfigured"),
+        }
+    }
+}
+
+/// A change to a line's request status or configuration, reported by
+/// [`LineWatch::poll`].
+///
+/// [`LineWatch::poll`]: struct.LineWatch.html#method.poll
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LineChange {
+    kind: LineChangeKind,
+    timestamp: Duration,
+    used: bool,
+    consumer: String,
+    output: bool,
+}
+
+impl LineChange {
+    /// Returns the type of change.
+    pub fn kind(&self) -> LineChangeKind {
+        self.kind
+    }
+
+    /// Returns the time at which the change occurred, based on
+    /// `CLOCK_MONOTONIC`.
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    /// Returns `true` if the line is in use after the change.
+    pub fn is_used(&self) -> bool {
+        self.used
+    }
+
+    /// Returns the label of the consumer that uses the line after the
+    /// change, or an empty string if the line isn't in use.
+    pub fn consumer(&self) -> &str {
+        &self.consumer
+    }
+
+    /// Returns `true` if the line is configured as an output after the
+    /// change.
+    pub fn is_output(&self) -> bool {
+        self.output
+    }
+}
+
+/// Monitors a GPIO line for changes to its request status and configuration,
+/// without requesting the line.
+///
+/// `LineWatch`es are constructed by calling [`Gpio::watch_line`].
+///
+/// A `LineWatch` reports when another process or kernel driver requests or
+/// releases the line, or changes its configuration. It doesn't report
+/// changes to the line's logic level. Because the line isn't requested, a
+/// `LineWatch` never prevents other consumers from requesting it.
+///
+/// Watching a line requires Linux kernel 5.10 or later. The watch is removed
+/// when the `LineWatch` goes out of scope.
+///
+/// [`Gpio::watch_line`]: struct.Gpio.html#method.watch_line
+#[derive(Debug)]
+pub struct LineWatch {
+    cdev: File,
+    pin: u8,
+    used: bool,
+    consumer: String,
+    output: bool,
+}
+
+impl LineWatch {
+    pub(crate) fn new(pin: u8) -> Result<LineWatch> {
+        // Watches are tied to a file descriptor, and change events are read
+        // from that same file descriptor, so each LineWatch gets its own
+        let cdev = ioctl::find_gpiochip()?;
+        let info = LineInfoV2::watch(cdev.as_raw_fd(), pin)?;
+
+        Ok(LineWatch {
+            cdev,
+            pin,
+            used: info.is_used(),
+            consumer: info.consumer(),
+            output: info.is_output(),
+        })
+    }
+
+    /// Returns the GPIO pin number.
+    pub fn pin(&self) -> u8 {
+        self.pin
+    }
+
+    /// Returns `true` if the line was in use at the time of the most recent
+    /// change, or when the watch was started if no changes have been
+    /// reported yet.
+    pub fn is_used(&self) -> bool {
+        self.used
+    }
+
+    /// Returns the label of the consumer that used the line at the time of
+    /// the most recent change, or an empty string if the line wasn't in use.
+    pub fn consumer(&self) -> &str {
+        &self.consumer
+    }
+
+    /// Returns `true` if the line was configured as an output at the time of
+    /// the most recent change.
+    pub fn is_output(&self) -> bool {
+        self.output
+    }
+
+    /// Blocks until the line's request status or configuration changes, or
+    /// a timeout occurs.
+    ///
+    /// Changes that occurred since the previous call are queued by the
+    /// kernel, and returned first. `timeout` can be set to `None` to wait
+    /// indefinitely. Returns `Ok(None)` if the timeout elapsed.
+    pub fn poll(&mut self, timeout: Option<Duration>) -> Result<Option<LineChange>> {
+        let mut pfd = libc::pollfd {
+            fd: self.cdev.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        let timeout_ms = timeout.map_or(-1, |timeout| {
+            timeout.as_millis().min(c_int::MAX as u128) as c_int
+        });
+
+        let ready = retry_interrupted!(parse_retval!(unsafe {
+            libc::poll(&mut pfd, 1, timeout_ms)
+        }))?;
+
+        if ready == 0 || pfd.revents & libc::POLLIN == 0 {
+            return Ok(None);
+        }
+
+        let event = LineInfoChangedV2::read(self.cdev.as_raw_fd())?;
+
+        let kind = match event.event_type {
+            ioctl::LINE_CHANGED_REQUESTED => LineChangeKind::Requested,
+            ioctl::LINE_CHANGED_RELEASED => LineChangeKind::Released,
+            ioctl::LINE_CHANGED_CONFIG => LineChangeKind::Reconfigured,
+            event_type => {
+                return Err(Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("unknown line change type {}", event_type),
+                )))
+            }
+        };
+
+        self.used = event.info.is_used();
+        self.consumer = event.info.consumer();
+        self.output = event.info.is_output();
+
+        Ok(Some(LineChange {
+            kind,
+            timestamp: Duration::from_nanos(event.timestamp_ns),
+            used: self.used,
+            consumer: self.consumer.clone(),
+            output: self.output,
+        }))
+    }
+}
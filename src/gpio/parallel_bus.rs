@@ -0,0 +1,232 @@
+use std::time::{Duration, Instant};
+
+use super::pin::wait_until;
+use super::{Level, LineGroup, OutputPin};
+
+// Default timing, based on the HD44780 datasheet's worst-case values at
+// 2.7 V with generous margins, since the delays are measured in userspace.
+const DEFAULT_SETUP: Duration = Duration::from_micros(1);
+const DEFAULT_ENABLE_PULSE: Duration = Duration::from_micros(1);
+const DEFAULT_HOLD: Duration = Duration::from_micros(1);
+// Most HD44780 instructions take 37 µs at 270 kHz. Clear Display and Return
+// Home take longer, and require an additional delay.
+const DEFAULT_EXECUTION: Duration = Duration::from_micros(50);
+
+/// Timing parameters for a [`ParallelBus`].
+///
+/// The minimum values required by a controller are listed in its datasheet,
+/// usually in a bus timing diagram. Delays are measured using a combination
+/// of sleeping and busy-waiting, and can only be extended, not shortened, by
+/// scheduling. Values below approximately 1 µs are rounded up by the
+/// overhead of a register write.
+///
+/// The default values are suitable for HD44780-compatible LCD controllers.
+///
+/// [`ParallelBus`]: struct.ParallelBus.html
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ParallelTiming {
+    /// The minimum time between presenting RS, R/W and the data lines, and
+    /// the rising edge of E (address and data setup time).
+    ///
+    /// Defaults to 1 µs.
+    pub setup: Duration,
+    /// The minimum time E is kept high (enable pulse width).
+    ///
+    /// Defaults to 1 µs.
+    pub enable_pulse: Duration,
+    /// The minimum time the data lines are kept stable after the falling
+    /// edge of E (data hold time).
+    ///
+    /// Defaults to 1 µs.
+    pub hold: Duration,
+    /// The time the controller needs to process a command or data byte,
+    /// before it accepts the next one.
+    ///
+    /// Defaults to 50 µs.
+    pub execution: Duration,
+}
+
+impl ParallelTiming {
+    /// Constructs a new `ParallelTiming`.
+    pub fn new(
+        setup: Duration,
+        enable_pulse: Duration,
+        hold: Duration,
+        execution: Duration,
+    ) -> ParallelTiming {
+        ParallelTiming {
+            setup,
+            enable_pulse,
+            hold,
+            execution,
+        }
+    }
+}
+
+impl Default for ParallelTiming {
+    fn default() -> ParallelTiming {
+        ParallelTiming {
+            setup: DEFAULT_SETUP,
+            enable_pulse: DEFAULT_ENABLE_PULSE,
+            hold: DEFAULT_HOLD,
+            execution: DEFAULT_EXECUTION,
+        }
+    }
+}
+
+/// Writes bytes to a 6800-style parallel bus, as used by HD44780-compatible
+/// character LCDs and many other display controllers.
+///
+/// `ParallelBus` presents each byte on the data lines through a
+/// [`LineGroup`], so all data lines change state simultaneously, selects
+/// either the command or data register through RS, and then latches the byte
+/// by strobing E high and low again. The [`ParallelTiming`] parameters
+/// control the delays between each of those steps.
+///
+/// Bit n of each byte is written to the pin at index n in the `LineGroup`,
+/// so the data lines should be ordered from D0 through D7. A 4-bit bus, such
+/// as an HD44780 in 4-bit mode, uses a `LineGroup` containing D4 through D7.
+/// Each byte then needs to be written as two separate nibbles, high nibble
+/// first.
+///
+/// R/W is optional. Many displays have R/W tied to ground, because reading
+/// from the display requires level shifting when it's powered from 5 V. If
+/// an R/W pin is provided, it's kept low, because `ParallelBus` only
+/// supports writes. For the same reason, the controller's busy flag can't be
+/// polled. Instead, `ParallelBus` waits for the configured execution time
+/// after each byte.
+///
+/// ```no_run
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// use rppal::gpio::{Gpio, LineGroup, ParallelBus};
+///
+/// # fn main() -> rppal::gpio::Result<()> {
+/// let gpio = Gpio::new()?;
+///
+/// // D0 through D7
+/// let mut data = Vec::new();
+/// for pin in [5, 6, 12, 13, 16, 19, 20, 21] {
+///     data.push(gpio.get(pin)?.into_output_low());
+/// }
+///
+/// let mut lcd = ParallelBus::new(
+///     LineGroup::new(data),
+///     gpio.get(25)?.into_output_low(),
+///     None,
+///     gpio.get(24)?.into_output_low(),
+/// );
+///
+/// // Function Set: 8-bit bus, 2 lines, 5x8 font
+/// lcd.write_command(0x38);
+/// // Display On, cursor off
+/// lcd.write_command(0x0c);
+/// // Clear Display, which takes up to 1.52 ms
+/// lcd.write_command(0x01);
+/// thread::sleep(Duration::from_millis(2));
+///
+/// for byte in b"Hello, world!" {
+///     lcd.write_data(*byte);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`LineGroup`]: struct.LineGroup.html
+/// [`ParallelTiming`]: struct.ParallelTiming.html
+#[derive(Debug)]
+pub struct ParallelBus {
+    data: LineGroup,
+    rs: OutputPin,
+    rw: Option<OutputPin>,
+    enable: OutputPin,
+    timing: ParallelTiming,
+    // Earliest time the next byte can be presented on the bus
+    ready_at: Instant,
+}
+
+impl ParallelBus {
+    /// Constructs a new `ParallelBus` using the specified data lines, and
+    /// RS, R/W and E control pins.
+    ///
+    /// E and R/W are set low. The default [`ParallelTiming`] is used until
+    /// it's changed with [`set_timing`].
+    ///
+    /// [`ParallelTiming`]: struct.ParallelTiming.html
+    /// [`set_timing`]: #method.set_timing
+    pub fn new(
+        data: LineGroup,
+        rs: OutputPin,
+        rw: Option<OutputPin>,
+        mut enable: OutputPin,
+    ) -> ParallelBus {
+        enable.set_low();
+
+        let rw = rw.map(|mut rw| {
+            rw.set_low();
+            rw
+        });
+
+        ParallelBus {
+            data,
+            rs,
+            rw,
+            enable,
+            timing: ParallelTiming::default(),
+            ready_at: Instant::now(),
+        }
+    }
+
+    /// Returns the timing parameters.
+    pub fn timing(&self) -> ParallelTiming {
+        self.timing
+    }
+
+    /// Sets the timing parameters.
+    pub fn set_timing(&mut self, timing: ParallelTiming) {
+        self.timing = timing;
+    }
+
+    /// Writes a byte to the command (instruction) register, with RS set low.
+    ///
+    /// `write_command` returns as soon as the byte is latched. The hold and
+    /// execution times are awaited before the next byte is presented on the
+    /// bus, so other work can be done in the meantime. Commands that take
+    /// longer than the configured execution time require an additional delay.
+    pub fn write_command(&mut self, value: u8) {
+        self.strobe(Level::Low, value);
+    }
+
+    /// Writes a byte to the data register, with RS set high.
+    ///
+    /// `write_data` returns as soon as the byte is latched. The hold and
+    /// execution times are awaited before the next byte is presented on the
+    /// bus, so other work can be done in the meantime.
+    pub fn write_data(&mut self, value: u8) {
+        self.strobe(Level::High, value);
+    }
+
+    /// Consumes the `ParallelBus`, and returns the data lines, and RS, R/W and
+    /// E control pins.
+    pub fn into_parts(self) -> (LineGroup, OutputPin, Option<OutputPin>, OutputPin) {
+        (self.data, self.rs, self.rw, self.enable)
+    }
+
+    // Presents rs and value on the bus, and latches them with a pulse on E.
+    fn strobe(&mut self, rs: Level, value: u8) {
+        // The previous byte may still be processing
+        wait_until(self.ready_at, None);
+
+        self.rs.write(rs);
+        self.data.write_atomic(u64::from(value), 0xff);
+        wait_until(Instant::now() + self.timing.setup, None);
+
+        self.enable.set_high();
+        wait_until(Instant::now() + self.timing.enable_pulse, None);
+        self.enable.set_low();
+
+        let latched = Instant::now();
+        self.ready_at = latched + self.timing.hold.max(self.timing.execution);
+    }
+}
@@ -1,12 +1,58 @@
+use std::hint;
+use std::io;
+use std::iter;
 use std::os::unix::io::AsRawFd;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use super::soft_pwm::SoftPwm;
-use crate::gpio::{interrupt::AsyncInterrupt, GpioState, Level, Mode, PullUpDown, Result, Trigger};
-
+use super::stable::StableWatch;
+use crate::gpio::{
+    interrupt::AsyncInterrupt, ioctl, Backend, Error, Event, GlitchFilter, GpioState, Level, Mode,
+    PullUpDown, RequestMode, Result, Trigger, WhichPin,
+};
+use crate::units::{DutyCycle, Frequency};
+
+#[cfg(feature = "hal-0")]
 const NANOS_PER_SEC: f64 = 1_000_000_000.0;
+// Only sleep during a pulse if we have enough time remaining
+const PULSE_SLEEP_THRESHOLD: Duration = Duration::from_micros(250);
+// Reserve some time for busy waiting after sleeping
+const PULSE_BUSYWAIT_MAX: Duration = Duration::from_micros(200);
+// Maximum time between cancellation checks while playing a pattern
+const PATTERN_CANCEL_INTERVAL: Duration = Duration::from_millis(10);
+
+// Waits until deadline by sleeping for most of the remaining time, and
+// busy-waiting for the rest. Returns false if cancel was set before the
+// deadline was reached.
+pub(crate) fn wait_until(deadline: Instant, cancel: Option<&AtomicBool>) -> bool {
+    loop {
+        if let Some(cancel) = cancel {
+            if cancel.load(Ordering::SeqCst) {
+                return false;
+            }
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining < PULSE_SLEEP_THRESHOLD {
+            break;
+        }
+
+        let sleep = remaining - PULSE_BUSYWAIT_MAX;
+        thread::sleep(match cancel {
+            Some(_) => sleep.min(PATTERN_CANCEL_INTERVAL),
+            None => sleep,
+        });
+    }
+
+    while Instant::now() < deadline {
+        hint::spin_loop();
+    }
+
+    true
+}
 
 macro_rules! impl_pin {
     () => {
@@ -25,6 +71,14 @@ macro_rules! impl_input {
         /// Reads the pin's logic level.
         #[inline]
         pub fn read(&self) -> Level {
+            // The character device backend can't request a line that's held by
+            // an interrupt, so its level is read through the event request
+            if self.pin.gpio_state.backend == Backend::CharacterDevice {
+                if let Some(level) = self.interrupt_level() {
+                    return level;
+                }
+            }
+
             self.pin.read()
         }
 
@@ -33,7 +87,7 @@ macro_rules! impl_input {
         /// [`Low`]: enum.Level.html#variant.Low
         #[inline]
         pub fn is_low(&self) -> bool {
-            self.pin.read() == Level::Low
+            self.read() == Level::Low
         }
 
         /// Reads the pin's logic level, and returns `true` if it's set to [`High`].
@@ -41,7 +95,7 @@ macro_rules! impl_input {
         /// [`High`]: enum.Level.html#variant.High
         #[inline]
         pub fn is_high(&self) -> bool {
-            self.pin.read() == Level::High
+            self.read() == Level::High
         }
     };
 }
@@ -112,7 +166,7 @@ macro_rules! impl_output {
             }
 
             // Store frequency/duty cycle for the embedded-hal PwmPin implementation.
-            #[cfg(feature = "hal")]
+            #[cfg(feature = "hal-0")]
             {
                 let period_s =
                     period.as_secs() as f64 + (f64::from(period.subsec_nanos()) / NANOS_PER_SEC);
@@ -136,18 +190,31 @@ macro_rules! impl_output {
         /// `set_pwm_frequency` is a convenience method that converts `frequency` to a period and
         /// `duty_cycle` to a pulse width, and then calls [`set_pwm`].
         ///
-        /// `frequency` is specified in hertz (Hz).
+        /// `frequency` is specified in hertz (Hz), either as a [`Frequency`] or as an `f64`.
+        /// If `frequency` is NaN, infinite, or not greater than `0.0`,
+        /// `Err(`[`Error::InvalidFrequency`]`)` is returned.
         ///
-        /// `duty_cycle` is specified as a floating point value between `0.0` (0%) and `1.0` (100%).
+        /// `duty_cycle` is specified as a [`DutyCycle`], or as an `f64` between `0.0` (0%)
+        /// and `1.0` (100%). If `duty_cycle` is NaN or out of range,
+        /// `Err(`[`Error::InvalidDutyCycle`]`)` is returned.
         ///
         /// [`set_pwm`]: #method.set_pwm
-        pub fn set_pwm_frequency(&mut self, frequency: f64, duty_cycle: f64) -> Result<()> {
-            let period = if frequency <= 0.0 {
-                0.0
-            } else {
-                (1.0 / frequency) * NANOS_PER_SEC
-            };
-            let pulse_width = period * duty_cycle.max(0.0).min(1.0);
+        /// [`Frequency`]: ../units/struct.Frequency.html
+        /// [`DutyCycle`]: ../units/struct.DutyCycle.html
+        /// [`Error::InvalidFrequency`]: enum.Error.html#variant.InvalidFrequency
+        /// [`Error::InvalidDutyCycle`]: enum.Error.html#variant.InvalidDutyCycle
+        pub fn set_pwm_frequency<F, D>(&mut self, frequency: F, duty_cycle: D) -> Result<()>
+        where
+            F: TryInto<Frequency>,
+            F::Error: Into<Error>,
+            D: TryInto<DutyCycle>,
+            D::Error: Into<Error>,
+        {
+            let frequency = frequency.try_into().map_err(Into::into)?;
+            let duty_cycle = duty_cycle.try_into().map_err(Into::into)?;
+
+            let period = frequency.period_nanos();
+            let pulse_width = period * duty_cycle.value();
 
             self.set_pwm(
                 Duration::from_nanos(period as u64),
@@ -169,6 +236,419 @@ macro_rules! impl_output {
     };
 }
 
+macro_rules! impl_interrupt {
+    () => {
+        /// Configures a synchronous interrupt trigger.
+        ///
+        /// After configuring a synchronous interrupt trigger, call [`poll_interrupt`] or
+        /// [`Gpio::poll_interrupts`] to block while waiting for a trigger event.
+        /// [`Gpio::poll_interrupts`] only accepts [`InputPin`]s.
+        ///
+        /// Any previously configured (a)synchronous interrupt triggers will be cleared.
+        ///
+        /// Interrupt triggers can only be configured while the pin's mode is set to
+        /// [`Input`]. Otherwise, [`Error::PinNotInput`] is returned.
+        ///
+        /// [`poll_interrupt`]: #method.poll_interrupt
+        /// [`Gpio::poll_interrupts`]: struct.Gpio.html#method.poll_interrupts
+        /// [`InputPin`]: struct.InputPin.html
+        /// [`Input`]: enum.Mode.html#variant.Input
+        /// [`Error::PinNotInput`]: enum.Error.html#variant.PinNotInput
+        pub fn set_interrupt(&mut self, trigger: Trigger) -> Result<()> {
+            self.check_interrupt_mode()?;
+            self.clear_async_interrupt()?;
+
+            log_debug!(
+                "Pin {} synchronous interrupt set to {}",
+                self.pin(),
+                trigger
+            );
+
+            // The character device backend holds its own line request
+            self.pin.gpio_state.gpio_mem.release(self.pin());
+
+            // Each pin can only be configured for a single trigger type
+            (*self.pin.gpio_state.sync_interrupts.lock().unwrap())
+                .set_interrupt(self.pin(), trigger)
+        }
+
+        /// Removes a previously configured synchronous interrupt trigger.
+        pub fn clear_interrupt(&mut self) -> Result<()> {
+            log_debug!("Pin {} synchronous interrupt cleared", self.pin());
+
+            (*self.pin.gpio_state.sync_interrupts.lock().unwrap()).clear_interrupt(self.pin())
+        }
+
+        /// Changes the trigger of a previously configured (a)synchronous interrupt.
+        ///
+        /// Unlike [`set_interrupt`] and [`set_async_interrupt`], `set_trigger` doesn't
+        /// request a new line event from the kernel when the kernel supports the v2
+        /// character device ABI (5.10 or later). The edges that trigger an interrupt are
+        /// updated in place, so events that are still queued, or that occur after the new
+        /// trigger takes effect, aren't lost. An active asynchronous interrupt keeps
+        /// running on the same thread, and calls its callback for the new trigger.
+        ///
+        /// On older kernels, the line event is requested again, which means any queued
+        /// events and any edges that occur during the reconfiguration are lost. If an
+        /// asynchronous interrupt is active, its thread is restarted with the same callback.
+        ///
+        /// `set_trigger` has no effect if no interrupt trigger is configured.
+        ///
+        /// [`set_interrupt`]: #method.set_interrupt
+        /// [`set_async_interrupt`]: #method.set_async_interrupt
+        pub fn set_trigger(&mut self, trigger: Trigger) -> Result<()> {
+            if let Some(ref mut interrupt) = self.async_interrupt {
+                log_debug!(
+                    "Pin {} asynchronous interrupt changed to {}",
+                    self.pin.pin,
+                    trigger
+                );
+
+                return interrupt.set_trigger(trigger);
+            }
+
+            let mut event_loop = self.pin.gpio_state.sync_interrupts.lock().unwrap();
+
+            if event_loop.has_interrupt(self.pin()) {
+                log_debug!(
+                    "Pin {} synchronous interrupt changed to {}",
+                    self.pin(),
+                    trigger
+                );
+
+                event_loop.set_trigger(self.pin(), trigger)?;
+            }
+
+            Ok(())
+        }
+
+        /// Blocks until an interrupt is triggered on the pin, or a timeout occurs.
+        ///
+        /// This only works after the pin has been configured for synchronous interrupts using
+        /// [`set_interrupt`]. Asynchronous interrupt triggers are automatically polled on a separate thread.
+        ///
+        /// Calling `poll_interrupt` blocks any other calls to `poll_interrupt` (including on other `InputPin`s) or
+        /// [`Gpio::poll_interrupts`] until it returns. If you need to poll multiple pins simultaneously, use
+        /// [`Gpio::poll_interrupts`] to block while waiting for any of the interrupts to trigger, or switch to
+        /// using asynchronous interrupts with [`set_async_interrupt`].
+        ///
+        /// Setting `reset` to `false` returns any cached interrupt trigger events if available. Setting `reset` to `true`
+        /// clears all cached events before polling for new events.
+        ///
+        /// The `timeout` duration indicates how long the call will block while waiting
+        /// for interrupt trigger events, after which an `Ok(None))` is returned.
+        /// `timeout` can be set to `None` to wait indefinitely.
+        ///
+        /// [`set_interrupt`]: #method.set_interrupt
+        /// [`Gpio::poll_interrupts`]: struct.Gpio.html#method.poll_interrupts
+        /// [`set_async_interrupt`]: #method.set_async_interrupt
+        pub fn poll_interrupt(
+            &mut self,
+            reset: bool,
+            timeout: Option<Duration>,
+        ) -> Result<Option<Level>> {
+            let opt = (*self.pin.gpio_state.sync_interrupts.lock().unwrap()).poll_pins(
+                &[self.pin()],
+                reset,
+                timeout,
+            )?;
+
+            if let Some(trigger) = opt {
+                Ok(Some(trigger.1.level()))
+            } else {
+                Ok(None)
+            }
+        }
+
+        /// Blocks until an interrupt is triggered on the pin, or a timeout occurs, and returns
+        /// the complete [`Event`].
+        ///
+        /// `poll_interrupt_event` works the same as [`poll_interrupt`], but returns the
+        /// [`Event`] instead of only the logic level. In addition to the edge and the
+        /// timestamp assigned by the kernel, the event contains the pin's
+        /// [current level], which is read immediately after the event is retrieved. With
+        /// [`Trigger::Both`], this shows whether the pin has changed again since the
+        /// event, without a separate call to [`read`].
+        ///
+        /// [`Event`]: struct.Event.html
+        /// [`poll_interrupt`]: #method.poll_interrupt
+        /// [current level]: struct.Event.html#method.current_level
+        /// [`Trigger::Both`]: enum.Trigger.html#variant.Both
+        /// [`read`]: #method.read
+        pub fn poll_interrupt_event(
+            &mut self,
+            reset: bool,
+            timeout: Option<Duration>,
+        ) -> Result<Option<Event>> {
+            let opt = (*self.pin.gpio_state.sync_interrupts.lock().unwrap()).poll_pins(
+                &[self.pin()],
+                reset,
+                timeout,
+            )?;
+
+            Ok(opt.map(|(_, event)| event))
+        }
+
+        /// Returns a future that resolves to the next interrupt trigger event for the pin.
+        ///
+        /// This only works after the pin has been configured for synchronous interrupts using
+        /// [`set_interrupt`]. Any cached events are returned first, the same as calling
+        /// [`poll_interrupt_event`] with `reset` set to `false`. If no synchronous interrupt
+        /// trigger is configured, the future resolves to an error with kind [`InvalidInput`].
+        ///
+        /// The future doesn't depend on a specific async runtime. While no event is available,
+        /// the pin's event file descriptor is registered with a background reactor thread,
+        /// which wakes the task once an event arrives. The reactor thread is shared by all
+        /// futures returned by RPPAL, started the first time a future has to wait, and keeps
+        /// running until the process exits.
+        ///
+        /// Dropping the future before it resolves cancels the wait, which makes it possible
+        /// to implement timeouts with any executor's timer or `select` combinator. Events
+        /// that arrive after the future is dropped are cached for the next poll.
+        ///
+        /// [`set_interrupt`]: #method.set_interrupt
+        /// [`poll_interrupt_event`]: #method.poll_interrupt_event
+        /// [`InvalidInput`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidInput
+        #[cfg(feature = "async")]
+        pub fn interrupt_async(&mut self) -> impl std::future::Future<Output = Result<Event>> + '_ {
+            crate::reactor::FdFuture::new(move || {
+                let mut event_loop = self.pin.gpio_state.sync_interrupts.lock().unwrap();
+
+                let fd = event_loop.interrupt_fd(self.pin()).ok_or_else(|| {
+                    Error::Io(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "no synchronous interrupt trigger configured",
+                    ))
+                })?;
+
+                match event_loop.poll_pins(&[self.pin()], false, Some(Duration::default()))? {
+                    Some((_, event)) => Ok(crate::reactor::Attempt::Ready(event)),
+                    None => Ok(crate::reactor::Attempt::Wait(fd)),
+                }
+            })
+        }
+
+        /// Returns an iterator over the interrupt trigger events for this pin.
+        ///
+        /// This only works after the pin has been configured for synchronous interrupts using
+        /// [`set_interrupt`]. Each call to `next` blocks until the next trigger event occurs,
+        /// similar to calling [`poll_interrupt`] without a timeout, and returns any cached
+        /// events first.
+        ///
+        /// The iterator ends when no synchronous interrupt trigger is configured, or when
+        /// waiting for an event is interrupted by a signal. Any other errors are returned as
+        /// `Some(Err(_))`, after which iteration can continue.
+        ///
+        /// The iterator borrows the pin mutably for its entire lifetime, so the pin can't be
+        /// reconfigured or read until the iterator is dropped. Use adapters like `take` to
+        /// limit the number of events.
+        ///
+        /// [`set_interrupt`]: #method.set_interrupt
+        /// [`poll_interrupt`]: #method.poll_interrupt
+        pub fn events(&mut self) -> impl Iterator<Item = Result<Event>> + '_ {
+            iter::from_fn(move || {
+                let mut event_loop = self.pin.gpio_state.sync_interrupts.lock().unwrap();
+
+                if !event_loop.has_interrupt(self.pin()) {
+                    return None;
+                }
+
+                match event_loop.poll_pins(&[self.pin()], false, None) {
+                    Ok(Some((_, event))) => Some(Ok(event)),
+                    Ok(None) => None,
+                    Err(Error::Io(ref e)) if e.kind() == io::ErrorKind::Interrupted => None,
+                    Err(e) => Some(Err(e)),
+                }
+            })
+        }
+
+        /// Returns an iterator over the interrupt trigger events for this pin, with any
+        /// pulses shorter than `min_width` removed.
+        ///
+        /// `glitch_filter` configures a synchronous interrupt trigger for both edges, and
+        /// passes the events through a [`GlitchFilter`]. Pulse widths are measured using the
+        /// timestamps assigned by the kernel when the interrupts occurred, so the filter
+        /// isn't affected by scheduling delays in userspace. This makes it suitable for
+        /// rejecting noise on inputs such as rotary encoders and flow sensors.
+        ///
+        /// Each edge is returned once the next edge arrives, or once `min_width` has passed
+        /// without a further edge, which delays every event by up to `min_width`. Edges
+        /// that don't change the level are skipped.
+        ///
+        /// Any previously configured synchronous interrupt trigger will be replaced.
+        /// The iterator ends under the same conditions as [`events`].
+        ///
+        /// [`GlitchFilter`]: struct.GlitchFilter.html
+        /// [`events`]: #method.events
+        pub fn glitch_filter(
+            &mut self,
+            min_width: Duration,
+        ) -> Result<impl Iterator<Item = Result<Event>> + '_> {
+            self.set_interrupt(Trigger::Both)?;
+
+            let mut filter = GlitchFilter::new(min_width);
+            // Kernel timestamp of the pending edge, and when we stop waiting for the next edge
+            let mut deadline: Option<(Duration, Instant)> = None;
+
+            Ok(iter::from_fn(move || loop {
+                let timeout = if let Some(pending) = filter.pending() {
+                    match deadline {
+                        Some((timestamp, _)) if timestamp == pending.timestamp() => (),
+                        _ => deadline = Some((pending.timestamp(), Instant::now() + min_width)),
+                    }
+
+                    deadline.map(|(_, deadline)| deadline.saturating_duration_since(Instant::now()))
+                } else {
+                    None
+                };
+
+                let mut event_loop = self.pin.gpio_state.sync_interrupts.lock().unwrap();
+
+                if !event_loop.has_interrupt(self.pin()) {
+                    return filter.flush().map(Ok);
+                }
+
+                match event_loop.poll_pins(&[self.pin()], false, timeout) {
+                    Ok(Some((_, event))) => {
+                        if let Some(event) = filter.push(event) {
+                            return Some(Ok(event));
+                        }
+                    }
+                    Ok(None) => {
+                        if let Some(event) = filter.flush() {
+                            return Some(Ok(event));
+                        }
+                    }
+                    Err(Error::Io(ref e)) if e.kind() == io::ErrorKind::Interrupted => return None,
+                    Err(e) => return Some(Err(e)),
+                }
+            }))
+        }
+
+        /// Configures an asynchronous interrupt trigger, which executes the callback on a
+        /// separate thread when the interrupt is triggered.
+        ///
+        /// The callback closure or function pointer is called with a single [`Level`] argument.
+        ///
+        /// Any previously configured (a)synchronous interrupt triggers for this pin are cleared
+        /// when `set_async_interrupt` is called, or when the pin goes out of scope.
+        ///
+        /// [`clear_async_interrupt`]: #method.clear_async_interrupt
+        /// [`Level`]: enum.Level.html
+        pub fn set_async_interrupt<C>(&mut self, trigger: Trigger, callback: C) -> Result<()>
+        where
+            C: FnMut(Level) + Send + 'static,
+        {
+            self.check_interrupt_mode()?;
+            self.clear_interrupt()?;
+            self.clear_async_interrupt()?;
+
+            log_debug!(
+                "Pin {} asynchronous interrupt set to {}",
+                self.pin(),
+                trigger
+            );
+
+            self.pin.gpio_state.gpio_mem.release(self.pin());
+
+            self.async_interrupt = Some(AsyncInterrupt::new(
+                self.pin.gpio_state.cdev.as_raw_fd(),
+                self.pin(),
+                trigger,
+                callback,
+            )?);
+
+            Ok(())
+        }
+
+        /// Configures an asynchronous interrupt trigger, which executes the callback on a
+        /// separate thread when the pin's logic level changes.
+        ///
+        /// Unlike [`set_async_interrupt`] with [`Trigger::Both`], `on_level_change` keeps
+        /// track of the last reported level, and only calls the callback when the new level
+        /// differs from the previous one. Consecutive trigger events for the same level,
+        /// which can occur on noisy signals, are discarded. The initial level is read when
+        /// `on_level_change` is called.
+        ///
+        /// The callback closure or function pointer is called with a single [`Level`] argument.
+        ///
+        /// Any previously configured (a)synchronous interrupt triggers for this pin are cleared
+        /// when `on_level_change` is called, or when the pin goes out of scope. Call
+        /// [`clear_async_interrupt`] to remove the trigger.
+        ///
+        /// [`set_async_interrupt`]: #method.set_async_interrupt
+        /// [`clear_async_interrupt`]: #method.clear_async_interrupt
+        /// [`Trigger::Both`]: enum.Trigger.html#variant.Both
+        /// [`Level`]: enum.Level.html
+        pub fn on_level_change<C>(&mut self, mut callback: C) -> Result<()>
+        where
+            C: FnMut(Level) + Send + 'static,
+        {
+            let mut last_level = self.read();
+
+            self.set_async_interrupt(Trigger::Both, move |level| {
+                if level != last_level {
+                    last_level = level;
+                    callback(level);
+                }
+            })
+        }
+
+        /// Removes a previously configured asynchronous interrupt trigger.
+        pub fn clear_async_interrupt(&mut self) -> Result<()> {
+            if let Some(mut interrupt) = self.async_interrupt.take() {
+                log_debug!("Pin {} asynchronous interrupt cleared", self.pin());
+
+                interrupt.stop()?;
+            }
+
+            Ok(())
+        }
+
+        // Reads the pin's level through the event request of an active
+        // (a)synchronous interrupt. Returns None if no interrupt is configured,
+        // or the level couldn't be read.
+        fn interrupt_level(&self) -> Option<Level> {
+            let result = match self.async_interrupt {
+                Some(ref interrupt) => interrupt.level(),
+                None => self
+                    .pin
+                    .gpio_state
+                    .sync_interrupts
+                    .lock()
+                    .unwrap()
+                    .interrupt_level(self.pin())?,
+            };
+
+            match result {
+                Ok(level) => Some(level),
+                Err(_e) => {
+                    log_warn!("Pin {} interrupt level read failed: {}", self.pin(), _e);
+
+                    None
+                }
+            }
+        }
+
+        // Removes any (a)synchronous interrupt triggers, which closes the event fd and
+        // stops the interrupt polling thread. Errors are ignored, because the
+        // resources are released regardless.
+        fn release_interrupts(&mut self) {
+            if let Ok(mut event_loop) = self.pin.gpio_state.sync_interrupts.lock() {
+                if event_loop.has_interrupt(self.pin()) {
+                    log_debug!("Pin {} synchronous interrupt cleared", self.pin());
+
+                    let _ = event_loop.clear_interrupt(self.pin());
+                }
+            }
+
+            // Dropping AsyncInterrupt stops the polling thread, unless the current
+            // thread is panicking.
+            self.async_interrupt = None;
+        }
+    };
+}
+
 macro_rules! impl_reset_on_drop {
     () => {
         /// Returns the value of `reset_on_drop`.
@@ -194,11 +674,13 @@ macro_rules! impl_reset_on_drop {
 }
 
 macro_rules! impl_drop {
-    ($struct:ident) => {
+    ($struct:ident $(, $release:ident)?) => {
         impl Drop for $struct {
             /// Resets the pin's mode and disables the built-in pull-up/pull-down
             /// resistors if `reset_on_drop` is set to `true` (default).
             fn drop(&mut self) {
+                $(self.$release();)?
+
                 if !self.reset_on_drop {
                     return;
                 }
@@ -327,6 +809,69 @@ impl Pin {
         InputPin::new(self, PullUpDown::PullUp)
     }
 
+    /// Consumes the `Pin` and returns an [`InputPin`], using the specified
+    /// [`RequestMode`].
+    ///
+    /// The kernel keeps track of which consumer has requested each GPIO line
+    /// through the `gpiochip` character device. A line can only be requested
+    /// by a single consumer at a time, and any further requests fail until
+    /// it's released. The kernel doesn't offer a way to request a line
+    /// without claiming it, or to share a request between processes.
+    /// Reading the GPIO registers directly doesn't involve any requests.
+    ///
+    /// * [`RequestMode::Exclusive`] returns [`Error::LineBusy`] if the line is
+    ///   requested by another process or kernel driver. Otherwise, the pin is
+    ///   configured the same way as [`into_input`]. With the
+    ///   [`Backend::CharacterDevice`] backend, the line is requested, and
+    ///   other consumers can't request it until the `InputPin` goes out of
+    ///   scope. With the [`Backend::Registers`] backend, RPPAL doesn't
+    ///   request the line, so other consumers can still request it later on.
+    /// * [`RequestMode::AsIs`] leaves the pin's mode and built-in
+    ///   pull-up/pull-down resistors unchanged, so the pin can be monitored
+    ///   while it's controlled elsewhere. With the [`Backend::Registers`]
+    ///   backend, the logic level is read from the registers without
+    ///   requesting the line, which always succeeds. With the
+    ///   [`Backend::CharacterDevice`] backend, reading the logic level
+    ///   requires requesting the line, so [`Error::LineBusy`] is returned if
+    ///   the line is in use by another consumer, and the line is requested
+    ///   when the pin is first read.
+    ///
+    /// To be notified when another consumer requests, releases or
+    /// reconfigures a line, without requesting it, use
+    /// [`Gpio::watch_line`].
+    ///
+    /// [`InputPin`]: struct.InputPin.html
+    /// [`RequestMode`]: enum.RequestMode.html
+    /// [`RequestMode::Exclusive`]: enum.RequestMode.html#variant.Exclusive
+    /// [`RequestMode::AsIs`]: enum.RequestMode.html#variant.AsIs
+    /// [`Error::LineBusy`]: enum.Error.html#variant.LineBusy
+    /// [`into_input`]: #method.into_input
+    /// [`Backend::CharacterDevice`]: enum.Backend.html#variant.CharacterDevice
+    /// [`Backend::Registers`]: enum.Backend.html#variant.Registers
+    /// [`Gpio::watch_line`]: struct.Gpio.html#method.watch_line
+    pub fn into_input_with(self, mode: RequestMode) -> Result<InputPin> {
+        // Drop any line request held by the character device backend, so
+        // only requests by other consumers are reported as in use
+        self.gpio_state.gpio_mem.release(self.pin);
+
+        let line_info = ioctl::LineInfo::get(self.gpio_state.cdev.as_raw_fd(), self.pin)?;
+        let busy = || Error::LineBusy {
+            pin: self.pin,
+            consumer: line_info.consumer(),
+        };
+
+        match mode {
+            RequestMode::Exclusive if line_info.is_used() => Err(busy()),
+            RequestMode::Exclusive => Ok(self.into_input()),
+            RequestMode::AsIs
+                if line_info.is_used() && self.gpio_state.backend != Backend::Registers =>
+            {
+                Err(busy())
+            }
+            RequestMode::AsIs => Ok(InputPin::as_is(self)),
+        }
+    }
+
     /// Consumes the `Pin` and returns an [`OutputPin`]. Sets the mode to [`Mode::Output`]
     /// and leaves the logic level unchanged.
     #[inline]
@@ -354,20 +899,40 @@ impl Pin {
 
     /// Consumes the `Pin` and returns an [`IoPin`]. Sets the mode to the specified mode.
     ///
+    /// Returns [`Error::ModeNotSupported`] if the GPIO peripheral doesn't support `mode`.
+    ///
     /// [`IoPin`]: struct.IoPin.html
     /// [`Mode`]: enum.Mode.html
+    /// [`Error::ModeNotSupported`]: enum.Error.html#variant.ModeNotSupported
     #[inline]
-    pub fn into_io(self, mode: Mode) -> IoPin {
-        IoPin::new(self, mode)
+    pub fn into_io(self, mode: Mode) -> Result<IoPin> {
+        self.check_mode(mode)?;
+
+        Ok(IoPin::new(self, mode))
+    }
+
+    pub(crate) fn check_mode(&self, mode: Mode) -> Result<()> {
+        if !self.gpio_state.gpio_mem.supports_mode(mode) {
+            return Err(Error::ModeNotSupported {
+                pin: self.pin,
+                mode,
+            });
+        }
+
+        Ok(())
     }
 
     #[inline]
     pub(crate) fn set_mode(&mut self, mode: Mode) {
+        log_debug!("Pin {} mode set to {}", self.pin, mode);
+
         self.gpio_state.gpio_mem.set_mode(self.pin, mode);
     }
 
     #[inline]
     pub(crate) fn set_pullupdown(&mut self, pud: PullUpDown) {
+        log_debug!("Pin {} pull-up/pull-down set to {}", self.pin, pud);
+
         self.gpio_state.gpio_mem.set_pullupdown(self.pin, pud);
     }
 
@@ -393,7 +958,8 @@ impl Pin {
 impl Drop for Pin {
     fn drop(&mut self) {
         // Release taken pin
-        self.gpio_state.pins_taken[self.pin as usize].store(false, Ordering::SeqCst);
+        self.gpio_state.gpio_mem.release(self.pin);
+        self.gpio_state.pins_taken.release(self.pin);
     }
 }
 
@@ -423,6 +989,7 @@ pub struct InputPin {
     pub(crate) pin: Pin,
     prev_mode: Option<Mode>,
     async_interrupt: Option<AsyncInterrupt>,
+    stable_watch: Option<StableWatch>,
     reset_on_drop: bool,
     pud_mode: PullUpDown,
 }
@@ -444,110 +1011,175 @@ impl InputPin {
             pin,
             prev_mode,
             async_interrupt: None,
+            stable_watch: None,
             reset_on_drop: true,
             pud_mode,
         }
     }
 
+    // Leaves the mode and pull-up/pull-down resistors unchanged, so dropping
+    // the InputPin doesn't change anything either.
+    pub(crate) fn as_is(pin: Pin) -> InputPin {
+        InputPin {
+            pin,
+            prev_mode: None,
+            async_interrupt: None,
+            stable_watch: None,
+            reset_on_drop: true,
+            pud_mode: PullUpDown::Off,
+        }
+    }
+
     impl_pin!();
     impl_input!();
 
-    /// Configures a synchronous interrupt trigger.
-    ///
-    /// After configuring a synchronous interrupt trigger, call [`poll_interrupt`] or
-    /// [`Gpio::poll_interrupts`] to block while waiting for a trigger event.
-    ///
-    /// Any previously configured (a)synchronous interrupt triggers will be cleared.
-    ///
-    /// [`poll_interrupt`]: #method.poll_interrupt
-    /// [`Gpio::poll_interrupts`]: struct.Gpio.html#method.poll_interrupts
-    pub fn set_interrupt(&mut self, trigger: Trigger) -> Result<()> {
-        self.clear_async_interrupt()?;
-
-        // Each pin can only be configured for a single trigger type
-        (*self.pin.gpio_state.sync_interrupts.lock().unwrap()).set_interrupt(self.pin(), trigger)
-    }
-
-    /// Removes a previously configured synchronous interrupt trigger.
-    pub fn clear_interrupt(&mut self) -> Result<()> {
-        (*self.pin.gpio_state.sync_interrupts.lock().unwrap()).clear_interrupt(self.pin())
-    }
+    impl_interrupt!();
+    impl_reset_on_drop!();
 
-    /// Blocks until an interrupt is triggered on the pin, or a timeout occurs.
+    /// Blocks until an interrupt is triggered on either this pin or `other`,
+    /// or until a timeout occurs.
+    ///
+    /// `wait_for_either` is intended for handshakes where a slave device
+    /// signals it's ready on one pin, while an abort request can arrive on
+    /// another pin. Both pins must have been configured for synchronous
+    /// interrupts using [`set_interrupt`].
     ///
-    /// This only works after the pin has been configured for synchronous interrupts using
-    /// [`set_interrupt`]. Asynchronous interrupt triggers are automatically polled on a separate thread.
+    /// Both pins are borrowed mutably for the duration of the call, so neither
+    /// of them can be reconfigured, or polled elsewhere, until
+    /// `wait_for_either` returns. This also guarantees the two pins are
+    /// different. Similar to [`poll_interrupt`], `wait_for_either` blocks any
+    /// other calls to [`poll_interrupt`] and [`Gpio::poll_interrupts`] until
+    /// it returns.
     ///
-    /// Calling `poll_interrupt` blocks any other calls to `poll_interrupt` (including on other `InputPin`s) or
-    /// [`Gpio::poll_interrupts`] until it returns. If you need to poll multiple pins simultaneously, use
-    /// [`Gpio::poll_interrupts`] to block while waiting for any of the interrupts to trigger, or switch to
-    /// using asynchronous interrupts with [`set_async_interrupt`].
+    /// Any cached interrupt trigger events are returned first, checking this
+    /// pin before `other`. To discard stale events before starting a
+    /// handshake, call [`poll_interrupt`] with `reset` set to `true` and a
+    /// zero timeout on both pins.
     ///
-    /// Setting `reset` to `false` returns any cached interrupt trigger events if available. Setting `reset` to `true`
-    /// clears all cached events before polling for new events.
+    /// The `timeout` duration indicates how long the call will block while
+    /// waiting for interrupt trigger events, after which
+    /// [`WhichPin::Timeout`] is returned. `timeout` can be set to `None` to
+    /// wait indefinitely.
     ///
-    /// The `timeout` duration indicates how long the call will block while waiting
-    /// for interrupt trigger events, after which an `Ok(None))` is returned.
-    /// `timeout` can be set to `None` to wait indefinitely.
+    /// If both pins trigger at the same time, only the first event is
+    /// returned. The remaining event is cached, and will be returned by the
+    /// next call.
     ///
     /// [`set_interrupt`]: #method.set_interrupt
+    /// [`poll_interrupt`]: #method.poll_interrupt
     /// [`Gpio::poll_interrupts`]: struct.Gpio.html#method.poll_interrupts
-    /// [`set_async_interrupt`]: #method.set_async_interrupt
-    pub fn poll_interrupt(
+    /// [`WhichPin::Timeout`]: enum.WhichPin.html#variant.Timeout
+    pub fn wait_for_either(
         &mut self,
-        reset: bool,
+        other: &mut InputPin,
         timeout: Option<Duration>,
-    ) -> Result<Option<Level>> {
-        let opt =
-            (*self.pin.gpio_state.sync_interrupts.lock().unwrap()).poll(&[self], reset, timeout)?;
-
-        if let Some(trigger) = opt {
-            Ok(Some(trigger.1))
-        } else {
-            Ok(None)
-        }
+    ) -> Result<WhichPin> {
+        let opt = (*self.pin.gpio_state.sync_interrupts.lock().unwrap()).poll_pins(
+            &[self.pin(), other.pin()],
+            false,
+            timeout,
+        )?;
+
+        Ok(match opt {
+            Some((0, event)) => WhichPin::This(event.level()),
+            Some((_, event)) => WhichPin::Other(event.level()),
+            None => WhichPin::Timeout,
+        })
     }
 
-    /// Configures an asynchronous interrupt trigger, which executes the callback on a
-    /// separate thread when the interrupt is triggered.
+    /// Samples the pin's logic level on a separate thread, and calls `callback`
+    /// after the level has changed and remained stable for at least the
+    /// `stable_for` duration.
+    ///
+    /// `watch_stable` is intended for slow or noisy signals that don't produce
+    /// clean edges, such as reed switches, mechanical contacts or signals
+    /// with a slow rise time, where interrupt triggers fire repeatedly or
+    /// miss transitions, and a single read may catch the line mid-bounce.
+    /// Because the level is sampled rather than relying on interrupts, a
+    /// change is only reported once the new level has been observed
+    /// continuously for `stable_for`. Shorter pulses are ignored.
+    ///
+    /// The initial level is read when `watch_stable` is called, and isn't
+    /// reported. The callback closure or function pointer is called with a
+    /// single [`Level`] argument, on the sampling thread.
+    ///
+    /// The level is sampled 10 times per `stable_for` duration, limited to
+    /// between once per millisecond and once every 50 ms. Each sample wakes
+    /// up the sampling thread, so a short `stable_for` duration results in
+    /// a noticeable CPU load on slower models. A change is reported between
+    /// `stable_for` and `stable_for` plus one sampling interval after the
+    /// level settled.
+    ///
+    /// Any previous `watch_stable` configuration is replaced. The sampling
+    /// thread is stopped when [`clear_watch_stable`] is called, or when the
+    /// pin goes out of scope.
     ///
-    /// The callback closure or function pointer is called with a single [`Level`] argument.
+    /// ```no_run
+    /// use std::time::Duration;
     ///
-    /// Any previously configured (a)synchronous interrupt triggers for this pin are cleared
-    /// when `set_async_interrupt` is called, or when `InputPin` goes out of scope.
+    /// use rppal::gpio::Gpio;
+    ///
+    /// # fn main() -> rppal::gpio::Result<()> {
+    /// let mut pin = Gpio::new()?.get(17)?.into_input_pullup();
+    ///
+    /// pin.watch_stable(Duration::from_millis(50), |level| {
+    ///     println!("Reed switch is now {}", level);
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
     ///
-    /// [`clear_async_interrupt`]: #method.clear_async_interrupt
     /// [`Level`]: enum.Level.html
-    pub fn set_async_interrupt<C>(&mut self, trigger: Trigger, callback: C) -> Result<()>
+    /// [`clear_watch_stable`]: #method.clear_watch_stable
+    pub fn watch_stable<C>(&mut self, stable_for: Duration, callback: C) -> Result<()>
     where
         C: FnMut(Level) + Send + 'static,
     {
-        self.clear_interrupt()?;
-        self.clear_async_interrupt()?;
+        self.clear_watch_stable()?;
 
-        self.async_interrupt = Some(AsyncInterrupt::new(
-            self.pin.gpio_state.cdev.as_raw_fd(),
+        self.stable_watch = Some(StableWatch::spawn(
+            self.pin.gpio_state.clone(),
             self.pin(),
-            trigger,
+            stable_for,
             callback,
         )?);
 
+        log_debug!(
+            "Pin {} watching for levels stable for {:?}",
+            self.pin(),
+            stable_for
+        );
+
         Ok(())
     }
 
-    /// Removes a previously configured asynchronous interrupt trigger.
-    pub fn clear_async_interrupt(&mut self) -> Result<()> {
-        if let Some(mut interrupt) = self.async_interrupt.take() {
-            interrupt.stop()?;
+    /// Stops the sampling thread started by [`watch_stable`].
+    ///
+    /// [`watch_stable`]: #method.watch_stable
+    pub fn clear_watch_stable(&mut self) -> Result<()> {
+        if let Some(mut stable_watch) = self.stable_watch.take() {
+            stable_watch.stop()?;
         }
 
         Ok(())
     }
 
-    impl_reset_on_drop!();
+    // InputPin is always in input mode.
+    #[inline]
+    fn check_interrupt_mode(&self) -> Result<()> {
+        Ok(())
+    }
+
+    // Stops all background threads before the pin is reset, so they don't
+    // observe the level changing when the pull-up/pull-down resistors are
+    // disabled.
+    fn release_background(&mut self) {
+        self.release_interrupts();
+        self.stable_watch = None;
+    }
 }
 
-impl_drop!(InputPin);
+impl_drop!(InputPin, release_background);
 impl_eq!(InputPin);
 
 /// GPIO pin configured as output.
@@ -581,10 +1213,10 @@ pub struct OutputPin {
     pud_mode: PullUpDown,
     pub(crate) soft_pwm: Option<SoftPwm>,
     // Stores the softpwm frequency. Used for embedded_hal::PwmPin.
-    #[cfg(feature = "hal")]
+    #[cfg(feature = "hal-0")]
     pub(crate) frequency: f64,
     // Stores the softpwm duty cycle. Used for embedded_hal::PwmPin.
-    #[cfg(feature = "hal")]
+    #[cfg(feature = "hal-0")]
     pub(crate) duty_cycle: f64,
 }
 
@@ -605,13 +1237,19 @@ impl OutputPin {
             reset_on_drop: true,
             pud_mode: PullUpDown::Off,
             soft_pwm: None,
-            #[cfg(feature = "hal")]
+            #[cfg(feature = "hal-0")]
             frequency: 0.0,
-            #[cfg(feature = "hal")]
+            #[cfg(feature = "hal-0")]
             duty_cycle: 0.0,
         }
     }
 
+    // Used by LineGroup to access the GPIO registers directly.
+    #[inline]
+    pub(crate) fn gpio_state(&self) -> &GpioState {
+        &self.pin.gpio_state
+    }
+
     impl_pin!();
 
     /// Returns `true` if the pin's output state is set to [`Low`].
@@ -630,6 +1268,156 @@ impl OutputPin {
         self.pin.read() == Level::High
     }
 
+    /// Emits a single pulse by setting the pin's output state to `level` for the
+    /// specified `duration`, after which the previous output state is restored.
+    ///
+    /// `pulse` blocks until the pulse has completed. Any software-based PWM signal
+    /// configured with [`set_pwm`] is stopped first.
+    ///
+    /// The output state is changed by writing directly to the GPIO registers,
+    /// and the pulse width is timed by sleeping for most of the duration, and
+    /// busy-waiting for the remainder. Pulses shorter than 250 µs only use
+    /// busy-waiting, which keeps the CPU occupied for the entire pulse.
+    ///
+    /// The pulse never ends early, but it may be extended. Typical accuracy
+    /// depends on the duration:
+    ///
+    /// * Below 10 µs, the pulse is extended by the time it takes to write to the
+    ///   GPIO registers and read the clock, which is usually less than 1 µs on
+    ///   release builds. The shortest possible pulse is limited by this overhead.
+    /// * Between 10 µs and 250 µs, the pulse is usually accurate to within
+    ///   1-2 µs on release builds.
+    /// * Above 250 µs, the pulse is usually accurate to within a few µs, as long
+    ///   as the thread resumes from sleep within the reserved busy-wait time.
+    ///
+    /// Because Linux isn't a real-time OS, the thread can be preempted at any
+    /// time, which extends the pulse by the amount of time it's suspended. Running
+    /// the thread with a real-time scheduling policy reduces the likelihood of
+    /// this happening.
+    ///
+    /// [`set_pwm`]: #method.set_pwm
+    pub fn pulse(&mut self, level: Level, duration: Duration) -> Result<()> {
+        self.clear_pwm()?;
+
+        let prev_level = self.pin.read();
+
+        self.pin.write(level);
+        wait_until(Instant::now() + duration, None);
+        self.pin.write(prev_level);
+
+        Ok(())
+    }
+
+    /// Plays a waveform by walking through `pattern`, setting the pin's output
+    /// state to each listed level for the listed duration.
+    ///
+    /// The entire `pattern` is played `repeat` times, after which the previous
+    /// output state is restored. If `repeat` is `0`, `play_pattern` returns
+    /// immediately. `play_pattern` blocks until the waveform has completed. Any
+    /// software-based PWM signal configured with [`set_pwm`] is stopped first.
+    ///
+    /// Each step is timed the same way as [`pulse`]. Steps are scheduled
+    /// relative to the moment playback started rather than to the end of the
+    /// previous step, so any delays caused by the scheduler don't accumulate
+    /// over the course of the waveform. A late step is shortened to get back on
+    /// schedule.
+    ///
+    /// This is useful for slow protocols that are timing-sensitive, but don't
+    /// require sub-microsecond accuracy, such as 1-Wire write slots or IR
+    /// remote control codes. Use [`play_pattern_cancellable`] if playback
+    /// needs to be stopped from another thread.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use rppal::gpio::{Gpio, Level};
+    ///
+    /// # fn main() -> rppal::gpio::Result<()> {
+    /// let mut pin = Gpio::new()?.get(23)?.into_output_high();
+    ///
+    /// // Send two short and one long low pulse, three times.
+    /// pin.play_pattern(
+    ///     &[
+    ///         (Level::Low, Duration::from_micros(500)),
+    ///         (Level::High, Duration::from_micros(500)),
+    ///         (Level::Low, Duration::from_micros(500)),
+    ///         (Level::High, Duration::from_micros(500)),
+    ///         (Level::Low, Duration::from_millis(2)),
+    ///         (Level::High, Duration::from_millis(5)),
+    ///     ],
+    ///     3,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_pwm`]: #method.set_pwm
+    /// [`pulse`]: #method.pulse
+    /// [`play_pattern_cancellable`]: #method.play_pattern_cancellable
+    pub fn play_pattern(&mut self, pattern: &[(Level, Duration)], repeat: usize) -> Result<()> {
+        self.play_pattern_inner(pattern, repeat, None)?;
+
+        Ok(())
+    }
+
+    /// Plays a waveform similar to [`play_pattern`], and stops early when
+    /// `cancel` is set to `true`.
+    ///
+    /// `cancel` is checked before each step, and at least every 10 ms while
+    /// waiting. When playback is cancelled, the previous output state is
+    /// restored immediately.
+    ///
+    /// Returns `true` if the waveform was played in its entirety, or `false` if
+    /// playback was cancelled. `cancel` isn't reset.
+    ///
+    /// [`play_pattern`]: #method.play_pattern
+    pub fn play_pattern_cancellable(
+        &mut self,
+        pattern: &[(Level, Duration)],
+        repeat: usize,
+        cancel: &AtomicBool,
+    ) -> Result<bool> {
+        self.play_pattern_inner(pattern, repeat, Some(cancel))
+    }
+
+    fn play_pattern_inner(
+        &mut self,
+        pattern: &[(Level, Duration)],
+        repeat: usize,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<bool> {
+        if repeat == 0 || pattern.is_empty() {
+            return Ok(true);
+        }
+
+        self.clear_pwm()?;
+
+        let prev_level = self.pin.read();
+        let mut deadline = Instant::now();
+        let mut completed = true;
+
+        'outer: for _ in 0..repeat {
+            for &(level, duration) in pattern {
+                if cancel.map_or(false, |cancel| cancel.load(Ordering::SeqCst)) {
+                    completed = false;
+                    break 'outer;
+                }
+
+                self.pin.write(level);
+                deadline += duration;
+
+                if !wait_until(deadline, cancel) {
+                    completed = false;
+                    break 'outer;
+                }
+            }
+        }
+
+        self.pin.write(prev_level);
+
+        Ok(completed)
+    }
+
     impl_output!();
     impl_reset_on_drop!();
 }
@@ -647,6 +1435,44 @@ impl_eq!(OutputPin);
 /// alters the pin's output state won't cause any changes when the pin's mode is set
 /// to [`Input`].
 ///
+/// While the pin's mode is set to [`Input`], (a)synchronous interrupt triggers can be
+/// configured the same way as on an [`InputPin`]. Any interrupt triggers are
+/// automatically cleared when the mode is changed to [`Output`] or an alternate
+/// function, which closes the underlying event file descriptor and stops the
+/// polling thread used by asynchronous interrupts.
+///
+/// This is useful for bidirectional protocols, where a single line is used to
+/// send a request, and then to detect the response. Because clearing the interrupt
+/// discards any pending trigger events, configure the trigger directly after
+/// switching to [`Input`], and poll without resetting so events that occur before
+/// [`poll_interrupt`] is called aren't lost.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use rppal::gpio::{Gpio, Mode, Trigger};
+///
+/// # fn main() -> rppal::gpio::Result<()> {
+/// let mut pin = Gpio::new()?.get(17)?.into_io(Mode::Output)?;
+///
+/// // 1. Send the request by pulling the line low.
+/// pin.set_low();
+///
+/// // 2. Release the line, and wait for the peer to respond by pulling it low.
+/// pin.set_mode(Mode::Input)?;
+/// pin.set_interrupt(Trigger::FallingEdge)?;
+///
+/// if pin.poll_interrupt(false, Some(Duration::from_millis(100)))?.is_none() {
+///     println!("No response");
+/// }
+///
+/// // 3. Take control of the line again. This clears the interrupt trigger.
+/// pin.set_mode(Mode::Output)?;
+/// pin.set_high();
+/// # Ok(())
+/// # }
+/// ```
+///
 /// The `embedded-hal` [`digital::OutputPin`] and [`PwmPin`] trait implementations for `IoPin`
 /// can be enabled by specifying the optional `hal` feature in the dependency
 /// declaration for the `rppal` crate.
@@ -662,6 +1488,9 @@ impl_eq!(OutputPin);
 /// [`Pwm`]: ../../embedded_hal/trait.Pwm.html
 /// [`Pin`]: struct.Pin.html
 /// [`Input`]: enum.Mode.html#variant.Input
+/// [`Output`]: enum.Mode.html#variant.Output
+/// [`InputPin`]: struct.InputPin.html
+/// [`poll_interrupt`]: #method.poll_interrupt
 /// [`Pin::into_io`]: struct.Pin.html#method.into_io
 /// [`digital::OutputPin`]: ../../embedded_hal/digital/trait.OutputPin.html
 /// [`PwmPin`]: ../../embedded_hal/trait.PwmPin.html
@@ -670,14 +1499,15 @@ pub struct IoPin {
     pin: Pin,
     mode: Mode,
     prev_mode: Option<Mode>,
+    async_interrupt: Option<AsyncInterrupt>,
     reset_on_drop: bool,
     pud_mode: PullUpDown,
     pub(crate) soft_pwm: Option<SoftPwm>,
     // Stores the softpwm frequency. Used for embedded_hal::PwmPin.
-    #[cfg(feature = "hal")]
+    #[cfg(feature = "hal-0")]
     pub(crate) frequency: f64,
     // Stores the softpwm duty cycle. Used for embedded_hal::PwmPin.
-    #[cfg(feature = "hal")]
+    #[cfg(feature = "hal-0")]
     pub(crate) duty_cycle: f64,
 }
 
@@ -696,12 +1526,13 @@ impl IoPin {
             pin,
             mode,
             prev_mode,
+            async_interrupt: None,
             reset_on_drop: true,
             pud_mode: PullUpDown::Off,
             soft_pwm: None,
-            #[cfg(feature = "hal")]
+            #[cfg(feature = "hal-0")]
             frequency: 0.0,
-            #[cfg(feature = "hal")]
+            #[cfg(feature = "hal-0")]
             duty_cycle: 0.0,
         }
     }
@@ -715,8 +1546,32 @@ impl IoPin {
     }
 
     /// Sets the pin's mode.
+    ///
+    /// Any (a)synchronous interrupt triggers are cleared when the mode is changed to
+    /// anything other than [`Input`]. Switching back to [`Input`] doesn't restore
+    /// previously configured interrupt triggers.
+    ///
+    /// Returns [`Error::ModeNotSupported`] if the GPIO peripheral doesn't support `mode`,
+    /// in which case the pin's mode is left unchanged.
+    ///
+    /// [`Input`]: enum.Mode.html#variant.Input
+    /// [`Error::ModeNotSupported`]: enum.Error.html#variant.ModeNotSupported
     #[inline]
-    pub fn set_mode(&mut self, mode: Mode) {
+    pub fn set_mode(&mut self, mode: Mode) -> Result<()> {
+        self.pin.check_mode(mode)?;
+        self.switch_mode(mode);
+
+        Ok(())
+    }
+
+    // Sets the pin's mode without checking whether it's supported. Input and
+    // Output are available on every GPIO peripheral.
+    pub(crate) fn switch_mode(&mut self, mode: Mode) {
+        // Interrupt triggers are only available in input mode
+        if mode != Mode::Input {
+            self.release_interrupts();
+        }
+
         // If self.prev_mode is set to None, that means the
         // requested mode during construction was the same as
         // the current mode. Save that mode if we're changing
@@ -737,8 +1592,18 @@ impl IoPin {
 
     impl_input!();
     impl_output!();
+    impl_interrupt!();
     impl_reset_on_drop!();
+
+    // Interrupt triggers can only be configured while the pin is in input mode.
+    fn check_interrupt_mode(&self) -> Result<()> {
+        if self.pin.mode() != Mode::Input {
+            return Err(Error::PinNotInput(self.pin()));
+        }
+
+        Ok(())
+    }
 }
 
-impl_drop!(IoPin);
+impl_drop!(IoPin, release_interrupts);
 impl_eq!(IoPin);
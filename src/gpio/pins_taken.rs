@@ -0,0 +1,104 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Keeps track of which pins are in use, shared by all Gpio handles. A pin is
+// taken by Gpio::get, and released when the Pin goes out of scope.
+pub(crate) struct PinsTaken {
+    pins: [AtomicBool; u8::MAX as usize],
+}
+
+impl PinsTaken {
+    pub(crate) fn new() -> PinsTaken {
+        PinsTaken {
+            pins: init_array!(AtomicBool::new(false), u8::MAX as usize),
+        }
+    }
+
+    // Atomically marks pin as taken. Returns false if it was already taken.
+    pub(crate) fn take(&self, pin: u8) -> bool {
+        self.pins[pin as usize]
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+
+    pub(crate) fn release(&self, pin: u8) {
+        self.pins[pin as usize].store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::PinsTaken;
+
+    #[test]
+    fn take_and_release() {
+        let pins_taken = PinsTaken::new();
+
+        assert!(pins_taken.take(4));
+        assert!(!pins_taken.take(4));
+        assert!(pins_taken.take(5));
+
+        pins_taken.release(4);
+
+        assert!(pins_taken.take(4));
+        assert!(!pins_taken.take(5));
+    }
+
+    #[test]
+    fn overlapping_threads() {
+        // Each thread takes a window of pins that overlaps with its neighbors
+        const THREADS: u8 = 8;
+        const PINS_PER_THREAD: u8 = 4;
+        const ITERATIONS: usize = 10_000;
+
+        let pins_taken = Arc::new(PinsTaken::new());
+        // Pins the threads believe they own
+        let owned: Arc<Vec<AtomicBool>> =
+            Arc::new((0..u8::MAX).map(|_| AtomicBool::new(false)).collect());
+        let taken = Arc::new(AtomicUsize::new(0));
+
+        let threads: Vec<_> = (0..THREADS)
+            .map(|thread_id| {
+                let pins_taken = Arc::clone(&pins_taken);
+                let owned = Arc::clone(&owned);
+                let taken = Arc::clone(&taken);
+
+                thread::spawn(move || {
+                    let first = thread_id * (PINS_PER_THREAD / 2);
+
+                    for _ in 0..ITERATIONS {
+                        for pin in first..first + PINS_PER_THREAD {
+                            if !pins_taken.take(pin) {
+                                continue;
+                            }
+
+                            assert!(
+                                !owned[pin as usize].swap(true, Ordering::SeqCst),
+                                "pin {} taken twice",
+                                pin
+                            );
+                            taken.fetch_add(1, Ordering::Relaxed);
+
+                            owned[pin as usize].store(false, Ordering::SeqCst);
+                            pins_taken.release(pin);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert!(taken.load(Ordering::Relaxed) > 0);
+
+        // Every pin is available again
+        for pin in 0..THREADS * PINS_PER_THREAD {
+            assert!(pins_taken.take(pin));
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{InputPin, Level, Result};
+
+// Default number of quadrature steps between two detents
+const STEPS_PER_DETENT: u8 = 4;
+// Default debounce period for the push button
+const BUTTON_DEBOUNCE: Duration = Duration::from_millis(5);
+
+// Quadrature step lookup table, indexed by (previous state << 2) | current
+// state, where each state is (A << 1) | B. Transitions where both channels
+// changed simultaneously are invalid, and ignored.
+const STEP_TABLE: [i8; 16] = [0, -1, 1, 0, 1, 0, 0, -1, -1, 0, 0, 1, 0, 1, -1, 0];
+
+/// Events reported by a [`RotaryEncoder`].
+///
+/// [`RotaryEncoder`]: struct.RotaryEncoder.html
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum EncoderEvent {
+    /// The shaft was rotated clockwise by one detent.
+    Clockwise,
+    /// The shaft was rotated counterclockwise by one detent.
+    CounterClockwise,
+    /// The push button was pressed.
+    Pressed,
+    /// The push button was released.
+    Released,
+}
+
+// Decoder state shared between the interrupt threads of all pins
+struct State {
+    callback: Box<dyn FnMut(EncoderEvent) + Send>,
+    levels: u8,
+    steps: i8,
+    steps_per_detent: u8,
+    pressed: bool,
+    debounce: Duration,
+    last_button_change: Option<Instant>,
+}
+
+impl State {
+    // Updates the channel selected by mask, and reports a rotation event once
+    // enough steps in the same direction have accumulated.
+    fn update_channel(&mut self, mask: u8, level: Level) {
+        let levels = match level {
+            Level::High => self.levels | mask,
+            Level::Low => self.levels & !mask,
+        };
+
+        if levels == self.levels {
+            return;
+        }
+
+        self.steps += STEP_TABLE[((self.levels << 2) | levels) as usize];
+        self.levels = levels;
+
+        if self.steps >= self.steps_per_detent as i8 {
+            self.steps = 0;
+            (self.callback)(EncoderEvent::Clockwise);
+        } else if self.steps <= -(self.steps_per_detent as i8) {
+            self.steps = 0;
+            (self.callback)(EncoderEvent::CounterClockwise);
+        }
+    }
+
+    fn update_button(&mut self, level: Level) {
+        let pressed = level == Level::Low;
+        if pressed == self.pressed {
+            return;
+        }
+
+        let now = Instant::now();
+        if let Some(last) = self.last_button_change {
+            if now.saturating_duration_since(last) < self.debounce {
+                return;
+            }
+        }
+
+        self.pressed = pressed;
+        self.last_button_change = Some(now);
+
+        (self.callback)(if pressed {
+            EncoderEvent::Pressed
+        } else {
+            EncoderEvent::Released
+        });
+    }
+}
+
+/// Decodes the signals of an incremental rotary encoder, with an optional
+/// push button.
+///
+/// `RotaryEncoder` configures asynchronous interrupt triggers on the encoder's
+/// A and B channels, and on the push button if one is present. The callback
+/// is called on one of the interrupt threads whenever an [`EncoderEvent`]
+/// occurs. Calls are serialized, so the callback never runs on more than one
+/// thread at a time. To process events on another thread, send them through
+/// a channel.
+///
+/// ## Wiring
+///
+/// Most encoders have three pins for the rotary part, labeled A, B and C, or
+/// CLK, DT and GND on breakout boards. Connect A and B to two GPIO pins, and
+/// C to ground. Unless the breakout board already includes pull-up
+/// resistors, configure A and B with [`Pin::into_input_pullup`]. If the
+/// reported direction is reversed, swap A and B.
+///
+/// Connect the push button, labeled SW on breakout boards, between a GPIO
+/// pin and ground, and configure the pin with [`Pin::into_input_pullup`].
+/// The button is considered pressed while its pin reads [`Low`].
+///
+/// ## Debouncing
+///
+/// Contact bounce on the A and B channels results in alternating forward
+/// and backward quadrature steps, which cancel each other out. A rotation
+/// event is only reported once the number of steps in the same direction
+/// reaches the number of steps per detent, which defaults to 4. Encoders
+/// that complete a full quadrature cycle every two detents should use a
+/// value of 2, which can be set with [`set_steps_per_detent`].
+///
+/// Push button level changes that occur within 5 ms of the previous change
+/// are ignored. The debounce period can be changed with
+/// [`set_button_debounce`].
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::sync::mpsc;
+///
+/// use rppal::gpio::{EncoderEvent, Gpio, RotaryEncoder};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let gpio = Gpio::new()?;
+/// let pin_a = gpio.get(17)?.into_input_pullup();
+/// let pin_b = gpio.get(27)?.into_input_pullup();
+/// let button = gpio.get(22)?.into_input_pullup();
+///
+/// let (tx, rx) = mpsc::channel();
+/// let _encoder = RotaryEncoder::with_button(pin_a, pin_b, button, move |event| {
+///     let _ = tx.send(event);
+/// })?;
+///
+/// let mut position = 0;
+/// for event in rx {
+///     match event {
+///         EncoderEvent::Clockwise => position += 1,
+///         EncoderEvent::CounterClockwise => position -= 1,
+///         EncoderEvent::Pressed => position = 0,
+///         EncoderEvent::Released => {}
+///     }
+///
+///     println!("Position: {}", position);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`EncoderEvent`]: enum.EncoderEvent.html
+/// [`Pin::into_input_pullup`]: struct.Pin.html#method.into_input_pullup
+/// [`Low`]: enum.Level.html#variant.Low
+/// [`set_steps_per_detent`]: #method.set_steps_per_detent
+/// [`set_button_debounce`]: #method.set_button_debounce
+pub struct RotaryEncoder {
+    pin_a: InputPin,
+    pin_b: InputPin,
+    button: Option<InputPin>,
+    state: Arc<Mutex<State>>,
+}
+
+impl RotaryEncoder {
+    /// Constructs a new `RotaryEncoder` without a push button.
+    ///
+    /// `callback` is called for every [`EncoderEvent`].
+    ///
+    /// [`EncoderEvent`]: enum.EncoderEvent.html
+    pub fn new<C>(pin_a: InputPin, pin_b: InputPin, callback: C) -> Result<RotaryEncoder>
+    where
+        C: FnMut(EncoderEvent) + Send + 'static,
+    {
+        RotaryEncoder::with_optional_button(pin_a, pin_b, None, callback)
+    }
+
+    /// Constructs a new `RotaryEncoder` with a push button.
+    ///
+    /// `callback` is called for every [`EncoderEvent`].
+    ///
+    /// [`EncoderEvent`]: enum.EncoderEvent.html
+    pub fn with_button<C>(
+        pin_a: InputPin,
+        pin_b: InputPin,
+        button: InputPin,
+        callback: C,
+    ) -> Result<RotaryEncoder>
+    where
+        C: FnMut(EncoderEvent) + Send + 'static,
+    {
+        RotaryEncoder::with_optional_button(pin_a, pin_b, Some(button), callback)
+    }
+
+    fn with_optional_button<C>(
+        mut pin_a: InputPin,
+        mut pin_b: InputPin,
+        mut button: Option<InputPin>,
+        callback: C,
+    ) -> Result<RotaryEncoder>
+    where
+        C: FnMut(EncoderEvent) + Send + 'static,
+    {
+        let levels = ((pin_a.read() as u8) << 1) | pin_b.read() as u8;
+        let pressed = button
+            .as_ref()
+            .map_or(false, |button| button.read() == Level::Low);
+
+        let state = Arc::new(Mutex::new(State {
+            callback: Box::new(callback),
+            levels,
+            steps: 0,
+            steps_per_detent: STEPS_PER_DETENT,
+            pressed,
+            debounce: BUTTON_DEBOUNCE,
+            last_button_change: None,
+        }));
+
+        let state_a = state.clone();
+        pin_a.on_level_change(move |level| {
+            if let Ok(mut state) = state_a.lock() {
+                state.update_channel(0b10, level);
+            }
+        })?;
+
+        let state_b = state.clone();
+        pin_b.on_level_change(move |level| {
+            if let Ok(mut state) = state_b.lock() {
+                state.update_channel(0b01, level);
+            }
+        })?;
+
+        if let Some(ref mut button) = button {
+            let state_button = state.clone();
+            button.on_level_change(move |level| {
+                if let Ok(mut state) = state_button.lock() {
+                    state.update_button(level);
+                }
+            })?;
+        }
+
+        Ok(RotaryEncoder {
+            pin_a,
+            pin_b,
+            button,
+            state,
+        })
+    }
+
+    /// Returns the number of quadrature steps between two detents.
+    pub fn steps_per_detent(&self) -> u8 {
+        self.state.lock().unwrap().steps_per_detent
+    }
+
+    /// Sets the number of quadrature steps between two detents.
+    ///
+    /// Most encoders complete a full quadrature cycle of 4 steps per detent.
+    /// Some encoders only move 1 or 2 steps per detent. Values outside the
+    /// range 1-4 are clamped.
+    ///
+    /// By default, `steps_per_detent` is set to 4.
+    pub fn set_steps_per_detent(&mut self, steps_per_detent: u8) {
+        let mut state = self.state.lock().unwrap();
+
+        state.steps_per_detent = steps_per_detent.clamp(1, 4);
+        state.steps = 0;
+    }
+
+    /// Returns the push button debounce period.
+    pub fn button_debounce(&self) -> Duration {
+        self.state.lock().unwrap().debounce
+    }
+
+    /// Sets the push button debounce period.
+    ///
+    /// Level changes that occur within `debounce` of the previous change are
+    /// ignored.
+    ///
+    /// By default, `button_debounce` is set to 5 ms.
+    pub fn set_button_debounce(&mut self, debounce: Duration) {
+        self.state.lock().unwrap().debounce = debounce;
+    }
+
+    /// Returns `true` if the push button is currently pressed.
+    ///
+    /// Always returns `false` if the encoder doesn't have a push button.
+    pub fn is_pressed(&self) -> bool {
+        self.state.lock().unwrap().pressed
+    }
+
+    /// Removes the interrupt triggers, and returns the A and B channel pins,
+    /// and the push button pin if present.
+    pub fn into_pins(mut self) -> Result<(InputPin, InputPin, Option<InputPin>)> {
+        self.pin_a.clear_async_interrupt()?;
+        self.pin_b.clear_async_interrupt()?;
+        if let Some(ref mut button) = self.button {
+            button.clear_async_interrupt()?;
+        }
+
+        Ok((self.pin_a, self.pin_b, self.button))
+    }
+}
+
+impl fmt::Debug for RotaryEncoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RotaryEncoder")
+            .field("pin_a", &self.pin_a)
+            .field("pin_b", &self.pin_b)
+            .field("button", &self.button)
+            .finish()
+    }
+}
@@ -0,0 +1,76 @@
+/// Converts an analog value into a digital state using hysteresis.
+///
+/// When a value derived from an analog source, such as an ADC reading, is
+/// compared against a single threshold, any noise causes the result to
+/// rapidly switch between states while the value is close to that threshold.
+/// `Schmitt` uses two separate thresholds instead, similar to a Schmitt
+/// trigger. The state changes to `true` once the value reaches the `high`
+/// threshold, and only changes back to `false` once the value drops to the
+/// `low` threshold.
+///
+/// `Schmitt` can be used with any numeric type that implements
+/// `PartialOrd` and `Copy`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Schmitt<T> {
+    low: T,
+    high: T,
+    state: bool,
+}
+
+impl<T: PartialOrd + Copy> Schmitt<T> {
+    /// Constructs a new `Schmitt` with the specified thresholds.
+    ///
+    /// If `low` is greater than `high`, the thresholds are swapped.
+    ///
+    /// The initial state is set to `false`.
+    pub fn new(low: T, high: T) -> Schmitt<T> {
+        let (low, high) = if low > high { (high, low) } else { (low, high) };
+
+        Schmitt {
+            low,
+            high,
+            state: false,
+        }
+    }
+
+    /// Returns the low threshold.
+    pub fn low(&self) -> T {
+        self.low
+    }
+
+    /// Returns the high threshold.
+    pub fn high(&self) -> T {
+        self.high
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> bool {
+        self.state
+    }
+
+    /// Sets the current state.
+    ///
+    /// `set_state` can be used to set the initial state based on a previous
+    /// reading, or to reset `Schmitt`.
+    pub fn set_state(&mut self, state: bool) {
+        self.state = state;
+    }
+
+    /// Updates the state based on `value`, and returns the new state.
+    ///
+    /// The state changes to `true` when `value` is greater than or equal to
+    /// the high threshold, and to `false` when `value` is less than or equal
+    /// to the low threshold. Values in between the two thresholds, or values
+    /// that can't be compared such as `NaN`, leave the state unchanged.
+    pub fn update(&mut self, value: T) -> bool {
+        if self.state {
+            if value <= self.low {
+                self.state = false;
+            }
+        } else if value >= self.high {
+            self.state = true;
+        }
+
+        self.state
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::gpio::{Error, GpioState, Level, Result};
+
+// Minimum and maximum interval between samples
+const SAMPLE_INTERVAL_MIN: Duration = Duration::from_millis(1);
+const SAMPLE_INTERVAL_MAX: Duration = Duration::from_millis(50);
+// Number of samples taken during the stable period
+const SAMPLES_PER_PERIOD: u32 = 10;
+
+// Samples a pin's logic level on a separate thread, and calls the callback
+// after a new level has remained stable for the configured duration.
+#[derive(Debug)]
+pub(crate) struct StableWatch {
+    sample_thread: Option<thread::JoinHandle<()>>,
+    tx: Sender<()>,
+}
+
+impl StableWatch {
+    pub(crate) fn spawn<C>(
+        gpio_state: Arc<GpioState>,
+        pin: u8,
+        stable_for: Duration,
+        mut callback: C,
+    ) -> Result<StableWatch>
+    where
+        C: FnMut(Level) + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let interval = sample_interval(stable_for);
+
+        let sample_thread = thread::Builder::new()
+            .name(format!("rppal-stable-{}", pin))
+            .spawn(move || {
+                let mut reported = gpio_state.gpio_mem.level(pin);
+                let mut candidate = reported;
+                let mut since = Instant::now();
+
+                // Sample until StableWatch is stopped or dropped
+                while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(interval) {
+                    let level = gpio_state.gpio_mem.level(pin);
+                    let now = Instant::now();
+
+                    if level != candidate {
+                        candidate = level;
+                        since = now;
+                    } else if candidate != reported && now.duration_since(since) >= stable_for {
+                        reported = candidate;
+                        callback(reported);
+                    }
+                }
+            })?;
+
+        Ok(StableWatch {
+            sample_thread: Some(sample_thread),
+            tx,
+        })
+    }
+
+    pub(crate) fn stop(&mut self) -> Result<()> {
+        // The thread may have already exited if the callback panicked
+        let _ = self.tx.send(());
+
+        if let Some(sample_thread) = self.sample_thread.take() {
+            if sample_thread.join().is_err() {
+                return Err(Error::ThreadPanic);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for StableWatch {
+    fn drop(&mut self) {
+        // Don't wait for the sample thread to exit if the main thread is panicking,
+        // because we could potentially block indefinitely while unwinding if the
+        // sample thread is executing a callback that doesn't return.
+        if !thread::panicking() {
+            let _ = self.stop();
+        }
+    }
+}
+
+// Samples the level several times during the stable period, within limits
+fn sample_interval(stable_for: Duration) -> Duration {
+    (stable_for / SAMPLES_PER_PERIOD).clamp(SAMPLE_INTERVAL_MIN, SAMPLE_INTERVAL_MAX)
+}
@@ -3,106 +3,208 @@
 //! The `hal` module consists of a collection of `embedded-hal` trait
 //! implementations for traits that aren't tied to a specific peripheral.
 //!
-//! This module is only included when either the `hal` or `hal-unproven` feature
-//! flag is enabled.
+//! This module is only included when any of the `hal`, `hal-0`, `hal-1` or
+//! `hal-unproven` feature flags are enabled. `hal-0` enables the trait
+//! implementations for `embedded-hal` v0.2.7, and `hal-1` those for
+//! `embedded-hal` v1.0.0-alpha.9. Both can be enabled at the same time.
+//!
+//! [`Hertz`] only depends on `core`. [`Delay`] and [`Timer`] are backed by the
+//! operating system's clocks and scheduler, and require `std`. Drivers that
+//! are shared with `no_std` targets should accept any type that implements the
+//! `embedded-hal` delay traits, rather than `Delay` itself.
+//!
+//! [`with_realtime_priority`] runs timing-critical code, such as a
+//! bit-banged transaction, with a real-time scheduling policy, which reduces
+//! the chance it's preempted halfway through.
+//!
+//! [`Hertz`]: struct.Hertz.html
+//! [`Delay`]: struct.Delay.html
+//! [`Timer`]: struct.Timer.html
+//! [`with_realtime_priority`]: fn.with_realtime_priority.html
 
+#[cfg(feature = "hal-1")]
 use core::convert::Infallible;
-use std::time::{Duration, Instant};
+use core::time::Duration;
+use std::mem;
+use std::time::Instant;
 
+#[cfg(feature = "hal-1")]
 use embedded_hal::delay::DelayUs;
-use spin_sleep::sleep;
+#[cfg(feature = "hal-0")]
 use void::Void;
 
+// Number of measurements used to calibrate a Delay
+const CALIBRATION_ITERATIONS: usize = 1000;
+// Delay used during calibration. Short enough to only use busy-waiting.
+const CALIBRATION_DELAY: Duration = Duration::from_micros(5);
+// Offset from the highest SCHED_FIFO priority used by with_realtime_priority.
+// Stays above threaded interrupt handlers (50), but below the kernel's
+// per-CPU migration and watchdog threads (99).
+const REALTIME_PRIORITY_OFFSET: i32 = 1;
+
 /// Implements the `embedded-hal` `DelayMs` and `DelayUs` traits.
-#[derive(Debug, Default)]
-pub struct Delay;
+///
+/// Delays are implemented using a combination of [`thread::sleep`] and a
+/// busy-wait loop for the remaining time, which improves accuracy for short
+/// delays at the cost of some CPU time.
+///
+/// Every delay is extended by a small, fixed amount of overhead, caused by
+/// the function calls and reading the clock. On slower models, this overhead
+/// can be a significant part of a delay of a few microseconds, which affects
+/// the timing of bit-banged protocols. A `Delay` constructed with
+/// [`calibrate`] measures the overhead, and shortens each delay to compensate.
+///
+/// [`thread::sleep`]: https://doc.rust-lang.org/std/thread/fn.sleep.html
+/// [`calibrate`]: #method.calibrate
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Delay {
+    overhead: Duration,
+}
 
 /// `Delay` trait implementation for `embedded-hal` v1.0.0-alpha.9.
 impl Delay {
     /// Constructs a new `Delay`.
+    ///
+    /// The returned `Delay` doesn't compensate for any overhead. Use
+    /// [`calibrate`] to construct a `Delay` that does.
+    ///
+    /// [`calibrate`]: #method.calibrate
     pub fn new() -> Delay {
-        Delay {}
+        Delay::default()
     }
+
+    /// Constructs a new `Delay` that compensates for the overhead of each
+    /// delay.
+    ///
+    /// `calibrate` performs 1000 delays of 5 µs, and compares the median
+    /// duration of each delay to the median time it takes to read the clock
+    /// twice. The difference is subtracted from every subsequent delay.
+    /// Delays shorter than the measured overhead return immediately.
+    ///
+    /// Calibration takes about 10 ms, and should be performed once at
+    /// startup, preferably while the system isn't under heavy load. The
+    /// measurement depends on the current CPU frequency, so results may
+    /// vary when frequency scaling is active.
+    pub fn calibrate() -> Delay {
+        let clock = median_elapsed(|| {});
+        let delay = median_elapsed(|| spin_sleep::sleep(CALIBRATION_DELAY));
+
+        Delay {
+            overhead: delay.saturating_sub(CALIBRATION_DELAY + clock),
+        }
+    }
+
+    /// Returns the overhead that's subtracted from each delay.
+    ///
+    /// Returns a zero duration if the `Delay` wasn't constructed with
+    /// [`calibrate`].
+    ///
+    /// [`calibrate`]: #method.calibrate
+    pub fn overhead(&self) -> Duration {
+        self.overhead
+    }
+
+    fn delay(&self, duration: Duration) {
+        let duration = duration.saturating_sub(self.overhead);
+
+        if duration > Duration::from_secs(0) {
+            spin_sleep::sleep(duration);
+        }
+    }
+}
+
+// Returns the median time it takes to call f
+fn median_elapsed<F: FnMut()>(mut f: F) -> Duration {
+    let mut samples: Vec<Duration> = (0..CALIBRATION_ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+
+    samples.sort_unstable();
+
+    samples[samples.len() / 2]
 }
 
 /// `DelayMs<u8>` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::delay::DelayMs<u8> for Delay {
     fn delay_ms(&mut self, ms: u8) {
-        DelayUs::delay_ms(self, ms as u32).unwrap()
+        self.delay(Duration::from_millis(u64::from(ms)));
     }
 }
 
 /// `DelayMs<u16>` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::delay::DelayMs<u16> for Delay {
     fn delay_ms(&mut self, ms: u16) {
-        DelayUs::delay_ms(self, ms as u32).unwrap()
+        self.delay(Duration::from_millis(u64::from(ms)));
     }
 }
 
 /// `DelayMs<u32>` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::delay::DelayMs<u32> for Delay {
     fn delay_ms(&mut self, ms: u32) {
-        DelayUs::delay_ms(self, ms).unwrap()
+        self.delay(Duration::from_millis(u64::from(ms)));
     }
 }
 
 /// `DelayMs<u64>` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::delay::DelayMs<u64> for Delay {
-    fn delay_ms(&mut self, mut ms: u64) {
-        while ms > (u32::MAX as u64) {
-            ms -= u32::MAX as u64;
-            DelayUs::delay_ms(self, u32::MAX).unwrap();
-        }
-
-        DelayUs::delay_ms(self, ms as u32).unwrap()
+    fn delay_ms(&mut self, ms: u64) {
+        self.delay(Duration::from_millis(ms));
     }
 }
 
 /// `DelayUs<u8>` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::delay::DelayUs<u8> for Delay {
     fn delay_us(&mut self, us: u8) {
-        DelayUs::delay_us(self, us as u32).unwrap()
+        self.delay(Duration::from_micros(u64::from(us)));
     }
 }
 
 /// `DelayUs<u16>` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::delay::DelayUs<u16> for Delay {
     fn delay_us(&mut self, us: u16) {
-        DelayUs::delay_us(self, us as u32).unwrap()
+        self.delay(Duration::from_micros(u64::from(us)));
     }
 }
 
 /// `DelayUs` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl DelayUs for Delay {
     type Error = Infallible;
 
     fn delay_us(&mut self, us: u32) -> Result<(), Self::Error> {
-        sleep(Duration::from_micros(us.into()));
+        self.delay(Duration::from_micros(us.into()));
         Ok(())
     }
 
     fn delay_ms(&mut self, ms: u32) -> Result<(), Self::Error> {
-        sleep(Duration::from_millis(u64::from(ms)));
+        self.delay(Duration::from_millis(u64::from(ms)));
         Ok(())
     }
 }
 
 /// `DelayUs<u32>` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::delay::DelayUs<u32> for Delay {
     fn delay_us(&mut self, us: u32) {
-        DelayUs::delay_us(self, us).unwrap()
+        self.delay(Duration::from_micros(u64::from(us)));
     }
 }
 
 /// `DelayUs<u64>` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::delay::DelayUs<u64> for Delay {
-    fn delay_us(&mut self, mut us: u64) {
-        while us > (u32::MAX as u64) {
-            us -= u32::MAX as u64;
-            DelayUs::delay_us(self, u32::MAX).unwrap();
-        }
-
-        DelayUs::delay_us(self, us as u32).unwrap()
+    fn delay_us(&mut self, us: u64) {
+        self.delay(Duration::from_micros(us));
     }
 }
 
@@ -121,13 +223,110 @@ impl From<Hertz> for Duration {
     }
 }
 
+// Restores the calling thread's original scheduling policy and priority,
+// even if the closure passed to with_realtime_priority panics.
+struct SchedulerGuard {
+    policy: i32,
+    params: libc::sched_param,
+}
+
+impl Drop for SchedulerGuard {
+    fn drop(&mut self) {
+        unsafe {
+            libc::sched_setscheduler(0, self.policy, &self.params);
+        }
+    }
+}
+
+/// Runs `f` with the calling thread's scheduling policy set to `SCHED_FIFO`
+/// at a high real-time priority, and restores the original policy and
+/// priority afterwards.
+///
+/// Bit-banged protocols, such as [`BitBangI2c`] or software-based PWM, can
+/// be preempted by other processes halfway through a transaction, which
+/// stretches or corrupts the signal. A thread with a real-time scheduling
+/// policy is only preempted by threads with a higher real-time priority,
+/// such as the kernel's migration and watchdog threads. `f` runs at one
+/// priority level below the maximum, which is above the kernel's threaded
+/// interrupt handlers.
+///
+/// Changing the scheduling policy requires superuser privileges, or the
+/// `CAP_SYS_NICE` capability, which can be granted to an executable with
+/// `sudo setcap cap_sys_nice+ep <executable>`. If the policy can't be
+/// changed, `f` runs with the original policy, and a warning is emitted
+/// through the optional `log` feature.
+///
+/// A real-time thread that doesn't block starves any other threads on the
+/// same CPU core that don't have a real-time policy, including other threads
+/// in your application. Keep `f` as short as possible. By default, the
+/// kernel limits real-time threads to 950 ms of every second
+/// (`/proc/sys/kernel/sched_rt_runtime_us`), so the system remains
+/// responsive if `f` doesn't return. Interrupts and higher-priority threads
+/// can still briefly delay `f`, so this doesn't provide hard real-time
+/// guarantees.
+///
+/// ```no_run
+/// use rppal::gpio::{Gpio, Mode};
+/// use rppal::hal::with_realtime_priority;
+/// use rppal::i2c::BitBangI2c;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let gpio = Gpio::new()?;
+/// let sda = gpio.get(23)?.into_io(Mode::Input)?;
+/// let scl = gpio.get(24)?.into_io(Mode::Input)?;
+///
+/// let mut i2c = BitBangI2c::new(sda, scl);
+/// i2c.set_slave_address(0x48)?;
+///
+/// let mut buffer = [0u8; 2];
+/// with_realtime_priority(|| i2c.write_read(&[0x00], &mut buffer))?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`BitBangI2c`]: ../i2c/struct.BitBangI2c.html
+pub fn with_realtime_priority<T, F>(f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    // sched_param contains additional fields on musl, which should be zero
+    let mut params: libc::sched_param = unsafe { mem::zeroed() };
+    let policy = unsafe { libc::sched_getscheduler(0) };
+
+    let _guard = if policy >= 0 && unsafe { libc::sched_getparam(0, &mut params) } == 0 {
+        let mut realtime_params: libc::sched_param = unsafe { mem::zeroed() };
+        realtime_params.sched_priority =
+            unsafe { libc::sched_get_priority_max(libc::SCHED_FIFO) } - REALTIME_PRIORITY_OFFSET;
+
+        if unsafe { libc::sched_setscheduler(0, libc::SCHED_FIFO, &realtime_params) } == 0 {
+            Some(SchedulerGuard { policy, params })
+        } else {
+            log_warn!(
+                "Failed to set real-time scheduling policy: {}",
+                std::io::Error::last_os_error()
+            );
+
+            None
+        }
+    } else {
+        None
+    };
+
+    f()
+}
+
 /// Implements the `embedded-hal` `CountDown` trait.
+///
+/// `CountDown` is only available in `embedded-hal` v0.2.7, so `Timer` requires
+/// the `hal-0` feature.
+#[cfg(feature = "hal-0")]
 #[derive(Debug, Copy, Clone)]
 pub struct Timer {
     start: Instant,
     duration: Duration,
 }
 
+#[cfg(feature = "hal-0")]
 impl Timer {
     /// Constructs a new `Timer`.
     pub fn new() -> Self {
@@ -138,6 +337,7 @@ impl Timer {
     }
 }
 
+#[cfg(feature = "hal-0")]
 impl Default for Timer {
     fn default() -> Self {
         Timer::new()
@@ -145,6 +345,7 @@ impl Default for Timer {
 }
 
 /// `CountDown` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::timer::CountDown for Timer {
     type Time = Duration;
 
@@ -68,6 +68,13 @@
 //! device tree overlay. More details on enabling and configuring `i2c-gpio`
 //! can be found in `/boot/overlays/README`.
 //!
+//! ### Bus discovery
+//!
+//! The bus number assigned to each I2C controller isn't guaranteed to match
+//! the controller's index, especially when multiple controllers or I2C
+//! multiplexers are enabled. [`I2c::buses`] lists the available buses, and
+//! uses the device tree to identify the controller each bus belongs to.
+//!
 //! ## Transmission speed
 //!
 //! The BSC supports I2C data transfer rates up to 400 kbit/s (Fast-mode).
@@ -79,6 +86,24 @@
 //! clock frequency in hertz (Hz). Remember to reboot
 //! the Raspberry Pi afterwards.
 //!
+//! To change the clock speed without rebooting, for instance to
+//! temporarily slow down the bus for a device with long wires, use
+//! [`I2c::set_clock_speed`]. The change applies until the next reboot.
+//!
+//! ## Plain I2C and SMBus transactions
+//!
+//! Methods prefixed with `smbus_` use the SMBus protocol framing. Most of
+//! them start each transaction by sending a command byte, which usually
+//! selects the register to read or write. For instance, [`smbus_read_byte`]
+//! sends a command byte followed by a repeated START, and then reads a byte.
+//!
+//! Some simple slave devices don't use registers, and just return a byte
+//! when they're read, or accept a byte when they're written to. Use
+//! [`read_byte`] and [`write_byte_raw`] for those devices, which transfer a
+//! single byte without a command byte. Passing a command byte to a device
+//! that doesn't expect one typically results in unexpected data, or a
+//! changed device setting.
+//!
 //! ## Not supported
 //!
 //! Some I2C and SMBus features aren't fully supported by the `i2cdev` interface, the underlying driver or
@@ -93,47 +118,152 @@
 //! A possible workaround for slave devices that require clock stretching at other points during the transfer is
 //! to use a bit-banged software I2C bus by configuring the `i2c-gpio` device tree overlay as described in `/boot/overlays/README`.
 //!
+//! ## Software-based I2C
+//!
+//! Slave devices connected to GPIO pins without hardware I2C support can be
+//! accessed through [`BitBangI2c`], which generates the I2C signals in
+//! software. Because the timing depends on the scheduler and CPU load,
+//! the hardware I2C buses should be preferred whenever possible.
+//!
+//! ```no_run
+//! use rppal::gpio::{Gpio, Mode};
+//! use rppal::i2c::BitBangI2c;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let gpio = Gpio::new()?;
+//! let sda = gpio.get(23)?.into_io(Mode::Input)?;
+//! let scl = gpio.get(24)?.into_io(Mode::Input)?;
+//!
+//! let mut i2c = BitBangI2c::new(sda, scl);
+//! i2c.set_clock_speed(50_000)?;
+//! i2c.set_slave_address(0x48)?;
+//!
+//! let mut buffer = [0u8; 2];
+//! i2c.write_read(&[0x00], &mut buffer)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Slave mode
+//!
+//! The BCM283x and BCM2711 SoCs contain a BSC slave peripheral, which lets
+//! the Raspberry Pi act as an I2C slave device for another I2C master.
+//! [`I2cSlave`] configures the peripheral, and provides access to its
+//! receive and transmit FIFOs. Because the Linux drivers don't support the
+//! peripheral, its registers are accessed through `/dev/mem`, which requires
+//! superuser privileges.
+//!
+//! ## Fault injection
+//!
+//! With the optional `test-faults` feature enabled, [`set_fault_injector`]
+//! and [`set_data_corruptor`] can force transactions to fail or modify the
+//! received data, which helps exercise the error handling and retry logic of
+//! drivers built on `I2c`. Combined with the `mock` feature, this doesn't
+//! require any hardware. Without the `test-faults` feature, both methods are
+//! no-ops.
+//!
 //! ## Troubleshooting
 //!
+//! ### Not enabled
+//!
+//! If [`new`] or [`with_bus`] returns [`Error::NotEnabled`], the I2C bus
+//! device node doesn't exist. The error's `hint` field suggests which
+//! `config.txt` setting or kernel module is missing, based on the current
+//! configuration. Changes to `config.txt` only take effect after a reboot.
+//!
 //! ### Permission denied
 //!
-//! If [`new`] or [`with_bus`] returns an `io::ErrorKind::PermissionDenied`
-//! error, make sure the file permissions for `/dev/i2c-1` or `/dev/i2c-0`
-//! are correct, and the current user is a member of the `i2c` group.
+//! If [`new`] or [`with_bus`] returns [`Error::PermissionDenied`], make sure
+//! the file permissions for `/dev/i2c-1` or `/dev/i2c-0` are correct, and the
+//! current user is a member of the `i2c` group.
 //!
 //! ### Timed out
 //!
 //! Transactions return an `io::ErrorKind::TimedOut` error when their duration
 //! exceeds the timeout value. You can change the timeout using [`set_timeout`].
 //!
+//! ### Slave device not found
+//!
+//! Transactions return [`Error::DeviceNotFound`] when the slave device
+//! doesn't acknowledge its address. Check the wiring, and make sure the
+//! device is powered and configured to use the expected address.
+//!
 //! [`new`]: struct.I2c.html#method.new
+//! [`BitBangI2c`]: struct.BitBangI2c.html
+//! [`I2cSlave`]: struct.I2cSlave.html
+//! [`smbus_read_byte`]: struct.I2c.html#method.smbus_read_byte
+//! [`read_byte`]: struct.I2c.html#method.read_byte
+//! [`write_byte_raw`]: struct.I2c.html#method.write_byte_raw
+//! [`I2c::buses`]: struct.I2c.html#method.buses
+//! [`I2c::set_clock_speed`]: struct.I2c.html#method.set_clock_speed
+//! [`Error::DeviceNotFound`]: enum.Error.html#variant.DeviceNotFound
+//! [`Error::NotEnabled`]: enum.Error.html#variant.NotEnabled
+//! [`Error::PermissionDenied`]: enum.Error.html#variant.PermissionDenied
 //! [`with_bus`]: struct.I2c.html#method.with_bus
 //! [`set_timeout`]: struct.I2c.html#method.set_timeout
+//! [`set_fault_injector`]: struct.I2c.html#method.set_fault_injector
+//! [`set_data_corruptor`]: struct.I2c.html#method.set_data_corruptor
 
 #![allow(dead_code)]
 
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::error;
 use std::fmt;
 use std::fs::{File, OpenOptions};
 use std::io;
-use std::io::{Read, Write};
+use std::io::Read;
 use std::marker::PhantomData;
-use std::os::unix::io::AsRawFd;
+use std::ops::ControlFlow;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::result;
+use std::thread;
+use std::time::Duration;
 
 use libc::c_ulong;
 
+use crate::gpio::{self, Gpio, Mode};
+#[cfg(feature = "mock")]
+use crate::mock::Simulator;
+use crate::retry::RetryPolicy;
+use crate::sys::DeviceNode;
 use crate::system;
-use crate::system::{DeviceInfo, Model};
-
-#[cfg(feature = "hal")]
+use crate::system::mailbox::{self, ClockId};
+use crate::system::{DeviceInfo, Model, ProcessInfo, SoC};
+
+mod bitbang;
+mod bsc;
+mod bus;
+mod config;
+mod dispatch;
+mod fault;
+#[cfg(any(feature = "hal-0", feature = "hal-1"))]
 mod hal;
 mod ioctl;
+#[cfg(feature = "mock")]
+mod mock;
+mod recording;
+mod slave;
 
-pub use self::ioctl::Capabilities;
+use self::dispatch as sys;
+
+pub use self::bitbang::BitBangI2c;
+pub use self::bus::{BusInfo, Controller};
+pub use self::config::I2cConfig;
+pub use self::fault::Operation;
+pub use self::ioctl::{Capabilities, MessageFlags};
+pub use self::recording::{Recording, Transfer};
+pub use self::slave::I2cSlave;
 
 /// Errors that can occur when accessing the I2C peripheral.
+///
+/// [`PartialWrite`] is displayed without the error that interrupted the
+/// transfer, which is available through [`source`] instead.
+///
+/// [`PartialWrite`]: #variant.PartialWrite
+/// [`source`]: https://doc.rust-lang.org/std/error/trait.Error.html#method.source
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// I/O error.
     Io(io::Error),
@@ -157,33 +287,220 @@ pub enum Error {
     /// doesn't provide any of the common user-accessible system files
     /// that are used to identify the model and SoC.
     UnknownModel,
+    /// Slave device not found.
+    ///
+    /// The slave device at the specified address didn't acknowledge its
+    /// address, which usually means it isn't connected, isn't powered, or
+    /// is configured to use a different address.
+    ///
+    /// The Linux I2C drivers only report that an address wasn't
+    /// acknowledged, and not which part of a transaction caused the error.
+    /// The Raspberry Pi's I2C drivers use the same error code for NAKs
+    /// during the data phase, so `DeviceNotFound` is also returned when the
+    /// slave device rejects a data byte.
+    /// `I2c` addresses a single slave device per transaction, so the
+    /// included address is the one configured with [`set_slave_address`].
+    /// When a transaction involves multiple slave devices, such as an I2C
+    /// multiplexer and a device behind it, the address refers to the last
+    /// configured slave address, and any of the devices may have caused the
+    /// error.
+    ///
+    /// [`set_slave_address`]: struct.I2c.html#method.set_slave_address
+    DeviceNotFound(u16),
+    /// I2C bus not enabled.
+    ///
+    /// The I2C bus device node didn't appear before the timeout elapsed. Make
+    /// sure the I2C bus is enabled. More information can be found [here].
+    ///
+    /// [here]: index.html#i2c-buses
+    BusNotEnabled,
+    /// I2C interface not enabled.
+    ///
+    /// The I2C bus device node at `path` doesn't exist, because the I2C interface
+    /// hasn't been enabled in the firmware configuration, or the change hasn't
+    /// taken effect yet. `hint` describes how to enable it, based on the
+    /// contents of `config.txt`.
+    ///
+    /// More information can be found [here].
+    ///
+    /// [here]: index.html#not-enabled
+    NotEnabled { path: String, hint: String },
+    /// Permission denied.
+    ///
+    /// The current user doesn't have read/write access to the I2C bus device node.
+    /// `path` contains the path that couldn't be accessed, `required_group` the
+    /// group that conventionally grants access to it (`i2c`), and `hint`
+    /// describes how to resolve the issue.
+    ///
+    /// More information on possible causes for this error can be found [here].
+    ///
+    /// [here]: index.html#permission-denied
+    PermissionDenied {
+        path: String,
+        required_group: Option<&'static str>,
+        hint: String,
+    },
+    /// Device or resource busy.
+    ///
+    /// The I2C bus device node at `path` is in use. `users` contains the
+    /// processes that currently have it opened, as returned by
+    /// [`system::find_device_users`]. The list may be empty or incomplete if
+    /// the current user isn't allowed to inspect the other processes.
+    ///
+    /// [`system::find_device_users`]: ../system/fn.find_device_users.html
+    Busy {
+        path: String,
+        users: Vec<ProcessInfo>,
+    },
+    /// Partial write.
+    ///
+    /// [`write_stream`] failed after `written` bytes were accepted by the
+    /// slave device. `error` contains the error that stopped the transfer.
+    /// This allows an interrupted upload to be resumed from the correct
+    /// offset, if the slave device supports it.
+    ///
+    /// [`write_stream`]: struct.I2c.html#method.write_stream
+    PartialWrite { written: usize, error: Box<Error> },
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Error::Io(ref err) => write!(f, "I/O error: {}", err),
-            Error::InvalidSlaveAddress(address) => write!(f, "Invalid slave address: {}", address),
+            Error::InvalidSlaveAddress(address) => {
+                write!(f, "Invalid slave address: 0x{:02X}", address)
+            }
             Error::FeatureNotSupported => write!(f, "I2C/SMBus feature not supported"),
             Error::UnknownModel => write!(f, "Unknown Raspberry Pi model"),
+            Error::DeviceNotFound(address) => {
+                write!(f, "Slave device not found at address 0x{:02X}", address)
+            }
+            Error::BusNotEnabled => write!(f, "I2C bus not enabled"),
+            Error::NotEnabled { ref path, ref hint } => {
+                write!(f, "I2C not enabled: {} ({})", path, hint)
+            }
+            Error::PermissionDenied {
+                ref path, ref hint, ..
+            } => write!(f, "Permission denied: {} ({})", path, hint),
+            Error::Busy {
+                ref path,
+                ref users,
+            } => {
+                write!(f, "Device busy: {} (", path)?;
+                crate::sys::fmt_users(f, users)?;
+                write!(f, ")")
+            }
+            Error::PartialWrite { written, .. } => {
+                write!(f, "Write failed after {} bytes", written)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => err.source(),
+            Error::PartialWrite { ref error, .. } => Some(error.as_ref()),
+            _ => None,
         }
     }
 }
 
-impl error::Error for Error {}
+impl Error {
+    // Converts an I/O error that occurred while accessing path, and classifies
+    // any missing interface, permission and busy errors.
+    fn with_path(err: io::Error, path: &str) -> Error {
+        if let Some(missing) = crate::sys::not_enabled(&err, path) {
+            return Error::NotEnabled {
+                path: missing.path,
+                hint: missing.hint,
+            };
+        }
+
+        if let Some(denied) = crate::sys::permission_denied(&err, path) {
+            return Error::PermissionDenied {
+                path: denied.path,
+                required_group: denied.required_group,
+                hint: denied.hint,
+            };
+        }
+
+        match crate::sys::busy(&err, path) {
+            Some(users) => Error::Busy {
+                path: path.to_owned(),
+                users,
+            },
+            None => Error::from(crate::sys::io_error_with_path(err, path)),
+        }
+    }
+
+    // Converts an I/O error returned by an ioctl on fd, and classifies any
+    // permission errors.
+    fn with_fd(err: io::Error, fd: RawFd) -> Error {
+        match crate::sys::permission_denied_fd(&err, fd) {
+            Some(denied) => Error::PermissionDenied {
+                path: denied.path,
+                required_group: denied.required_group,
+                hint: denied.hint,
+            },
+            None => Error::from(err),
+        }
+    }
+
+    // Returns true if the error was caused by a device node that doesn't exist
+    // (yet).
+    fn is_missing_device(&self) -> bool {
+        match *self {
+            Error::NotEnabled { .. } => true,
+            Error::Io(ref err) => err.kind() == io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+}
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Error {
+        log_debug!("I/O error: {}", err);
+
         Error::Io(err)
     }
 }
 
+impl From<gpio::Error> for Error {
+    fn from(err: gpio::Error) -> Error {
+        match err {
+            gpio::Error::Io(e) => Error::Io(e),
+            gpio::Error::UnknownModel => Error::UnknownModel,
+            gpio::Error::PermissionDenied {
+                path,
+                required_group,
+                hint,
+            } => Error::PermissionDenied {
+                path,
+                required_group,
+                hint,
+            },
+            _ => Error::Io(io::Error::new(io::ErrorKind::Other, err.to_string())),
+        }
+    }
+}
+
 impl From<system::Error> for Error {
     fn from(_err: system::Error) -> Error {
         Error::UnknownModel
     }
 }
 
+// Interval between attempts to open a device node that doesn't exist yet
+const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+// Transaction timeout the kernel assigns to an I2C adapter if its driver
+// doesn't specify one
+const DEFAULT_TIMEOUT_MS: u32 = 1000;
+
+// SMBus Alert Response Address
+const ALERT_RESPONSE_ADDRESS: u16 = 0x0C;
+
 /// Result type returned from methods that can have `i2c::Error`s.
 pub type Result<T> = result::Result<T, Error>;
 
@@ -212,9 +529,18 @@ pub type Result<T> = result::Result<T, Error>;
 pub struct I2c {
     bus: u8,
     funcs: Capabilities,
-    i2cdev: File,
+    i2cdev: DeviceNode,
     addr_10bit: bool,
     address: u16,
+    // Set once the slave address has been sent to the driver, so repeated
+    // calls to set_slave_address() with the same address can be skipped.
+    address_set: bool,
+    address_map: HashMap<u16, u16>,
+    default_stop: bool,
+    retry_policy: RetryPolicy,
+    recording: RefCell<Option<Recording>>,
+    #[cfg(feature = "test-faults")]
+    faults: fault::Faults,
     // The not_sync field is a workaround to force !Sync. I2c isn't safe for
     // Sync because of ioctl() and the underlying drivers. This avoids needing
     // #![feature(optin_builtin_traits)] to manually add impl !Sync for I2c.
@@ -231,18 +557,67 @@ impl I2c {
     ///
     /// [here]: index.html#i2c-buses
     pub fn new() -> Result<I2c> {
-        match DeviceInfo::new()?.model() {
+        I2c::with_default_bus(&DeviceInfo::new()?.model(), I2c::with_bus)
+    }
+
+    // Opens the bus that's bound to physical pins 3 and 5 on model with open
+    fn with_default_bus<F>(model: &Model, open: F) -> Result<I2c>
+    where
+        F: Fn(u8) -> Result<I2c>,
+    {
+        match *model {
             // Pi B Rev 1 uses I2C0
-            Model::RaspberryPiBRev1 => I2c::with_bus(0),
+            Model::RaspberryPiBRev1 => open(0),
             Model::RaspberryPi4B | Model::RaspberryPi400 => {
-                // Pi 4B/400 could have I2C3 enabled on pins 3 and 5
-                I2c::with_bus(1).or_else(|_| I2c::with_bus(3))
+                // Pi 4B/400 could have I2C3 enabled on pins 3 and 5. If neither
+                // bus exists, report bus 1, which is the one usually enabled.
+                open(1).or_else(|err| match open(3) {
+                    Err(ref e) if e.is_missing_device() => Err(err),
+                    result => result,
+                })
             }
             // Everything else should be using I2C1
-            _ => I2c::with_bus(1),
+            _ => open(1),
         }
     }
 
+    /// Constructs a new `I2c`, waiting for the I2C bus device node to appear.
+    ///
+    /// `new_with_retry` works similarly to [`new`], but keeps polling for
+    /// the device node until it exists, or until the `timeout` duration
+    /// elapses. This can be useful when your application is started during
+    /// boot, before the I2C kernel modules have finished creating the device
+    /// nodes.
+    ///
+    /// If the device node doesn't appear before the timeout elapses,
+    /// `new_with_retry` returns [`Error::BusNotEnabled`].
+    ///
+    /// [`new`]: #method.new
+    /// [`Error::BusNotEnabled`]: enum.Error.html#variant.BusNotEnabled
+    pub fn new_with_retry(timeout: Duration) -> Result<I2c> {
+        I2c::new_with_retry_policy(RetryPolicy::with_timeout(timeout, RETRY_INTERVAL))
+    }
+
+    /// Constructs a new `I2c`, retrying according to `policy` while the I2C
+    /// bus device node doesn't exist.
+    ///
+    /// If the device node still doesn't exist after the last attempt,
+    /// `new_with_retry_policy` returns [`Error::BusNotEnabled`]. Any other
+    /// error is returned immediately.
+    ///
+    /// [`Error::BusNotEnabled`]: enum.Error.html#variant.BusNotEnabled
+    pub fn new_with_retry_policy(policy: RetryPolicy) -> Result<I2c> {
+        policy
+            .retry(I2c::new, Error::is_missing_device)
+            .map_err(|err| {
+                if err.is_missing_device() {
+                    Error::BusNotEnabled
+                } else {
+                    err
+                }
+            })
+    }
+
     /// Constructs a new `I2c` using the specified bus.
     ///
     /// `bus` indicates the selected I2C bus. You'll typically want to select the
@@ -257,33 +632,122 @@ impl I2c {
     pub fn with_bus(bus: u8) -> Result<I2c> {
         // bus is a u8, because any 8-bit bus ID could potentially
         // be configured for bit banging I2C using i2c-gpio.
+        let path = format!("/dev/i2c-{}", bus);
+
         let i2cdev = OpenOptions::new()
             .read(true)
             .write(true)
-            .open(format!("/dev/i2c-{}", bus))?;
+            .open(&path)
+            .map_err(|e| Error::with_path(e, &path))?;
 
-        let capabilities = ioctl::funcs(i2cdev.as_raw_fd())?;
+        I2c::with_device(bus, DeviceNode::File(i2cdev))
+    }
+
+    /// Constructs a new `I2c` connected to the simulated devices attached to
+    /// `simulator`, instead of the I2C bus device nodes.
+    ///
+    /// `with_simulator` selects the bus based on the simulated model, the
+    /// same way [`new`] does on a Raspberry Pi. The bus needs to be enabled
+    /// on `simulator` first, for instance by adding a device to it.
+    ///
+    /// Only available when the optional `mock` feature is enabled.
+    ///
+    /// [`new`]: #method.new
+    #[cfg(feature = "mock")]
+    pub fn with_simulator(simulator: &Simulator) -> Result<I2c> {
+        I2c::with_default_bus(&simulator.model(), |bus| {
+            I2c::with_simulator_bus(simulator, bus)
+        })
+    }
+
+    /// Constructs a new `I2c` connected to the simulated devices attached to
+    /// the specified bus on `simulator`.
+    ///
+    /// Only available when the optional `mock` feature is enabled.
+    #[cfg(feature = "mock")]
+    pub fn with_simulator_bus(simulator: &Simulator, bus: u8) -> Result<I2c> {
+        I2c::with_device(bus, DeviceNode::Simulated(mock::open(simulator, bus)?))
+    }
+
+    // Finishes constructing an I2c for an opened device node
+    fn with_device(bus: u8, i2cdev: DeviceNode) -> Result<I2c> {
+        let capabilities =
+            sys::funcs(&i2cdev).map_err(|e| Error::with_fd(e, i2cdev.as_raw_fd()))?;
 
         // Disable 10-bit addressing if it's supported
         if capabilities.addr_10bit() {
-            ioctl::set_addr_10bit(i2cdev.as_raw_fd(), 0)?;
+            sys::set_addr_10bit(&i2cdev, 0).map_err(|e| Error::with_fd(e, i2cdev.as_raw_fd()))?;
         }
 
         // Disable PEC if it's supported
         if capabilities.smbus_pec() {
-            ioctl::set_pec(i2cdev.as_raw_fd(), 0)?;
+            sys::set_pec(&i2cdev, 0).map_err(|e| Error::with_fd(e, i2cdev.as_raw_fd()))?;
         }
 
+        log_debug!("Opened I2C bus {} ({:?})", bus, capabilities);
+
         Ok(I2c {
             bus,
             funcs: capabilities,
             i2cdev,
             addr_10bit: false,
             address: 0,
+            address_set: false,
+            address_map: HashMap::new(),
+            default_stop: false,
+            retry_policy: RetryPolicy::none(),
+            recording: RefCell::new(None),
+            #[cfg(feature = "test-faults")]
+            faults: fault::Faults::default(),
             not_sync: PhantomData,
         })
     }
 
+    /// Returns a list of the available I2C buses, sorted by bus number.
+    ///
+    /// Bus numbers are assigned by the kernel, and depend on the model, the
+    /// kernel version and the enabled overlays. `buses` looks up each
+    /// `/dev/i2c-*` device node, and uses the device tree aliases in
+    /// `/proc/device-tree/aliases` to determine which hardware I2C controller
+    /// it belongs to. Buses provided by I2C multiplexers are reported as
+    /// multiplexer channels, together with the bus they're connected to.
+    ///
+    /// If the `i2c-dev` kernel module isn't loaded, `buses` returns an empty
+    /// list.
+    ///
+    /// ```no_run
+    /// use rppal::i2c::{Controller, I2c};
+    ///
+    /// # fn main() -> rppal::i2c::Result<()> {
+    /// // Find the bus that belongs to I2C3, regardless of its bus number.
+    /// let bus = I2c::buses()?
+    ///     .into_iter()
+    ///     .find(|info| info.controller() == Controller::Hardware(3));
+    ///
+    /// if let Some(bus) = bus {
+    ///     let i2c = I2c::with_bus(bus.bus())?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn buses() -> Result<Vec<BusInfo>> {
+        Ok(bus::discover()?)
+    }
+
+    /// Constructs a new `I2c` using the settings in `config`.
+    ///
+    /// `from_config` opens the bus specified by `config.bus` with [`with_bus`],
+    /// and then sets the slave address with [`set_slave_address`].
+    ///
+    /// [`with_bus`]: #method.with_bus
+    /// [`set_slave_address`]: #method.set_slave_address
+    pub fn from_config(config: &I2cConfig) -> Result<I2c> {
+        let mut i2c = I2c::with_bus(config.bus)?;
+        i2c.set_slave_address(config.slave_address)?;
+
+        Ok(i2c)
+    }
+
     /// Returns information on the functionality supported by the underlying drivers.
     ///
     /// The returned [`Capabilities`] instance lists the available
@@ -294,13 +758,73 @@ impl I2c {
         self.funcs
     }
 
+    /// Returns `true` if SMBus protocols are emulated by the kernel using
+    /// plain I2C transactions.
+    ///
+    /// Most I2C bus drivers, including the Raspberry Pi's BSC driver, only
+    /// support plain I2C transactions. For those buses, the kernel emulates
+    /// the SMBus protocols by translating them into one or two I2C messages.
+    /// Dedicated SMBus controllers offer native SMBus support, but usually
+    /// can't send arbitrary I2C transactions. `is_emulated_smbus` is based
+    /// on the `I2C_FUNCS` flags, and assumes SMBus protocols are emulated
+    /// whenever plain I2C transactions are supported.
+    ///
+    /// Known quirks of the SMBus emulation:
+    ///
+    /// * SMBus Block Read and Block Process Call require the bus driver to
+    ///   support reading a length byte at the start of the incoming data,
+    ///   which isn't supported by the BSC driver. [`smbus_block_read`] returns
+    ///   [`Error::FeatureNotSupported`] in that case. Use [`block_read`] or
+    ///   [`write_read`] with a fixed length instead.
+    /// * SMBus Quick Command is sent as a zero-length message, which isn't
+    ///   supported by all bus drivers.
+    /// * The PEC byte is calculated and verified in software. Slave devices
+    ///   that send more or fewer bytes than expected cause PEC errors rather
+    ///   than a protocol error.
+    /// * SMBus timeouts aren't enforced by the bus driver. Slave devices that
+    ///   stretch the clock are only limited by the timeout configured with
+    ///   [`set_timeout`].
+    ///
+    /// For devices that are sensitive to any of these quirks, plain I2C
+    /// transactions through [`read`], [`write`] and [`write_read`] offer
+    /// the most predictable results.
+    ///
+    /// [`smbus_block_read`]: #method.smbus_block_read
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    /// [`block_read`]: #method.block_read
+    /// [`write_read`]: #method.write_read
+    /// [`set_timeout`]: #method.set_timeout
+    /// [`read`]: #method.read
+    /// [`write`]: #method.write
+    pub fn is_emulated_smbus(&self) -> Result<bool> {
+        let capabilities =
+            sys::funcs(&self.i2cdev).map_err(|e| Error::with_fd(e, self.i2cdev.as_raw_fd()))?;
+
+        Ok(capabilities.i2c())
+    }
+
     /// Returns the I2C bus ID.
     pub fn bus(&self) -> u8 {
         self.bus
     }
 
     /// Returns the clock frequency in hertz (Hz).
+    ///
+    /// For buses that belong to a Broadcom Serial Controller (BSC), the
+    /// frequency is calculated based on the controller's clock divider and
+    /// the current core clock frequency, which includes any changes made with
+    /// [`set_clock_speed`]. Reading the clock divider requires access to
+    /// `/dev/mem`, and retrieving the core clock frequency requires access to
+    /// `/dev/vcio`. If either isn't accessible, or the bus doesn't belong to
+    /// a BSC, `clock_speed` returns the frequency configured in the device
+    /// tree.
+    ///
+    /// [`set_clock_speed`]: #method.set_clock_speed
     pub fn clock_speed(&self) -> Result<u32> {
+        if let Ok(clock_speed) = self.bsc_clock_speed() {
+            return Ok(clock_speed);
+        }
+
         let mut buffer = [0u8; 4];
 
         File::open(format!(
@@ -315,6 +839,185 @@ impl I2c {
             | (u32::from(buffer[0]) << 24))
     }
 
+    /// Changes the clock frequency of a hardware I2C bus, and returns the
+    /// frequency in hertz (Hz) that's actually used.
+    ///
+    /// The Linux I2C drivers don't support changing the clock frequency at
+    /// runtime, so `set_clock_speed` writes the clock divider of the
+    /// Broadcom Serial Controller (BSC) directly through `/dev/mem`, which
+    /// requires superuser privileges. The divider is calculated based on the
+    /// current core clock frequency reported by the VideoCore firmware, and
+    /// is rounded so the bus never runs faster than `clock_speed`. Because
+    /// the BSC is clocked by the core clock, the bus frequency changes along
+    /// with it if the core clock frequency isn't fixed.
+    ///
+    /// The new frequency applies to every process using the bus, and remains
+    /// in effect until the Raspberry Pi is rebooted or the I2C driver is
+    /// reloaded. [`clock_speed`] returns the new frequency as long as it can
+    /// access `/dev/mem`. To change the frequency permanently, set
+    /// `dtparam=i2c_arm_baudrate` in `/boot/config.txt` as described
+    /// [here].
+    ///
+    /// Only change the frequency while the bus is idle. Any transaction
+    /// that's in progress, including those started by other processes, may
+    /// be corrupted. The BSC supports frequencies up to 400 kHz.
+    ///
+    /// Buses that don't belong to a hardware I2C controller, the BSC2
+    /// controller reserved for HDMI, and the Raspberry Pi 5's DesignWare I2C
+    /// controllers aren't supported, and return
+    /// `Err(`[`Error::FeatureNotSupported`]`)`.
+    ///
+    /// [`clock_speed`]: #method.clock_speed
+    /// [here]: index.html#transmission-speed
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    pub fn set_clock_speed(&mut self, clock_speed: u32) -> Result<u32> {
+        if clock_speed == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "clock speed must be higher than 0",
+            )));
+        }
+
+        let address = self.bsc_address()?;
+        let core_clock = core_clock()?;
+
+        let divider = bsc::divider(core_clock, clock_speed);
+        bsc::set_divider(address, divider)?;
+
+        log_debug!(
+            "Set I2C bus {} clock speed to {} Hz (divider {})",
+            self.bus,
+            core_clock / divider,
+            divider
+        );
+
+        Ok(core_clock / divider)
+    }
+
+    // Calculates the clock frequency based on the BSC's clock divider.
+    fn bsc_clock_speed(&self) -> Result<u32> {
+        let address = self.bsc_address()?;
+        let core_clock = core_clock()?;
+
+        Ok(core_clock / bsc::read_divider(address)?)
+    }
+
+    // Returns the physical address of the BSC registers for the bus.
+    fn bsc_address(&self) -> Result<u64> {
+        let device_info = DeviceInfo::new()?;
+        let offset = bsc::offset(device_info.soc(), self.controller_index()?)
+            .ok_or(Error::FeatureNotSupported)?;
+
+        Ok(device_info.peripheral_base() + offset)
+    }
+
+    // Returns the index of the hardware I2C controller the bus belongs to.
+    fn controller_index(&self) -> Result<u8> {
+        let controller = I2c::buses()?
+            .into_iter()
+            .find(|info| info.bus() == self.bus)
+            .map(|info| info.controller());
+
+        match controller {
+            Some(Controller::Hardware(index)) => Ok(index),
+            // Without a device tree alias, assume the bus number matches the controller
+            None => Ok(self.bus),
+            Some(_) => Err(Error::FeatureNotSupported),
+        }
+    }
+
+    /// Attempts to free a slave device that's holding SDA low.
+    ///
+    /// When a slave device is reset or interrupted in the middle of a
+    /// transaction, it may keep SDA low while it waits for clock pulses that
+    /// never arrive. This prevents the I2C controller from sending a START
+    /// condition, and every subsequent transaction fails or times out until
+    /// the slave device is power cycled.
+    ///
+    /// `recover_bus` temporarily switches the SDA and SCL pins from their
+    /// I2C alternate function to GPIO, clocks out up to 9 pulses on SCL
+    /// until the slave device releases SDA, sends a STOP condition, and then
+    /// hands the pins back to the I2C controller.
+    ///
+    /// The pins are determined based on the hardware I2C controller the bus
+    /// belongs to, using the default pins listed [here]. If the controller
+    /// has been configured to use alternative pins, use
+    /// [`recover_bus_with_pins`] instead. Buses that don't belong to a
+    /// hardware I2C controller, such as multiplexer channels or `i2c-gpio`
+    /// buses, aren't supported, and return [`Error::FeatureNotSupported`].
+    ///
+    /// Only call `recover_bus` after you've detected the bus is stuck, for
+    /// instance when transactions keep timing out. While the pins are
+    /// reconfigured, the I2C controller is disconnected from the bus, so any
+    /// transactions started by other processes fail or produce corrupted
+    /// data. The clock pulses may also cause slave devices that weren't stuck
+    /// to misinterpret the bus state. If SDA is still held low after 9
+    /// pulses, an `io::ErrorKind::Other` error is returned, and the slave
+    /// device needs to be power cycled.
+    ///
+    /// [here]: index.html#i2c-buses
+    /// [`recover_bus_with_pins`]: #method.recover_bus_with_pins
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    pub fn recover_bus(&mut self) -> Result<()> {
+        let index = self.controller_index()?;
+
+        let (sda, scl) = if DeviceInfo::new()?.soc() == SoC::Bcm2712 {
+            match index {
+                0 => (0, 1),
+                1 => (2, 3),
+                2 => (4, 5),
+                3 => (6, 7),
+                _ => return Err(Error::FeatureNotSupported),
+            }
+        } else {
+            match index {
+                0 => (0, 1),
+                1 => (2, 3),
+                3 => (4, 5),
+                4 => (8, 9),
+                5 => (12, 13),
+                6 => (22, 23),
+                _ => return Err(Error::FeatureNotSupported),
+            }
+        };
+
+        self.recover_bus_with_pins(sda, scl)
+    }
+
+    /// Attempts to free a slave device that's holding SDA low, using the
+    /// specified BCM GPIO pin numbers for SDA and SCL.
+    ///
+    /// `recover_bus_with_pins` works similarly to [`recover_bus`], but can be
+    /// used when the I2C controller has been configured to use alternative
+    /// pins. To avoid interfering with pins that are used for other
+    /// purposes, [`Error::FeatureNotSupported`] is returned if either pin
+    /// isn't currently set to an alternate function.
+    ///
+    /// [`recover_bus`]: #method.recover_bus
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    pub fn recover_bus_with_pins(&mut self, sda: u8, scl: u8) -> Result<()> {
+        let gpio = Gpio::new()?;
+        let sda = gpio.get(sda)?;
+        let scl = gpio.get(scl)?;
+
+        if matches!(sda.mode(), Mode::Input | Mode::Output | Mode::Null)
+            || matches!(scl.mode(), Mode::Input | Mode::Output | Mode::Null)
+        {
+            return Err(Error::FeatureNotSupported);
+        }
+
+        log_debug!(
+            "Recovering I2C bus {} (SDA: GPIO {}, SCL: GPIO {})",
+            self.bus,
+            sda.pin(),
+            scl.pin()
+        );
+
+        // The pins switch back to their original alternate function when
+        // they're dropped
+        BitBangI2c::new(sda.into_io(Mode::Input)?, scl.into_io(Mode::Input)?).recover()
+    }
+
     /// Sets a 7-bit or 10-bit slave address.
     ///
     /// `slave_address` refers to the slave device you're communicating with.
@@ -326,22 +1029,103 @@ impl I2c {
     /// address when 10-bit addressing is enabled won't correctly target a
     /// slave device that doesn't support 10-bit addresses.
     ///
+    /// If an address map has been configured with [`set_address_map`],
+    /// `slave_address` is treated as a logical address, and replaced by the
+    /// physical address it maps to.
+    ///
+    /// If the same slave address is already active, the address is only
+    /// validated, and not sent to the driver again.
+    ///
     /// [`set_addr_10bit`]: #method.set_addr_10bit
+    /// [`set_address_map`]: #method.set_address_map
     pub fn set_slave_address(&mut self, slave_address: u16) -> Result<()> {
+        let slave_address = *self
+            .address_map
+            .get(&slave_address)
+            .unwrap_or(&slave_address);
+
         // Filter out invalid and unsupported addresses
         if (!self.addr_10bit && ((slave_address >> 3) == 0b1111 || slave_address > 0x7F))
             || (self.addr_10bit && slave_address > 0x03FF)
         {
+            log_debug!("Invalid slave address 0x{:02X}", slave_address);
+
             return Err(Error::InvalidSlaveAddress(slave_address));
         }
 
-        ioctl::set_slave_address(self.i2cdev.as_raw_fd(), c_ulong::from(slave_address))?;
+        // The driver keeps the slave address until it's changed
+        if self.address_set && slave_address == self.address {
+            return Ok(());
+        }
+
+        sys::set_slave_address(&self.i2cdev, c_ulong::from(slave_address))
+            .map_err(|e| Error::with_fd(e, self.i2cdev.as_raw_fd()))?;
+
+        log_debug!("Slave address set to 0x{:02X}", slave_address);
 
         self.address = slave_address;
+        self.address_set = true;
 
         Ok(())
     }
 
+    /// Returns the address map.
+    pub fn address_map(&self) -> &HashMap<u16, u16> {
+        &self.address_map
+    }
+
+    /// Sets an address map, which translates logical slave addresses to
+    /// physical slave addresses.
+    ///
+    /// `address_map` allows the same driver code to support multiple
+    /// hardware revisions where a slave device has been moved to a different
+    /// address. Each key is a logical address, as passed to
+    /// [`set_slave_address`], and each value is the physical address that's
+    /// used on the bus instead. Logical addresses that aren't present in the
+    /// map are used as is.
+    ///
+    /// The address map is only applied by [`set_slave_address`], and doesn't
+    /// affect the currently selected slave device. Call [`set_slave_address`]
+    /// again after changing the address map.
+    ///
+    /// Any addresses reported back, such as the address included in
+    /// [`Error::DeviceNotFound`], or addresses shown by bus scanning tools like
+    /// `i2cdetect`, are physical addresses, not logical ones.
+    ///
+    /// By default, the address map is empty.
+    ///
+    /// [`set_slave_address`]: #method.set_slave_address
+    /// [`Error::DeviceNotFound`]: enum.Error.html#variant.DeviceNotFound
+    pub fn set_address_map(&mut self, address_map: HashMap<u16, u16>) {
+        self.address_map = address_map;
+    }
+
+    /// Returns the retry policy.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    /// Sets the retry policy applied to transactions.
+    ///
+    /// Transactions that fail because the slave device didn't acknowledge
+    /// its address or data (`ENXIO`, `EREMOTEIO`), the bus was in use by
+    /// another bus master (`EAGAIN`), or the transaction timed out
+    /// (`ETIMEDOUT`) are retried according to `retry_policy`. This includes
+    /// devices that temporarily stop responding while they're busy, such as
+    /// EEPROMs during a write cycle. Other errors are returned immediately.
+    ///
+    /// Each retry repeats the complete transaction. Methods that send
+    /// multiple transactions, such as [`write_stream`], retry each
+    /// transaction separately.
+    ///
+    /// By default, `retry_policy` is set to [`RetryPolicy::none`].
+    ///
+    /// [`write_stream`]: #method.write_stream
+    /// [`RetryPolicy::none`]: ../retry/struct.RetryPolicy.html#method.none
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
     /// Sets the maximum duration of a transaction in milliseconds (ms).
     ///
     /// Transactions that take longer than `timeout` return an
@@ -351,14 +1135,130 @@ impl I2c {
     pub fn set_timeout(&self, timeout: u32) -> Result<()> {
         // Contrary to the i2cdev documentation, this seems to
         // be used as a timeout for (part of?) the I2C transaction.
-        ioctl::set_timeout(self.i2cdev.as_raw_fd(), timeout as c_ulong)?;
+        sys::set_timeout(&self.i2cdev, timeout as c_ulong)
+            .map_err(|e| Error::with_fd(e, self.i2cdev.as_raw_fd()))?;
+
+        log_debug!("Timeout set to {} ms", timeout);
+
+        Ok(())
+    }
+
+    /// Returns `true` if a STOP condition is sent in between the messages of
+    /// multi-message transactions.
+    pub fn default_stop(&self) -> bool {
+        self.default_stop
+    }
+
+    /// Configures whether a STOP condition is sent in between the messages of
+    /// multi-message transactions.
+    ///
+    /// By default, the convenience methods that send multiple messages in a
+    /// single transaction separate those messages with a repeated START, and
+    /// hold on to the bus until the final STOP. Some slave devices only
+    /// process a message once they receive a STOP, while others reset their
+    /// register pointer when they do. If `stop` is set to `true`, every
+    /// message is terminated with a STOP, followed by a new START for the
+    /// next message.
+    ///
+    /// `default_stop` is honored by [`write_read`], [`write_registers`],
+    /// [`init_sequence`], and the plain I2C fallback of [`read_word_auto`].
+    /// Single-message methods such as [`read`] and [`write`] always end with
+    /// a STOP. The SMBus methods and [`block_read`] and [`block_write`]
+    /// follow the sequence defined by their respective protocol, and aren't
+    /// affected.
+    ///
+    /// The `i2cdev` interface always ends a transaction with a STOP, so the
+    /// bus can't be held in between separate method calls.
+    ///
+    /// By default, `default_stop` is set to `false`.
+    ///
+    /// [`write_read`]: #method.write_read
+    /// [`write_registers`]: #method.write_registers
+    /// [`init_sequence`]: #method.init_sequence
+    /// [`read_word_auto`]: #method.read_word_auto
+    /// [`read`]: #method.read
+    /// [`write`]: #method.write
+    /// [`block_read`]: #method.block_read
+    /// [`block_write`]: #method.block_write
+    pub fn set_default_stop(&mut self, stop: bool) {
+        self.default_stop = stop;
+    }
+
+    /// Restores the bus configuration to its default state.
+    ///
+    /// `reset` disables SMBus Packet Error Checking and 10-bit addressing if
+    /// they're supported, sets the transaction timeout to the kernel's
+    /// default value of 1 second, disables retries, and sets
+    /// [`default_stop`] to `false`. This provides a known starting point
+    /// after an error, or before handing the bus over to other code.
+    ///
+    /// The driver doesn't offer a way to deselect the slave device, so the
+    /// currently selected slave address remains active in the kernel.
+    /// However, `reset` clears the cached address, which means the next call
+    /// to [`set_slave_address`] is always sent to the driver, even if the
+    /// address hasn't changed. The address map, any active recording, and
+    /// any test fault callbacks are left unchanged.
+    ///
+    /// [`default_stop`]: #method.default_stop
+    /// [`set_slave_address`]: #method.set_slave_address
+    pub fn reset(&mut self) -> Result<()> {
+        if self.funcs.addr_10bit() {
+            sys::set_addr_10bit(&self.i2cdev, 0)?;
+        }
+
+        if self.funcs.smbus_pec() {
+            sys::set_pec(&self.i2cdev, 0)?;
+        }
+
+        self.set_timeout(DEFAULT_TIMEOUT_MS)?;
+        self.set_retries(0)?;
+
+        self.addr_10bit = false;
+        self.address = 0;
+        self.address_set = false;
+        self.default_stop = false;
+
+        log_debug!("I2C bus {} reset to defaults", self.bus);
 
         Ok(())
     }
 
+    /// Starts recording all transfers to and from the slave devices.
+    ///
+    /// While recording, every successful transaction is stored as the
+    /// sequence of plain I2C transfers it consists of, including the slave
+    /// address and the exact bytes sent and received. This makes it possible
+    /// to capture the traffic of a driver on real hardware, save it with
+    /// [`Recording::save`], and replay it in tests with the optional `mock`
+    /// feature through [`Simulator::replay_i2c`].
+    ///
+    /// Any transfers recorded since a previous call to `start_recording` are
+    /// discarded.
+    ///
+    /// [`Recording::save`]: struct.Recording.html#method.save
+    /// [`Simulator::replay_i2c`]: ../mock/struct.Simulator.html#method.replay_i2c
+    pub fn start_recording(&mut self) {
+        *self.recording.borrow_mut() = Some(Recording::new());
+    }
+
+    /// Stops recording, and returns the recorded transfers.
+    ///
+    /// Returns `None` if [`start_recording`] wasn't called.
+    ///
+    /// [`start_recording`]: #method.start_recording
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.borrow_mut().take()
+    }
+
+    /// Returns `true` if transfers are currently being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recording.borrow().is_some()
+    }
+
     fn set_retries(&self, retries: u32) -> Result<()> {
         // Set to private. While i2cdev implements retries, the underlying drivers don't.
-        ioctl::set_retries(self.i2cdev.as_raw_fd(), retries as c_ulong)?;
+        sys::set_retries(&self.i2cdev, retries as c_ulong)
+            .map_err(|e| Error::with_fd(e, self.i2cdev.as_raw_fd()))?;
 
         Ok(())
     }
@@ -376,9 +1276,14 @@ impl I2c {
             return Err(Error::FeatureNotSupported);
         }
 
-        ioctl::set_addr_10bit(self.i2cdev.as_raw_fd(), addr_10bit as c_ulong)?;
+        sys::set_addr_10bit(&self.i2cdev, addr_10bit as c_ulong)
+            .map_err(|e| Error::with_fd(e, self.i2cdev.as_raw_fd()))?;
 
         self.addr_10bit = addr_10bit;
+        // The address needs to be sent again in the new format
+        self.address_set = false;
+
+        log_debug!("10-bit addressing set to {}", addr_10bit);
 
         Ok(())
     }
@@ -391,7 +1296,20 @@ impl I2c {
     ///
     /// Returns how many bytes were read.
     pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
-        Ok(self.i2cdev.read(buffer)?)
+        self.inject_fault(Operation::Read)?;
+
+        let result = self.retry_policy.retry(
+            || retry_interrupted!(sys::read(&self.i2cdev, buffer)),
+            is_transient,
+        );
+
+        let length = result.map_err(|e| self.transaction_error(e))?;
+
+        self.corrupt_data(Operation::Read, &mut buffer[..length]);
+        self.log_transaction("read", &[], &buffer[..length]);
+        self.record_read(&[&buffer[..length]]);
+
+        Ok(length)
     }
 
     /// Sends the outgoing data contained in `buffer` to the slave device.
@@ -400,7 +1318,217 @@ impl I2c {
     ///
     /// Returns how many bytes were written.
     pub fn write(&mut self, buffer: &[u8]) -> Result<usize> {
-        Ok(self.i2cdev.write(buffer)?)
+        self.inject_fault(Operation::Write)?;
+
+        let result = self.retry_policy.retry(
+            || retry_interrupted!(sys::write(&self.i2cdev, buffer)),
+            is_transient,
+        );
+
+        let length = result.map_err(|e| self.transaction_error(e))?;
+
+        self.log_transaction("write", &buffer[..length], &[]);
+        self.record_write(&[&buffer[..length]]);
+
+        Ok(length)
+    }
+
+    /// Receives a single byte from the slave device, without sending a
+    /// command byte first.
+    ///
+    /// `read_byte` is intended for simple slave devices that don't have any
+    /// registers, and just return a value when they're read. Unlike
+    /// [`smbus_read_byte`], which sends a command byte to select a register
+    /// before reading, `read_byte` only addresses the slave device.
+    ///
+    /// Sequence: START → Address + Read Bit → Incoming Byte → STOP
+    ///
+    /// [`smbus_read_byte`]: #method.smbus_read_byte
+    pub fn read_byte(&mut self) -> Result<u8> {
+        let mut buffer = [0u8; 1];
+
+        if self.read(&mut buffer)? == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "no data received",
+            )));
+        }
+
+        Ok(buffer[0])
+    }
+
+    /// Sends a single byte to the slave device, without sending a command
+    /// byte first.
+    ///
+    /// `write_byte_raw` is intended for simple slave devices that don't have
+    /// any registers, and accept a single value when they're written to.
+    /// Unlike [`smbus_write_byte`], which sends a command byte to select a
+    /// register before the value, `write_byte_raw` only sends `value`.
+    ///
+    /// Sequence: START → Address + Write Bit → Outgoing Byte → STOP
+    ///
+    /// [`smbus_write_byte`]: #method.smbus_write_byte
+    pub fn write_byte_raw(&mut self, value: u8) -> Result<()> {
+        if self.write(&[value])? == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "no data sent",
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Sends the outgoing data contained in `buffer` to the slave device in
+    /// blocks of `chunk_size` bytes, waiting for `delay` in between blocks.
+    ///
+    /// Some slave devices, such as certain e-ink display controllers, need
+    /// time to process a block of data before they can accept the next one,
+    /// and drop any bytes received in the meantime. `write_chunked_delayed`
+    /// sends each block as a separate [`write`] transaction, and doesn't wait
+    /// after the last block.
+    ///
+    /// If the slave device accepts fewer bytes than requested for a block,
+    /// `write_chunked_delayed` stops sending any remaining blocks.
+    ///
+    /// Sequence per block: START → Address + Write Bit → Outgoing Bytes → STOP
+    ///
+    /// Returns how many bytes were written in total. Returns an
+    /// [`Error::Io`] with kind `InvalidInput` if `chunk_size` is 0.
+    ///
+    /// [`write`]: #method.write
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    pub fn write_chunked_delayed(
+        &mut self,
+        buffer: &[u8],
+        chunk_size: usize,
+        delay: Duration,
+    ) -> Result<usize> {
+        if chunk_size == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "chunk size must be greater than 0",
+            )));
+        }
+
+        let mut written = 0;
+
+        for (idx, chunk) in buffer.chunks(chunk_size).enumerate() {
+            if idx > 0 && delay > Duration::ZERO {
+                thread::sleep(delay);
+            }
+
+            let length = self.write(chunk)?;
+            written += length;
+
+            if length < chunk.len() {
+                break;
+            }
+        }
+
+        Ok(written)
+    }
+
+    /// Sends the outgoing data contained in `buffer` to the slave device in
+    /// blocks of `chunk_size` bytes, reporting progress after each block.
+    ///
+    /// `write_stream` is intended for long-running transfers, such as
+    /// uploading firmware to a co-processor. After each block, `progress` is
+    /// called with the total number of bytes written so far. Returning
+    /// `ControlFlow::Break(())` cancels the transfer before the next block is
+    /// sent. Each block is sent as a separate [`write`] transaction, so a
+    /// cancelled transfer always ends on a block boundary.
+    ///
+    /// If the slave device accepts fewer bytes than requested for a block,
+    /// `write_stream` stops sending any remaining blocks.
+    ///
+    /// Sequence per block: START → Address + Write Bit → Outgoing Bytes → STOP
+    ///
+    /// Returns how many bytes were written in total. If a block fails after
+    /// at least one byte was written, `write_stream` returns
+    /// [`Error::PartialWrite`], which contains the number of bytes written
+    /// and the original error. Returns an [`Error::Io`] with kind
+    /// `InvalidInput` if `chunk_size` is 0.
+    ///
+    /// ```no_run
+    /// use std::ops::ControlFlow;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// use rppal::i2c::{Error, I2c};
+    ///
+    /// # fn main() -> rppal::i2c::Result<()> {
+    /// # let firmware = [0u8; 4096];
+    /// # static CANCEL: AtomicBool = AtomicBool::new(false);
+    /// let mut i2c = I2c::new()?;
+    /// i2c.set_slave_address(0x42)?;
+    ///
+    /// let result = i2c.write_stream(&firmware, 32, |written| {
+    ///     println!("{}/{} bytes", written, firmware.len());
+    ///
+    ///     if CANCEL.load(Ordering::Relaxed) {
+    ///         ControlFlow::Break(())
+    ///     } else {
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// });
+    ///
+    /// match result {
+    ///     Ok(written) if written == firmware.len() => println!("Upload complete"),
+    ///     Ok(written) => println!("Upload stopped after {} bytes", written),
+    ///     Err(Error::PartialWrite { written, error }) => {
+    ///         println!("Upload failed after {} bytes: {}", written, error)
+    ///     }
+    ///     Err(err) => return Err(err),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`write`]: #method.write
+    /// [`Error::PartialWrite`]: enum.Error.html#variant.PartialWrite
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    pub fn write_stream<F>(
+        &mut self,
+        buffer: &[u8],
+        chunk_size: usize,
+        mut progress: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(usize) -> ControlFlow<()>,
+    {
+        if chunk_size == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "chunk size must be greater than 0",
+            )));
+        }
+
+        let mut written = 0;
+
+        for chunk in buffer.chunks(chunk_size) {
+            let length = match self.write(chunk) {
+                Ok(length) => length,
+                Err(err) if written == 0 => return Err(err),
+                Err(err) => {
+                    return Err(Error::PartialWrite {
+                        written,
+                        error: Box::new(err),
+                    })
+                }
+            };
+
+            written += length;
+
+            if let ControlFlow::Break(()) = progress(written) {
+                break;
+            }
+
+            if length < chunk.len() {
+                break;
+            }
+        }
+
+        Ok(written)
     }
 
     /// Sends the outgoing data contained in `write_buffer` to the slave device, and
@@ -408,7 +1536,8 @@ impl I2c {
     ///
     /// Compared to calling [`write`] and [`read`] separately, `write_read` doesn't
     /// issue a STOP condition in between the write and read operation. A repeated
-    /// START is sent instead.
+    /// START is sent instead, unless a STOP has been enabled with
+    /// [`set_default_stop`].
     ///
     /// `write_read` reads as many bytes as can fit in `read_buffer`. The maximum
     /// number of bytes in either `write_buffer` or `read_buffer` can't exceed 8192.
@@ -416,16 +1545,170 @@ impl I2c {
     /// Sequence: START → Address + Write Bit → Outgoing Bytes → Repeated START →
     /// Address + Read Bit → Incoming Bytes → STOP
     ///
+    /// ## Atomicity
+    ///
+    /// Unless a STOP has been enabled with [`set_default_stop`], the write and
+    /// read are always submitted to the kernel as a single `I2C_RDWR` request.
+    /// The kernel holds the bus for the duration of the request, so no other
+    /// process or driver on the Raspberry Pi can access the bus in between the
+    /// write and the read. This guarantee doesn't extend to other bus masters
+    /// connected to the same bus. See [`Controller::multi_master`] for
+    /// details.
+    ///
+    /// With [`set_default_stop`] enabled, the write and read are submitted as
+    /// two separate requests, because the STOP can only be issued at the end
+    /// of a request. Another transaction may be sent in between.
+    ///
     /// [`write`]: #method.write
     /// [`read`]: #method.read
+    /// [`set_default_stop`]: #method.set_default_stop
+    /// [`Controller::multi_master`]: enum.Controller.html#method.multi_master
     pub fn write_read(&self, write_buffer: &[u8], read_buffer: &mut [u8]) -> Result<()> {
-        ioctl::i2c_write_read(
-            self.i2cdev.as_raw_fd(),
-            self.address,
-            self.addr_10bit,
-            write_buffer,
-            read_buffer,
-        )?;
+        self.inject_fault(Operation::WriteRead)?;
+
+        self.retry_policy
+            .retry(
+                || {
+                    sys::i2c_write_read(
+                        &self.i2cdev,
+                        self.address,
+                        self.addr_10bit,
+                        write_buffer,
+                        read_buffer,
+                        self.default_stop,
+                    )
+                },
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+
+        self.corrupt_data(Operation::WriteRead, read_buffer);
+        self.log_transaction("write_read", write_buffer, read_buffer);
+        self.record_write(&[write_buffer]);
+        self.record_read(&[read_buffer]);
+
+        Ok(())
+    }
+
+    /// Sends the outgoing data contained in `buffer` to the slave device as a
+    /// single I2C message, with the protocol mangling [`MessageFlags`]
+    /// applied.
+    ///
+    /// Sequence: START → Address + Write Bit → Outgoing Bytes → STOP
+    ///
+    /// With [`MessageFlags::set_ignore_nak`] enabled, the transaction
+    /// completes even if the slave device doesn't acknowledge its address or
+    /// any of the data bytes. That's appropriate for devices that NAK bytes
+    /// they did receive, but it also hides a missing or busy device.
+    ///
+    /// Acknowledge polling, where the master repeatedly addresses a device
+    /// such as an EEPROM until it acknowledges the end of an internal write
+    /// cycle, relies on detecting the NAK, and should be implemented with
+    /// the default flags instead. While the device is busy, each attempt
+    /// returns [`Error::DeviceNotFound`], which can be retried.
+    ///
+    /// If any flags are enabled, and the underlying drivers don't support
+    /// protocol mangling, `write_with_flags` returns
+    /// `Err(`[`Error::FeatureNotSupported`]`)`. The hardware I2C controllers
+    /// accept, but ignore, the flags. They're honored by bit-banged `i2c-gpio`
+    /// buses.
+    ///
+    /// ```no_run
+    /// use std::thread;
+    /// use std::time::Duration;
+    ///
+    /// use rppal::i2c::{Error, I2c, MessageFlags};
+    ///
+    /// # fn main() -> rppal::i2c::Result<()> {
+    /// let mut i2c = I2c::with_bus(3)?;
+    /// i2c.set_slave_address(0x50)?;
+    ///
+    /// // Write a byte to address 0x0010 of a 24LC256 EEPROM
+    /// i2c.write(&[0x00, 0x10, 0xAB])?;
+    ///
+    /// // Acknowledge polling: the EEPROM NAKs its address until the write cycle completes
+    /// let mut attempts = 0;
+    /// loop {
+    ///     match i2c.write_with_flags(&[0x00, 0x10], MessageFlags::new()) {
+    ///         Err(Error::DeviceNotFound(_)) if attempts < 10 => {
+    ///             attempts += 1;
+    ///             thread::sleep(Duration::from_millis(1));
+    ///         }
+    ///         result => break result?,
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`MessageFlags`]: struct.MessageFlags.html
+    /// [`MessageFlags::set_ignore_nak`]: struct.MessageFlags.html#method.set_ignore_nak
+    /// [`Error::DeviceNotFound`]: enum.Error.html#variant.DeviceNotFound
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    pub fn write_with_flags(&mut self, buffer: &[u8], flags: MessageFlags) -> Result<()> {
+        self.check_message_flags(flags)?;
+        self.inject_fault(Operation::Write)?;
+
+        self.retry_policy
+            .retry(
+                || sys::i2c_write_flags(&self.i2cdev, self.address, self.addr_10bit, buffer, flags),
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+
+        self.log_transaction("write_with_flags", buffer, &[]);
+        self.record_write(&[buffer]);
+
+        Ok(())
+    }
+
+    /// Receives incoming data from the slave device as a single I2C message,
+    /// with the protocol mangling [`MessageFlags`] applied, and writes it to
+    /// `buffer`.
+    ///
+    /// Sequence: START → Address + Read Bit → Incoming Bytes → STOP
+    ///
+    /// With [`MessageFlags::set_ignore_nak`] enabled, a slave device that
+    /// doesn't acknowledge its address doesn't cause an error. Nothing drives
+    /// SDA in that case, so `buffer` is filled with `0xFF`. With
+    /// [`MessageFlags::set_no_read_ack`] enabled, the master skips the
+    /// acknowledge bit after each byte, which only works with devices that
+    /// expect it.
+    ///
+    /// If any flags are enabled, and the underlying drivers don't support
+    /// protocol mangling, `read_with_flags` returns
+    /// `Err(`[`Error::FeatureNotSupported`]`)`. The hardware I2C controllers
+    /// accept, but ignore, the flags. They're honored by bit-banged `i2c-gpio`
+    /// buses.
+    ///
+    /// [`MessageFlags`]: struct.MessageFlags.html
+    /// [`MessageFlags::set_ignore_nak`]: struct.MessageFlags.html#method.set_ignore_nak
+    /// [`MessageFlags::set_no_read_ack`]: struct.MessageFlags.html#method.set_no_read_ack
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    pub fn read_with_flags(&mut self, buffer: &mut [u8], flags: MessageFlags) -> Result<()> {
+        self.check_message_flags(flags)?;
+        self.inject_fault(Operation::Read)?;
+
+        self.retry_policy
+            .retry(
+                || sys::i2c_read_flags(&self.i2cdev, self.address, self.addr_10bit, buffer, flags),
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+
+        self.corrupt_data(Operation::Read, buffer);
+        self.log_transaction("read_with_flags", &[], buffer);
+        self.record_read(&[buffer]);
+
+        Ok(())
+    }
+
+    // Protocol mangling flags are silently ignored by drivers that don't
+    // support them, so we check up front.
+    fn check_message_flags(&self, flags: MessageFlags) -> Result<()> {
+        if !flags.is_empty() && !self.funcs.protocol_mangling() {
+            return Err(Error::FeatureNotSupported);
+        }
 
         Ok(())
     }
@@ -445,7 +1728,19 @@ impl I2c {
     ///
     /// [`smbus_block_read`]: #method.smbus_block_read
     pub fn block_read(&self, command: u8, buffer: &mut [u8]) -> Result<()> {
-        ioctl::i2c_block_read(self.i2cdev.as_raw_fd(), command, buffer)?;
+        self.inject_fault(Operation::BlockRead)?;
+
+        self.retry_policy
+            .retry(
+                || sys::i2c_block_read(&self.i2cdev, command, buffer),
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+
+        self.corrupt_data(Operation::BlockRead, buffer);
+        self.log_transaction("block_read", &[command], buffer);
+        self.record_write(&[&[command]]);
+        self.record_read(&[&buffer[..buffer.len().min(32)]]);
 
         Ok(())
     }
@@ -464,7 +1759,17 @@ impl I2c {
     ///
     /// [`smbus_block_write`]: #method.smbus_block_write
     pub fn block_write(&self, command: u8, buffer: &[u8]) -> Result<()> {
-        ioctl::i2c_block_write(self.i2cdev.as_raw_fd(), command, buffer)?;
+        self.inject_fault(Operation::BlockWrite)?;
+
+        self.retry_policy
+            .retry(
+                || sys::i2c_block_write(&self.i2cdev, command, buffer),
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+
+        self.log_transaction("block_write", &[command], &buffer[..buffer.len().min(32)]);
+        self.record_write(&[&[command], &buffer[..buffer.len().min(32)]]);
 
         Ok(())
     }
@@ -476,7 +1781,21 @@ impl I2c {
     ///
     /// Sequence: START → Address + Command Bit → STOP
     pub fn smbus_quick_command(&self, command: bool) -> Result<()> {
-        ioctl::smbus_quick_command(self.i2cdev.as_raw_fd(), command)?;
+        self.inject_fault(Operation::SmbusQuickCommand)?;
+
+        self.retry_policy
+            .retry(
+                || sys::smbus_quick_command(&self.i2cdev, command),
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+
+        self.log_transaction("smbus_quick_command", &[], &[]);
+        if command {
+            self.record_read(&[]);
+        } else {
+            self.record_write(&[]);
+        }
 
         Ok(())
     }
@@ -485,31 +1804,293 @@ impl I2c {
     ///
     /// Sequence: START → Address + Read Bit → Incoming Byte → STOP
     pub fn smbus_receive_byte(&self) -> Result<u8> {
-        Ok(ioctl::smbus_receive_byte(self.i2cdev.as_raw_fd())?)
+        self.inject_fault(Operation::SmbusReceiveByte)?;
+
+        let value = self
+            .retry_policy
+            .retry(|| sys::smbus_receive_byte(&self.i2cdev), is_transient)
+            .map_err(|e| self.transaction_error(e))?;
+        let value = self.corrupt_byte(Operation::SmbusReceiveByte, value);
+
+        self.log_transaction("smbus_receive_byte", &[], &[value]);
+        self.record_read(&[&[value]]);
+
+        Ok(value)
     }
 
     /// Sends an 8-bit `value`.
     ///
     /// Sequence: START → Address + Write Bit → Outgoing Byte → STOP
     pub fn smbus_send_byte(&self, value: u8) -> Result<()> {
-        ioctl::smbus_send_byte(self.i2cdev.as_raw_fd(), value)?;
+        self.inject_fault(Operation::SmbusSendByte)?;
+
+        self.retry_policy
+            .retry(|| sys::smbus_send_byte(&self.i2cdev, value), is_transient)
+            .map_err(|e| self.transaction_error(e))?;
+
+        self.log_transaction("smbus_send_byte", &[value], &[]);
+        self.record_write(&[&[value]]);
 
         Ok(())
     }
 
     /// Sends an 8-bit `command`, and receives an 8-bit value.
     ///
+    /// The command byte usually selects the register to read. For slave
+    /// devices without registers, use [`read_byte`] instead.
+    ///
     /// Sequence: START → Address + Write Bit → Command → Repeated START
     /// → Address + Read Bit → Incoming Byte → STOP
+    ///
+    /// [`read_byte`]: #method.read_byte
     pub fn smbus_read_byte(&self, command: u8) -> Result<u8> {
-        Ok(ioctl::smbus_read_byte(self.i2cdev.as_raw_fd(), command)?)
+        self.inject_fault(Operation::SmbusReadByte)?;
+
+        let value = self
+            .retry_policy
+            .retry(|| sys::smbus_read_byte(&self.i2cdev, command), is_transient)
+            .map_err(|e| self.transaction_error(e))?;
+        let value = self.corrupt_byte(Operation::SmbusReadByte, value);
+
+        self.log_transaction("smbus_read_byte", &[command], &[value]);
+        self.record_write(&[&[command]]);
+        self.record_read(&[&[value]]);
+
+        Ok(value)
     }
 
     /// Sends an 8-bit `command` and an 8-bit `value`.
     ///
+    /// The command byte usually selects the register to write. For slave
+    /// devices without registers, use [`write_byte_raw`] instead.
+    ///
     /// Sequence: START → Address + Write Bit → Command → Outgoing Byte → STOP
+    ///
+    /// [`write_byte_raw`]: #method.write_byte_raw
     pub fn smbus_write_byte(&self, command: u8, value: u8) -> Result<()> {
-        ioctl::smbus_write_byte(self.i2cdev.as_raw_fd(), command, value)?;
+        self.inject_fault(Operation::SmbusWriteByte)?;
+
+        self.retry_policy
+            .retry(
+                || sys::smbus_write_byte(&self.i2cdev, command, value),
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+
+        self.log_transaction("smbus_write_byte", &[command, value], &[]);
+        self.record_write(&[&[command, value]]);
+
+        Ok(())
+    }
+
+    /// Changes the bits selected by `mask` in an 8-bit register to the
+    /// corresponding bits in `value`.
+    ///
+    /// `update_register` reads the register's current value with
+    /// [`smbus_read_byte`], calculates `(old & !mask) | (value & mask)`, and writes
+    /// the result back with [`smbus_write_byte`]. Returns the new register value.
+    ///
+    /// The read and write are separate transactions. Another master on the same
+    /// bus, or another process or thread accessing the same slave device, could
+    /// change the register in between, in which case that change is overwritten.
+    /// If the register is shared, synchronize access, for instance by wrapping
+    /// `I2c` in a `Mutex` and holding the lock for the duration of the call.
+    ///
+    /// [`smbus_read_byte`]: #method.smbus_read_byte
+    /// [`smbus_write_byte`]: #method.smbus_write_byte
+    pub fn update_register(&mut self, reg: u8, mask: u8, value: u8) -> Result<u8> {
+        let old = self.smbus_read_byte(reg)?;
+        let new = (old & !mask) | (value & mask);
+
+        self.smbus_write_byte(reg, new)?;
+
+        Ok(new)
+    }
+
+    /// Writes a list of 8-bit values to 8-bit registers.
+    ///
+    /// Each `(register, value)` pair in `pairs` is written in order. This is
+    /// useful for initializing devices that need a long list of registers
+    /// configured.
+    ///
+    /// If the underlying drivers support plain I2C transfers, the pairs are
+    /// sent as a single combined transaction, where each register write is a
+    /// separate message separated by a repeated START, with a single STOP at
+    /// the end. Other masters and processes can't access the bus during the
+    /// transaction. Combined transactions are limited to 42 messages, so
+    /// longer lists are split into multiple transactions. Otherwise, each pair
+    /// is written with a separate SMBus Write Byte transaction.
+    ///
+    /// Most devices accept a repeated START between register writes, but some
+    /// only apply a write once they receive a STOP. For those devices, enable
+    /// a STOP after each message with [`set_default_stop`], or loop over
+    /// [`smbus_write_byte`].
+    ///
+    /// Sequence: START → Address + Write Bit → Register → Outgoing Byte
+    /// → Repeated START → Address + Write Bit → Register → Outgoing Byte
+    /// → ... → STOP
+    ///
+    /// [`set_default_stop`]: #method.set_default_stop
+    /// [`smbus_write_byte`]: #method.smbus_write_byte
+    pub fn write_registers(&mut self, pairs: &[(u8, u8)]) -> Result<()> {
+        self.inject_fault(Operation::WriteRegisters)?;
+
+        let messages: Vec<[u8; 2]> = pairs.iter().map(|&(reg, value)| [reg, value]).collect();
+
+        if self.funcs.i2c() {
+            let messages: Vec<&[u8]> = messages.iter().map(|message| &message[..]).collect();
+
+            self.retry_policy
+                .retry(
+                    || {
+                        sys::i2c_write_messages(
+                            &self.i2cdev,
+                            self.address,
+                            self.addr_10bit,
+                            &messages,
+                            self.default_stop,
+                        )
+                    },
+                    is_transient,
+                )
+                .map_err(|e| self.transaction_error(e))?;
+        } else {
+            for &[reg, value] in &messages {
+                self.retry_policy
+                    .retry(
+                        || sys::smbus_write_byte(&self.i2cdev, reg, value),
+                        is_transient,
+                    )
+                    .map_err(|e| self.transaction_error(e))?;
+            }
+        }
+
+        self.log_transaction("write_registers", &messages.concat(), &[]);
+        for message in &messages {
+            self.record_write(&[message]);
+        }
+
+        Ok(())
+    }
+
+    /// Writes `data` to consecutive 8-bit registers, starting at `start_reg`,
+    /// in a single write.
+    ///
+    /// Most devices automatically increment the register address after each
+    /// byte. Some devices, such as the STMicroelectronics LIS3DH
+    /// accelerometer and L3GD20 gyroscope, only increment the register
+    /// address when a specific bit of the register address byte is set, and
+    /// otherwise write every byte to the same register. `auto_increment_bit`
+    /// is a mask that's combined with `start_reg` using a bitwise OR, for
+    /// instance `Some(0x80)` for the most significant bit. Set it to `None`
+    /// for devices that always increment the register address.
+    ///
+    /// If `data` is empty, only the register address is sent.
+    ///
+    /// Sequence: START → Address + Write Bit → Register (| Auto-Increment Bit)
+    /// → Outgoing Bytes → STOP
+    ///
+    /// ```
+    /// # #[cfg(feature = "mock")]
+    /// # {
+    /// use rppal::i2c::I2c;
+    /// use rppal::mock::{Event, Registers, Simulator};
+    ///
+    /// // LIS3DH control registers CTRL_REG1 (0x20) through CTRL_REG4 (0x23)
+    /// const CTRL_REG1: u8 = 0x20;
+    /// const AUTO_INCREMENT: u8 = 0x80;
+    ///
+    /// let simulator = Simulator::new();
+    /// simulator.add_i2c_device(1, 0x18, Registers::new());
+    ///
+    /// let mut i2c = I2c::with_simulator(&simulator)?;
+    /// i2c.set_slave_address(0x18)?;
+    /// i2c.write_registers_incrementing(CTRL_REG1, &[0x57, 0x00, 0x00, 0x08], Some(AUTO_INCREMENT))?;
+    ///
+    /// assert_eq!(
+    ///     simulator.events(),
+    ///     vec![Event::I2cWrite {
+    ///         bus: 1,
+    ///         address: 0x18,
+    ///         data: vec![0xA0, 0x57, 0x00, 0x00, 0x08],
+    ///     }]
+    /// );
+    /// # }
+    /// # Ok::<(), rppal::i2c::Error>(())
+    /// ```
+    pub fn write_registers_incrementing(
+        &mut self,
+        start_reg: u8,
+        data: &[u8],
+        auto_increment_bit: Option<u8>,
+    ) -> Result<()> {
+        let mut buffer = Vec::with_capacity(data.len() + 1);
+        buffer.push(start_reg | auto_increment_bit.unwrap_or(0));
+        buffer.extend_from_slice(data);
+
+        if self.write(&buffer)? < buffer.len() {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "not all register values were written",
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Writes a list of 8-bit values to 8-bit registers, waiting for the
+    /// specified delay after each write.
+    ///
+    /// `init_sequence` works similarly to [`write_registers`], but each step
+    /// consists of a register, a value, and the amount of time to wait after
+    /// writing the value, for registers that need time to settle, such as
+    /// reset or oscillator control registers. A delay of
+    /// [`Duration::ZERO`] means the next register is written immediately.
+    ///
+    /// Consecutive steps without a delay are combined into a single
+    /// transaction as described for [`write_registers`]. A step with a
+    /// non-zero delay ends the current transaction, so the device receives a
+    /// STOP before the delay starts.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use rppal::i2c::I2c;
+    ///
+    /// # fn main() -> rppal::i2c::Result<()> {
+    /// let mut i2c = I2c::new()?;
+    /// i2c.set_slave_address(0x76)?;
+    ///
+    /// i2c.init_sequence(&[
+    ///     // Soft reset, followed by a 2 ms startup time
+    ///     (0xE0, 0xB6, Duration::from_millis(2)),
+    ///     (0xF2, 0x01, Duration::ZERO),
+    ///     (0xF4, 0x27, Duration::ZERO),
+    ///     (0xF5, 0xA0, Duration::ZERO),
+    /// ])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`write_registers`]: #method.write_registers
+    /// [`Duration::ZERO`]: https://doc.rust-lang.org/std/time/struct.Duration.html#associatedconstant.ZERO
+    pub fn init_sequence(&mut self, steps: &[(u8, u8, Duration)]) -> Result<()> {
+        let mut pairs = Vec::with_capacity(steps.len());
+
+        for &(reg, value, delay) in steps {
+            pairs.push((reg, value));
+
+            if delay > Duration::ZERO {
+                self.write_registers(&pairs)?;
+                pairs.clear();
+
+                thread::sleep(delay);
+            }
+        }
+
+        if !pairs.is_empty() {
+            self.write_registers(&pairs)?;
+        }
 
         Ok(())
     }
@@ -526,7 +2107,57 @@ impl I2c {
     ///
     /// [`smbus_read_word_swapped`]: #method.smbus_read_word_swapped
     pub fn smbus_read_word(&self, command: u8) -> Result<u16> {
-        Ok(ioctl::smbus_read_word(self.i2cdev.as_raw_fd(), command)?)
+        self.inject_fault(Operation::SmbusReadWord)?;
+
+        let value = self
+            .retry_policy
+            .retry(|| sys::smbus_read_word(&self.i2cdev, command), is_transient)
+            .map_err(|e| self.transaction_error(e))?;
+        let value = self.corrupt_word(Operation::SmbusReadWord, value);
+
+        self.log_transaction("smbus_read_word", &[command], &value.to_le_bytes());
+        self.record_write(&[&[command]]);
+        self.record_read(&[&value.to_le_bytes()]);
+
+        Ok(value)
+    }
+
+    /// Sends an 8-bit `command`, and receives a 16-bit value, using whichever
+    /// transaction type the bus supports.
+    ///
+    /// `read_word_auto` uses [`smbus_read_word`] if the bus supports SMBus
+    /// Read Word, either natively or through the kernel's SMBus emulation.
+    /// Otherwise, it falls back to a plain I2C [`write_read`] transaction,
+    /// which produces the same sequence on the bus. The supported
+    /// functionality is determined once when the bus is opened, and is
+    /// available through [`capabilities`].
+    ///
+    /// This is useful for drivers that need to work consistently across
+    /// different I2C adapters, including those that only support a subset of
+    /// the SMBus protocols. If neither transaction type is supported,
+    /// `read_word_auto` returns `Err(`[`Error::FeatureNotSupported`]`)`.
+    ///
+    /// The first byte received is stored as the low byte of the 16-bit value,
+    /// and the second byte as the high byte, similar to [`smbus_read_word`].
+    ///
+    /// Sequence: START → Address + Write Bit → Command → Repeated START
+    /// → Address + Read Bit → Incoming Byte Low → Incoming Byte High → STOP
+    ///
+    /// [`smbus_read_word`]: #method.smbus_read_word
+    /// [`write_read`]: #method.write_read
+    /// [`capabilities`]: #method.capabilities
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    pub fn read_word_auto(&self, command: u8) -> Result<u16> {
+        if self.funcs.smbus_read_word() {
+            self.smbus_read_word(command)
+        } else if self.funcs.i2c() {
+            let mut buffer = [0u8; 2];
+            self.write_read(&[command], &mut buffer)?;
+
+            Ok(u16::from_le_bytes(buffer))
+        } else {
+            Err(Error::FeatureNotSupported)
+        }
     }
 
     /// Sends an 8-bit `command`, and receives a 16-bit `value` in a non-standard swapped byte order.
@@ -540,7 +2171,17 @@ impl I2c {
     ///
     /// [`smbus_read_word`]: #method.smbus_read_word
     pub fn smbus_read_word_swapped(&self, command: u8) -> Result<u16> {
-        let value = ioctl::smbus_read_word(self.i2cdev.as_raw_fd(), command)?;
+        self.inject_fault(Operation::SmbusReadWord)?;
+
+        let value = self
+            .retry_policy
+            .retry(|| sys::smbus_read_word(&self.i2cdev, command), is_transient)
+            .map_err(|e| self.transaction_error(e))?;
+        let value = self.corrupt_word(Operation::SmbusReadWord, value);
+
+        self.log_transaction("smbus_read_word_swapped", &[command], &value.to_le_bytes());
+        self.record_write(&[&[command]]);
+        self.record_read(&[&value.to_le_bytes()]);
 
         Ok(((value & 0xFF00) >> 8) | ((value & 0xFF) << 8))
     }
@@ -556,7 +2197,18 @@ impl I2c {
     ///
     /// [`smbus_write_word_swapped`]: #method.smbus_write_word_swapped
     pub fn smbus_write_word(&self, command: u8, value: u16) -> Result<()> {
-        ioctl::smbus_write_word(self.i2cdev.as_raw_fd(), command, value)?;
+        self.inject_fault(Operation::SmbusWriteWord)?;
+
+        self.retry_policy
+            .retry(
+                || sys::smbus_write_word(&self.i2cdev, command, value),
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+
+        let [low, high] = value.to_le_bytes();
+        self.log_transaction("smbus_write_word", &[command, low, high], &[]);
+        self.record_write(&[&[command, low, high]]);
 
         Ok(())
     }
@@ -570,11 +2222,24 @@ impl I2c {
     ///
     /// [`smbus_write_word`]: #method.smbus_write_word
     pub fn smbus_write_word_swapped(&self, command: u8, value: u16) -> Result<()> {
-        ioctl::smbus_write_word(
-            self.i2cdev.as_raw_fd(),
-            command,
-            ((value & 0xFF00) >> 8) | ((value & 0xFF) << 8),
-        )?;
+        self.inject_fault(Operation::SmbusWriteWord)?;
+
+        self.retry_policy
+            .retry(
+                || {
+                    sys::smbus_write_word(
+                        &self.i2cdev,
+                        command,
+                        ((value & 0xFF00) >> 8) | ((value & 0xFF) << 8),
+                    )
+                },
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+
+        let [low, high] = value.to_le_bytes();
+        self.log_transaction("smbus_write_word_swapped", &[command, high, low], &[]);
+        self.record_write(&[&[command, high, low]]);
 
         Ok(())
     }
@@ -592,11 +2257,27 @@ impl I2c {
     ///
     /// [`smbus_process_call_swapped`]: #method.smbus_process_call_swapped
     pub fn smbus_process_call(&self, command: u8, value: u16) -> Result<u16> {
-        Ok(ioctl::smbus_process_call(
-            self.i2cdev.as_raw_fd(),
-            command,
-            value,
-        )?)
+        self.inject_fault(Operation::SmbusProcessCall)?;
+
+        let response = self
+            .retry_policy
+            .retry(
+                || sys::smbus_process_call(&self.i2cdev, command, value),
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+        let response = self.corrupt_word(Operation::SmbusProcessCall, response);
+
+        let [low, high] = value.to_le_bytes();
+        self.log_transaction(
+            "smbus_process_call",
+            &[command, low, high],
+            &response.to_le_bytes(),
+        );
+        self.record_write(&[&[command, low, high]]);
+        self.record_read(&[&response.to_le_bytes()]);
+
+        Ok(response)
     }
 
     /// Sends an 8-bit `command` and a 16-bit `value`, and then receives a 16-bit value in response, in
@@ -612,11 +2293,31 @@ impl I2c {
     ///
     /// [`smbus_process_call`]: #method.smbus_process_call
     pub fn smbus_process_call_swapped(&self, command: u8, value: u16) -> Result<u16> {
-        let response = ioctl::smbus_process_call(
-            self.i2cdev.as_raw_fd(),
-            command,
-            ((value & 0xFF00) >> 8) | ((value & 0xFF) << 8),
-        )?;
+        self.inject_fault(Operation::SmbusProcessCall)?;
+
+        let response = self
+            .retry_policy
+            .retry(
+                || {
+                    sys::smbus_process_call(
+                        &self.i2cdev,
+                        command,
+                        ((value & 0xFF00) >> 8) | ((value & 0xFF) << 8),
+                    )
+                },
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+        let response = self.corrupt_word(Operation::SmbusProcessCall, response);
+
+        let [low, high] = value.to_le_bytes();
+        self.log_transaction(
+            "smbus_process_call_swapped",
+            &[command, high, low],
+            &response.to_le_bytes(),
+        );
+        self.record_write(&[&[command, high, low]]);
+        self.record_read(&[&response.to_le_bytes()]);
 
         Ok(((response & 0xFF00) >> 8) | ((response & 0xFF) << 8))
     }
@@ -647,11 +2348,22 @@ impl I2c {
             return Err(Error::FeatureNotSupported);
         }
 
-        Ok(ioctl::smbus_block_read(
-            self.i2cdev.as_raw_fd(),
-            command,
-            buffer,
-        )?)
+        self.inject_fault(Operation::SmbusBlockRead)?;
+
+        let length = self
+            .retry_policy
+            .retry(
+                || sys::smbus_block_read(&self.i2cdev, command, buffer),
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+        self.corrupt_data(Operation::SmbusBlockRead, &mut buffer[..length]);
+
+        self.log_transaction("smbus_block_read", &[command], &buffer[..length]);
+        self.record_write(&[&[command]]);
+        self.record_read(&[&[length as u8], &buffer[..length.min(buffer.len())]]);
+
+        Ok(length)
     }
 
     /// Sends an 8-bit `command` and an 8-bit byte count along with a multi-byte `buffer`.
@@ -662,11 +2374,118 @@ impl I2c {
     /// Sequence: START → Address + Write Bit → Command → Outgoing Byte Count
     /// → Outgoing Bytes → STOP
     pub fn smbus_block_write(&self, command: u8, buffer: &[u8]) -> Result<()> {
-        ioctl::smbus_block_write(self.i2cdev.as_raw_fd(), command, buffer)?;
+        self.inject_fault(Operation::SmbusBlockWrite)?;
+
+        self.retry_policy
+            .retry(
+                || sys::smbus_block_write(&self.i2cdev, command, buffer),
+                is_transient,
+            )
+            .map_err(|e| self.transaction_error(e))?;
+
+        self.log_transaction(
+            "smbus_block_write",
+            &[command],
+            &buffer[..buffer.len().min(32)],
+        );
+        let length = buffer.len().min(32);
+        self.record_write(&[&[command, length as u8], &buffer[..length]]);
 
         Ok(())
     }
 
+    /// Reads the SMBus Alert Response Address (ARA) to find out which slave
+    /// device asserted the SMBALERT# line.
+    ///
+    /// SMBALERT# is an optional, shared, open-drain interrupt line. A slave
+    /// device pulls it low when it needs attention. When the ARA (`0x0C`) is
+    /// read, every device that has an alert pending responds with its own
+    /// 7-bit address. If multiple devices respond simultaneously, they're
+    /// arbitrated by address, so the device with the lowest address wins.
+    /// Only the winning device releases SMBALERT#. Call
+    /// `smbus_alert_response` repeatedly until it returns `None` to service
+    /// every pending alert.
+    ///
+    /// Returns the 7-bit address of the responding device, or `None` if no
+    /// device responded. The current slave address is left unchanged, and
+    /// the address map doesn't apply to the returned address.
+    ///
+    /// SMBALERT# isn't part of the I2C bus itself. Connect it to a GPIO pin
+    /// with a pull-up resistor, and use [`InputPin::set_interrupt`] or
+    /// [`InputPin::set_async_interrupt`] with [`Trigger::FallingEdge`] to
+    /// find out when to call `smbus_alert_response`. Don't use this method
+    /// when the kernel's `smbus-alert` driver is configured for the same bus,
+    /// since it responds to the alerts itself.
+    ///
+    /// Sequence: START → ARA + Read Bit → Incoming Byte → STOP
+    ///
+    /// ```no_run
+    /// use rppal::gpio::{Gpio, Trigger};
+    /// use rppal::i2c::I2c;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut i2c = I2c::new()?;
+    /// let mut alert = Gpio::new()?.get(17)?.into_input_pullup();
+    /// alert.set_interrupt(Trigger::FallingEdge)?;
+    ///
+    /// loop {
+    ///     alert.poll_interrupt(true, None)?;
+    ///
+    ///     while let Some(address) = i2c.smbus_alert_response()? {
+    ///         println!("Alert from slave device at 0x{:02X}", address);
+    ///         // Clear the alert condition on the device
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`InputPin::set_interrupt`]: ../gpio/struct.InputPin.html#method.set_interrupt
+    /// [`InputPin::set_async_interrupt`]: ../gpio/struct.InputPin.html#method.set_async_interrupt
+    /// [`Trigger::FallingEdge`]: ../gpio/enum.Trigger.html#variant.FallingEdge
+    pub fn smbus_alert_response(&mut self) -> Result<Option<u16>> {
+        self.inject_fault(Operation::SmbusReceiveByte)?;
+
+        let mut buffer = [0u8; 1];
+
+        // Send the read directly to the ARA, so the configured slave address
+        // doesn't need to be changed and restored
+        match sys::i2c_read_flags(
+            &self.i2cdev,
+            ALERT_RESPONSE_ADDRESS,
+            false,
+            &mut buffer,
+            MessageFlags::new(),
+        ) {
+            Ok(()) => {}
+            Err(err) => {
+                return match self.transaction_error_at(ALERT_RESPONSE_ADDRESS, err) {
+                    Error::DeviceNotFound(_) => {
+                        log_debug!("No response from SMBus Alert Response Address");
+
+                        Ok(None)
+                    }
+                    err => Err(err),
+                }
+            }
+        }
+
+        self.corrupt_data(Operation::SmbusReceiveByte, &mut buffer);
+
+        // The responding device's address is sent in the upper 7 bits
+        let address = u16::from(buffer[0] >> 1);
+
+        log_debug!("SMBus alert from slave address 0x{:02X}", address);
+
+        if let Some(ref mut recording) = *self.recording.borrow_mut() {
+            recording.push(Transfer::Read {
+                address: ALERT_RESPONSE_ADDRESS,
+                data: buffer.to_vec(),
+            });
+        }
+
+        Ok(Some(address))
+    }
+
     /// Enables or disables SMBus Packet Error Checking.
     ///
     /// Packet Error Checking inserts a CRC-8 Packet Error Code (PEC) byte before each STOP
@@ -676,12 +2495,250 @@ impl I2c {
     ///
     /// By default, `pec` is set to `false`.
     pub fn set_smbus_pec(&self, pec: bool) -> Result<()> {
-        ioctl::set_pec(self.i2cdev.as_raw_fd(), pec as c_ulong)?;
+        sys::set_pec(&self.i2cdev, pec as c_ulong)
+            .map_err(|e| Error::with_fd(e, self.i2cdev.as_raw_fd()))?;
+
+        log_debug!("SMBus PEC set to {}", pec);
 
         Ok(())
     }
+
+    /// Sets a callback that can force transactions to fail.
+    ///
+    /// `injector` is called with the transaction type before each transaction
+    /// is sent to the slave device. If it returns `Some(error)`, the transaction
+    /// is skipped, and the method returns `Err(error)` instead. This can be used
+    /// to test how drivers built on `I2c` handle bus errors, for instance by
+    /// returning [`Error::DeviceNotFound`] for every third transaction.
+    ///
+    /// `set_fault_injector` is a no-op unless the optional `test-faults` feature
+    /// is enabled, in which case it replaces any previously set callback.
+    ///
+    /// [`Error::DeviceNotFound`]: enum.Error.html#variant.DeviceNotFound
+    pub fn set_fault_injector<F>(&mut self, injector: F)
+    where
+        F: Fn(Operation) -> Option<Error> + Send + 'static,
+    {
+        #[cfg(feature = "test-faults")]
+        {
+            self.faults.injector = Some(Box::new(injector));
+        }
+        #[cfg(not(feature = "test-faults"))]
+        drop(injector);
+    }
+
+    /// Sets a callback that can modify incoming data.
+    ///
+    /// `corruptor` is called with the transaction type and the received bytes
+    /// after each successful transaction that reads data from the slave device,
+    /// before the data is returned. 16-bit values are passed as 2 bytes in
+    /// the order they were received.
+    ///
+    /// `set_data_corruptor` is a no-op unless the optional `test-faults` feature
+    /// is enabled, in which case it replaces any previously set callback.
+    pub fn set_data_corruptor<F>(&mut self, corruptor: F)
+    where
+        F: Fn(Operation, &mut [u8]) + Send + 'static,
+    {
+        #[cfg(feature = "test-faults")]
+        {
+            self.faults.corruptor = Some(Box::new(corruptor));
+        }
+        #[cfg(not(feature = "test-faults"))]
+        drop(corruptor);
+    }
+
+    /// Removes the callbacks set with [`set_fault_injector`] and [`set_data_corruptor`].
+    ///
+    /// [`set_fault_injector`]: #method.set_fault_injector
+    /// [`set_data_corruptor`]: #method.set_data_corruptor
+    pub fn clear_faults(&mut self) {
+        #[cfg(feature = "test-faults")]
+        {
+            self.faults = fault::Faults::default();
+        }
+    }
+
+    // Returns the error set by the fault injector for operation, if any.
+    #[inline(always)]
+    fn inject_fault(&self, _operation: Operation) -> Result<()> {
+        #[cfg(feature = "test-faults")]
+        if let Some(err) = self.faults.inject(_operation) {
+            log_debug!("Injected fault for {:?}: {}", _operation, err);
+
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn corrupt_data(&self, _operation: Operation, _data: &mut [u8]) {
+        #[cfg(feature = "test-faults")]
+        self.faults.corrupt(_operation, _data);
+    }
+
+    #[inline(always)]
+    fn corrupt_byte(&self, operation: Operation, value: u8) -> u8 {
+        let mut data = [value];
+        self.corrupt_data(operation, &mut data);
+
+        data[0]
+    }
+
+    // SMBus words are transferred low byte first
+    #[inline(always)]
+    fn corrupt_word(&self, operation: Operation, value: u16) -> u16 {
+        let mut data = value.to_le_bytes();
+        self.corrupt_data(operation, &mut data);
+
+        u16::from_le_bytes(data)
+    }
+
+    // Appends a write containing the concatenated parts to the active
+    // recording. The data is only copied while recording.
+    #[inline(always)]
+    fn record_write(&self, parts: &[&[u8]]) {
+        if let Some(ref mut recording) = *self.recording.borrow_mut() {
+            recording.push(Transfer::Write {
+                address: self.address,
+                data: parts.concat(),
+            });
+        }
+    }
+
+    #[inline(always)]
+    fn record_read(&self, parts: &[&[u8]]) {
+        if let Some(ref mut recording) = *self.recording.borrow_mut() {
+            recording.push(Transfer::Read {
+                address: self.address,
+                data: parts.concat(),
+            });
+        }
+    }
+
+    // ENXIO and EREMOTEIO indicate the slave device didn't acknowledge. The
+    // i2c-bcm2835 and i2c-designware drivers report address NAKs as
+    // EREMOTEIO, while other adapters use ENXIO. Since all transactions are
+    // sent to the current slave address, that's the one we report.
+    fn transaction_error(&self, err: io::Error) -> Error {
+        self.transaction_error_at(self.address, err)
+    }
+
+    // Same as transaction_error, for transactions sent to a fixed address
+    // instead of the current slave address.
+    fn transaction_error_at(&self, address: u16, err: io::Error) -> Error {
+        log_debug!(
+            "Transaction with slave address 0x{:02X} failed: {}",
+            address,
+            err
+        );
+
+        if is_nak(&err) {
+            Error::DeviceNotFound(address)
+        } else {
+            Error::with_fd(err, self.i2cdev.as_raw_fd())
+        }
+    }
+
+    // Logs a completed transaction. The transferred bytes are only included
+    // at trace level.
+    #[inline]
+    fn log_transaction(&self, operation: &str, write_buffer: &[u8], read_buffer: &[u8]) {
+        log_debug!(
+            "{} at slave address 0x{:02X} ({} bytes written, {} bytes read)",
+            operation,
+            self.address,
+            write_buffer.len(),
+            read_buffer.len()
+        );
+        log_trace!(
+            "{} at slave address 0x{:02X} wrote {:02X?}, read {:02X?}",
+            operation,
+            self.address,
+            log_payload!(write_buffer),
+            log_payload!(read_buffer)
+        );
+    }
 }
 
 // Send is safe for I2c, but we're marked !Send because of the dummy pointer that's
 // needed to force !Sync.
 unsafe impl Send for I2c {}
+
+// Errors that may not occur when the transaction is repeated
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.raw_os_error(),
+        Some(libc::ENXIO) | Some(libc::EREMOTEIO) | Some(libc::EAGAIN) | Some(libc::ETIMEDOUT)
+    )
+}
+
+// Errors that indicate the slave device didn't acknowledge
+fn is_nak(err: &io::Error) -> bool {
+    matches!(
+        err.raw_os_error(),
+        Some(libc::ENXIO) | Some(libc::EREMOTEIO)
+    )
+}
+
+/// Returns the file descriptor of the underlying device file.
+///
+/// The file descriptor can be shared with other code, such as C libraries
+/// that need access to the same device. `I2c` retains ownership of the
+/// file descriptor, which means it must not be closed, and it's only valid
+/// for as long as `I2c` exists.
+///
+/// Any transactions performed through the file descriptor aren't synchronized
+/// with `I2c`, and may be interleaved with its own transactions. Changing the
+/// slave address or other settings through the file descriptor isn't reflected
+/// in `I2c`'s cached state.
+impl AsRawFd for I2c {
+    fn as_raw_fd(&self) -> RawFd {
+        self.i2cdev.as_raw_fd()
+    }
+}
+
+// Returns the core clock frequency, which drives the BSC controllers
+fn core_clock() -> Result<u32> {
+    match mailbox::get_clock_rate(ClockId::Core) {
+        Ok(core_clock) if core_clock > 0 => Ok(core_clock),
+        Ok(_) => Err(Error::FeatureNotSupported),
+        Err(system::Error::Io(err)) => Err(Error::Io(err)),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn error_display_and_source() {
+        let err = Error::from(io::Error::new(io::ErrorKind::Other, "Remote I/O error"));
+        assert_eq!(err.to_string(), "I/O error: Remote I/O error");
+        assert!(err.source().is_none());
+
+        let err = Error::with_path(io::Error::from_raw_os_error(libc::EIO), "/dev/i2c-1");
+        assert!(err.to_string().starts_with("I/O error: /dev/i2c-1: "));
+        let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.raw_os_error(), Some(libc::EIO));
+
+        let err = Error::PartialWrite {
+            written: 3,
+            error: Box::new(Error::DeviceNotFound(0x50)),
+        };
+        assert_eq!(err.to_string(), "Write failed after 3 bytes");
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "Slave device not found at address 0x50"
+        );
+
+        let err = Error::InvalidSlaveAddress(0x80);
+        assert_eq!(err.to_string(), "Invalid slave address: 0x80");
+        assert!(err.source().is_none());
+    }
+}
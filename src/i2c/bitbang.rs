@@ -0,0 +1,411 @@
+use std::hint;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::gpio::{IoPin, Mode, PullUpDown};
+use crate::i2c::{Error, Result};
+
+// Half of the clock period for 100 kHz (standard mode)
+const DEFAULT_HALF_PERIOD: Duration = Duration::from_micros(5);
+// Maximum time a slave device is allowed to hold SCL low. Matches the default
+// used by the kernel's i2c-algo-bit driver.
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(100);
+// Number of clock pulses needed to free a slave device that's in the middle
+// of sending a byte and its ACK/NACK bit
+const RECOVERY_PULSES: usize = 9;
+const NANOS_PER_HALF_SECOND: u128 = 500_000_000;
+
+/// Provides software-based I2C communication through any two GPIO pins.
+///
+/// `BitBangI2c` can be used to communicate with slave devices that are
+/// connected to GPIO pins without hardware I2C support. START, STOP,
+/// (N)ACK and data bits are generated in software, which means the timing
+/// depends on the scheduler and CPU load. Slave devices that stretch the
+/// clock by holding SCL low are supported.
+///
+/// SDA and SCL are emulated as open-drain lines by switching the pins between
+/// [`Output`] mode with the output set low, and [`Input`] mode to release the
+/// line. The built-in pull-up resistors are enabled, but external pull-up
+/// resistors are recommended for reliable communication.
+///
+/// Only 7-bit slave addresses are supported.
+///
+/// The `embedded-hal` [`blocking::i2c::Read`], [`blocking::i2c::Write`],
+/// [`blocking::i2c::WriteRead`] and [`i2c::I2c`] trait implementations for
+/// `BitBangI2c` can be enabled by specifying the optional `hal` feature in the
+/// dependency declaration for the `rppal` crate.
+///
+/// [`Output`]: ../gpio/enum.Mode.html#variant.Output
+/// [`Input`]: ../gpio/enum.Mode.html#variant.Input
+/// [`blocking::i2c::Read`]: ../../embedded_hal/blocking/i2c/trait.Read.html
+/// [`blocking::i2c::Write`]: ../../embedded_hal/blocking/i2c/trait.Write.html
+/// [`blocking::i2c::WriteRead`]: ../../embedded_hal/blocking/i2c/trait.WriteRead.html
+/// [`i2c::I2c`]: ../../embedded_hal/i2c/trait.I2c.html
+#[derive(Debug)]
+pub struct BitBangI2c {
+    sda: IoPin,
+    scl: IoPin,
+    half_period: Duration,
+    timeout: Duration,
+    address: u16,
+}
+
+impl BitBangI2c {
+    /// Constructs a new `BitBangI2c`.
+    ///
+    /// `sda` and `scl` are released and their built-in pull-up resistors are
+    /// enabled. By default, the clock speed is set to 100 kHz.
+    pub fn new(mut sda: IoPin, mut scl: IoPin) -> BitBangI2c {
+        sda.set_pullupdown(PullUpDown::PullUp);
+        scl.set_pullupdown(PullUpDown::PullUp);
+
+        sda.switch_mode(Mode::Input);
+        scl.switch_mode(Mode::Input);
+
+        BitBangI2c {
+            sda,
+            scl,
+            half_period: DEFAULT_HALF_PERIOD,
+            timeout: DEFAULT_TIMEOUT,
+            address: 0,
+        }
+    }
+
+    /// Returns the half-period of the clock signal.
+    pub fn half_period(&self) -> Duration {
+        self.half_period
+    }
+
+    /// Sets the half-period of the clock signal.
+    ///
+    /// `half_period` determines how long SCL is held low and high for each
+    /// bit, which sets the maximum clock speed. For instance, a half-period
+    /// of 5 µs results in a clock speed of up to 100 kHz, and 1.25 µs results
+    /// in up to 400 kHz. The actual clock speed will be lower due to the
+    /// overhead of changing the pin states. To specify the clock speed
+    /// directly, use [`set_clock_speed`].
+    ///
+    /// By default, `half_period` is set to 5 µs.
+    ///
+    /// [`set_clock_speed`]: #method.set_clock_speed
+    pub fn set_half_period(&mut self, half_period: Duration) {
+        self.half_period = half_period;
+    }
+
+    /// Returns the maximum clock frequency in hertz (Hz) based on the
+    /// current half-period.
+    ///
+    /// A half-period shorter than 1 ns is treated as 1 ns.
+    pub fn clock_speed(&self) -> u32 {
+        let half_period = self.half_period.as_nanos().max(1);
+
+        (NANOS_PER_HALF_SECOND / half_period).min(u32::MAX as u128) as u32
+    }
+
+    /// Sets the maximum clock frequency in hertz (Hz), and returns the
+    /// frequency that's actually configured.
+    ///
+    /// `set_clock_speed` is a convenience method that sets the half-period
+    /// with [`set_half_period`], rounded up to the nearest nanosecond so the
+    /// clock never runs faster than `clock_speed`. The actual clock speed
+    /// will be lower due to the overhead of changing the pin states.
+    ///
+    /// If `clock_speed` is 0, `set_clock_speed` returns an
+    /// `io::ErrorKind::InvalidInput` error.
+    ///
+    /// [`set_half_period`]: #method.set_half_period
+    pub fn set_clock_speed(&mut self, clock_speed: u32) -> Result<u32> {
+        if clock_speed == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "clock speed must be higher than 0",
+            )));
+        }
+
+        let clock_speed = u128::from(clock_speed);
+        let half_period = (NANOS_PER_HALF_SECOND + clock_speed - 1) / clock_speed;
+        self.half_period = Duration::from_nanos(half_period as u64);
+
+        Ok(self.clock_speed())
+    }
+
+    /// Returns the maximum duration a slave device is allowed to stretch the
+    /// clock.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Sets the maximum duration a slave device is allowed to stretch the
+    /// clock.
+    ///
+    /// If SCL is held low by a slave device for longer than `timeout`, the
+    /// transaction is aborted, and an `io::ErrorKind::TimedOut` error is
+    /// returned.
+    ///
+    /// By default, `timeout` is set to 100 ms.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Sets a 7-bit slave address.
+    ///
+    /// `slave_address` refers to the slave device you're communicating with.
+    /// The specified address shouldn't include the R/W bit.
+    pub fn set_slave_address(&mut self, slave_address: u16) -> Result<()> {
+        // Filter out invalid and unsupported addresses
+        if (slave_address >> 3) == 0b1111 || slave_address > 0x7F {
+            return Err(Error::InvalidSlaveAddress(slave_address));
+        }
+
+        self.address = slave_address;
+
+        Ok(())
+    }
+
+    /// Receives incoming data from the slave device and writes it to `buffer`.
+    ///
+    /// `read` reads as many bytes as can fit in `buffer`.
+    ///
+    /// Sequence: START → Address + Read Bit → Incoming Bytes → STOP
+    ///
+    /// Returns how many bytes were read.
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        let result = self
+            .start()
+            .and_then(|_| self.write_address(true))
+            .and_then(|_| self.read_bytes(buffer, true));
+
+        self.finish(result)?;
+
+        Ok(buffer.len())
+    }
+
+    /// Sends the outgoing data contained in `buffer` to the slave device.
+    ///
+    /// Sequence: START → Address + Write Bit → Outgoing Bytes → STOP
+    ///
+    /// Returns how many bytes were written.
+    pub fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+        let result = self
+            .start()
+            .and_then(|_| self.write_address(false))
+            .and_then(|_| self.write_bytes(buffer));
+
+        self.finish(result)?;
+
+        Ok(buffer.len())
+    }
+
+    /// Sends the outgoing data contained in `write_buffer` to the slave device, and
+    /// then fills `read_buffer` with incoming data.
+    ///
+    /// Compared to calling [`write`] and [`read`] separately, `write_read` doesn't
+    /// issue a STOP condition in between the write and read operation. A repeated
+    /// START is sent instead.
+    ///
+    /// Sequence: START → Address + Write Bit → Outgoing Bytes → Repeated START →
+    /// Address + Read Bit → Incoming Bytes → STOP
+    ///
+    /// [`write`]: #method.write
+    /// [`read`]: #method.read
+    pub fn write_read(&mut self, write_buffer: &[u8], read_buffer: &mut [u8]) -> Result<()> {
+        let result = self
+            .start()
+            .and_then(|_| self.write_address(false))
+            .and_then(|_| self.write_bytes(write_buffer))
+            .and_then(|_| self.start())
+            .and_then(|_| self.write_address(true))
+            .and_then(|_| self.read_bytes(read_buffer, true));
+
+        self.finish(result)
+    }
+
+    // Sends a STOP condition, and returns the original result. If the STOP
+    // condition can't be sent, the original error takes precedence.
+    pub(super) fn finish(&mut self, result: Result<()>) -> Result<()> {
+        let stop = self.stop();
+
+        result.and(stop)
+    }
+
+    // Sends a (repeated) START condition
+    pub(super) fn start(&mut self) -> Result<()> {
+        self.release_sda();
+        self.delay();
+        self.release_scl()?;
+        self.delay();
+        self.pull_sda();
+        self.delay();
+        self.pull_scl();
+
+        Ok(())
+    }
+
+    // Sends a STOP condition
+    pub(super) fn stop(&mut self) -> Result<()> {
+        self.pull_sda();
+        self.delay();
+        self.release_scl()?;
+        self.delay();
+        self.release_sda();
+        self.delay();
+
+        Ok(())
+    }
+
+    // Frees a slave device that's holding SDA low by clocking out up to 9
+    // bits, and then sends a STOP condition.
+    pub(super) fn recover(&mut self) -> Result<()> {
+        self.release_sda();
+        self.release_scl()?;
+        self.delay();
+
+        for _ in 0..RECOVERY_PULSES {
+            if self.sda.is_high() {
+                break;
+            }
+
+            self.pull_scl();
+            self.delay();
+            self.release_scl()?;
+            self.delay();
+        }
+
+        if self.sda.is_low() {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::Other,
+                "SDA is still held low",
+            )));
+        }
+
+        self.pull_scl();
+        self.delay();
+        self.stop()
+    }
+
+    // Sends the slave address and R/W bit
+    pub(super) fn write_address(&mut self, read: bool) -> Result<()> {
+        if !self.write_byte(((self.address as u8) << 1) | read as u8)? {
+            return Err(Error::DeviceNotFound(self.address));
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn write_bytes(&mut self, buffer: &[u8]) -> Result<()> {
+        for byte in buffer {
+            if !self.write_byte(*byte)? {
+                // Data byte wasn't acknowledged. Use the same error the
+                // kernel drivers return in this situation.
+                return Err(Error::Io(io::Error::from_raw_os_error(libc::EREMOTEIO)));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Reads incoming bytes. If last is true, the final byte is followed by a
+    // NACK to indicate the end of the read operation.
+    pub(super) fn read_bytes(&mut self, buffer: &mut [u8], last: bool) -> Result<()> {
+        let len = buffer.len();
+
+        for (idx, byte) in buffer.iter_mut().enumerate() {
+            *byte = self.read_byte(!(last && idx == len - 1))?;
+        }
+
+        Ok(())
+    }
+
+    // Sends a byte, MSB first. Returns true if the slave device acknowledged.
+    fn write_byte(&mut self, byte: u8) -> Result<bool> {
+        for bit in (0..8).rev() {
+            self.write_bit((byte >> bit) & 0x01 == 0x01)?;
+        }
+
+        // ACK is indicated by the slave device pulling SDA low
+        Ok(!self.read_bit()?)
+    }
+
+    // Receives a byte, MSB first, followed by either an ACK or NACK.
+    fn read_byte(&mut self, ack: bool) -> Result<u8> {
+        let mut byte = 0u8;
+
+        for _ in 0..8 {
+            byte = (byte << 1) | self.read_bit()? as u8;
+        }
+
+        self.write_bit(!ack)?;
+
+        Ok(byte)
+    }
+
+    fn write_bit(&mut self, bit: bool) -> Result<()> {
+        if bit {
+            self.release_sda();
+        } else {
+            self.pull_sda();
+        }
+
+        self.delay();
+        self.release_scl()?;
+        self.delay();
+        self.pull_scl();
+
+        Ok(())
+    }
+
+    fn read_bit(&mut self) -> Result<bool> {
+        self.release_sda();
+        self.delay();
+        self.release_scl()?;
+
+        let bit = self.sda.is_high();
+
+        self.delay();
+        self.pull_scl();
+
+        Ok(bit)
+    }
+
+    fn release_sda(&mut self) {
+        self.sda.switch_mode(Mode::Input);
+    }
+
+    fn pull_sda(&mut self) {
+        self.sda.set_low();
+        self.sda.switch_mode(Mode::Output);
+    }
+
+    // Releases SCL, and waits until any slave device stops stretching the clock
+    fn release_scl(&mut self) -> Result<()> {
+        self.scl.switch_mode(Mode::Input);
+
+        let start = Instant::now();
+        while self.scl.is_low() {
+            if start.elapsed() > self.timeout {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "clock stretching timed out",
+                )));
+            }
+
+            hint::spin_loop();
+        }
+
+        Ok(())
+    }
+
+    fn pull_scl(&mut self) {
+        self.scl.set_low();
+        self.scl.switch_mode(Mode::Output);
+    }
+
+    // Busy wait, since thread::sleep() isn't accurate enough for
+    // microsecond delays.
+    fn delay(&self) {
+        let start = Instant::now();
+        while start.elapsed() < self.half_period {
+            hint::spin_loop();
+        }
+    }
+}
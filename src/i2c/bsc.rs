@@ -0,0 +1,124 @@
+// Direct access to the clock divider registers of the Broadcom Serial
+// Controller (BSC), which aren't exposed through the i2cdev interface.
+
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::sync::atomic::{self, Ordering};
+
+use libc::{off_t, MAP_FAILED, MAP_SHARED, O_SYNC, PROT_READ, PROT_WRITE};
+
+use crate::system::SoC;
+
+const PATH_DEV_MEM: &str = "/dev/mem";
+
+const PAGE_SIZE: u64 = 4096;
+
+// Register offsets in 32-bit words
+const BSC_DIV: usize = 0x14 / std::mem::size_of::<u32>();
+const BSC_DEL: usize = 0x18 / std::mem::size_of::<u32>();
+
+const DEL_FEDL_SHIFT: u32 = 16;
+
+const DIVIDER_MIN: u32 = 2;
+const DIVIDER_MAX: u32 = 0xfffe;
+
+// Returns the offset of the BSC registers from the peripheral base address
+// for the specified controller. BSC2 is reserved for HDMI.
+pub fn offset(soc: SoC, controller: u8) -> Option<u64> {
+    match (soc, controller) {
+        (SoC::Bcm2712, _) => None,
+        (_, 0) => Some(0x0020_5000),
+        (_, 1) => Some(0x0080_4000),
+        (SoC::Bcm2711, 3) => Some(0x0020_5600),
+        (SoC::Bcm2711, 4) => Some(0x0020_5800),
+        (SoC::Bcm2711, 5) => Some(0x0020_5a00),
+        (SoC::Bcm2711, 6) => Some(0x0020_5c00),
+        _ => None,
+    }
+}
+
+// Returns the divider closest to, but not faster than, the requested clock
+// speed. The BSC ignores the least significant bit, so the divider is rounded
+// up to an even number.
+pub fn divider(core_clock: u32, clock_speed: u32) -> u32 {
+    let divider = ((core_clock as u64 + clock_speed as u64 - 1) / clock_speed as u64)
+        .min(DIVIDER_MAX as u64) as u32;
+
+    (divider + (divider & 1)).clamp(DIVIDER_MIN, DIVIDER_MAX)
+}
+
+// Reads the clock divider for the BSC located at address. A divider of 0 is
+// interpreted as 32768.
+pub fn read_divider(address: u64) -> io::Result<u32> {
+    with_registers(address, |registers| {
+        let divider = unsafe { ptr::read_volatile(registers.add(BSC_DIV)) } & 0xffff;
+        atomic::fence(Ordering::SeqCst);
+
+        if divider == 0 {
+            32768
+        } else {
+            divider
+        }
+    })
+}
+
+// Writes the clock divider for the BSC located at address, and sets the
+// falling and rising edge delays to the same values the i2c-bcm2835 driver
+// uses.
+pub fn set_divider(address: u64, divider: u32) -> io::Result<()> {
+    let fedl = (divider / 16).max(1);
+    let redl = (divider / 4).max(1);
+
+    with_registers(address, |registers| {
+        // A SeqCst fence orders our accesses with respect to regular memory. On
+        // ARMv7 and AArch64 it's a DMB ISH, which is weaker than the barrier
+        // described in the BCM2835 ARM Peripherals manual (section 1.3).
+        atomic::fence(Ordering::SeqCst);
+        unsafe {
+            ptr::write_volatile(registers.add(BSC_DIV), divider);
+            ptr::write_volatile(registers.add(BSC_DEL), (fedl << DEL_FEDL_SHIFT) | redl);
+        }
+        atomic::fence(Ordering::SeqCst);
+    })
+}
+
+// Maps the page containing the BSC registers at address through /dev/mem, and
+// calls f with a pointer to the first register.
+fn with_registers<F, R>(address: u64, f: F) -> io::Result<R>
+where
+    F: FnOnce(*mut u32) -> R,
+{
+    let mem_file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(O_SYNC)
+        .open(PATH_DEV_MEM)?;
+
+    let page = address & !(PAGE_SIZE - 1);
+    let mem_ptr = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            PAGE_SIZE as usize,
+            PROT_READ | PROT_WRITE,
+            MAP_SHARED,
+            mem_file.as_raw_fd(),
+            page as off_t,
+        )
+    };
+
+    if mem_ptr == MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+
+    let registers = unsafe { (mem_ptr as *mut u32).add((address - page) as usize / 4) };
+    let result = f(registers);
+
+    unsafe {
+        libc::munmap(mem_ptr, PAGE_SIZE as usize);
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const PATH_I2C_DEV: &str = "/sys/class/i2c-dev";
+const PATH_I2C_ADAPTER: &str = "/sys/class/i2c-adapter";
+const PATH_DT_ALIASES: &str = "/proc/device-tree/aliases";
+const PATH_DT_BASE: &str = "/sys/firmware/devicetree/base";
+
+/// The controller an I2C bus belongs to.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Controller {
+    /// Hardware I2C controller, identified by the index of its `i2cN` device
+    /// tree alias.
+    ///
+    /// For instance, `Hardware(1)` refers to I2C1, which is the BSC1
+    /// controller on the Raspberry Pi 4 B and earlier models. The index
+    /// doesn't necessarily match the bus number.
+    Hardware(u8),
+    /// Channel of an I2C multiplexer, such as a TCA9548A, that's connected to
+    /// another I2C bus.
+    MuxChannel {
+        /// The bus number of the bus the multiplexer is connected to.
+        parent: u8,
+        /// The multiplexer channel, or `None` if it can't be determined.
+        channel: Option<u32>,
+    },
+    /// Any other I2C adapter, such as a software-based `i2c-gpio` bus, or a
+    /// hardware controller without a device tree alias.
+    Other,
+}
+
+impl Controller {
+    /// Returns whether the controller can safely share the bus with other
+    /// bus masters.
+    ///
+    /// Sharing a bus with other masters requires the controller to detect
+    /// when it loses arbitration, and to back off until the bus is idle. The
+    /// BSC controllers on the Raspberry Pi don't support arbitration, so
+    /// `multi_master` returns `Some(false)` for [`Hardware`]. Other masters
+    /// may corrupt or interleave with any transaction, regardless of whether
+    /// it's sent as a single request.
+    ///
+    /// Returns `None` for [`MuxChannel`] and [`Other`], because it depends
+    /// on the underlying adapter, which can't be determined. The `i2c-gpio`
+    /// software implementation doesn't support multiple masters either.
+    ///
+    /// [`Hardware`]: #variant.Hardware
+    /// [`MuxChannel`]: #variant.MuxChannel
+    /// [`Other`]: #variant.Other
+    pub fn multi_master(self) -> Option<bool> {
+        match self {
+            Controller::Hardware(_) => Some(false),
+            Controller::MuxChannel { .. } | Controller::Other => None,
+        }
+    }
+}
+
+/// Information about an available I2C bus.
+///
+/// A list of available buses is returned by [`I2c::buses`].
+///
+/// [`I2c::buses`]: struct.I2c.html#method.buses
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BusInfo {
+    bus: u8,
+    path: PathBuf,
+    name: String,
+    controller: Controller,
+}
+
+impl BusInfo {
+    pub(crate) fn new(bus: u8, name: String, controller: Controller) -> BusInfo {
+        BusInfo {
+            bus,
+            path: PathBuf::from(format!("/dev/i2c-{}", bus)),
+            name,
+            controller,
+        }
+    }
+
+    /// Returns the bus number, which can be passed to [`I2c::with_bus`].
+    ///
+    /// [`I2c::with_bus`]: struct.I2c.html#method.with_bus
+    pub fn bus(&self) -> u8 {
+        self.bus
+    }
+
+    /// Returns the path of the bus device node.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the I2C adapter name reported by the kernel.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the controller the bus belongs to.
+    pub fn controller(&self) -> Controller {
+        self.controller
+    }
+}
+
+// Lists all I2C buses that have a device node, sorted by bus number.
+pub(crate) fn discover() -> io::Result<Vec<BusInfo>> {
+    let entries = match fs::read_dir(PATH_I2C_DEV) {
+        Ok(entries) => entries,
+        // The i2c-dev module isn't loaded, so there aren't any device nodes
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let aliases = device_tree_aliases();
+    let mut buses = Vec::new();
+
+    for entry in entries {
+        let bus = match parse_bus(&entry?.file_name().to_string_lossy()) {
+            Some(bus) => bus,
+            None => continue,
+        };
+
+        let adapter = Path::new(PATH_I2C_ADAPTER).join(format!("i2c-{}", bus));
+
+        let name = fs::read_to_string(adapter.join("name"))
+            .map(|name| name.trim_end().to_owned())
+            .unwrap_or_default();
+
+        let controller = if let Some(controller) = mux_channel(&adapter) {
+            controller
+        } else if let Some(&alias) = of_node(&adapter).and_then(|node| aliases.get(&node)) {
+            Controller::Hardware(alias)
+        } else {
+            Controller::Other
+        };
+
+        buses.push(BusInfo::new(bus, name, controller));
+    }
+
+    buses.sort_by_key(|info| info.bus);
+
+    Ok(buses)
+}
+
+// Parses the bus number from an i2c-N device or adapter name.
+fn parse_bus(name: &str) -> Option<u8> {
+    name.strip_prefix("i2c-")?.parse().ok()
+}
+
+// Maps device tree node paths to the index of their i2cN alias.
+fn device_tree_aliases() -> HashMap<String, u8> {
+    let mut aliases = HashMap::new();
+
+    let entries = match fs::read_dir(PATH_DT_ALIASES) {
+        Ok(entries) => entries,
+        Err(_) => return aliases,
+    };
+
+    for entry in entries.flatten() {
+        let index = match entry
+            .file_name()
+            .to_string_lossy()
+            .strip_prefix("i2c")
+            .and_then(|index| index.parse::<u8>().ok())
+        {
+            Some(index) => index,
+            None => continue,
+        };
+
+        if let Ok(node) = fs::read(entry.path()) {
+            let node = String::from_utf8_lossy(&node);
+            aliases.insert(node.trim_end_matches('\0').to_owned(), index);
+        }
+    }
+
+    aliases
+}
+
+// Returns the device tree node path for an I2C adapter.
+fn of_node(adapter: &Path) -> Option<String> {
+    let node = fs::canonicalize(adapter.join("of_node")).ok()?;
+    let base = fs::canonicalize(PATH_DT_BASE).ok()?;
+
+    Some(format!(
+        "/{}",
+        node.strip_prefix(base).ok()?.to_string_lossy()
+    ))
+}
+
+// Returns the parent bus and channel if the adapter is a multiplexer channel.
+fn mux_channel(adapter: &Path) -> Option<Controller> {
+    // mux_device links to the multiplexer's I2C client device, which is
+    // located below the parent adapter
+    let mux_device = fs::canonicalize(adapter.join("mux_device")).ok()?;
+    let parent = parse_bus(&mux_device.parent()?.file_name()?.to_string_lossy())?;
+
+    // The multiplexer links to each of its channels through channel-N
+    let adapter = fs::canonicalize(adapter).ok();
+    let channel = fs::read_dir(&mux_device).ok().and_then(|entries| {
+        entries.flatten().find_map(|entry| {
+            let channel = entry
+                .file_name()
+                .to_string_lossy()
+                .strip_prefix("channel-")?
+                .parse()
+                .ok()?;
+
+            if fs::canonicalize(entry.path()).ok() == adapter {
+                Some(channel)
+            } else {
+                None
+            }
+        })
+    });
+
+    Some(Controller::MuxChannel { parent, channel })
+}
@@ -0,0 +1,40 @@
+/// Configuration for an [`I2c`] instance.
+///
+/// `I2cConfig` contains the bus and slave address used to construct and
+/// configure an `I2c` instance through [`I2c::from_config`]. This is useful
+/// when the I2C configuration is loaded from a file.
+///
+/// With the optional `serde` feature enabled, `I2cConfig` is (de)serialized
+/// using kebab-case field names, and unknown fields are rejected. For
+/// instance, in TOML:
+///
+/// ```toml
+/// bus = 1
+/// slave-address = 0x20
+/// ```
+///
+/// [`I2c`]: struct.I2c.html
+/// [`I2c::from_config`]: struct.I2c.html#method.from_config
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(rename_all = "kebab-case", deny_unknown_fields)
+)]
+pub struct I2cConfig {
+    /// The selected I2C bus, as used by [`I2c::with_bus`].
+    ///
+    /// [`I2c::with_bus`]: struct.I2c.html#method.with_bus
+    pub bus: u8,
+    /// The 7-bit slave address, as used by [`I2c::set_slave_address`].
+    ///
+    /// [`I2c::set_slave_address`]: struct.I2c.html#method.set_slave_address
+    pub slave_address: u16,
+}
+
+impl I2cConfig {
+    /// Constructs a new `I2cConfig`.
+    pub fn new(bus: u8, slave_address: u16) -> I2cConfig {
+        I2cConfig { bus, slave_address }
+    }
+}
@@ -0,0 +1,62 @@
+// Forwards i2cdev calls to the ioctl backend, or to the simulated backend for
+// I2c instances that were constructed with a Simulator.
+
+use std::os::unix::io::AsRawFd;
+
+use libc::c_ulong;
+
+use crate::sys::DeviceNode;
+
+use super::ioctl::{Capabilities, MessageFlags, Result};
+
+dispatch_device_node! {
+    pub fn read(fd: c_int, buffer: &mut [u8]) -> Result<usize>;
+    pub fn write(fd: c_int, buffer: &[u8]) -> Result<usize>;
+    pub fn smbus_quick_command(fd: c_int, value: bool) -> Result<()>;
+    pub fn smbus_receive_byte(fd: c_int) -> Result<u8>;
+    pub fn smbus_send_byte(fd: c_int, value: u8) -> Result<()>;
+    pub fn smbus_read_byte(fd: c_int, command: u8) -> Result<u8>;
+    pub fn smbus_read_word(fd: c_int, command: u8) -> Result<u16>;
+    pub fn smbus_write_byte(fd: c_int, command: u8, value: u8) -> Result<()>;
+    pub fn smbus_write_word(fd: c_int, command: u8, value: u16) -> Result<()>;
+    pub fn smbus_process_call(fd: c_int, command: u8, value: u16) -> Result<u16>;
+    pub fn smbus_block_read(fd: c_int, command: u8, value: &mut [u8]) -> Result<usize>;
+    pub fn smbus_block_write(fd: c_int, command: u8, value: &[u8]) -> Result<()>;
+    pub fn i2c_block_read(fd: c_int, command: u8, value: &mut [u8]) -> Result<()>;
+    pub fn i2c_block_write(fd: c_int, command: u8, value: &[u8]) -> Result<()>;
+    pub fn i2c_write_read(
+        fd: c_int,
+        address: u16,
+        addr_10bit: bool,
+        write_buffer: &[u8],
+        read_buffer: &mut [u8],
+        stop: bool
+    ) -> Result<()>;
+    pub fn i2c_write_messages(
+        fd: c_int,
+        address: u16,
+        addr_10bit: bool,
+        messages: &[&[u8]],
+        stop: bool
+    ) -> Result<()>;
+    pub fn i2c_write_flags(
+        fd: c_int,
+        address: u16,
+        addr_10bit: bool,
+        buffer: &[u8],
+        flags: MessageFlags
+    ) -> Result<()>;
+    pub fn i2c_read_flags(
+        fd: c_int,
+        address: u16,
+        addr_10bit: bool,
+        buffer: &mut [u8],
+        flags: MessageFlags
+    ) -> Result<()>;
+    pub fn set_slave_address(fd: c_int, value: c_ulong) -> Result<()>;
+    pub fn set_addr_10bit(fd: c_int, value: c_ulong) -> Result<()>;
+    pub fn set_pec(fd: c_int, value: c_ulong) -> Result<()>;
+    pub fn set_timeout(fd: c_int, value: c_ulong) -> Result<()>;
+    pub fn set_retries(fd: c_int, value: c_ulong) -> Result<()>;
+    pub fn funcs(fd: c_int) -> Result<Capabilities>;
+}
@@ -0,0 +1,91 @@
+#[cfg(feature = "test-faults")]
+use std::fmt;
+
+#[cfg(feature = "test-faults")]
+use super::Error;
+
+/// I2C transaction types passed to fault injection callbacks.
+///
+/// Methods that only differ in byte order, such as [`smbus_read_word`] and
+/// [`smbus_read_word_swapped`], are reported as the same operation.
+///
+/// [`smbus_read_word`]: struct.I2c.html#method.smbus_read_word
+/// [`smbus_read_word_swapped`]: struct.I2c.html#method.smbus_read_word_swapped
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[non_exhaustive]
+pub enum Operation {
+    /// [`I2c::read`](struct.I2c.html#method.read)
+    Read,
+    /// [`I2c::write`](struct.I2c.html#method.write)
+    Write,
+    /// [`I2c::write_read`](struct.I2c.html#method.write_read)
+    WriteRead,
+    /// [`I2c::block_read`](struct.I2c.html#method.block_read)
+    BlockRead,
+    /// [`I2c::block_write`](struct.I2c.html#method.block_write)
+    BlockWrite,
+    /// [`I2c::write_registers`](struct.I2c.html#method.write_registers) and
+    /// [`I2c::init_sequence`](struct.I2c.html#method.init_sequence)
+    WriteRegisters,
+    /// [`I2c::smbus_quick_command`](struct.I2c.html#method.smbus_quick_command)
+    SmbusQuickCommand,
+    /// [`I2c::smbus_receive_byte`](struct.I2c.html#method.smbus_receive_byte)
+    SmbusReceiveByte,
+    /// [`I2c::smbus_send_byte`](struct.I2c.html#method.smbus_send_byte)
+    SmbusSendByte,
+    /// [`I2c::smbus_read_byte`](struct.I2c.html#method.smbus_read_byte)
+    SmbusReadByte,
+    /// [`I2c::smbus_write_byte`](struct.I2c.html#method.smbus_write_byte)
+    SmbusWriteByte,
+    /// [`I2c::smbus_read_word`](struct.I2c.html#method.smbus_read_word)
+    SmbusReadWord,
+    /// [`I2c::smbus_write_word`](struct.I2c.html#method.smbus_write_word)
+    SmbusWriteWord,
+    /// [`I2c::smbus_process_call`](struct.I2c.html#method.smbus_process_call)
+    SmbusProcessCall,
+    /// [`I2c::smbus_block_read`](struct.I2c.html#method.smbus_block_read)
+    SmbusBlockRead,
+    /// [`I2c::smbus_block_write`](struct.I2c.html#method.smbus_block_write)
+    SmbusBlockWrite,
+}
+
+#[cfg(feature = "test-faults")]
+pub(crate) type FaultInjector = Box<dyn Fn(Operation) -> Option<Error> + Send>;
+#[cfg(feature = "test-faults")]
+pub(crate) type DataCorruptor = Box<dyn Fn(Operation, &mut [u8]) + Send>;
+
+// Callbacks configured through I2c::set_fault_injector and
+// I2c::set_data_corruptor.
+#[cfg(feature = "test-faults")]
+#[derive(Default)]
+pub(crate) struct Faults {
+    pub injector: Option<FaultInjector>,
+    pub corruptor: Option<DataCorruptor>,
+}
+
+#[cfg(feature = "test-faults")]
+impl Faults {
+    // Returns the error the fault injector wants operation to fail with, if any.
+    pub fn inject(&self, operation: Operation) -> Option<Error> {
+        self.injector
+            .as_ref()
+            .and_then(|injector| injector(operation))
+    }
+
+    // Lets the data corruptor modify the incoming data for operation.
+    pub fn corrupt(&self, operation: Operation, data: &mut [u8]) {
+        if let Some(ref corruptor) = self.corruptor {
+            corruptor(operation, data);
+        }
+    }
+}
+
+#[cfg(feature = "test-faults")]
+impl fmt::Debug for Faults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Faults")
+            .field("injector", &self.injector.is_some())
+            .field("corruptor", &self.corruptor.is_some())
+            .finish()
+    }
+}
@@ -1,26 +1,36 @@
+#[cfg(feature = "hal-1")]
 use embedded_hal::i2c::{self, ErrorType, I2c as I2cHal, Operation as I2cOperation};
 
-use super::{Error, I2c};
+use super::{BitBangI2c, Error, I2c};
 
 /// `Write` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::i2c::Write for I2c {
     type Error = Error;
 
     fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
-        I2cHal::write(self, address, bytes)
+        self.set_slave_address(u16::from(address))?;
+        I2c::write(self, bytes)?;
+
+        Ok(())
     }
 }
 
 /// `Read` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::i2c::Read for I2c {
     type Error = Error;
 
     fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
-        I2cHal::read(self, address, buffer)
+        self.set_slave_address(u16::from(address))?;
+        I2c::read(self, buffer)?;
+
+        Ok(())
     }
 }
 
 /// `WriteRead` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::i2c::WriteRead for I2c {
     type Error = Error;
 
@@ -30,31 +40,41 @@ impl embedded_hal_0::blocking::i2c::WriteRead for I2c {
         bytes: &[u8],
         buffer: &mut [u8],
     ) -> Result<(), Self::Error> {
-        I2cHal::write_read(self, address, bytes, buffer)
+        self.set_slave_address(u16::from(address))?;
+        I2c::write_read(self, bytes, buffer)?;
+
+        Ok(())
     }
 }
 
+#[cfg(feature = "hal-1")]
 impl ErrorType for I2c {
     type Error = Error;
 }
 
+#[cfg(feature = "hal-1")]
 impl i2c::Error for Error {
     fn kind(&self) -> i2c::ErrorKind {
-        if let Error::Io(e) = self {
-            use std::io::ErrorKind::*;
+        match self {
+            Error::Io(e) => {
+                use std::io::ErrorKind::*;
 
-            match e.kind() {
-                /* ResourceBusy | */ InvalidData => i2c::ErrorKind::Bus,
-                WouldBlock => i2c::ErrorKind::ArbitrationLoss,
-                _ => i2c::ErrorKind::Other,
+                match e.kind() {
+                    /* ResourceBusy | */ InvalidData => i2c::ErrorKind::Bus,
+                    WouldBlock => i2c::ErrorKind::ArbitrationLoss,
+                    _ => i2c::ErrorKind::Other,
+                }
             }
-        } else {
-            i2c::ErrorKind::Other
+            Error::DeviceNotFound(_) => {
+                i2c::ErrorKind::NoAcknowledge(i2c::NoAcknowledgeSource::Address)
+            }
+            _ => i2c::ErrorKind::Other,
         }
     }
 }
 
 /// `I2c` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
 impl I2cHal for I2c {
     fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
         self.set_slave_address(u16::from(address))?;
@@ -122,3 +142,157 @@ impl I2cHal for I2c {
         self.transaction(address, &mut ops)
     }
 }
+
+/// `Write` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
+impl embedded_hal_0::blocking::i2c::Write for BitBangI2c {
+    type Error = Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.set_slave_address(u16::from(address))?;
+        BitBangI2c::write(self, bytes)?;
+
+        Ok(())
+    }
+}
+
+/// `Read` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
+impl embedded_hal_0::blocking::i2c::Read for BitBangI2c {
+    type Error = Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.set_slave_address(u16::from(address))?;
+        BitBangI2c::read(self, buffer)?;
+
+        Ok(())
+    }
+}
+
+/// `WriteRead` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
+impl embedded_hal_0::blocking::i2c::WriteRead for BitBangI2c {
+    type Error = Error;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.set_slave_address(u16::from(address))?;
+        BitBangI2c::write_read(self, bytes, buffer)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "hal-1")]
+impl ErrorType for BitBangI2c {
+    type Error = Error;
+}
+
+/// `I2c` trait implementation for `embedded-hal` v1.0.0-alpha.9.
+#[cfg(feature = "hal-1")]
+impl I2cHal for BitBangI2c {
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.set_slave_address(u16::from(address))?;
+        BitBangI2c::write(self, bytes)?;
+
+        Ok(())
+    }
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.set_slave_address(u16::from(address))?;
+        BitBangI2c::read(self, buffer)?;
+
+        Ok(())
+    }
+
+    fn write_iter<B>(&mut self, address: u8, bytes: B) -> Result<(), Self::Error>
+    where
+        B: IntoIterator<Item = u8>,
+    {
+        let bytes: Vec<_> = bytes.into_iter().collect();
+        I2cHal::write(self, address, &bytes)
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.set_slave_address(u16::from(address))?;
+        BitBangI2c::write_read(self, bytes, buffer)
+    }
+
+    fn write_iter_read<B>(
+        &mut self,
+        address: u8,
+        bytes: B,
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error>
+    where
+        B: IntoIterator<Item = u8>,
+    {
+        let bytes: Vec<_> = bytes.into_iter().collect();
+        self.transaction(
+            address,
+            &mut [I2cOperation::Write(&bytes), I2cOperation::Read(buffer)],
+        )
+    }
+
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [I2cOperation],
+    ) -> Result<(), Self::Error> {
+        self.set_slave_address(u16::from(address))?;
+
+        let len = operations.len();
+        let mut result = Ok(());
+        let mut prev_read = None;
+
+        for idx in 0..len {
+            let read = matches!(operations[idx], I2cOperation::Read(_));
+
+            // Adjacent operations of the same type are sent without a
+            // repeated START or the slave address in between.
+            if prev_read != Some(read) {
+                result = self.start().and_then(|_| self.write_address(read));
+                if result.is_err() {
+                    break;
+                }
+            }
+
+            // NACK the last byte of a read, unless the next operation
+            // continues reading without a repeated START
+            let last = idx == len - 1 || !matches!(operations[idx + 1], I2cOperation::Read(_));
+
+            result = match operations[idx] {
+                I2cOperation::Read(ref mut buffer) => self.read_bytes(buffer, last),
+                I2cOperation::Write(buffer) => self.write_bytes(buffer),
+            };
+            if result.is_err() {
+                break;
+            }
+
+            prev_read = Some(read);
+        }
+
+        if prev_read.is_none() && result.is_ok() {
+            return Ok(());
+        }
+
+        self.finish(result)
+    }
+
+    fn transaction_iter<'a, O>(&mut self, address: u8, operations: O) -> Result<(), Self::Error>
+    where
+        O: IntoIterator<Item = I2cOperation<'a>>,
+    {
+        let mut ops: Vec<_> = operations.into_iter().collect();
+        self.transaction(address, &mut ops)
+    }
+}
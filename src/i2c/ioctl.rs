@@ -3,7 +3,7 @@ use std::io;
 use std::ptr;
 use std::result;
 
-use libc::{self, c_int, c_ulong, ioctl};
+use libc::{self, c_int, c_ulong, c_void, ioctl};
 
 #[cfg(target_env = "gnu")]
 type IoctlLong = libc::c_ulong;
@@ -48,7 +48,7 @@ impl Capabilities {
     ///
     /// `Capabilities` indicates which I2C features and SMBus protocols
     /// are supported by the underlying drivers.
-    fn new(funcs: c_ulong) -> Capabilities {
+    pub(super) fn new(funcs: c_ulong) -> Capabilities {
         Capabilities { funcs }
     }
 
@@ -174,6 +174,107 @@ impl fmt::Debug for Capabilities {
     }
 }
 
+/// Protocol mangling flags applied to a single I2C message.
+///
+/// `MessageFlags` alters how the I2C bus driver handles acknowledgements
+/// during [`I2c::write_with_flags`] and [`I2c::read_with_flags`]. The flags
+/// deliberately break the I2C protocol to work around non-compliant slave
+/// devices, and should only be enabled when a device's datasheet requires
+/// it.
+///
+/// The flags are only honored by bus drivers that support protocol
+/// mangling, such as the bit-banged `i2c-gpio` bus. The hardware I2C
+/// controllers on the Raspberry Pi ignore them.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use rppal::i2c::{Error, I2c, MessageFlags};
+/// use rppal::mock::Simulator;
+///
+/// let simulator = Simulator::new();
+/// simulator.enable_i2c_bus(1);
+///
+/// let mut i2c = I2c::with_simulator(&simulator)?;
+/// i2c.set_slave_address(0x50)?;
+///
+/// let mut flags = MessageFlags::new();
+/// assert!(matches!(
+///     i2c.write_with_flags(&[0x00], flags),
+///     Err(Error::DeviceNotFound(0x50))
+/// ));
+///
+/// // Without a device on the bus, every byte is NAKed
+/// flags.set_ignore_nak(true);
+/// i2c.write_with_flags(&[0x00], flags)?;
+/// # }
+/// # Ok::<(), rppal::i2c::Error>(())
+/// ```
+///
+/// [`I2c::write_with_flags`]: struct.I2c.html#method.write_with_flags
+/// [`I2c::read_with_flags`]: struct.I2c.html#method.read_with_flags
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct MessageFlags {
+    flags: u16,
+}
+
+impl MessageFlags {
+    /// Constructs a new `MessageFlags` with all flags disabled.
+    pub fn new() -> MessageFlags {
+        MessageFlags { flags: 0 }
+    }
+
+    /// Returns `true` if NAKs sent by the slave device are ignored.
+    pub fn ignore_nak(self) -> bool {
+        (self.flags & RDWR_FLAG_IGNORE_NAK) > 0
+    }
+
+    /// When enabled, a NAK sent by the slave device in response to its
+    /// address or a data byte is treated as an ACK, and the message
+    /// continues as if the byte was acknowledged (`I2C_M_IGNORE_NAK`).
+    ///
+    /// Use this for devices that are known to NAK bytes they did receive,
+    /// such as devices that NAK the final byte of a write, or that NAK
+    /// their address while busy but still need to receive the remainder of
+    /// a message. Because every NAK is ignored, the transaction succeeds even
+    /// if no device is connected, or the device discarded the data. Don't
+    /// use it to detect whether a device is busy.
+    pub fn set_ignore_nak(&mut self, ignore_nak: bool) {
+        if ignore_nak {
+            self.flags |= RDWR_FLAG_IGNORE_NAK;
+        } else {
+            self.flags &= !RDWR_FLAG_IGNORE_NAK;
+        }
+    }
+
+    /// Returns `true` if the master doesn't acknowledge bytes it reads.
+    pub fn no_read_ack(self) -> bool {
+        (self.flags & RDWR_FLAG_NO_RD_ACK) > 0
+    }
+
+    /// When enabled, the master doesn't send an ACK or NAK after receiving
+    /// a byte during a read, and skips that clock cycle entirely
+    /// (`I2C_M_NO_RD_ACK`).
+    ///
+    /// Use this only for devices whose datasheet specifies reads without
+    /// an acknowledge bit. A compliant device that expects an ACK will lose
+    /// synchronization, and may keep driving SDA low, which requires
+    /// [`I2c::recover_bus`] to resolve. Only applies to reads.
+    ///
+    /// [`I2c::recover_bus`]: struct.I2c.html#method.recover_bus
+    pub fn set_no_read_ack(&mut self, no_read_ack: bool) {
+        if no_read_ack {
+            self.flags |= RDWR_FLAG_NO_RD_ACK;
+        } else {
+            self.flags &= !RDWR_FLAG_NO_RD_ACK;
+        }
+    }
+
+    pub(crate) fn is_empty(self) -> bool {
+        self.flags == 0
+    }
+}
+
 // ioctl() requests supported by i2cdev
 const REQ_RETRIES: IoctlLong = 0x0701; // How many retries when waiting for an ACK
 const REQ_TIMEOUT: IoctlLong = 0x0702; // Timeout in 10ms units
@@ -190,6 +291,8 @@ const REQ_SMBUS: IoctlLong = 0x0720; // SMBus: Transfer data
 
 const RDWR_FLAG_RD: u16 = 0x0001; // Read operation
 const RDWR_FLAG_TEN: u16 = 0x0010; // 10-bit slave address
+const RDWR_FLAG_NO_RD_ACK: u16 = 0x0800; // Skip the ACK/NAK bit on reads (requires FUNC_PROTOCOL_MANGLING)
+const RDWR_FLAG_IGNORE_NAK: u16 = 0x1000; // Treat NAK from the slave as ACK (requires FUNC_PROTOCOL_MANGLING)
 
 const RDWR_MSG_MAX: usize = 42; // Maximum messages per RDWR operation
 const SMBUS_BLOCK_MAX: usize = 32; // Maximum bytes per block transfer
@@ -299,11 +402,27 @@ fn smbus_request(
         },
     };
 
-    parse_retval!(unsafe { ioctl(fd, REQ_SMBUS, &mut request) })?;
+    retry_interrupted!(parse_retval!(unsafe { ioctl(fd, REQ_SMBUS, &mut request) }))?;
 
     Ok(())
 }
 
+// Plain reads and writes on the device node, which the driver completes as a
+// single transfer
+pub fn read(fd: c_int, buffer: &mut [u8]) -> Result<usize> {
+    let length =
+        parse_retval!(unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut c_void, buffer.len()) })?;
+
+    Ok(length as usize)
+}
+
+pub fn write(fd: c_int, buffer: &[u8]) -> Result<usize> {
+    let length =
+        parse_retval!(unsafe { libc::write(fd, buffer.as_ptr() as *const c_void, buffer.len()) })?;
+
+    Ok(length as usize)
+}
+
 pub fn smbus_quick_command(fd: c_int, value: bool) -> Result<()> {
     // Quick Command uses the read_write field, instead of the data buffer
     smbus_request(
@@ -495,12 +614,16 @@ struct RdwrRequest {
     nmsgs: u32,
 }
 
+// Sends write_buffer, followed by a repeated START and a read into
+// read_buffer. If stop is true, the write and read are sent as separate RDWR
+// requests instead, which results in a STOP followed by a START in between.
 pub fn i2c_write_read(
     fd: c_int,
     address: u16,
     addr_10bit: bool,
     write_buffer: &[u8],
     read_buffer: &mut [u8],
+    stop: bool,
 ) -> Result<()> {
     // 0 length buffers may cause issues
     if write_buffer.is_empty() || read_buffer.is_empty() {
@@ -526,12 +649,101 @@ pub fn i2c_write_read(
     };
 
     let mut segments: [RdwrSegment; 2] = [segment_write, segment_read];
+    let segments_per_request = if stop { 1 } else { 2 };
+
+    for chunk in segments.chunks_mut(segments_per_request) {
+        let mut request = RdwrRequest {
+            nmsgs: chunk.len() as u32,
+            segments: chunk,
+        };
+
+        retry_interrupted!(parse_retval!(unsafe { ioctl(fd, REQ_RDWR, &mut request) }))?;
+    }
+
+    Ok(())
+}
+
+// Sends each message as a separate write, separated by a repeated START, with
+// a single STOP at the end. Messages are split into multiple RDWR requests if
+// there are more than RDWR_MSG_MAX. If stop is true, each message is sent as
+// a separate RDWR request, which results in a STOP after every message.
+pub fn i2c_write_messages(
+    fd: c_int,
+    address: u16,
+    addr_10bit: bool,
+    messages: &[&[u8]],
+    stop: bool,
+) -> Result<()> {
+    let messages_per_request = if stop { 1 } else { RDWR_MSG_MAX };
+
+    for chunk in messages.chunks(messages_per_request) {
+        let mut segments: Vec<RdwrSegment> = chunk
+            .iter()
+            .map(|message| RdwrSegment {
+                addr: address,
+                flags: if addr_10bit { RDWR_FLAG_TEN } else { 0 },
+                len: message.len() as u16,
+                data: message.as_ptr() as usize,
+            })
+            .collect();
+
+        let mut request = RdwrRequest {
+            segments: &mut segments[..],
+            nmsgs: segments.len() as u32,
+        };
+
+        retry_interrupted!(parse_retval!(unsafe { ioctl(fd, REQ_RDWR, &mut request) }))?;
+    }
+
+    Ok(())
+}
+
+// Sends a single write message with the specified protocol mangling flags
+pub fn i2c_write_flags(
+    fd: c_int,
+    address: u16,
+    addr_10bit: bool,
+    buffer: &[u8],
+    flags: MessageFlags,
+) -> Result<()> {
+    let mut segments = [RdwrSegment {
+        addr: address,
+        flags: flags.flags | if addr_10bit { RDWR_FLAG_TEN } else { 0 },
+        len: buffer.len() as u16,
+        data: buffer.as_ptr() as usize,
+    }];
+
+    let mut request = RdwrRequest {
+        segments: &mut segments[..],
+        nmsgs: 1,
+    };
+
+    retry_interrupted!(parse_retval!(unsafe { ioctl(fd, REQ_RDWR, &mut request) }))?;
+
+    Ok(())
+}
+
+// Sends a single read message with the specified protocol mangling flags
+pub fn i2c_read_flags(
+    fd: c_int,
+    address: u16,
+    addr_10bit: bool,
+    buffer: &mut [u8],
+    flags: MessageFlags,
+) -> Result<()> {
+    let mut segments = [RdwrSegment {
+        addr: address,
+        flags: flags.flags | RDWR_FLAG_RD | if addr_10bit { RDWR_FLAG_TEN } else { 0 },
+        len: buffer.len() as u16,
+        data: buffer.as_mut_ptr() as usize,
+    }];
+
     let mut request = RdwrRequest {
-        segments: &mut segments,
-        nmsgs: 2,
+        segments: &mut segments[..],
+        nmsgs: 1,
     };
 
-    parse_retval!(unsafe { ioctl(fd, REQ_RDWR, &mut request) })?;
+    retry_interrupted!(parse_retval!(unsafe { ioctl(fd, REQ_RDWR, &mut request) }))?;
 
     Ok(())
 }
@@ -0,0 +1,297 @@
+use std::io;
+use std::result;
+
+use libc::{self, c_int, c_ulong};
+
+use super::ioctl::{Capabilities, MessageFlags};
+use crate::mock::{self, NodeHandle, Simulator};
+
+pub type Result<T> = result::Result<T, io::Error>;
+
+// Simulated replacement for the i2cdev interface. Follows the same function
+// signatures as the ioctl module. SMBus protocols are emulated using plain
+// I2C transfers, similar to the kernel's SMBus emulation.
+
+// I2C, protocol mangling and emulated SMBus, matching the functionality
+// reported by i2c-bcm2835
+const FUNCS: c_ulong = 0x0eff_000d;
+
+const SMBUS_BLOCK_MAX: usize = 32;
+// Maximum messages per RDWR operation
+const RDWR_MSG_MAX: usize = 42;
+
+struct Node {
+    simulator: Simulator,
+    bus: u8,
+    address: u16,
+}
+
+pub fn open(simulator: &Simulator, bus: u8) -> Result<NodeHandle> {
+    if !simulator.i2c_bus_enabled(bus) {
+        return Err(io::Error::from_raw_os_error(libc::ENOENT));
+    }
+
+    mock::open(Node {
+        simulator: simulator.clone(),
+        bus,
+        address: 0,
+    })
+}
+
+fn write_to(fd: c_int, address: Option<u16>, data: &[u8]) -> Result<()> {
+    mock::with_node(fd, |node: &mut Node| {
+        node.simulator
+            .i2c_write(node.bus, address.unwrap_or(node.address), data)
+    })
+}
+
+fn read_from(fd: c_int, address: Option<u16>, buffer: &mut [u8]) -> Result<()> {
+    mock::with_node(fd, |node: &mut Node| {
+        node.simulator
+            .i2c_read(node.bus, address.unwrap_or(node.address), buffer)
+    })
+}
+
+// Counts a single read, write or ioctl call on the i2cdev interface.
+fn request(fd: c_int) -> Result<()> {
+    mock::with_node(fd, |node: &mut Node| {
+        node.simulator.count_i2c_request();
+
+        Ok(())
+    })
+}
+
+pub fn read(fd: c_int, buffer: &mut [u8]) -> Result<usize> {
+    request(fd)?;
+    read_from(fd, None, buffer)?;
+
+    Ok(buffer.len())
+}
+
+pub fn write(fd: c_int, buffer: &[u8]) -> Result<usize> {
+    request(fd)?;
+    write_to(fd, None, buffer)?;
+
+    Ok(buffer.len())
+}
+
+pub fn smbus_quick_command(fd: c_int, value: bool) -> Result<()> {
+    request(fd)?;
+    // The R/W bit is the only data sent
+    if value {
+        read_from(fd, None, &mut [])
+    } else {
+        write_to(fd, None, &[])
+    }
+}
+
+pub fn smbus_receive_byte(fd: c_int) -> Result<u8> {
+    request(fd)?;
+    let mut buffer = [0u8; 1];
+    read_from(fd, None, &mut buffer)?;
+
+    Ok(buffer[0])
+}
+
+pub fn smbus_send_byte(fd: c_int, value: u8) -> Result<()> {
+    request(fd)?;
+    write_to(fd, None, &[value])
+}
+
+pub fn smbus_read_byte(fd: c_int, command: u8) -> Result<u8> {
+    request(fd)?;
+    let mut buffer = [0u8; 1];
+    write_to(fd, None, &[command])?;
+    read_from(fd, None, &mut buffer)?;
+
+    Ok(buffer[0])
+}
+
+pub fn smbus_read_word(fd: c_int, command: u8) -> Result<u16> {
+    request(fd)?;
+    let mut buffer = [0u8; 2];
+    write_to(fd, None, &[command])?;
+    read_from(fd, None, &mut buffer)?;
+
+    // Low byte is received first (SMBus 3.1 spec @ 6.5.5)
+    Ok(u16::from_le_bytes(buffer))
+}
+
+pub fn smbus_write_byte(fd: c_int, command: u8, value: u8) -> Result<()> {
+    request(fd)?;
+    write_to(fd, None, &[command, value])
+}
+
+pub fn smbus_write_word(fd: c_int, command: u8, value: u16) -> Result<()> {
+    request(fd)?;
+    let value = value.to_le_bytes();
+
+    write_to(fd, None, &[command, value[0], value[1]])
+}
+
+pub fn smbus_process_call(fd: c_int, command: u8, value: u16) -> Result<u16> {
+    request(fd)?;
+    let value = value.to_le_bytes();
+    let mut buffer = [0u8; 2];
+    write_to(fd, None, &[command, value[0], value[1]])?;
+    read_from(fd, None, &mut buffer)?;
+
+    // Low byte is received first (SMBus 3.1 spec @ 6.5.6)
+    Ok(u16::from_le_bytes(buffer))
+}
+
+pub fn smbus_block_read(fd: c_int, command: u8, value: &mut [u8]) -> Result<usize> {
+    request(fd)?;
+    // The byte count is read in the same transfer as the data, so the
+    // maximum block size is requested
+    let mut buffer = [0u8; SMBUS_BLOCK_MAX + 1];
+    write_to(fd, None, &[command])?;
+    read_from(fd, None, &mut buffer)?;
+
+    let incoming_length = (buffer[0] as usize).min(SMBUS_BLOCK_MAX);
+    let copy_length = incoming_length.min(value.len());
+    value[..copy_length].copy_from_slice(&buffer[1..=copy_length]);
+
+    Ok(incoming_length)
+}
+
+pub fn smbus_block_write(fd: c_int, command: u8, value: &[u8]) -> Result<()> {
+    request(fd)?;
+    let length = value.len().min(SMBUS_BLOCK_MAX);
+
+    let mut buffer = Vec::with_capacity(length + 2);
+    buffer.push(command);
+    buffer.push(length as u8);
+    buffer.extend_from_slice(&value[..length]);
+
+    write_to(fd, None, &buffer)
+}
+
+pub fn i2c_block_read(fd: c_int, command: u8, value: &mut [u8]) -> Result<()> {
+    request(fd)?;
+    let length = value.len().min(SMBUS_BLOCK_MAX);
+    write_to(fd, None, &[command])?;
+    read_from(fd, None, &mut value[..length])
+}
+
+pub fn i2c_block_write(fd: c_int, command: u8, value: &[u8]) -> Result<()> {
+    request(fd)?;
+    let length = value.len().min(SMBUS_BLOCK_MAX);
+
+    let mut buffer = Vec::with_capacity(length + 1);
+    buffer.push(command);
+    buffer.extend_from_slice(&value[..length]);
+
+    write_to(fd, None, &buffer)
+}
+
+pub fn i2c_write_read(
+    fd: c_int,
+    address: u16,
+    _addr_10bit: bool,
+    write_buffer: &[u8],
+    read_buffer: &mut [u8],
+    stop: bool,
+) -> Result<()> {
+    // 0 length buffers may cause issues
+    if write_buffer.is_empty() || read_buffer.is_empty() {
+        return Ok(());
+    }
+
+    // The write and read are sent as separate requests when stop is true
+    for _ in 0..if stop { 2 } else { 1 } {
+        request(fd)?;
+    }
+
+    write_to(fd, Some(address), write_buffer)?;
+    read_from(fd, Some(address), read_buffer)
+}
+
+pub fn i2c_write_messages(
+    fd: c_int,
+    address: u16,
+    _addr_10bit: bool,
+    messages: &[&[u8]],
+    stop: bool,
+) -> Result<()> {
+    let requests = if stop {
+        messages.len()
+    } else {
+        (messages.len() + RDWR_MSG_MAX - 1) / RDWR_MSG_MAX
+    };
+
+    for _ in 0..requests {
+        request(fd)?;
+    }
+
+    for message in messages {
+        write_to(fd, Some(address), message)?;
+    }
+
+    Ok(())
+}
+
+// NAKs are reported as ENXIO. When they're ignored, the master continues as
+// if the bytes were acknowledged. An absent device leaves SDA high, so reads
+// return 0xFF.
+pub fn i2c_write_flags(
+    fd: c_int,
+    address: u16,
+    _addr_10bit: bool,
+    buffer: &[u8],
+    flags: MessageFlags,
+) -> Result<()> {
+    request(fd)?;
+
+    match write_to(fd, Some(address), buffer) {
+        Err(ref err) if flags.ignore_nak() && err.raw_os_error() == Some(libc::ENXIO) => Ok(()),
+        result => result,
+    }
+}
+
+pub fn i2c_read_flags(
+    fd: c_int,
+    address: u16,
+    _addr_10bit: bool,
+    buffer: &mut [u8],
+    flags: MessageFlags,
+) -> Result<()> {
+    request(fd)?;
+
+    match read_from(fd, Some(address), buffer) {
+        Err(ref err) if flags.ignore_nak() && err.raw_os_error() == Some(libc::ENXIO) => {
+            buffer.iter_mut().for_each(|byte| *byte = 0xff);
+
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+pub fn set_slave_address(fd: c_int, value: c_ulong) -> Result<()> {
+    mock::with_node(fd, |node: &mut Node| {
+        node.address = value as u16;
+
+        Ok(())
+    })
+}
+
+pub fn set_addr_10bit(fd: c_int, _value: c_ulong) -> Result<()> {
+    mock::with_node(fd, |_: &mut Node| Ok(()))
+}
+
+pub fn set_pec(fd: c_int, _value: c_ulong) -> Result<()> {
+    mock::with_node(fd, |_: &mut Node| Ok(()))
+}
+
+pub fn set_timeout(fd: c_int, _value: c_ulong) -> Result<()> {
+    mock::with_node(fd, |_: &mut Node| Ok(()))
+}
+
+pub fn set_retries(fd: c_int, _value: c_ulong) -> Result<()> {
+    mock::with_node(fd, |_: &mut Node| Ok(()))
+}
+
+pub fn funcs(fd: c_int) -> Result<Capabilities> {
+    mock::with_node(fd, |_: &mut Node| Ok(Capabilities::new(FUNCS)))
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// A single transfer between the master and an I2C slave device.
+///
+/// SMBus transactions are stored as the plain I2C transfers they consist
+/// of. For example, [`smbus_read_byte`] is stored as a `Write` containing the
+/// command byte, followed by a `Read` containing the incoming byte.
+///
+/// [`smbus_read_byte`]: struct.I2c.html#method.smbus_read_byte
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Transfer {
+    /// Data was written to the slave device.
+    Write { address: u16, data: Vec<u8> },
+    /// Data was read from the slave device.
+    Read { address: u16, data: Vec<u8> },
+}
+
+impl Transfer {
+    /// Returns the slave address.
+    pub fn address(&self) -> u16 {
+        match self {
+            Transfer::Write { address, .. } | Transfer::Read { address, .. } => *address,
+        }
+    }
+
+    /// Returns the data that was transferred.
+    pub fn data(&self) -> &[u8] {
+        match self {
+            Transfer::Write { data, .. } | Transfer::Read { data, .. } => data,
+        }
+    }
+}
+
+impl fmt::Display for Transfer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transfer::Write { address, .. } => write!(f, "W 0x{:02X}", address)?,
+            Transfer::Read { address, .. } => write!(f, "R 0x{:02X}", address)?,
+        }
+
+        for byte in self.data() {
+            write!(f, " {:02X}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Parses a single line in the format produced by Transfer's Display impl.
+fn parse_transfer(line: &str) -> Option<Transfer> {
+    let mut fields = line.split_whitespace();
+
+    let direction = fields.next()?;
+    let address = fields.next()?;
+    let address = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))?;
+    let address = u16::from_str_radix(address, 16).ok()?;
+
+    let mut data = Vec::new();
+    for byte in fields {
+        data.push(u8::from_str_radix(byte, 16).ok()?);
+    }
+
+    match direction {
+        "W" => Some(Transfer::Write { address, data }),
+        "R" => Some(Transfer::Read { address, data }),
+        _ => None,
+    }
+}
+
+/// A sequence of I2C transfers captured by [`I2c::start_recording`].
+///
+/// Recordings are stored as plain text, with one transfer per line. Each
+/// line starts with `W` for writes or `R` for reads, followed by the slave
+/// address and the transferred bytes in hexadecimal. Empty lines, and lines
+/// starting with `#`, are ignored.
+///
+/// ```text
+/// # TMP102 temperature read
+/// W 0x48 00
+/// R 0x48 19 80
+/// ```
+///
+/// With the optional `mock` feature enabled, a `Recording` can be replayed
+/// against application code with [`Simulator::replay_i2c`].
+///
+/// [`I2c::start_recording`]: struct.I2c.html#method.start_recording
+/// [`Simulator::replay_i2c`]: ../mock/struct.Simulator.html#method.replay_i2c
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Recording {
+    transfers: Vec<Transfer>,
+}
+
+impl Recording {
+    /// Constructs a new, empty `Recording`.
+    pub fn new() -> Recording {
+        Recording::default()
+    }
+
+    /// Returns the recorded transfers, in chronological order.
+    pub fn transfers(&self) -> &[Transfer] {
+        &self.transfers
+    }
+
+    /// Appends a transfer.
+    pub fn push(&mut self, transfer: Transfer) {
+        self.transfers.push(transfer);
+    }
+
+    /// Returns `true` if the recording doesn't contain any transfers.
+    pub fn is_empty(&self) -> bool {
+        self.transfers.is_empty()
+    }
+
+    /// Returns the number of recorded transfers.
+    pub fn len(&self) -> usize {
+        self.transfers.len()
+    }
+
+    /// Reads a recording from `reader`.
+    ///
+    /// Returns an error with kind [`InvalidData`] if a line can't be parsed.
+    ///
+    /// [`InvalidData`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData
+    pub fn read_from<R: BufRead>(reader: R) -> io::Result<Recording> {
+        let mut recording = Recording::new();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let transfer = parse_transfer(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid I2C transfer on line {}: {}", index + 1, line),
+                )
+            })?;
+
+            recording.push(transfer);
+        }
+
+        Ok(recording)
+    }
+
+    /// Writes the recording to `writer`.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for transfer in &self.transfers {
+            writeln!(writer, "{}", transfer)?;
+        }
+
+        writer.flush()
+    }
+
+    /// Reads a recording from the file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Recording> {
+        Recording::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the recording to the file at `path`, replacing any existing
+    /// contents.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+}
@@ -0,0 +1,326 @@
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::sync::atomic::{self, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use libc::{off_t, MAP_FAILED, MAP_SHARED, O_SYNC, PROT_READ, PROT_WRITE};
+
+use crate::gpio::{Gpio, IoPin, Mode};
+use crate::i2c::{Error, Result};
+use crate::system::{DeviceInfo, SoC};
+
+const PATH_DEV_MEM: &str = "/dev/mem";
+
+const BSC_SLAVE_OFFSET: u64 = 0x0021_4000;
+const BSC_SLAVE_SIZE: usize = 4096;
+
+// Register offsets in 32-bit words
+const REG_DR: usize = 0;
+const REG_RSR: usize = 0x04 / std::mem::size_of::<u32>();
+const REG_SLV: usize = 0x08 / std::mem::size_of::<u32>();
+const REG_CR: usize = 0x0c / std::mem::size_of::<u32>();
+const REG_FR: usize = 0x10 / std::mem::size_of::<u32>();
+const REG_IMSC: usize = 0x18 / std::mem::size_of::<u32>();
+const REG_ICR: usize = 0x24 / std::mem::size_of::<u32>();
+
+const CR_EN: u32 = 1 << 0;
+const CR_I2C: u32 = 1 << 2;
+const CR_BRK: u32 = 1 << 7;
+const CR_TXE: u32 = 1 << 8;
+const CR_RXE: u32 = 1 << 9;
+
+const FR_RXFE: u32 = 1 << 1;
+const FR_TXFF: u32 = 1 << 2;
+const FR_TXFE: u32 = 1 << 4;
+
+const ICR_ALL: u32 = 0x0f;
+
+// Time between FIFO status checks in the blocking read and write methods.
+// The FIFOs hold 16 bytes, which take about 1.4 ms to transfer at 100 kHz.
+const POLL_INTERVAL: Duration = Duration::from_micros(100);
+
+/// Provides access to the Raspberry Pi's BSC slave peripheral, which lets
+/// the Raspberry Pi act as an I2C slave device for another I2C master.
+///
+/// The BSC slave peripheral is separate from the BSC controllers used by
+/// [`I2c`], and isn't supported by the standard Linux drivers. `I2cSlave`
+/// accesses its registers directly through `/dev/mem`, which requires
+/// superuser privileges.
+///
+/// The peripheral uses fixed pins, which are switched to their ALT3 function
+/// while the `I2cSlave` instance exists, and restored to their original mode
+/// when it goes out of scope.
+///
+/// * Raspberry Pi 3 B+ and earlier: SDA on BCM GPIO 18 (physical pin 12),
+///   SCL on BCM GPIO 19 (physical pin 35)
+/// * Raspberry Pi 4 B and 400: SDA on BCM GPIO 10 (physical pin 19), SCL on
+///   BCM GPIO 11 (physical pin 23)
+///
+/// The Raspberry Pi 5 doesn't have a BSC slave peripheral, and returns
+/// `Err(`[`Error::FeatureNotSupported`]`)`.
+///
+/// Data is exchanged through a 16-byte receive FIFO and a 16-byte transmit
+/// FIFO. Incoming data written by the master is stored in the receive FIFO
+/// until it's retrieved with [`read`]. When the master reads from the slave
+/// address, the peripheral sends the contents of the transmit FIFO, which
+/// needs to be filled in advance with [`write`]. The peripheral doesn't
+/// stretch the clock, so if the transmit FIFO is empty while the master is
+/// reading, the master receives undefined data. Make sure the master leaves
+/// enough time between its requests for your application to prepare a
+/// response.
+///
+/// [`read`] and [`write`] return immediately, while [`read_blocking`] and
+/// [`write_blocking`] wait until data is available or the transmit FIFO has
+/// room, with an optional timeout.
+///
+/// ```no_run
+/// use rppal::i2c::I2cSlave;
+///
+/// # fn main() -> rppal::i2c::Result<()> {
+/// let mut slave = I2cSlave::new(0x42)?;
+///
+/// let mut buffer = [0u8; 16];
+/// loop {
+///     // Echo every command back to the master
+///     let len = slave.read_blocking(&mut buffer, None)?;
+///     slave.write_blocking(&buffer[..len], None)?;
+/// }
+/// # }
+/// ```
+///
+/// [`I2c`]: struct.I2c.html
+/// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+/// [`read`]: #method.read
+/// [`write`]: #method.write
+/// [`read_blocking`]: #method.read_blocking
+/// [`write_blocking`]: #method.write_blocking
+#[derive(Debug)]
+pub struct I2cSlave {
+    mem_ptr: *mut u32,
+    address: u16,
+    // The pins are only held to keep them set to ALT3, and to restore their
+    // original mode when I2cSlave goes out of scope.
+    _sda: IoPin,
+    _scl: IoPin,
+}
+
+impl I2cSlave {
+    /// Constructs a new `I2cSlave` that responds to the specified 7-bit
+    /// slave address.
+    ///
+    /// The FIFOs are cleared, and the receiver and transmitter are enabled.
+    pub fn new(address: u16) -> Result<I2cSlave> {
+        check_address(address)?;
+
+        let device_info = DeviceInfo::new()?;
+        let (sda, scl) = match device_info.soc() {
+            SoC::Bcm2835 | SoC::Bcm2836 | SoC::Bcm2837A1 | SoC::Bcm2837B0 => (18, 19),
+            SoC::Bcm2711 => (10, 11),
+            _ => return Err(Error::FeatureNotSupported),
+        };
+
+        // The pins are switched back to their original mode if any of the
+        // following steps fail
+        let gpio = Gpio::new()?;
+        let sda = gpio.get(sda)?.into_io(Mode::Alt3)?;
+        let scl = gpio.get(scl)?.into_io(Mode::Alt3)?;
+
+        let mem_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(O_SYNC)
+            .open(PATH_DEV_MEM)?;
+
+        let mem_ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                BSC_SLAVE_SIZE,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED,
+                mem_file.as_raw_fd(),
+                (device_info.peripheral_base() + BSC_SLAVE_OFFSET) as off_t,
+            )
+        };
+
+        if mem_ptr == MAP_FAILED {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+
+        let slave = I2cSlave {
+            mem_ptr: mem_ptr as *mut u32,
+            address,
+            _sda: sda,
+            _scl: scl,
+        };
+
+        // Disable the peripheral and its interrupts, clear the FIFOs and any
+        // pending errors, and then enable it in I2C mode.
+        slave.write_reg(REG_CR, 0);
+        slave.write_reg(REG_IMSC, 0);
+        slave.write_reg(REG_ICR, ICR_ALL);
+        slave.write_reg(REG_RSR, 0);
+        slave.write_reg(REG_CR, CR_BRK);
+        slave.write_reg(REG_SLV, u32::from(address));
+        slave.write_reg(REG_CR, CR_EN | CR_I2C | CR_TXE | CR_RXE);
+
+        log_debug!("Opened I2C slave (address: {:#04x})", address);
+
+        Ok(slave)
+    }
+
+    /// Returns the 7-bit slave address the peripheral responds to.
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// Changes the 7-bit slave address the peripheral responds to.
+    pub fn set_address(&mut self, address: u16) -> Result<()> {
+        check_address(address)?;
+
+        self.write_reg(REG_SLV, u32::from(address));
+        self.address = address;
+
+        Ok(())
+    }
+
+    /// Reads the data that's currently available in the receive FIFO into
+    /// `buffer`, and returns immediately.
+    ///
+    /// Returns how many bytes were read, which is 0 if the receive FIFO is
+    /// empty.
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        let mut len = 0;
+
+        for byte in buffer.iter_mut() {
+            if self.read_reg(REG_FR) & FR_RXFE > 0 {
+                break;
+            }
+
+            *byte = self.read_reg(REG_DR) as u8;
+            len += 1;
+        }
+
+        Ok(len)
+    }
+
+    /// Blocks until the receive FIFO contains data, or the timeout elapses,
+    /// and then reads the available data into `buffer`.
+    ///
+    /// Setting `timeout` to `None` waits indefinitely. Returns how many bytes
+    /// were read, which is 0 if the timeout elapsed without receiving any
+    /// data.
+    pub fn read_blocking(&mut self, buffer: &mut [u8], timeout: Option<Duration>) -> Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        let start = Instant::now();
+
+        while self.read_reg(REG_FR) & FR_RXFE > 0 {
+            if timeout.map_or(false, |timeout| start.elapsed() >= timeout) {
+                return Ok(0);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        self.read(buffer)
+    }
+
+    /// Copies as much of `buffer` into the transmit FIFO as currently fits,
+    /// and returns immediately.
+    ///
+    /// The data is sent the next time the master reads from the slave
+    /// address. Returns how many bytes were written, which is 0 if the
+    /// transmit FIFO is full.
+    pub fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+        let mut len = 0;
+
+        for byte in buffer {
+            if self.read_reg(REG_FR) & FR_TXFF > 0 {
+                break;
+            }
+
+            self.write_reg(REG_DR, u32::from(*byte));
+            len += 1;
+        }
+
+        Ok(len)
+    }
+
+    /// Blocks until all of `buffer` has been copied into the transmit FIFO,
+    /// or the timeout elapses.
+    ///
+    /// Because the transmit FIFO holds 16 bytes, larger buffers are only
+    /// written completely while the master is reading. Setting `timeout` to
+    /// `None` waits indefinitely. Returns how many bytes were written.
+    pub fn write_blocking(&mut self, buffer: &[u8], timeout: Option<Duration>) -> Result<usize> {
+        let start = Instant::now();
+        let mut len = 0;
+
+        loop {
+            len += self.write(&buffer[len..])?;
+
+            if len == buffer.len() || timeout.map_or(false, |timeout| start.elapsed() >= timeout) {
+                return Ok(len);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Returns `true` if the transmit FIFO is empty, which means the master
+    /// has read all data written with [`write`] or [`write_blocking`].
+    ///
+    /// [`write`]: #method.write
+    /// [`write_blocking`]: #method.write_blocking
+    pub fn is_tx_empty(&self) -> bool {
+        self.read_reg(REG_FR) & FR_TXFE > 0
+    }
+
+    // A SeqCst fence orders our accesses with respect to regular memory. On
+    // ARMv7 and AArch64 it's a DMB ISH, which is weaker than the barrier
+    // described in the BCM2835 ARM Peripherals manual (section 1.3).
+    #[inline(always)]
+    fn read_reg(&self, offset: usize) -> u32 {
+        let value = unsafe { ptr::read_volatile(self.mem_ptr.add(offset)) };
+        atomic::fence(Ordering::SeqCst);
+
+        value
+    }
+
+    #[inline(always)]
+    fn write_reg(&self, offset: usize, value: u32) {
+        atomic::fence(Ordering::SeqCst);
+        unsafe {
+            ptr::write_volatile(self.mem_ptr.add(offset), value);
+        }
+    }
+}
+
+impl Drop for I2cSlave {
+    fn drop(&mut self) {
+        self.write_reg(REG_CR, 0);
+
+        unsafe {
+            libc::munmap(self.mem_ptr as *mut libc::c_void, BSC_SLAVE_SIZE);
+        }
+    }
+}
+
+// The registers are only accessed through the I2cSlave that owns the
+// mapping, and its pins.
+unsafe impl Send for I2cSlave {}
+
+// Filters out invalid and reserved addresses
+fn check_address(address: u16) -> Result<()> {
+    if address < 0x08 || (address >> 3) == 0b1111 || address > 0x7F {
+        return Err(Error::InvalidSlaveAddress(address));
+    }
+
+    Ok(())
+}
@@ -6,11 +6,46 @@
 //! drivers through its `embedded-hal` trait implementations. Both `embedded-hal`
 //! v0.2.7 and v1.0.0-alpha.9 are supported.
 //!
+//! RPPAL itself depends on `std`, since every peripheral is accessed through
+//! Linux device nodes, `ioctl` calls, and in some cases background threads.
+//! The `embedded-hal` trait implementations form the boundary between RPPAL
+//! and platform-agnostic code. Drivers that are shared between a Raspberry Pi
+//! and a `no_std` target should be generic over the `embedded-hal` traits, and
+//! match on the trait's error kinds (for instance `embedded_hal::i2c::Error::kind`)
+//! instead of RPPAL's error types, which wrap [`io::Error`]. With that
+//! approach, only the application code that constructs the peripherals needs
+//! to depend on RPPAL.
+//!
+//! Each peripheral module is only compiled when the feature with the same
+//! name (`gpio`, `i2c`, `pwm`, `spi` or `uart`) is enabled. All of them are
+//! enabled by default. Projects that only use a few peripherals can disable
+//! the default features to reduce compile times.
+//!
 //! RPPAL requires Raspberry Pi OS or any similar, recent, Linux distribution.
 //! Both `gnu` and `musl` libc targets are supported. RPPAL is compatible with the
 //! Raspberry Pi A, A+, B, B+, 2B, 3A+, 3B, 3B+, 4B, CM, CM 3, CM 3+, CM 4, 400,
 //! Zero, Zero W and Zero 2 W. Backwards compatibility for minor revisions isn't
 //! guaranteed until v1.0.0.
+//!
+//! Read, write and transfer operations on the I2C, SPI and UART peripherals
+//! that are interrupted by a signal before any data is transferred are
+//! automatically retried, up to 10 times per operation. Only when the
+//! operation keeps getting interrupted is an [`io::ErrorKind::Interrupted`]
+//! error returned. With the optional `log` feature enabled, each retry is
+//! logged at debug level.
+//!
+//! Other transient errors, such as a slave device that temporarily doesn't
+//! acknowledge its address, can be retried by configuring a [`RetryPolicy`]
+//! for each peripheral.
+//!
+//! When a peripheral can't be accessed, [`self_test`] checks which interfaces
+//! are enabled and accessible on the current system, and suggests how to
+//! resolve any issues it finds.
+//!
+//! [`io::Error`]: https://doc.rust-lang.org/std/io/struct.Error.html
+//! [`io::ErrorKind::Interrupted`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.Interrupted
+//! [`RetryPolicy`]: retry/struct.RetryPolicy.html
+//! [`self_test`]: fn.self_test.html
 
 // Used by rustdoc to link other crates to rppal's docs
 #![doc(html_root_url = "https://docs.rs/rppal/0.14.1")]
@@ -18,11 +53,33 @@
 #[macro_use]
 mod macros;
 
+pub mod clock;
+#[cfg(feature = "drivers")]
+pub mod drivers;
+#[cfg(feature = "gpio")]
 pub mod gpio;
-#[cfg(feature = "hal")]
+#[cfg(any(feature = "hal-0", feature = "hal-1"))]
 pub mod hal;
+#[cfg(feature = "i2c")]
 pub mod i2c;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod onewire;
+#[cfg(feature = "pwm")]
 pub mod pwm;
+#[cfg(all(feature = "async", feature = "gpio"))]
+mod reactor;
+pub mod register;
+pub mod retry;
+mod self_test;
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests;
+#[cfg(feature = "spi")]
 pub mod spi;
+mod sys;
 pub mod system;
+#[cfg(feature = "uart")]
 pub mod uart;
+pub mod units;
+
+pub use self::self_test::{self_test, Check, CheckStatus, SelfTestReport};
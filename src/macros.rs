@@ -1,3 +1,15 @@
+// Not every macro is used when only some of the peripherals are enabled
+#![cfg_attr(
+    not(all(
+        feature = "gpio",
+        feature = "i2c",
+        feature = "pwm",
+        feature = "spi",
+        feature = "uart"
+    )),
+    allow(unused_macros)
+)]
+
 macro_rules! parse_retval {
     ($retval:expr) => {{
         let retval = $retval;
@@ -36,3 +48,110 @@ macro_rules! init_array {
         unsafe { mem::transmute::<_, [_; $len]>(data) }
     }};
 }
+
+// Emit a log record through the optional log crate. When the log feature is
+// disabled, the arguments are still type-checked, but no code is generated.
+#[cfg(feature = "log")]
+macro_rules! log_debug {
+    ($($arg:tt)+) => {
+        log::debug!($($arg)+)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! log_debug {
+    ($($arg:tt)+) => {
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+#[cfg(feature = "log")]
+macro_rules! log_warn {
+    ($($arg:tt)+) => {
+        log::warn!($($arg)+)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! log_warn {
+    ($($arg:tt)+) => {
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+#[cfg(feature = "log")]
+macro_rules! log_trace {
+    ($($arg:tt)+) => {
+        log::trace!($($arg)+)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! log_trace {
+    ($($arg:tt)+) => {
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+// Limit the number of payload bytes included in trace-level log records
+macro_rules! log_payload {
+    ($buffer:expr) => {{
+        let buffer: &[u8] = $buffer;
+
+        &buffer[..buffer.len().min(32)]
+    }};
+}
+
+// Retry an I/O operation that was interrupted by a signal before any data was
+// transferred (EINTR). Gives up after 10 retries, and returns the last error.
+macro_rules! retry_interrupted {
+    ($op:expr) => {{
+        const MAX_RETRIES: u32 = 10;
+
+        let mut retries = 0;
+
+        loop {
+            match $op {
+                Err(ref e)
+                    if e.kind() == std::io::ErrorKind::Interrupted && retries < MAX_RETRIES =>
+                {
+                    retries += 1;
+
+                    log_debug!(
+                        "Interrupted by a signal, retrying ({}/{})",
+                        retries,
+                        MAX_RETRIES
+                    );
+                }
+                result => break result,
+            }
+        }
+    }};
+}
+
+// Define functions that forward each call to the ioctl module, or to the
+// sibling mock module if node is a simulated device node. The backend is
+// determined by the DeviceNode variant, which is chosen when the peripheral is
+// constructed. The signatures need to match the functions in both modules.
+#[cfg(any(feature = "i2c", feature = "spi"))]
+macro_rules! dispatch_device_node {
+    ($(pub fn $name:ident(fd: c_int $(, $arg:ident: $ty:ty)*) -> $ret:ty;)+) => {
+        $(
+            pub fn $name(node: &DeviceNode $(, $arg: $ty)*) -> $ret {
+                match *node {
+                    DeviceNode::File(ref file) => super::ioctl::$name(file.as_raw_fd() $(, $arg)*),
+                    #[cfg(feature = "mock")]
+                    DeviceNode::Simulated(ref node) => {
+                        super::mock::$name(node.as_raw_fd() $(, $arg)*)
+                    }
+                }
+            }
+        )+
+    };
+}
@@ -0,0 +1,809 @@
+//! Simulated peripherals for testing without a Raspberry Pi.
+//!
+//! The optional `mock` feature adds a simulated backend for [`I2c`] and
+//! [`Spi`], which communicates with simulated slave devices instead of the
+//! device nodes in `/dev`. This makes it possible to test code paths that
+//! access peripherals on systems without the necessary hardware, such as CI
+//! containers.
+//!
+//! Simulated devices are attached to a [`Simulator`]. A peripheral only uses
+//! the simulated backend when it's explicitly connected to a `Simulator`
+//! through [`I2c::with_simulator`], [`I2c::with_simulator_bus`] or
+//! [`Spi::with_simulator`]. All other constructors, as well as
+//! [`DeviceInfo`], keep accessing the hardware, so enabling the `mock`
+//! feature doesn't change the behavior of any existing code. Each
+//! `Simulator` has its own set of devices, which allows tests to run in
+//! parallel without sharing any state.
+//!
+//! Every transfer between a peripheral and a simulated device is recorded
+//! as an [`Event`], which can be used to verify the interactions with the
+//! device.
+//!
+//! ## Record and replay
+//!
+//! Instead of writing a simulated device by hand, the I2C traffic of a driver
+//! can be captured on a real Raspberry Pi with [`I2c::start_recording`], and
+//! saved to a file with [`Recording::save`]. In tests, [`Simulator::replay_i2c`]
+//! attaches the recorded devices, which return the recorded responses, and
+//! fail any transfer that doesn't match the next one in the recording.
+//!
+//! ```
+//! use rppal::i2c::{I2c, Recording, Transfer};
+//! use rppal::mock::Simulator;
+//!
+//! // Usually loaded from a file with Recording::load()
+//! let mut recording = Recording::new();
+//! recording.push(Transfer::Write { address: 0x48, data: vec![0x00] });
+//! recording.push(Transfer::Read { address: 0x48, data: vec![0x19, 0x80] });
+//!
+//! let simulator = Simulator::new();
+//! let replay = simulator.replay_i2c(1, recording);
+//!
+//! let mut i2c = I2c::with_simulator(&simulator)?;
+//! i2c.set_slave_address(0x48)?;
+//!
+//! let mut buffer = [0u8; 2];
+//! i2c.write_read(&[0x00], &mut buffer)?;
+//! assert_eq!(buffer, [0x19, 0x80]);
+//!
+//! // A different command would have failed with an error
+//! assert!(replay.is_finished());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! GPIO, PWM and UART don't have a simulated backend yet, and still require
+//! access to the hardware.
+//!
+//! ## Example
+//!
+//! ```
+//! use rppal::i2c::{self, I2c};
+//! use rppal::mock::{Event, Registers, Simulator};
+//!
+//! // Application code that reads the temperature from a TMP102-style sensor
+//! fn read_temperature(i2c: &mut I2c) -> i2c::Result<f32> {
+//!     i2c.set_slave_address(0x48)?;
+//!
+//!     let mut buffer = [0u8; 2];
+//!     i2c.write_read(&[0x00], &mut buffer)?;
+//!
+//!     Ok(f32::from(i16::from_be_bytes(buffer) >> 4) * 0.0625)
+//! }
+//!
+//! // Attach a simulated sensor that reports 25.5 °C
+//! let simulator = Simulator::new();
+//! let mut sensor = Registers::new();
+//! sensor.set(0x00, 0x19);
+//! sensor.set(0x01, 0x80);
+//! simulator.add_i2c_device(1, 0x48, sensor);
+//!
+//! let mut i2c = I2c::with_simulator(&simulator)?;
+//! assert_eq!(read_temperature(&mut i2c)?, 25.5);
+//!
+//! assert_eq!(
+//!     simulator.events(),
+//!     vec![
+//!         Event::I2cWrite {
+//!             bus: 1,
+//!             address: 0x48,
+//!             data: vec![0x00],
+//!         },
+//!         Event::I2cRead {
+//!             bus: 1,
+//!             address: 0x48,
+//!             data: vec![0x19, 0x80],
+//!         },
+//!     ]
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`I2c`]: ../i2c/struct.I2c.html
+//! [`I2c::with_simulator`]: ../i2c/struct.I2c.html#method.with_simulator
+//! [`I2c::with_simulator_bus`]: ../i2c/struct.I2c.html#method.with_simulator_bus
+//! [`Spi`]: ../spi/struct.Spi.html
+//! [`Spi::with_simulator`]: ../spi/struct.Spi.html#method.with_simulator
+//! [`DeviceInfo`]: ../system/struct.DeviceInfo.html
+//! [`Simulator`]: struct.Simulator.html
+//! [`Event`]: enum.Event.html
+//! [`I2c::start_recording`]: ../i2c/struct.I2c.html#method.start_recording
+//! [`Recording::save`]: ../i2c/struct.Recording.html#method.save
+//! [`Simulator::replay_i2c`]: struct.Simulator.html#method.replay_i2c
+
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Arc, Mutex, Once};
+
+use crate::i2c::{Recording, Transfer};
+use crate::spi::{Bus, SlaveSelect};
+use crate::system::Model;
+
+/// A simulated I2C slave device.
+pub trait I2cDevice: Send {
+    /// Handles the outgoing data contained in `data`, which was sent by the
+    /// master.
+    ///
+    /// Returning an error aborts the transfer. An error created from
+    /// `ENXIO` is reported as a missing slave device.
+    fn write(&mut self, data: &[u8]) -> io::Result<()>;
+
+    /// Fills `buffer` with the incoming data requested by the master.
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<()>;
+}
+
+impl<T: I2cDevice> I2cDevice for Arc<Mutex<T>> {
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.lock().unwrap().write(data)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        self.lock().unwrap().read(buffer)
+    }
+}
+
+/// A simulated SPI slave device.
+pub trait SpiDevice: Send {
+    /// Handles a full-duplex transfer.
+    ///
+    /// `write_buffer` contains the outgoing data sent by the master on the
+    /// MOSI line, and `read_buffer` should be filled with the incoming data
+    /// sent on the MISO line. Both buffers have the same length.
+    fn transfer(&mut self, read_buffer: &mut [u8], write_buffer: &[u8]) -> io::Result<()>;
+}
+
+impl<T: SpiDevice> SpiDevice for Arc<Mutex<T>> {
+    fn transfer(&mut self, read_buffer: &mut [u8], write_buffer: &[u8]) -> io::Result<()> {
+        self.lock().unwrap().transfer(read_buffer, write_buffer)
+    }
+}
+
+/// A simulated I2C or SPI slave device with 256 8-bit registers.
+///
+/// `Registers` behaves like a typical register-based sensor. The first byte
+/// of a write sets the register pointer, and any remaining bytes are written
+/// to consecutive registers. Reads start at the register pointer. The
+/// register pointer is incremented after every register access, and wraps
+/// around after register `0xFF`.
+///
+/// When attached to an SPI bus, `Registers` follows the addressing
+/// convention described in the [`register`] module. The first byte of each
+/// transfer sets the register pointer to its lower 7 bits. If bit 7 is set,
+/// consecutive registers are read for the remainder of the transfer.
+/// Otherwise, the remaining bytes are written.
+///
+/// To inspect the registers after attaching the device to a [`Simulator`],
+/// wrap it in an `Arc<Mutex<Registers>>` and keep a clone.
+///
+/// [`Simulator`]: struct.Simulator.html
+/// [`register`]: ../register/index.html
+#[derive(Debug, Clone)]
+pub struct Registers {
+    registers: [u8; 256],
+    pointer: u8,
+}
+
+impl Registers {
+    /// Constructs a new `Registers` with all registers set to `0`.
+    pub fn new() -> Registers {
+        Registers {
+            registers: [0; 256],
+            pointer: 0,
+        }
+    }
+
+    /// Returns the value of the specified register.
+    pub fn get(&self, register: u8) -> u8 {
+        self.registers[register as usize]
+    }
+
+    /// Sets the value of the specified register.
+    pub fn set(&mut self, register: u8, value: u8) {
+        self.registers[register as usize] = value;
+    }
+
+    /// Returns the current register pointer.
+    pub fn pointer(&self) -> u8 {
+        self.pointer
+    }
+}
+
+impl Default for Registers {
+    fn default() -> Registers {
+        Registers::new()
+    }
+}
+
+impl I2cDevice for Registers {
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        if let Some((&pointer, values)) = data.split_first() {
+            self.pointer = pointer;
+
+            for value in values {
+                self.registers[self.pointer as usize] = *value;
+                self.pointer = self.pointer.wrapping_add(1);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        for value in buffer.iter_mut() {
+            *value = self.registers[self.pointer as usize];
+            self.pointer = self.pointer.wrapping_add(1);
+        }
+
+        Ok(())
+    }
+}
+
+impl SpiDevice for Registers {
+    fn transfer(&mut self, read_buffer: &mut [u8], write_buffer: &[u8]) -> io::Result<()> {
+        let (&address, values) = match write_buffer.split_first() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+
+        read_buffer[0] = 0;
+        self.pointer = address & 0x7F;
+
+        if address & 0x80 != 0 {
+            I2cDevice::read(self, &mut read_buffer[1..])
+        } else {
+            read_buffer[1..].iter_mut().for_each(|value| *value = 0);
+
+            for value in values {
+                self.registers[self.pointer as usize] = *value;
+                self.pointer = self.pointer.wrapping_add(1);
+            }
+
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ReplayState {
+    transfers: Vec<Transfer>,
+    position: usize,
+}
+
+impl ReplayState {
+    // Compares a transfer issued by the master with the next recorded
+    // transfer, and returns the recorded transfer if they match.
+    fn expect(&mut self, issued: &Transfer) -> io::Result<&Transfer> {
+        let matches = match (self.transfers.get(self.position), issued) {
+            (Some(Transfer::Write { address, data }), Transfer::Write { .. }) => {
+                *address == issued.address() && data[..] == *issued.data()
+            }
+            // Reads only have to match the length, since the master doesn't
+            // know the contents yet. Some emulated SMBus transactions request
+            // more data than was recorded, such as block reads that include
+            // the byte count.
+            (Some(Transfer::Read { address, data }), Transfer::Read { .. }) => {
+                *address == issued.address() && data.len() <= issued.data().len()
+            }
+            _ => false,
+        };
+
+        if !matches {
+            let message = match self.transfers.get(self.position) {
+                Some(expected) => format!(
+                    "Unexpected I2C transfer {} at position {} (expected {})",
+                    issued, self.position, expected
+                ),
+                None => format!(
+                    "Unexpected I2C transfer {} after the end of the recording",
+                    issued
+                ),
+            };
+
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+
+        self.position += 1;
+
+        Ok(&self.transfers[self.position - 1])
+    }
+}
+
+/// Replays a [`Recording`] of I2C transfers.
+///
+/// `Replay` is returned by [`Simulator::replay_i2c`], and tracks how much of
+/// the recording has been replayed. To make sure the code under test issued
+/// every recorded transfer, check [`is_finished`] at the end of the test.
+///
+/// [`Recording`]: ../i2c/struct.Recording.html
+/// [`Simulator::replay_i2c`]: struct.Simulator.html#method.replay_i2c
+/// [`is_finished`]: #method.is_finished
+#[derive(Debug, Clone)]
+pub struct Replay {
+    state: Arc<Mutex<ReplayState>>,
+}
+
+impl Replay {
+    /// Returns the number of recorded transfers that haven't been replayed
+    /// yet.
+    pub fn remaining(&self) -> usize {
+        let state = self.state.lock().unwrap();
+
+        state.transfers.len() - state.position
+    }
+
+    /// Returns `true` if all recorded transfers have been replayed.
+    pub fn is_finished(&self) -> bool {
+        self.remaining() == 0
+    }
+}
+
+// Simulated slave device that replays the recorded transfers for a single
+// address. All devices attached by the same Replay share its position, so
+// the order of transfers across addresses is verified as well.
+struct ReplayDevice {
+    address: u16,
+    replay: Replay,
+}
+
+impl I2cDevice for ReplayDevice {
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.replay.state.lock().unwrap().expect(&Transfer::Write {
+            address: self.address,
+            data: data.to_vec(),
+        })?;
+
+        Ok(())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        let mut state = self.replay.state.lock().unwrap();
+        let recorded = state.expect(&Transfer::Read {
+            address: self.address,
+            data: vec![0; buffer.len()],
+        })?;
+
+        let data = recorded.data();
+        buffer[..data.len()].copy_from_slice(data);
+        for value in buffer[data.len()..].iter_mut() {
+            *value = 0;
+        }
+
+        Ok(())
+    }
+}
+
+/// An interaction between a peripheral and a simulated device.
+///
+/// `Event` might be extended with additional variants in a minor or
+/// patch revision, and must not be exhaustively matched against.
+/// Instead, add a `_` catch-all arm to match future variants.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum Event {
+    /// Data was written to an I2C slave device.
+    I2cWrite {
+        bus: u8,
+        address: u16,
+        data: Vec<u8>,
+    },
+    /// Data was read from an I2C slave device.
+    I2cRead {
+        bus: u8,
+        address: u16,
+        data: Vec<u8>,
+    },
+    /// A full-duplex transfer took place with an SPI slave device.
+    SpiTransfer {
+        bus: Bus,
+        slave_select: SlaveSelect,
+        write: Vec<u8>,
+        read: Vec<u8>,
+    },
+}
+
+struct State {
+    model: Model,
+    i2c_buses: Vec<u8>,
+    i2c_devices: HashMap<(u8, u16), Box<dyn I2cDevice>>,
+    spi_devices: HashMap<(u8, u8), Box<dyn SpiDevice>>,
+    events: Vec<Event>,
+    i2c_requests: usize,
+}
+
+/// Simulates the devices connected to the Raspberry Pi's peripherals.
+///
+/// `Simulator` is a handle to shared state. Cloning a `Simulator` returns a
+/// new handle to the same simulated devices.
+///
+/// Peripherals are connected to a `Simulator` by constructing them with
+/// [`I2c::with_simulator`], [`I2c::with_simulator_bus`] or
+/// [`Spi::with_simulator`], and remain connected to it when they're moved to
+/// a different thread.
+///
+/// Devices are called while the `Simulator` is locked, so simulated devices
+/// must not call any `Simulator` methods, or construct any peripherals.
+///
+/// [`I2c::with_simulator`]: ../i2c/struct.I2c.html#method.with_simulator
+/// [`I2c::with_simulator_bus`]: ../i2c/struct.I2c.html#method.with_simulator_bus
+/// [`Spi::with_simulator`]: ../spi/struct.Spi.html#method.with_simulator
+#[derive(Clone)]
+pub struct Simulator {
+    state: Arc<Mutex<State>>,
+}
+
+impl Simulator {
+    /// Constructs a new `Simulator` without any devices.
+    ///
+    /// By default, the simulated model is set to [`RaspberryPi4B`].
+    ///
+    /// [`RaspberryPi4B`]: ../system/enum.Model.html#variant.RaspberryPi4B
+    pub fn new() -> Simulator {
+        Simulator {
+            state: Arc::new(Mutex::new(State {
+                model: Model::RaspberryPi4B,
+                i2c_buses: Vec::new(),
+                i2c_devices: HashMap::new(),
+                spi_devices: HashMap::new(),
+                events: Vec::new(),
+                i2c_requests: 0,
+            })),
+        }
+    }
+
+    /// Returns the simulated Raspberry Pi model.
+    pub fn model(&self) -> Model {
+        self.state.lock().unwrap().model.clone()
+    }
+
+    /// Sets the simulated Raspberry Pi model.
+    ///
+    /// The model determines which I2C bus [`I2c::with_simulator`] selects.
+    ///
+    /// [`I2c::with_simulator`]: ../i2c/struct.I2c.html#method.with_simulator
+    pub fn set_model(&self, model: Model) {
+        self.state.lock().unwrap().model = model;
+    }
+
+    /// Enables the specified I2C bus without attaching any devices.
+    ///
+    /// Constructing an [`I2c`] for a bus that hasn't been enabled fails the
+    /// same way it would when the device node doesn't exist.
+    ///
+    /// [`I2c`]: ../i2c/struct.I2c.html
+    pub fn enable_i2c_bus(&self, bus: u8) {
+        let mut state = self.state.lock().unwrap();
+
+        if !state.i2c_buses.contains(&bus) {
+            state.i2c_buses.push(bus);
+        }
+    }
+
+    /// Attaches a simulated I2C slave device, and enables the bus.
+    ///
+    /// Any device previously attached to the same bus and address is
+    /// replaced.
+    pub fn add_i2c_device<D>(&self, bus: u8, address: u16, device: D)
+    where
+        D: I2cDevice + 'static,
+    {
+        self.enable_i2c_bus(bus);
+
+        self.state
+            .lock()
+            .unwrap()
+            .i2c_devices
+            .insert((bus, address), Box::new(device));
+    }
+
+    /// Detaches the simulated I2C slave device at the specified bus and
+    /// address.
+    pub fn remove_i2c_device(&self, bus: u8, address: u16) {
+        self.state
+            .lock()
+            .unwrap()
+            .i2c_devices
+            .remove(&(bus, address));
+    }
+
+    /// Attaches simulated I2C slave devices that replay `recording`, and
+    /// enables the bus.
+    ///
+    /// A device is attached for every slave address contained in the
+    /// recording, replacing any device previously attached to the same bus
+    /// and address. Each transfer issued by the master is compared with the
+    /// next recorded transfer. Writes must match the recorded address and
+    /// data exactly. Reads must match the recorded address, and are filled
+    /// with the recorded data. Any other transfer fails with an error of kind
+    /// [`InvalidData`], which describes both the issued and the expected
+    /// transfer.
+    ///
+    /// [`InvalidData`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData
+    pub fn replay_i2c(&self, bus: u8, recording: Recording) -> Replay {
+        let addresses: HashSet<u16> = recording
+            .transfers()
+            .iter()
+            .map(|transfer| transfer.address())
+            .collect();
+
+        let replay = Replay {
+            state: Arc::new(Mutex::new(ReplayState {
+                transfers: recording.transfers().to_vec(),
+                position: 0,
+            })),
+        };
+
+        for address in addresses {
+            self.add_i2c_device(
+                bus,
+                address,
+                ReplayDevice {
+                    address,
+                    replay: replay.clone(),
+                },
+            );
+        }
+
+        replay
+    }
+
+    /// Attaches a simulated SPI slave device.
+    ///
+    /// Constructing an [`Spi`] only succeeds for combinations of `bus` and
+    /// `slave_select` that have a device attached. Any device previously
+    /// attached to the same bus and Slave Select pin is replaced.
+    ///
+    /// [`Spi`]: ../spi/struct.Spi.html
+    pub fn add_spi_device<D>(&self, bus: Bus, slave_select: SlaveSelect, device: D)
+    where
+        D: SpiDevice + 'static,
+    {
+        self.state
+            .lock()
+            .unwrap()
+            .spi_devices
+            .insert((bus as u8, slave_select as u8), Box::new(device));
+    }
+
+    /// Detaches the simulated SPI slave device at the specified bus and
+    /// Slave Select pin.
+    pub fn remove_spi_device(&self, bus: Bus, slave_select: SlaveSelect) {
+        self.state
+            .lock()
+            .unwrap()
+            .spi_devices
+            .remove(&(bus as u8, slave_select as u8));
+    }
+
+    /// Returns all recorded events, in chronological order.
+    pub fn events(&self) -> Vec<Event> {
+        self.state.lock().unwrap().events.clone()
+    }
+
+    /// Removes all recorded events, and resets the count returned by
+    /// [`i2c_requests`].
+    ///
+    /// [`i2c_requests`]: #method.i2c_requests
+    pub fn clear_events(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        state.events.clear();
+        state.i2c_requests = 0;
+    }
+
+    /// Returns the number of requests made through the simulated I2C device
+    /// interfaces.
+    ///
+    /// Each request corresponds to a single `read`, `write` or `ioctl` call
+    /// on a real I2C bus device node, which the kernel completes as an
+    /// uninterrupted transaction. This can be used to verify that a sequence
+    /// of transfers is sent as a single transaction. Note that a single
+    /// request may consist of multiple [`Event`]s.
+    ///
+    /// ```
+    /// use rppal::i2c::I2c;
+    /// use rppal::mock::{Registers, Simulator};
+    ///
+    /// let simulator = Simulator::new();
+    /// simulator.add_i2c_device(1, 0x48, Registers::new());
+    ///
+    /// let mut i2c = I2c::with_simulator(&simulator)?;
+    /// i2c.set_slave_address(0x48)?;
+    ///
+    /// let mut buffer = [0u8; 2];
+    /// i2c.write_read(&[0x00], &mut buffer)?;
+    /// assert_eq!(simulator.events().len(), 2);
+    /// assert_eq!(simulator.i2c_requests(), 1);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// [`Event`]: enum.Event.html
+    pub fn i2c_requests(&self) -> usize {
+        self.state.lock().unwrap().i2c_requests
+    }
+
+    pub(crate) fn count_i2c_request(&self) {
+        self.state.lock().unwrap().i2c_requests += 1;
+    }
+
+    pub(crate) fn i2c_bus_enabled(&self, bus: u8) -> bool {
+        self.state.lock().unwrap().i2c_buses.contains(&bus)
+    }
+
+    pub(crate) fn i2c_write(&self, bus: u8, address: u16, data: &[u8]) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        state
+            .i2c_devices
+            .get_mut(&(bus, address))
+            .ok_or_else(|| io::Error::from_raw_os_error(libc::ENXIO))?
+            .write(data)?;
+
+        state.events.push(Event::I2cWrite {
+            bus,
+            address,
+            data: data.to_vec(),
+        });
+
+        Ok(())
+    }
+
+    pub(crate) fn i2c_read(&self, bus: u8, address: u16, buffer: &mut [u8]) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        state
+            .i2c_devices
+            .get_mut(&(bus, address))
+            .ok_or_else(|| io::Error::from_raw_os_error(libc::ENXIO))?
+            .read(buffer)?;
+
+        state.events.push(Event::I2cRead {
+            bus,
+            address,
+            data: buffer.to_vec(),
+        });
+
+        Ok(())
+    }
+
+    pub(crate) fn spi_device_exists(&self, bus: Bus, slave_select: SlaveSelect) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .spi_devices
+            .contains_key(&(bus as u8, slave_select as u8))
+    }
+
+    pub(crate) fn spi_transfer(
+        &self,
+        bus: Bus,
+        slave_select: SlaveSelect,
+        read_buffer: &mut [u8],
+        write_buffer: &[u8],
+    ) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        state
+            .spi_devices
+            .get_mut(&(bus as u8, slave_select as u8))
+            .ok_or_else(|| io::Error::from_raw_os_error(libc::ENODEV))?
+            .transfer(read_buffer, write_buffer)?;
+
+        state.events.push(Event::SpiTransfer {
+            bus,
+            slave_select,
+            write: write_buffer.to_vec(),
+            read: read_buffer.to_vec(),
+        });
+
+        Ok(())
+    }
+}
+
+impl Default for Simulator {
+    fn default() -> Simulator {
+        Simulator::new()
+    }
+}
+
+impl fmt::Debug for Simulator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+
+        f.debug_struct("Simulator")
+            .field("model", &state.model)
+            .field("i2c_buses", &state.i2c_buses)
+            .field("events", &state.events)
+            .finish()
+    }
+}
+
+type Nodes = Mutex<HashMap<RawFd, Box<dyn Any + Send>>>;
+
+// Simulated device nodes, indexed by file descriptor
+fn nodes() -> &'static Nodes {
+    static NODES: AtomicPtr<Nodes> = AtomicPtr::new(ptr::null_mut());
+    static ONCE: Once = Once::new();
+
+    ONCE.call_once(|| {
+        NODES.store(
+            Box::into_raw(Box::new(Mutex::new(HashMap::new()))),
+            Ordering::SeqCst,
+        );
+    });
+
+    // NODES is initialized at this point, and never freed
+    unsafe { &*NODES.load(Ordering::SeqCst) }
+}
+
+// A simulated device node, backed by an eventfd so it has a unique file
+// descriptor. The node's state is removed when the handle is dropped, before
+// the file descriptor is closed and can be reused by another node.
+#[derive(Debug)]
+pub(crate) struct NodeHandle {
+    fd: RawFd,
+}
+
+impl AsRawFd for NodeHandle {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl Drop for NodeHandle {
+    fn drop(&mut self) {
+        nodes().lock().unwrap().remove(&self.fd);
+
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+// Opens a simulated device node with the associated state
+pub(crate) fn open<T: Any + Send>(node: T) -> io::Result<NodeHandle> {
+    let fd = parse_retval!(unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) })?;
+
+    nodes().lock().unwrap().insert(fd, Box::new(node));
+
+    Ok(NodeHandle { fd })
+}
+
+// Calls f with the state associated with a simulated device node
+pub(crate) fn with_node<T, R, F>(fd: RawFd, f: F) -> io::Result<R>
+where
+    T: Any + Send,
+    F: FnOnce(&mut T) -> io::Result<R>,
+{
+    let mut nodes = nodes().lock().unwrap();
+
+    match nodes.get_mut(&fd).and_then(|node| node.downcast_mut::<T>()) {
+        Some(node) => f(node),
+        None => Err(io::Error::from_raw_os_error(libc::EBADF)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_removed_on_drop() {
+        let node = open(1u8).unwrap();
+        let fd = node.as_raw_fd();
+
+        assert_eq!(with_node(fd, |value: &mut u8| Ok(*value)).unwrap(), 1);
+
+        drop(node);
+
+        assert_eq!(
+            with_node(fd, |value: &mut u8| Ok(*value))
+                .unwrap_err()
+                .raw_os_error(),
+            Some(libc::EBADF)
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! Interface for 1-Wire temperature sensors.
+//!
+//! RPPAL reads 1-Wire devices through the sysfs interface provided by the
+//! kernel's `w1-gpio` bus master and `w1-therm` slave drivers. The kernel
+//! takes care of the timing-sensitive bit-banging, which makes this a more
+//! reliable option than a userspace implementation.
+//!
+//! ## Enabling 1-Wire
+//!
+//! The 1-Wire bus is disabled by default. To enable it on its default pin
+//! (BCM GPIO 4, physical pin 7), add `dtoverlay=w1-gpio` to
+//! `/boot/config.txt`. A different pin can be selected by appending
+//! `,gpiopin=<pin>`. After a reboot, every device detected on the bus is
+//! listed in `/sys/bus/w1/devices`.
+//!
+//! The data line requires a pull-up resistor, typically 4.7 kΩ, to 3.3 V.
+//!
+//! ## Temperature sensors
+//!
+//! [`OneWire::read_temperature`] supports the DS18B20, DS18S20, DS1822,
+//! DS1825 and DS28EA00 temperature sensors. Each reading triggers a new
+//! conversion, which takes up to 750 ms at the default 12-bit resolution.
+//!
+//! Readings occasionally fail because of noise on the bus, or a sensor that
+//! lost power during the conversion. RPPAL verifies the CRC reported by the
+//! kernel, and discards the sensor's power-on reset value of 85 °C and
+//! scratchpads that only contain zeros, which indicate a missing sensor.
+//! Failed readings are retried a few times before
+//! [`Error::ReadFailed`] is returned.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use rppal::onewire::OneWire;
+//!
+//! # fn main() -> rppal::onewire::Result<()> {
+//! let onewire = OneWire::new()?;
+//!
+//! for id in onewire.devices()? {
+//!     if id.is_temperature_sensor() {
+//!         println!("{}: {:.2} °C", id, onewire.read_temperature(id)?);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`OneWire::read_temperature`]: struct.OneWire.html#method.read_temperature
+//! [`Error::ReadFailed`]: enum.Error.html#variant.ReadFailed
+
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::result;
+use std::str::FromStr;
+
+const PATH_W1_DEVICES: &str = "/sys/bus/w1/devices";
+
+// Number of attempts before a failed reading is reported
+const READ_ATTEMPTS: u32 = 3;
+
+// Family codes of the devices supported by the w1-therm driver
+const FAMILY_DS18S20: u8 = 0x10;
+const FAMILY_DS1822: u8 = 0x22;
+const FAMILY_DS18B20: u8 = 0x28;
+const FAMILY_DS1825: u8 = 0x3b;
+const FAMILY_DS28EA00: u8 = 0x42;
+
+// Temperature register value after a power-on reset, in millidegrees Celsius
+const POWER_ON_RESET: i32 = 85_000;
+
+// Serial numbers are 48 bits
+const SERIAL_MAX: u64 = 0xffff_ffff_ffff;
+
+/// Errors that can occur when accessing 1-Wire devices.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// I/O error.
+    Io(io::Error),
+    /// 1-Wire not enabled.
+    ///
+    /// The `w1` sysfs interface doesn't exist. Make sure the 1-Wire bus is
+    /// enabled. More information can be found [here].
+    ///
+    /// [here]: index.html#enabling-1-wire
+    BusNotEnabled,
+    /// Invalid device ID.
+    ///
+    /// The string isn't formatted as a 1-Wire device ID, such as
+    /// `28-0316a2795bff`. Contains the rejected string.
+    InvalidId(String),
+    /// Reading failed.
+    ///
+    /// The sensor didn't return a valid reading after several attempts. The
+    /// CRC didn't match, the sensor reported its power-on reset value, or
+    /// the sensor didn't respond. Contains the sensor's ID.
+    ReadFailed(OneWireId),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::BusNotEnabled => write!(f, "1-Wire not enabled"),
+            Error::InvalidId(ref id) => write!(f, "Invalid 1-Wire device ID: {}", id),
+            Error::ReadFailed(id) => write!(f, "Reading failed: {}", id),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => err.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+/// Result type returned from methods that can have `onewire::Error`s.
+pub type Result<T> = result::Result<T, Error>;
+
+/// A 1-Wire device ID, consisting of an 8-bit family code and a 48-bit
+/// serial number.
+///
+/// `OneWireId` is displayed and parsed in the format used by the kernel,
+/// with the family code and serial number in lowercase hexadecimal, for
+/// instance `28-0316a2795bff`.
+///
+/// ```
+/// use rppal::onewire::OneWireId;
+///
+/// let id: OneWireId = "28-0316a2795bff".parse()?;
+/// assert_eq!(id.family(), 0x28);
+/// assert_eq!(id.serial(), 0x0316_a279_5bff);
+/// assert!(id.is_temperature_sensor());
+/// assert_eq!(id.to_string(), "28-0316a2795bff");
+/// # Ok::<(), rppal::onewire::Error>(())
+/// ```
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct OneWireId {
+    family: u8,
+    serial: u64,
+}
+
+impl OneWireId {
+    /// Constructs a new `OneWireId`.
+    ///
+    /// Only the lower 48 bits of `serial` are used.
+    pub fn new(family: u8, serial: u64) -> OneWireId {
+        OneWireId {
+            family,
+            serial: serial & SERIAL_MAX,
+        }
+    }
+
+    /// Returns the family code, which identifies the device type.
+    pub fn family(&self) -> u8 {
+        self.family
+    }
+
+    /// Returns the 48-bit serial number.
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    /// Returns `true` if the family code belongs to a temperature sensor
+    /// supported by [`OneWire::read_temperature`].
+    ///
+    /// [`OneWire::read_temperature`]: struct.OneWire.html#method.read_temperature
+    pub fn is_temperature_sensor(&self) -> bool {
+        matches!(
+            self.family,
+            FAMILY_DS18S20 | FAMILY_DS1822 | FAMILY_DS18B20 | FAMILY_DS1825 | FAMILY_DS28EA00
+        )
+    }
+}
+
+impl fmt::Display for OneWireId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}-{:012x}", self.family, self.serial)
+    }
+}
+
+impl FromStr for OneWireId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<OneWireId> {
+        let invalid = || Error::InvalidId(s.to_owned());
+
+        let (family, serial) = s.split_once('-').ok_or_else(invalid)?;
+        if family.len() != 2 || serial.len() != 12 {
+            return Err(invalid());
+        }
+
+        Ok(OneWireId {
+            family: u8::from_str_radix(family, 16).map_err(|_| invalid())?,
+            serial: u64::from_str_radix(serial, 16).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Provides access to the devices on the 1-Wire bus.
+///
+/// More information on enabling the 1-Wire bus can be found in the
+/// [module-level documentation].
+///
+/// [module-level documentation]: index.html
+#[derive(Debug, Clone)]
+pub struct OneWire {
+    path: PathBuf,
+}
+
+impl OneWire {
+    /// Constructs a new `OneWire`.
+    ///
+    /// If the 1-Wire bus isn't enabled, `new` returns
+    /// `Err(`[`Error::BusNotEnabled`]`)`.
+    ///
+    /// [`Error::BusNotEnabled`]: enum.Error.html#variant.BusNotEnabled
+    pub fn new() -> Result<OneWire> {
+        let path = PathBuf::from(PATH_W1_DEVICES);
+
+        if !path.is_dir() {
+            return Err(Error::BusNotEnabled);
+        }
+
+        Ok(OneWire { path })
+    }
+
+    /// Returns the IDs of all devices detected on the 1-Wire bus, sorted by
+    /// family code and serial number.
+    ///
+    /// The kernel periodically searches the bus for new devices, so recently
+    /// connected devices may take a few seconds to appear.
+    pub fn devices(&self) -> Result<Vec<OneWireId>> {
+        let mut devices = Vec::new();
+
+        for entry in fs::read_dir(&self.path)? {
+            // Skip bus masters and any other entries that aren't devices
+            if let Ok(id) = entry?.file_name().to_string_lossy().parse() {
+                devices.push(id);
+            }
+        }
+
+        devices.sort();
+
+        Ok(devices)
+    }
+
+    /// Reads the temperature in degrees Celsius (°C) from the sensor
+    /// identified by `id`.
+    ///
+    /// `read_temperature` blocks until the sensor completes a conversion,
+    /// which takes up to 750 ms. Invalid readings are retried up to 3 times
+    /// before `read_temperature` returns `Err(`[`Error::ReadFailed`]`)`.
+    ///
+    /// If the device isn't a supported temperature sensor, or it's no longer
+    /// connected, an [`Error::Io`] is returned.
+    ///
+    /// [`Error::ReadFailed`]: enum.Error.html#variant.ReadFailed
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    pub fn read_temperature(&self, id: OneWireId) -> Result<f32> {
+        let path = self.path.join(id.to_string()).join("w1_slave");
+
+        for _ in 0..READ_ATTEMPTS {
+            let contents = fs::read_to_string(&path)?;
+
+            if let Some(millidegrees) = parse_w1_slave(&contents)? {
+                return Ok(millidegrees as f32 / 1000.0);
+            }
+
+            log_debug!("Invalid reading from 1-Wire device {}", id);
+        }
+
+        Err(Error::ReadFailed(id))
+    }
+}
+
+// Parses the output of w1_slave, which contains the scratchpad bytes, CRC
+// check result and temperature in millidegrees Celsius:
+//
+// 72 01 4b 46 7f ff 0e 10 57 : crc=57 YES
+// 72 01 4b 46 7f ff 0e 10 57 t=23125
+//
+// Returns None if the reading should be discarded.
+fn parse_w1_slave(contents: &str) -> io::Result<Option<i32>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "unexpected w1_slave format");
+
+    let mut lines = contents.lines();
+    let crc_line = lines.next().ok_or_else(invalid)?;
+    let temperature_line = lines.next().ok_or_else(invalid)?;
+
+    if !crc_line.trim_end().ends_with("YES") {
+        return Ok(None);
+    }
+
+    let (scratchpad, temperature) = temperature_line.split_once("t=").ok_or_else(invalid)?;
+
+    let scratchpad = scratchpad
+        .split_whitespace()
+        .map(|byte| u8::from_str_radix(byte, 16))
+        .collect::<result::Result<Vec<u8>, _>>()
+        .map_err(|_| invalid())?;
+
+    // An all-zero scratchpad passes the CRC check, but means the sensor
+    // didn't respond
+    if scratchpad.iter().all(|&byte| byte == 0) {
+        return Ok(None);
+    }
+
+    let temperature: i32 = temperature.trim().parse().map_err(|_| invalid())?;
+
+    // The sensor didn't complete a conversion since it was powered on
+    if temperature == POWER_ON_RESET {
+        return Ok(None);
+    }
+
+    Ok(Some(temperature))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn error_display_and_source() {
+        let err = Error::from(io::Error::new(io::ErrorKind::Other, "No such device"));
+        assert_eq!(err.to_string(), "I/O error: No such device");
+        assert!(err.source().is_none());
+
+        let err = Error::BusNotEnabled;
+        assert_eq!(err.to_string(), "1-Wire not enabled");
+        assert!(err.source().is_none());
+    }
+}
@@ -20,6 +20,15 @@
 //! use with other peripherals. Be careful not to enable two peripherals on the same pin
 //! at the same time.
 //!
+//! Both channels can be started together through a [`PwmGroup`], which keeps their
+//! periods aligned. This is useful for complementary or otherwise phase-aligned signals.
+//!
+//! [`Tone`] plays notes and simple melodies on a piezo buzzer or speaker connected to a PWM
+//! channel.
+//!
+//! [`Servo`] positions a hobby servo, using per-unit [`ServoCalibration`] data that can be
+//! stored alongside the rest of a device's configuration.
+//!
 //! ## Using PWM without superuser privileges (`sudo`)
 //!
 //! As of kernel version 4.14.34, released on April 16 2018, it's possible to
@@ -45,10 +54,10 @@
 //!
 //! ### Permission denied
 //!
-//! If [`new`] returns an `io::ErrorKind::PermissionDenied`
-//! error, make sure `/sys/class/pwm` and all of its subdirectories
-//! are owned by `root:gpio`, the current user is a member of the `gpio` group
-//! and `udev` is properly configured as mentioned above. Alternatively, you can
+//! If [`new`] returns [`Error::PermissionDenied`], make sure `/sys/class/pwm`
+//! and all of its subdirectories are owned by `root:gpio`, the current user is
+//! a member of the `gpio` group and `udev` is properly configured as mentioned
+//! above. Alternatively, you can
 //! launch your application using `sudo`.
 //!
 //! ### Not found
@@ -59,37 +68,156 @@
 //!
 //! [patch]: https://github.com/raspberrypi/linux/issues/1983
 //! [`new`]: struct.Pwm.html#method.new
+//! [`Error::PermissionDenied`]: enum.Error.html#variant.PermissionDenied
+//! [`PwmGroup`]: struct.PwmGroup.html
+//! [`Tone`]: struct.Tone.html
+//! [`Servo`]: struct.Servo.html
+//! [`ServoCalibration`]: struct.ServoCalibration.html
 
+use std::convert::Infallible;
 use std::error;
 use std::fmt;
 use std::io;
 use std::result;
 use std::time::Duration;
 
-#[cfg(feature = "hal")]
+use crate::retry::RetryPolicy;
+use crate::units;
+
+mod config;
+mod group;
+#[cfg(feature = "hal-0")]
 mod hal;
 #[cfg(feature = "hal-unproven")]
 mod hal_unproven;
+mod ramp;
+mod servo;
 mod sysfs;
+mod tone;
+
+pub use self::config::PwmConfig;
+pub use self::group::PwmGroup;
+pub use self::ramp::{RampCurve, RampHandle};
+pub use self::servo::{Servo, ServoCalibration};
+pub use self::tone::{Note, Tone};
+pub use crate::units::{DutyCycle, Frequency};
 
 const NANOS_PER_SEC: f64 = 1_000_000_000.0;
 
+const PATH_PWMCHIP: &str = "/sys/class/pwm/pwmchip0";
+
+// Interval between attempts to export a channel when the sysfs interface doesn't exist yet
+const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
 /// Errors that can occur when accessing the PWM peripheral.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// I/O error.
     Io(io::Error),
+    /// PWM not enabled.
+    ///
+    /// The PWM sysfs interface didn't appear before the timeout elapsed. Make
+    /// sure PWM is enabled. More information can be found [here].
+    ///
+    /// [here]: index.html
+    BusNotEnabled,
+    /// Permission denied.
+    ///
+    /// The current user doesn't have write access to the PWM sysfs interface.
+    /// `path` contains the path that couldn't be accessed, `required_group` the
+    /// group that conventionally grants access to it (`gpio`), and `hint`
+    /// describes how to resolve the issue.
+    ///
+    /// More information on possible causes for this error can be found [here].
+    ///
+    /// [here]: index.html#permission-denied
+    PermissionDenied {
+        path: String,
+        required_group: Option<&'static str>,
+        hint: String,
+    },
+    /// Thread panicked.
+    ///
+    /// The background thread used by [`Pwm::ramp_to_background`] panicked.
+    ///
+    /// [`Pwm::ramp_to_background`]: struct.Pwm.html#method.ramp_to_background
+    ThreadPanic,
+    /// Invalid duty cycle.
+    ///
+    /// The duty cycle is NaN, or outside the range `0.0..=1.0`. Contains the
+    /// rejected value.
+    InvalidDutyCycle(f64),
+    /// Invalid frequency.
+    ///
+    /// The frequency is NaN, infinite, or not greater than `0.0`. Contains the
+    /// rejected value.
+    InvalidFrequency(f64),
+    /// Invalid servo calibration.
+    ///
+    /// The minimum pulse width isn't less than the maximum pulse width, or
+    /// the angle range is NaN, infinite, or not greater than `0.0`. Contains
+    /// the rejected calibration.
+    InvalidCalibration(ServoCalibration),
+    /// Invalid servo angle.
+    ///
+    /// The angle is NaN. Contains the rejected value.
+    InvalidAngle(f64),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::BusNotEnabled => write!(f, "PWM not enabled"),
+            Error::PermissionDenied {
+                ref path, ref hint, ..
+            } => write!(f, "Permission denied: {} ({})", path, hint),
+            Error::ThreadPanic => write!(f, "Thread panicked"),
+            Error::InvalidDutyCycle(value) => write!(f, "Invalid duty cycle: {}", value),
+            Error::InvalidFrequency(value) => write!(f, "Invalid frequency: {}", value),
+            Error::InvalidCalibration(ref calibration) => write!(
+                f,
+                "Invalid servo calibration: {} µs to {} µs over {}°",
+                calibration.min_pulse_us, calibration.max_pulse_us, calibration.range
+            ),
+            Error::InvalidAngle(value) => write!(f, "Invalid servo angle: {}", value),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => err.source(),
+            _ => None,
         }
     }
 }
 
-impl error::Error for Error {}
+impl Error {
+    // Converts an I/O error that occurred while accessing path, and classifies
+    // any permission errors.
+    fn with_path(err: io::Error, path: &str) -> Error {
+        match crate::sys::permission_denied(&err, path) {
+            Some(denied) => Error::PermissionDenied {
+                path: denied.path,
+                required_group: denied.required_group,
+                hint: denied.hint,
+            },
+            None => Error::from(crate::sys::io_error_with_path(err, path)),
+        }
+    }
+
+    // Returns true if the error was caused by the sysfs interface not
+    // existing yet, which new_with_retry waits for.
+    fn is_missing_interface(&self) -> bool {
+        match *self {
+            Error::Io(ref err) => err.kind() == io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+}
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Error {
@@ -97,6 +225,22 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<units::Error> for Error {
+    fn from(err: units::Error) -> Error {
+        match err {
+            units::Error::InvalidDutyCycle(value) => Error::InvalidDutyCycle(value),
+            units::Error::InvalidFrequency(value) => Error::InvalidFrequency(value),
+        }
+    }
+}
+
+// Converting a DutyCycle or Frequency into itself can't fail.
+impl From<Infallible> for Error {
+    fn from(err: Infallible) -> Error {
+        match err {}
+    }
+}
+
 /// Result type returned from methods that can have `pwm::Error`s.
 pub type Result<T> = result::Result<T, Error>;
 
@@ -105,8 +249,13 @@ pub type Result<T> = result::Result<T, Error>;
 /// More information on enabling and configuring the PWM channels can be
 /// found [here].
 ///
+/// With the optional `serde` feature enabled, `Channel` is (de)serialized as
+/// `"pwm0"` or `"pwm1"`.
+///
 /// [here]: index.html
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum Channel {
     Pwm0 = 0,
     Pwm1 = 1,
@@ -122,7 +271,12 @@ impl fmt::Display for Channel {
 }
 
 /// Output polarities.
+///
+/// With the optional `serde` feature enabled, `Polarity` is (de)serialized as
+/// `"normal"` or `"inverse"`.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum Polarity {
     Normal,
     Inverse,
@@ -167,7 +321,7 @@ impl Pwm {
     ///
     /// [`enable`]: #method.enable
     pub fn new(channel: Channel) -> Result<Pwm> {
-        sysfs::export(channel as u8)?;
+        sysfs::export(channel as u8).map_err(|e| Error::with_path(e, PATH_PWMCHIP))?;
 
         let pwm = Pwm {
             channel,
@@ -182,6 +336,42 @@ impl Pwm {
         Ok(pwm)
     }
 
+    /// Constructs a new `Pwm`, waiting for the PWM sysfs interface to appear.
+    ///
+    /// `new_with_retry` works similarly to [`new`], but keeps polling for
+    /// the sysfs interface until it exists, or until the `timeout` duration
+    /// elapses. This can be useful when your application is started during
+    /// boot, before the PWM kernel module has finished loading.
+    ///
+    /// If the sysfs interface doesn't appear before the timeout elapses,
+    /// `new_with_retry` returns [`Error::BusNotEnabled`].
+    ///
+    /// [`new`]: #method.new
+    /// [`Error::BusNotEnabled`]: enum.Error.html#variant.BusNotEnabled
+    pub fn new_with_retry(channel: Channel, timeout: Duration) -> Result<Pwm> {
+        Pwm::new_with_retry_policy(channel, RetryPolicy::with_timeout(timeout, RETRY_INTERVAL))
+    }
+
+    /// Constructs a new `Pwm`, retrying according to `policy` while the PWM
+    /// sysfs interface doesn't exist.
+    ///
+    /// If the sysfs interface still doesn't exist after the last attempt,
+    /// `new_with_retry_policy` returns [`Error::BusNotEnabled`]. Any other
+    /// error is returned immediately.
+    ///
+    /// [`Error::BusNotEnabled`]: enum.Error.html#variant.BusNotEnabled
+    pub fn new_with_retry_policy(channel: Channel, policy: RetryPolicy) -> Result<Pwm> {
+        policy
+            .retry(|| Pwm::new(channel), Error::is_missing_interface)
+            .map_err(|err| {
+                if err.is_missing_interface() {
+                    Error::BusNotEnabled
+                } else {
+                    err
+                }
+            })
+    }
+
     /// Constructs a new `Pwm` using the specified settings.
     ///
     /// `period` indicates the time it takes for the PWM channel to complete one cycle.
@@ -207,7 +397,7 @@ impl Pwm {
         polarity: Polarity,
         enabled: bool,
     ) -> Result<Pwm> {
-        sysfs::export(channel as u8)?;
+        sysfs::export(channel as u8).map_err(|e| Error::with_path(e, PATH_PWMCHIP))?;
 
         let pwm = Pwm {
             channel,
@@ -237,9 +427,13 @@ impl Pwm {
     /// `with_frequency` is a convenience method that converts `frequency` to a period,
     /// and calculates the duty cycle as a percentage of the frequency.
     ///
-    /// `frequency` is specified in hertz (Hz).
+    /// `frequency` is specified in hertz (Hz), either as a [`Frequency`] or as an `f64`.
+    /// If `frequency` is NaN, infinite, or not greater than `0.0`,
+    /// `Err(`[`Error::InvalidFrequency`]`)` is returned.
     ///
-    /// `duty_cycle` is specified as a floating point value between `0.0` (0%) and `1.0` (100%).
+    /// `duty_cycle` is specified as a [`DutyCycle`], or as an `f64` between `0.0` (0%)
+    /// and `1.0` (100%). If `duty_cycle` is NaN or out of range,
+    /// `Err(`[`Error::InvalidDutyCycle`]`)` is returned.
     ///
     /// `polarity` configures the active logic level as either high ([`Normal`])
     /// or low ([`Inverse`]).
@@ -250,14 +444,27 @@ impl Pwm {
     /// [`Normal`]: enum.Polarity.html#variant.Normal
     /// [`Inverse`]: enum.Polarity.html#variant.Inverse
     /// [`enable`]: #method.enable
-    pub fn with_frequency(
+    /// [`Frequency`]: ../units/struct.Frequency.html
+    /// [`DutyCycle`]: ../units/struct.DutyCycle.html
+    /// [`Error::InvalidFrequency`]: enum.Error.html#variant.InvalidFrequency
+    /// [`Error::InvalidDutyCycle`]: enum.Error.html#variant.InvalidDutyCycle
+    pub fn with_frequency<F, D>(
         channel: Channel,
-        frequency: f64,
-        duty_cycle: f64,
+        frequency: F,
+        duty_cycle: D,
         polarity: Polarity,
         enabled: bool,
-    ) -> Result<Pwm> {
-        sysfs::export(channel as u8)?;
+    ) -> Result<Pwm>
+    where
+        F: TryInto<Frequency>,
+        F::Error: Into<Error>,
+        D: TryInto<DutyCycle>,
+        D::Error: Into<Error>,
+    {
+        let frequency = frequency.try_into().map_err(Into::into)?;
+        let duty_cycle = duty_cycle.try_into().map_err(Into::into)?;
+
+        sysfs::export(channel as u8).map_err(|e| Error::with_path(e, PATH_PWMCHIP))?;
 
         let pwm = Pwm {
             channel,
@@ -273,12 +480,8 @@ impl Pwm {
         let _ = sysfs::set_pulse_width(channel as u8, 0);
 
         // Convert to nanoseconds
-        let period = if frequency == 0.0 {
-            0.0
-        } else {
-            (1.0 / frequency) * NANOS_PER_SEC
-        };
-        let pulse_width = period * duty_cycle.clamp(0.0, 1.0);
+        let period = frequency.period_nanos();
+        let pulse_width = period * duty_cycle.value();
 
         sysfs::set_period(channel as u8, period as u64)?;
         sysfs::set_pulse_width(channel as u8, pulse_width as u64)?;
@@ -290,6 +493,21 @@ impl Pwm {
         Ok(pwm)
     }
 
+    /// Constructs a new `Pwm` using the settings in `config`.
+    ///
+    /// `from_config` works similarly to [`with_frequency`].
+    ///
+    /// [`with_frequency`]: #method.with_frequency
+    pub fn from_config(config: &PwmConfig) -> Result<Pwm> {
+        Pwm::with_frequency(
+            config.channel,
+            config.frequency,
+            config.duty_cycle,
+            config.polarity,
+            config.enabled,
+        )
+    }
+
     /// Returns the period.
     pub fn period(&self) -> Result<Duration> {
         Ok(Duration::from_nanos(sysfs::period(self.channel as u8)?))
@@ -352,20 +570,34 @@ impl Pwm {
     /// `set_frequency` is a convenience method that converts `frequency` to a period,
     /// and calculates the duty cycle as a percentage of the frequency.
     ///
-    /// `frequency` is specified in hertz (Hz).
+    /// `frequency` is specified in hertz (Hz), either as a [`Frequency`] or as an `f64`.
+    /// If `frequency` is NaN, infinite, or not greater than `0.0`,
+    /// `Err(`[`Error::InvalidFrequency`]`)` is returned.
     ///
-    /// `duty_cycle` is specified as a floating point value between `0.0` (0%) and `1.0` (100%).
-    pub fn set_frequency(&self, frequency: f64, duty_cycle: f64) -> Result<()> {
+    /// `duty_cycle` is specified as a [`DutyCycle`], or as an `f64` between `0.0` (0%)
+    /// and `1.0` (100%). If `duty_cycle` is NaN or out of range,
+    /// `Err(`[`Error::InvalidDutyCycle`]`)` is returned.
+    ///
+    /// [`Frequency`]: ../units/struct.Frequency.html
+    /// [`DutyCycle`]: ../units/struct.DutyCycle.html
+    /// [`Error::InvalidFrequency`]: enum.Error.html#variant.InvalidFrequency
+    /// [`Error::InvalidDutyCycle`]: enum.Error.html#variant.InvalidDutyCycle
+    pub fn set_frequency<F, D>(&self, frequency: F, duty_cycle: D) -> Result<()>
+    where
+        F: TryInto<Frequency>,
+        F::Error: Into<Error>,
+        D: TryInto<DutyCycle>,
+        D::Error: Into<Error>,
+    {
+        let frequency = frequency.try_into().map_err(Into::into)?;
+        let duty_cycle = duty_cycle.try_into().map_err(Into::into)?;
+
         // Set duty cycle to 0 first in case the new period is shorter than the current duty cycle
         let _ = sysfs::set_pulse_width(self.channel as u8, 0);
 
         // Convert to nanoseconds
-        let period = if frequency == 0.0 {
-            0.0
-        } else {
-            (1.0 / frequency) * NANOS_PER_SEC
-        };
-        let pulse_width = period * duty_cycle.clamp(0.0, 1.0);
+        let period = frequency.period_nanos();
+        let pulse_width = period * duty_cycle.value();
 
         sysfs::set_period(self.channel as u8, period as u64)?;
         sysfs::set_pulse_width(self.channel as u8, pulse_width as u64)?;
@@ -394,16 +626,128 @@ impl Pwm {
     /// `set_duty_cycle` is a convenience method that converts `duty_cycle` to a
     /// pulse width based on the configured period.
     ///
-    /// `duty_cycle` is specified as a floating point value between `0.0` (0%) and `1.0` (100%).
-    pub fn set_duty_cycle(&self, duty_cycle: f64) -> Result<()> {
+    /// `duty_cycle` is specified as a [`DutyCycle`], or as an `f64` between `0.0` (0%)
+    /// and `1.0` (100%). If `duty_cycle` is NaN or out of range,
+    /// `Err(`[`Error::InvalidDutyCycle`]`)` is returned.
+    ///
+    /// [`DutyCycle`]: ../units/struct.DutyCycle.html
+    /// [`Error::InvalidDutyCycle`]: enum.Error.html#variant.InvalidDutyCycle
+    pub fn set_duty_cycle<D>(&self, duty_cycle: D) -> Result<()>
+    where
+        D: TryInto<DutyCycle>,
+        D::Error: Into<Error>,
+    {
+        let duty_cycle = duty_cycle.try_into().map_err(Into::into)?;
         let period = sysfs::period(self.channel as u8)? as f64;
-        let pulse_width = period * duty_cycle.clamp(0.0, 1.0);
+        let pulse_width = period * duty_cycle.value();
 
         sysfs::set_pulse_width(self.channel as u8, pulse_width as u64)?;
 
         Ok(())
     }
 
+    /// Gradually changes the duty cycle from its current value to `target_duty`.
+    ///
+    /// `ramp_to` changes the duty cycle linearly in `steps` equal steps, spread
+    /// evenly over `duration`, and blocks until the ramp completes. This avoids
+    /// the inrush current caused by suddenly applying a high duty cycle to a
+    /// motor or high-current LED. Use [`ramp_to_with_curve`] to select a
+    /// different curve, or [`ramp_to_background`] to ramp without blocking.
+    ///
+    /// `target_duty` is specified as a [`DutyCycle`], or as an `f64` between
+    /// `0.0` (0%) and `1.0` (100%). If `target_duty` is NaN or out of range,
+    /// `Err(`[`Error::InvalidDutyCycle`]`)` is returned. If `steps` is `0`, a
+    /// single step is used.
+    ///
+    /// The duty cycle is changed regardless of whether the PWM channel is
+    /// enabled. Calling [`disable`] doesn't stop a ramp, and the duty cycle
+    /// configured at the end of the ramp is used when the channel is enabled
+    /// again. To soft-start an output, set the duty cycle to `0.0`, [`enable`]
+    /// the channel, and then call `ramp_to`.
+    ///
+    /// [`ramp_to_with_curve`]: #method.ramp_to_with_curve
+    /// [`ramp_to_background`]: #method.ramp_to_background
+    /// [`disable`]: #method.disable
+    /// [`enable`]: #method.enable
+    /// [`DutyCycle`]: ../units/struct.DutyCycle.html
+    /// [`Error::InvalidDutyCycle`]: enum.Error.html#variant.InvalidDutyCycle
+    pub fn ramp_to<D>(&mut self, target_duty: D, duration: Duration, steps: u32) -> Result<()>
+    where
+        D: TryInto<DutyCycle>,
+        D::Error: Into<Error>,
+    {
+        self.ramp_to_with_curve(target_duty, duration, steps, RampCurve::Linear)
+    }
+
+    /// Gradually changes the duty cycle from its current value to `target_duty`,
+    /// following the specified `curve`.
+    ///
+    /// `ramp_to_with_curve` works similarly to [`ramp_to`], but distributes
+    /// the change in duty cycle over the steps according to `curve`.
+    ///
+    /// [`ramp_to`]: #method.ramp_to
+    pub fn ramp_to_with_curve<D>(
+        &mut self,
+        target_duty: D,
+        duration: Duration,
+        steps: u32,
+        curve: RampCurve,
+    ) -> Result<()>
+    where
+        D: TryInto<DutyCycle>,
+        D::Error: Into<Error>,
+    {
+        ramp::ramp(
+            self.channel as u8,
+            target_duty.try_into().map_err(Into::into)?,
+            duration,
+            steps,
+            curve,
+            None,
+        )
+    }
+
+    /// Gradually changes the duty cycle from its current value to `target_duty`
+    /// on a background thread.
+    ///
+    /// `ramp_to_background` works similarly to [`ramp_to_with_curve`], but
+    /// returns immediately. Use the returned [`RampHandle`] to wait for the
+    /// ramp to complete, or to cancel it. If `target_duty` is invalid, the ramp
+    /// doesn't start, and the error is returned by [`RampHandle::wait`].
+    ///
+    /// Any changes to the period or duty cycle made while the ramp is running
+    /// are overwritten by the next step. Like [`ramp_to`], the ramp continues
+    /// after [`disable`] is called. Cancel the ramp first if the duty cycle
+    /// shouldn't change any further. If the `Pwm` instance goes out of scope
+    /// while the ramp is running and [`reset_on_drop`] is enabled, the channel
+    /// is unexported, and the ramp stops with an error at the next step.
+    ///
+    /// [`ramp_to_with_curve`]: #method.ramp_to_with_curve
+    /// [`ramp_to`]: #method.ramp_to
+    /// [`RampHandle`]: struct.RampHandle.html
+    /// [`RampHandle::wait`]: struct.RampHandle.html#method.wait
+    /// [`disable`]: #method.disable
+    /// [`reset_on_drop`]: #method.reset_on_drop
+    pub fn ramp_to_background<D>(
+        &self,
+        target_duty: D,
+        duration: Duration,
+        steps: u32,
+        curve: RampCurve,
+    ) -> RampHandle
+    where
+        D: TryInto<DutyCycle>,
+        D::Error: Into<Error>,
+    {
+        RampHandle::new(
+            self.channel as u8,
+            target_duty.try_into().map_err(Into::into),
+            duration,
+            steps,
+            curve,
+        )
+    }
+
     /// Returns the polarity.
     pub fn polarity(&self) -> Result<Polarity> {
         Ok(sysfs::polarity(self.channel as u8)?)
@@ -469,3 +813,32 @@ impl Drop for Pwm {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn error_display_and_source() {
+        let err = Error::from(io::Error::new(io::ErrorKind::Other, "No such device"));
+        assert_eq!(err.to_string(), "I/O error: No such device");
+        assert!(err.source().is_none());
+
+        let err = Error::with_path(
+            io::Error::from_raw_os_error(libc::EIO),
+            "/sys/class/pwm/pwmchip0",
+        );
+        assert!(err
+            .to_string()
+            .starts_with("I/O error: /sys/class/pwm/pwmchip0: "));
+        let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.raw_os_error(), Some(libc::EIO));
+
+        let err = Error::InvalidDutyCycle(1.5);
+        assert_eq!(err.to_string(), "Invalid duty cycle: 1.5");
+        assert!(err.source().is_none());
+    }
+}
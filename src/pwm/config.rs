@@ -0,0 +1,60 @@
+use super::{Channel, Polarity};
+
+/// Configuration for a [`Pwm`] instance.
+///
+/// `PwmConfig` contains the same settings as the arguments for
+/// [`Pwm::with_frequency`], and can be passed to [`Pwm::from_config`]. This
+/// is useful when the PWM configuration is loaded from a file.
+///
+/// With the optional `serde` feature enabled, `PwmConfig` is (de)serialized
+/// using kebab-case field names, and unknown fields are rejected. For
+/// instance, in TOML:
+///
+/// ```toml
+/// channel = "pwm0"
+/// frequency = 1000.0
+/// duty-cycle = 0.5
+/// polarity = "normal"
+/// enabled = true
+/// ```
+///
+/// [`Pwm`]: struct.Pwm.html
+/// [`Pwm::with_frequency`]: struct.Pwm.html#method.with_frequency
+/// [`Pwm::from_config`]: struct.Pwm.html#method.from_config
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(rename_all = "kebab-case", deny_unknown_fields)
+)]
+pub struct PwmConfig {
+    /// The PWM channel.
+    pub channel: Channel,
+    /// The frequency in hertz (Hz).
+    pub frequency: f64,
+    /// The duty cycle as a floating point value between `0.0` (0%) and `1.0` (100%).
+    pub duty_cycle: f64,
+    /// The polarity.
+    pub polarity: Polarity,
+    /// Enables PWM on the selected channel.
+    pub enabled: bool,
+}
+
+impl PwmConfig {
+    /// Constructs a new `PwmConfig`.
+    pub fn new(
+        channel: Channel,
+        frequency: f64,
+        duty_cycle: f64,
+        polarity: Polarity,
+        enabled: bool,
+    ) -> PwmConfig {
+        PwmConfig {
+            channel,
+            frequency,
+            duty_cycle,
+            polarity,
+            enabled,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use super::{sysfs, Error, Frequency, Pwm, Result};
+
+/// A group of PWM channels that are enabled simultaneously.
+///
+/// `PwmGroup` takes ownership of multiple [`Pwm`] instances, and starts all
+/// channels together so their periods are aligned. This is useful for signals
+/// that consist of multiple phase-aligned channels, such as complementary
+/// H-bridge drive, where one channel is configured with [`Inverse`] polarity,
+/// or stereo audio.
+///
+/// Both PWM channels are driven by the same PWM clock, so once they've been
+/// started, channels configured with the same period remain aligned. Pulse
+/// widths, duty cycles and polarities can be changed on the individual
+/// channels through [`pwms`] without affecting the alignment.
+///
+/// ```no_run
+/// use rppal::pwm::{Channel, Polarity, Pwm, PwmGroup};
+///
+/// # fn main() -> rppal::pwm::Result<()> {
+/// // Complementary outputs for an H-bridge at 20 kHz with a 25% duty cycle
+/// let high_side = Pwm::with_frequency(Channel::Pwm0, 20_000.0, 0.25, Polarity::Normal, false)?;
+/// let low_side = Pwm::with_frequency(Channel::Pwm1, 20_000.0, 0.25, Polarity::Inverse, false)?;
+///
+/// let group = PwmGroup::new(vec![high_side, low_side]);
+/// group.enable()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ## Limitations
+///
+/// The `pwm` sysfs interface exports and enables each channel independently.
+/// When channels are enabled one at a time, each channel's counter starts at
+/// the moment its `enable` attribute is written, which results in a phase
+/// offset that depends on system load. [`enable`] minimizes this offset by
+/// opening all `enable` attributes first, and then writing to them back-to-back,
+/// but the channels still start a few sysfs writes apart. If an exact phase
+/// relationship is required, measure the remaining offset on your hardware,
+/// and compensate for it in your pulse widths where possible.
+///
+/// [`Pwm`]: struct.Pwm.html
+/// [`Inverse`]: enum.Polarity.html#variant.Inverse
+/// [`pwms`]: #method.pwms
+/// [`enable`]: #method.enable
+#[derive(Debug)]
+pub struct PwmGroup {
+    pwms: Vec<Pwm>,
+    channels: Vec<u8>,
+}
+
+impl PwmGroup {
+    /// Constructs a new `PwmGroup` containing the specified PWM channels.
+    ///
+    /// The channels' current settings aren't changed. Call [`enable`] to
+    /// (re)start all channels together.
+    ///
+    /// [`enable`]: #method.enable
+    pub fn new(pwms: Vec<Pwm>) -> PwmGroup {
+        let channels = pwms.iter().map(|pwm| pwm.channel as u8).collect();
+
+        PwmGroup { pwms, channels }
+    }
+
+    /// Returns the PWM channels in the group.
+    pub fn pwms(&self) -> &[Pwm] {
+        &self.pwms
+    }
+
+    /// Consumes the `PwmGroup` and returns the PWM channels it contains.
+    pub fn into_pwms(self) -> Vec<Pwm> {
+        self.pwms
+    }
+
+    /// Returns `true` if any of the PWM channels in the group are enabled.
+    pub fn is_enabled(&self) -> Result<bool> {
+        for pwm in &self.pwms {
+            if pwm.is_enabled()? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Enables all PWM channels in the group.
+    ///
+    /// Any channels that are already enabled are disabled first, so all
+    /// channels restart their periods together. More details can be found
+    /// in the [limitations].
+    ///
+    /// [limitations]: #limitations
+    pub fn enable(&self) -> Result<()> {
+        sysfs::set_enabled_all(&self.channels, false)?;
+        sysfs::set_enabled_all(&self.channels, true)?;
+
+        Ok(())
+    }
+
+    /// Disables all PWM channels in the group.
+    pub fn disable(&self) -> Result<()> {
+        sysfs::set_enabled_all(&self.channels, false)?;
+
+        Ok(())
+    }
+
+    /// Sets the frequency of all PWM channels in the group.
+    ///
+    /// `frequency` is specified in hertz (Hz), either as a [`Frequency`] or as
+    /// an `f64`. Each channel's duty cycle is preserved. If `frequency` is
+    /// invalid, `Err(`[`Error::InvalidFrequency`]`)` is returned.
+    ///
+    /// Changing the period of a running channel doesn't restart its current
+    /// period, so the channels are disabled while the new frequency is
+    /// applied. If any of the channels were enabled, all channels are
+    /// re-enabled together afterwards.
+    ///
+    /// [`Frequency`]: ../units/struct.Frequency.html
+    /// [`Error::InvalidFrequency`]: enum.Error.html#variant.InvalidFrequency
+    pub fn set_frequency<F>(&self, frequency: F) -> Result<()>
+    where
+        F: TryInto<Frequency>,
+        F::Error: Into<Error>,
+    {
+        let frequency = frequency.try_into().map_err(Into::into)?;
+        let enabled = self.is_enabled()?;
+        let duty_cycles = self
+            .pwms
+            .iter()
+            .map(|pwm| pwm.duty_cycle())
+            .collect::<Result<Vec<f64>>>()?;
+
+        self.disable()?;
+
+        for (pwm, duty_cycle) in self.pwms.iter().zip(duty_cycles) {
+            pwm.set_frequency(frequency, duty_cycle)?;
+        }
+
+        if enabled {
+            sysfs::set_enabled_all(&self.channels, true)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use super::{sysfs, DutyCycle, Error, Result};
+
+/// Duty cycle ramp curves.
+///
+/// The curve determines how the duty cycle progresses from its starting value
+/// to the target value over the course of a ramp started with [`Pwm::ramp_to`].
+///
+/// With the optional `serde` feature enabled, `RampCurve` is (de)serialized as
+/// `"linear"`, `"quadratic"` or `"s-curve"`.
+///
+/// [`Pwm::ramp_to`]: struct.Pwm.html#method.ramp_to
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum RampCurve {
+    /// Changes the duty cycle by the same amount at each step.
+    Linear,
+    /// Starts slowly, and speeds up towards the end. Useful for LEDs, where
+    /// the perceived brightness isn't linear.
+    Quadratic,
+    /// Starts and ends slowly, and speeds up in the middle (smoothstep).
+    /// Useful for limiting mechanical stress on motors.
+    SCurve,
+}
+
+impl RampCurve {
+    // Maps the ramp's progress (0.0 - 1.0) to the fraction of the total change
+    // in duty cycle (0.0 - 1.0).
+    fn apply(self, progress: f64) -> f64 {
+        match self {
+            RampCurve::Linear => progress,
+            RampCurve::Quadratic => progress * progress,
+            RampCurve::SCurve => progress * progress * (3.0 - 2.0 * progress),
+        }
+    }
+}
+
+impl fmt::Display for RampCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RampCurve::Linear => write!(f, "Linear"),
+            RampCurve::Quadratic => write!(f, "Quadratic"),
+            RampCurve::SCurve => write!(f, "SCurve"),
+        }
+    }
+}
+
+/// A duty cycle ramp running on a background thread.
+///
+/// Returned by [`Pwm::ramp_to_background`]. If `RampHandle` goes out of scope,
+/// the ramp continues in the background until it completes.
+///
+/// [`Pwm::ramp_to_background`]: struct.Pwm.html#method.ramp_to_background
+#[derive(Debug)]
+pub struct RampHandle {
+    thread: JoinHandle<Result<()>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl RampHandle {
+    pub(crate) fn new(
+        channel: u8,
+        target_duty: Result<DutyCycle>,
+        duration: Duration,
+        steps: u32,
+        curve: RampCurve,
+    ) -> RampHandle {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let thread_cancel = cancel.clone();
+
+        let thread = thread::spawn(move || {
+            ramp(
+                channel,
+                target_duty?,
+                duration,
+                steps,
+                curve,
+                Some(&thread_cancel),
+            )
+        });
+
+        RampHandle { thread, cancel }
+    }
+
+    /// Blocks until the ramp completes.
+    ///
+    /// Returns any error that occurred while changing the duty cycle.
+    pub fn wait(self) -> Result<()> {
+        match self.thread.join() {
+            Ok(result) => result,
+            Err(_) => Err(Error::ThreadPanic),
+        }
+    }
+
+    /// Stops the ramp, and leaves the duty cycle at its current value.
+    pub fn cancel(self) -> Result<()> {
+        self.cancel.store(true, Ordering::SeqCst);
+
+        self.wait()
+    }
+}
+
+// Changes the duty cycle from its current value to target_duty in the
+// specified number of steps. Each step is scheduled relative to the start of
+// the ramp, so delays caused by the sysfs writes don't accumulate.
+pub(crate) fn ramp(
+    channel: u8,
+    target_duty: DutyCycle,
+    duration: Duration,
+    steps: u32,
+    curve: RampCurve,
+    cancel: Option<&AtomicBool>,
+) -> Result<()> {
+    let period = sysfs::period(channel)? as f64;
+    let target_duty = target_duty.value();
+    let start_duty = if period == 0.0 {
+        0.0
+    } else {
+        (sysfs::pulse_width(channel)? as f64 / period).clamp(0.0, 1.0)
+    };
+
+    let steps = steps.max(1);
+    let start = Instant::now();
+
+    for step in 1..=steps {
+        if let Some(cancel) = cancel {
+            if cancel.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+        }
+
+        let deadline = duration.mul_f64(f64::from(step) / f64::from(steps));
+        let elapsed = start.elapsed();
+        if deadline > elapsed {
+            thread::sleep(deadline - elapsed);
+        }
+
+        let progress = curve.apply(f64::from(step) / f64::from(steps));
+        let duty_cycle = start_duty + (target_duty - start_duty) * progress;
+
+        sysfs::set_pulse_width(channel, (period * duty_cycle) as u64)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,310 @@
+use std::time::Duration;
+
+use super::{Error, Pwm, Result};
+
+// Standard servo period (50 Hz)
+const SERVO_PERIOD: Duration = Duration::from_millis(20);
+// Textbook pulse widths for the minimum and maximum angle, which serve as a
+// starting point until the servo is calibrated
+const DEFAULT_MIN_PULSE_US: u32 = 1000;
+const DEFAULT_MAX_PULSE_US: u32 = 2000;
+const DEFAULT_RANGE: f64 = 180.0;
+
+/// Calibration data for a single [`Servo`].
+///
+/// Servos rarely honor the nominal 1–2 ms pulse width range precisely, and
+/// the pulse widths that move a servo to its mechanical endpoints differ
+/// between units, even of the same model. `ServoCalibration` stores the pulse
+/// widths measured for a specific servo, together with a trim offset that
+/// corrects the center position, for instance to compensate for a horn that
+/// can't be mounted at exactly the right angle.
+///
+/// An angle of `0.0` maps to `min_pulse_us`, and an angle of `range` maps to
+/// `max_pulse_us`. Angles in between are interpolated linearly. `trim_us` is
+/// added to every pulse width afterwards.
+///
+/// With the optional `serde` feature enabled, `ServoCalibration` is
+/// (de)serialized using kebab-case field names, and unknown fields are
+/// rejected. If `trim-us` is omitted, it defaults to `0`. If `range` is
+/// omitted, it defaults to `180.0`. This allows calibration data to be
+/// stored alongside the rest of a device's configuration. For instance, in
+/// TOML:
+///
+/// ```toml
+/// min-pulse-us = 560
+/// max-pulse-us = 2380
+/// trim-us = -15
+/// ```
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use rppal::pwm::ServoCalibration;
+///
+/// let calibration = ServoCalibration::new(600, 2400, 10, 180.0);
+/// assert_eq!(calibration.pulse_width(0.0), Duration::from_micros(610));
+/// assert_eq!(calibration.pulse_width(90.0), Duration::from_micros(1510));
+/// // Angles are clamped to the calibrated range
+/// assert_eq!(calibration.pulse_width(200.0), Duration::from_micros(2410));
+/// ```
+///
+/// [`Servo`]: struct.Servo.html
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(rename_all = "kebab-case", deny_unknown_fields)
+)]
+pub struct ServoCalibration {
+    /// The pulse width in microseconds (µs) that moves the servo to an angle
+    /// of `0.0`.
+    pub min_pulse_us: u32,
+    /// The pulse width in microseconds (µs) that moves the servo to the
+    /// maximum angle, as specified by `range`.
+    pub max_pulse_us: u32,
+    /// The offset in microseconds (µs) added to every pulse width.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub trim_us: i32,
+    /// The angle in degrees between the positions reached with
+    /// `min_pulse_us` and `max_pulse_us`.
+    #[cfg_attr(feature = "serde", serde(default = "default_range"))]
+    pub range: f64,
+}
+
+impl ServoCalibration {
+    /// Constructs a new `ServoCalibration`.
+    pub fn new(min_pulse_us: u32, max_pulse_us: u32, trim_us: i32, range: f64) -> ServoCalibration {
+        ServoCalibration {
+            min_pulse_us,
+            max_pulse_us,
+            trim_us,
+            range,
+        }
+    }
+
+    /// Returns the pulse width for the specified angle in degrees.
+    ///
+    /// `angle` is clamped to the range `0.0..=range`. NaN is treated as
+    /// `0.0`. The trim offset is applied after clamping, so it can extend
+    /// the pulse width beyond the calibrated endpoints.
+    pub fn pulse_width(&self, angle: f64) -> Duration {
+        let angle = if angle.is_nan() {
+            0.0
+        } else {
+            angle.clamp(0.0, self.range)
+        };
+
+        let span = f64::from(self.max_pulse_us) - f64::from(self.min_pulse_us);
+        let pulse_us =
+            f64::from(self.min_pulse_us) + span * (angle / self.range) + f64::from(self.trim_us);
+
+        Duration::from_micros(pulse_us.round().max(0.0) as u64)
+    }
+
+    // Checks that the endpoints are in the correct order, and the range
+    // results in a valid interpolation.
+    fn validate(&self) -> Result<()> {
+        if self.min_pulse_us >= self.max_pulse_us || !self.range.is_finite() || self.range <= 0.0 {
+            return Err(Error::InvalidCalibration(*self));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ServoCalibration {
+    fn default() -> ServoCalibration {
+        ServoCalibration {
+            min_pulse_us: DEFAULT_MIN_PULSE_US,
+            max_pulse_us: DEFAULT_MAX_PULSE_US,
+            trim_us: 0,
+            range: DEFAULT_RANGE,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn default_range() -> f64 {
+    DEFAULT_RANGE
+}
+
+/// Positions a hobby servo connected to a PWM channel.
+///
+/// `Servo` sets the PWM period to 20 ms (50 Hz), and converts angles to
+/// pulse widths based on a [`ServoCalibration`]. Until the servo is
+/// calibrated, the nominal 1–2 ms pulse width range for a 180° servo is
+/// used, which only approximates the actual endpoints of most servos.
+///
+/// To calibrate a servo, gradually move it towards each endpoint with
+/// [`Pwm::set_pulse_width`] until it stops responding or starts to strain,
+/// back off slightly, and pass the resulting pulse widths to [`calibrate`].
+/// Driving a servo past its mechanical endpoints can damage its gears.
+///
+/// Don't power the servo directly from the Raspberry Pi's GPIO header.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use rppal::pwm::{Channel, Pwm, Servo};
+///
+/// # fn main() -> rppal::pwm::Result<()> {
+/// let mut servo = Servo::new(Pwm::new(Channel::Pwm0)?)?;
+/// servo.calibrate(Duration::from_micros(560), Duration::from_micros(2380))?;
+/// servo.set_trim(-15);
+///
+/// servo.set_angle(90.0)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`ServoCalibration`]: struct.ServoCalibration.html
+/// [`Pwm::set_pulse_width`]: struct.Pwm.html#method.set_pulse_width
+/// [`calibrate`]: #method.calibrate
+#[derive(Debug)]
+pub struct Servo {
+    pwm: Pwm,
+    calibration: ServoCalibration,
+    angle: Option<f64>,
+}
+
+impl Servo {
+    /// Constructs a new `Servo` using the specified PWM channel.
+    ///
+    /// The PWM period is set to 20 ms. The servo doesn't move until an angle
+    /// is set with [`set_angle`].
+    ///
+    /// [`set_angle`]: #method.set_angle
+    pub fn new(pwm: Pwm) -> Result<Servo> {
+        Servo::with_calibration(pwm, ServoCalibration::default())
+    }
+
+    /// Constructs a new `Servo` using the specified PWM channel and
+    /// calibration data.
+    ///
+    /// If `calibration` is invalid, `with_calibration` returns
+    /// `Err(`[`Error::InvalidCalibration`]`)`.
+    ///
+    /// [`Error::InvalidCalibration`]: enum.Error.html#variant.InvalidCalibration
+    pub fn with_calibration(pwm: Pwm, calibration: ServoCalibration) -> Result<Servo> {
+        calibration.validate()?;
+
+        // Disable the channel first, so the servo doesn't move to whatever
+        // position the current pulse width corresponds to.
+        pwm.disable()?;
+        pwm.set_period(SERVO_PERIOD)?;
+
+        Ok(Servo {
+            pwm,
+            calibration,
+            angle: None,
+        })
+    }
+
+    /// Returns the calibration data.
+    pub fn calibration(&self) -> ServoCalibration {
+        self.calibration
+    }
+
+    /// Replaces the calibration data, for instance after loading it from a
+    /// file.
+    ///
+    /// If `calibration.min_pulse_us` isn't less than
+    /// `calibration.max_pulse_us`, or `calibration.range` isn't a positive
+    /// number, `set_calibration` returns
+    /// `Err(`[`Error::InvalidCalibration`]`)`, and the current calibration is
+    /// left unchanged. If an angle was set previously, the servo is moved to
+    /// the same angle using the new calibration.
+    ///
+    /// [`Error::InvalidCalibration`]: enum.Error.html#variant.InvalidCalibration
+    pub fn set_calibration(&mut self, calibration: ServoCalibration) -> Result<()> {
+        calibration.validate()?;
+
+        self.calibration = calibration;
+
+        if let Some(angle) = self.angle {
+            self.set_angle(angle)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the pulse widths that move the servo to an angle of `0.0` and
+    /// the maximum angle, respectively.
+    ///
+    /// The trim offset and angle range are left unchanged. If
+    /// `min_angle_pulse` isn't less than `max_angle_pulse`, `calibrate`
+    /// returns `Err(`[`Error::InvalidCalibration`]`)`. Pulse widths are
+    /// rounded down to whole microseconds.
+    ///
+    /// [`Error::InvalidCalibration`]: enum.Error.html#variant.InvalidCalibration
+    pub fn calibrate(
+        &mut self,
+        min_angle_pulse: Duration,
+        max_angle_pulse: Duration,
+    ) -> Result<()> {
+        self.set_calibration(ServoCalibration {
+            min_pulse_us: min_angle_pulse.as_micros().min(u128::from(u32::MAX)) as u32,
+            max_pulse_us: max_angle_pulse.as_micros().min(u128::from(u32::MAX)) as u32,
+            ..self.calibration
+        })
+    }
+
+    /// Returns the trim offset in microseconds (µs).
+    pub fn trim(&self) -> i32 {
+        self.calibration.trim_us
+    }
+
+    /// Sets the trim offset in microseconds (µs), which is added to every
+    /// pulse width.
+    ///
+    /// The change takes effect the next time an angle is set.
+    pub fn set_trim(&mut self, trim_us: i32) {
+        self.calibration.trim_us = trim_us;
+    }
+
+    /// Returns the angle in degrees most recently set with [`set_angle`],
+    /// after clamping, or `None` if no angle has been set.
+    ///
+    /// [`set_angle`]: #method.set_angle
+    pub fn angle(&self) -> Option<f64> {
+        self.angle
+    }
+
+    /// Moves the servo to the specified angle in degrees, and enables the
+    /// PWM channel.
+    ///
+    /// `angle` is clamped to the range between `0.0` and the calibrated
+    /// angle range. If `angle` is NaN, `set_angle` returns
+    /// `Err(`[`Error::InvalidAngle`]`)`.
+    ///
+    /// [`Error::InvalidAngle`]: enum.Error.html#variant.InvalidAngle
+    pub fn set_angle(&mut self, angle: f64) -> Result<()> {
+        if angle.is_nan() {
+            return Err(Error::InvalidAngle(angle));
+        }
+
+        let angle = angle.clamp(0.0, self.calibration.range);
+
+        self.pwm
+            .set_pulse_width(self.calibration.pulse_width(angle))?;
+        self.pwm.enable()?;
+        self.angle = Some(angle);
+
+        Ok(())
+    }
+
+    /// Disables the PWM channel.
+    ///
+    /// Most servos stop actively holding their position when they don't
+    /// receive any pulses.
+    pub fn release(&mut self) -> Result<()> {
+        self.pwm.disable()
+    }
+
+    /// Consumes the `Servo`, and returns the underlying [`Pwm`].
+    ///
+    /// [`Pwm`]: struct.Pwm.html
+    pub fn into_inner(self) -> Pwm {
+        self.pwm
+    }
+}
@@ -229,18 +229,30 @@ pub fn enabled(channel: u8) -> Result<bool> {
 }
 
 pub fn set_enabled(channel: u8, enabled: bool) -> Result<()> {
-    File::create(format!("/sys/class/pwm/pwmchip0/pwm{}/enable", channel))?
-        .write_fmt(format_args!("{}", enabled as u8))
-        .map_err(|e| {
-            if e.kind() == io::ErrorKind::InvalidInput {
-                io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    "Make sure you have set either a period or frequency before enabling PWM",
-                )
-            } else {
-                e
-            }
-        })?;
+    set_enabled_all(&[channel], enabled)
+}
+
+// Opens the enable attribute for all channels before writing to any of them,
+// which keeps the delay between the individual channels as short as possible.
+pub fn set_enabled_all(channels: &[u8], enabled: bool) -> Result<()> {
+    let mut files = channels
+        .iter()
+        .map(|channel| File::create(format!("/sys/class/pwm/pwmchip0/pwm{}/enable", channel)))
+        .collect::<Result<Vec<File>>>()?;
+
+    for file in files.iter_mut() {
+        file.write_fmt(format_args!("{}", enabled as u8))
+            .map_err(|e| {
+                if e.kind() == io::ErrorKind::InvalidInput {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Make sure you have set either a period or frequency before enabling PWM",
+                    )
+                } else {
+                    e
+                }
+            })?;
+    }
 
     Ok(())
 }
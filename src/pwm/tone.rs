@@ -0,0 +1,215 @@
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+use super::{DutyCycle, Pwm, Result};
+
+// Default duty cycle. A square wave produces the loudest tone on most buzzers.
+const DEFAULT_DUTY_CYCLE: f64 = 0.5;
+// Default silence between consecutive notes
+const DEFAULT_GAP: Duration = Duration::from_millis(10);
+
+// Frequencies in hertz (Hz) for C0 through B8, based on twelve-tone equal
+// temperament with A4 tuned to 440 Hz.
+const FREQUENCIES: [f64; 108] = [
+    16.3516, 17.3239, 18.3540, 19.4454, 20.6017, 21.8268, 23.1247, 24.4997, 25.9565, 27.5000,
+    29.1352, 30.8677, 32.7032, 34.6478, 36.7081, 38.8909, 41.2034, 43.6535, 46.2493, 48.9994,
+    51.9131, 55.0000, 58.2705, 61.7354, 65.4064, 69.2957, 73.4162, 77.7817, 82.4069, 87.3071,
+    92.4986, 97.9989, 103.8262, 110.0000, 116.5409, 123.4708, 130.8128, 138.5913, 146.8324,
+    155.5635, 164.8138, 174.6141, 184.9972, 195.9977, 207.6523, 220.0000, 233.0819, 246.9417,
+    261.6256, 277.1826, 293.6648, 311.1270, 329.6276, 349.2282, 369.9944, 391.9954, 415.3047,
+    440.0000, 466.1638, 493.8833, 523.2511, 554.3653, 587.3295, 622.2540, 659.2551, 698.4565,
+    739.9888, 783.9909, 830.6094, 880.0000, 932.3275, 987.7666, 1046.5023, 1108.7305, 1174.6591,
+    1244.5079, 1318.5102, 1396.9129, 1479.9777, 1567.9817, 1661.2188, 1760.0000, 1864.6550,
+    1975.5332, 2093.0045, 2217.4610, 2349.3181, 2489.0159, 2637.0205, 2793.8259, 2959.9554,
+    3135.9635, 3322.4376, 3520.0000, 3729.3101, 3951.0664, 4186.0090, 4434.9221, 4698.6363,
+    4978.0317, 5274.0409, 5587.6517, 5919.9108, 6271.9270, 6644.8752, 7040.0000, 7458.6202,
+    7902.1328,
+];
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Musical notes, from C0 through B8.
+///
+/// Sharps are indicated by an `s` suffix. For instance, `Cs4` is C♯4, which
+/// sounds the same as D♭4. `A4` is tuned to 440 Hz. Use [`Rest`] for a
+/// period of silence in a melody.
+///
+/// [`Rest`]: #variant.Rest
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[rustfmt::skip]
+pub enum Note {
+    C0, Cs0, D0, Ds0, E0, F0, Fs0, G0, Gs0, A0, As0, B0,
+    C1, Cs1, D1, Ds1, E1, F1, Fs1, G1, Gs1, A1, As1, B1,
+    C2, Cs2, D2, Ds2, E2, F2, Fs2, G2, Gs2, A2, As2, B2,
+    C3, Cs3, D3, Ds3, E3, F3, Fs3, G3, Gs3, A3, As3, B3,
+    C4, Cs4, D4, Ds4, E4, F4, Fs4, G4, Gs4, A4, As4, B4,
+    C5, Cs5, D5, Ds5, E5, F5, Fs5, G5, Gs5, A5, As5, B5,
+    C6, Cs6, D6, Ds6, E6, F6, Fs6, G6, Gs6, A6, As6, B6,
+    C7, Cs7, D7, Ds7, E7, F7, Fs7, G7, Gs7, A7, As7, B7,
+    C8, Cs8, D8, Ds8, E8, F8, Fs8, G8, Gs8, A8, As8, B8,
+    /// Silence.
+    Rest,
+}
+
+impl Note {
+    /// Returns the frequency in hertz (Hz), or `None` for [`Rest`].
+    ///
+    /// [`Rest`]: #variant.Rest
+    pub fn frequency(self) -> Option<f64> {
+        FREQUENCIES.get(self as usize).copied()
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Note::Rest => write!(f, "Rest"),
+            note => {
+                let idx = note as usize;
+                write!(f, "{}{}", NOTE_NAMES[idx % 12], idx / 12)
+            }
+        }
+    }
+}
+
+/// Plays tones and melodies on a piezo buzzer or speaker connected to a PWM
+/// channel.
+///
+/// `Tone` sets the PWM frequency to the frequency of each note, and enables
+/// the PWM channel for the duration of the note. Passive buzzers and small
+/// speakers can be driven directly from a GPIO pin, or through a transistor
+/// for louder output. Active buzzers contain their own oscillator, and only
+/// produce a single tone regardless of the PWM frequency.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use rppal::pwm::{Channel, Note, Pwm, Tone};
+///
+/// # fn main() -> rppal::pwm::Result<()> {
+/// let mut tone = Tone::new(Pwm::new(Channel::Pwm0)?);
+///
+/// let quarter = Duration::from_millis(400);
+/// tone.play_melody(&[
+///     (Note::C4, quarter),
+///     (Note::E4, quarter),
+///     (Note::G4, quarter),
+///     (Note::Rest, quarter),
+///     (Note::C5, quarter * 2),
+/// ])?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Tone {
+    pwm: Pwm,
+    duty_cycle: DutyCycle,
+    gap: Duration,
+}
+
+impl Tone {
+    /// Constructs a new `Tone` using the specified PWM channel.
+    ///
+    /// The PWM channel is disabled until a note is played.
+    pub fn new(pwm: Pwm) -> Tone {
+        Tone {
+            pwm,
+            duty_cycle: DutyCycle::clamped(DEFAULT_DUTY_CYCLE),
+            gap: DEFAULT_GAP,
+        }
+    }
+
+    /// Returns the duty cycle used for each tone.
+    pub fn duty_cycle(&self) -> f64 {
+        self.duty_cycle.value()
+    }
+
+    /// Sets the duty cycle used for each tone, as a floating point value
+    /// between `0.0` (0%) and `1.0` (100%). Values outside that range are
+    /// clamped, and NaN is treated as `0.0`.
+    ///
+    /// Lowering the duty cycle reduces the volume on most buzzers, and
+    /// changes the timbre of the tone.
+    ///
+    /// By default, `duty_cycle` is set to `0.5`.
+    pub fn set_duty_cycle(&mut self, duty_cycle: f64) {
+        self.duty_cycle = DutyCycle::clamped(duty_cycle);
+    }
+
+    /// Returns the silence inserted at the end of each note.
+    pub fn gap(&self) -> Duration {
+        self.gap
+    }
+
+    /// Sets the silence inserted at the end of each note.
+    ///
+    /// The gap is taken from the duration of each note, which makes
+    /// consecutive notes with the same pitch distinguishable, without
+    /// changing the total duration of a melody. Set the gap to
+    /// [`Duration::ZERO`] to play consecutive notes without any silence in
+    /// between.
+    ///
+    /// By default, `gap` is set to 10 ms.
+    ///
+    /// [`Duration::ZERO`]: https://doc.rust-lang.org/std/time/struct.Duration.html#associatedconstant.ZERO
+    pub fn set_gap(&mut self, gap: Duration) {
+        self.gap = gap;
+    }
+
+    /// Plays a tone at the specified frequency in hertz (Hz), and blocks
+    /// until `duration` has elapsed.
+    ///
+    /// A `frequency` of `0.0` results in silence.
+    pub fn play_frequency(&mut self, frequency: f64, duration: Duration) -> Result<()> {
+        let tone_duration = duration.saturating_sub(self.gap);
+
+        if frequency > 0.0 && tone_duration > Duration::ZERO {
+            self.pwm.set_frequency(frequency, self.duty_cycle)?;
+            self.pwm.enable()?;
+            thread::sleep(tone_duration);
+            self.pwm.disable()?;
+            thread::sleep(duration - tone_duration);
+        } else {
+            self.pwm.disable()?;
+            thread::sleep(duration);
+        }
+
+        Ok(())
+    }
+
+    /// Plays the specified note, and blocks until `duration` has elapsed.
+    ///
+    /// [`Note::Rest`] results in silence.
+    ///
+    /// [`Note::Rest`]: enum.Note.html#variant.Rest
+    pub fn play(&mut self, note: Note, duration: Duration) -> Result<()> {
+        self.play_frequency(note.frequency().unwrap_or(0.0), duration)
+    }
+
+    /// Plays a sequence of notes, and blocks until the last note has
+    /// finished.
+    ///
+    /// Each element of `melody` contains a note and its duration.
+    pub fn play_melody(&mut self, melody: &[(Note, Duration)]) -> Result<()> {
+        for &(note, duration) in melody {
+            self.play(note, duration)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stops any tone that's currently playing by disabling the PWM channel.
+    pub fn stop(&mut self) -> Result<()> {
+        self.pwm.disable()
+    }
+
+    /// Consumes the `Tone`, and returns the underlying [`Pwm`].
+    ///
+    /// [`Pwm`]: struct.Pwm.html
+    pub fn into_inner(self) -> Pwm {
+        self.pwm
+    }
+}
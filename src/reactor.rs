@@ -0,0 +1,212 @@
+// Minimal reactor that turns file descriptor readiness into Waker
+// notifications, so blocking waits can be exposed as futures that work with
+// any executor. A single background thread is started the first time a
+// future needs to wait, and keeps running for the remainder of the process.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::os::unix::io::RawFd;
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Mutex, Once};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use libc::{EEXIST, ENOENT};
+
+use crate::gpio::epoll::{epoll_event, Epoll, EPOLLIN, EPOLLONESHOT, EPOLLPRI};
+
+// Maximum number of events retrieved by a single epoll_wait() call
+const MAX_EVENTS: usize = 16;
+
+struct Reactor {
+    poll: Epoll,
+    wakers: Mutex<HashMap<RawFd, Waker>>,
+}
+
+impl Reactor {
+    // Wakes waker once fd becomes readable. Each registration only fires
+    // once, and replaces any waker previously registered for fd.
+    fn register(&self, fd: RawFd, waker: &Waker) -> io::Result<()> {
+        let mut wakers = self.wakers.lock().unwrap();
+        let events = EPOLLIN | EPOLLPRI | EPOLLONESHOT;
+
+        // A closed fd is automatically removed from the epoll set, so a
+        // stale entry in wakers doesn't mean the fd is still registered
+        match self.poll.modify(fd, fd as u64, events) {
+            Err(ref e) if e.raw_os_error() == Some(ENOENT) => {
+                match self.poll.add(fd, fd as u64, events) {
+                    Err(ref e) if e.raw_os_error() == Some(EEXIST) => {
+                        self.poll.modify(fd, fd as u64, events)?
+                    }
+                    result => result?,
+                }
+            }
+            result => result?,
+        }
+
+        wakers.insert(fd, waker.clone());
+
+        Ok(())
+    }
+
+    fn deregister(&self, fd: RawFd) {
+        let mut wakers = self.wakers.lock().unwrap();
+
+        wakers.remove(&fd);
+        let _ = self.poll.delete(fd);
+    }
+
+    fn run(&self) {
+        let mut events = [epoll_event { events: 0, u64: 0 }; MAX_EVENTS];
+
+        loop {
+            let num_events = match self.poll.wait(&mut events, None) {
+                Ok(num_events) => num_events,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_e) => {
+                    log_warn!("Reactor poll failed: {}", _e);
+                    continue;
+                }
+            };
+
+            let mut wakers = self.wakers.lock().unwrap();
+            let ready: Vec<Waker> = events[..num_events]
+                .iter()
+                .filter_map(|event| wakers.remove(&(event.u64 as RawFd)))
+                .collect();
+            drop(wakers);
+
+            // Wake outside of the lock, in case the executor polls the future
+            // on this thread
+            for waker in ready {
+                waker.wake();
+            }
+        }
+    }
+}
+
+// Returns the reactor, starting its background thread on first use.
+fn reactor() -> io::Result<&'static Reactor> {
+    static REACTOR: AtomicPtr<Reactor> = AtomicPtr::new(ptr::null_mut());
+    static ONCE: Once = Once::new();
+
+    ONCE.call_once(|| {
+        let reactor = match Epoll::new() {
+            Ok(poll) => Box::new(Reactor {
+                poll,
+                wakers: Mutex::new(HashMap::new()),
+            }),
+            Err(_e) => {
+                log_warn!("Reactor epoll instance couldn't be created: {}", _e);
+                return;
+            }
+        };
+
+        // The reactor is never freed, so the background thread can hold on
+        // to a 'static reference
+        let reactor: &'static Reactor = Box::leak(reactor);
+
+        match thread::Builder::new()
+            .name("rppal-reactor".into())
+            .spawn(move || reactor.run())
+        {
+            Ok(_) => REACTOR.store(reactor as *const _ as *mut _, Ordering::SeqCst),
+            Err(_e) => log_warn!("Reactor thread couldn't be started: {}", _e),
+        }
+    });
+
+    let reactor = REACTOR.load(Ordering::SeqCst);
+    if reactor.is_null() {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "background reactor couldn't be started",
+        ))
+    } else {
+        // REACTOR is only set after successful initialization, and never freed
+        Ok(unsafe { &*reactor })
+    }
+}
+
+// Result of a single attempt to complete the operation wrapped by FdFuture.
+pub(crate) enum Attempt<T> {
+    // The operation completed
+    Ready(T),
+    // The operation can't make progress until fd becomes readable
+    Wait(RawFd),
+}
+
+// Future that repeatedly attempts a non-blocking operation, and waits for
+// the reactor to signal the relevant fd is readable in between attempts.
+// Dropping the future before it completes removes the registration, which
+// cancels the wait.
+pub(crate) struct FdFuture<F> {
+    attempt: F,
+    registered: Option<RawFd>,
+}
+
+impl<F> FdFuture<F> {
+    pub(crate) fn new(attempt: F) -> FdFuture<F> {
+        FdFuture {
+            attempt,
+            registered: None,
+        }
+    }
+
+    fn deregister(&mut self) {
+        if let Some(fd) = self.registered.take() {
+            if let Ok(reactor) = reactor() {
+                reactor.deregister(fd);
+            }
+        }
+    }
+}
+
+impl<F, T, E> Future for FdFuture<F>
+where
+    F: FnMut() -> Result<Attempt<T>, E> + Unpin,
+    E: From<io::Error>,
+{
+    type Output = Result<T, E>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        match (this.attempt)() {
+            Ok(Attempt::Ready(value)) => {
+                this.deregister();
+
+                Poll::Ready(Ok(value))
+            }
+            Ok(Attempt::Wait(fd)) => {
+                // The fd might have changed since the previous attempt
+                if this.registered.map_or(false, |registered| registered != fd) {
+                    this.deregister();
+                }
+
+                // Level-triggered, so readiness that occurred after the
+                // attempt is still reported
+                if let Err(e) = reactor().and_then(|reactor| reactor.register(fd, cx.waker())) {
+                    return Poll::Ready(Err(e.into()));
+                }
+
+                this.registered = Some(fd);
+
+                Poll::Pending
+            }
+            Err(e) => {
+                this.deregister();
+
+                Poll::Ready(Err(e))
+            }
+        }
+    }
+}
+
+impl<F> Drop for FdFuture<F> {
+    fn drop(&mut self) {
+        self.deregister();
+    }
+}
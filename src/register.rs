@@ -0,0 +1,218 @@
+//! Uniform access to slave devices that expose their functionality through
+//! 8-bit registers.
+//!
+//! Many sensors, ADCs and I/O expanders are controlled by reading and writing
+//! registers, and are available with an I2C interface, an SPI interface, or
+//! both. The [`RegisterDevice`] trait abstracts the transport, so a driver
+//! that's generic over `RegisterDevice` can communicate with the slave device
+//! through [`I2c`], [`BitBangI2c`] or [`Spi`] without any changes.
+//!
+//! ## I2C
+//!
+//! For [`I2c`] and [`BitBangI2c`], the register address is sent as the first
+//! byte of a write. Registers are read with a write of the register address,
+//! followed by a repeated START and a read, using [`I2c::write_read`].
+//! The slave address has to be selected with `set_slave_address` before the
+//! peripheral is passed to the driver.
+//!
+//! ## SPI
+//!
+//! SPI doesn't define a standard way to address registers. [`Spi`] follows
+//! the convention used by most register-based slave devices, such as the
+//! BME280, LIS3DH and MCP23S17 families:
+//!
+//! * The register address is sent as the first byte of the transfer, with
+//!   bit 7 set for reads, and cleared for writes. Register addresses are
+//!   limited to 7 bits.
+//! * Any data is sent or received immediately after the address byte, in
+//!   the same transfer. Multi-byte transfers rely on the slave device
+//!   automatically incrementing the register address.
+//!
+//! Slave devices that use a different convention, such as a separate bit to
+//! enable auto-increment, or a command byte before the register address,
+//! can be supported by implementing `RegisterDevice` for a wrapper type.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use rppal::i2c::I2c;
+//! use rppal::register::RegisterDevice;
+//! use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
+//!
+//! // Reads the chip ID register, regardless of the transport
+//! fn chip_id<D: RegisterDevice>(device: &mut D) -> Result<u8, D::Error> {
+//!     device.read_register(0xD0)
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut i2c = I2c::new()?;
+//! i2c.set_slave_address(0x76)?;
+//! println!("I2C chip ID: 0x{:02X}", chip_id(&mut i2c)?);
+//!
+//! let mut spi = Spi::new(Bus::Spi0, SlaveSelect::Ss0, 1_000_000, Mode::Mode0)?;
+//! println!("SPI chip ID: 0x{:02X}", chip_id(&mut spi)?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`RegisterDevice`]: trait.RegisterDevice.html
+//! [`I2c`]: ../i2c/struct.I2c.html
+//! [`I2c::write_read`]: ../i2c/struct.I2c.html#method.write_read
+//! [`BitBangI2c`]: ../i2c/struct.BitBangI2c.html
+//! [`Spi`]: ../spi/struct.Spi.html
+
+#[cfg(any(feature = "i2c", feature = "spi"))]
+use std::io;
+
+#[cfg(feature = "i2c")]
+use crate::i2c::{self, BitBangI2c, I2c};
+#[cfg(feature = "spi")]
+use crate::spi::{self, Spi};
+
+// Set in the SPI address byte to indicate a read
+#[cfg(feature = "spi")]
+const SPI_READ: u8 = 0x80;
+
+/// Provides access to a slave device's 8-bit registers.
+///
+/// Implementations are available for [`I2c`], [`BitBangI2c`] and [`Spi`].
+/// See the [module documentation] for the addressing conventions they
+/// follow.
+///
+/// Only [`read_block`] and [`write_block`] have to be implemented. The
+/// single-register methods call the block methods by default.
+///
+/// ```
+/// # #[cfg(feature = "mock")]
+/// # {
+/// use rppal::mock::{Registers, Simulator};
+/// use rppal::register::RegisterDevice;
+/// use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
+///
+/// let simulator = Simulator::new();
+/// simulator.add_spi_device(Bus::Spi0, SlaveSelect::Ss0, Registers::new());
+///
+/// let mut spi = Spi::with_simulator(&simulator, Bus::Spi0, SlaveSelect::Ss0, 1_000_000, Mode::Mode0)?;
+/// spi.write_block(0x10, &[0x12, 0x34])?;
+/// assert_eq!(spi.read_register(0x11)?, 0x34);
+///
+/// let mut buffer = [0u8; 2];
+/// spi.read_block(0x10, &mut buffer)?;
+/// assert_eq!(buffer, [0x12, 0x34]);
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`I2c`]: ../i2c/struct.I2c.html
+/// [`BitBangI2c`]: ../i2c/struct.BitBangI2c.html
+/// [`Spi`]: ../spi/struct.Spi.html
+/// [module documentation]: index.html
+/// [`read_block`]: #tymethod.read_block
+/// [`write_block`]: #tymethod.write_block
+pub trait RegisterDevice {
+    /// Error type returned by the transport.
+    type Error;
+
+    /// Reads consecutive registers, starting at `register`, into `buffer`.
+    fn read_block(&mut self, register: u8, buffer: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Writes `values` to consecutive registers, starting at `register`.
+    fn write_block(&mut self, register: u8, values: &[u8]) -> Result<(), Self::Error>;
+
+    /// Reads a single register.
+    fn read_register(&mut self, register: u8) -> Result<u8, Self::Error> {
+        let mut value = [0u8];
+        self.read_block(register, &mut value)?;
+
+        Ok(value[0])
+    }
+
+    /// Writes `value` to a single register.
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), Self::Error> {
+        self.write_block(register, &[value])
+    }
+}
+
+// Builds a write consisting of the address byte followed by values
+#[cfg(any(feature = "i2c", feature = "spi"))]
+fn address_and_values(address: u8, values: &[u8]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(values.len() + 1);
+    buffer.push(address);
+    buffer.extend_from_slice(values);
+
+    buffer
+}
+
+// The i2cdev interface and BitBangI2c report a NACK as an error, so a short
+// write is unexpected
+#[cfg(feature = "i2c")]
+fn check_i2c_write(length: usize, expected: usize) -> i2c::Result<()> {
+    if length < expected {
+        Err(i2c::Error::Io(io::Error::new(
+            io::ErrorKind::WriteZero,
+            "not all register values were written",
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "i2c")]
+impl RegisterDevice for I2c {
+    type Error = i2c::Error;
+
+    fn read_block(&mut self, register: u8, buffer: &mut [u8]) -> i2c::Result<()> {
+        self.write_read(&[register], buffer)
+    }
+
+    fn write_block(&mut self, register: u8, values: &[u8]) -> i2c::Result<()> {
+        let buffer = address_and_values(register, values);
+
+        check_i2c_write(self.write(&buffer)?, buffer.len())
+    }
+}
+
+#[cfg(feature = "i2c")]
+impl RegisterDevice for BitBangI2c {
+    type Error = i2c::Error;
+
+    fn read_block(&mut self, register: u8, buffer: &mut [u8]) -> i2c::Result<()> {
+        self.write_read(&[register], buffer)
+    }
+
+    fn write_block(&mut self, register: u8, values: &[u8]) -> i2c::Result<()> {
+        let buffer = address_and_values(register, values);
+
+        check_i2c_write(self.write(&buffer)?, buffer.len())
+    }
+}
+
+#[cfg(feature = "spi")]
+impl RegisterDevice for Spi {
+    type Error = spi::Error;
+
+    fn read_block(&mut self, register: u8, buffer: &mut [u8]) -> spi::Result<()> {
+        // The bytes received while the address is sent are discarded
+        let write_buffer = address_and_values(register | SPI_READ, &vec![0u8; buffer.len()]);
+        let mut read_buffer = vec![0u8; write_buffer.len()];
+
+        self.transfer(&mut read_buffer, &write_buffer)?;
+        buffer.copy_from_slice(&read_buffer[1..]);
+
+        Ok(())
+    }
+
+    fn write_block(&mut self, register: u8, values: &[u8]) -> spi::Result<()> {
+        let buffer = address_and_values(register & !SPI_READ, values);
+        let length = self.write(&buffer)?;
+
+        if length < buffer.len() {
+            return Err(spi::Error::Io(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "not all register values were written",
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+//! Retry policies for transient communication errors.
+//!
+//! A [`RetryPolicy`] describes how often a failed operation is attempted,
+//! and how long to wait in between attempts. [`I2c`], [`Spi`] and [`Uart`]
+//! each carry a retry policy, which is applied to their read, write and
+//! transfer operations. By default, the policy is [`RetryPolicy::none`], so
+//! every operation is attempted only once. Set a different policy with
+//! [`I2c::set_retry_policy`], [`Spi::set_retry_policy`] or
+//! [`Uart::set_retry_policy`].
+//!
+//! Only errors that are likely to be resolved by trying again are retried.
+//! Which errors those are depends on the peripheral, and is listed in the
+//! documentation for each `set_retry_policy` method. Operations that were
+//! interrupted by a signal are always retried, regardless of the retry
+//! policy, as described in the [crate-level documentation].
+//!
+//! The same policy can be used for your own operations through
+//! [`RetryPolicy::retry`], for instance to retry a complete multi-step
+//! exchange with a slave device.
+//!
+//! A policy can also be used to wait for a peripheral to become available
+//! during boot, with [`I2c::new_with_retry_policy`],
+//! [`Spi::new_with_retry_policy`] or [`Pwm::new_with_retry_policy`]. Their
+//! `new_with_retry` counterparts use a policy created by
+//! [`RetryPolicy::with_timeout`].
+//!
+//! [`RetryPolicy`]: struct.RetryPolicy.html
+//! [`RetryPolicy::none`]: struct.RetryPolicy.html#method.none
+//! [`RetryPolicy::retry`]: struct.RetryPolicy.html#method.retry
+//! [`RetryPolicy::with_timeout`]: struct.RetryPolicy.html#method.with_timeout
+//! [`I2c::new_with_retry_policy`]: ../i2c/struct.I2c.html#method.new_with_retry_policy
+//! [`Spi::new_with_retry_policy`]: ../spi/struct.Spi.html#method.new_with_retry_policy
+//! [`Pwm::new_with_retry_policy`]: ../pwm/struct.Pwm.html#method.new_with_retry_policy
+//! [`I2c`]: ../i2c/struct.I2c.html
+//! [`I2c::set_retry_policy`]: ../i2c/struct.I2c.html#method.set_retry_policy
+//! [`Spi`]: ../spi/struct.Spi.html
+//! [`Spi::set_retry_policy`]: ../spi/struct.Spi.html#method.set_retry_policy
+//! [`Uart`]: ../uart/struct.Uart.html
+//! [`Uart::set_retry_policy`]: ../uart/struct.Uart.html#method.set_retry_policy
+//! [crate-level documentation]: ../index.html
+
+use std::thread;
+use std::time::Duration;
+
+/// Describes how a failed operation is retried.
+///
+/// An operation is attempted up to `max_attempts` times in total. Before
+/// the first retry, `retry` waits for `initial_backoff`. Each subsequent
+/// wait is `multiplier` times as long as the previous one, up to a maximum
+/// of `max_backoff`.
+///
+/// The delays can be inspected with [`backoff`], which makes the policy's
+/// behavior testable without waiting for it.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use rppal::retry::RetryPolicy;
+///
+/// let policy = RetryPolicy::exponential(
+///     5,
+///     Duration::from_millis(1),
+///     Duration::from_millis(5),
+/// );
+///
+/// assert_eq!(policy.backoff(1), Duration::from_millis(1));
+/// assert_eq!(policy.backoff(2), Duration::from_millis(2));
+/// assert_eq!(policy.backoff(3), Duration::from_millis(4));
+/// assert_eq!(policy.backoff(4), Duration::from_millis(5));
+///
+/// let mut attempts = 0;
+/// let result = policy.retry(
+///     || {
+///         attempts += 1;
+///         if attempts < 3 { Err("busy") } else { Ok(attempts) }
+///     },
+///     |err| *err == "busy",
+/// );
+///
+/// assert_eq!(result, Ok(3));
+/// ```
+///
+/// [`backoff`]: #method.backoff
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one. A value of
+    /// `0` is treated as `1`.
+    pub max_attempts: u32,
+    /// The time to wait before the first retry.
+    pub initial_backoff: Duration,
+    /// The maximum time to wait before any retry.
+    pub max_backoff: Duration,
+    /// The factor applied to the wait time after each retry.
+    pub multiplier: f64,
+}
+
+impl RetryPolicy {
+    /// Constructs a new `RetryPolicy`.
+    pub fn new(
+        max_attempts: u32,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+        multiplier: f64,
+    ) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff,
+            max_backoff,
+            multiplier,
+        }
+    }
+
+    /// Constructs a new `RetryPolicy` that attempts each operation only
+    /// once.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::new(1, Duration::ZERO, Duration::ZERO, 1.0)
+    }
+
+    /// Constructs a new `RetryPolicy` that waits for the same `interval`
+    /// before each retry.
+    pub fn fixed(max_attempts: u32, interval: Duration) -> RetryPolicy {
+        RetryPolicy::new(max_attempts, interval, interval, 1.0)
+    }
+
+    /// Constructs a new `RetryPolicy` that waits for the same `interval`
+    /// before each retry, and keeps retrying until the waits add up to at
+    /// least `timeout`.
+    ///
+    /// The time spent on the attempts themselves isn't included, so the
+    /// total duration can exceed `timeout`.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use rppal::retry::RetryPolicy;
+    ///
+    /// let policy = RetryPolicy::with_timeout(Duration::from_secs(1), Duration::from_millis(10));
+    /// assert_eq!(policy.max_attempts, 101);
+    /// ```
+    pub fn with_timeout(timeout: Duration, interval: Duration) -> RetryPolicy {
+        let interval_ns = interval.as_nanos().max(1);
+        let retries = (timeout.as_nanos() + interval_ns - 1) / interval_ns;
+
+        RetryPolicy::fixed(
+            retries.saturating_add(1).min(u32::MAX as u128) as u32,
+            interval,
+        )
+    }
+
+    /// Constructs a new `RetryPolicy` that starts by waiting for
+    /// `initial_backoff`, and doubles the wait time after each retry, up to
+    /// a maximum of `max_backoff`.
+    pub fn exponential(
+        max_attempts: u32,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> RetryPolicy {
+        RetryPolicy::new(max_attempts, initial_backoff, max_backoff, 2.0)
+    }
+
+    /// Returns the time to wait before the specified retry.
+    ///
+    /// Retries are numbered starting at `1`. The first attempt isn't
+    /// preceded by a wait, so `backoff(0)` returns a zero duration.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        if retry == 0 {
+            return Duration::ZERO;
+        }
+
+        let factor = self
+            .multiplier
+            .max(0.0)
+            .powi((retry - 1).min(i32::MAX as u32) as i32);
+        let backoff_ns = (self.initial_backoff.as_nanos() as f64 * factor).round();
+
+        // A non-finite result means the multiplication overflowed
+        if !backoff_ns.is_finite() || backoff_ns >= self.max_backoff.as_nanos() as f64 {
+            self.max_backoff
+        } else {
+            Duration::from_nanos(backoff_ns as u64)
+        }
+    }
+
+    /// Calls `operation` until it succeeds, it returns an error for which
+    /// `is_transient` returns `false`, or the maximum number of attempts is
+    /// reached.
+    ///
+    /// Returns the result of the last attempt.
+    pub fn retry<T, E, F, P>(&self, mut operation: F, mut is_transient: P) -> Result<T, E>
+    where
+        F: FnMut() -> Result<T, E>,
+        P: FnMut(&E) -> bool,
+    {
+        let mut attempt = 1;
+
+        loop {
+            match operation() {
+                Err(ref err) if attempt < self.max_attempts && is_transient(err) => {
+                    log_debug!(
+                        "Transient error, retrying ({}/{})",
+                        attempt,
+                        self.max_attempts - 1
+                    );
+
+                    thread::sleep(self.backoff(attempt));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy::none()
+    }
+}
@@ -0,0 +1,403 @@
+// Without any peripheral features, every check is skipped
+#![cfg_attr(
+    not(any(
+        feature = "gpio",
+        feature = "i2c",
+        feature = "pwm",
+        feature = "spi",
+        feature = "uart"
+    )),
+    allow(dead_code, unused_imports)
+)]
+
+use std::error;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io;
+
+use crate::system::{DeviceInfo, Model, SoC};
+
+/// The outcome of a single self-test check.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum CheckStatus {
+    /// The check succeeded.
+    Pass,
+    /// The check failed. The check's hint describes how to resolve the issue.
+    Fail,
+    /// The check wasn't performed, because the relevant feature is disabled,
+    /// or the check doesn't apply to the current configuration.
+    Skip,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            CheckStatus::Pass => write!(f, "PASS"),
+            CheckStatus::Fail => write!(f, "FAIL"),
+            CheckStatus::Skip => write!(f, "SKIP"),
+        }
+    }
+}
+
+/// A single check performed by [`self_test`].
+///
+/// [`self_test`]: fn.self_test.html
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Check {
+    name: String,
+    status: CheckStatus,
+    detail: String,
+    hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &str, detail: String) -> Check {
+        Check::new(name, CheckStatus::Pass, detail, None)
+    }
+
+    fn fail(name: &str, detail: String, hint: Option<String>) -> Check {
+        Check::new(name, CheckStatus::Fail, detail, hint)
+    }
+
+    fn skip(name: &str, detail: String) -> Check {
+        Check::new(name, CheckStatus::Skip, detail, None)
+    }
+
+    fn new(name: &str, status: CheckStatus, detail: String, hint: Option<String>) -> Check {
+        Check {
+            name: name.to_owned(),
+            status,
+            detail,
+            hint,
+        }
+    }
+
+    /// Returns the name of the check, such as `"I2C"`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the outcome of the check.
+    pub fn status(&self) -> CheckStatus {
+        self.status
+    }
+
+    /// Returns a description of what was checked, and what was found.
+    pub fn detail(&self) -> &str {
+        &self.detail
+    }
+
+    /// Returns a suggestion on how to resolve a failed check, if available.
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.status, self.name, self.detail)?;
+
+        if let Some(ref hint) = self.hint {
+            write!(f, "\n       {}", hint)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The results of [`self_test`].
+///
+/// The [`Display`] implementation formats the report as one line per check,
+/// followed by an indented hint for each failed check, which is suitable for
+/// including in a bug report.
+///
+/// [`self_test`]: fn.self_test.html
+/// [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SelfTestReport {
+    model: Option<Model>,
+    soc: Option<SoC>,
+    checks: Vec<Check>,
+}
+
+impl SelfTestReport {
+    /// Returns the detected Raspberry Pi model, or `None` if the model
+    /// couldn't be identified.
+    pub fn model(&self) -> Option<Model> {
+        self.model.clone()
+    }
+
+    /// Returns the detected SoC, or `None` if the SoC couldn't be identified.
+    pub fn soc(&self) -> Option<SoC> {
+        self.soc
+    }
+
+    /// Returns all checks, in the order they were performed.
+    pub fn checks(&self) -> &[Check] {
+        &self.checks
+    }
+
+    /// Returns `true` if none of the checks failed.
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Returns an iterator over the failed checks.
+    pub fn failures(&self) -> impl Iterator<Item = &Check> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Fail)
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, check) in self.checks.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{}", check)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks whether the peripherals enabled through RPPAL's features are
+/// accessible on the current system.
+///
+/// `self_test` identifies the Raspberry Pi model and SoC, and verifies that
+/// the device nodes and sysfs interfaces for each peripheral exist and can
+/// be opened by the current user. Failed checks include a hint that
+/// describes how to resolve the issue, for instance which `config.txt`
+/// setting enables an interface, or which group the current user needs to
+/// be a member of. Checks for peripherals whose feature is disabled are
+/// skipped.
+///
+/// Device nodes are only opened and closed again. `self_test` doesn't
+/// communicate with any connected devices, or change any peripheral
+/// settings, so it's safe to call while other applications are running.
+/// The UART check only covers the primary UART (`/dev/serial0`).
+///
+/// ```no_run
+/// let report = rppal::self_test();
+/// println!("{}", report);
+///
+/// if !report.passed() {
+///     std::process::exit(1);
+/// }
+/// ```
+pub fn self_test() -> SelfTestReport {
+    let mut checks = Vec::new();
+
+    let device_info = match DeviceInfo::new() {
+        Ok(device_info) => {
+            checks.push(Check::pass(
+                "Model",
+                format!("{} ({})", device_info.model(), device_info.soc()),
+            ));
+
+            Some(device_info)
+        }
+        Err(err) => {
+            let detail = match error::Error::source(&err) {
+                Some(source) => format!("{}: {}", err, source),
+                None => err.to_string(),
+            };
+
+            checks.push(Check::fail(
+                "Model",
+                format!("Unable to identify the Raspberry Pi model: {}", detail),
+                Some(
+                    "Make sure /proc/device-tree or /proc/cpuinfo is accessible, and you're \
+                     using the latest release of RPPAL."
+                        .to_owned(),
+                ),
+            ));
+
+            None
+        }
+    };
+
+    check_gpio(&mut checks, device_info.as_ref());
+    check_i2c(&mut checks);
+    check_spi(&mut checks);
+    check_uart(&mut checks);
+    check_pwm(&mut checks);
+
+    SelfTestReport {
+        model: device_info.as_ref().map(|device_info| device_info.model()),
+        soc: device_info.as_ref().map(|device_info| device_info.soc()),
+        checks,
+    }
+}
+
+// Opens path for reading and writing, and describes the result. Devices are
+// opened non-blocking, and without becoming the controlling terminal, so
+// serial devices aren't affected.
+#[cfg(any(feature = "gpio", feature = "i2c", feature = "spi", feature = "uart"))]
+fn open_device(name: &str, path: &str) -> Check {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    use libc::{O_NOCTTY, O_NONBLOCK};
+
+    match OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(O_NOCTTY | O_NONBLOCK)
+        .open(path)
+    {
+        Ok(_) => Check::pass(name, format!("{} is accessible", path)),
+        Err(err) => failed(name, path, &err),
+    }
+}
+
+// Describes an error that occurred while accessing path, including a hint
+// if the cause is known.
+fn failed(name: &str, path: &str, err: &io::Error) -> Check {
+    let hint = crate::sys::permission_denied(err, path)
+        .map(|denied| denied.hint)
+        .or_else(|| crate::sys::not_enabled(err, path).map(|missing| missing.hint));
+
+    Check::fail(name, format!("Unable to access {}: {}", path, err), hint)
+}
+
+// Returns the paths in /dev that start with prefix, sorted by name.
+#[cfg(any(feature = "i2c", feature = "spi"))]
+fn device_nodes(prefix: &str) -> Vec<String> {
+    let mut paths: Vec<String> = std::fs::read_dir("/dev")
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| name.starts_with(prefix))
+                .map(|name| format!("/dev/{}", name))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    paths.sort();
+    paths
+}
+
+// Checks every device node starting with prefix, or reports the default
+// device node as missing if there aren't any.
+#[cfg(any(feature = "i2c", feature = "spi"))]
+fn check_device_nodes(checks: &mut Vec<Check>, name: &str, prefix: &str, default: &str) {
+    let paths = device_nodes(prefix);
+
+    if paths.is_empty() {
+        checks.push(failed(
+            name,
+            default,
+            &io::Error::from_raw_os_error(libc::ENOENT),
+        ));
+    }
+
+    for path in paths {
+        checks.push(open_device(name, &path));
+    }
+}
+
+#[cfg(feature = "gpio")]
+fn check_gpio(checks: &mut Vec<Check>, device_info: Option<&DeviceInfo>) {
+    checks.push(open_device("GPIO", "/dev/gpiochip0"));
+
+    let device_info = match device_info {
+        Some(device_info) => device_info,
+        None => {
+            checks.push(Check::skip("GPIO registers", "Unknown model".to_owned()));
+            return;
+        }
+    };
+
+    let path = if device_info.capabilities().rp1() {
+        "/dev/gpiomem0"
+    } else {
+        "/dev/gpiomem"
+    };
+
+    if std::path::Path::new(path).exists() {
+        checks.push(open_device("GPIO registers", path));
+    } else {
+        checks.push(Check::skip(
+            "GPIO registers",
+            format!(
+                "{} doesn't exist, the slower gpiochip backend is used",
+                path
+            ),
+        ));
+    }
+}
+
+#[cfg(not(feature = "gpio"))]
+fn check_gpio(checks: &mut Vec<Check>, _: Option<&DeviceInfo>) {
+    checks.push(Check::skip(
+        "GPIO",
+        "The gpio feature is disabled".to_owned(),
+    ));
+}
+
+#[cfg(feature = "i2c")]
+fn check_i2c(checks: &mut Vec<Check>) {
+    check_device_nodes(checks, "I2C", "i2c-", "/dev/i2c-1");
+}
+
+#[cfg(not(feature = "i2c"))]
+fn check_i2c(checks: &mut Vec<Check>) {
+    checks.push(Check::skip("I2C", "The i2c feature is disabled".to_owned()));
+}
+
+#[cfg(feature = "spi")]
+fn check_spi(checks: &mut Vec<Check>) {
+    check_device_nodes(checks, "SPI", "spidev", "/dev/spidev0.0");
+}
+
+#[cfg(not(feature = "spi"))]
+fn check_spi(checks: &mut Vec<Check>) {
+    checks.push(Check::skip("SPI", "The spi feature is disabled".to_owned()));
+}
+
+#[cfg(feature = "uart")]
+fn check_uart(checks: &mut Vec<Check>) {
+    const PATH_SERIAL0: &str = "/dev/serial0";
+
+    match std::fs::canonicalize(PATH_SERIAL0) {
+        Ok(path) => {
+            let mut check = open_device("UART", &path.to_string_lossy());
+            check.detail = format!("{} ({})", check.detail, PATH_SERIAL0);
+            checks.push(check);
+        }
+        Err(err) => checks.push(failed("UART", PATH_SERIAL0, &err)),
+    }
+}
+
+#[cfg(not(feature = "uart"))]
+fn check_uart(checks: &mut Vec<Check>) {
+    checks.push(Check::skip(
+        "UART",
+        "The uart feature is disabled".to_owned(),
+    ));
+}
+
+#[cfg(feature = "pwm")]
+fn check_pwm(checks: &mut Vec<Check>) {
+    const PATH_PWMCHIP: &str = "/sys/class/pwm/pwmchip0";
+
+    let path = format!("{}/export", PATH_PWMCHIP);
+
+    // Opening export for writing checks access without exporting a channel
+    match OpenOptions::new().write(true).open(&path) {
+        Ok(_) => checks.push(Check::pass("PWM", format!("{} is accessible", path))),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+            checks.push(failed("PWM", PATH_PWMCHIP, err))
+        }
+        Err(err) => checks.push(failed("PWM", &path, &err)),
+    }
+}
+
+#[cfg(not(feature = "pwm"))]
+fn check_pwm(checks: &mut Vec<Check>) {
+    checks.push(Check::skip("PWM", "The pwm feature is disabled".to_owned()));
+}
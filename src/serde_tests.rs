@@ -0,0 +1,282 @@
+// Round-trip tests for the string names each enum is (de)serialized as with
+// the optional serde feature enabled.
+
+use std::fmt;
+
+use serde::de::{value, IntoDeserializer};
+use serde::{Deserialize, Serialize};
+
+// Serializes each value through fmt::Formatter, which supports unit variants
+// without needing a serde data format as a dev-dependency, and deserializes
+// each name back from a string.
+fn round_trip<T>(table: &[(T, &str)])
+where
+    T: Serialize + for<'de> Deserialize<'de> + PartialEq + fmt::Debug,
+{
+    struct Serialized<'a, T>(&'a T);
+
+    impl<T: Serialize> fmt::Display for Serialized<'_, T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.serialize(f)
+        }
+    }
+
+    for (value, name) in table {
+        assert_eq!(Serialized(value).to_string(), *name);
+        assert_eq!(
+            T::deserialize(IntoDeserializer::<value::Error>::into_deserializer(*name)).unwrap(),
+            *value
+        );
+    }
+}
+
+#[cfg(feature = "gpio")]
+#[test]
+fn gpio_mode() {
+    use crate::gpio::Mode;
+
+    round_trip(&[
+        (Mode::Input, "input"),
+        (Mode::Output, "output"),
+        (Mode::Alt0, "alt0"),
+        (Mode::Alt1, "alt1"),
+        (Mode::Alt2, "alt2"),
+        (Mode::Alt3, "alt3"),
+        (Mode::Alt4, "alt4"),
+        (Mode::Alt5, "alt5"),
+        (Mode::Alt6, "alt6"),
+        (Mode::Alt7, "alt7"),
+        (Mode::Alt8, "alt8"),
+        (Mode::Null, "null"),
+    ]);
+}
+
+#[cfg(feature = "gpio")]
+#[test]
+fn gpio_level() {
+    use crate::gpio::Level;
+
+    round_trip(&[(Level::Low, "low"), (Level::High, "high")]);
+}
+
+#[cfg(feature = "gpio")]
+#[test]
+fn gpio_pull_up_down() {
+    use crate::gpio::PullUpDown;
+
+    round_trip(&[
+        (PullUpDown::Off, "off"),
+        (PullUpDown::PullDown, "pull-down"),
+        (PullUpDown::PullUp, "pull-up"),
+    ]);
+}
+
+#[cfg(feature = "gpio")]
+#[test]
+fn gpio_trigger() {
+    use crate::gpio::Trigger;
+
+    round_trip(&[
+        (Trigger::Disabled, "disabled"),
+        (Trigger::RisingEdge, "rising-edge"),
+        (Trigger::FallingEdge, "falling-edge"),
+        (Trigger::Both, "both"),
+    ]);
+}
+
+#[cfg(feature = "pwm")]
+#[test]
+fn pwm_channel() {
+    use crate::pwm::Channel;
+
+    round_trip(&[(Channel::Pwm0, "pwm0"), (Channel::Pwm1, "pwm1")]);
+}
+
+#[cfg(feature = "pwm")]
+#[test]
+fn pwm_polarity() {
+    use crate::pwm::Polarity;
+
+    round_trip(&[(Polarity::Normal, "normal"), (Polarity::Inverse, "inverse")]);
+}
+
+#[cfg(feature = "pwm")]
+#[test]
+fn pwm_ramp_curve() {
+    use crate::pwm::RampCurve;
+
+    round_trip(&[
+        (RampCurve::Linear, "linear"),
+        (RampCurve::Quadratic, "quadratic"),
+        (RampCurve::SCurve, "s-curve"),
+    ]);
+}
+
+#[cfg(feature = "spi")]
+#[test]
+fn spi_bus() {
+    use crate::spi::Bus;
+
+    round_trip(&[
+        (Bus::Spi0, "spi0"),
+        (Bus::Spi1, "spi1"),
+        (Bus::Spi2, "spi2"),
+        (Bus::Spi3, "spi3"),
+        (Bus::Spi4, "spi4"),
+        (Bus::Spi5, "spi5"),
+        (Bus::Spi6, "spi6"),
+    ]);
+}
+
+#[cfg(feature = "spi")]
+#[test]
+fn spi_slave_select() {
+    use crate::spi::SlaveSelect;
+
+    round_trip(&[
+        (SlaveSelect::Ss0, "ss0"),
+        (SlaveSelect::Ss1, "ss1"),
+        (SlaveSelect::Ss2, "ss2"),
+        (SlaveSelect::Ss3, "ss3"),
+        (SlaveSelect::Ss4, "ss4"),
+        (SlaveSelect::Ss5, "ss5"),
+        (SlaveSelect::Ss6, "ss6"),
+        (SlaveSelect::Ss7, "ss7"),
+        (SlaveSelect::Ss8, "ss8"),
+        (SlaveSelect::Ss9, "ss9"),
+        (SlaveSelect::Ss10, "ss10"),
+        (SlaveSelect::Ss11, "ss11"),
+        (SlaveSelect::Ss12, "ss12"),
+        (SlaveSelect::Ss13, "ss13"),
+        (SlaveSelect::Ss14, "ss14"),
+        (SlaveSelect::Ss15, "ss15"),
+    ]);
+}
+
+#[cfg(feature = "spi")]
+#[test]
+fn spi_polarity() {
+    use crate::spi::Polarity;
+
+    round_trip(&[
+        (Polarity::ActiveLow, "active-low"),
+        (Polarity::ActiveHigh, "active-high"),
+    ]);
+}
+
+#[cfg(feature = "spi")]
+#[test]
+fn spi_mode() {
+    use crate::spi::Mode;
+
+    round_trip(&[
+        (Mode::Mode0, "mode0"),
+        (Mode::Mode1, "mode1"),
+        (Mode::Mode2, "mode2"),
+        (Mode::Mode3, "mode3"),
+    ]);
+}
+
+#[cfg(feature = "spi")]
+#[test]
+fn spi_bit_order() {
+    use crate::spi::BitOrder;
+
+    round_trip(&[
+        (BitOrder::MsbFirst, "msb-first"),
+        (BitOrder::LsbFirst, "lsb-first"),
+    ]);
+}
+
+#[test]
+fn system_model() {
+    use crate::system::Model;
+
+    round_trip(&[
+        (Model::RaspberryPiA, "raspberry-pi-a"),
+        (Model::RaspberryPiAPlus, "raspberry-pi-a-plus"),
+        (Model::RaspberryPiBRev1, "raspberry-pi-b-rev1"),
+        (Model::RaspberryPiBRev2, "raspberry-pi-b-rev2"),
+        (Model::RaspberryPiBPlus, "raspberry-pi-b-plus"),
+        (Model::RaspberryPi2B, "raspberry-pi2-b"),
+        (Model::RaspberryPi3APlus, "raspberry-pi3-a-plus"),
+        (Model::RaspberryPi3B, "raspberry-pi3-b"),
+        (Model::RaspberryPi3BPlus, "raspberry-pi3-b-plus"),
+        (Model::RaspberryPi4B, "raspberry-pi4-b"),
+        (Model::RaspberryPi400, "raspberry-pi400"),
+        (Model::RaspberryPi5, "raspberry-pi5"),
+        (Model::RaspberryPi500, "raspberry-pi500"),
+        (
+            Model::RaspberryPiComputeModule,
+            "raspberry-pi-compute-module",
+        ),
+        (
+            Model::RaspberryPiComputeModule3,
+            "raspberry-pi-compute-module3",
+        ),
+        (
+            Model::RaspberryPiComputeModule3Plus,
+            "raspberry-pi-compute-module3-plus",
+        ),
+        (
+            Model::RaspberryPiComputeModule4,
+            "raspberry-pi-compute-module4",
+        ),
+        (
+            Model::RaspberryPiComputeModule4S,
+            "raspberry-pi-compute-module4-s",
+        ),
+        (
+            Model::RaspberryPiComputeModule5,
+            "raspberry-pi-compute-module5",
+        ),
+        (
+            Model::RaspberryPiComputeModule5Lite,
+            "raspberry-pi-compute-module5-lite",
+        ),
+        (Model::RaspberryPiZero, "raspberry-pi-zero"),
+        (Model::RaspberryPiZeroW, "raspberry-pi-zero-w"),
+        (Model::RaspberryPiZero2W, "raspberry-pi-zero2-w"),
+    ]);
+}
+
+#[test]
+fn system_soc() {
+    use crate::system::SoC;
+
+    round_trip(&[
+        (SoC::Bcm2835, "bcm2835"),
+        (SoC::Bcm2836, "bcm2836"),
+        (SoC::Bcm2837A1, "bcm2837-a1"),
+        (SoC::Bcm2837B0, "bcm2837-b0"),
+        (SoC::Bcm2711, "bcm2711"),
+        (SoC::Bcm2712, "bcm2712"),
+    ]);
+}
+
+#[cfg(feature = "uart")]
+#[test]
+fn uart_parity() {
+    use crate::uart::Parity;
+
+    round_trip(&[
+        (Parity::None, "none"),
+        (Parity::Even, "even"),
+        (Parity::Odd, "odd"),
+        (Parity::Mark, "mark"),
+        (Parity::Space, "space"),
+    ]);
+}
+
+#[cfg(feature = "uart")]
+#[test]
+fn uart_parity_check() {
+    use crate::uart::ParityCheck;
+
+    round_trip(&[
+        (ParityCheck::None, "none"),
+        (ParityCheck::Strip, "strip"),
+        (ParityCheck::Replace, "replace"),
+        (ParityCheck::Mark, "mark"),
+    ]);
+}
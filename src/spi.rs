@@ -111,10 +111,13 @@
 //!
 //! Some features exposed by the generic `spidev` interface aren't fully
 //! supported by the underlying driver or the BCM283x SoC: `SPI_LSB_FIRST` (LSB
-//! first bit order), `SPI_3WIRE` (bidirectional mode), `SPI_LOOP` (loopback mode),
-//! `SPI_NO_CS` (no Slave Select), `SPI_READY` (slave ready signal),
-//! `SPI_TX_DUAL`/`SPI_RX_DUAL` (dual SPI), `SPI_TX_QUAD`/`SPI_RX_QUAD` (quad SPI),
-//! and any number of bits per word other than 8.
+//! first bit order), `SPI_LOOP` (loopback mode), `SPI_NO_CS` (no Slave Select),
+//! `SPI_READY` (slave ready signal), `SPI_TX_DUAL`/`SPI_RX_DUAL` (dual SPI),
+//! `SPI_TX_QUAD`/`SPI_RX_QUAD` (quad SPI), and any number of bits per word other
+//! than 8.
+//!
+//! `SPI_3WIRE` (bidirectional mode) is supported by SPI0 on the Raspberry Pi 4 B
+//! and earlier models, and can be enabled with [`Spi::set_3wire`].
 //!
 //! If your slave device requires `SPI_LSB_FIRST`, you can use the
 //! [`reverse_bits`] function instead to reverse the bit order in software.
@@ -126,33 +129,90 @@
 //! slave device to any other available GPIO pin on the Pi, and manually
 //! changing it to high and low as needed.
 //!
+//! ## Troubleshooting
+//!
+//! ### Not enabled
+//!
+//! If [`new`] returns [`Error::NotEnabled`], the device node for the selected
+//! bus and Slave Select pin doesn't exist. The error's `hint` field suggests
+//! which `config.txt` setting is missing, based on the current configuration.
+//! Changes to `config.txt` only take effect after a reboot.
+//!
+//! ### Permission denied
+//!
+//! If [`new`] returns [`Error::PermissionDenied`], make sure the file
+//! permissions for `/dev/spidevB.S` are correct, and the current user is a
+//! member of the `spi` group.
+//!
+//! [`new`]: struct.Spi.html#method.new
+//! [`Error::NotEnabled`]: enum.Error.html#variant.NotEnabled
+//! [`Error::PermissionDenied`]: enum.Error.html#variant.PermissionDenied
 //! [`Ss0`]: enum.SlaveSelect.html
 //! [`Ss1`]: enum.SlaveSelect.html
 //! [`Ss2`]: enum.SlaveSelect.html
 //! [`Mode1`]: enum.Mode.html
 //! [`Mode3`]: enum.Mode.html
 //! [`reverse_bits`]: fn.reverse_bits.html
+//! [`Spi::set_3wire`]: struct.Spi.html#method.set_3wire
 
+#[cfg(feature = "metrics")]
+use std::cell::Cell;
 use std::error;
 use std::fmt;
-use std::fs::{File, OpenOptions};
+use std::fs::{self, OpenOptions};
 use std::io;
-use std::io::{Read, Write};
 use std::marker::PhantomData;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::result;
+use std::time::Duration;
+
+#[cfg(feature = "mock")]
+use crate::mock::Simulator;
+use crate::retry::RetryPolicy;
+use crate::sys::DeviceNode;
+use crate::system::mailbox::ClockId;
+use crate::system::{self, DeviceInfo, ProcessInfo, SoC};
 
-#[cfg(feature = "hal")]
+mod config;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+mod dispatch;
+#[cfg(any(feature = "hal-0", feature = "hal-1"))]
 mod hal;
 mod ioctl;
+#[cfg(feature = "mock")]
+mod mock;
 mod segment;
+#[cfg(feature = "metrics")]
+mod stats;
 
+use self::dispatch as sys;
+
+pub use self::config::SpiConfig;
+#[cfg(feature = "diagnostics")]
+pub use self::diagnostics::CsTiming;
+#[cfg(feature = "hal-1")]
+pub use self::hal::SimpleHalSpiDevice;
 pub use self::segment::Segment;
-#[cfg(feature = "hal")]
-pub use hal::SimpleHalSpiDevice;
+#[cfg(feature = "metrics")]
+pub use self::stats::SpiStats;
+
+const PATH_SPIDEV_BUFSIZ: &str = "/sys/module/spidev/parameters/bufsiz";
+const PATH_SPI_DEVICES: &str = "/sys/bus/spi/devices";
+// Default spidev buffer size
+const DEFAULT_BUFSIZ: usize = 4096;
+
+// Interval between attempts to open a device node that doesn't exist yet
+const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+// The RP1's SPI controllers are clocked by its fixed 200 MHz system clock
+const RP1_SPI_CLOCK: u32 = 200_000_000;
+// Largest clock divider supported by the SPI controllers
+const MAX_CLOCK_DIVIDER: u32 = 65_536;
 
 /// Errors that can occur when accessing the SPI peripheral.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// I/O error.
     Io(io::Error),
@@ -179,6 +239,52 @@ pub enum Error {
     ModeNotSupported(Mode),
     /// The specified Slave Select polarity is not supported.
     PolarityNotSupported(Polarity),
+    /// SPI bus not enabled.
+    ///
+    /// The SPI bus device node didn't appear before the timeout elapsed. Make
+    /// sure the SPI bus and the selected Slave Select pin are enabled. More
+    /// information can be found [here].
+    ///
+    /// [here]: index.html#spi-buses
+    BusNotEnabled,
+    /// SPI interface not enabled.
+    ///
+    /// The SPI device node at `path` doesn't exist, because the SPI interface
+    /// hasn't been enabled in the firmware configuration, or the change hasn't
+    /// taken effect yet. `hint` describes how to enable it, based on the
+    /// contents of `config.txt`.
+    ///
+    /// More information can be found [here].
+    ///
+    /// [here]: index.html#not-enabled
+    NotEnabled { path: String, hint: String },
+    /// Permission denied.
+    ///
+    /// The current user doesn't have read/write access to the SPI device node.
+    /// `path` contains the path that couldn't be accessed, `required_group` the
+    /// group that conventionally grants access to it (`spi`), and `hint`
+    /// describes how to resolve the issue.
+    ///
+    /// More information on possible causes for this error can be found [here].
+    ///
+    /// [here]: index.html#permission-denied
+    PermissionDenied {
+        path: String,
+        required_group: Option<&'static str>,
+        hint: String,
+    },
+    /// Device or resource busy.
+    ///
+    /// The SPI device node at `path` is in use. `users` contains the
+    /// processes that currently have it opened, as returned by
+    /// [`system::find_device_users`]. The list may be empty or incomplete if
+    /// the current user isn't allowed to inspect the other processes.
+    ///
+    /// [`system::find_device_users`]: ../system/fn.find_device_users.html
+    Busy {
+        path: String,
+        users: Vec<ProcessInfo>,
+    },
 }
 
 impl fmt::Display for Error {
@@ -198,18 +304,135 @@ impl fmt::Display for Error {
             Error::PolarityNotSupported(polarity) => {
                 write!(f, "Polarity value not supported: {:?}", polarity)
             }
+            Error::BusNotEnabled => write!(f, "SPI bus not enabled"),
+            Error::NotEnabled { ref path, ref hint } => {
+                write!(f, "SPI not enabled: {} ({})", path, hint)
+            }
+            Error::PermissionDenied {
+                ref path, ref hint, ..
+            } => write!(f, "Permission denied: {} ({})", path, hint),
+            Error::Busy {
+                ref path,
+                ref users,
+            } => {
+                write!(f, "Device busy: {} (", path)?;
+                crate::sys::fmt_users(f, users)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => err.source(),
+            _ => None,
         }
     }
 }
 
-impl error::Error for Error {}
+impl Error {
+    // Converts an I/O error that occurred while accessing path, and classifies
+    // any missing interface, permission and busy errors.
+    fn with_path(err: io::Error, path: &str) -> Error {
+        if let Some(missing) = crate::sys::not_enabled(&err, path) {
+            return Error::NotEnabled {
+                path: missing.path,
+                hint: missing.hint,
+            };
+        }
+
+        if let Some(denied) = crate::sys::permission_denied(&err, path) {
+            return Error::PermissionDenied {
+                path: denied.path,
+                required_group: denied.required_group,
+                hint: denied.hint,
+            };
+        }
+
+        match crate::sys::busy(&err, path) {
+            Some(users) => Error::Busy {
+                path: path.to_owned(),
+                users,
+            },
+            None => Error::from(crate::sys::io_error_with_path(err, path)),
+        }
+    }
+
+    // Converts an I/O error returned by an ioctl on fd, and classifies any
+    // permission errors.
+    fn with_fd(err: io::Error, fd: RawFd) -> Error {
+        match crate::sys::permission_denied_fd(&err, fd) {
+            Some(denied) => Error::PermissionDenied {
+                path: denied.path,
+                required_group: denied.required_group,
+                hint: denied.hint,
+            },
+            None => Error::from(err),
+        }
+    }
+
+    // Returns true if the error was caused by a device node that doesn't exist
+    // (yet).
+    fn is_missing_device(&self) -> bool {
+        match *self {
+            Error::NotEnabled { .. } => true,
+            Error::Io(ref err) => err.kind() == io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+}
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Error {
+        log_debug!("I/O error: {}", err);
+
         Error::Io(err)
     }
 }
 
+impl From<system::Error> for Error {
+    fn from(err: system::Error) -> Error {
+        match err {
+            system::Error::Io(err) => Error::from(err),
+            err => Error::from(io::Error::new(io::ErrorKind::Other, err)),
+        }
+    }
+}
+
+// Sign-extends a 24-bit value stored in the lower 24 bits of value.
+fn sign_extend_24(value: u32) -> i32 {
+    ((value << 8) as i32) >> 8
+}
+
+// Calculates the clock speed produced for the requested speed, using the
+// same divider calculation as the SPI drivers.
+fn divided_clock_speed(input_clock: u32, requested: u32) -> u32 {
+    if requested == 0 {
+        return input_clock / MAX_CLOCK_DIVIDER;
+    }
+
+    let mut divider = input_clock / requested;
+    if input_clock % requested != 0 {
+        divider += 1;
+    }
+
+    // The divider must be even
+    let divider = (divider + divider % 2).clamp(2, MAX_CLOCK_DIVIDER);
+
+    input_clock / divider
+}
+
+// Reads the current spidev bufsiz value, which limits the size of a single
+// read, write or transfer
+fn bufsiz() -> usize {
+    fs::read_to_string(PATH_SPIDEV_BUFSIZ)
+        .ok()
+        .and_then(|bufsiz| bufsiz.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_BUFSIZ)
+}
+
 /// Result type returned from methods that can have `spi::Error`s.
 pub type Result<T> = result::Result<T, Error>;
 
@@ -224,13 +447,33 @@ pub fn reverse_bits(buffer: &mut [u8]) {
     }
 }
 
+// Errors that may not occur when the operation is repeated
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.raw_os_error(),
+        Some(libc::EAGAIN) | Some(libc::ETIMEDOUT)
+    )
+}
+
+// Converts a delay to whole microseconds, rounded up, for use in a Segment
+fn delay_micros(delay: Duration) -> u16 {
+    let micros = (delay.as_nanos() + 999) / 1000;
+
+    micros.min(u128::from(u16::MAX)) as u16
+}
+
 /// SPI buses.
 ///
 /// The Raspberry Pi exposes up to five SPI buses, depending on the model and
 /// your `/boot/config.txt` configuration. More information can be found [here].
 ///
+/// With the optional `serde` feature enabled, `Bus` is (de)serialized as
+/// `"spi0"` through `"spi6"`.
+///
 /// [here]: index.html
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum Bus {
     Spi0 = 0,
     Spi1 = 1,
@@ -268,8 +511,13 @@ impl fmt::Display for Bus {
 /// on your `/boot/config.txt` configuration. More information can be found
 /// [here].
 ///
+/// With the optional `serde` feature enabled, `SlaveSelect` is (de)serialized
+/// as `"ss0"` through `"ss15"`.
+///
 /// [here]: index.html
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum SlaveSelect {
     Ss0 = 0,
     Ss1 = 1,
@@ -313,7 +561,12 @@ impl fmt::Display for SlaveSelect {
 }
 
 /// Slave Select polarities.
+///
+/// With the optional `serde` feature enabled, `Polarity` is (de)serialized as
+/// `"active-low"` or `"active-high"`.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum Polarity {
     ActiveLow = 0,
     ActiveHigh = 1,
@@ -333,21 +586,37 @@ impl fmt::Display for Polarity {
 /// Select the appropriate SPI mode for your device. Each mode configures the
 /// clock polarity (CPOL) and clock phase (CPHA) as shown below:
 ///
-/// * Mode0: CPOL 0, CPHA 0
-/// * Mode1: CPOL 0, CPHA 1
-/// * Mode2: CPOL 1, CPHA 0
-/// * Mode3: CPOL 1, CPHA 1
+/// * Mode0: CPOL 0, CPHA 0. The clock idles low. Data is sampled on the
+///   rising edge, and shifted out on the falling edge.
+/// * Mode1: CPOL 0, CPHA 1. The clock idles low. Data is shifted out on the
+///   rising edge, and sampled on the falling edge.
+/// * Mode2: CPOL 1, CPHA 0. The clock idles high. Data is sampled on the
+///   falling edge, and shifted out on the rising edge.
+/// * Mode3: CPOL 1, CPHA 1. The clock idles high. Data is shifted out on the
+///   falling edge, and sampled on the rising edge.
+///
+/// In other words, CPOL determines the clock's idle state, and CPHA whether
+/// data is sampled on the first (leading) or second (trailing) clock edge of
+/// each bit. Datasheets don't always mention the mode number, so if a
+/// datasheet only lists CPOL and CPHA, use [`from_cpol_cpha`] to avoid mixing
+/// up the mode numbers.
 ///
 /// The [`Spi0`] bus supports all 4 modes. [`Spi1`] and [`Spi2`] only support
 /// `Mode0` and `Mode2`.
 ///
 /// More information on clock polarity and phase can be found on [Wikipedia].
 ///
+/// With the optional `serde` feature enabled, `Mode` is (de)serialized as
+/// `"mode0"` through `"mode3"`.
+///
 /// [`Spi0`]: enum.Bus.html
 /// [`Spi1`]: enum.Bus.html
 /// [`Spi2`]: enum.Bus.html
+/// [`from_cpol_cpha`]: #method.from_cpol_cpha
 /// [Wikipedia]: https://en.wikipedia.org/wiki/Serial_Peripheral_Interface_Bus#Clock_polarity_and_phase
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum Mode {
     Mode0 = 0,
     Mode1 = 1,
@@ -355,6 +624,41 @@ pub enum Mode {
     Mode3 = 3,
 }
 
+impl Mode {
+    /// Constructs a `Mode` based on the clock polarity (`cpol`) and clock
+    /// phase (`cpha`), where `false` corresponds to 0 and `true` to 1.
+    ///
+    /// ```
+    /// use rppal::spi::Mode;
+    ///
+    /// assert_eq!(Mode::from_cpol_cpha(true, false), Mode::Mode2);
+    /// ```
+    pub const fn from_cpol_cpha(cpol: bool, cpha: bool) -> Mode {
+        match (cpol, cpha) {
+            (false, false) => Mode::Mode0,
+            (false, true) => Mode::Mode1,
+            (true, false) => Mode::Mode2,
+            (true, true) => Mode::Mode3,
+        }
+    }
+
+    /// Returns the clock polarity (CPOL).
+    ///
+    /// `false` (CPOL 0) indicates the clock idles low, and `true` (CPOL 1)
+    /// indicates the clock idles high.
+    pub const fn cpol(self) -> bool {
+        matches!(self, Mode::Mode2 | Mode::Mode3)
+    }
+
+    /// Returns the clock phase (CPHA).
+    ///
+    /// `false` (CPHA 0) indicates data is sampled on the leading clock edge,
+    /// and `true` (CPHA 1) indicates data is sampled on the trailing clock edge.
+    pub const fn cpha(self) -> bool {
+        matches!(self, Mode::Mode1 | Mode::Mode3)
+    }
+}
+
 impl fmt::Display for Mode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -381,8 +685,13 @@ impl fmt::Display for Mode {
 /// buffer before sending it to the slave device, and your read buffer after
 /// reading any incoming data.
 ///
+/// With the optional `serde` feature enabled, `BitOrder` is (de)serialized as
+/// `"msb-first"` or `"lsb-first"`.
+///
 /// [`reverse_bits`]: fn.reverse_bits.html
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum BitOrder {
     MsbFirst = 0,
     LsbFirst = 1,
@@ -412,10 +721,23 @@ impl fmt::Display for BitOrder {
 /// [`blocking::spi::Write<u8>`]: ../../embedded_hal/blocking/spi/trait.Write.html
 /// [`spi::FullDuplex<u8>`]: ../../embedded_hal/spi/trait.FullDuplex.html
 pub struct Spi {
-    spidev: File,
+    spidev: DeviceNode,
     // Stores the last read value. Used for embedded_hal::spi::FullDuplex.
-    #[cfg(feature = "hal")]
+    #[cfg(any(feature = "hal-0", feature = "hal-1"))]
     last_read: Option<u8>,
+    #[cfg(feature = "metrics")]
+    stats: Cell<SpiStats>,
+    // Slave Select setup and hold delays in microseconds (µs)
+    ss_setup_delay: u16,
+    ss_hold_delay: u16,
+    retry_policy: RetryPolicy,
+    // Clock speed passed to new(), which is restored by reset()
+    initial_clock_speed: u32,
+    // Used to look up the default pins for measure_cs_timing()
+    #[cfg(feature = "diagnostics")]
+    bus: Bus,
+    #[cfg(feature = "diagnostics")]
+    slave_select: SlaveSelect,
     // The not_sync field is a workaround to force !Sync. Spi isn't safe for
     // Sync because of ioctl() and the underlying drivers. This avoids needing
     // #![feature(optin_builtin_traits)] to manually add impl !Sync for Spi.
@@ -443,24 +765,85 @@ impl Spi {
         // TX_DUAL/TX_QUAD/RX_DUAL/RX_QUAD - Not supported by BCM283x
         // bits per word - any value other than 0 or 8 returns EINVAL when set
 
+        let path = format!("/dev/spidev{}.{}", bus as u8, slave_select as u8);
+
         let spidev = OpenOptions::new()
             .read(true)
             .write(true)
-            .open(format!("/dev/spidev{}.{}", bus as u8, slave_select as u8))?;
+            .open(&path)
+            .map_err(|e| Error::with_path(e, &path))?;
+
+        Spi::with_device(
+            bus,
+            slave_select,
+            clock_speed,
+            mode,
+            DeviceNode::File(spidev),
+        )
+    }
+
+    /// Constructs a new `Spi` connected to the simulated device attached to
+    /// `simulator`, instead of the SPI bus device node.
+    ///
+    /// The arguments are the same as for [`new`]. A device needs to be
+    /// attached to the specified bus and Slave Select pin on `simulator`
+    /// first.
+    ///
+    /// Only available when the optional `mock` feature is enabled.
+    ///
+    /// [`new`]: #method.new
+    #[cfg(feature = "mock")]
+    pub fn with_simulator(
+        simulator: &Simulator,
+        bus: Bus,
+        slave_select: SlaveSelect,
+        clock_speed: u32,
+        mode: Mode,
+    ) -> Result<Spi> {
+        let spidev = mock::open(simulator, bus, slave_select)?;
+
+        Spi::with_device(
+            bus,
+            slave_select,
+            clock_speed,
+            mode,
+            DeviceNode::Simulated(spidev),
+        )
+    }
 
+    // Finishes constructing a Spi for an opened device node
+    fn with_device(
+        bus: Bus,
+        slave_select: SlaveSelect,
+        clock_speed: u32,
+        mode: Mode,
+        spidev: DeviceNode,
+    ) -> Result<Spi> {
         // Reset all mode flags
-        if let Err(e) = ioctl::set_mode32(spidev.as_raw_fd(), mode as u32) {
+        if let Err(e) = sys::set_mode32(&spidev, mode as u32) {
+            log_debug!("Failed to set {}: {}", mode, e);
+
             if e.kind() == io::ErrorKind::InvalidInput {
                 return Err(Error::ModeNotSupported(mode));
             } else {
-                return Err(Error::Io(e));
+                return Err(Error::with_fd(e, spidev.as_raw_fd()));
             }
         }
 
         let spi = Spi {
             spidev,
-            #[cfg(feature = "hal")]
+            #[cfg(any(feature = "hal-0", feature = "hal-1"))]
             last_read: None,
+            #[cfg(feature = "metrics")]
+            stats: Cell::new(SpiStats::default()),
+            ss_setup_delay: 0,
+            ss_hold_delay: 0,
+            retry_policy: RetryPolicy::none(),
+            initial_clock_speed: clock_speed,
+            #[cfg(feature = "diagnostics")]
+            bus,
+            #[cfg(feature = "diagnostics")]
+            slave_select,
             not_sync: PhantomData,
         };
 
@@ -468,13 +851,151 @@ impl Spi {
         spi.set_bits_per_word(8)?;
         spi.set_clock_speed(clock_speed)?;
 
+        log_debug!("Opened {} {} ({})", bus, slave_select, mode);
+
         Ok(spi)
     }
 
+    /// Constructs a new `Spi`, waiting for the SPI bus device node to appear.
+    ///
+    /// `new_with_retry` works similarly to [`new`], but keeps polling for
+    /// the device node until it exists, or until the `timeout` duration
+    /// elapses. This can be useful when your application is started during
+    /// boot, before the SPI kernel modules have finished creating the device
+    /// nodes.
+    ///
+    /// If the device node doesn't appear before the timeout elapses,
+    /// `new_with_retry` returns [`Error::BusNotEnabled`].
+    ///
+    /// [`new`]: #method.new
+    /// [`Error::BusNotEnabled`]: enum.Error.html#variant.BusNotEnabled
+    pub fn new_with_retry(
+        bus: Bus,
+        slave_select: SlaveSelect,
+        clock_speed: u32,
+        mode: Mode,
+        timeout: Duration,
+    ) -> Result<Spi> {
+        Spi::new_with_retry_policy(
+            bus,
+            slave_select,
+            clock_speed,
+            mode,
+            RetryPolicy::with_timeout(timeout, RETRY_INTERVAL),
+        )
+    }
+
+    /// Constructs a new `Spi`, retrying according to `policy` while the SPI
+    /// bus device node doesn't exist.
+    ///
+    /// If the device node still doesn't exist after the last attempt,
+    /// `new_with_retry_policy` returns [`Error::BusNotEnabled`]. Any other
+    /// error is returned immediately.
+    ///
+    /// [`Error::BusNotEnabled`]: enum.Error.html#variant.BusNotEnabled
+    pub fn new_with_retry_policy(
+        bus: Bus,
+        slave_select: SlaveSelect,
+        clock_speed: u32,
+        mode: Mode,
+        policy: RetryPolicy,
+    ) -> Result<Spi> {
+        policy
+            .retry(
+                || Spi::new(bus, slave_select, clock_speed, mode),
+                Error::is_missing_device,
+            )
+            .map_err(|err| {
+                if err.is_missing_device() {
+                    Error::BusNotEnabled
+                } else {
+                    err
+                }
+            })
+    }
+
+    /// Constructs a new `Spi` using the settings in `config`.
+    ///
+    /// `from_config` works similarly to [`new`].
+    ///
+    /// [`new`]: #method.new
+    pub fn from_config(config: &SpiConfig) -> Result<Spi> {
+        Spi::new(
+            config.bus,
+            config.slave_select,
+            config.clock_speed,
+            config.mode,
+        )
+    }
+
+    /// Returns the device tree configuration of the SPI device at `bus` and
+    /// `slave_select`, without opening its device node.
+    ///
+    /// `inspect` reads the device's `of_node` properties under
+    /// `/sys/bus/spi/devices/spiB.S`, which are readable by any user, and
+    /// don't interfere with another process that has the device opened. This
+    /// is intended for diagnostics that need to coexist with a running SPI
+    /// application.
+    ///
+    /// sysfs only exposes the configuration the device was created with at
+    /// boot. The returned [`SpiConfig`] contains:
+    ///
+    /// * `clock_speed`: the `spi-max-frequency` property, which spidev uses as
+    ///   the upper limit for the clock speed, and as the default until a
+    ///   different clock speed is set. For the default spidev overlays, this
+    ///   is 125 MHz, rather than the clock speed actually in use.
+    /// * `mode`: based on the `spi-cpol` and `spi-cpha` properties, which are
+    ///   the defaults before the device node is configured.
+    ///
+    /// Settings changed through the device node, such as the clock speed,
+    /// mode, bit order, bits per word and Slave Select polarity configured
+    /// by [`new`], [`set_clock_speed`] or [`set_mode`], are stored by
+    /// spidev for the open device, and are only available to the process that
+    /// has it opened, through getters such as [`clock_speed`] and [`mode`].
+    ///
+    /// If the device doesn't exist, or it wasn't created from the device
+    /// tree, `inspect` returns an [`Error::Io`] with
+    /// `io::ErrorKind::NotFound`.
+    ///
+    /// [`SpiConfig`]: struct.SpiConfig.html
+    /// [`new`]: #method.new
+    /// [`set_clock_speed`]: #method.set_clock_speed
+    /// [`set_mode`]: #method.set_mode
+    /// [`clock_speed`]: #method.clock_speed
+    /// [`mode`]: #method.mode
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    pub fn inspect(bus: Bus, slave_select: SlaveSelect) -> Result<SpiConfig> {
+        let path = format!(
+            "{}/spi{}.{}/of_node",
+            PATH_SPI_DEVICES, bus as u8, slave_select as u8
+        );
+
+        // Device tree properties are stored as big-endian cells
+        let clock_speed = match fs::read(format!("{}/spi-max-frequency", path))?[..] {
+            [a, b, c, d] => u32::from_be_bytes([a, b, c, d]),
+            _ => {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "unexpected spi-max-frequency format",
+                )))
+            }
+        };
+
+        // spi-cpol and spi-cpha are boolean properties, which are true if
+        // they exist
+        let mode = Mode::from_cpol_cpha(
+            fs::metadata(format!("{}/spi-cpol", path)).is_ok(),
+            fs::metadata(format!("{}/spi-cpha", path)).is_ok(),
+        );
+
+        Ok(SpiConfig::new(bus, slave_select, clock_speed, mode))
+    }
+
     /// Gets the bit order.
     pub fn bit_order(&self) -> Result<BitOrder> {
         let mut bit_order: u8 = 0;
-        ioctl::lsb_first(self.spidev.as_raw_fd(), &mut bit_order)?;
+        sys::lsb_first(&self.spidev, &mut bit_order)
+            .map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
 
         Ok(match bit_order {
             0 => BitOrder::MsbFirst,
@@ -496,19 +1017,26 @@ impl Spi {
     /// [`LsbFirst`]: enum.BitOrder.html
     /// [`reverse_bits`]: fn.reverse_bits.html
     pub fn set_bit_order(&self, bit_order: BitOrder) -> Result<()> {
-        match ioctl::set_lsb_first(self.spidev.as_raw_fd(), bit_order as u8) {
-            Ok(_) => Ok(()),
+        match sys::set_lsb_first(&self.spidev, bit_order as u8) {
+            Ok(_) => {
+                log_debug!("Bit order set to {}", bit_order);
+
+                Ok(())
+            }
             Err(ref e) if e.kind() == io::ErrorKind::InvalidInput => {
+                log_debug!("Bit order {} not supported: {}", bit_order, e);
+
                 Err(Error::BitOrderNotSupported(bit_order))
             }
-            Err(e) => Err(Error::Io(e)),
+            Err(e) => Err(Error::with_fd(e, self.spidev.as_raw_fd())),
         }
     }
 
     /// Gets the number of bits per word.
     pub fn bits_per_word(&self) -> Result<u8> {
         let mut bits_per_word: u8 = 0;
-        ioctl::bits_per_word(self.spidev.as_raw_fd(), &mut bits_per_word)?;
+        sys::bits_per_word(&self.spidev, &mut bits_per_word)
+            .map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
 
         Ok(bits_per_word)
     }
@@ -519,19 +1047,26 @@ impl Spi {
     ///
     /// By default, `bits_per_word` is set to 8.
     pub fn set_bits_per_word(&self, bits_per_word: u8) -> Result<()> {
-        match ioctl::set_bits_per_word(self.spidev.as_raw_fd(), bits_per_word) {
-            Ok(_) => Ok(()),
+        match sys::set_bits_per_word(&self.spidev, bits_per_word) {
+            Ok(_) => {
+                log_debug!("Bits per word set to {}", bits_per_word);
+
+                Ok(())
+            }
             Err(ref e) if e.kind() == io::ErrorKind::InvalidInput => {
+                log_debug!("{} bits per word not supported: {}", bits_per_word, e);
+
                 Err(Error::BitsPerWordNotSupported(bits_per_word))
             }
-            Err(e) => Err(Error::Io(e)),
+            Err(e) => Err(Error::with_fd(e, self.spidev.as_raw_fd())),
         }
     }
 
     /// Gets the clock frequency in hertz (Hz).
     pub fn clock_speed(&self) -> Result<u32> {
         let mut clock_speed: u32 = 0;
-        ioctl::clock_speed(self.spidev.as_raw_fd(), &mut clock_speed)?;
+        sys::clock_speed(&self.spidev, &mut clock_speed)
+            .map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
 
         Ok(clock_speed)
     }
@@ -540,26 +1075,66 @@ impl Spi {
     ///
     /// The SPI driver will automatically round down to the closest valid frequency.
     pub fn set_clock_speed(&self, clock_speed: u32) -> Result<()> {
-        match ioctl::set_clock_speed(self.spidev.as_raw_fd(), clock_speed) {
-            Ok(_) => Ok(()),
+        match sys::set_clock_speed(&self.spidev, clock_speed) {
+            Ok(_) => {
+                log_debug!("Clock speed set to {} Hz", clock_speed);
+
+                Ok(())
+            }
             Err(ref e) if e.kind() == io::ErrorKind::InvalidInput => {
+                log_debug!("Clock speed {} Hz not supported: {}", clock_speed, e);
+
                 Err(Error::ClockSpeedNotSupported(clock_speed))
             }
-            Err(e) => Err(Error::Io(e)),
+            Err(e) => Err(Error::with_fd(e, self.spidev.as_raw_fd())),
         }
     }
 
+    /// Returns the clock frequency in hertz (Hz) the hardware produces when
+    /// `requested` is passed to [`set_clock_speed`].
+    ///
+    /// The SPI clock is derived from an input clock through an even integer
+    /// divider, so only specific frequencies can be produced. The SPI driver
+    /// selects the smallest even divider that results in a frequency at or
+    /// below the requested frequency, with a minimum of `2` and a maximum of
+    /// `65536`:
+    ///
+    /// `clock_speed = input_clock / max(2, round_up_to_even(ceil(input_clock / requested)))`
+    ///
+    /// On the Raspberry Pi 5, the input clock is the RP1's fixed 200 MHz
+    /// system clock. On earlier models, SPI0 and SPI3 through SPI6 are
+    /// driven by the core (VPU) clock, which is retrieved with
+    /// [`system::clock_frequency`]. The core clock speed depends on the model and
+    /// firmware settings such as `core_freq` and `force_turbo` in
+    /// `/boot/config.txt`, and may change at runtime when the firmware
+    /// adjusts the clock speed, so the returned value only applies to the
+    /// current core clock speed. The auxiliary SPI1 and SPI2 peripherals use a
+    /// different divider, and aren't covered by `nearest_clock_speed`.
+    ///
+    /// If `requested` is `0`, the slowest supported frequency is returned.
+    ///
+    /// [`set_clock_speed`]: #method.set_clock_speed
+    /// [`system::clock_frequency`]: ../system/fn.clock_frequency.html
+    pub fn nearest_clock_speed(requested: u32) -> Result<u32> {
+        let input_clock = if DeviceInfo::new()?.soc() == SoC::Bcm2712 {
+            RP1_SPI_CLOCK
+        } else {
+            system::clock_frequency(ClockId::Core)?
+        };
+
+        Ok(divided_clock_speed(input_clock, requested))
+    }
+
     /// Gets the SPI mode.
     pub fn mode(&self) -> Result<Mode> {
         let mut mode: u8 = 0;
-        ioctl::mode(self.spidev.as_raw_fd(), &mut mode)?;
+        sys::mode(&self.spidev, &mut mode)
+            .map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
 
-        Ok(match mode & 0x03 {
-            0x01 => Mode::Mode1,
-            0x02 => Mode::Mode2,
-            0x03 => Mode::Mode3,
-            _ => Mode::Mode0,
-        })
+        Ok(Mode::from_cpol_cpha(
+            mode & sys::MODE_CPOL != 0,
+            mode & sys::MODE_CPHA != 0,
+        ))
     }
 
     /// Sets the SPI mode.
@@ -568,26 +1143,34 @@ impl Spi {
     /// may not be available depending on the SPI bus that's used.
     pub fn set_mode(&self, mode: Mode) -> Result<()> {
         let mut new_mode: u8 = 0;
-        ioctl::mode(self.spidev.as_raw_fd(), &mut new_mode)?;
+        sys::mode(&self.spidev, &mut new_mode)
+            .map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
 
         // Make sure we only replace the CPOL/CPHA bits
         new_mode = (new_mode & !0x03) | (mode as u8);
 
-        match ioctl::set_mode(self.spidev.as_raw_fd(), new_mode) {
-            Ok(_) => Ok(()),
+        match sys::set_mode(&self.spidev, new_mode) {
+            Ok(_) => {
+                log_debug!("SPI mode set to {}", mode);
+
+                Ok(())
+            }
             Err(ref e) if e.kind() == io::ErrorKind::InvalidInput => {
+                log_debug!("SPI {} not supported: {}", mode, e);
+
                 Err(Error::ModeNotSupported(mode))
             }
-            Err(e) => Err(Error::Io(e)),
+            Err(e) => Err(Error::with_fd(e, self.spidev.as_raw_fd())),
         }
     }
 
     /// Gets the Slave Select polarity.
     pub fn ss_polarity(&self) -> Result<Polarity> {
         let mut mode: u8 = 0;
-        ioctl::mode(self.spidev.as_raw_fd(), &mut mode)?;
+        sys::mode(&self.spidev, &mut mode)
+            .map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
 
-        if (mode & ioctl::MODE_CS_HIGH) == 0 {
+        if (mode & sys::MODE_CS_HIGH) == 0 {
             Ok(Polarity::ActiveLow)
         } else {
             Ok(Polarity::ActiveHigh)
@@ -599,23 +1182,120 @@ impl Spi {
     /// By default, the Slave Select polarity is set to `ActiveLow`.
     pub fn set_ss_polarity(&self, polarity: Polarity) -> Result<()> {
         let mut new_mode: u8 = 0;
-        ioctl::mode(self.spidev.as_raw_fd(), &mut new_mode)?;
+        sys::mode(&self.spidev, &mut new_mode)
+            .map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
 
         if polarity == Polarity::ActiveHigh {
-            new_mode |= ioctl::MODE_CS_HIGH;
+            new_mode |= sys::MODE_CS_HIGH;
         } else {
-            new_mode &= !ioctl::MODE_CS_HIGH;
+            new_mode &= !sys::MODE_CS_HIGH;
         }
 
-        match ioctl::set_mode(self.spidev.as_raw_fd(), new_mode) {
-            Ok(_) => Ok(()),
+        match sys::set_mode(&self.spidev, new_mode) {
+            Ok(_) => {
+                log_debug!("Slave Select polarity set to {}", polarity);
+
+                Ok(())
+            }
             Err(ref e) if e.kind() == io::ErrorKind::InvalidInput => {
+                log_debug!("Slave Select polarity {} not supported: {}", polarity, e);
+
                 Err(Error::PolarityNotSupported(polarity))
             }
-            Err(e) => Err(Error::Io(e)),
+            Err(e) => Err(Error::with_fd(e, self.spidev.as_raw_fd())),
         }
     }
 
+    /// Returns `true` if 3-wire (bidirectional) mode is enabled.
+    pub fn is_3wire(&self) -> Result<bool> {
+        let mut mode: u8 = 0;
+        sys::mode(&self.spidev, &mut mode)
+            .map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
+
+        Ok((mode & sys::MODE_3WIRE) != 0)
+    }
+
+    /// Enables or disables 3-wire (bidirectional) mode.
+    ///
+    /// In 3-wire mode, MOSI is used as a shared bidirectional data line, and
+    /// MISO is unused. Every transfer is half-duplex, so a segment can either
+    /// write or read data, but not both. Use [`write_then_read_halfduplex`]
+    /// to send a command and receive the response within a single
+    /// transaction.
+    ///
+    /// 3-wire mode is supported by the SPI0 controller on the Raspberry Pi 4 B
+    /// and earlier models. If the underlying driver doesn't support 3-wire
+    /// mode, an [`Error::Io`] with kind `InvalidInput` is returned.
+    ///
+    /// By default, 3-wire mode is disabled.
+    ///
+    /// [`write_then_read_halfduplex`]: #method.write_then_read_halfduplex
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    pub fn set_3wire(&self, enabled: bool) -> Result<()> {
+        let mut new_mode: u8 = 0;
+        sys::mode(&self.spidev, &mut new_mode)
+            .map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
+
+        if enabled {
+            new_mode |= sys::MODE_3WIRE;
+        } else {
+            new_mode &= !sys::MODE_3WIRE;
+        }
+
+        sys::set_mode(&self.spidev, new_mode)
+            .map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
+
+        log_debug!(
+            "SPI 3-wire mode {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+
+        Ok(())
+    }
+
+    /// Sends the outgoing data contained in `write_buffer` to the slave device,
+    /// and then switches the bidirectional data line to input and fills
+    /// `read_buffer` with incoming data.
+    ///
+    /// `write_then_read_halfduplex` is intended for 3-wire slave devices, and
+    /// requires 3-wire mode to be enabled with [`set_3wire`]`(true)`. If
+    /// 3-wire mode is disabled, an [`Error::Io`] with kind `InvalidInput` is
+    /// returned.
+    ///
+    /// Both phases are sent as separate segments within a single message, so
+    /// Slave Select stays active in between. The controller switches the data
+    /// line to input immediately after the last bit of the write phase, and
+    /// starts clocking in data on the next clock cycle, so the slave device
+    /// has a single clock cycle to release the line and start driving it.
+    /// Slave devices that need more turnaround time can be accessed with
+    /// [`transfer_segments`] instead, by adding a delay to the write segment
+    /// with [`Segment::set_delay`].
+    ///
+    /// Sequence: SS active → Outgoing Bytes → Direction Change → Incoming
+    /// Bytes → SS inactive
+    ///
+    /// [`set_3wire`]: #method.set_3wire
+    /// [`transfer_segments`]: #method.transfer_segments
+    /// [`Segment::set_delay`]: struct.Segment.html#method.set_delay
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    pub fn write_then_read_halfduplex(
+        &mut self,
+        write_buffer: &[u8],
+        read_buffer: &mut [u8],
+    ) -> Result<()> {
+        if !self.is_3wire()? {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "3-wire mode not enabled",
+            )));
+        }
+
+        self.transfer_segments(&[
+            Segment::with_write(write_buffer),
+            Segment::with_read(read_buffer),
+        ])
+    }
+
     /// Receives incoming data from the slave device and writes it to `buffer`.
     ///
     /// The SPI protocol doesn't indicate how much incoming data is waiting,
@@ -630,7 +1310,25 @@ impl Spi {
     ///
     /// Returns how many bytes were read.
     pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
-        Ok(self.spidev.read(buffer)?)
+        let result = if self.has_ss_delay() {
+            let length = buffer.len();
+
+            self.transfer_message(&[Segment::with_read(buffer)])
+                .map(|_| length)
+        } else {
+            self.retry_policy.retry(
+                || retry_interrupted!(sys::read(&self.spidev, buffer)),
+                is_transient,
+            )
+        };
+
+        self.record(result.is_ok(), *result.as_ref().unwrap_or(&0), 0);
+
+        let length = result.map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
+
+        log_debug!("Read {} bytes", length);
+
+        Ok(length)
     }
 
     /// Sends the outgoing data contained in `buffer` to the slave device.
@@ -642,7 +1340,120 @@ impl Spi {
     ///
     /// Returns how many bytes were written.
     pub fn write(&mut self, buffer: &[u8]) -> Result<usize> {
-        Ok(self.spidev.write(buffer)?)
+        let result = if self.has_ss_delay() {
+            self.transfer_message(&[Segment::with_write(buffer)])
+                .map(|_| buffer.len())
+        } else {
+            self.retry_policy.retry(
+                || retry_interrupted!(sys::write(&self.spidev, buffer)),
+                is_transient,
+            )
+        };
+
+        self.record(result.is_ok(), 0, *result.as_ref().unwrap_or(&0));
+
+        let length = result.map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
+
+        log_debug!("Wrote {} bytes", length);
+
+        Ok(length)
+    }
+
+    /// Sends the outgoing data contained in multiple buffers to the slave
+    /// device as a single transaction.
+    ///
+    /// `write_vectored` is useful for structured protocols, where a header
+    /// and a payload are stored in separate buffers. Each buffer is sent as a
+    /// separate segment, without copying the data into an intermediate
+    /// buffer. Slave Select stays active in between buffers, so the slave
+    /// device receives the data the same way it would if it was sent with a
+    /// single call to [`write`].
+    ///
+    /// `spidev` limits the combined size of all buffers to its bufsiz value.
+    /// If the combined size exceeds bufsiz, `write_vectored` returns an I/O
+    /// error (`EMSGSIZE`) without sending any data. More information on
+    /// increasing the bufsiz value can be found [here].
+    ///
+    /// Any data received on the MISO line from the slave is ignored.
+    ///
+    /// Returns how many bytes were written.
+    ///
+    /// [`write`]: #method.write
+    /// [here]: index.html#buffer-size-limits
+    pub fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<usize> {
+        let length = bufs.iter().map(|buf| buf.len()).sum();
+
+        if length > bufsiz() {
+            return Err(Error::Io(io::Error::from_raw_os_error(libc::EMSGSIZE)));
+        }
+
+        let segments: Vec<Segment<'_, '_>> = bufs
+            .iter()
+            .filter(|buf| !buf.is_empty())
+            .map(|buf| Segment::with_write(buf))
+            .collect();
+
+        if segments.is_empty() {
+            return Ok(0);
+        }
+
+        let result = self.transfer_message(&segments);
+
+        self.record(result.is_ok(), 0, if result.is_ok() { length } else { 0 });
+
+        result.map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
+
+        log_debug!("Wrote {} bytes in {} segments", length, segments.len());
+
+        Ok(length)
+    }
+
+    /// Sends the RGB565 pixel data contained in `pixels` to the slave device.
+    ///
+    /// `write_pixels` is intended for SPI displays that expect 16-bit RGB565
+    /// pixel data, with the high byte of each pixel sent first. The pixels
+    /// are converted to big-endian bytes in chunks, using a single
+    /// intermediate buffer.
+    ///
+    /// Because `spidev` limits the size of a single transfer to its bufsiz
+    /// value, the data is sent in multiple consecutive writes when needed.
+    /// Slave Select is set to inactive in between writes, which most
+    /// display controllers accept during a memory write command. More
+    /// information on increasing the bufsiz value can be found [here].
+    ///
+    /// Any data received on the MISO line from the slave is ignored.
+    ///
+    /// [here]: index.html#buffer-size-limits
+    pub fn write_pixels(&mut self, pixels: &[u16]) -> Result<()> {
+        // Send as much data as possible per write
+        let bufsiz = bufsiz();
+
+        // Make sure we never split a pixel in between two writes
+        let chunk_len = (bufsiz / 2).max(1);
+        let mut buffer = vec![0u8; chunk_len.min(pixels.len()) * 2];
+
+        for chunk in pixels.chunks(chunk_len) {
+            for (bytes, pixel) in buffer.chunks_exact_mut(2).zip(chunk) {
+                bytes.copy_from_slice(&pixel.to_be_bytes());
+            }
+
+            let bytes = &buffer[..chunk.len() * 2];
+
+            let result = if self.has_ss_delay() {
+                self.transfer_message(&[Segment::with_write(bytes)])
+                    .map(|_| ())
+            } else {
+                sys::write(&self.spidev, bytes).map(|_| ())
+            };
+
+            self.record(result.is_ok(), 0, bytes.len());
+
+            result.map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
+
+            log_debug!("Wrote {} bytes of pixel data", bytes.len());
+        }
+
+        Ok(())
     }
 
     /// Sends and receives data at the same time.
@@ -662,11 +1473,251 @@ impl Spi {
     pub fn transfer(&self, read_buffer: &mut [u8], write_buffer: &[u8]) -> Result<usize> {
         let segment = Segment::new(read_buffer, write_buffer);
 
-        ioctl::transfer(self.spidev.as_raw_fd(), &[segment])?;
+        let result = self.transfer_message(&[segment]);
+
+        self.record(result.is_ok(), segment.len(), segment.len());
+
+        result.map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
+
+        log_debug!("Transferred {} bytes", segment.len());
 
         Ok(segment.len())
     }
 
+    /// Sends and receives data at the same time, using a custom clock speed.
+    ///
+    /// `transfer_at` works the same as [`transfer`], but the transfer is
+    /// clocked at `clock_speed` hertz (Hz) instead of the clock speed
+    /// configured with [`set_clock_speed`]. The SPI driver will automatically
+    /// select the closest valid frequency.
+    ///
+    /// The custom clock speed only applies to this transfer, and is passed to
+    /// the driver together with the data, so the configured clock speed isn't
+    /// changed at any point. This makes `transfer_at` suitable for
+    /// communicating with multiple slave devices that require different
+    /// clock speeds through a shared `Spi` instance. Setting `clock_speed` to
+    /// `0` uses the configured clock speed.
+    ///
+    /// Returns how many bytes were transferred.
+    ///
+    /// [`transfer`]: #method.transfer
+    /// [`set_clock_speed`]: #method.set_clock_speed
+    pub fn transfer_at(
+        &self,
+        read_buffer: &mut [u8],
+        write_buffer: &[u8],
+        clock_speed: u32,
+    ) -> Result<usize> {
+        let mut segment = Segment::new(read_buffer, write_buffer);
+        segment.set_clock_speed(clock_speed);
+
+        let result = self.transfer_message(&[segment]);
+
+        self.record(result.is_ok(), segment.len(), segment.len());
+
+        result.map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
+
+        log_debug!("Transferred {} bytes at {} Hz", segment.len(), clock_speed);
+
+        Ok(segment.len())
+    }
+
+    /// Sends `command`, and returns the 2 bytes received after it as a
+    /// big-endian unsigned 16-bit integer.
+    ///
+    /// `read_u16_be` and the related methods remove the need to assemble
+    /// multi-byte values by hand, which is common for ADCs and sensors.
+    /// `command` is sent first, followed by as many `0` bytes as the integer
+    /// requires, in a single full-duplex [`transfer`]. Any bytes received
+    /// while `command` is being sent are ignored. `command` may be empty if
+    /// the slave device doesn't expect a command.
+    ///
+    /// ```no_run
+    /// use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
+    ///
+    /// # fn main() -> rppal::spi::Result<()> {
+    /// let spi = Spi::new(Bus::Spi0, SlaveSelect::Ss0, 1_000_000, Mode::Mode0)?;
+    ///
+    /// // Read conversion register 0x00
+    /// let value = spi.read_u16_be(&[0x80])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`transfer`]: #method.transfer
+    pub fn read_u16_be(&self, command: &[u8]) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.read_bytes(command)?))
+    }
+
+    /// Sends `command`, and returns the 2 bytes received after it as a
+    /// little-endian unsigned 16-bit integer.
+    ///
+    /// See [`read_u16_be`] for more information.
+    ///
+    /// [`read_u16_be`]: #method.read_u16_be
+    pub fn read_u16_le(&self, command: &[u8]) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.read_bytes(command)?))
+    }
+
+    /// Sends `command`, and returns the 2 bytes received after it as a
+    /// big-endian signed 16-bit integer.
+    ///
+    /// See [`read_u16_be`] for more information.
+    ///
+    /// [`read_u16_be`]: #method.read_u16_be
+    pub fn read_i16_be(&self, command: &[u8]) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.read_bytes(command)?))
+    }
+
+    /// Sends `command`, and returns the 2 bytes received after it as a
+    /// little-endian signed 16-bit integer.
+    ///
+    /// See [`read_u16_be`] for more information.
+    ///
+    /// [`read_u16_be`]: #method.read_u16_be
+    pub fn read_i16_le(&self, command: &[u8]) -> Result<i16> {
+        Ok(i16::from_le_bytes(self.read_bytes(command)?))
+    }
+
+    /// Sends `command`, and returns the 3 bytes received after it as a
+    /// big-endian unsigned 24-bit integer.
+    ///
+    /// See [`read_u16_be`] for more information.
+    ///
+    /// [`read_u16_be`]: #method.read_u16_be
+    pub fn read_u24_be(&self, command: &[u8]) -> Result<u32> {
+        let [b0, b1, b2] = self.read_bytes(command)?;
+
+        Ok(u32::from_be_bytes([0, b0, b1, b2]))
+    }
+
+    /// Sends `command`, and returns the 3 bytes received after it as a
+    /// little-endian unsigned 24-bit integer.
+    ///
+    /// See [`read_u16_be`] for more information.
+    ///
+    /// [`read_u16_be`]: #method.read_u16_be
+    pub fn read_u24_le(&self, command: &[u8]) -> Result<u32> {
+        let [b0, b1, b2] = self.read_bytes(command)?;
+
+        Ok(u32::from_le_bytes([b0, b1, b2, 0]))
+    }
+
+    /// Sends `command`, and returns the 3 bytes received after it as a
+    /// big-endian signed 24-bit integer.
+    ///
+    /// The value is sign-extended, so a negative 24-bit value results in a
+    /// negative `i32`.
+    ///
+    /// ```
+    /// # #[cfg(feature = "mock")]
+    /// # {
+    /// use rppal::mock::{Simulator, SpiDevice};
+    /// use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
+    ///
+    /// // A simulated ADC that returns -2 as a big-endian value of any width
+    /// struct Adc;
+    ///
+    /// impl SpiDevice for Adc {
+    ///     fn transfer(&mut self, read_buffer: &mut [u8], _: &[u8]) -> std::io::Result<()> {
+    ///         read_buffer.iter_mut().for_each(|byte| *byte = 0xFF);
+    ///         if let Some(last) = read_buffer.last_mut() {
+    ///             *last = 0xFE;
+    ///         }
+    ///
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let simulator = Simulator::new();
+    /// simulator.add_spi_device(Bus::Spi0, SlaveSelect::Ss0, Adc);
+    ///
+    /// let spi = Spi::with_simulator(&simulator, Bus::Spi0, SlaveSelect::Ss0, 1_000_000, Mode::Mode0)?;
+    /// assert_eq!(spi.read_u24_be(&[0x01])?, 0xFF_FFFE);
+    /// assert_eq!(spi.read_i24_be(&[0x01])?, -2);
+    /// assert_eq!(spi.read_i24_le(&[0x01])?, -0x01_0001);
+    /// assert_eq!(spi.read_i16_be(&[0x01])?, -2);
+    /// assert_eq!(spi.read_i16_le(&[0x01])?, -0x0101);
+    /// assert_eq!(spi.read_i32_be(&[])?, -2);
+    /// # }
+    /// # Ok::<(), rppal::spi::Error>(())
+    /// ```
+    ///
+    /// See [`read_u16_be`] for more information.
+    ///
+    /// [`read_u16_be`]: #method.read_u16_be
+    pub fn read_i24_be(&self, command: &[u8]) -> Result<i32> {
+        Ok(sign_extend_24(self.read_u24_be(command)?))
+    }
+
+    /// Sends `command`, and returns the 3 bytes received after it as a
+    /// little-endian signed 24-bit integer.
+    ///
+    /// The value is sign-extended, so a negative 24-bit value results in a
+    /// negative `i32`.
+    ///
+    /// See [`read_u16_be`] for more information.
+    ///
+    /// [`read_u16_be`]: #method.read_u16_be
+    pub fn read_i24_le(&self, command: &[u8]) -> Result<i32> {
+        Ok(sign_extend_24(self.read_u24_le(command)?))
+    }
+
+    /// Sends `command`, and returns the 4 bytes received after it as a
+    /// big-endian unsigned 32-bit integer.
+    ///
+    /// See [`read_u16_be`] for more information.
+    ///
+    /// [`read_u16_be`]: #method.read_u16_be
+    pub fn read_u32_be(&self, command: &[u8]) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.read_bytes(command)?))
+    }
+
+    /// Sends `command`, and returns the 4 bytes received after it as a
+    /// little-endian unsigned 32-bit integer.
+    ///
+    /// See [`read_u16_be`] for more information.
+    ///
+    /// [`read_u16_be`]: #method.read_u16_be
+    pub fn read_u32_le(&self, command: &[u8]) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.read_bytes(command)?))
+    }
+
+    /// Sends `command`, and returns the 4 bytes received after it as a
+    /// big-endian signed 32-bit integer.
+    ///
+    /// See [`read_u16_be`] for more information.
+    ///
+    /// [`read_u16_be`]: #method.read_u16_be
+    pub fn read_i32_be(&self, command: &[u8]) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.read_bytes(command)?))
+    }
+
+    /// Sends `command`, and returns the 4 bytes received after it as a
+    /// little-endian signed 32-bit integer.
+    ///
+    /// See [`read_u16_be`] for more information.
+    ///
+    /// [`read_u16_be`]: #method.read_u16_be
+    pub fn read_i32_le(&self, command: &[u8]) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.read_bytes(command)?))
+    }
+
+    // Sends command followed by N zero bytes, and returns the N bytes received
+    // after the command.
+    fn read_bytes<const N: usize>(&self, command: &[u8]) -> Result<[u8; N]> {
+        let mut write_buffer = vec![0u8; command.len() + N];
+        write_buffer[..command.len()].copy_from_slice(command);
+        let mut read_buffer = vec![0u8; write_buffer.len()];
+
+        self.transfer(&mut read_buffer, &write_buffer)?;
+
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(&read_buffer[command.len()..]);
+
+        Ok(bytes)
+    }
+
     /// Transfers multiple half-duplex or full-duplex segments.
     ///
     /// `transfer_segments` transfers multiple segments in a single call. Each
@@ -680,18 +1731,344 @@ impl Spi {
     /// [`Segment`]: struct.Segment.html
     /// [`Segment::set_ss_change`]: struct.Segment.html#method.set_ss_change
     pub fn transfer_segments(&self, segments: &[Segment<'_, '_>]) -> Result<()> {
-        ioctl::transfer(self.spidev.as_raw_fd(), segments)?;
+        let result = self.transfer_message(segments);
+
+        #[cfg(feature = "metrics")]
+        {
+            let (bytes_read, bytes_written) =
+                segments.iter().fold((0, 0), |(read, written), segment| {
+                    (
+                        read + if segment.rx_buf() != 0 {
+                            segment.len()
+                        } else {
+                            0
+                        },
+                        written
+                            + if segment.tx_buf() != 0 {
+                                segment.len()
+                            } else {
+                                0
+                            },
+                    )
+                });
+
+            self.record(result.is_ok(), bytes_read, bytes_written);
+        }
+
+        result.map_err(|e| Error::with_fd(e, self.spidev.as_raw_fd()))?;
+
+        log_debug!(
+            "Transferred {} segments ({} bytes)",
+            segments.len(),
+            segments.iter().map(|segment| segment.len()).sum::<usize>()
+        );
 
         Ok(())
     }
+
+    /// Returns the retry policy.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    /// Sets the retry policy applied to reads, writes and transfers.
+    ///
+    /// Operations that fail because the SPI driver timed out waiting for the
+    /// transfer to complete (`ETIMEDOUT`), or the controller was temporarily
+    /// unavailable (`EAGAIN`), are retried according to `retry_policy`.
+    /// Other errors are returned immediately.
+    ///
+    /// SPI doesn't provide any feedback from the slave device, so a failed
+    /// transfer may still have been partially clocked out. Only use a retry
+    /// policy with slave devices that tolerate receiving the same data
+    /// twice.
+    ///
+    /// By default, `retry_policy` is set to [`RetryPolicy::none`].
+    ///
+    /// [`RetryPolicy::none`]: ../retry/struct.RetryPolicy.html#method.none
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Gets the Slave Select setup delay.
+    pub fn ss_setup_delay(&self) -> Duration {
+        Duration::from_micros(u64::from(self.ss_setup_delay))
+    }
+
+    /// Sets the Slave Select setup delay.
+    ///
+    /// The setup delay is added between setting Slave Select to active and
+    /// the start of the first clock cycle for every read, write and transfer,
+    /// including those sent with [`transfer_segments`]. Any per-segment delays
+    /// are applied in addition to the setup delay.
+    ///
+    /// `delay` is rounded up to a 1 microsecond (µs) resolution. The maximum
+    /// value is 65,535 µs.
+    ///
+    /// By default, `ss_setup_delay` is set to `0`.
+    ///
+    /// [`transfer_segments`]: #method.transfer_segments
+    pub fn set_ss_setup_delay(&mut self, delay: Duration) {
+        self.ss_setup_delay = delay_micros(delay);
+    }
+
+    /// Gets the Slave Select hold delay.
+    pub fn ss_hold_delay(&self) -> Duration {
+        Duration::from_micros(u64::from(self.ss_hold_delay))
+    }
+
+    /// Sets the Slave Select hold delay.
+    ///
+    /// The hold delay is added between the last clock cycle and setting Slave
+    /// Select to inactive for every read, write and transfer, including those
+    /// sent with [`transfer_segments`]. If the last segment is configured to
+    /// change Slave Select with [`Segment::set_ss_change`], the change occurs
+    /// after the hold delay.
+    ///
+    /// `delay` is rounded up to a 1 microsecond (µs) resolution. The maximum
+    /// value is 65,535 µs.
+    ///
+    /// By default, `ss_hold_delay` is set to `0`.
+    ///
+    /// [`transfer_segments`]: #method.transfer_segments
+    /// [`Segment::set_ss_change`]: struct.Segment.html#method.set_ss_change
+    pub fn set_ss_hold_delay(&mut self, delay: Duration) {
+        self.ss_hold_delay = delay_micros(delay);
+    }
+
+    /// Restores the bus configuration to its default state.
+    ///
+    /// `reset` sets the SPI mode to [`Mode0`], clears all other mode flags,
+    /// which resets the Slave Select polarity to `ActiveLow`, disables
+    /// 3-wire mode and selects the `MsbFirst` bit order, sets the number of
+    /// bits per word to 8, and restores the clock speed that was passed to
+    /// [`new`]. The Slave Select setup and hold delays are set to `0`.
+    ///
+    /// This provides a known starting point after an error, or before
+    /// handing the bus over to other code.
+    ///
+    /// [`Mode0`]: enum.Mode.html#variant.Mode0
+    /// [`new`]: #method.new
+    pub fn reset(&mut self) -> Result<()> {
+        if let Err(e) = sys::set_mode32(&self.spidev, Mode::Mode0 as u32) {
+            log_debug!("Failed to set {}: {}", Mode::Mode0, e);
+
+            if e.kind() == io::ErrorKind::InvalidInput {
+                return Err(Error::ModeNotSupported(Mode::Mode0));
+            } else {
+                return Err(Error::with_fd(e, self.spidev.as_raw_fd()));
+            }
+        }
+
+        self.set_bits_per_word(8)?;
+        self.set_clock_speed(self.initial_clock_speed)?;
+
+        self.ss_setup_delay = 0;
+        self.ss_hold_delay = 0;
+
+        #[cfg(any(feature = "hal-0", feature = "hal-1"))]
+        {
+            self.last_read = None;
+        }
+
+        log_debug!("SPI reset to defaults");
+
+        Ok(())
+    }
+
+    /// Measures the Slave Select setup and hold delays by sampling the SCLK and
+    /// Slave Select pins while `buffer` is written to the slave device.
+    ///
+    /// `measure_cs_timing` is a diagnostic tool intended for bring-up, to
+    /// help determine suitable values for [`set_ss_setup_delay`] and
+    /// [`set_ss_hold_delay`]. It shouldn't be used in production code. The
+    /// pins are sampled on a separate thread by continuously reading the GPIO
+    /// level register, similar to [`Gpio::capture`]. The results are
+    /// approximate, and depend on how often the sampling thread is able to
+    /// read the register. Edges that occur within a few hundred nanoseconds
+    /// of each other may not be distinguishable. [`CsTiming::resolution`]
+    /// returns the longest observed interval between two samples.
+    ///
+    /// The pins are looked up based on the bus and Slave Select pin passed to
+    /// [`new`], using the default pin assignments listed in the [module
+    /// documentation]. Results are meaningless if the pins were moved with a
+    /// device tree overlay. The pins don't need to be configured through
+    /// [`Gpio`], and are left unchanged.
+    ///
+    /// `buffer` is sent with [`write`], so choose data that doesn't alter the
+    /// slave device's state. Longer buffers increase the memory needed to
+    /// store the samples.
+    ///
+    /// Returns an `io::ErrorKind::Unsupported` error if the GPIO registers
+    /// can't be accessed, for instance when [`Gpio`] has fallen back to the
+    /// `gpiochip` backend.
+    ///
+    /// This method is only available when the `diagnostics` feature is
+    /// enabled.
+    ///
+    /// ```no_run
+    /// use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut spi = Spi::new(Bus::Spi0, SlaveSelect::Ss0, 1_000_000, Mode::Mode0)?;
+    ///
+    /// let timing = spi.measure_cs_timing(&[0x00])?;
+    /// println!(
+    ///     "Setup delay: {:?} (resolution {:?})",
+    ///     timing.setup_delay(),
+    ///     timing.resolution()
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_ss_setup_delay`]: #method.set_ss_setup_delay
+    /// [`set_ss_hold_delay`]: #method.set_ss_hold_delay
+    /// [`Gpio::capture`]: ../gpio/struct.Gpio.html#method.capture
+    /// [`Gpio`]: ../gpio/struct.Gpio.html
+    /// [`CsTiming::resolution`]: struct.CsTiming.html#method.resolution
+    /// [`new`]: #method.new
+    /// [module documentation]: index.html#spi-buses
+    /// [`write`]: #method.write
+    #[cfg(feature = "diagnostics")]
+    pub fn measure_cs_timing(&mut self, buffer: &[u8]) -> Result<CsTiming> {
+        let (bus, slave_select) = (self.bus, self.slave_select);
+
+        diagnostics::measure(self, bus, slave_select, buffer)
+    }
+
+    fn has_ss_delay(&self) -> bool {
+        self.ss_setup_delay > 0 || self.ss_hold_delay > 0
+    }
+
+    // Sends the segments as a single message. The Slave Select setup and hold
+    // delays are implemented as empty segments at the start and end of the
+    // message, which only add a delay while Slave Select remains active.
+    fn transfer_message(&self, segments: &[Segment<'_, '_>]) -> io::Result<i32> {
+        if !self.has_ss_delay() {
+            return self
+                .retry_policy
+                .retry(|| sys::transfer(&self.spidev, segments), is_transient);
+        }
+
+        let mut message = Vec::with_capacity(segments.len() + 2);
+
+        if self.ss_setup_delay > 0 {
+            message.push(Segment::with_settings(
+                None,
+                None,
+                0,
+                self.ss_setup_delay,
+                0,
+                false,
+            ));
+        }
+
+        message.extend_from_slice(segments);
+
+        if self.ss_hold_delay > 0 {
+            // Move any Slave Select change after the last segment to the end
+            // of the hold delay
+            let ss_change = match message.last_mut() {
+                Some(segment) if !segments.is_empty() => {
+                    let ss_change = segment.ss_change();
+                    segment.set_ss_change(false);
+                    ss_change
+                }
+                _ => false,
+            };
+
+            message.push(Segment::with_settings(
+                None,
+                None,
+                0,
+                self.ss_hold_delay,
+                0,
+                ss_change,
+            ));
+        }
+
+        self.retry_policy
+            .retry(|| sys::transfer(&self.spidev, &message), is_transient)
+    }
+
+    /// Returns the transfer statistics.
+    ///
+    /// `stats` is only available when the optional `metrics` feature is
+    /// enabled.
+    #[cfg(feature = "metrics")]
+    pub fn stats(&self) -> SpiStats {
+        self.stats.get()
+    }
+
+    /// Resets all transfer statistics to `0`.
+    ///
+    /// `reset_stats` is only available when the optional `metrics` feature is
+    /// enabled.
+    #[cfg(feature = "metrics")]
+    pub fn reset_stats(&self) {
+        self.stats.set(SpiStats::default());
+    }
+
+    // Updates the transfer statistics. Compiles to a no-op when the metrics
+    // feature is disabled.
+    #[inline]
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn record(&self, success: bool, bytes_read: usize, bytes_written: usize) {
+        #[cfg(feature = "metrics")]
+        {
+            let mut stats = self.stats.get();
+            stats.record(success, bytes_read, bytes_written);
+            self.stats.set(stats);
+        }
+    }
 }
 
 // Send is safe for Spi, but we're marked !Send because of the dummy pointer that's
 // needed to force !Sync.
 unsafe impl Send for Spi {}
 
+/// Returns the file descriptor of the underlying device file.
+///
+/// The file descriptor can be shared with other code, such as C libraries
+/// that need access to the same device. `Spi` retains ownership of the
+/// file descriptor, which means it must not be closed, and it's only valid
+/// for as long as `Spi` exists.
+///
+/// Any transfers performed through the file descriptor aren't synchronized
+/// with `Spi`, and may be interleaved with its own transfers.
+impl AsRawFd for Spi {
+    fn as_raw_fd(&self) -> RawFd {
+        self.spidev.as_raw_fd()
+    }
+}
+
 impl fmt::Debug for Spi {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Spi").field("spidev", &self.spidev).finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn error_display_and_source() {
+        let err = Error::from(io::Error::new(io::ErrorKind::Other, "Invalid argument"));
+        assert_eq!(err.to_string(), "I/O error: Invalid argument");
+        assert!(err.source().is_none());
+
+        let err = Error::with_path(io::Error::from_raw_os_error(libc::EIO), "/dev/spidev0.0");
+        assert!(err.to_string().starts_with("I/O error: /dev/spidev0.0: "));
+        let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.raw_os_error(), Some(libc::EIO));
+
+        let err = Error::BitsPerWordNotSupported(9);
+        assert_eq!(err.to_string(), "Bits per word value not supported: 9");
+        assert!(err.source().is_none());
+    }
+}
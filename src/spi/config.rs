@@ -0,0 +1,50 @@
+use super::{Bus, Mode, SlaveSelect};
+
+/// Configuration for an [`Spi`] instance.
+///
+/// `SpiConfig` contains the same settings as the arguments for [`Spi::new`],
+/// and can be passed to [`Spi::from_config`]. This is useful when the SPI
+/// configuration is loaded from a file.
+///
+/// With the optional `serde` feature enabled, `SpiConfig` is (de)serialized
+/// using kebab-case field names, and unknown fields are rejected. For
+/// instance, in TOML:
+///
+/// ```toml
+/// bus = "spi0"
+/// slave-select = "ss0"
+/// clock-speed = 8000000
+/// mode = "mode0"
+/// ```
+///
+/// [`Spi`]: struct.Spi.html
+/// [`Spi::new`]: struct.Spi.html#method.new
+/// [`Spi::from_config`]: struct.Spi.html#method.from_config
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(rename_all = "kebab-case", deny_unknown_fields)
+)]
+pub struct SpiConfig {
+    /// The selected SPI bus.
+    pub bus: Bus,
+    /// The selected Slave Select pin.
+    pub slave_select: SlaveSelect,
+    /// The maximum clock frequency in hertz (Hz).
+    pub clock_speed: u32,
+    /// The SPI mode.
+    pub mode: Mode,
+}
+
+impl SpiConfig {
+    /// Constructs a new `SpiConfig`.
+    pub fn new(bus: Bus, slave_select: SlaveSelect, clock_speed: u32, mode: Mode) -> SpiConfig {
+        SpiConfig {
+            bus,
+            slave_select,
+            clock_speed,
+            mode,
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use super::{Bus, Error, Polarity, Result, SlaveSelect, Spi};
+use crate::gpio::{self, Backend, Capture, Gpio, Level};
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+// Requested sample rate. The actual rate is limited by how fast the level
+// register can be read, which is well below this value on every model.
+const SAMPLE_RATE: u32 = 5_000_000;
+// Time between starting the capture and starting the transfer, which gives
+// the sampling thread a chance to get scheduled.
+const LEAD_TIME: Duration = Duration::from_millis(2);
+// Added to the expected capture duration to account for driver overhead and
+// scheduling delays.
+const MARGIN: Duration = Duration::from_millis(10);
+
+/// Slave Select timing observed by [`Spi::measure_cs_timing`].
+///
+/// All values are approximate. The sampling loop can't detect a level change
+/// until it reads the level register, so each value can be off by up to
+/// [`resolution`].
+///
+/// [`Spi::measure_cs_timing`]: struct.Spi.html#method.measure_cs_timing
+/// [`resolution`]: #method.resolution
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct CsTiming {
+    sclk_pin: u8,
+    ss_pin: u8,
+    setup_delay: Option<Duration>,
+    hold_delay: Option<Duration>,
+    resolution: Duration,
+}
+
+impl CsTiming {
+    /// Returns the BCM GPIO number of the sampled SCLK pin.
+    pub fn sclk_pin(&self) -> u8 {
+        self.sclk_pin
+    }
+
+    /// Returns the BCM GPIO number of the sampled Slave Select pin.
+    pub fn ss_pin(&self) -> u8 {
+        self.ss_pin
+    }
+
+    /// Returns the time between Slave Select becoming active and the first
+    /// SCLK edge.
+    ///
+    /// Returns `None` if either edge wasn't captured.
+    pub fn setup_delay(&self) -> Option<Duration> {
+        self.setup_delay
+    }
+
+    /// Returns the time between the last SCLK edge and Slave Select becoming
+    /// inactive.
+    ///
+    /// Returns `None` if either edge wasn't captured.
+    pub fn hold_delay(&self) -> Option<Duration> {
+        self.hold_delay
+    }
+
+    /// Returns the longest interval between two consecutive samples.
+    pub fn resolution(&self) -> Duration {
+        self.resolution
+    }
+}
+
+// Returns the default SCLK and Slave Select pins for the specified bus, as
+// listed in the module documentation.
+fn default_pins(bus: Bus, slave_select: SlaveSelect) -> Option<(u8, u8)> {
+    let (sclk, ss): (u8, &[u8]) = match bus {
+        Bus::Spi0 => (11, &[8, 7]),
+        Bus::Spi1 => (21, &[18, 17, 16]),
+        Bus::Spi2 => (42, &[43, 44, 45]),
+        Bus::Spi3 => (3, &[0, 24]),
+        Bus::Spi4 => (7, &[4, 25]),
+        Bus::Spi5 => (15, &[12, 26]),
+        Bus::Spi6 => (21, &[18, 27]),
+    };
+
+    ss.get(slave_select as usize).map(|&ss| (sclk, ss))
+}
+
+fn gpio_error(err: gpio::Error) -> Error {
+    match err {
+        gpio::Error::Io(err) => Error::Io(err),
+        err => Error::Io(io::Error::new(io::ErrorKind::Other, err.to_string())),
+    }
+}
+
+// Samples SCLK and Slave Select on a separate thread while buffer is written
+// to the slave device.
+pub(crate) fn measure(
+    spi: &mut Spi,
+    bus: Bus,
+    slave_select: SlaveSelect,
+    buffer: &[u8],
+) -> Result<CsTiming> {
+    let (sclk_pin, ss_pin) = default_pins(bus, slave_select).ok_or_else(|| {
+        Error::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("no default pins for {} {}", bus, slave_select),
+        ))
+    })?;
+
+    let gpio = Gpio::new().map_err(gpio_error)?;
+    if gpio.backend() != Backend::Registers {
+        return Err(Error::Io(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "GPIO registers aren't accessible",
+        )));
+    }
+
+    let active = match spi.ss_polarity()? {
+        Polarity::ActiveLow => Level::Low,
+        Polarity::ActiveHigh => Level::High,
+    };
+
+    let bits = buffer.len() as u64 * 8;
+    let transfer_time =
+        Duration::from_nanos(bits * NANOS_PER_SEC / u64::from(spi.clock_speed()?.max(1)));
+    let duration =
+        LEAD_TIME * 2 + spi.ss_setup_delay() + transfer_time + spi.ss_hold_delay() + MARGIN;
+
+    log_debug!(
+        "Measuring Slave Select timing on GPIO {} (SCLK GPIO {})",
+        ss_pin,
+        sclk_pin
+    );
+
+    let sampler = thread::spawn(move || gpio.capture(&[sclk_pin, ss_pin], SAMPLE_RATE, duration));
+
+    thread::sleep(LEAD_TIME);
+    let written = spi.write(buffer);
+
+    let capture = sampler
+        .join()
+        .map_err(|_| {
+            Error::Io(io::Error::new(
+                io::ErrorKind::Other,
+                "sampling thread panicked",
+            ))
+        })?
+        .map_err(gpio_error)?;
+
+    written?;
+
+    Ok(analyze(&capture, sclk_pin, ss_pin, active))
+}
+
+// Finds the first period where Slave Select is active, and measures the time
+// to the first and from the last SCLK edge within that period.
+fn analyze(capture: &Capture, sclk_pin: u8, ss_pin: u8, active: Level) -> CsTiming {
+    let samples = capture.samples();
+
+    let resolution = samples
+        .windows(2)
+        .map(|pair| pair[1].timestamp() - pair[0].timestamp())
+        .max()
+        .unwrap_or_default();
+
+    let mut timing = CsTiming {
+        sclk_pin,
+        ss_pin,
+        setup_delay: None,
+        hold_delay: None,
+        resolution,
+    };
+
+    // Slave Select must be inactive first, or the start of the transfer was
+    // missed
+    let start = match samples
+        .windows(2)
+        .position(|pair| pair[0].level(ss_pin) != active && pair[1].level(ss_pin) == active)
+    {
+        Some(idx) => idx + 1,
+        None => return timing,
+    };
+
+    let end = samples[start..]
+        .iter()
+        .position(|sample| sample.level(ss_pin) != active)
+        .map(|idx| start + idx);
+
+    let is_sclk_edge =
+        |&idx: &usize| samples[idx].level(sclk_pin) != samples[idx - 1].level(sclk_pin);
+    let range = (start + 1)..end.unwrap_or(samples.len());
+
+    let first_edge = range.clone().find(is_sclk_edge);
+    let last_edge = range.rev().find(is_sclk_edge);
+
+    timing.setup_delay =
+        first_edge.map(|idx| samples[idx].timestamp() - samples[start].timestamp());
+    if let (Some(last_edge), Some(end)) = (last_edge, end) {
+        timing.hold_delay = Some(samples[end].timestamp() - samples[last_edge].timestamp());
+    }
+
+    timing
+}
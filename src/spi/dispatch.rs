@@ -0,0 +1,26 @@
+// Forwards spidev calls to the ioctl backend, or to the simulated backend for
+// Spi instances that were constructed with a Simulator.
+
+use std::os::unix::io::AsRawFd;
+
+use crate::sys::DeviceNode;
+
+use super::ioctl::Result;
+use super::segment::Segment;
+
+pub use super::ioctl::{MODE_3WIRE, MODE_CPHA, MODE_CPOL, MODE_CS_HIGH};
+
+dispatch_device_node! {
+    pub fn read(fd: c_int, buffer: &mut [u8]) -> Result<usize>;
+    pub fn write(fd: c_int, buffer: &[u8]) -> Result<usize>;
+    pub fn mode(fd: c_int, value: &mut u8) -> Result<i32>;
+    pub fn set_mode(fd: c_int, value: u8) -> Result<i32>;
+    pub fn lsb_first(fd: c_int, value: &mut u8) -> Result<i32>;
+    pub fn set_lsb_first(fd: c_int, value: u8) -> Result<i32>;
+    pub fn bits_per_word(fd: c_int, value: &mut u8) -> Result<i32>;
+    pub fn set_bits_per_word(fd: c_int, value: u8) -> Result<i32>;
+    pub fn clock_speed(fd: c_int, value: &mut u32) -> Result<i32>;
+    pub fn set_clock_speed(fd: c_int, value: u32) -> Result<i32>;
+    pub fn set_mode32(fd: c_int, value: u32) -> Result<i32>;
+    pub fn transfer(fd: c_int, segments: &[Segment<'_, '_>]) -> Result<i32>;
+}
@@ -1,13 +1,18 @@
+#[cfg(feature = "hal-1")]
 use embedded_hal::spi::{self, ErrorType, SpiBus, SpiBusFlush, SpiBusRead, SpiBusWrite, SpiDevice};
+#[cfg(feature = "hal-1")]
 use embedded_hal_nb::spi::FullDuplex;
+#[cfg(feature = "hal-1")]
 use std::io;
 
 use super::{Error, Spi};
 
+#[cfg(feature = "hal-1")]
 impl ErrorType for Spi {
     type Error = Error;
 }
 
+#[cfg(feature = "hal-1")]
 impl spi::Error for Error {
     fn kind(&self) -> spi::ErrorKind {
         spi::ErrorKind::Other
@@ -15,6 +20,7 @@ impl spi::Error for Error {
 }
 
 /// `Transfer<u8>` trait implementation for `embedded-hal` v1.0.0-alpha.8.
+#[cfg(feature = "hal-1")]
 impl SpiBus<u8> for Spi {
     fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
         Spi::transfer(self, read, write)?;
@@ -29,17 +35,19 @@ impl SpiBus<u8> for Spi {
 }
 
 /// `Transfer<u8>` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::spi::Transfer<u8> for Spi {
     type Error = Error;
 
     fn transfer<'a>(&mut self, buffer: &'a mut [u8]) -> Result<&'a [u8], Self::Error> {
         let write_buffer = buffer.to_vec();
-        SpiBus::transfer(self, buffer, &write_buffer)?;
+        Spi::transfer(self, buffer, &write_buffer)?;
         Ok(buffer)
     }
 }
 
 /// `SpiBusWrite<u8>` trait implementation for `embedded-hal` v1.0.0-alpha.8.
+#[cfg(feature = "hal-1")]
 impl SpiBusWrite<u8> for Spi {
     fn write(&mut self, buffer: &[u8]) -> Result<(), Self::Error> {
         Spi::write(self, buffer)?;
@@ -49,15 +57,19 @@ impl SpiBusWrite<u8> for Spi {
 }
 
 /// `Write<u8>` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::blocking::spi::Write<u8> for Spi {
     type Error = Error;
 
     fn write(&mut self, buffer: &[u8]) -> Result<(), Self::Error> {
-        SpiBusWrite::write(self, buffer)
+        Spi::write(self, buffer)?;
+
+        Ok(())
     }
 }
 
 /// `SpiBusRead<u8>` trait implementation for `embedded-hal` v1.0.0-alpha.8.
+#[cfg(feature = "hal-1")]
 impl SpiBusRead<u8> for Spi {
     fn read(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error> {
         Spi::read(self, buffer)?;
@@ -67,6 +79,7 @@ impl SpiBusRead<u8> for Spi {
 }
 
 /// `SpiBusFlush` trait implementation for `embedded-hal` v1.0.0-alpha.8.
+#[cfg(feature = "hal-1")]
 impl SpiBusFlush for Spi {
     fn flush(&mut self) -> Result<(), Self::Error> {
         Ok(())
@@ -74,6 +87,7 @@ impl SpiBusFlush for Spi {
 }
 
 /// `FullDuplex<u8>` trait implementation for `embedded-hal` v1.0.0-alpha.8.
+#[cfg(feature = "hal-1")]
 impl FullDuplex<u8> for Spi {
     fn read(&mut self) -> nb::Result<u8, Self::Error> {
         if let Some(last_read) = self.last_read.take() {
@@ -94,15 +108,25 @@ impl FullDuplex<u8> for Spi {
 }
 
 /// `FullDuplex<u8>` trait implementation for `embedded-hal` v0.2.7.
+#[cfg(feature = "hal-0")]
 impl embedded_hal_0::spi::FullDuplex<u8> for Spi {
     type Error = Error;
 
     fn read(&mut self) -> nb::Result<u8, Self::Error> {
-        FullDuplex::read(self)
+        if let Some(last_read) = self.last_read.take() {
+            Ok(last_read)
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
     }
 
     fn send(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
-        FullDuplex::write(self, byte)
+        let mut read_buffer: [u8; 1] = [0];
+
+        Spi::transfer(self, &mut read_buffer, &[byte])?;
+        self.last_read = Some(read_buffer[0]);
+
+        Ok(())
     }
 }
 
@@ -113,16 +137,19 @@ impl embedded_hal_0::spi::FullDuplex<u8> for Spi {
 /// Slave-select is currently handled at the bus level.
 /// This no-op device implementation can be used to satisfy the trait.
 // TODO: The underlying crate::spi::Spi shall be split up to support proper slave-select handling here.
+#[cfg(feature = "hal-1")]
 pub struct SimpleHalSpiDevice<B> {
     bus: B,
 }
 
+#[cfg(feature = "hal-1")]
 impl<B> SimpleHalSpiDevice<B> {
     pub fn new(bus: B) -> SimpleHalSpiDevice<B> {
         SimpleHalSpiDevice { bus }
     }
 }
 
+#[cfg(feature = "hal-1")]
 impl<B: ErrorType> SpiDevice for SimpleHalSpiDevice<B> {
     type Bus = B;
 
@@ -139,6 +166,7 @@ impl<B: ErrorType> SpiDevice for SimpleHalSpiDevice<B> {
     }
 }
 
+#[cfg(feature = "hal-1")]
 impl<B: ErrorType> ErrorType for SimpleHalSpiDevice<B> {
     type Error = Error;
 }
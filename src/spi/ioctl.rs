@@ -4,7 +4,7 @@ use std::io;
 use std::mem;
 use std::result;
 
-use libc::{self, c_int, ioctl};
+use libc::{self, c_int, c_void, ioctl};
 
 use super::segment::Segment;
 
@@ -63,6 +63,22 @@ pub const MODE_TX_QUAD: u32 = 0x0200; // Send on 4 outgoing lines
 pub const MODE_RX_DUAL: u32 = 0x0400; // Receive on 2 incoming lines
 pub const MODE_RX_QUAD: u32 = 0x0800; // Receive on 4 incoming lines
 
+// Plain reads and writes on the device node, which the driver completes as a
+// single transfer
+pub fn read(fd: c_int, buffer: &mut [u8]) -> Result<usize> {
+    let length =
+        parse_retval!(unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut c_void, buffer.len()) })?;
+
+    Ok(length as usize)
+}
+
+pub fn write(fd: c_int, buffer: &[u8]) -> Result<usize> {
+    let length =
+        parse_retval!(unsafe { libc::write(fd, buffer.as_ptr() as *const c_void, buffer.len()) })?;
+
+    Ok(length as usize)
+}
+
 pub fn mode(fd: c_int, value: &mut u8) -> Result<i32> {
     parse_retval!(unsafe { ioctl(fd, REQ_RD_MODE, value) })
 }
@@ -104,7 +120,7 @@ pub fn set_mode32(fd: c_int, value: u32) -> Result<i32> {
 }
 
 pub fn transfer(fd: c_int, segments: &[Segment<'_, '_>]) -> Result<i32> {
-    parse_retval!(unsafe {
+    retry_interrupted!(parse_retval!(unsafe {
         ioctl(
             fd,
             REQ_WR_MESSAGE
@@ -112,5 +128,5 @@ pub fn transfer(fd: c_int, segments: &[Segment<'_, '_>]) -> Result<i32> {
                     << SIZESHIFT),
             segments,
         )
-    })
+    }))
 }
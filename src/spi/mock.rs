@@ -0,0 +1,183 @@
+use std::io;
+use std::result;
+use std::slice;
+
+use libc::{self, c_int};
+
+use super::segment::Segment;
+use super::{Bus, SlaveSelect};
+use crate::mock::{self, NodeHandle, Simulator};
+
+pub type Result<T> = result::Result<T, io::Error>;
+
+// Simulated replacement for the spidev interface. Follows the same function
+// signatures as the ioctl module, and rejects the same settings the
+// Raspberry Pi's SPI driver doesn't support.
+
+struct Node {
+    simulator: Simulator,
+    bus: Bus,
+    slave_select: SlaveSelect,
+    mode: u32,
+    bits_per_word: u8,
+    clock_speed: u32,
+}
+
+fn invalid_input() -> io::Error {
+    io::Error::from_raw_os_error(libc::EINVAL)
+}
+
+pub fn open(simulator: &Simulator, bus: Bus, slave_select: SlaveSelect) -> Result<NodeHandle> {
+    if !simulator.spi_device_exists(bus, slave_select) {
+        return Err(io::Error::from_raw_os_error(libc::ENOENT));
+    }
+
+    mock::open(Node {
+        simulator: simulator.clone(),
+        bus,
+        slave_select,
+        mode: 0,
+        bits_per_word: 8,
+        clock_speed: 125_000_000,
+    })
+}
+
+pub fn read(fd: c_int, buffer: &mut [u8]) -> Result<usize> {
+    let write_buffer = vec![0u8; buffer.len()];
+
+    mock::with_node(fd, |node: &mut Node| {
+        node.simulator
+            .spi_transfer(node.bus, node.slave_select, buffer, &write_buffer)
+    })?;
+
+    Ok(buffer.len())
+}
+
+pub fn write(fd: c_int, buffer: &[u8]) -> Result<usize> {
+    let mut read_buffer = vec![0u8; buffer.len()];
+
+    mock::with_node(fd, |node: &mut Node| {
+        node.simulator
+            .spi_transfer(node.bus, node.slave_select, &mut read_buffer, buffer)
+    })?;
+
+    Ok(buffer.len())
+}
+
+pub fn mode(fd: c_int, value: &mut u8) -> Result<i32> {
+    mock::with_node(fd, |node: &mut Node| {
+        *value = node.mode as u8;
+
+        Ok(0)
+    })
+}
+
+pub fn set_mode(fd: c_int, value: u8) -> Result<i32> {
+    set_mode32(fd, u32::from(value))
+}
+
+pub fn lsb_first(fd: c_int, value: &mut u8) -> Result<i32> {
+    mock::with_node(fd, |_: &mut Node| {
+        *value = 0;
+
+        Ok(0)
+    })
+}
+
+pub fn set_lsb_first(fd: c_int, value: u8) -> Result<i32> {
+    mock::with_node(fd, |_: &mut Node| {
+        if value != 0 {
+            return Err(invalid_input());
+        }
+
+        Ok(0)
+    })
+}
+
+pub fn bits_per_word(fd: c_int, value: &mut u8) -> Result<i32> {
+    mock::with_node(fd, |node: &mut Node| {
+        *value = node.bits_per_word;
+
+        Ok(0)
+    })
+}
+
+pub fn set_bits_per_word(fd: c_int, value: u8) -> Result<i32> {
+    mock::with_node(fd, |node: &mut Node| {
+        match value {
+            0 | 8 => node.bits_per_word = 8,
+            _ => return Err(invalid_input()),
+        }
+
+        Ok(0)
+    })
+}
+
+pub fn clock_speed(fd: c_int, value: &mut u32) -> Result<i32> {
+    mock::with_node(fd, |node: &mut Node| {
+        *value = node.clock_speed;
+
+        Ok(0)
+    })
+}
+
+pub fn set_clock_speed(fd: c_int, value: u32) -> Result<i32> {
+    mock::with_node(fd, |node: &mut Node| {
+        node.clock_speed = value;
+
+        Ok(0)
+    })
+}
+
+pub fn set_mode32(fd: c_int, value: u32) -> Result<i32> {
+    mock::with_node(fd, |node: &mut Node| {
+        // Only CPHA, CPOL and CS_HIGH are supported
+        if value & !0x07 != 0 {
+            return Err(invalid_input());
+        }
+
+        node.mode = value;
+
+        Ok(0)
+    })
+}
+
+pub fn transfer(fd: c_int, segments: &[Segment<'_, '_>]) -> Result<i32> {
+    mock::with_node(fd, |node: &mut Node| {
+        let mut total = 0;
+
+        for segment in segments {
+            let len = segment.len();
+
+            // Empty segments only add a delay, and don't reach the device
+            if len == 0 {
+                continue;
+            }
+
+            // Segments without a write buffer send zero-value bytes
+            let write_buffer = if segment.tx_buf() != 0 {
+                unsafe { slice::from_raw_parts(segment.tx_buf() as *const u8, len) }.to_vec()
+            } else {
+                vec![0u8; len]
+            };
+
+            let mut read_buffer = vec![0u8; len];
+            node.simulator.spi_transfer(
+                node.bus,
+                node.slave_select,
+                &mut read_buffer,
+                &write_buffer,
+            )?;
+
+            // Segments without a read buffer discard any incoming data
+            if segment.rx_buf() != 0 {
+                unsafe { slice::from_raw_parts_mut(segment.rx_buf() as *mut u8, len) }
+                    .copy_from_slice(&read_buffer);
+            }
+
+            total += len;
+        }
+
+        Ok(total as i32)
+    })
+}
@@ -159,6 +159,18 @@ impl<'a, 'b> Segment<'a, 'b> {
         }
     }
 
+    // Pointer to the write buffer, or 0
+    #[cfg(any(feature = "metrics", feature = "mock"))]
+    pub(super) fn tx_buf(&self) -> u64 {
+        self.tx_buf
+    }
+
+    // Pointer to the read buffer, or 0
+    #[cfg(any(feature = "metrics", feature = "mock"))]
+    pub(super) fn rx_buf(&self) -> u64 {
+        self.rx_buf
+    }
+
     /// Returns the number of bytes that will be transferred.
     ///
     /// If both a read buffer and write buffer are supplied,
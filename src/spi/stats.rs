@@ -0,0 +1,59 @@
+/// Transfer statistics for an [`Spi`] instance.
+///
+/// `SpiStats` is retrieved using [`Spi::stats`], which is only available when
+/// the optional `metrics` feature is enabled. All counters start at `0` when
+/// `Spi` is constructed, and wrap around on overflow.
+///
+/// [`Spi`]: struct.Spi.html
+/// [`Spi::stats`]: struct.Spi.html#method.stats
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct SpiStats {
+    bytes_written: u64,
+    bytes_read: u64,
+    transfer_count: u64,
+    error_count: u64,
+}
+
+impl SpiStats {
+    /// Returns the total number of bytes sent to slave devices.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Returns the total number of bytes received from slave devices.
+    ///
+    /// Incoming data that's discarded, for instance during a [`write`], isn't
+    /// included.
+    ///
+    /// [`write`]: struct.Spi.html#method.write
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Returns the number of successfully completed read, write and transfer
+    /// calls.
+    ///
+    /// A call to [`transfer_segments`] counts as a single transfer, regardless
+    /// of the number of segments.
+    ///
+    /// [`transfer_segments`]: struct.Spi.html#method.transfer_segments
+    pub fn transfer_count(&self) -> u64 {
+        self.transfer_count
+    }
+
+    /// Returns the number of read, write and transfer calls that returned an
+    /// error.
+    pub fn error_count(&self) -> u64 {
+        self.error_count
+    }
+
+    pub(super) fn record(&mut self, success: bool, bytes_read: usize, bytes_written: usize) {
+        if success {
+            self.bytes_read = self.bytes_read.wrapping_add(bytes_read as u64);
+            self.bytes_written = self.bytes_written.wrapping_add(bytes_written as u64);
+            self.transfer_count = self.transfer_count.wrapping_add(1);
+        } else {
+            self.error_count = self.error_count.wrapping_add(1);
+        }
+    }
+}
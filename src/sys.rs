@@ -0,0 +1,396 @@
+// Helpers shared between the peripheral modules.
+
+// Not every helper is used when only some of the peripherals are enabled
+#![cfg_attr(
+    not(all(
+        feature = "gpio",
+        feature = "i2c",
+        feature = "pwm",
+        feature = "spi",
+        feature = "uart"
+    )),
+    allow(dead_code)
+)]
+
+use std::fs;
+#[cfg(any(feature = "i2c", feature = "spi"))]
+use std::fs::File;
+use std::io;
+#[cfg(any(feature = "i2c", feature = "spi"))]
+use std::os::unix::io::AsRawFd;
+use std::os::unix::io::RawFd;
+use std::path::Path;
+
+use crate::system::ProcessInfo;
+
+// Details on a permission error that occurred while opening or accessing a
+// device node or sysfs file. Each module converts these into its own
+// Error::PermissionDenied variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PermissionDenied {
+    pub path: String,
+    pub required_group: Option<&'static str>,
+    pub hint: String,
+}
+
+// Returns the conventional Raspberry Pi OS group that grants access to path,
+// or None if path requires superuser privileges.
+fn required_group(path: &str) -> Option<&'static str> {
+    if path.starts_with("/dev/gpiomem")
+        || path.starts_with("/dev/gpiochip")
+        || path.starts_with("/sys/class/pwm")
+    {
+        Some("gpio")
+    } else if path.starts_with("/dev/i2c-") {
+        Some("i2c")
+    } else if path.starts_with("/dev/spidev") {
+        Some("spi")
+    } else if path.starts_with("/dev/tty") || path.starts_with("/dev/serial") {
+        Some("dialout")
+    } else {
+        None
+    }
+}
+
+// Classifies err, which occurred while opening or accessing path. Returns
+// Some if err was caused by EACCES or EPERM, or None for any other error.
+pub(crate) fn permission_denied(err: &io::Error, path: &str) -> Option<PermissionDenied> {
+    if err.kind() != io::ErrorKind::PermissionDenied {
+        return None;
+    }
+
+    let required_group = required_group(path);
+    let hint = match required_group {
+        Some(group) => format!(
+            "Make sure {} is owned by the {} group, and the current user is a member of \
+             the {} group (sudo usermod -aG {} $USER). Log out and back in for group \
+             changes to take effect.",
+            path, group, group, group
+        ),
+        None => format!(
+            "Accessing {} requires superuser privileges. Run the application using sudo.",
+            path
+        ),
+    };
+
+    Some(PermissionDenied {
+        path: path.to_owned(),
+        required_group,
+        hint,
+    })
+}
+
+// Same as permission_denied, for an error returned by an ioctl on fd. The
+// ioctl wrappers only have access to the file descriptor, so the path is
+// looked up through /proc/self/fd. Returns None if the lookup fails.
+pub(crate) fn permission_denied_fd(err: &io::Error, fd: RawFd) -> Option<PermissionDenied> {
+    if err.kind() != io::ErrorKind::PermissionDenied {
+        return None;
+    }
+
+    let path = fs::read_link(format!("/proc/self/fd/{}", fd)).ok()?;
+
+    permission_denied(err, &path.to_string_lossy())
+}
+
+// Details on a missing device node for a peripheral that hasn't been enabled.
+// Each module converts these into its own Error::NotEnabled variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NotEnabled {
+    pub path: String,
+    pub hint: String,
+}
+
+// Firmware configuration file on Raspberry Pi OS Bookworm and later, and on
+// earlier releases.
+const PATH_CONFIG_TXT: [&str; 2] = ["/boot/firmware/config.txt", "/boot/config.txt"];
+
+fn config_txt() -> &'static str {
+    PATH_CONFIG_TXT
+        .iter()
+        .find(|path| Path::new(path).exists())
+        .unwrap_or(&PATH_CONFIG_TXT[1])
+}
+
+// Returns true if config.txt contains an active line that sets key (for
+// instance dtparam or enable_uart) to a comma-separated list containing value.
+fn config_txt_contains(key: &str, value: &str) -> bool {
+    let contents = match fs::read_to_string(config_txt()) {
+        Ok(contents) => contents,
+        Err(_) => return false,
+    };
+
+    contents
+        .lines()
+        .any(|line| match line.trim().split_once('=') {
+            Some((k, v)) if k.trim() == key => v.split(',').any(|v| v.trim() == value),
+            _ => false,
+        })
+}
+
+// Describes how to enable the peripheral for the device node at path, based
+// on the firmware configuration.
+fn not_enabled_hint(path: &str) -> Option<String> {
+    let config = config_txt();
+
+    // A change to config.txt only takes effect after a reboot
+    let pending = |setting: &str| {
+        format!(
+            "{} is set in {}, but {} doesn't exist. Reboot to apply the change, and make sure \
+             it isn't overridden later in the file.",
+            setting, config, path
+        )
+    };
+
+    if let Some(device) = path.strip_prefix("/dev/spidev") {
+        let (bus, _) = device.split_once('.')?;
+
+        return Some(if bus != "0" {
+            format!(
+                "SPI{} isn't enabled. Add dtoverlay=spi{}-1cs (or -2cs/-3cs for additional Slave \
+                 Select pins) to {}, and reboot. More details can be found in \
+                 /boot/overlays/README.",
+                bus, bus, config
+            )
+        } else if config_txt_contains("dtparam", "spi=on") {
+            pending("dtparam=spi=on")
+        } else {
+            format!(
+                "SPI isn't enabled. Enable it with sudo raspi-config (Interface Options > SPI), \
+                 or add dtparam=spi=on to {}, and reboot.",
+                config
+            )
+        });
+    }
+
+    if let Some(bus) = path.strip_prefix("/dev/i2c-") {
+        // The controller exists, but i2c-dev doesn't provide a device node for it
+        if Path::new(&format!("/sys/class/i2c-adapter/i2c-{}", bus)).exists() {
+            return Some(
+                "The i2c-dev kernel module isn't loaded. Load it with sudo modprobe i2c-dev, and \
+                 add i2c-dev to /etc/modules to load it at boot."
+                    .to_owned(),
+            );
+        }
+
+        return Some(if bus != "1" {
+            format!(
+                "I2C bus {} isn't enabled. Add the matching dtoverlay, such as dtoverlay=i2c{} on \
+                 the Raspberry Pi 4 B, or dtoverlay=i2c-gpio, to {}, and reboot. More details can \
+                 be found in /boot/overlays/README.",
+                bus, bus, config
+            )
+        } else if config_txt_contains("dtparam", "i2c_arm=on") {
+            pending("dtparam=i2c_arm=on")
+        } else {
+            format!(
+                "I2C isn't enabled. Enable it with sudo raspi-config (Interface Options > I2C), \
+                 or add dtparam=i2c_arm=on to {}, and reboot.",
+                config
+            )
+        });
+    }
+
+    match path {
+        "/dev/serial0" | "/dev/ttyS0" if config_txt_contains("enable_uart", "1") => {
+            Some(pending("enable_uart=1"))
+        }
+        "/dev/serial0" => Some(format!(
+            "The primary UART isn't enabled. Enable the serial port hardware with sudo \
+             raspi-config (Interface Options > Serial Port), or add enable_uart=1 to {}, and \
+             reboot.",
+            config
+        )),
+        "/dev/ttyS0" => Some(format!(
+            "The mini UART isn't enabled. On models with Bluetooth, the PL011 UART \
+             (/dev/ttyAMA0) is connected to the Bluetooth module, and the mini UART is only \
+             available on the GPIO header after enable_uart=1 is added to {}. On models \
+             without Bluetooth, use /dev/ttyAMA0 instead.",
+            config
+        )),
+        "/dev/ttyAMA0" => Some(format!(
+            "The PL011 UART isn't enabled. Make sure no overlay in {} disables it, and reboot.",
+            config
+        )),
+        "/sys/class/pwm/pwmchip0" => Some(format!(
+            "PWM isn't enabled. Add dtoverlay=pwm (one channel) or dtoverlay=pwm-2chan (two \
+             channels) to {}, and reboot. More details can be found in /boot/overlays/README.",
+            config
+        )),
+        _ => None,
+    }
+}
+
+// Classifies err, which occurred while opening path. Returns Some if err was
+// caused by ENOENT, and path is a device node for a peripheral that needs to
+// be enabled in the firmware configuration, or None otherwise.
+pub(crate) fn not_enabled(err: &io::Error, path: &str) -> Option<NotEnabled> {
+    if err.kind() != io::ErrorKind::NotFound {
+        return None;
+    }
+
+    Some(NotEnabled {
+        path: path.to_owned(),
+        hint: not_enabled_hint(path)?,
+    })
+}
+
+// Returns the processes that have path opened if err was caused by EBUSY, or
+// None for any other error. Lookup failures result in an empty list, since
+// they shouldn't hide the original error.
+pub(crate) fn busy(err: &io::Error, path: &str) -> Option<Vec<ProcessInfo>> {
+    if err.raw_os_error() != Some(libc::EBUSY) {
+        return None;
+    }
+
+    Some(crate::system::find_device_users(path).unwrap_or_default())
+}
+
+// An I/O error that occurred while accessing path. The original error is
+// returned by source(), so its error code remains available.
+#[derive(Debug)]
+struct PathError {
+    path: String,
+    source: io::Error,
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.source)
+    }
+}
+
+impl std::error::Error for PathError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+// Adds path to an I/O error that couldn't be classified as one of the more
+// specific errors, so it's included when the error is displayed. The error
+// kind is preserved.
+pub(crate) fn io_error_with_path(err: io::Error, path: &str) -> io::Error {
+    io::Error::new(
+        err.kind(),
+        PathError {
+            path: path.to_owned(),
+            source: err,
+        },
+    )
+}
+
+// An opened i2cdev or spidev device node, or a simulated one when the
+// peripheral was constructed with a Simulator.
+#[cfg(any(feature = "i2c", feature = "spi"))]
+#[derive(Debug)]
+pub(crate) enum DeviceNode {
+    File(File),
+    #[cfg(feature = "mock")]
+    Simulated(crate::mock::NodeHandle),
+}
+
+#[cfg(any(feature = "i2c", feature = "spi"))]
+impl AsRawFd for DeviceNode {
+    fn as_raw_fd(&self) -> RawFd {
+        match *self {
+            DeviceNode::File(ref file) => file.as_raw_fd(),
+            #[cfg(feature = "mock")]
+            DeviceNode::Simulated(ref node) => node.as_raw_fd(),
+        }
+    }
+}
+
+// Formats the processes listed in an Error::Busy variant.
+pub(crate) fn fmt_users(
+    f: &mut std::fmt::Formatter<'_>,
+    users: &[ProcessInfo],
+) -> std::fmt::Result {
+    if users.is_empty() {
+        return write!(f, "no other users found");
+    }
+
+    write!(f, "in use by ")?;
+    for (idx, user) in users.iter().enumerate() {
+        if idx > 0 {
+            write!(f, ", ")?;
+        }
+
+        write!(f, "{}", user)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::os::unix::io::AsRawFd;
+
+    use super::*;
+
+    #[test]
+    fn required_groups() {
+        assert_eq!(required_group("/dev/gpiomem"), Some("gpio"));
+        assert_eq!(required_group("/dev/gpiomem0"), Some("gpio"));
+        assert_eq!(required_group("/dev/gpiochip0"), Some("gpio"));
+        assert_eq!(required_group("/sys/class/pwm/pwmchip0"), Some("gpio"));
+        assert_eq!(required_group("/dev/i2c-1"), Some("i2c"));
+        assert_eq!(required_group("/dev/spidev0.0"), Some("spi"));
+        assert_eq!(required_group("/dev/ttyAMA0"), Some("dialout"));
+        assert_eq!(required_group("/dev/serial0"), Some("dialout"));
+        assert_eq!(required_group("/dev/mem"), None);
+    }
+
+    #[test]
+    fn permission_denied_eacces() {
+        let err = io::Error::from_raw_os_error(libc::EACCES);
+        let denied = permission_denied(&err, "/dev/i2c-1").unwrap();
+
+        assert_eq!(denied.path, "/dev/i2c-1");
+        assert_eq!(denied.required_group, Some("i2c"));
+        assert!(denied.hint.contains("sudo usermod -aG i2c $USER"));
+    }
+
+    #[test]
+    fn permission_denied_eperm() {
+        let err = io::Error::from_raw_os_error(libc::EPERM);
+        let denied = permission_denied(&err, "/dev/mem").unwrap();
+
+        assert_eq!(denied.path, "/dev/mem");
+        assert_eq!(denied.required_group, None);
+        assert!(denied.hint.contains("superuser privileges"));
+    }
+
+    #[test]
+    fn permission_denied_other_errors() {
+        for errno in [libc::ENOENT, libc::EBUSY, libc::EIO] {
+            let err = io::Error::from_raw_os_error(errno);
+            assert_eq!(permission_denied(&err, "/dev/spidev0.0"), None);
+        }
+    }
+
+    #[test]
+    fn permission_denied_fd_path() {
+        let file = File::open("/dev/null").unwrap();
+        let fd = file.as_raw_fd();
+
+        let err = io::Error::from_raw_os_error(libc::EACCES);
+        let denied = permission_denied_fd(&err, fd).unwrap();
+        assert_eq!(denied.path, "/dev/null");
+
+        let err = io::Error::from_raw_os_error(libc::ENOTTY);
+        assert_eq!(permission_denied_fd(&err, fd), None);
+    }
+
+    #[test]
+    fn io_error_path() {
+        let err = io_error_with_path(io::Error::from_raw_os_error(libc::ENOENT), "/dev/i2c-1");
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().starts_with("/dev/i2c-1: "));
+
+        let source = std::error::Error::source(&err)
+            .and_then(|source| source.downcast_ref::<io::Error>())
+            .unwrap();
+        assert_eq!(source.raw_os_error(), Some(libc::ENOENT));
+    }
+}
@@ -2,35 +2,130 @@
 //!
 //! Use [`DeviceInfo`] to identify the Raspberry Pi's model and SoC.
 //!
+//! The model is identified based on the device tree's `compatible` and
+//! `model` properties, which are available through `/proc/device-tree/` and
+//! `/sys/firmware/devicetree/base/`. If the model can't be identified that
+//! way, [`DeviceInfo`] falls back to the revision code in `/proc/cpuinfo`.
+//!
+//! Revision codes that aren't explicitly recognized, for instance those of
+//! newly released board revisions, are decoded using the new-style revision
+//! code bit fields, which encode the board type and processor.
+//!
+//! If none of these sources identify a known model, but the device tree
+//! describes a Raspberry Pi board with a supported SoC, the model is reported
+//! as [`Model::Unknown`]. Peripherals that don't depend on the exact model
+//! remain usable on those boards.
+//!
+//! Use [`Status`] to retrieve the SoC's temperature, clock speeds and
+//! throttle state. Long-running workloads with strict timing requirements
+//! can use this information to detect when the SoC is being thermally
+//! throttled or running on an insufficient power supply.
+//!
+//! Use [`clock_frequency`] to retrieve the current frequency of the ARM,
+//! core and other clocks, for timing calculations that depend on them.
+//!
+//! Use [`Hat`] to identify a fitted HAT add-on board based on the vendor
+//! info stored in its ID EEPROM.
+//!
+//! Use [`serial_number`] and [`eth_mac`] to retrieve hardware identifiers
+//! that remain the same when the SD card or operating system is replaced.
+//!
+//! The [`mailbox`] module provides direct access to values reported by the
+//! VideoCore firmware, such as clock rates, voltages and the firmware
+//! revision, which are otherwise only available through `vcgencmd`.
+//!
+//! Use [`gpio_bank`] to find out which pad control group a GPIO pin belongs
+//! to. Drive strength, slew rate limiting and hysteresis are shared by all
+//! pins in a group.
+//!
+//! Use [`find_device_users`] to find out which processes are using a
+//! peripheral's device node.
+//!
+//! Use [`system_timer`] to read the SoC's free-running 1 MHz counter, for
+//! timestamps that don't depend on the scheduler.
+//!
+//! [`clock_frequency`]: fn.clock_frequency.html
 //! [`DeviceInfo`]: struct.DeviceInfo.html
+//! [`eth_mac`]: fn.eth_mac.html
+//! [`find_device_users`]: fn.find_device_users.html
+//! [`gpio_bank`]: fn.gpio_bank.html
+//! [`Hat`]: struct.Hat.html
+//! [`mailbox`]: mailbox/index.html
+//! [`Model::Unknown`]: enum.Model.html#variant.Unknown
+//! [`serial_number`]: fn.serial_number.html
+//! [`Status`]: struct.Status.html
+//! [`system_timer`]: fn.system_timer.html
 
 use std::error;
 use std::fmt;
 use std::fs;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
 use std::result;
 
+mod bank;
+mod hat;
+mod header;
+mod identity;
+pub mod mailbox;
+mod process;
+mod status;
+mod timer;
+
+pub use self::bank::{gpio_bank, GpioBank};
+pub use self::hat::Hat;
+pub use self::header::{Header, HeaderPin, PinType};
+pub use self::identity::{eth_mac, serial_number};
+pub use self::process::{find_device_users, ProcessInfo};
+pub use self::status::{clock_frequency, Status, ThrottleStatus};
+pub use self::timer::{system_timer, system_timer_source, TimerSource};
+
 // Peripheral base address
-const PERIPHERAL_BASE_RPI: u32 = 0x2000_0000;
-const PERIPHERAL_BASE_RPI2: u32 = 0x3f00_0000;
-const PERIPHERAL_BASE_RPI4: u32 = 0xfe00_0000;
+const PERIPHERAL_BASE_RPI: u64 = 0x2000_0000;
+const PERIPHERAL_BASE_RPI2: u64 = 0x3f00_0000;
+const PERIPHERAL_BASE_RPI4: u64 = 0xfe00_0000;
+// On the Pi 5, GPIO is handled by the RP1 I/O controller, which is accessed over PCIe
+const PERIPHERAL_BASE_RPI5: u64 = 0x1f_0000_0000;
 
 // Offset from the peripheral base address
 const GPIO_OFFSET: u32 = 0x20_0000;
+const GPIO_OFFSET_RP1: u32 = 0x0d_0000;
 
 // Number of GPIO lines
 const GPIO_LINES_BCM283X: u8 = 54;
 const GPIO_LINES_BCM2711: u8 = 58;
+const GPIO_LINES_RP1: u8 = 54;
+
+const PATH_DEV_GPIOMEM: &str = "/dev/gpiomem";
+const PATH_DEV_GPIOMEM_RP1: &str = "/dev/gpiomem0";
+const PATH_PROC_CPUINFO: &str = "/proc/cpuinfo";
+const PATH_DT_COMPATIBLE: [&str; 2] = [
+    "/proc/device-tree/compatible",
+    "/sys/firmware/devicetree/base/compatible",
+];
+const PATH_DT_MODEL: [&str; 2] = [
+    "/proc/device-tree/model",
+    "/sys/firmware/devicetree/base/model",
+];
+
+// New-style revision code fields. Based on
+// https://www.raspberrypi.com/documentation/computers/raspberry-pi.html#raspberry-pi-revision-codes
+const REVISION_NEW_STYLE: u32 = 1 << 23;
+const REVISION_PROCESSOR_SHIFT: u32 = 12;
+const REVISION_PROCESSOR_MASK: u32 = 0x0f;
+const REVISION_TYPE_SHIFT: u32 = 4;
+const REVISION_TYPE_MASK: u32 = 0xff;
 
 /// Errors that can occur when trying to identify the Raspberry Pi hardware.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Unknown model.
     ///
-    /// `DeviceInfo` was unable to identify the Raspberry Pi model based on the
-    /// contents of `/proc/cpuinfo`, `/sys/firmware/devicetree/base/compatible`
-    /// and `/sys/firmware/devicetree/base/model`.
+    /// `DeviceInfo` was unable to identify the Raspberry Pi model or SoC based
+    /// on the contents of `/proc/device-tree/compatible`,
+    /// `/proc/device-tree/model` and `/proc/cpuinfo`.
     ///
     /// Support for new models is usually added shortly after they are officially
     /// announced and available to the public. Make sure you're using the latest
@@ -40,17 +135,44 @@ pub enum Error {
     /// doesn't provide any of the common user-accessible system files
     /// that are used to identify the model and SoC.
     UnknownModel,
+    /// I/O error.
+    ///
+    /// [`Status`] was unable to retrieve the requested value through either
+    /// sysfs or the VideoCore mailbox interface.
+    ///
+    /// [`Status`]: struct.Status.html
+    Io(io::Error),
+    /// Serial number not set.
+    ///
+    /// The serial number reported by the firmware consists of all zeros. Some
+    /// Compute Module variants are shipped without a programmed serial number.
+    SerialNumberNotSet,
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Error::UnknownModel => write!(f, "Unknown Raspberry Pi model"),
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::SerialNumberNotSet => write!(f, "Serial number not set"),
         }
     }
 }
 
-impl error::Error for Error {}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => err.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
 
 /// Result type returned from methods that can have `system::Error`s.
 pub type Result<T> = result::Result<T, Error>;
@@ -60,7 +182,16 @@ pub type Result<T> = result::Result<T, Error>;
 /// `Model` might be extended with additional variants in a minor or
 /// patch revision, and must not be exhaustively matched against.
 /// Instead, add a `_` catch-all arm to match future variants.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+///
+/// With the optional `serde` feature enabled, `Model` is (de)serialized as the
+/// variant name in kebab-case, for instance `"raspberry-pi4-b"` or
+/// `"raspberry-pi-zero2-w"`. [`Unknown`] is (de)serialized as a struct variant
+/// named `"unknown"`, containing the `compatible` string.
+///
+/// [`Unknown`]: #variant.Unknown
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 #[non_exhaustive]
 pub enum Model {
     RaspberryPiA,
@@ -74,19 +205,35 @@ pub enum Model {
     RaspberryPi3BPlus,
     RaspberryPi4B,
     RaspberryPi400,
+    RaspberryPi5,
+    RaspberryPi500,
     RaspberryPiComputeModule,
     RaspberryPiComputeModule3,
     RaspberryPiComputeModule3Plus,
     RaspberryPiComputeModule4,
     RaspberryPiComputeModule4S,
+    RaspberryPiComputeModule5,
+    RaspberryPiComputeModule5Lite,
     RaspberryPiZero,
     RaspberryPiZeroW,
     RaspberryPiZero2W,
+    /// A Raspberry Pi board that isn't explicitly recognized.
+    ///
+    /// `Unknown` is returned when the device tree identifies the board as a
+    /// Raspberry Pi with a supported SoC, but the specific model isn't known,
+    /// for instance because it was released after this version of RPPAL.
+    ///
+    /// `compatible` contains the most specific entry of the device tree's
+    /// `compatible` property, such as `"raspberrypi,5-model-b"`, which
+    /// identifies the board.
+    Unknown {
+        compatible: String,
+    },
 }
 
 impl fmt::Display for Model {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match *self {
+        match self {
             Model::RaspberryPiA => write!(f, "Raspberry Pi A"),
             Model::RaspberryPiAPlus => write!(f, "Raspberry Pi A+"),
             Model::RaspberryPiBRev1 => write!(f, "Raspberry Pi B Rev 1"),
@@ -98,14 +245,23 @@ impl fmt::Display for Model {
             Model::RaspberryPi3APlus => write!(f, "Raspberry Pi 3 A+"),
             Model::RaspberryPi4B => write!(f, "Raspberry Pi 4 B"),
             Model::RaspberryPi400 => write!(f, "Raspberry Pi 400"),
+            Model::RaspberryPi5 => write!(f, "Raspberry Pi 5"),
+            Model::RaspberryPi500 => write!(f, "Raspberry Pi 500"),
             Model::RaspberryPiComputeModule => write!(f, "Raspberry Pi Compute Module"),
             Model::RaspberryPiComputeModule3 => write!(f, "Raspberry Pi Compute Module 3"),
             Model::RaspberryPiComputeModule3Plus => write!(f, "Raspberry Pi Compute Module 3+"),
             Model::RaspberryPiComputeModule4 => write!(f, "Raspberry Pi Compute Module 4"),
             Model::RaspberryPiComputeModule4S => write!(f, "Raspberry Pi Compute Module 4S"),
+            Model::RaspberryPiComputeModule5 => write!(f, "Raspberry Pi Compute Module 5"),
+            Model::RaspberryPiComputeModule5Lite => {
+                write!(f, "Raspberry Pi Compute Module 5 Lite")
+            }
             Model::RaspberryPiZero => write!(f, "Raspberry Pi Zero"),
             Model::RaspberryPiZeroW => write!(f, "Raspberry Pi Zero W"),
             Model::RaspberryPiZero2W => write!(f, "Raspberry Pi Zero 2 W"),
+            Model::Unknown { compatible } => {
+                write!(f, "Unknown Raspberry Pi model ({})", compatible)
+            }
         }
     }
 }
@@ -115,7 +271,12 @@ impl fmt::Display for Model {
 /// `SoC` might be extended with additional variants in a minor or
 /// patch revision, and must not be exhaustively matched against.
 /// Instead, add a `_` catch-all arm to match future variants.
+///
+/// With the optional `serde` feature enabled, `SoC` is (de)serialized as the
+/// variant name in kebab-case, for instance `"bcm2711"` or `"bcm2837-b0"`.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 #[non_exhaustive]
 pub enum SoC {
     Bcm2835,
@@ -123,6 +284,7 @@ pub enum SoC {
     Bcm2837A1,
     Bcm2837B0,
     Bcm2711,
+    Bcm2712,
 }
 
 impl fmt::Display for SoC {
@@ -133,36 +295,115 @@ impl fmt::Display for SoC {
             SoC::Bcm2837A1 => write!(f, "BCM2837A1"),
             SoC::Bcm2837B0 => write!(f, "BCM2837B0"),
             SoC::Bcm2711 => write!(f, "BCM2711"),
+            SoC::Bcm2712 => write!(f, "BCM2712"),
         }
     }
 }
 
-// Identify Pi model based on /proc/cpuinfo
-fn parse_proc_cpuinfo() -> Result<Model> {
-    let proc_cpuinfo = BufReader::new(match File::open("/proc/cpuinfo") {
+/// Memory-mapped peripheral register blocks.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[non_exhaustive]
+pub enum RegisterBlock {
+    /// GPIO registers.
+    Gpio,
+    /// PWM registers.
+    Pwm,
+    /// Clock manager registers.
+    Clock,
+    /// System timer registers.
+    SystemTimer,
+}
+
+impl fmt::Display for RegisterBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RegisterBlock::Gpio => write!(f, "GPIO"),
+            RegisterBlock::Pwm => write!(f, "PWM"),
+            RegisterBlock::Clock => write!(f, "Clock"),
+            RegisterBlock::SystemTimer => write!(f, "System timer"),
+        }
+    }
+}
+
+/// Indicates which methods are available to access the peripheral registers.
+///
+/// Returned by [`DeviceInfo::capabilities`].
+///
+/// [`DeviceInfo::capabilities`]: struct.DeviceInfo.html#method.capabilities
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Capabilities {
+    gpiomem: bool,
+    rp1: bool,
+}
+
+impl Capabilities {
+    /// Returns `true` if `/dev/gpiomem` exists, or `/dev/gpiomem0` on models
+    /// with an RP1 I/O controller.
+    ///
+    /// `/dev/gpiomem` provides access to the GPIO registers without
+    /// requiring superuser privileges.
+    pub fn gpiomem(&self) -> bool {
+        self.gpiomem
+    }
+
+    /// Returns `true` if the Raspberry Pi uses the RP1 I/O controller.
+    ///
+    /// On models with an RP1, such as the Raspberry Pi 5, the GPIO, PWM and
+    /// clock registers are located on the RP1 instead of the SoC, and use a
+    /// different register layout.
+    pub fn rp1(&self) -> bool {
+        self.rp1
+    }
+
+    /// Returns `true` if accessing the specified register block requires
+    /// mapping `/dev/mem`, which requires superuser privileges.
+    ///
+    /// `/dev/gpiomem` only provides access to the GPIO registers. Any other
+    /// register block is only accessible through `/dev/mem`.
+    pub fn requires_devmem(&self, block: RegisterBlock) -> bool {
+        match block {
+            RegisterBlock::Gpio => !self.gpiomem,
+            RegisterBlock::Pwm | RegisterBlock::Clock | RegisterBlock::SystemTimer => true,
+        }
+    }
+}
+
+// Identify Pi model based on /proc/cpuinfo, located at path
+fn parse_proc_cpuinfo(path: &str) -> Result<Model> {
+    let proc_cpuinfo = BufReader::new(match File::open(path) {
         Ok(file) => file,
         Err(_) => return Err(Error::UnknownModel),
     });
 
     let mut hardware: String = String::new();
     let mut revision: String = String::new();
+    let mut model: String = String::new();
     for line in proc_cpuinfo.lines().flatten() {
         if let Some(line_value) = line.strip_prefix("Hardware\t: ") {
             hardware = String::from(line_value);
         } else if let Some(line_value) = line.strip_prefix("Revision\t: ") {
             revision = String::from(line_value).to_lowercase();
+        } else if let Some(line_value) = line.strip_prefix("Model\t\t: ") {
+            model = String::from(line_value);
         }
     }
 
     // Return an error if we don't recognize the SoC. This check is
     // done to prevent accidentally identifying a non-Pi SBC as a Pi
-    // solely based on the revision field.
+    // solely based on the revision field. 64-bit kernels don't include
+    // the Hardware field, in which case we check the Model field instead.
     match &hardware[..] {
         "BCM2708" | "BCM2835" | "BCM2709" | "BCM2836" | "BCM2710" | "BCM2837" | "BCM2837A1"
-        | "BCM2837B0" | "RP3A0-AU" | "BCM2710A1" | "BCM2711" => {}
+        | "BCM2837B0" | "RP3A0-AU" | "BCM2710A1" | "BCM2711" | "BCM2712" => {}
+        "" if model.starts_with("Raspberry Pi") => {}
         _ => return Err(Error::UnknownModel),
     }
 
+    parse_revision(&revision)
+}
+
+// Identify Pi model based on the revision code
+fn parse_revision(revision: &str) -> Result<Model> {
     let model = if (revision.len() == 4) || (revision.len() == 8) {
         // Older revisions are 4 characters long, or 8 if they've been over-volted
         match &revision[revision.len() - 4..] {
@@ -176,7 +417,7 @@ fn parse_proc_cpuinfo() -> Result<Model> {
         }
     } else if revision.len() >= 6 {
         // Newer revisions consist of at least 6 characters
-        match &revision[..] {
+        match revision {
             "900021" => Model::RaspberryPiAPlus,
             "900032" => Model::RaspberryPiBPlus,
             "a01040" | "a01041" | "a21041" | "a02042" | "a22042" => Model::RaspberryPi2B,
@@ -196,7 +437,9 @@ fn parse_proc_cpuinfo() -> Result<Model> {
             }
             "a03150" => Model::RaspberryPiComputeModule4S,
             "902120" => Model::RaspberryPiZero2W,
-            _ => return Err(Error::UnknownModel),
+            "c04170" | "d04170" => Model::RaspberryPi5,
+            "d04190" => Model::RaspberryPi500,
+            _ => return parse_revision_fields(revision),
         }
     } else {
         return Err(Error::UnknownModel);
@@ -205,18 +448,76 @@ fn parse_proc_cpuinfo() -> Result<Model> {
     Ok(model)
 }
 
-// Identify Pi model based on /sys/firmware/devicetree/base/compatible
-fn parse_base_compatible() -> Result<Model> {
-    let base_compatible = match fs::read_to_string("/sys/firmware/devicetree/base/compatible") {
-        Ok(buffer) => buffer,
-        Err(_) => return Err(Error::UnknownModel),
+// Identify Pi model based on the new-style revision code bit fields
+fn parse_revision_fields(revision: &str) -> Result<Model> {
+    let revision = u32::from_str_radix(revision, 16).map_err(|_| Error::UnknownModel)?;
+
+    if revision & REVISION_NEW_STYLE == 0 {
+        return Err(Error::UnknownModel);
+    }
+
+    let model = match (revision >> REVISION_TYPE_SHIFT) & REVISION_TYPE_MASK {
+        0x00 => Model::RaspberryPiA,
+        0x01 => Model::RaspberryPiBRev2,
+        0x02 => Model::RaspberryPiAPlus,
+        0x03 => Model::RaspberryPiBPlus,
+        0x04 => Model::RaspberryPi2B,
+        0x06 => Model::RaspberryPiComputeModule,
+        0x08 => Model::RaspberryPi3B,
+        0x09 => Model::RaspberryPiZero,
+        0x0a => Model::RaspberryPiComputeModule3,
+        0x0c => Model::RaspberryPiZeroW,
+        0x0d => Model::RaspberryPi3BPlus,
+        0x0e => Model::RaspberryPi3APlus,
+        0x10 => Model::RaspberryPiComputeModule3Plus,
+        0x11 => Model::RaspberryPi4B,
+        0x12 => Model::RaspberryPiZero2W,
+        0x13 => Model::RaspberryPi400,
+        0x14 => Model::RaspberryPiComputeModule4,
+        0x15 => Model::RaspberryPiComputeModule4S,
+        0x17 => Model::RaspberryPi5,
+        0x18 => Model::RaspberryPiComputeModule5,
+        0x19 => Model::RaspberryPi500,
+        0x1a => Model::RaspberryPiComputeModule5Lite,
+        _ => return Err(Error::UnknownModel),
     };
 
+    // Make sure the processor field matches the SoC we expect for this
+    // model, so a corrupted or unrelated revision code isn't accepted.
+    let soc = match (revision >> REVISION_PROCESSOR_SHIFT) & REVISION_PROCESSOR_MASK {
+        0 => SoC::Bcm2835,
+        1 => SoC::Bcm2836,
+        2 => SoC::Bcm2837A1,
+        3 => SoC::Bcm2711,
+        4 => SoC::Bcm2712,
+        _ => return Err(Error::UnknownModel),
+    };
+
+    match (model_soc(&model), soc) {
+        (Some(SoC::Bcm2837B0), SoC::Bcm2837A1) => Ok(model),
+        (Some(expected), soc) if expected == soc => Ok(model),
+        _ => Err(Error::UnknownModel),
+    }
+}
+
+// Read the first device tree property file that's available
+fn read_device_tree(paths: &[&str]) -> Result<String> {
+    paths
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .ok_or(Error::UnknownModel)
+}
+
+// Identify Pi model based on /proc/device-tree/compatible, which contains a
+// list of NUL-terminated strings, ordered from most to least specific
+fn parse_base_compatible(base_compatible: &str) -> Result<Model> {
     // Based on /arch/arm/boot/dts/ and /Documentation/devicetree/bindings/arm/bcm/
     for comp_id in base_compatible.split('\0') {
         let model = match comp_id {
             "raspberrypi,model-b-i2c0" => Model::RaspberryPiBRev1,
-            "raspberrypi,model-b" => Model::RaspberryPiBRev1,
+            // The downstream kernel uses the same device tree for the Pi B Rev 1 and
+            // Rev 2, so we need to rely on the revision code to tell them apart.
+            "raspberrypi,model-b" => continue,
             "raspberrypi,model-a" => Model::RaspberryPiA,
             "raspberrypi,model-b-rev2" => Model::RaspberryPiBRev2,
             "raspberrypi,model-a-plus" => Model::RaspberryPiAPlus,
@@ -228,13 +529,16 @@ fn parse_base_compatible() -> Result<Model> {
             "raspberrypi,3-compute-module" => Model::RaspberryPiComputeModule3,
             "raspberrypi,3-compute-module-plus" => Model::RaspberryPiComputeModule3Plus,
             "raspberrypi,model-zero-w" => Model::RaspberryPiZeroW,
-            "raspberrypi,model-zero-2" => Model::RaspberryPiZero2W,
+            "raspberrypi,model-zero-2" | "raspberrypi,model-zero-2-w" => Model::RaspberryPiZero2W,
             "raspberrypi,3-model-b-plus" => Model::RaspberryPi3BPlus,
             "raspberrypi,3-model-a-plus" => Model::RaspberryPi3APlus,
             "raspberrypi,4-model-b" => Model::RaspberryPi4B,
             "raspberrypi,400" => Model::RaspberryPi400,
             "raspberrypi,4-compute-module" => Model::RaspberryPiComputeModule4,
             "raspberrypi,4-compute-module-s" => Model::RaspberryPiComputeModule4S,
+            "raspberrypi,5-model-b" => Model::RaspberryPi5,
+            "raspberrypi,500" => Model::RaspberryPi500,
+            "raspberrypi,5-compute-module" => Model::RaspberryPiComputeModule5,
             _ => continue,
         };
 
@@ -244,18 +548,45 @@ fn parse_base_compatible() -> Result<Model> {
     Err(Error::UnknownModel)
 }
 
-// Identify Pi model based on /sys/firmware/devicetree/base/model
-fn parse_base_model() -> Result<Model> {
-    let mut base_model = match fs::read_to_string("/sys/firmware/devicetree/base/model") {
-        Ok(mut buffer) => {
-            if let Some(idx) = buffer.find('\0') {
-                buffer.truncate(idx);
-            }
+// Identify the SoC based on /proc/device-tree/compatible. Only used for
+// boards that are identified as a Raspberry Pi, but aren't explicitly
+// recognized.
+fn parse_base_compatible_soc(base_compatible: &str) -> Option<SoC> {
+    let mut raspberry_pi = false;
+    let mut soc = None;
 
-            buffer
+    for comp_id in base_compatible.split('\0') {
+        if comp_id.starts_with("raspberrypi,") {
+            raspberry_pi = true;
         }
-        Err(_) => return Err(Error::UnknownModel),
-    };
+
+        // The downstream kernel uses the bcm2708/2709/2710 identifiers
+        let comp_soc = match comp_id {
+            "brcm,bcm2835" | "brcm,bcm2708" => SoC::Bcm2835,
+            "brcm,bcm2836" | "brcm,bcm2709" => SoC::Bcm2836,
+            "brcm,bcm2837" | "brcm,bcm2710" => SoC::Bcm2837A1,
+            "brcm,bcm2711" => SoC::Bcm2711,
+            "brcm,bcm2712" => SoC::Bcm2712,
+            _ => continue,
+        };
+
+        // Keep the first, most specific, match
+        soc = soc.or(Some(comp_soc));
+    }
+
+    if raspberry_pi {
+        soc
+    } else {
+        None
+    }
+}
+
+// Identify Pi model based on /proc/device-tree/model
+fn parse_base_model(base_model: &str) -> Result<Model> {
+    let mut base_model = String::from(base_model);
+    if let Some(idx) = base_model.find('\0') {
+        base_model.truncate(idx);
+    }
 
     // Check if this is a Pi B rev 2 before we remove the revision part, assuming the
     // PCB Revision numbers on https://elinux.org/RPi_HardwareHistory are correct, and
@@ -273,7 +604,9 @@ fn parse_base_model() -> Result<Model> {
     // Based on /arch/arm/boot/dts/ and /Documentation/devicetree/bindings/arm/bcm/
     let model = match &base_model[..] {
         "Raspberry Pi Model B (no P5)" => Model::RaspberryPiBRev1,
-        "Raspberry Pi Model B" => Model::RaspberryPiBRev1,
+        // The firmware reports both the Pi B Rev 1 and Rev 2 as Raspberry Pi Model B,
+        // so we need to rely on the revision code to tell them apart.
+        "Raspberry Pi Model B" => return Err(Error::UnknownModel),
         "Raspberry Pi Model A" => Model::RaspberryPiA,
         "Raspberry Pi Model B rev2" => Model::RaspberryPiBRev2,
         "Raspberry Pi Model A+" => Model::RaspberryPiAPlus,
@@ -287,7 +620,7 @@ fn parse_base_model() -> Result<Model> {
         "Raspberry Pi Compute Module 3" => Model::RaspberryPiComputeModule3,
         "Raspberry Pi Compute Module 3 Plus" => Model::RaspberryPiComputeModule3Plus,
         "Raspberry Pi Zero W" => Model::RaspberryPiZeroW,
-        "Raspberry Pi Zero 2" => Model::RaspberryPiZero2W,
+        "Raspberry Pi Zero 2" | "Raspberry Pi Zero 2 W" => Model::RaspberryPiZero2W,
         "Raspberry Pi 3 Model B+" => Model::RaspberryPi3BPlus,
         "Raspberry Pi 3 Model B Plus" => Model::RaspberryPi3BPlus,
         "Raspberry Pi 3 Model A Plus" => Model::RaspberryPi3APlus,
@@ -295,19 +628,55 @@ fn parse_base_model() -> Result<Model> {
         "Raspberry Pi 400" => Model::RaspberryPi400,
         "Raspberry Pi Compute Module 4" => Model::RaspberryPiComputeModule4,
         "Raspberry Pi Compute Module 4S" => Model::RaspberryPiComputeModule4S,
+        "Raspberry Pi 5 Model B" => Model::RaspberryPi5,
+        "Raspberry Pi 500" => Model::RaspberryPi500,
+        "Raspberry Pi Compute Module 5" => Model::RaspberryPiComputeModule5,
+        "Raspberry Pi Compute Module 5 Lite" => Model::RaspberryPiComputeModule5Lite,
         _ => return Err(Error::UnknownModel),
     };
 
     Ok(model)
 }
 
+// Identify the SoC used by the specified model
+fn model_soc(model: &Model) -> Option<SoC> {
+    let soc = match model {
+        Model::RaspberryPiA
+        | Model::RaspberryPiAPlus
+        | Model::RaspberryPiBRev1
+        | Model::RaspberryPiBRev2
+        | Model::RaspberryPiBPlus
+        | Model::RaspberryPiComputeModule
+        | Model::RaspberryPiZero
+        | Model::RaspberryPiZeroW => SoC::Bcm2835,
+        Model::RaspberryPi2B => SoC::Bcm2836,
+        Model::RaspberryPi3B | Model::RaspberryPiComputeModule3 | Model::RaspberryPiZero2W => {
+            SoC::Bcm2837A1
+        }
+        Model::RaspberryPi3BPlus
+        | Model::RaspberryPi3APlus
+        | Model::RaspberryPiComputeModule3Plus => SoC::Bcm2837B0,
+        Model::RaspberryPi4B
+        | Model::RaspberryPi400
+        | Model::RaspberryPiComputeModule4
+        | Model::RaspberryPiComputeModule4S => SoC::Bcm2711,
+        Model::RaspberryPi5
+        | Model::RaspberryPi500
+        | Model::RaspberryPiComputeModule5
+        | Model::RaspberryPiComputeModule5Lite => SoC::Bcm2712,
+        Model::Unknown { .. } => return None,
+    };
+
+    Some(soc)
+}
+
 /// Retrieves Raspberry Pi device information.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct DeviceInfo {
     model: Model,
     soc: SoC,
     // Peripheral base memory address
-    peripheral_base: u32,
+    peripheral_base: u64,
     // Offset from the peripheral base memory address for the GPIO section
     gpio_offset: u32,
     // Number of GPIO lines available for this SoC
@@ -318,70 +687,144 @@ impl DeviceInfo {
     /// Constructs a new `DeviceInfo`.
     ///
     /// `new` attempts to identify the Raspberry Pi's model and SoC based on
-    /// the contents of `/proc/cpuinfo`, `/sys/firmware/devicetree/base/compatible`
-    /// and `/sys/firmware/devicetree/base/model`.
+    /// the contents of `/proc/device-tree/compatible`,
+    /// `/proc/device-tree/model` and `/proc/cpuinfo`.
+    ///
+    /// If the device tree identifies the board as a Raspberry Pi with a
+    /// supported SoC, but the specific model isn't recognized, the model is
+    /// set to [`Model::Unknown`].
+    ///
+    /// [`Model::Unknown`]: enum.Model.html#variant.Unknown
     pub fn new() -> Result<DeviceInfo> {
-        // Parse order from most-detailed to least-detailed info
-        let model = parse_proc_cpuinfo()
-            .or_else(|_| parse_base_compatible().or_else(|_| parse_base_model()))?;
+        DeviceInfo::from_paths(&PATH_DT_COMPATIBLE, &PATH_DT_MODEL, PATH_PROC_CPUINFO)
+    }
+
+    // Identifies the model based on the first available compatible and model
+    // device tree properties, and the cpuinfo file. Only tests use paths other
+    // than the ones in /proc and /sys.
+    fn from_paths(compatible: &[&str], model: &[&str], cpuinfo: &str) -> Result<DeviceInfo> {
+        let base_compatible = read_device_tree(compatible);
+
+        // The device tree is the most reliable source. The revision code is
+        // only used when the device tree is unavailable, or doesn't provide
+        // enough information to identify the model.
+        let model = base_compatible
+            .as_ref()
+            .map_err(|_| Error::UnknownModel)
+            .and_then(|base_compatible| parse_base_compatible(base_compatible))
+            .or_else(|_| {
+                read_device_tree(model).and_then(|base_model| parse_base_model(&base_model))
+            })
+            .or_else(|_| parse_proc_cpuinfo(cpuinfo));
 
-        // Set SoC and memory offsets based on model
         match model {
-            Model::RaspberryPiA
-            | Model::RaspberryPiAPlus
-            | Model::RaspberryPiBRev1
-            | Model::RaspberryPiBRev2
-            | Model::RaspberryPiBPlus
-            | Model::RaspberryPiComputeModule
-            | Model::RaspberryPiZero
-            | Model::RaspberryPiZeroW => Ok(DeviceInfo {
-                model,
-                soc: SoC::Bcm2835,
-                peripheral_base: PERIPHERAL_BASE_RPI,
-                gpio_offset: GPIO_OFFSET,
-                gpio_lines: GPIO_LINES_BCM283X,
-            }),
-            Model::RaspberryPi2B => Ok(DeviceInfo {
-                model,
-                soc: SoC::Bcm2836,
-                peripheral_base: PERIPHERAL_BASE_RPI2,
-                gpio_offset: GPIO_OFFSET,
-                gpio_lines: GPIO_LINES_BCM283X,
-            }),
-            Model::RaspberryPi3B | Model::RaspberryPiComputeModule3 | Model::RaspberryPiZero2W => {
-                Ok(DeviceInfo {
-                    model,
-                    soc: SoC::Bcm2837A1,
-                    peripheral_base: PERIPHERAL_BASE_RPI2,
-                    gpio_offset: GPIO_OFFSET,
-                    gpio_lines: GPIO_LINES_BCM283X,
-                })
+            Ok(model) => DeviceInfo::with_model(model),
+            Err(e) => base_compatible
+                .ok()
+                .and_then(|base_compatible| DeviceInfo::with_unknown_model(&base_compatible))
+                .ok_or(e),
+        }
+    }
+
+    /// Constructs a new `DeviceInfo` based on the contents of the device
+    /// tree's `compatible` property.
+    ///
+    /// `compatible` contains a list of NUL-terminated strings, ordered from
+    /// most to least specific, in the same format as
+    /// `/proc/device-tree/compatible`. Unlike [`new`], `from_compatible`
+    /// doesn't fall back to the device tree's `model` property or the
+    /// revision code in `/proc/cpuinfo`.
+    ///
+    /// If `compatible` identifies the board as a Raspberry Pi with a
+    /// supported SoC, but the specific model isn't recognized, the model is
+    /// set to [`Model::Unknown`].
+    ///
+    /// ```
+    /// use rppal::system::{DeviceInfo, Model, SoC};
+    ///
+    /// // Raspberry Pi 4 B
+    /// let device_info = DeviceInfo::from_compatible("raspberrypi,4-model-b\0brcm,bcm2711\0")?;
+    /// assert_eq!(device_info.model(), Model::RaspberryPi4B);
+    /// assert_eq!(device_info.soc(), SoC::Bcm2711);
+    ///
+    /// // Raspberry Pi 5
+    /// let device_info = DeviceInfo::from_compatible("raspberrypi,5-model-b\0brcm,bcm2712\0")?;
+    /// assert_eq!(device_info.model(), Model::RaspberryPi5);
+    /// assert_eq!(device_info.soc(), SoC::Bcm2712);
+    ///
+    /// // Raspberry Pi 3 B+, running the downstream kernel
+    /// let device_info =
+    ///     DeviceInfo::from_compatible("raspberrypi,3-model-b-plus\0brcm,bcm2837\0")?;
+    /// assert_eq!(device_info.model(), Model::RaspberryPi3BPlus);
+    /// assert_eq!(device_info.soc(), SoC::Bcm2837B0);
+    ///
+    /// // Raspberry Pi B, running the downstream kernel, which uses the same
+    /// // compatible string for the B Rev 1 and Rev 2
+    /// let device_info = DeviceInfo::from_compatible("raspberrypi,model-b\0brcm,bcm2835\0")?;
+    /// assert_eq!(
+    ///     device_info.model(),
+    ///     Model::Unknown {
+    ///         compatible: "raspberrypi,model-b".to_owned()
+    ///     }
+    /// );
+    /// assert_eq!(device_info.soc(), SoC::Bcm2835);
+    ///
+    /// // A board that isn't a Raspberry Pi
+    /// assert!(DeviceInfo::from_compatible("pine64,rock64\0rockchip,rk3328\0").is_err());
+    /// # Ok::<(), rppal::system::Error>(())
+    /// ```
+    ///
+    /// [`new`]: #method.new
+    /// [`Model::Unknown`]: enum.Model.html#variant.Unknown
+    pub fn from_compatible(compatible: &str) -> Result<DeviceInfo> {
+        match parse_base_compatible(compatible) {
+            Ok(model) => DeviceInfo::with_model(model),
+            Err(e) => DeviceInfo::with_unknown_model(compatible).ok_or(e),
+        }
+    }
+
+    fn with_model(model: Model) -> Result<DeviceInfo> {
+        let soc = model_soc(&model).ok_or(Error::UnknownModel)?;
+
+        Ok(DeviceInfo::with_soc(model, soc))
+    }
+
+    // Returns a DeviceInfo with the model set to Model::Unknown, if the
+    // device tree identifies the board as a Raspberry Pi with a supported SoC
+    fn with_unknown_model(base_compatible: &str) -> Option<DeviceInfo> {
+        let soc = parse_base_compatible_soc(base_compatible)?;
+        let compatible = base_compatible
+            .split('\0')
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+
+        Some(DeviceInfo::with_soc(Model::Unknown { compatible }, soc))
+    }
+
+    fn with_soc(model: Model, soc: SoC) -> DeviceInfo {
+        // Set memory offsets based on SoC
+        let (peripheral_base, gpio_offset, gpio_lines) = match soc {
+            SoC::Bcm2835 => (PERIPHERAL_BASE_RPI, GPIO_OFFSET, GPIO_LINES_BCM283X),
+            SoC::Bcm2836 | SoC::Bcm2837A1 | SoC::Bcm2837B0 => {
+                (PERIPHERAL_BASE_RPI2, GPIO_OFFSET, GPIO_LINES_BCM283X)
             }
-            Model::RaspberryPi3BPlus
-            | Model::RaspberryPi3APlus
-            | Model::RaspberryPiComputeModule3Plus => Ok(DeviceInfo {
-                model,
-                soc: SoC::Bcm2837B0,
-                peripheral_base: PERIPHERAL_BASE_RPI2,
-                gpio_offset: GPIO_OFFSET,
-                gpio_lines: GPIO_LINES_BCM283X,
-            }),
-            Model::RaspberryPi4B
-            | Model::RaspberryPi400
-            | Model::RaspberryPiComputeModule4
-            | Model::RaspberryPiComputeModule4S => Ok(DeviceInfo {
-                model,
-                soc: SoC::Bcm2711,
-                peripheral_base: PERIPHERAL_BASE_RPI4,
-                gpio_offset: GPIO_OFFSET,
-                gpio_lines: GPIO_LINES_BCM2711,
-            }),
+            SoC::Bcm2711 => (PERIPHERAL_BASE_RPI4, GPIO_OFFSET, GPIO_LINES_BCM2711),
+            SoC::Bcm2712 => (PERIPHERAL_BASE_RPI5, GPIO_OFFSET_RP1, GPIO_LINES_RP1),
+        };
+
+        DeviceInfo {
+            model,
+            soc,
+            peripheral_base,
+            gpio_offset,
+            gpio_lines,
         }
     }
 
     /// Returns the Raspberry Pi's model.
     pub fn model(&self) -> Model {
-        self.model
+        self.model.clone()
     }
 
     /// Returns the Raspberry Pi's SoC.
@@ -390,17 +833,211 @@ impl DeviceInfo {
     }
 
     /// Returns the peripheral base memory address.
-    pub(crate) fn peripheral_base(&self) -> u32 {
+    ///
+    /// On models with an RP1 I/O controller, `peripheral_base` returns the
+    /// RP1's base memory address.
+    pub fn peripheral_base(&self) -> u64 {
         self.peripheral_base
     }
 
     /// Returns the offset from the peripheral base memory address for the GPIO section.
-    pub(crate) fn gpio_offset(&self) -> u32 {
+    pub fn gpio_offset(&self) -> u32 {
         self.gpio_offset
     }
 
+    /// Returns the layout of the GPIO header.
+    ///
+    /// The Raspberry Pi A and B feature a 26-pin header, with a different
+    /// layout for the B Rev 1. All other models, including the Zero models
+    /// where the header is unpopulated, feature a 40-pin header. Returns
+    /// `None` for the Compute Modules, which don't have a GPIO header, and for
+    /// models that aren't explicitly recognized.
+    ///
+    /// The unpopulated P5 header on the Raspberry Pi B Rev 2 isn't included.
+    pub fn header(&self) -> Option<Header> {
+        match self.model {
+            Model::RaspberryPiBRev1 => Some(header::HEADER_26_REV1),
+            Model::RaspberryPiA | Model::RaspberryPiBRev2 => Some(header::HEADER_26),
+            Model::RaspberryPiComputeModule
+            | Model::RaspberryPiComputeModule3
+            | Model::RaspberryPiComputeModule3Plus
+            | Model::RaspberryPiComputeModule4
+            | Model::RaspberryPiComputeModule4S
+            | Model::RaspberryPiComputeModule5
+            | Model::RaspberryPiComputeModule5Lite
+            | Model::Unknown { .. } => None,
+            _ => Some(header::HEADER_40),
+        }
+    }
+
+    /// Returns the methods available to access the peripheral registers.
+    ///
+    /// `capabilities` checks which device files are currently available, so
+    /// the result may change when the system configuration is changed.
+    pub fn capabilities(&self) -> Capabilities {
+        let rp1 = self.soc == SoC::Bcm2712;
+
+        Capabilities {
+            gpiomem: Path::new(if rp1 {
+                PATH_DEV_GPIOMEM_RP1
+            } else {
+                PATH_DEV_GPIOMEM
+            })
+            .exists(),
+            rp1,
+        }
+    }
+
     /// Returns the number of GPIO lines available for this SoC.
+    #[cfg(feature = "gpio")]
     pub(crate) fn gpio_lines(&self) -> u8 {
         self.gpio_lines
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revision_codes() {
+        let revisions = [
+            // Old-style codes, including over-volted ones
+            ("beta", Model::RaspberryPiBRev1),
+            ("0002", Model::RaspberryPiBRev1),
+            ("0003", Model::RaspberryPiBRev1),
+            ("0007", Model::RaspberryPiA),
+            ("000e", Model::RaspberryPiBRev2),
+            ("1000000e", Model::RaspberryPiBRev2),
+            ("0010", Model::RaspberryPiBPlus),
+            ("0011", Model::RaspberryPiComputeModule),
+            ("0012", Model::RaspberryPiAPlus),
+            ("0015", Model::RaspberryPiA),
+            // New-style codes
+            ("900021", Model::RaspberryPiAPlus),
+            ("900032", Model::RaspberryPiBPlus),
+            ("a01041", Model::RaspberryPi2B),
+            ("a22042", Model::RaspberryPi2B),
+            ("a02082", Model::RaspberryPi3B),
+            ("900093", Model::RaspberryPiZero),
+            ("a020a0", Model::RaspberryPiComputeModule3),
+            ("9000c1", Model::RaspberryPiZeroW),
+            ("a020d3", Model::RaspberryPi3BPlus),
+            ("9020e0", Model::RaspberryPi3APlus),
+            ("a02100", Model::RaspberryPiComputeModule3Plus),
+            ("c03111", Model::RaspberryPi4B),
+            ("d03115", Model::RaspberryPi4B),
+            ("c03130", Model::RaspberryPi400),
+            ("b03140", Model::RaspberryPiComputeModule4),
+            ("a03150", Model::RaspberryPiComputeModule4S),
+            ("902120", Model::RaspberryPiZero2W),
+            ("d04170", Model::RaspberryPi5),
+            ("d04190", Model::RaspberryPi500),
+            // Codes that aren't listed explicitly are decoded from the bit fields
+            ("b03118", Model::RaspberryPi4B),
+            ("e04171", Model::RaspberryPi5),
+            ("a020d4", Model::RaspberryPi3BPlus),
+            ("b04180", Model::RaspberryPiComputeModule5),
+            ("c041a0", Model::RaspberryPiComputeModule5Lite),
+        ];
+
+        for (revision, model) in revisions.iter() {
+            assert_eq!(
+                parse_revision(revision).ok().as_ref(),
+                Some(model),
+                "{}",
+                revision
+            );
+        }
+    }
+
+    // Real device tree and cpuinfo dumps, with the serial numbers replaced
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/device-tree");
+
+    const BOARDS: [(&str, Model); 6] = [
+        ("raspberry-pi-b-plus-no-dt", Model::RaspberryPiBPlus),
+        ("raspberry-pi-b-rev2", Model::RaspberryPiBRev2),
+        ("raspberry-pi-zero2-w", Model::RaspberryPiZero2W),
+        ("raspberry-pi3-b-plus", Model::RaspberryPi3BPlus),
+        ("raspberry-pi4-b", Model::RaspberryPi4B),
+        ("raspberry-pi5", Model::RaspberryPi5),
+    ];
+
+    // Identifies board using the fixture files for the selected sources
+    fn identify(board: &str, compatible: bool, model: bool) -> Result<DeviceInfo> {
+        let path = |name: &str| format!("{}/{}/{}", FIXTURES, board, name);
+        let compatible_path = path("compatible");
+        let model_path = path("model");
+        let compatible_paths = [compatible_path.as_str()];
+        let model_paths = [model_path.as_str()];
+
+        DeviceInfo::from_paths(
+            if compatible { &compatible_paths } else { &[] },
+            if model { &model_paths } else { &[] },
+            &path("cpuinfo"),
+        )
+    }
+
+    #[test]
+    fn device_tree_fixtures() {
+        for (board, model) in BOARDS.iter() {
+            let device_info = identify(board, true, true).unwrap();
+
+            assert_eq!(device_info.model(), *model, "{}", board);
+            assert_eq!(Some(device_info.soc()), model_soc(model), "{}", board);
+        }
+    }
+
+    #[test]
+    fn device_tree_fixtures_fallback() {
+        // Without the compatible property, the model property is used, and
+        // without either, the revision code in cpuinfo
+        for (board, model) in BOARDS.iter() {
+            assert_eq!(
+                identify(board, false, true).unwrap().model(),
+                *model,
+                "{}",
+                board
+            );
+            assert_eq!(
+                identify(board, false, false).unwrap().model(),
+                *model,
+                "{}",
+                board
+            );
+        }
+    }
+
+    #[test]
+    fn device_tree_fixtures_not_a_pi() {
+        assert!(identify("rock64", true, true).is_err());
+        assert!(identify("rock64", false, false).is_err());
+    }
+
+    #[test]
+    fn invalid_revision_codes() {
+        let revisions = [
+            "", "0001", "0016", "abc",    // Old-style code without the new-style flag
+            "003111", // Unknown board type
+            "c031f0", // Processor doesn't match the board type
+            "c04111", "a03170", "not-hex",
+        ];
+
+        for revision in revisions.iter() {
+            assert!(parse_revision(revision).is_err(), "{}", revision);
+        }
+    }
+
+    #[test]
+    fn error_display_and_source() {
+        use std::error::Error as _;
+
+        let err = Error::from(io::Error::new(io::ErrorKind::Other, "Permission denied"));
+        assert_eq!(err.to_string(), "I/O error: Permission denied");
+        assert!(err.source().is_none());
+
+        let err = Error::SerialNumberNotSet;
+        assert_eq!(err.to_string(), "Serial number not set");
+        assert!(err.source().is_none());
+    }
+}
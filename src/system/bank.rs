@@ -0,0 +1,88 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Pad control groups of the BCM283x and BCM2711 GPIO pins.
+///
+/// The pins are divided into three groups, each with a single pad control
+/// register. Drive strength (2–16 mA, 8 mA by default), slew rate limiting
+/// and input hysteresis are configured per group, not per pin. Changing any
+/// of these settings for one pin changes them for every pin in the same
+/// group. For instance, increasing the drive strength for GPIO 18 also
+/// affects the I2C, SPI and UART pins on the GPIO header, which all belong
+/// to [`Bank0`].
+///
+/// Every group uses 3.3 V logic. None of the pins are 5 V tolerant,
+/// regardless of their group.
+///
+/// On the Raspberry Pi 5, the GPIO pins are provided by the RP1, which
+/// configures the pads per pin, and uses a different bank layout.
+///
+/// [`Bank0`]: #variant.Bank0
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum GpioBank {
+    /// GPIO 0-27, which includes every GPIO pin on the GPIO header.
+    Bank0,
+    /// GPIO 28-45, which are mostly used internally, or exposed on the
+    /// Compute Module.
+    Bank1,
+    /// GPIO 46-53, which are used internally, for instance for the SD card
+    /// interface.
+    Bank2,
+}
+
+impl GpioBank {
+    /// Returns the BCM GPIO numbers of the pins in this group.
+    pub fn pins(&self) -> RangeInclusive<u8> {
+        match *self {
+            GpioBank::Bank0 => 0..=27,
+            GpioBank::Bank1 => 28..=45,
+            GpioBank::Bank2 => 46..=53,
+        }
+    }
+
+    /// Returns `true` if `pin` belongs to this group.
+    pub fn contains(&self, pin: u8) -> bool {
+        self.pins().contains(&pin)
+    }
+}
+
+impl fmt::Display for GpioBank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            GpioBank::Bank0 => write!(f, "Bank 0"),
+            GpioBank::Bank1 => write!(f, "Bank 1"),
+            GpioBank::Bank2 => write!(f, "Bank 2"),
+        }
+    }
+}
+
+/// Returns the pad control group the specified BCM GPIO pin belongs to, or
+/// `None` if `pin` is higher than 53.
+///
+/// Use `gpio_bank` to find out which other pins are affected when the drive
+/// strength, slew rate limiting or hysteresis of a pin is changed, since
+/// those settings are shared by all pins in a group. More details can be
+/// found in the [`GpioBank`] documentation.
+///
+/// ```
+/// use rppal::system::{gpio_bank, GpioBank};
+///
+/// assert_eq!(gpio_bank(0), Some(GpioBank::Bank0));
+/// assert_eq!(gpio_bank(27), Some(GpioBank::Bank0));
+/// assert_eq!(gpio_bank(28), Some(GpioBank::Bank1));
+/// assert_eq!(gpio_bank(45), Some(GpioBank::Bank1));
+/// assert_eq!(gpio_bank(46), Some(GpioBank::Bank2));
+/// assert_eq!(gpio_bank(53), Some(GpioBank::Bank2));
+/// assert_eq!(gpio_bank(54), None);
+///
+/// // GPIO 2 (SDA) and GPIO 18 (PCM CLK) share their drive strength
+/// assert_eq!(gpio_bank(2), gpio_bank(18));
+/// ```
+///
+/// [`GpioBank`]: enum.GpioBank.html
+pub fn gpio_bank(pin: u8) -> Option<GpioBank> {
+    [GpioBank::Bank0, GpioBank::Bank1, GpioBank::Bank2]
+        .iter()
+        .copied()
+        .find(|bank| bank.contains(pin))
+}
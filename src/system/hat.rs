@@ -0,0 +1,207 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::Result;
+#[cfg(feature = "i2c")]
+use crate::i2c::{self, I2c};
+
+const PATH_DT_HAT: [&str; 2] = ["/proc/device-tree/hat", "/sys/firmware/devicetree/base/hat"];
+
+// HAT ID EEPROM layout. Based on
+// https://github.com/raspberrypi/hats/blob/master/eeprom-format.md
+#[cfg(feature = "i2c")]
+const EEPROM_SIGNATURE: [u8; 4] = *b"R-Pi";
+#[cfg(feature = "i2c")]
+const EEPROM_HEADER_SIZE: usize = 12;
+#[cfg(feature = "i2c")]
+const ATOM_HEADER_SIZE: usize = 8;
+#[cfg(feature = "i2c")]
+const ATOM_TYPE_VENDOR_INFO: u16 = 0x0001;
+// UUID (16), product ID (2), product version (2), string lengths (2)
+#[cfg(feature = "i2c")]
+const VENDOR_INFO_SIZE: usize = 22;
+
+/// Identification of the HAT (Hardware Attached on Top) add-on board.
+///
+/// The Raspberry Pi's firmware reads the ID EEPROM of a fitted HAT during boot,
+/// and exposes its vendor info through the device tree. Use [`detect`] to
+/// retrieve this information. On systems where the firmware hasn't populated
+/// the device tree, [`from_eeprom`] can read the EEPROM directly.
+///
+/// [`detect`]: #method.detect
+/// [`from_eeprom`]: #method.from_eeprom
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Hat {
+    vendor: String,
+    product: String,
+    product_id: u16,
+    product_ver: u16,
+    uuid: String,
+}
+
+impl Hat {
+    /// Retrieves the HAT's identification from the device tree.
+    ///
+    /// The properties are read from `/proc/device-tree/hat/` or
+    /// `/sys/firmware/devicetree/base/hat/`. Returns `Ok(None)` if neither
+    /// directory exists, which means either no HAT is fitted, or its EEPROM
+    /// couldn't be read by the firmware.
+    ///
+    /// Properties that are missing are reported as an empty string, or `0`
+    /// for the product ID and version.
+    pub fn detect() -> Result<Option<Hat>> {
+        let base = match PATH_DT_HAT.iter().find(|path| Path::new(path).is_dir()) {
+            Some(base) => base,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Hat {
+            vendor: read_string(base, "vendor")?,
+            product: read_string(base, "product")?,
+            product_id: read_id(base, "product_id")?,
+            product_ver: read_id(base, "product_ver")?,
+            uuid: read_string(base, "uuid")?,
+        }))
+    }
+
+    /// Reads the HAT's identification directly from its ID EEPROM.
+    ///
+    /// The ID EEPROM is connected to the ID_SD and ID_SC pins (BCM GPIO 0 and 1),
+    /// which are normally reserved for the firmware. Enable the I2C bus on those
+    /// pins by adding `dtparam=i2c_vc=on` to `/boot/config.txt`, and pass an
+    /// [`I2c`] instance for that bus, typically bus 0. `from_eeprom` changes the
+    /// slave address to `0x50`.
+    ///
+    /// Returns `Ok(None)` if the EEPROM doesn't contain a valid HAT signature or
+    /// vendor info atom.
+    ///
+    /// [`I2c`]: ../i2c/struct.I2c.html
+    #[cfg(feature = "i2c")]
+    pub fn from_eeprom(i2c: &mut I2c) -> i2c::Result<Option<Hat>> {
+        const ADDR_EEPROM: u16 = 0x50;
+
+        i2c.set_slave_address(ADDR_EEPROM)?;
+
+        // The EEPROM uses 16-bit addresses. The vendor info atom is always
+        // stored directly after the EEPROM header.
+        let mut header = [0u8; EEPROM_HEADER_SIZE + ATOM_HEADER_SIZE + VENDOR_INFO_SIZE];
+        i2c.write_read(&[0x00, 0x00], &mut header)?;
+
+        if header[0..4] != EEPROM_SIGNATURE
+            || u16::from_le_bytes([header[12], header[13]]) != ATOM_TYPE_VENDOR_INFO
+        {
+            return Ok(None);
+        }
+
+        let vslen = header[EEPROM_HEADER_SIZE + ATOM_HEADER_SIZE + 20] as usize;
+        let pslen = header[EEPROM_HEADER_SIZE + ATOM_HEADER_SIZE + 21] as usize;
+
+        let mut strings = vec![0u8; vslen + pslen];
+        let offset = (header.len() as u16).to_be_bytes();
+        i2c.write_read(&offset, &mut strings)?;
+
+        let mut data = header[EEPROM_HEADER_SIZE + ATOM_HEADER_SIZE..].to_vec();
+        data.extend_from_slice(&strings);
+
+        Ok(Hat::parse_vendor_info(&data))
+    }
+
+    // Parses the data section of a vendor info atom.
+    #[cfg(feature = "i2c")]
+    fn parse_vendor_info(data: &[u8]) -> Option<Hat> {
+        if data.len() < VENDOR_INFO_SIZE {
+            return None;
+        }
+
+        // The UUID is stored as four 32-bit little-endian words, starting with
+        // the least significant one.
+        let word = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        let uuid = format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:04x}{:08x}",
+            word(12),
+            word(8) >> 16,
+            word(8) & 0xffff,
+            word(4) >> 16,
+            word(4) & 0xffff,
+            word(0)
+        );
+
+        let vslen = data[20] as usize;
+        let pslen = data[21] as usize;
+        let strings = &data[VENDOR_INFO_SIZE..];
+        if strings.len() < vslen + pslen {
+            return None;
+        }
+
+        Some(Hat {
+            vendor: trim(&String::from_utf8_lossy(&strings[..vslen])).to_owned(),
+            product: trim(&String::from_utf8_lossy(&strings[vslen..vslen + pslen])).to_owned(),
+            product_id: u16::from_le_bytes([data[16], data[17]]),
+            product_ver: u16::from_le_bytes([data[18], data[19]]),
+            uuid,
+        })
+    }
+
+    /// Returns the vendor name.
+    pub fn vendor(&self) -> &str {
+        &self.vendor
+    }
+
+    /// Returns the product name.
+    pub fn product(&self) -> &str {
+        &self.product
+    }
+
+    /// Returns the vendor-assigned product ID.
+    pub fn product_id(&self) -> u16 {
+        self.product_id
+    }
+
+    /// Returns the vendor-assigned product version.
+    pub fn product_ver(&self) -> u16 {
+        self.product_ver
+    }
+
+    /// Returns the HAT's UUID, formatted as a lowercase hyphenated string.
+    ///
+    /// The UUID is unique to each individual board, and can be used to tell
+    /// apart multiple boards of the same product.
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+}
+
+// Device tree string properties are NUL-terminated
+fn trim(value: &str) -> &str {
+    value.trim_end_matches('\0').trim()
+}
+
+fn read_string(base: &str, property: &str) -> Result<String> {
+    match fs::read(format!("{}/{}", base, property)) {
+        Ok(value) => Ok(trim(&String::from_utf8_lossy(&value)).to_owned()),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Product IDs and versions are stored as hexadecimal strings, such as "0x0001"
+fn read_id(base: &str, property: &str) -> Result<u16> {
+    let value = read_string(base, property)?;
+    if value.is_empty() {
+        return Ok(0);
+    }
+
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(&value);
+
+    u16::from_str_radix(digits, 16).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid HAT {}: {}", property, value),
+        )
+        .into()
+    })
+}
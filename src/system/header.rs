@@ -0,0 +1,243 @@
+use std::borrow::Cow;
+use std::fmt;
+
+/// Types of pins on the GPIO header.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum PinType {
+    /// 3.3 V power supply.
+    Power3v3,
+    /// 5 V power supply.
+    Power5v,
+    /// Ground.
+    Ground,
+    /// GPIO pin with the specified BCM GPIO number.
+    Gpio(u8),
+}
+
+impl fmt::Display for PinType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PinType::Power3v3 => write!(f, "3V3"),
+            PinType::Power5v => write!(f, "5V"),
+            PinType::Ground => write!(f, "GND"),
+            PinType::Gpio(pin) => write!(f, "GPIO{}", pin),
+        }
+    }
+}
+
+/// A physical pin on the GPIO header.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeaderPin {
+    physical: u8,
+    pin_type: PinType,
+    // Borrowed for the built-in header layouts, owned when deserialized
+    function: Option<Cow<'static, str>>,
+}
+
+impl HeaderPin {
+    const fn new(physical: u8, pin_type: PinType, function: Option<&'static str>) -> HeaderPin {
+        HeaderPin {
+            physical,
+            pin_type,
+            function: match function {
+                Some(function) => Some(Cow::Borrowed(function)),
+                None => None,
+            },
+        }
+    }
+
+    /// Returns the physical pin number.
+    ///
+    /// Physical pins are numbered starting at `1`, with odd numbers on the
+    /// inner row, and even numbers on the outer row.
+    pub fn physical(&self) -> u8 {
+        self.physical
+    }
+
+    /// Returns the pin type.
+    pub fn pin_type(&self) -> PinType {
+        self.pin_type
+    }
+
+    /// Returns the BCM GPIO number, or `None` if this isn't a GPIO pin.
+    pub fn bcm(&self) -> Option<u8> {
+        match self.pin_type {
+            PinType::Gpio(pin) => Some(pin),
+            _ => None,
+        }
+    }
+
+    /// Returns a short description of the alternate function commonly
+    /// assigned to this pin, such as `"SDA1"` or `"SPI0 MOSI"`, or `None` if
+    /// the pin doesn't have a commonly used alternate function.
+    pub fn function(&self) -> Option<&str> {
+        self.function.as_deref()
+    }
+}
+
+/// Layout of the GPIO header.
+///
+/// Returned by [`DeviceInfo::header`].
+///
+/// [`DeviceInfo::header`]: struct.DeviceInfo.html#method.header
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Header {
+    // Borrowed for the built-in header layouts, owned when deserialized
+    pins: Cow<'static, [HeaderPin]>,
+}
+
+impl Header {
+    /// Returns all physical pins, ordered by their physical pin number.
+    pub fn pins(&self) -> &[HeaderPin] {
+        &self.pins
+    }
+
+    /// Returns the number of physical pins.
+    pub fn len(&self) -> usize {
+        self.pins.len()
+    }
+
+    /// Returns `true` if the header doesn't contain any pins.
+    pub fn is_empty(&self) -> bool {
+        self.pins.is_empty()
+    }
+
+    /// Returns the physical pin with the specified number.
+    pub fn pin(&self, physical: u8) -> Option<HeaderPin> {
+        self.pins
+            .iter()
+            .find(|pin| pin.physical == physical)
+            .cloned()
+    }
+
+    /// Converts a physical pin number to a BCM GPIO number.
+    ///
+    /// Returns `None` if the physical pin doesn't exist, or isn't a GPIO pin.
+    pub fn to_bcm(&self, physical: u8) -> Option<u8> {
+        self.pin(physical).and_then(|pin| pin.bcm())
+    }
+
+    /// Converts a BCM GPIO number to a physical pin number.
+    ///
+    /// Returns `None` if the GPIO pin isn't exposed through the header.
+    pub fn to_physical(&self, bcm: u8) -> Option<u8> {
+        self.pins
+            .iter()
+            .find(|pin| pin.bcm() == Some(bcm))
+            .map(|pin| pin.physical)
+    }
+}
+
+// 26-pin header on the Raspberry Pi B Rev 1
+pub(crate) const HEADER_26_REV1: Header = Header {
+    pins: Cow::Borrowed(&[
+        HeaderPin::new(1, PinType::Power3v3, None),
+        HeaderPin::new(2, PinType::Power5v, None),
+        HeaderPin::new(3, PinType::Gpio(0), Some("SDA0")),
+        HeaderPin::new(4, PinType::Power5v, None),
+        HeaderPin::new(5, PinType::Gpio(1), Some("SCL0")),
+        HeaderPin::new(6, PinType::Ground, None),
+        HeaderPin::new(7, PinType::Gpio(4), Some("GPCLK0")),
+        HeaderPin::new(8, PinType::Gpio(14), Some("TXD")),
+        HeaderPin::new(9, PinType::Ground, None),
+        HeaderPin::new(10, PinType::Gpio(15), Some("RXD")),
+        HeaderPin::new(11, PinType::Gpio(17), None),
+        HeaderPin::new(12, PinType::Gpio(18), Some("PWM0")),
+        HeaderPin::new(13, PinType::Gpio(21), None),
+        HeaderPin::new(14, PinType::Ground, None),
+        HeaderPin::new(15, PinType::Gpio(22), None),
+        HeaderPin::new(16, PinType::Gpio(23), None),
+        HeaderPin::new(17, PinType::Power3v3, None),
+        HeaderPin::new(18, PinType::Gpio(24), None),
+        HeaderPin::new(19, PinType::Gpio(10), Some("SPI0 MOSI")),
+        HeaderPin::new(20, PinType::Ground, None),
+        HeaderPin::new(21, PinType::Gpio(9), Some("SPI0 MISO")),
+        HeaderPin::new(22, PinType::Gpio(25), None),
+        HeaderPin::new(23, PinType::Gpio(11), Some("SPI0 SCLK")),
+        HeaderPin::new(24, PinType::Gpio(8), Some("SPI0 CE0")),
+        HeaderPin::new(25, PinType::Ground, None),
+        HeaderPin::new(26, PinType::Gpio(7), Some("SPI0 CE1")),
+    ]),
+};
+
+// 26-pin header on the Raspberry Pi A and B Rev 2
+pub(crate) const HEADER_26: Header = Header {
+    pins: Cow::Borrowed(&[
+        HeaderPin::new(1, PinType::Power3v3, None),
+        HeaderPin::new(2, PinType::Power5v, None),
+        HeaderPin::new(3, PinType::Gpio(2), Some("SDA1")),
+        HeaderPin::new(4, PinType::Power5v, None),
+        HeaderPin::new(5, PinType::Gpio(3), Some("SCL1")),
+        HeaderPin::new(6, PinType::Ground, None),
+        HeaderPin::new(7, PinType::Gpio(4), Some("GPCLK0")),
+        HeaderPin::new(8, PinType::Gpio(14), Some("TXD")),
+        HeaderPin::new(9, PinType::Ground, None),
+        HeaderPin::new(10, PinType::Gpio(15), Some("RXD")),
+        HeaderPin::new(11, PinType::Gpio(17), None),
+        HeaderPin::new(12, PinType::Gpio(18), Some("PWM0")),
+        HeaderPin::new(13, PinType::Gpio(27), None),
+        HeaderPin::new(14, PinType::Ground, None),
+        HeaderPin::new(15, PinType::Gpio(22), None),
+        HeaderPin::new(16, PinType::Gpio(23), None),
+        HeaderPin::new(17, PinType::Power3v3, None),
+        HeaderPin::new(18, PinType::Gpio(24), None),
+        HeaderPin::new(19, PinType::Gpio(10), Some("SPI0 MOSI")),
+        HeaderPin::new(20, PinType::Ground, None),
+        HeaderPin::new(21, PinType::Gpio(9), Some("SPI0 MISO")),
+        HeaderPin::new(22, PinType::Gpio(25), None),
+        HeaderPin::new(23, PinType::Gpio(11), Some("SPI0 SCLK")),
+        HeaderPin::new(24, PinType::Gpio(8), Some("SPI0 CE0")),
+        HeaderPin::new(25, PinType::Ground, None),
+        HeaderPin::new(26, PinType::Gpio(7), Some("SPI0 CE1")),
+    ]),
+};
+
+// 40-pin header on all other models
+pub(crate) const HEADER_40: Header = Header {
+    pins: Cow::Borrowed(&[
+        HeaderPin::new(1, PinType::Power3v3, None),
+        HeaderPin::new(2, PinType::Power5v, None),
+        HeaderPin::new(3, PinType::Gpio(2), Some("SDA1")),
+        HeaderPin::new(4, PinType::Power5v, None),
+        HeaderPin::new(5, PinType::Gpio(3), Some("SCL1")),
+        HeaderPin::new(6, PinType::Ground, None),
+        HeaderPin::new(7, PinType::Gpio(4), Some("GPCLK0")),
+        HeaderPin::new(8, PinType::Gpio(14), Some("TXD")),
+        HeaderPin::new(9, PinType::Ground, None),
+        HeaderPin::new(10, PinType::Gpio(15), Some("RXD")),
+        HeaderPin::new(11, PinType::Gpio(17), Some("SPI1 CE1")),
+        HeaderPin::new(12, PinType::Gpio(18), Some("PWM0")),
+        HeaderPin::new(13, PinType::Gpio(27), None),
+        HeaderPin::new(14, PinType::Ground, None),
+        HeaderPin::new(15, PinType::Gpio(22), None),
+        HeaderPin::new(16, PinType::Gpio(23), None),
+        HeaderPin::new(17, PinType::Power3v3, None),
+        HeaderPin::new(18, PinType::Gpio(24), None),
+        HeaderPin::new(19, PinType::Gpio(10), Some("SPI0 MOSI")),
+        HeaderPin::new(20, PinType::Ground, None),
+        HeaderPin::new(21, PinType::Gpio(9), Some("SPI0 MISO")),
+        HeaderPin::new(22, PinType::Gpio(25), None),
+        HeaderPin::new(23, PinType::Gpio(11), Some("SPI0 SCLK")),
+        HeaderPin::new(24, PinType::Gpio(8), Some("SPI0 CE0")),
+        HeaderPin::new(25, PinType::Ground, None),
+        HeaderPin::new(26, PinType::Gpio(7), Some("SPI0 CE1")),
+        HeaderPin::new(27, PinType::Gpio(0), Some("ID_SD")),
+        HeaderPin::new(28, PinType::Gpio(1), Some("ID_SC")),
+        HeaderPin::new(29, PinType::Gpio(5), Some("GPCLK1")),
+        HeaderPin::new(30, PinType::Ground, None),
+        HeaderPin::new(31, PinType::Gpio(6), Some("GPCLK2")),
+        HeaderPin::new(32, PinType::Gpio(12), Some("PWM0")),
+        HeaderPin::new(33, PinType::Gpio(13), Some("PWM1")),
+        HeaderPin::new(34, PinType::Ground, None),
+        HeaderPin::new(35, PinType::Gpio(19), Some("SPI1 MISO")),
+        HeaderPin::new(36, PinType::Gpio(16), Some("SPI1 CE2")),
+        HeaderPin::new(37, PinType::Gpio(26), None),
+        HeaderPin::new(38, PinType::Gpio(20), Some("SPI1 MOSI")),
+        HeaderPin::new(39, PinType::Ground, None),
+        HeaderPin::new(40, PinType::Gpio(21), Some("SPI1 SCLK")),
+    ]),
+};
@@ -0,0 +1,163 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+
+use super::{Error, Result};
+
+const PATH_PROC_CPUINFO: &str = "/proc/cpuinfo";
+const PATH_DT_SERIAL_NUMBER: [&str; 2] = [
+    "/proc/device-tree/serial-number",
+    "/sys/firmware/devicetree/base/serial-number",
+];
+const PATH_SYSFS_ETH0_ADDRESS: &str = "/sys/class/net/eth0/address";
+const PATH_DT_ALIAS_ETHERNET0: [&str; 2] = [
+    "/proc/device-tree/aliases/ethernet0",
+    "/sys/firmware/devicetree/base/aliases/ethernet0",
+];
+const PATH_DT_BASE: [&str; 2] = ["/proc/device-tree", "/sys/firmware/devicetree/base"];
+
+/// Returns the Raspberry Pi's unique serial number.
+///
+/// The serial number is read from the `Serial` field in `/proc/cpuinfo`. If
+/// that field isn't available, the device tree's `serial-number` property is
+/// used instead.
+///
+/// The serial number is programmed into the SoC's OTP memory during
+/// manufacturing, and doesn't change when the SD card or operating system is
+/// replaced. Some Compute Module variants are shipped without a programmed
+/// serial number, in which case `Err(`[`Error::SerialNumberNotSet`]`)` is
+/// returned.
+///
+/// [`Error::SerialNumberNotSet`]: enum.Error.html#variant.SerialNumberNotSet
+pub fn serial_number() -> Result<u64> {
+    let serial = match cpuinfo_serial()? {
+        Some(serial) => serial,
+        None => dt_serial()?,
+    };
+
+    let serial = u64::from_str_radix(&serial, 16).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid serial number: {}", serial),
+        )
+    })?;
+
+    if serial == 0 {
+        return Err(Error::SerialNumberNotSet);
+    }
+
+    Ok(serial)
+}
+
+/// Returns the MAC address of the Raspberry Pi's onboard Ethernet interface.
+///
+/// The MAC address is read from `/sys/class/net/eth0/address`. If `eth0`
+/// doesn't exist, for instance because it's been renamed or its driver isn't
+/// loaded, the `local-mac-address` property of the device tree node referenced
+/// by the `ethernet0` alias is used instead.
+///
+/// Returns an [`Error::Io`] with kind `NotFound` on models without onboard
+/// Ethernet.
+///
+/// [`Error::Io`]: enum.Error.html#variant.Io
+pub fn eth_mac() -> Result<[u8; 6]> {
+    match fs::read_to_string(PATH_SYSFS_ETH0_ADDRESS) {
+        Ok(address) => return parse_mac(address.trim()),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    dt_mac()
+}
+
+// Returns the Serial field in /proc/cpuinfo, if present
+fn cpuinfo_serial() -> Result<Option<String>> {
+    let proc_cpuinfo = BufReader::new(File::open(PATH_PROC_CPUINFO)?);
+
+    for line in proc_cpuinfo.lines() {
+        if let Some(line_value) = line?.strip_prefix("Serial") {
+            if let Some(serial) = line_value.trim_start().strip_prefix(':') {
+                return Ok(Some(serial.trim().to_owned()));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+// Device tree string properties are NUL-terminated
+fn dt_serial() -> Result<String> {
+    let mut last_err = io::Error::from(io::ErrorKind::NotFound);
+
+    for path in PATH_DT_SERIAL_NUMBER.iter() {
+        match fs::read(path) {
+            Ok(serial) => {
+                return Ok(String::from_utf8_lossy(&serial)
+                    .trim_end_matches('\0')
+                    .trim()
+                    .to_owned())
+            }
+            Err(e) => last_err = e,
+        }
+    }
+
+    Err(last_err.into())
+}
+
+// The ethernet0 alias contains the full path of the Ethernet controller's node
+fn dt_mac() -> Result<[u8; 6]> {
+    let mut last_err = io::Error::from(io::ErrorKind::NotFound);
+
+    for (alias, base) in PATH_DT_ALIAS_ETHERNET0.iter().zip(PATH_DT_BASE.iter()) {
+        let node = match fs::read(alias) {
+            Ok(node) => node,
+            Err(e) => {
+                last_err = e;
+                continue;
+            }
+        };
+
+        let node = String::from_utf8_lossy(&node);
+        let address = fs::read(format!(
+            "{}{}/local-mac-address",
+            base,
+            node.trim_end_matches('\0')
+        ))?;
+
+        if address.len() != 6 {
+            return Err(
+                io::Error::new(io::ErrorKind::InvalidData, "invalid local-mac-address").into(),
+            );
+        }
+
+        let mut mac = [0u8; 6];
+        mac.copy_from_slice(&address);
+
+        return Ok(mac);
+    }
+
+    Err(last_err.into())
+}
+
+// Parses a MAC address formatted as six colon-separated hexadecimal octets
+fn parse_mac(address: &str) -> Result<[u8; 6]> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid MAC address: {}", address),
+        )
+    };
+
+    let mut mac = [0u8; 6];
+    let mut octets = address.split(':');
+
+    for octet in mac.iter_mut() {
+        let value = octets.next().ok_or_else(invalid)?;
+        *octet = u8::from_str_radix(value, 16).map_err(|_| invalid())?;
+    }
+
+    if octets.next().is_some() {
+        return Err(invalid().into());
+    }
+
+    Ok(mac)
+}
@@ -0,0 +1,168 @@
+//! Safe wrappers for the VideoCore mailbox property interface.
+//!
+//! The VideoCore firmware provides information about the SoC's clocks,
+//! voltages and temperature through a set of mailbox properties, which can
+//! be requested through `/dev/vcio`. This is the same interface `vcgencmd`
+//! uses for most of its queries. Accessing `/dev/vcio` requires membership of
+//! the `video` group.
+//!
+//! For the most common values, [`Status`] prefers the equivalent sysfs files
+//! when they're available, and only falls back to the mailbox interface
+//! when they're not.
+//!
+//! [`Status`]: ../struct.Status.html
+
+#![allow(clippy::unnecessary_cast)]
+
+use std::fs::OpenOptions;
+use std::io;
+use std::mem;
+use std::os::unix::io::AsRawFd;
+
+use libc::{self, c_void};
+
+use super::Result;
+
+#[cfg(target_env = "gnu")]
+type IoctlLong = libc::c_ulong;
+#[cfg(target_env = "musl")]
+type IoctlLong = libc::c_int;
+
+// Based on the VideoCore mailbox property interface documentation at
+// https://github.com/raspberrypi/firmware/wiki/Mailbox-property-interface
+
+const PATH_VCIO: &str = "/dev/vcio";
+
+// _IOWR(100, 0, char *)
+const NRSHIFT: u8 = 0;
+const TYPESHIFT: u8 = 8;
+const SIZESHIFT: u8 = 16;
+const DIRSHIFT: u8 = 30;
+const DIR_READ_WRITE: IoctlLong = 3 << DIRSHIFT;
+const TYPE_MBOX: IoctlLong = 100 << TYPESHIFT;
+const NR_PROPERTY: IoctlLong = 0 << NRSHIFT;
+const SIZE_PROPERTY: IoctlLong = (mem::size_of::<*mut c_void>() as IoctlLong) << SIZESHIFT;
+const REQ_PROPERTY: IoctlLong = DIR_READ_WRITE | TYPE_MBOX | NR_PROPERTY | SIZE_PROPERTY;
+
+const CODE_REQUEST: u32 = 0x0000_0000;
+const CODE_RESPONSE_SUCCESS: u32 = 0x8000_0000;
+const TAG_END: u32 = 0x0000_0000;
+const TAG_RESPONSE: u32 = 0x8000_0000;
+
+const TAG_GET_FIRMWARE_REVISION: u32 = 0x0000_0001;
+const TAG_GET_CLOCK_RATE: u32 = 0x0003_0002;
+const TAG_GET_VOLTAGE: u32 = 0x0003_0003;
+const TAG_GET_TEMPERATURE: u32 = 0x0003_0006;
+const TAG_GET_THROTTLED: u32 = 0x0003_0046;
+
+// Maximum number of words in a property buffer, including the header and
+// end tag
+const BUFFER_LEN: usize = 32;
+
+// The property interface requires a 16-byte aligned buffer
+#[repr(C, align(16))]
+struct PropertyBuffer([u32; BUFFER_LEN]);
+
+/// Clocks managed by the VideoCore firmware.
+///
+/// Not every clock is available on every model.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[non_exhaustive]
+pub enum ClockId {
+    Emmc = 1,
+    Uart = 2,
+    Arm = 3,
+    Core = 4,
+    V3d = 5,
+    H264 = 6,
+    Isp = 7,
+    Sdram = 8,
+    Pixel = 9,
+    Pwm = 10,
+    Hevc = 11,
+    Emmc2 = 12,
+    M2mc = 13,
+    PixelBvb = 14,
+}
+
+/// Voltage domains managed by the VideoCore firmware.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[non_exhaustive]
+pub enum VoltageId {
+    Core = 1,
+    SdramC = 2,
+    SdramP = 3,
+    SdramI = 4,
+}
+
+// Sends a single property tag to the VideoCore firmware, and returns the
+// response values. `request` is padded with zeroes up to `response_len`
+// values, which is the size of the value buffer.
+fn property(tag: u32, request: &[u32], response_len: usize) -> io::Result<Vec<u32>> {
+    let value_len = response_len.max(request.len());
+
+    if value_len + 6 > BUFFER_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "mailbox property value buffer too large",
+        ));
+    }
+
+    // Buffer size, request code, tag, value buffer size, request/response
+    // size, value buffer, end tag
+    let mut buffer = PropertyBuffer([0; BUFFER_LEN]);
+    buffer.0[0] = ((value_len + 6) * mem::size_of::<u32>()) as u32;
+    buffer.0[1] = CODE_REQUEST;
+    buffer.0[2] = tag;
+    buffer.0[3] = (value_len * mem::size_of::<u32>()) as u32;
+    buffer.0[4] = mem::size_of_val(request) as u32;
+    buffer.0[5..5 + request.len()].copy_from_slice(request);
+    buffer.0[5 + value_len] = TAG_END;
+
+    let vcio = OpenOptions::new().read(true).write(true).open(PATH_VCIO)?;
+
+    parse_retval!(unsafe { libc::ioctl(vcio.as_raw_fd(), REQ_PROPERTY, buffer.0.as_mut_ptr()) })?;
+
+    if buffer.0[1] != CODE_RESPONSE_SUCCESS || (buffer.0[4] & TAG_RESPONSE) == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "mailbox property request failed",
+        ));
+    }
+
+    Ok(buffer.0[5..5 + response_len].to_vec())
+}
+
+/// Returns the current rate of the specified clock in hertz (Hz).
+///
+/// Returns 0 if the clock doesn't exist or isn't enabled.
+pub fn get_clock_rate(clock_id: ClockId) -> Result<u32> {
+    Ok(property(TAG_GET_CLOCK_RATE, &[clock_id as u32], 2)?[1])
+}
+
+/// Returns the SoC temperature in degrees Celsius (°C).
+pub fn get_temperature() -> Result<f32> {
+    let millidegrees = property(TAG_GET_TEMPERATURE, &[0], 2)?[1];
+
+    Ok(millidegrees as f32 / 1000.0)
+}
+
+/// Returns the VideoCore firmware revision, which is the firmware's build
+/// date as a Unix timestamp.
+pub fn get_firmware_revision() -> Result<u32> {
+    Ok(property(TAG_GET_FIRMWARE_REVISION, &[], 1)?[0])
+}
+
+/// Returns the current voltage of the specified voltage domain in volts (V).
+pub fn get_voltage(voltage_id: VoltageId) -> Result<f32> {
+    // The voltage is reported in microvolts
+    let microvolts = property(TAG_GET_VOLTAGE, &[voltage_id as u32], 2)?[1];
+
+    Ok(microvolts as f32 / 1_000_000.0)
+}
+
+// Returns the throttled state bitfield. A request value of 0 prevents the
+// firmware from clearing any of the sticky bits.
+pub(crate) fn throttled() -> io::Result<u32> {
+    Ok(property(TAG_GET_THROTTLED, &[0], 1)?[0])
+}
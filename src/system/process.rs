@@ -0,0 +1,88 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use super::Result;
+
+const PATH_PROC: &str = "/proc";
+
+/// Information about a process.
+///
+/// A list of processes that have a device node opened is returned by
+/// [`find_device_users`].
+///
+/// [`find_device_users`]: fn.find_device_users.html
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ProcessInfo {
+    pid: u32,
+    name: String,
+}
+
+impl ProcessInfo {
+    /// Returns the process ID.
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the command name, as reported by `/proc/<pid>/comm`.
+    ///
+    /// The kernel truncates command names to 15 characters.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for ProcessInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (PID {})", self.name, self.pid)
+    }
+}
+
+/// Returns a list of processes that currently have the specified device node
+/// opened, sorted by process ID.
+///
+/// `find_device_users` scans the open file descriptors in `/proc/<pid>/fd`
+/// of every running process, and compares their targets to `path`. Symbolic
+/// links, such as `/dev/serial0`, are resolved first.
+///
+/// The file descriptors of processes owned by other users can only be
+/// inspected with superuser privileges. Those processes are silently
+/// skipped, so the returned list may be incomplete when running as a
+/// regular user.
+///
+/// This is useful to find out which process is responsible when opening a
+/// peripheral fails because it's busy.
+pub fn find_device_users<P: AsRef<Path>>(path: P) -> Result<Vec<ProcessInfo>> {
+    let path = fs::canonicalize(path)?;
+    let own_pid = std::process::id();
+    let mut users = Vec::new();
+
+    for entry in fs::read_dir(PATH_PROC)?.flatten() {
+        let pid = match entry.file_name().to_string_lossy().parse::<u32>() {
+            Ok(pid) if pid != own_pid => pid,
+            _ => continue,
+        };
+
+        // Processes may exit, or deny access, while we're scanning them
+        let fds = match fs::read_dir(entry.path().join("fd")) {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+
+        let has_device_open = fds
+            .flatten()
+            .any(|fd| fs::read_link(fd.path()).map_or(false, |target| target == path));
+
+        if has_device_open {
+            let name = fs::read_to_string(entry.path().join("comm"))
+                .map(|name| name.trim_end().to_owned())
+                .unwrap_or_default();
+
+            users.push(ProcessInfo { pid, name });
+        }
+    }
+
+    users.sort_by_key(|info| info.pid);
+
+    Ok(users)
+}
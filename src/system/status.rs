@@ -0,0 +1,213 @@
+use std::fs;
+use std::io;
+use std::num::ParseIntError;
+
+use super::mailbox::{self, ClockId};
+use super::Result;
+
+const PATH_THERMAL_ZONE: &str = "/sys/class/thermal/thermal_zone0/temp";
+const PATH_CPUFREQ: &str = "/sys/devices/system/cpu/cpu0/cpufreq/scaling_cur_freq";
+const PATH_CLK_CORE: [&str; 2] = [
+    "/sys/kernel/debug/clk/vpu/clk_rate",
+    "/sys/kernel/debug/clk/core/clk_rate",
+];
+const PATH_GET_THROTTLED: &str = "/sys/devices/platform/soc/soc:firmware/get_throttled";
+
+// get_throttled bit fields
+const THROTTLED_UNDER_VOLTAGE: u32 = 1 << 0;
+const THROTTLED_FREQUENCY_CAPPED: u32 = 1 << 1;
+const THROTTLED_THROTTLED: u32 = 1 << 2;
+const THROTTLED_SOFT_TEMP_LIMIT: u32 = 1 << 3;
+const THROTTLED_OCCURRED_SHIFT: u32 = 16;
+
+/// Decoded throttle state, as reported by the VideoCore firmware.
+///
+/// The `*_occurred` flags are sticky, and indicate whether the condition has
+/// occurred at any point since the Raspberry Pi was booted.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ThrottleStatus {
+    bits: u32,
+}
+
+impl ThrottleStatus {
+    fn is_set(self, flag: u32) -> bool {
+        (self.bits & flag) > 0
+    }
+
+    /// Returns `true` if under-voltage is currently detected.
+    pub fn under_voltage(self) -> bool {
+        self.is_set(THROTTLED_UNDER_VOLTAGE)
+    }
+
+    /// Returns `true` if the ARM frequency is currently capped.
+    pub fn frequency_capped(self) -> bool {
+        self.is_set(THROTTLED_FREQUENCY_CAPPED)
+    }
+
+    /// Returns `true` if the SoC is currently throttled.
+    pub fn throttled(self) -> bool {
+        self.is_set(THROTTLED_THROTTLED)
+    }
+
+    /// Returns `true` if the soft temperature limit is currently active.
+    pub fn soft_temp_limit(self) -> bool {
+        self.is_set(THROTTLED_SOFT_TEMP_LIMIT)
+    }
+
+    /// Returns `true` if under-voltage has occurred since boot.
+    pub fn under_voltage_occurred(self) -> bool {
+        self.is_set(THROTTLED_UNDER_VOLTAGE << THROTTLED_OCCURRED_SHIFT)
+    }
+
+    /// Returns `true` if the ARM frequency has been capped since boot.
+    pub fn frequency_capped_occurred(self) -> bool {
+        self.is_set(THROTTLED_FREQUENCY_CAPPED << THROTTLED_OCCURRED_SHIFT)
+    }
+
+    /// Returns `true` if the SoC has been throttled since boot.
+    pub fn throttled_occurred(self) -> bool {
+        self.is_set(THROTTLED_THROTTLED << THROTTLED_OCCURRED_SHIFT)
+    }
+
+    /// Returns `true` if the soft temperature limit has been active since boot.
+    pub fn soft_temp_limit_occurred(self) -> bool {
+        self.is_set(THROTTLED_SOFT_TEMP_LIMIT << THROTTLED_OCCURRED_SHIFT)
+    }
+
+    /// Returns the raw `get_throttled` bit field.
+    pub fn bits(self) -> u32 {
+        self.bits
+    }
+}
+
+/// Returns the current frequency of the specified clock in hertz (Hz).
+///
+/// The ARM clock is read from
+/// `/sys/devices/system/cpu/cpu0/cpufreq/scaling_cur_freq` when available.
+/// All other clocks, and the ARM clock on systems without `cpufreq`, are
+/// requested from the VideoCore firmware through [`mailbox::get_clock_rate`].
+/// The core clock falls back to the clock tree in debugfs if the firmware
+/// can't be reached. Not every clock is available on every model.
+///
+/// Clock frequencies depend on the model and firmware settings such as
+/// `arm_freq`, `core_freq` and `force_turbo` in `/boot/config.txt`, and
+/// may change at runtime due to dynamic frequency scaling. Timing
+/// calculations based on the returned value, such as the delays used when
+/// bit-banging a protocol, only apply to the current clock frequency.
+///
+/// [`mailbox::get_clock_rate`]: mailbox/fn.get_clock_rate.html
+pub fn clock_frequency(clock: ClockId) -> Result<u32> {
+    match clock {
+        ClockId::Arm => Status::new().arm_clock(),
+        ClockId::Core => Status::new().core_clock(),
+        clock => mailbox::get_clock_rate(clock),
+    }
+}
+
+/// Provides access to the SoC's temperature, clock speeds and throttle state.
+///
+/// Values are retrieved through the sysfs interface when available, and
+/// otherwise requested from the VideoCore firmware through `/dev/vcio`, which
+/// requires membership of the `video` group. On systems where neither is
+/// available, the methods return an [`Error::Io`].
+///
+/// [`Error::Io`]: enum.Error.html#variant.Io
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct Status;
+
+impl Status {
+    /// Constructs a new `Status`.
+    pub fn new() -> Status {
+        Status
+    }
+
+    /// Returns the CPU temperature in degrees Celsius (°C).
+    pub fn cpu_temperature(&self) -> Result<f32> {
+        match read_millidegrees(PATH_THERMAL_ZONE) {
+            Ok(millidegrees) => Ok(millidegrees as f32 / 1000.0),
+            Err(_) => mailbox::get_temperature(),
+        }
+    }
+
+    /// Returns the current ARM clock speed in hertz (Hz).
+    pub fn arm_clock(&self) -> Result<u32> {
+        match read_value(PATH_CPUFREQ, 10) {
+            // scaling_cur_freq is reported in kHz
+            Ok(khz) => Ok((khz * 1000) as u32),
+            Err(_) => mailbox::get_clock_rate(ClockId::Arm),
+        }
+    }
+
+    /// Returns the current core (VPU) clock speed in hertz (Hz).
+    ///
+    /// The core clock drives the mini UART, so its baud rate depends on this
+    /// value.
+    pub fn core_clock(&self) -> Result<u32> {
+        if let Ok(rate) = mailbox::get_clock_rate(ClockId::Core) {
+            return Ok(rate);
+        }
+
+        // The clock tree in debugfs is usually only accessible as root
+        for path in PATH_CLK_CORE.iter() {
+            if let Ok(rate) = read_value(path, 10) {
+                return Ok(rate as u32);
+            }
+        }
+
+        Err(io::Error::new(io::ErrorKind::NotFound, "core clock not available").into())
+    }
+
+    /// Returns the throttle state.
+    pub fn throttle_status(&self) -> Result<ThrottleStatus> {
+        let bits = match read_value(PATH_GET_THROTTLED, 16) {
+            Ok(bits) => bits as u32,
+            Err(_) => mailbox::throttled()?,
+        };
+
+        Ok(ThrottleStatus { bits })
+    }
+}
+
+// Reads a temperature in millidegrees Celsius. Unlike the other values, it's
+// signed, since thermal zones report negative temperatures when it's cold
+// enough.
+fn read_millidegrees(path: &str) -> io::Result<i64> {
+    fs::read_to_string(path)?
+        .trim()
+        .parse()
+        .map_err(|e: ParseIntError| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+fn read_value(path: &str, radix: u32) -> io::Result<u64> {
+    let value = fs::read_to_string(path)?;
+    let value = value.trim();
+    let value = value.strip_prefix("0x").unwrap_or(value);
+
+    u64::from_str_radix(value, radix)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_temperature() {
+        let path = std::env::temp_dir().join(format!("rppal-thermal-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        fs::write(path, "-5250\n").unwrap();
+        assert_eq!(read_millidegrees(path).unwrap(), -5250);
+
+        fs::write(path, "48312\n").unwrap();
+        assert_eq!(read_millidegrees(path).unwrap(), 48312);
+
+        fs::write(path, "invalid\n").unwrap();
+        assert_eq!(
+            read_millidegrees(path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+}
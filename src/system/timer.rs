@@ -0,0 +1,169 @@
+use std::fmt;
+use std::fs::OpenOptions;
+use std::mem::MaybeUninit;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::sync::Once;
+use std::time::Instant;
+
+use libc::{off_t, MAP_FAILED, MAP_SHARED, O_SYNC, PROT_READ};
+
+use crate::system::{DeviceInfo, SoC};
+
+const PATH_DEV_MEM: &str = "/dev/mem";
+// Offset from the peripheral base address (BCM2835 ARM Peripherals @ 12.1)
+const SYSTEM_TIMER_OFFSET: u64 = 0x3000;
+// The timer registers fit within a single page
+const SYSTEM_TIMER_SIZE: usize = 4096;
+// Counter lower and higher 32 bits
+const CLO: usize = 0x04 / std::mem::size_of::<u32>();
+const CHI: usize = 0x08 / std::mem::size_of::<u32>();
+
+/// Time sources used by [`system_timer`].
+///
+/// [`system_timer`]: fn.system_timer.html
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum TimerSource {
+    /// The SoC's memory-mapped 1 MHz system timer counter.
+    SystemTimer,
+    /// The kernel's monotonic clock, through [`Instant`].
+    ///
+    /// [`Instant`]: https://doc.rust-lang.org/std/time/struct.Instant.html
+    Monotonic,
+}
+
+impl fmt::Display for TimerSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TimerSource::SystemTimer => write!(f, "System timer"),
+            TimerSource::Monotonic => write!(f, "Monotonic"),
+        }
+    }
+}
+
+struct TimerState {
+    // Memory-mapped system timer registers, or null if unavailable
+    registers: *const u32,
+    // Reference point for the Instant fallback
+    epoch: Instant,
+}
+
+// Lazily maps the system timer registers, which stay mapped until the
+// process exits.
+fn state() -> &'static TimerState {
+    static mut STATE: MaybeUninit<TimerState> = MaybeUninit::uninit();
+    static ONCE: Once = Once::new();
+
+    // call_once is thread-safe, and STATE is never written to again
+    unsafe {
+        ONCE.call_once(|| {
+            (*ptr::addr_of_mut!(STATE)).write(TimerState {
+                registers: map_registers().unwrap_or(ptr::null()),
+                epoch: Instant::now(),
+            });
+        });
+
+        (*ptr::addr_of!(STATE)).assume_init_ref()
+    }
+}
+
+fn map_registers() -> Option<*const u32> {
+    let device_info = DeviceInfo::new().ok()?;
+
+    // On the Pi 5, the peripheral base points to the RP1, which doesn't
+    // contain the BCM2712's system timer
+    if device_info.soc() == SoC::Bcm2712 {
+        return None;
+    }
+
+    let mem_file = OpenOptions::new()
+        .read(true)
+        .custom_flags(O_SYNC)
+        .open(PATH_DEV_MEM)
+        .ok()?;
+
+    let mem_ptr = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            SYSTEM_TIMER_SIZE,
+            PROT_READ,
+            MAP_SHARED,
+            mem_file.as_raw_fd(),
+            (device_info.peripheral_base() + SYSTEM_TIMER_OFFSET) as off_t,
+        )
+    };
+
+    if mem_ptr == MAP_FAILED {
+        log_debug!(
+            "Failed to map system timer: {}",
+            std::io::Error::last_os_error()
+        );
+
+        return None;
+    }
+
+    Some(mem_ptr as *const u32)
+}
+
+/// Returns the value of a free-running microsecond counter.
+///
+/// On the Raspberry Pi 4 B and earlier models, `system_timer` reads the
+/// SoC's 1 MHz system timer, which keeps counting independently of the
+/// scheduler and CPU frequency scaling. This provides a hardware timebase
+/// for precise timestamps, and for measuring the timing accuracy of
+/// bit-banged protocols. Each call is a memory-mapped register read, without
+/// a system call.
+///
+/// The system timer registers are only accessible through `/dev/mem`, which
+/// requires superuser privileges. The registers are mapped on first use.
+/// If they can't be mapped, or on the Raspberry Pi 5, where the system
+/// timer is located at a different address than the RP1 peripherals,
+/// `system_timer` falls back to the number of microseconds elapsed since
+/// the first call, measured with [`Instant`]. Use [`system_timer_source`]
+/// to check which source is used.
+///
+/// The counter wraps around after 2<sup>64</sup> microseconds. Only the
+/// difference between two values is meaningful.
+///
+/// ```no_run
+/// use rppal::system::system_timer;
+///
+/// let start = system_timer();
+/// // Bit-bang a protocol
+/// println!("Elapsed: {} µs", system_timer().wrapping_sub(start));
+/// ```
+///
+/// [`Instant`]: https://doc.rust-lang.org/std/time/struct.Instant.html
+/// [`system_timer_source`]: fn.system_timer_source.html
+pub fn system_timer() -> u64 {
+    let state = state();
+
+    if state.registers.is_null() {
+        return state.epoch.elapsed().as_micros() as u64;
+    }
+
+    let read = |offset: usize| unsafe { ptr::read_volatile(state.registers.add(offset)) };
+
+    // CLO and CHI can't be read atomically. Retry if CLO wrapped around in
+    // between reads
+    loop {
+        let high = read(CHI);
+        let low = read(CLO);
+
+        if read(CHI) == high {
+            return (u64::from(high) << 32) | u64::from(low);
+        }
+    }
+}
+
+/// Returns the time source used by [`system_timer`].
+///
+/// [`system_timer`]: fn.system_timer.html
+pub fn system_timer_source() -> TimerSource {
+    if state().registers.is_null() {
+        TimerSource::Monotonic
+    } else {
+        TimerSource::SystemTimer
+    }
+}
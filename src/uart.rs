@@ -20,6 +20,11 @@
 //! limitations of `/dev/ttyS0` and the requirement for a fixed core frequency,
 //! in most cases you'll want to use `/dev/ttyAMA0` for serial communication.
 //!
+//! [`Uart::new_pl011`] and [`Uart::new_mini`] explicitly select either
+//! peripheral, while [`Uart::peripheral`] reports which one is in use. When
+//! the mini UART is selected, any settings it doesn't support are logged as
+//! a warning if the optional `log` feature is enabled.
+//!
 //! By default, TX (outgoing data) is tied to BCM GPIO 14 (physical pin 8) and
 //! RX (incoming data) is tied to BCM GPIO 15 (physical pin 10). You can move
 //! these lines to different GPIO pins using the `uart0` and `uart1` overlays,
@@ -62,6 +67,10 @@
 //! Add the line `enable_uart=1` to `/boot/config.txt` to enable serial
 //! communication on `/dev/ttyS0`, which also sets a fixed core frequency.
 //!
+//! The baud rate of the mini UART is derived from the core clock, so any
+//! change in core clock speed will affect the baud rate. You can verify the
+//! current core clock speed with [`Status::core_clock`].
+//!
 //! Remember to reboot the Raspberry Pi after making any changes.
 //!
 //! ## USB to serial adapters
@@ -115,20 +124,51 @@
 //! from resetting the pins. You can catch those using crates such as
 //! [`simple_signal`].
 //!
+//! ## RS-485
+//!
+//! RS-485 transceivers typically require a driver-enable (DE) signal to be
+//! asserted while data is being transmitted, and released immediately after
+//! the last stop bit has been sent, so the external device can respond.
+//!
+//! If the serial driver supports it, [`set_rs485`] configures the kernel to
+//! automatically use RTS as the DE signal, with optional delays before and
+//! after transmission. Otherwise, [`set_rs485_with_pin`] can be used to drive
+//! DE through any available GPIO pin. In that case, DE is controlled in
+//! software by [`write`], which blocks until all data in the output queue has
+//! been transmitted before releasing DE. Because the timing depends on the
+//! scheduler, the kernel implementation results in a shorter and more
+//! predictable turnaround time.
+//!
 //! ## Troubleshooting
 //!
+//! ### Not enabled
+//!
+//! If [`new`] or [`with_path`] returns [`Error::NotEnabled`], the serial
+//! device doesn't exist. The error's `hint` field suggests which
+//! `config.txt` setting is missing, based on the current configuration.
+//! Changes to `config.txt` only take effect after a reboot.
+//!
 //! ### Permission denied
 //!
-//! If [`new`] or [`with_path`] returns an `io::ErrorKind::PermissionDenied`
-//! error, make sure the file permissions for the specified device are correct,
-//! and the current user is a member of the group that owns the device, which is
-//! usually either `dialout` or `tty`.
+//! If [`new`] or [`with_path`] returns [`Error::PermissionDenied`], make sure
+//! the file permissions for the specified device are correct, and the current
+//! user is a member of the group that owns the device, which is usually either
+//! `dialout` or `tty`.
 //!
 //! [documentation]: https://www.raspberrypi.org/documentation/configuration/uart.md
 //! [`simple_signal`]: https://crates.io/crates/simple-signal
 //! [`Uart`]: struct.Uart.html
 //! [`new`]: struct.Uart.html#method.new
 //! [`with_path`]: struct.Uart.html#method.with_path
+//! [`Error::NotEnabled`]: enum.Error.html#variant.NotEnabled
+//! [`Error::PermissionDenied`]: enum.Error.html#variant.PermissionDenied
+//! [`set_rs485`]: struct.Uart.html#method.set_rs485
+//! [`set_rs485_with_pin`]: struct.Uart.html#method.set_rs485_with_pin
+//! [`write`]: struct.Uart.html#method.write
+//! [`Status::core_clock`]: ../system/struct.Status.html#method.core_clock
+//! [`Uart::new_pl011`]: struct.Uart.html#method.new_pl011
+//! [`Uart::new_mini`]: struct.Uart.html#method.new_mini
+//! [`Uart::peripheral`]: struct.Uart.html#method.peripheral
 
 use std::error;
 use std::fmt;
@@ -139,18 +179,35 @@ use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::path::Path;
 use std::result;
-use std::time::Duration;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use libc::{c_int, O_NOCTTY, O_NONBLOCK};
 use libc::{TIOCM_CAR, TIOCM_CTS, TIOCM_DSR, TIOCM_DTR, TIOCM_RNG, TIOCM_RTS};
 
-use crate::gpio::{self, Gpio, IoPin, Mode};
+use crate::gpio::{self, Gpio, IoPin, Mode, OutputPin};
+use crate::retry::RetryPolicy;
 use crate::system::{self, DeviceInfo, Model};
 
-#[cfg(feature = "hal")]
+mod config;
+#[cfg(any(feature = "hal-0", feature = "hal-1"))]
 mod hal;
 mod termios;
 
+pub use self::config::UartConfig;
+
+// Self-test pattern containing alternating and walking bits
+const SELF_TEST_PATTERN: [u8; 12] = [
+    0x55, 0xAA, 0x00, 0xFF, 0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80,
+];
+
+// Maximum fraction of framing and parity errors in a response accepted by
+// detect_baud
+const DETECT_BAUD_MAX_ERROR_RATIO: f64 = 0.1;
+
+// Maximum number of bytes read_line stores while waiting for a delimiter
+const LINE_BUFFER_MAX: usize = 4096;
+
 const GPIO_RTS: u8 = 17;
 const GPIO_CTS: u8 = 16;
 
@@ -164,7 +221,15 @@ const GPIO_RTS_MODE_UART1: Mode = Mode::Alt5;
 const GPIO_CTS_MODE_UART1: Mode = Mode::Alt5;
 
 /// Errors that can occur when accessing the UART peripheral.
+///
+/// [`Gpio`] is displayed without the underlying [`gpio::Error`], which is
+/// available through [`source`] instead.
+///
+/// [`Gpio`]: #variant.Gpio
+/// [`gpio::Error`]: ../gpio/enum.Error.html
+/// [`source`]: https://doc.rust-lang.org/std/error/trait.Error.html#method.source
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// I/O error.
     Io(io::Error),
@@ -172,22 +237,119 @@ pub enum Error {
     Gpio(gpio::Error),
     /// Invalid or unsupported value.
     InvalidValue,
+    /// Feature not supported by the serial driver.
+    FeatureNotSupported,
+    /// Timed out.
+    ///
+    /// [`read_line`] didn't receive a complete line before the timeout
+    /// duration elapsed. Any partially received data is kept for the next
+    /// call to [`read_line`].
+    ///
+    /// [`read_line`]: struct.Uart.html#method.read_line
+    Timeout,
+    /// Line too long.
+    ///
+    /// [`read_line`] received more data than fits in its internal buffer
+    /// without finding the delimiter. The buffered data is discarded.
+    ///
+    /// [`read_line`]: struct.Uart.html#method.read_line
+    LineTooLong,
+    /// UART not enabled.
+    ///
+    /// The serial device at `path` doesn't exist, because the UART hasn't
+    /// been enabled in the firmware configuration, or the change hasn't taken
+    /// effect yet. `hint` describes how to enable it, based on the contents of
+    /// `config.txt`.
+    ///
+    /// More information can be found [here].
+    ///
+    /// [here]: index.html#not-enabled
+    NotEnabled { path: String, hint: String },
+    /// Permission denied.
+    ///
+    /// The current user doesn't have read/write access to the serial device.
+    /// `path` contains the path that couldn't be accessed, `required_group` the
+    /// group that conventionally grants access to it (`dialout`), and `hint`
+    /// describes how to resolve the issue.
+    ///
+    /// More information on possible causes for this error can be found [here].
+    ///
+    /// [here]: index.html#permission-denied
+    PermissionDenied {
+        path: String,
+        required_group: Option<&'static str>,
+        hint: String,
+    },
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Error::Io(ref err) => write!(f, "I/O error: {}", err),
-            Error::Gpio(ref err) => write!(f, "GPIO error: {}", err),
+            Error::Gpio(_) => write!(f, "GPIO error"),
             Error::InvalidValue => write!(f, "Invalid or unsupported value"),
+            Error::FeatureNotSupported => write!(f, "Feature not supported"),
+            Error::Timeout => write!(f, "Timed out"),
+            Error::LineTooLong => write!(f, "Line too long"),
+            Error::NotEnabled { ref path, ref hint } => {
+                write!(f, "UART not enabled: {} ({})", path, hint)
+            }
+            Error::PermissionDenied {
+                ref path, ref hint, ..
+            } => write!(f, "Permission denied: {} ({})", path, hint),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => err.source(),
+            Error::Gpio(ref err) => Some(err),
+            _ => None,
         }
     }
 }
 
-impl error::Error for Error {}
+impl Error {
+    // Converts an I/O error that occurred while accessing path, and classifies
+    // any missing interface and permission errors.
+    fn with_path(err: io::Error, path: &str) -> Error {
+        if let Some(missing) = crate::sys::not_enabled(&err, path) {
+            return Error::NotEnabled {
+                path: missing.path,
+                hint: missing.hint,
+            };
+        }
+
+        match crate::sys::permission_denied(&err, path) {
+            Some(denied) => Error::PermissionDenied {
+                path: denied.path,
+                required_group: denied.required_group,
+                hint: denied.hint,
+            },
+            None => Error::from(crate::sys::io_error_with_path(err, path)),
+        }
+    }
+
+    // Converts an I/O error returned by an ioctl on fd, and classifies any
+    // permission errors.
+    pub(crate) fn with_fd(err: io::Error, fd: RawFd) -> Error {
+        match crate::sys::permission_denied_fd(&err, fd) {
+            Some(denied) => Error::PermissionDenied {
+                path: denied.path,
+                required_group: denied.required_group,
+                hint: denied.hint,
+            },
+            None => Error::from(err),
+        }
+    }
+}
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Error {
+        log_debug!("I/O error: {}", err);
+
         Error::Io(err)
     }
 }
@@ -214,7 +376,12 @@ pub type Result<T> = result::Result<T, Error>;
 /// `None` omits the parity bit. `Even` and `Odd` count the total number of
 /// 1-bits in the data bits. `Mark` and `Space` always set the parity
 /// bit to `1` or `0` respectively.
+///
+/// With the optional `serde` feature enabled, `Parity` is (de)serialized as
+/// `"none"`, `"even"`, `"odd"`, `"mark"` or `"space"`.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum Parity {
     /// No parity bit.
     None,
@@ -243,7 +410,12 @@ impl fmt::Display for Parity {
 /// Parity check modes.
 ///
 /// The parity check mode determines how parity errors are handled.
+///
+/// With the optional `serde` feature enabled, `ParityCheck` is (de)serialized
+/// as `"none"`, `"strip"`, `"replace"` or `"mark"`.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum ParityCheck {
     /// Ignores parity errors.
     None,
@@ -290,6 +462,81 @@ impl fmt::Display for Queue {
     }
 }
 
+/// UART peripheral types.
+///
+/// Returned by [`Uart::peripheral`].
+///
+/// [`Uart::peripheral`]: struct.Uart.html#method.peripheral
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[non_exhaustive]
+pub enum Peripheral {
+    /// PL011 UART (`/dev/ttyAMAx`).
+    Pl011,
+    /// Mini UART (`/dev/ttyS0`).
+    ///
+    /// The mini UART's baud rate is derived from the core clock. It doesn't
+    /// support parity, 2 stop bits, 5 or 6 data bits, or the detection of
+    /// parity and framing errors.
+    MiniUart,
+    /// Any other serial device, such as a USB to serial adapter.
+    Other,
+}
+
+impl Peripheral {
+    // Identifies the peripheral based on the canonical device path.
+    fn from_path(path: &Path) -> Peripheral {
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.starts_with("ttyAMA") => Peripheral::Pl011,
+            Some("ttyS0") => Peripheral::MiniUart,
+            _ => Peripheral::Other,
+        }
+    }
+}
+
+impl fmt::Display for Peripheral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Peripheral::Pl011 => write!(f, "PL011"),
+            Peripheral::MiniUart => write!(f, "Mini UART"),
+            Peripheral::Other => write!(f, "Other"),
+        }
+    }
+}
+
+// Errors that may not occur when the read or write is repeated
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::TimedOut
+    )
+}
+
+// The mini UART silently ignores settings it doesn't support, which are
+// logged as a warning instead.
+fn check_mini_uart_parity(parity: Parity) {
+    if parity != Parity::None {
+        log_warn!("Mini UART doesn't support parity (requested {})", parity);
+    }
+}
+
+fn check_mini_uart_data_bits(data_bits: u8) {
+    if data_bits != 7 && data_bits != 8 {
+        log_warn!(
+            "Mini UART only supports 7 or 8 data bits (requested {})",
+            data_bits
+        );
+    }
+}
+
+fn check_mini_uart_stop_bits(stop_bits: u8) {
+    if stop_bits != 1 {
+        log_warn!(
+            "Mini UART only supports 1 stop bit (requested {})",
+            stop_bits
+        );
+    }
+}
+
 /// Control signal status.
 pub struct Status {
     tiocm: c_int,
@@ -380,21 +627,184 @@ impl fmt::Debug for Status {
     }
 }
 
+/// RS-485 driver-enable configuration.
+///
+/// Returned by [`Uart::rs485`].
+///
+/// [`Uart::rs485`]: struct.Uart.html#method.rs485
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Rs485 {
+    delay_before_send: Duration,
+    delay_after_send: Duration,
+    de_pin: Option<u8>,
+}
+
+impl Rs485 {
+    /// Returns the delay between asserting DE and the start of the
+    /// transmission.
+    pub fn delay_before_send(&self) -> Duration {
+        self.delay_before_send
+    }
+
+    /// Returns the delay between the end of the transmission and releasing
+    /// DE.
+    pub fn delay_after_send(&self) -> Duration {
+        self.delay_after_send
+    }
+
+    /// Returns the BCM GPIO pin number used as the DE signal, or `None` if DE
+    /// is controlled by the serial driver through RTS.
+    pub fn de_pin(&self) -> Option<u8> {
+        self.de_pin
+    }
+}
+
+/// Line error counters.
+///
+/// Returned by [`Uart::error_counts`].
+///
+/// The counters are maintained by the serial driver, and increase
+/// monotonically. They aren't reset when [`Uart`] is constructed, or when
+/// [`Uart::error_counts`] is called, and wrap around on overflow. To detect new
+/// errors, store a previous `ErrorCounts` and compare it with the latest one.
+///
+/// [`Uart`]: struct.Uart.html
+/// [`Uart::error_counts`]: struct.Uart.html#method.error_counts
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ErrorCounts {
+    overrun: u32,
+    buffer_overrun: u32,
+    framing: u32,
+    parity: u32,
+    brk: u32,
+}
+
+impl ErrorCounts {
+    /// Returns the number of hardware overruns.
+    ///
+    /// A hardware overrun occurs when incoming data is received while the
+    /// UART's receive FIFO is full, in which case the new data is lost.
+    pub fn overrun(&self) -> u32 {
+        self.overrun
+    }
+
+    /// Returns the number of input queue overruns.
+    ///
+    /// An input queue overrun occurs when the serial driver is unable to
+    /// copy incoming data to the input queue because it's full, in which
+    /// case the new data is lost.
+    pub fn buffer_overrun(&self) -> u32 {
+        self.buffer_overrun
+    }
+
+    /// Returns the number of framing errors.
+    ///
+    /// A framing error occurs when a byte isn't followed by a valid stop bit,
+    /// which usually indicates a baud rate mismatch.
+    pub fn framing(&self) -> u32 {
+        self.framing
+    }
+
+    /// Returns the number of parity errors.
+    pub fn parity(&self) -> u32 {
+        self.parity
+    }
+
+    /// Returns the number of break conditions.
+    pub fn brk(&self) -> u32 {
+        self.brk
+    }
+
+    /// Returns the total number of overruns, framing errors and parity
+    /// errors. Break conditions aren't included.
+    pub fn total(&self) -> u32 {
+        self.overrun
+            .wrapping_add(self.buffer_overrun)
+            .wrapping_add(self.framing)
+            .wrapping_add(self.parity)
+    }
+}
+
+/// Results of a serial path self-test.
+///
+/// Returned by [`Uart::self_test`].
+///
+/// [`Uart::self_test`]: struct.Uart.html#method.self_test
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct SelfTest {
+    bytes_sent: usize,
+    bytes_received: usize,
+    byte_errors: usize,
+    round_trip: Option<Duration>,
+}
+
+impl SelfTest {
+    /// Returns the number of bytes sent.
+    pub fn bytes_sent(&self) -> usize {
+        self.bytes_sent
+    }
+
+    /// Returns the number of bytes received before the timeout elapsed.
+    pub fn bytes_received(&self) -> usize {
+        self.bytes_received
+    }
+
+    /// Returns the number of bytes that were either received with a
+    /// different value than the one sent, or weren't received at all.
+    pub fn byte_errors(&self) -> usize {
+        self.byte_errors
+    }
+
+    /// Returns the time between the start of the transmission and the
+    /// reception of the last byte, or `None` if not all bytes were received
+    /// before the timeout elapsed.
+    pub fn round_trip(&self) -> Option<Duration> {
+        self.round_trip
+    }
+
+    /// Returns `true` if all bytes were received without any errors.
+    pub fn is_ok(&self) -> bool {
+        self.byte_errors == 0 && self.round_trip.is_some()
+    }
+}
+
+#[derive(Debug)]
+struct Rs485Inner {
+    config: Rs485,
+    de_pin: Option<OutputPin>,
+}
+
 #[derive(Debug)]
 struct UartInner {
     device: File,
     fd: RawFd,
+    peripheral: Peripheral,
     rtscts_mode: Option<(Mode, Mode)>,
     rtscts_pins: Option<(IoPin, IoPin)>,
     blocking_read: bool,
     blocking_write: bool,
+    // Read mode configured with set_read_mode(), used by read_timestamped()
+    read_min_length: u8,
+    read_timeout: Duration,
     baud_rate: u32,
     parity: Parity,
     parity_check: ParityCheck,
     data_bits: u8,
     stop_bits: u8,
+    // Baud rate, parity, data bits and stop bits passed to with_path(),
+    // which are restored by reset()
+    initial_line_coding: (u32, Parity, u8, u8),
     software_flow_control: bool,
     hardware_flow_control: bool,
+    loopback: bool,
+    rs485: Option<Rs485Inner>,
+    retry_policy: RetryPolicy,
+    // Data received by read_line() after the last complete line. Limited to
+    // LINE_BUFFER_MAX bytes.
+    line_buffer: Vec<u8>,
+    // Data received by read_with_status() that still contains (partial)
+    // PARMRK escape sequences.
+    marked_buffer: Vec<u8>,
 }
 
 /// Provides access to the Raspberry Pi's UART peripherals and any USB to
@@ -423,6 +833,33 @@ impl Uart {
         Self::with_path("/dev/serial0", baud_rate, parity, data_bits, stop_bits)
     }
 
+    /// Constructs a new `Uart` connected to the PL011 UART through
+    /// `/dev/ttyAMA0`.
+    ///
+    /// On models with Bluetooth, the PL011 is connected to the Bluetooth
+    /// module by default, and has to be freed up first as explained
+    /// [here].
+    ///
+    /// [here]: index.html#configure-devttyama0-for-serial-communication-recommended
+    pub fn new_pl011(baud_rate: u32, parity: Parity, data_bits: u8, stop_bits: u8) -> Result<Uart> {
+        Self::with_path("/dev/ttyAMA0", baud_rate, parity, data_bits, stop_bits)
+    }
+
+    /// Constructs a new `Uart` connected to the mini UART through
+    /// `/dev/ttyS0`.
+    ///
+    /// The mini UART's baud rate is derived from the core clock, which needs
+    /// to be fixed as explained [here] to prevent the baud rate from
+    /// drifting. It also lacks several features offered by the PL011. If the
+    /// optional `log` feature is enabled, a warning is logged whenever
+    /// unsupported settings are requested, since the driver ignores them
+    /// without returning an error.
+    ///
+    /// [here]: index.html#configure-devttys0-for-serial-communication
+    pub fn new_mini(baud_rate: u32, parity: Parity, data_bits: u8, stop_bits: u8) -> Result<Uart> {
+        Self::with_path("/dev/ttyS0", baud_rate, parity, data_bits, stop_bits)
+    }
+
     /// Constructs a new `Uart` connected to the serial character device
     /// specified by `path`.
     ///
@@ -441,8 +878,24 @@ impl Uart {
         data_bits: u8,
         stop_bits: u8,
     ) -> Result<Uart> {
-        // Follow symbolic links
-        let path = fs::canonicalize(path)?;
+        // Follow symbolic links. /dev/serial0 and /dev/serial1 only exist when
+        // the corresponding UART is enabled.
+        let path = path.as_ref();
+        let path =
+            fs::canonicalize(path).map_err(|e| Error::with_path(e, &path.to_string_lossy()))?;
+        let peripheral = Peripheral::from_path(&path);
+
+        // On models with Bluetooth, the PL011 is connected to the Bluetooth
+        // module unless it's disabled with an overlay, in which case
+        // /dev/serial1 points to the mini UART instead.
+        if path == Path::new("/dev/ttyAMA0")
+            && fs::canonicalize("/dev/serial1").ok().as_deref() == Some(Path::new("/dev/ttyAMA0"))
+        {
+            log_warn!(
+                "/dev/ttyAMA0 is connected to the Bluetooth module. Add dtoverlay=disable-bt or \
+                 dtoverlay=miniuart-bt to config.txt to use it on the GPIO header"
+            );
+        }
 
         // Check if we're using /dev/ttyAMA0 or /dev/ttyS0 so we can set the
         // correct RTS/CTS pin modes when needed.
@@ -460,7 +913,8 @@ impl Uart {
             .read(true)
             .write(true)
             .custom_flags(O_NOCTTY | O_NONBLOCK)
-            .open(path)?;
+            .open(&path)
+            .map_err(|e| Error::with_path(e, &path.to_string_lossy()))?;
 
         let fd = device.as_raw_fd();
 
@@ -494,26 +948,88 @@ impl Uart {
         // Flush the input and output queue
         termios::flush(fd, Queue::Both)?;
 
+        if peripheral == Peripheral::MiniUart {
+            log_warn!(
+                "{} baud rate depends on the core clock, which should be fixed with enable_uart=1 or core_freq",
+                path.display()
+            );
+
+            check_mini_uart_parity(parity);
+            check_mini_uart_data_bits(data_bits);
+            check_mini_uart_stop_bits(stop_bits);
+        }
+
+        log_debug!(
+            "Opened {} ({} Bd, {} data bits, {} parity, {} stop bits)",
+            path.display(),
+            baud_rate,
+            data_bits,
+            parity,
+            stop_bits
+        );
+
         Ok(Uart {
             inner: UartInner {
                 device,
                 fd,
+                peripheral,
                 rtscts_mode,
                 rtscts_pins: None,
                 blocking_read: false,
                 blocking_write: false,
+                read_min_length: 0,
+                read_timeout: Duration::default(),
                 baud_rate,
                 parity,
                 parity_check: ParityCheck::None,
                 data_bits,
                 stop_bits,
+                initial_line_coding: (baud_rate, parity, data_bits, stop_bits),
                 software_flow_control: false,
                 hardware_flow_control: false,
+                loopback: false,
+                rs485: None,
+                retry_policy: RetryPolicy::none(),
+                line_buffer: Vec::new(),
+                marked_buffer: Vec::new(),
             },
         })
     }
 
+    /// Constructs a new `Uart` using the settings in `config`.
+    ///
+    /// `from_config` works similarly to [`with_path`].
+    ///
+    /// [`with_path`]: #method.with_path
+    pub fn from_config(config: &UartConfig) -> Result<Uart> {
+        Uart::with_path(
+            &config.path,
+            config.baud_rate,
+            config.parity,
+            config.data_bits,
+            config.stop_bits,
+        )
+    }
+
+    /// Returns the type of UART peripheral `Uart` is connected to.
+    ///
+    /// The peripheral is identified based on the device path after following
+    /// any symbolic links, so a `Uart` constructed with [`new`] reports
+    /// whichever peripheral `/dev/serial0` points to.
+    ///
+    /// [`new`]: #method.new
+    pub fn peripheral(&self) -> Peripheral {
+        self.inner.peripheral
+    }
+
     /// Returns the line speed in baud (Bd).
+    ///
+    /// The returned value is the nominal baud rate configured with
+    /// [`set_baud_rate`] or during construction. The actual baud rate on the
+    /// wire may differ slightly, as explained in the documentation for
+    /// [`set_baud_rate`].
+    ///
+    /// [`set_baud_rate`]: #method.set_baud_rate
     pub fn baud_rate(&self) -> u32 {
         self.inner.baud_rate
     }
@@ -523,19 +1039,50 @@ impl Uart {
     /// On the Raspberry Pi, baud rate is equivalent to bit rate in bits per
     /// second (bit/s).
     ///
-    /// Accepted values:
+    /// Standard values:
     /// `0`, `50`, `75`, `110`, `134`, `150`, `200`, `300`, `600`, `1_200`,
     /// `1_800`, `2_400`, `4_800`, `9_600`, `19_200`, `38_400`, `57_600`,
     /// `115_200`, `230_400`, `460_800`, `500_000`, `576_000`, `921_600`,
     /// `1_000_000`, `1_152_000`, `1_500_000`, `2_000_000`, `2_500_000`,
     /// `3_000_000`, `3_500_000`, `4_000_000`.
     ///
+    /// Any other value, such as `250_000`, is configured as a custom baud
+    /// rate. If the driver can't set a custom baud rate within 3% of the
+    /// requested value, the previous baud rate is restored, and
+    /// [`Error::InvalidValue`] is returned.
+    ///
     /// Support for some values may be device-dependent.
+    ///
+    /// ## Actual baud rate
+    ///
+    /// The UART peripherals derive the baud rate from an input clock and a
+    /// divisor, so the actual baud rate may differ slightly from the
+    /// requested value. You can calculate the actual baud rate as follows:
+    ///
+    /// * PL011 (`/dev/ttyAMAx`): the divisor consists of a 16-bit integer
+    ///   part and a 6-bit fractional part, and is calculated as
+    ///   `uart_clock / (16 * baud_rate)`, rounded to the nearest 1/64. The
+    ///   actual baud rate is `uart_clock / (16 * divisor)`. `uart_clock` is
+    ///   set to 48 MHz by default on the Raspberry Pi 4 B and earlier models.
+    /// * Mini UART (`/dev/ttyS0`): the divisor is a 16-bit integer, calculated
+    ///   as `core_clock / (8 * baud_rate) - 1`, rounded to the nearest integer.
+    ///   The actual baud rate is `core_clock / (8 * (divisor + 1))`. The
+    ///   current core clock speed can be retrieved with [`Status::core_clock`].
+    ///
+    /// For instance, with a 48 MHz UART clock, 250 000 Bd results in a divisor
+    /// of exactly 12, while 230 400 Bd results in a divisor of 13 1/64, and an
+    /// actual baud rate of about 230 492 Bd. Most devices tolerate a
+    /// deviation of up to 2-3%.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    /// [`Status::core_clock`]: ../system/struct.Status.html#method.core_clock
     pub fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
         termios::set_line_speed(self.inner.fd, baud_rate)?;
 
         self.inner.baud_rate = baud_rate;
 
+        log_debug!("Baud rate set to {} Bd", baud_rate);
+
         Ok(())
     }
 
@@ -550,10 +1097,16 @@ impl Uart {
     ///
     /// Support for some modes may be device-dependent.
     pub fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        if self.inner.peripheral == Peripheral::MiniUart {
+            check_mini_uart_parity(parity);
+        }
+
         termios::set_parity(self.inner.fd, parity)?;
 
         self.inner.parity = parity;
 
+        log_debug!("Parity set to {}", parity);
+
         Ok(())
     }
 
@@ -572,13 +1125,62 @@ impl Uart {
     ///
     /// [`None`]: enum.ParityCheck.html#variant.None
     pub fn set_parity_check(&mut self, parity_check: ParityCheck) -> Result<()> {
+        if self.inner.peripheral == Peripheral::MiniUart && parity_check != ParityCheck::None {
+            log_warn!(
+                "Mini UART doesn't detect parity or framing errors (requested {})",
+                parity_check
+            );
+        }
+
         termios::set_parity_check(self.inner.fd, parity_check)?;
 
         self.inner.parity_check = parity_check;
 
+        log_debug!("Parity check set to {}", parity_check);
+
         Ok(())
     }
 
+    /// Returns `true` if bytes received with a parity or framing error are
+    /// reported by [`read_with_status`].
+    ///
+    /// [`read_with_status`]: #method.read_with_status
+    pub fn report_errors(&self) -> bool {
+        self.inner.parity_check == ParityCheck::Mark
+    }
+
+    /// Enables or disables marking bytes that were received with a parity
+    /// or framing error.
+    ///
+    /// When enabled, the `INPCK` and `PARMRK` input flags are set, and the
+    /// parity check mode changes to [`Mark`]. When disabled, the parity check
+    /// mode changes to [`None`]. Parity errors can only be detected when
+    /// [`parity`] isn't set to [`Parity::None`]. Framing errors are detected
+    /// regardless of the parity setting.
+    ///
+    /// With `PARMRK` set, the serial driver stuffs the incoming data. Each byte
+    /// received with a parity or framing error is preceded by a `255` and a `0`
+    /// byte, a break condition is received as `255`, `0`, `0`, and every valid
+    /// `255` byte is doubled. [`read`] returns the stuffed data as-is. Use
+    /// [`read_with_status`] to retrieve the original bytes along with their
+    /// error status.
+    ///
+    /// By default, `report_errors` is set to `false`.
+    ///
+    /// [`Mark`]: enum.ParityCheck.html#variant.Mark
+    /// [`None`]: enum.ParityCheck.html#variant.None
+    /// [`parity`]: #method.parity
+    /// [`Parity::None`]: enum.Parity.html#variant.None
+    /// [`read`]: #method.read
+    /// [`read_with_status`]: #method.read_with_status
+    pub fn set_report_errors(&mut self, report_errors: bool) -> Result<()> {
+        self.set_parity_check(if report_errors {
+            ParityCheck::Mark
+        } else {
+            ParityCheck::None
+        })
+    }
+
     /// Returns the number of data bits.
     pub fn data_bits(&self) -> u8 {
         self.inner.data_bits
@@ -590,10 +1192,16 @@ impl Uart {
     ///
     /// Support for some values may be device-dependent.
     pub fn set_data_bits(&mut self, data_bits: u8) -> Result<()> {
+        if self.inner.peripheral == Peripheral::MiniUart {
+            check_mini_uart_data_bits(data_bits);
+        }
+
         termios::set_data_bits(self.inner.fd, data_bits)?;
 
         self.inner.data_bits = data_bits;
 
+        log_debug!("Data bits set to {}", data_bits);
+
         Ok(())
     }
 
@@ -608,10 +1216,16 @@ impl Uart {
     ///
     /// Support for some values may be device-dependent.
     pub fn set_stop_bits(&mut self, stop_bits: u8) -> Result<()> {
+        if self.inner.peripheral == Peripheral::MiniUart {
+            check_mini_uart_stop_bits(stop_bits);
+        }
+
         termios::set_stop_bits(self.inner.fd, stop_bits)?;
 
         self.inner.stop_bits = stop_bits;
 
+        log_debug!("Stop bits set to {}", stop_bits);
+
         Ok(())
     }
 
@@ -635,6 +1249,38 @@ impl Uart {
         termios::set_rts(self.inner.fd, rts)
     }
 
+    /// Returns the line error counters maintained by the serial driver.
+    ///
+    /// The counters increase monotonically, and aren't reset by
+    /// `error_counts`. More information can be found in the documentation
+    /// for [`ErrorCounts`].
+    ///
+    /// Lost bytes due to a full UART receive FIFO or input queue are
+    /// reported as hardware or input queue overruns. At high baud rates,
+    /// overruns can usually be avoided by reading incoming data more
+    /// frequently, or by enabling hardware flow control.
+    ///
+    /// To identify which bytes were affected by a framing or parity error,
+    /// set the parity check mode to [`ParityCheck::Mark`].
+    ///
+    /// If the serial driver doesn't maintain error counters, `error_counts`
+    /// returns [`Error::FeatureNotSupported`].
+    ///
+    /// [`ErrorCounts`]: struct.ErrorCounts.html
+    /// [`ParityCheck::Mark`]: enum.ParityCheck.html#variant.Mark
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    pub fn error_counts(&self) -> Result<ErrorCounts> {
+        let icount = termios::icounter(self.inner.fd)?;
+
+        Ok(ErrorCounts {
+            overrun: icount.overrun as u32,
+            buffer_overrun: icount.buf_overrun as u32,
+            framing: icount.frame as u32,
+            parity: icount.parity as u32,
+            brk: icount.brk as u32,
+        })
+    }
+
     /// Returns `true` if XON/XOFF software flow control is enabled.
     pub fn software_flow_control(&self) -> bool {
         self.inner.software_flow_control
@@ -672,6 +1318,8 @@ impl Uart {
 
         self.inner.software_flow_control = software_flow_control;
 
+        log_debug!("Software flow control set to {}", software_flow_control);
+
         Ok(())
     }
 
@@ -724,8 +1372,8 @@ impl Uart {
                     (GPIO_RTS, GPIO_CTS)
                 };
 
-                let pin_rts = gpio.get(gpio_rts)?.into_io(rts_mode);
-                let pin_cts = gpio.get(gpio_cts)?.into_io(cts_mode);
+                let pin_rts = gpio.get(gpio_rts)?.into_io(rts_mode)?;
+                let pin_cts = gpio.get(gpio_cts)?.into_io(cts_mode)?;
 
                 self.inner.rtscts_pins = Some((pin_rts, pin_cts));
             }
@@ -737,6 +1385,8 @@ impl Uart {
 
         self.inner.hardware_flow_control = hardware_flow_control;
 
+        log_debug!("Hardware flow control set to {}", hardware_flow_control);
+
         Ok(())
     }
 
@@ -778,6 +1428,356 @@ impl Uart {
         Ok(())
     }
 
+    /// Returns `true` if internal loopback is enabled.
+    pub fn loopback(&self) -> bool {
+        self.inner.loopback
+    }
+
+    /// Enables or disables internal loopback.
+    ///
+    /// When internal loopback is enabled, any outgoing data is routed
+    /// directly to the input queue instead of the TX line, and incoming data
+    /// on the RX line is ignored. Internal loopback can be used in
+    /// combination with [`self_test`] to verify the serial path up to the
+    /// UART peripheral.
+    ///
+    /// Internal loopback is supported by the PL011 UART (`/dev/ttyAMA0`).
+    /// If the serial driver doesn't support internal loopback,
+    /// `set_loopback` returns [`Error::FeatureNotSupported`]. In that case,
+    /// you can connect TX to RX with a physical jumper instead.
+    ///
+    /// By default, internal loopback is disabled.
+    ///
+    /// [`self_test`]: #method.self_test
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    pub fn set_loopback(&mut self, loopback: bool) -> Result<()> {
+        termios::set_loopback(self.inner.fd, loopback)?;
+
+        self.inner.loopback = loopback;
+
+        log_debug!("Internal loopback set to {}", loopback);
+
+        Ok(())
+    }
+
+    /// Sends a known test pattern, and compares it with the incoming data.
+    ///
+    /// `self_test` requires outgoing data to be routed back to the input
+    /// queue, either by enabling internal loopback with [`set_loopback`], or
+    /// by connecting TX to RX with a physical jumper.
+    ///
+    /// Any data waiting in the input queue is discarded before the test
+    /// starts. `self_test` waits until either all bytes have been received,
+    /// or the `timeout` duration elapses.
+    ///
+    /// [`set_loopback`]: #method.set_loopback
+    pub fn self_test(&mut self, timeout: Duration) -> Result<SelfTest> {
+        let mut received = [0u8; SELF_TEST_PATTERN.len()];
+        let mut bytes_sent = 0;
+        let mut bytes_received = 0;
+        let mut round_trip = None;
+
+        termios::flush(self.inner.fd, Queue::Input)?;
+
+        let start = Instant::now();
+
+        while bytes_sent < SELF_TEST_PATTERN.len() && start.elapsed() < timeout {
+            bytes_sent += self.write(&SELF_TEST_PATTERN[bytes_sent..])?;
+        }
+
+        while bytes_received < bytes_sent && start.elapsed() < timeout {
+            // Only request the number of bytes that are available, so read()
+            // doesn't block regardless of the configured read mode.
+            let len = self.input_len()?.min(bytes_sent - bytes_received);
+
+            if len == 0 {
+                thread::sleep(Duration::from_millis(1));
+                continue;
+            }
+
+            bytes_received += self.read(&mut received[bytes_received..bytes_received + len])?;
+
+            if bytes_received == SELF_TEST_PATTERN.len() {
+                round_trip = Some(start.elapsed());
+            }
+        }
+
+        let byte_errors = SELF_TEST_PATTERN.len()
+            - SELF_TEST_PATTERN
+                .iter()
+                .zip(received[..bytes_received].iter())
+                .filter(|(sent, received)| sent == received)
+                .count();
+
+        Ok(SelfTest {
+            bytes_sent,
+            bytes_received,
+            byte_errors,
+            round_trip,
+        })
+    }
+
+    /// Attempts to detect the baud rate of the external device, by sending a
+    /// probe at each of the candidate baud rates, and checking the response
+    /// for framing and parity errors.
+    ///
+    /// `detect_baud` is a best-effort heuristic, intended for identifying
+    /// the settings of an undocumented serial device. The device has to
+    /// respond to `probe`, for instance with a prompt or an error message
+    /// after receiving a line break or an AT command. Devices that
+    /// continuously send data can be detected with an empty `probe`.
+    ///
+    /// For each candidate, in order, `detect_baud` discards any data waiting
+    /// in the input queue, sends `probe`, and collects the response until
+    /// the `timeout` duration elapses. Data received at the wrong baud rate
+    /// usually contains framing errors, which are counted by the serial
+    /// driver. A candidate is accepted when it results in a response with
+    /// framing and parity errors in fewer than 10% of the received bytes.
+    /// A response without any errors is accepted immediately. Otherwise, the
+    /// candidate with the lowest error rate is selected, with earlier
+    /// candidates preferred in case of a tie.
+    ///
+    /// Some USB to serial adapters don't report error counts, in which case
+    /// the first candidate that results in any response is selected. Because
+    /// a baud rate that's a multiple of the actual baud rate can occasionally
+    /// produce error-free garbage, verify the result by checking the content
+    /// of the response.
+    ///
+    /// If a baud rate is detected, it's left configured, and returned as
+    /// `Ok(Some(baud_rate))`. Otherwise, the original baud rate is restored,
+    /// and `Ok(None)` is returned. Candidates that aren't supported by the
+    /// serial driver are skipped.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use rppal::uart::{Parity, Uart};
+    ///
+    /// # fn main() -> rppal::uart::Result<()> {
+    /// let mut uart = Uart::new(9_600, Parity::None, 8, 1)?;
+    ///
+    /// match uart.detect_baud(
+    ///     &[9_600, 19_200, 38_400, 57_600, 115_200],
+    ///     b"\r\n",
+    ///     Duration::from_millis(250),
+    /// )? {
+    ///     Some(baud_rate) => println!("Detected {} Bd", baud_rate),
+    ///     None => println!("No response"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn detect_baud(
+        &mut self,
+        candidates: &[u32],
+        probe: &[u8],
+        timeout: Duration,
+    ) -> Result<Option<u32>> {
+        let original_baud_rate = self.inner.baud_rate;
+        let mut best: Option<(u32, f64)> = None;
+        let mut buffer = [0u8; 256];
+
+        for &baud_rate in candidates {
+            match self.set_baud_rate(baud_rate) {
+                Ok(()) => (),
+                Err(Error::InvalidValue) => continue,
+                Err(e) => return Err(e),
+            }
+
+            termios::flush(self.inner.fd, Queue::Input)?;
+            let errors_before = self.error_counts().ok();
+
+            let mut bytes_sent = 0;
+            while bytes_sent < probe.len() {
+                bytes_sent += self.write(&probe[bytes_sent..])?;
+            }
+            self.drain()?;
+
+            let start = Instant::now();
+            let mut bytes_received = 0;
+
+            while start.elapsed() < timeout {
+                // Only request the number of bytes that are available, so
+                // read() doesn't block regardless of the configured read mode.
+                let len = self.input_len()?.min(buffer.len());
+
+                if len == 0 {
+                    thread::sleep(Duration::from_millis(1));
+                    continue;
+                }
+
+                bytes_received += self.read(&mut buffer[..len])?;
+            }
+
+            if bytes_received == 0 {
+                continue;
+            }
+
+            let errors = match (errors_before, self.error_counts().ok()) {
+                (Some(before), Some(after)) => after
+                    .framing
+                    .wrapping_sub(before.framing)
+                    .wrapping_add(after.parity.wrapping_sub(before.parity)),
+                _ => 0,
+            };
+            let error_ratio = f64::from(errors) / bytes_received as f64;
+
+            log_debug!(
+                "Baud rate {} Bd: received {} bytes, {} errors",
+                baud_rate,
+                bytes_received,
+                errors
+            );
+
+            if error_ratio >= DETECT_BAUD_MAX_ERROR_RATIO {
+                continue;
+            }
+
+            if best.map_or(true, |(_, best_ratio)| error_ratio < best_ratio) {
+                best = Some((baud_rate, error_ratio));
+            }
+
+            if errors == 0 {
+                break;
+            }
+        }
+
+        match best {
+            Some((baud_rate, _)) => {
+                self.set_baud_rate(baud_rate)?;
+
+                Ok(Some(baud_rate))
+            }
+            None => {
+                self.set_baud_rate(original_baud_rate)?;
+
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the RS-485 driver-enable configuration, or `None` if RS-485
+    /// mode is disabled.
+    pub fn rs485(&self) -> Option<Rs485> {
+        self.inner.rs485.as_ref().map(|rs485| rs485.config)
+    }
+
+    /// Enables RS-485 mode, using RTS as the driver-enable (DE) signal.
+    ///
+    /// The serial driver automatically sets RTS to its active state before
+    /// transmitting data, and to its inactive state after the last stop bit
+    /// has been sent.
+    ///
+    /// `delay_before_send` and `delay_after_send` add a delay between
+    /// asserting DE and the start of the transmission, and between the end of
+    /// the transmission and releasing DE. Both delays use a 1 millisecond
+    /// resolution. The serial driver may limit the maximum value, in which
+    /// case the actual delays can be retrieved with [`rs485`].
+    ///
+    /// If the serial driver doesn't support RS-485 mode,
+    /// `set_rs485` returns [`Error::FeatureNotSupported`]. Use
+    /// [`set_rs485_with_pin`] instead.
+    ///
+    /// [`rs485`]: #method.rs485
+    /// [`set_rs485_with_pin`]: #method.set_rs485_with_pin
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    pub fn set_rs485(
+        &mut self,
+        delay_before_send: Duration,
+        delay_after_send: Duration,
+    ) -> Result<()> {
+        // Release a previously configured DE pin before switching modes
+        self.clear_rs485()?;
+
+        let rs485 = termios::set_rs485(self.inner.fd, true, delay_before_send, delay_after_send)?;
+
+        self.inner.rs485 = Some(Rs485Inner {
+            config: Rs485 {
+                delay_before_send: rs485.delay_before_send(),
+                delay_after_send: rs485.delay_after_send(),
+                de_pin: None,
+            },
+            de_pin: None,
+        });
+
+        log_debug!(
+            "RS-485 mode enabled using RTS (delays: {:?} before send, {:?} after send)",
+            rs485.delay_before_send(),
+            rs485.delay_after_send()
+        );
+
+        Ok(())
+    }
+
+    /// Enables RS-485 mode, using a GPIO pin as the driver-enable (DE) signal.
+    ///
+    /// `set_rs485_with_pin` can be used when the serial driver doesn't support
+    /// RS-485 mode. `de_pin` is set high before [`write`] sends any data,
+    /// and set low after all data in the output queue has been transmitted.
+    /// [`write`] always blocks until the transmission is finished.
+    ///
+    /// `delay_before_send` and `delay_after_send` add a delay between
+    /// setting `de_pin` high and the start of the transmission, and between
+    /// the end of the transmission and setting `de_pin` low.
+    ///
+    /// `de_pin` is set low when RS-485 mode is disabled, or when `Uart`
+    /// goes out of scope.
+    ///
+    /// [`write`]: #method.write
+    pub fn set_rs485_with_pin(
+        &mut self,
+        mut de_pin: OutputPin,
+        delay_before_send: Duration,
+        delay_after_send: Duration,
+    ) -> Result<()> {
+        self.clear_rs485()?;
+
+        de_pin.set_low();
+
+        log_debug!(
+            "RS-485 mode enabled using pin {} (delays: {:?} before send, {:?} after send)",
+            de_pin.pin(),
+            delay_before_send,
+            delay_after_send
+        );
+
+        self.inner.rs485 = Some(Rs485Inner {
+            config: Rs485 {
+                delay_before_send,
+                delay_after_send,
+                de_pin: Some(de_pin.pin()),
+            },
+            de_pin: Some(de_pin),
+        });
+
+        Ok(())
+    }
+
+    /// Disables RS-485 mode.
+    ///
+    /// If RS-485 mode was enabled with [`set_rs485_with_pin`], the DE pin is
+    /// set low and released.
+    ///
+    /// [`set_rs485_with_pin`]: #method.set_rs485_with_pin
+    pub fn clear_rs485(&mut self) -> Result<()> {
+        if let Some(rs485) = self.inner.rs485.take() {
+            if let Some(mut de_pin) = rs485.de_pin {
+                de_pin.set_low();
+            } else {
+                termios::set_rs485(
+                    self.inner.fd,
+                    false,
+                    Duration::default(),
+                    Duration::default(),
+                )?;
+            }
+
+            log_debug!("RS-485 mode disabled");
+        }
+
+        Ok(())
+    }
+
     /// Returns `true` if [`read`] is configured to block when needed.
     ///
     /// [`read`]: #method.write
@@ -827,7 +1827,15 @@ impl Uart {
     pub fn set_read_mode(&mut self, min_length: u8, timeout: Duration) -> Result<()> {
         termios::set_read_mode(self.inner.fd, min_length, timeout)?;
 
+        log_debug!(
+            "Read mode set to minimum length {}, timeout {:?}",
+            min_length,
+            timeout
+        );
+
         self.inner.blocking_read = min_length > 0 || timeout.as_millis() > 0;
+        self.inner.read_min_length = min_length;
+        self.inner.read_timeout = timeout;
 
         // If both read() and write() are non-blocking, we can safely set
         // O_NONBLOCK once instead of toggling it for every write. We can't
@@ -865,6 +1873,8 @@ impl Uart {
     pub fn set_write_mode(&mut self, blocking: bool) -> Result<()> {
         self.inner.blocking_write = blocking;
 
+        log_debug!("Blocking write set to {}", blocking);
+
         // If both read() and write() are non-blocking, we can safely set
         // O_NONBLOCK once instead of toggling it for every write. We can't
         // leave it set when read() should block, because it ignores the
@@ -903,51 +1913,392 @@ impl Uart {
     ///
     /// [`set_read_mode`]: #method.set_read_mode
     pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
-        self.inner.device.read(buffer).or_else(|e| {
-            if e.kind() == io::ErrorKind::WouldBlock {
-                Ok(0)
-            } else {
-                Err(Error::Io(e))
+        self.inner
+            .retry_policy
+            .retry(
+                || retry_interrupted!(self.inner.device.read(buffer)),
+                is_transient,
+            )
+            .or_else(|e| {
+                if e.kind() == io::ErrorKind::WouldBlock {
+                    Ok(0)
+                } else {
+                    Err(e.into())
+                }
+            })
+    }
+
+    /// Receives incoming data from the external device and stores it in
+    /// `buffer`, together with the time at which the data became available.
+    ///
+    /// `read_timestamped` waits for incoming data in the same (non)blocking
+    /// mode as [`read`], and records a timestamp as soon as the serial driver
+    /// signals that data has arrived, before the data is copied into `buffer`.
+    /// This allows incoming messages to be correlated with other events, such
+    /// as the timestamp of a GPS receiver's PPS signal captured through a
+    /// GPIO interrupt. If no data was received, the timestamp indicates when
+    /// `read_timestamped` stopped waiting.
+    ///
+    /// The serial `tty` layer doesn't provide hardware or kernel timestamps
+    /// for incoming data, so the timestamp is taken in user space, and is
+    /// always later than the arrival of the first byte on the RX line. The
+    /// delay consists of the following:
+    ///
+    /// * The time needed to transmit the received bytes, which is about
+    ///   `10 / baud_rate` seconds per byte with 8 data bits, no parity and 1
+    ///   stop bit.
+    /// * The UART peripheral only raises an interrupt once its receive FIFO
+    ///   reaches a certain fill level, or once no new data has arrived for
+    ///   the duration of a few bytes.
+    /// * The driver passes the data on to the `tty` layer from a kernel
+    ///   worker thread, after which the calling thread has to be woken up.
+    ///
+    /// On a kernel without `PREEMPT_RT`, the combined delay is typically in
+    /// the range of a few hundred microseconds to a few milliseconds, and can
+    /// be considerably higher under heavy system load. The delay is mostly
+    /// consistent for a specific baud rate and message length, so calibrating
+    /// it against a known reference improves accuracy. Running the thread
+    /// with a real-time scheduling policy reduces the variation.
+    ///
+    /// If data is already waiting in the input queue when `read_timestamped`
+    /// is called, the timestamp only indicates the data was received at some
+    /// point before the call. To get meaningful timestamps, call
+    /// `read_timestamped` again as soon as it returns, and discard the
+    /// contents of the input queue with [`flush`] before waiting for the
+    /// first message.
+    ///
+    /// Returns how many bytes were read, and the timestamp.
+    ///
+    /// [`read`]: #method.read
+    /// [`flush`]: #method.flush
+    pub fn read_timestamped(&mut self, buffer: &mut [u8]) -> Result<(usize, Instant)> {
+        let timeout = if !self.inner.blocking_read {
+            Some(Duration::default())
+        } else if self.inner.read_min_length == 0 {
+            Some(self.inner.read_timeout)
+        } else {
+            None
+        };
+
+        let ready = termios::wait_readable(self.inner.fd, timeout)?;
+        let timestamp = Instant::now();
+
+        if !ready || buffer.is_empty() {
+            return Ok((0, timestamp));
+        }
+
+        let length = self.read(buffer)?;
+
+        Ok((length, timestamp))
+    }
+
+    /// Returns a future that receives incoming data from the external device
+    /// and stores it in `buffer`.
+    ///
+    /// The future resolves as soon as at least one byte is available, and reads
+    /// as many bytes as are waiting in the input queue and fit in `buffer`. The
+    /// settings configured by [`set_read_mode`] are ignored. If `buffer` is
+    /// empty, the future resolves to `Ok(0)` immediately.
+    ///
+    /// The future doesn't depend on a specific async runtime. While no data is
+    /// available, the file descriptor is registered with a background reactor
+    /// thread, which wakes the task once data arrives. The reactor thread is
+    /// shared by all futures returned by RPPAL, started the first time a future
+    /// has to wait, and keeps running until the process exits.
+    ///
+    /// Dropping the future before it resolves cancels the read without losing
+    /// any data, which makes it possible to implement timeouts with any
+    /// executor's timer or `select` combinator.
+    ///
+    /// Returns how many bytes were read.
+    ///
+    /// [`set_read_mode`]: #method.set_read_mode
+    #[cfg(feature = "async")]
+    pub fn read_async<'a>(
+        &'a mut self,
+        buffer: &'a mut [u8],
+    ) -> impl std::future::Future<Output = Result<usize>> + 'a {
+        crate::reactor::FdFuture::new(move || {
+            if buffer.is_empty() {
+                return Ok(crate::reactor::Attempt::Ready(0));
+            }
+
+            match self.read_nonblocking(buffer)? {
+                0 => Ok(crate::reactor::Attempt::Wait(self.inner.fd)),
+                length => Ok(crate::reactor::Attempt::Ready(length)),
             }
         })
     }
 
+    // Reads any data that's waiting in the input queue, regardless of the
+    // configured read mode.
+    #[cfg(feature = "async")]
+    fn read_nonblocking(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        // O_NONBLOCK is only cleared when read() or write() is configured
+        // as blocking
+        let blocking = self.inner.blocking_read || self.inner.blocking_write;
+        if blocking {
+            unsafe {
+                libc::fcntl(self.inner.fd, libc::F_SETFL, libc::O_NONBLOCK);
+            }
+        }
+
+        let result = self.read(buffer);
+
+        if blocking {
+            unsafe {
+                libc::fcntl(self.inner.fd, libc::F_SETFL, 0);
+            }
+        }
+
+        result
+    }
+
+    /// Receives incoming data from the external device, and stores it in
+    /// `buffer` along with a per-byte error status in `errors`.
+    ///
+    /// When error reporting is enabled with [`set_report_errors`],
+    /// `read_with_status` removes the escape sequences inserted by the serial
+    /// driver, and sets the corresponding entry in `errors` to `true` for each
+    /// byte that was received with a parity or framing error. A break condition
+    /// is reported as a `0` byte marked with an error. When error reporting is
+    /// disabled, all entries are set to `false`.
+    ///
+    /// `read_with_status` operates in the same (non)blocking mode as [`read`],
+    /// and reads at most as many bytes as fit in both `buffer` and `errors`. An
+    /// escape sequence that's only partially received is kept in an internal
+    /// buffer until the remainder arrives. Data stored in the internal buffer
+    /// isn't returned by [`read`].
+    ///
+    /// Returns how many bytes were stored in `buffer`.
+    ///
+    /// [`set_report_errors`]: #method.set_report_errors
+    /// [`read`]: #method.read
+    pub fn read_with_status(&mut self, buffer: &mut [u8], errors: &mut [bool]) -> Result<usize> {
+        let max_length = buffer.len().min(errors.len());
+
+        if !self.report_errors() && self.inner.marked_buffer.is_empty() {
+            let length = self.read(&mut buffer[..max_length])?;
+            errors[..length].iter_mut().for_each(|error| *error = false);
+
+            return Ok(length);
+        }
+
+        // Only read new data if the internal buffer doesn't contain any
+        // complete bytes, so a blocking read doesn't delay the available data.
+        let mut length = decode_marked(&mut self.inner.marked_buffer, buffer, errors);
+        if length == 0 && max_length > 0 {
+            let mut chunk = vec![0u8; max_length];
+            let bytes_read = self.read(&mut chunk)?;
+            self.inner
+                .marked_buffer
+                .extend_from_slice(&chunk[..bytes_read]);
+
+            length = decode_marked(&mut self.inner.marked_buffer, buffer, errors);
+        }
+
+        Ok(length)
+    }
+
+    /// Receives incoming data until `delimiter` is found, and appends the
+    /// line to `buf`.
+    ///
+    /// `read_line` blocks until a complete line is available, or the `timeout`
+    /// duration elapses, regardless of the settings configured by
+    /// [`set_read_mode`]. Any data received after the delimiter is stored in
+    /// an internal buffer, and returned by subsequent calls to `read_line`.
+    /// If the timeout duration elapses before a complete line is available,
+    /// `read_line` returns [`Error::Timeout`], and keeps the partial line in
+    /// the internal buffer.
+    ///
+    /// The internal buffer holds up to 4096 bytes. If it fills up without
+    /// containing the delimiter, its contents are discarded, and `read_line`
+    /// returns [`Error::LineTooLong`].
+    ///
+    /// The line is appended to `buf` including the delimiter. Any invalid UTF-8
+    /// sequences are replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// Data stored in the internal buffer isn't returned by [`read`], isn't
+    /// included in [`input_len`], and isn't discarded by [`flush`].
+    ///
+    /// Returns how many bytes were read, including the delimiter.
+    ///
+    /// [`set_read_mode`]: #method.set_read_mode
+    /// [`read`]: #method.read
+    /// [`input_len`]: #method.input_len
+    /// [`flush`]: #method.flush
+    /// [`Error::Timeout`]: enum.Error.html#variant.Timeout
+    /// [`Error::LineTooLong`]: enum.Error.html#variant.LineTooLong
+    pub fn read_line(
+        &mut self,
+        buf: &mut String,
+        delimiter: u8,
+        timeout: Duration,
+    ) -> Result<usize> {
+        let mut chunk = [0u8; 256];
+        let start = Instant::now();
+
+        loop {
+            if let Some(pos) = self
+                .inner
+                .line_buffer
+                .iter()
+                .position(|&byte| byte == delimiter)
+            {
+                let line: Vec<u8> = self.inner.line_buffer.drain(..=pos).collect();
+                buf.push_str(&String::from_utf8_lossy(&line));
+
+                return Ok(line.len());
+            }
+
+            if self.inner.line_buffer.len() >= LINE_BUFFER_MAX {
+                self.inner.line_buffer.clear();
+
+                return Err(Error::LineTooLong);
+            }
+
+            let elapsed = start.elapsed();
+            if elapsed >= timeout
+                || !termios::wait_readable(self.inner.fd, Some(timeout - elapsed))?
+            {
+                return Err(Error::Timeout);
+            }
+
+            // Only request the number of bytes that are available, so read()
+            // doesn't block regardless of the configured read mode.
+            let len = self
+                .input_len()?
+                .min(chunk.len())
+                .min(LINE_BUFFER_MAX - self.inner.line_buffer.len());
+
+            if len == 0 {
+                continue;
+            }
+
+            let bytes_read = self.read(&mut chunk[..len])?;
+            self.inner
+                .line_buffer
+                .extend_from_slice(&chunk[..bytes_read]);
+        }
+    }
+
     /// Sends the contents of `buffer` to the external device.
     ///
     /// `write` operates in either blocking or non-blocking mode, depending on the
     /// settings configured by [`set_write_mode`]. By default, `write` is configured
     /// as non-blocking.
     ///
+    /// When RS-485 mode is enabled with [`set_rs485_with_pin`], `write`
+    /// sets the DE pin high, and blocks until all data in the output queue
+    /// has been transmitted before setting the DE pin low.
+    ///
     /// Returns how many bytes were written.
     ///
     /// [`set_write_mode`]: #method.set_write_mode
+    /// [`set_rs485_with_pin`]: #method.set_rs485_with_pin
     pub fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+        if let Some(Rs485Inner {
+            config,
+            de_pin: Some(ref mut de_pin),
+        }) = self.inner.rs485
+        {
+            de_pin.set_high();
+            if config.delay_before_send > Duration::default() {
+                thread::sleep(config.delay_before_send);
+            }
+
+            let result = Self::write_device(
+                &mut self.inner.device,
+                self.inner.fd,
+                self.inner.blocking_read,
+                self.inner.blocking_write,
+                self.inner.retry_policy,
+                buffer,
+            );
+
+            // DE must stay asserted until the last stop bit has been sent,
+            // which is only guaranteed after the output queue is drained.
+            let drained = termios::drain(self.inner.fd);
+
+            if config.delay_after_send > Duration::default() {
+                thread::sleep(config.delay_after_send);
+            }
+            de_pin.set_low();
+
+            drained?;
+
+            return result;
+        }
+
+        Self::write_device(
+            &mut self.inner.device,
+            self.inner.fd,
+            self.inner.blocking_read,
+            self.inner.blocking_write,
+            self.inner.retry_policy,
+            buffer,
+        )
+    }
+
+    fn write_device(
+        device: &mut File,
+        fd: RawFd,
+        blocking_read: bool,
+        blocking_write: bool,
+        retry_policy: RetryPolicy,
+        buffer: &[u8],
+    ) -> Result<usize> {
         // We only need to toggle O_NONBLOCK when read() is configured as
         // blocking. If read() is non-blocking, either with_path() or
         // set_read_mode() will have already enabled O_NONBLOCK.
-        if self.inner.blocking_read && !self.inner.blocking_write {
+        if blocking_read && !blocking_write {
             unsafe {
-                libc::fcntl(self.inner.fd, libc::F_SETFL, libc::O_NONBLOCK);
+                libc::fcntl(fd, libc::F_SETFL, libc::O_NONBLOCK);
             }
         }
 
-        let result = self.inner.device.write(buffer).or_else(|e| {
-            if e.kind() == io::ErrorKind::WouldBlock {
-                Ok(0)
-            } else {
-                Err(Error::Io(e))
-            }
-        });
+        let result = retry_policy
+            .retry(|| retry_interrupted!(device.write(buffer)), is_transient)
+            .or_else(|e| {
+                if e.kind() == io::ErrorKind::WouldBlock {
+                    Ok(0)
+                } else {
+                    Err(e.into())
+                }
+            });
 
-        if self.inner.blocking_read && !self.inner.blocking_write {
+        if blocking_read && !blocking_write {
             unsafe {
-                libc::fcntl(self.inner.fd, libc::F_SETFL, 0);
+                libc::fcntl(fd, libc::F_SETFL, 0);
             }
         }
 
         result
     }
 
+    /// Returns the retry policy.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.inner.retry_policy
+    }
+
+    /// Sets the retry policy applied to reads and writes.
+    ///
+    /// Reads and writes that keep getting interrupted by a signal after the
+    /// automatic retries described in the [crate-level documentation]
+    /// (`EINTR`), or that time out (`ETIMEDOUT`), are retried according to
+    /// `retry_policy`. Other errors, such as a USB to serial adapter being
+    /// disconnected, are returned immediately. Reads and writes that don't
+    /// transfer any data in non-blocking mode aren't considered errors, and
+    /// aren't retried.
+    ///
+    /// By default, `retry_policy` is set to [`RetryPolicy::none`].
+    ///
+    /// [crate-level documentation]: ../index.html
+    /// [`RetryPolicy::none`]: ../retry/struct.RetryPolicy.html#method.none
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.inner.retry_policy = retry_policy;
+    }
+
     /// Blocks until all data in the output queue has been transmitted.
     pub fn drain(&self) -> Result<()> {
         termios::drain(self.inner.fd)
@@ -957,4 +2308,134 @@ impl Uart {
     pub fn flush(&self, queue_type: Queue) -> Result<()> {
         termios::flush(self.inner.fd, queue_type)
     }
+
+    /// Restores the port configuration to the state it was in after
+    /// construction.
+    ///
+    /// `reset` restores the baud rate, parity, data bits and stop bits that
+    /// were passed to [`with_path`], disables parity checking, software and
+    /// hardware flow control, internal loopback and RS-485 mode, and
+    /// configures both [`read`] and [`write`] as non-blocking. Any data
+    /// waiting in the input and output queues is discarded, together with
+    /// any partial lines buffered by [`read_line`].
+    ///
+    /// This provides a known starting point after an error, or before
+    /// handing the port over to other code. The DTR and RTS control signals
+    /// are left unchanged.
+    ///
+    /// [`with_path`]: #method.with_path
+    /// [`read`]: #method.read
+    /// [`write`]: #method.write
+    /// [`read_line`]: #method.read_line
+    pub fn reset(&mut self) -> Result<()> {
+        let (baud_rate, parity, data_bits, stop_bits) = self.inner.initial_line_coding;
+
+        self.clear_rs485()?;
+
+        // Only disable features that were enabled, since not every device
+        // supports them
+        if self.inner.loopback {
+            self.set_loopback(false)?;
+        }
+
+        if self.inner.hardware_flow_control {
+            self.set_hardware_flow_control(false)?;
+        }
+
+        self.set_software_flow_control(false)?;
+        self.set_parity_check(ParityCheck::None)?;
+
+        self.set_baud_rate(baud_rate)?;
+        self.set_parity(parity)?;
+        self.set_data_bits(data_bits)?;
+        self.set_stop_bits(stop_bits)?;
+
+        self.set_read_mode(0, Duration::default())?;
+        self.set_write_mode(false)?;
+
+        self.inner.line_buffer.clear();
+        self.inner.marked_buffer.clear();
+        self.flush(Queue::Both)?;
+
+        log_debug!("UART reset to defaults");
+
+        Ok(())
+    }
+}
+
+/// Returns the file descriptor of the underlying device file.
+///
+/// The file descriptor can be shared with other code, such as C libraries
+/// that need access to the same device. `Uart` retains ownership of the
+/// file descriptor, which means it must not be closed, and it's only valid
+/// for as long as `Uart` exists.
+///
+/// Any data read or written through the file descriptor isn't synchronized
+/// with `Uart`, and may be interleaved with its own reads and writes.
+/// Changing the port settings through the file descriptor isn't reflected in
+/// `Uart`'s cached state.
+// Removes the PARMRK escape sequences from the start of marked, and stores the
+// decoded bytes in buffer and their error status in errors. Incomplete escape
+// sequences and any bytes that don't fit are left in marked. Returns the
+// number of decoded bytes.
+fn decode_marked(marked: &mut Vec<u8>, buffer: &mut [u8], errors: &mut [bool]) -> usize {
+    let max_length = buffer.len().min(errors.len());
+    let mut pos = 0;
+    let mut length = 0;
+
+    while length < max_length && pos < marked.len() {
+        let (byte, error, size) = match marked[pos..] {
+            [0xff, 0xff, ..] => (0xff, false, 2),
+            [0xff, 0x00, byte, ..] => (byte, true, 3),
+            [0xff] | [0xff, 0x00] => break,
+            [byte, ..] => (byte, false, 1),
+            [] => break,
+        };
+
+        buffer[length] = byte;
+        errors[length] = error;
+        length += 1;
+        pos += size;
+    }
+
+    marked.drain(..pos);
+
+    length
+}
+
+impl AsRawFd for Uart {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.fd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn error_display_and_source() {
+        let err = Error::from(io::Error::new(io::ErrorKind::Other, "Input/output error"));
+        assert_eq!(err.to_string(), "I/O error: Input/output error");
+        assert!(err.source().is_none());
+
+        let err = Error::with_path(io::Error::from_raw_os_error(libc::EIO), "/dev/ttyAMA0");
+        assert!(err.to_string().starts_with("I/O error: /dev/ttyAMA0: "));
+        let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.raw_os_error(), Some(libc::EIO));
+
+        let err = Error::from(gpio::Error::PinUsed(17));
+        assert_eq!(err.to_string(), "GPIO error");
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "Pin 17 is already in use"
+        );
+
+        let err = Error::Timeout;
+        assert_eq!(err.to_string(), "Timed out");
+        assert!(err.source().is_none());
+    }
 }
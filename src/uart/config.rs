@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use super::Parity;
+
+// Device path used by Uart::new
+const DEFAULT_PATH: &str = "/dev/serial0";
+
+/// Configuration for a [`Uart`] instance.
+///
+/// `UartConfig` contains the same settings as the arguments for
+/// [`Uart::with_path`], and can be passed to [`Uart::from_config`]. This is
+/// useful when the UART configuration is loaded from a file.
+///
+/// With the optional `serde` feature enabled, `UartConfig` is (de)serialized
+/// using kebab-case field names, and unknown fields are rejected. If `path`
+/// is omitted, it defaults to `/dev/serial0`, similar to [`Uart::new`]. For
+/// instance, in TOML:
+///
+/// ```toml
+/// baud-rate = 115200
+/// parity = "none"
+/// data-bits = 8
+/// stop-bits = 1
+/// ```
+///
+/// [`Uart`]: struct.Uart.html
+/// [`Uart::new`]: struct.Uart.html#method.new
+/// [`Uart::with_path`]: struct.Uart.html#method.with_path
+/// [`Uart::from_config`]: struct.Uart.html#method.from_config
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(rename_all = "kebab-case", deny_unknown_fields)
+)]
+pub struct UartConfig {
+    /// The serial character device path.
+    #[cfg_attr(feature = "serde", serde(default = "default_path"))]
+    pub path: PathBuf,
+    /// The baud rate.
+    pub baud_rate: u32,
+    /// The parity bit mode.
+    pub parity: Parity,
+    /// The number of data bits.
+    pub data_bits: u8,
+    /// The number of stop bits.
+    pub stop_bits: u8,
+}
+
+impl UartConfig {
+    /// Constructs a new `UartConfig` for `/dev/serial0`.
+    ///
+    /// Use [`with_path`] to select a different device.
+    ///
+    /// [`with_path`]: #method.with_path
+    pub fn new(baud_rate: u32, parity: Parity, data_bits: u8, stop_bits: u8) -> UartConfig {
+        UartConfig {
+            path: default_path(),
+            baud_rate,
+            parity,
+            data_bits,
+            stop_bits,
+        }
+    }
+
+    /// Constructs a new `UartConfig` for the serial character device specified
+    /// by `path`.
+    pub fn with_path<P: Into<PathBuf>>(
+        path: P,
+        baud_rate: u32,
+        parity: Parity,
+        data_bits: u8,
+        stop_bits: u8,
+    ) -> UartConfig {
+        UartConfig {
+            path: path.into(),
+            baud_rate,
+            parity,
+            data_bits,
+            stop_bits,
+        }
+    }
+}
+
+fn default_path() -> PathBuf {
+    PathBuf::from(DEFAULT_PATH)
+}
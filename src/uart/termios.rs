@@ -3,7 +3,7 @@
 use std::io;
 use std::time::Duration;
 
-use libc::{c_int, termios};
+use libc::{c_int, tcflag_t, termios};
 use libc::{B0, B110, B134, B150, B200, B300, B50, B75};
 use libc::{B1000000, B1152000, B460800, B500000, B576000, B921600};
 use libc::{B115200, B19200, B230400, B38400, B57600};
@@ -14,13 +14,98 @@ use libc::{CS5, CS6, CS7, CS8, CSIZE, CSTOPB, PARENB, PARODD};
 use libc::{IGNPAR, INPCK, PARMRK};
 use libc::{IXANY, IXOFF, IXON, TCIFLUSH, TCIOFLUSH, TCOFLUSH, VMIN, VSTART, VSTOP, VTIME};
 use libc::{TCIOFF, TCION, TIOCMGET, TIOCM_CTS, TIOCM_DSR, TIOCM_DTR, TIOCM_RTS};
-use libc::{TIOCINQ, TIOCMBIC, TIOCMBIS, TIOCM_CAR, TIOCM_RNG, TIOCOUTQ};
+use libc::{
+    TIOCGICOUNT, TIOCGRS485, TIOCINQ, TIOCMBIC, TIOCMBIS, TIOCM_CAR, TIOCM_RNG, TIOCOUTQ,
+    TIOCSRS485,
+};
 
 use crate::uart::{Error, Parity, ParityCheck, Queue, Result};
 
 const XON: u8 = 17;
 const XOFF: u8 = 19;
 
+// Internal loopback modem control bit (not defined in libc)
+const TIOCM_LOOP: c_int = 0x8000;
+
+#[cfg(target_env = "gnu")]
+type IoctlLong = libc::c_ulong;
+#[cfg(target_env = "musl")]
+type IoctlLong = c_int;
+
+// termios2 ioctls and flags (include/uapi/asm-generic/ioctls.h and
+// termbits.h). musl doesn't expose termios2, so these are defined here.
+const TCGETS2: IoctlLong = 0x802c_542a_u32 as IoctlLong;
+const TCSETS2: IoctlLong = 0x402c_542b_u32 as IoctlLong;
+const CBAUD: tcflag_t = 0o010_017;
+const BOTHER: tcflag_t = 0o010_000;
+const IBSHIFT: u32 = 16;
+
+// Maximum relative deviation between the requested custom baud rate and the
+// rate reported by the driver
+const BAUD_RATE_TOLERANCE: f64 = 0.03;
+
+// Based on struct termios2 in include/uapi/asm-generic/termbits.h
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+struct Termios2 {
+    c_iflag: tcflag_t,
+    c_oflag: tcflag_t,
+    c_cflag: tcflag_t,
+    c_lflag: tcflag_t,
+    c_line: u8,
+    c_cc: [u8; 19],
+    c_ispeed: u32,
+    c_ospeed: u32,
+}
+
+// RS-485 flags (serial_rs485.flags)
+const SER_RS485_ENABLED: u32 = 1 << 0;
+const SER_RS485_RTS_ON_SEND: u32 = 1 << 1;
+
+// Based on struct serial_rs485 in include/uapi/linux/serial.h
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct SerialRs485 {
+    flags: u32,
+    // Delay before send in milliseconds
+    delay_rts_before_send: u32,
+    // Delay after send in milliseconds
+    delay_rts_after_send: u32,
+    padding: [u32; 5],
+}
+
+impl SerialRs485 {
+    pub fn enabled(&self) -> bool {
+        (self.flags & SER_RS485_ENABLED) > 0
+    }
+
+    pub fn delay_before_send(&self) -> Duration {
+        Duration::from_millis(u64::from(self.delay_rts_before_send))
+    }
+
+    pub fn delay_after_send(&self) -> Duration {
+        Duration::from_millis(u64::from(self.delay_rts_after_send))
+    }
+}
+
+// Based on struct serial_icounter_struct in include/uapi/linux/serial.h
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct SerialIcounter {
+    pub cts: c_int,
+    pub dsr: c_int,
+    pub rng: c_int,
+    pub dcd: c_int,
+    pub rx: c_int,
+    pub tx: c_int,
+    pub frame: c_int,
+    pub overrun: c_int,
+    pub parity: c_int,
+    pub brk: c_int,
+    pub buf_overrun: c_int,
+    reserved: [c_int; 9],
+}
+
 #[cfg(target_env = "gnu")]
 pub fn attributes(fd: c_int) -> Result<termios> {
     let mut attr = termios {
@@ -62,7 +147,28 @@ pub fn set_attributes(fd: c_int, attr: &termios) -> Result<()> {
         if e.kind() == io::ErrorKind::InvalidInput {
             Error::InvalidValue
         } else {
-            Error::Io(e)
+            Error::with_fd(e, fd)
+        }
+    })?;
+
+    Ok(())
+}
+
+fn attributes2(fd: c_int) -> Result<Termios2> {
+    let mut attr = Termios2::default();
+
+    parse_retval!(unsafe { libc::ioctl(fd, TCGETS2, &mut attr) })
+        .map_err(|e| Error::with_fd(e, fd))?;
+
+    Ok(attr)
+}
+
+fn set_attributes2(fd: c_int, attr: &Termios2) -> Result<()> {
+    parse_retval!(unsafe { libc::ioctl(fd, TCSETS2, attr) }).map_err(|e| {
+        if e.kind() == io::ErrorKind::InvalidInput {
+            Error::InvalidValue
+        } else {
+            Error::with_fd(e, fd)
         }
     })?;
 
@@ -70,7 +176,12 @@ pub fn set_attributes(fd: c_int, attr: &termios) -> Result<()> {
 }
 
 pub fn line_speed(fd: c_int) -> Result<u32> {
-    // We can't use termios2 here, because it's not supported by musl
+    // Custom baud rates are only available through termios2
+    let attr2 = attributes2(fd)?;
+    if attr2.c_cflag & CBAUD == BOTHER {
+        return Ok(attr2.c_ospeed);
+    }
+
     Ok(match unsafe { libc::cfgetospeed(&attributes(fd)?) } {
         B0 => 0,
         B50 => 50,
@@ -140,10 +251,9 @@ pub fn set_line_speed(fd: c_int, line_speed: u32) -> Result<()> {
         3_000_000 => B3000000,
         3_500_000 => B3500000,
         4_000_000 => B4000000,
-        _ => return Err(Error::InvalidValue),
+        _ => return set_custom_line_speed(fd, line_speed),
     };
 
-    // We can't use termios2 here, because it's not supported by musl
     let mut attr = attributes(fd)?;
 
     parse_retval!(unsafe { libc::cfsetispeed(&mut attr, baud) })?;
@@ -152,6 +262,36 @@ pub fn set_line_speed(fd: c_int, line_speed: u32) -> Result<()> {
     set_attributes(fd, &attr)
 }
 
+// Sets a baud rate that doesn't have a corresponding Bxxx constant
+fn set_custom_line_speed(fd: c_int, line_speed: u32) -> Result<()> {
+    if line_speed == 0 {
+        return Err(Error::InvalidValue);
+    }
+
+    let prev_attr = attributes2(fd)?;
+
+    let mut attr = prev_attr;
+    attr.c_cflag &= !(CBAUD | (CBAUD << IBSHIFT));
+    attr.c_cflag |= BOTHER | (BOTHER << IBSHIFT);
+    attr.c_ispeed = line_speed;
+    attr.c_ospeed = line_speed;
+
+    set_attributes2(fd, &attr)?;
+
+    // The driver replaces the requested baud rate with the closest rate it
+    // supports, or falls back to a different rate entirely if it's out of
+    // range
+    let actual = attributes2(fd)?.c_ospeed;
+    let deviation = (f64::from(actual) - f64::from(line_speed)).abs() / f64::from(line_speed);
+    if deviation > BAUD_RATE_TOLERANCE {
+        set_attributes2(fd, &prev_attr)?;
+
+        return Err(Error::InvalidValue);
+    }
+
+    Ok(())
+}
+
 pub fn parity(fd: c_int) -> Result<Parity> {
     let attr = attributes(fd)?;
 
@@ -375,7 +515,8 @@ pub fn set_hardware_flow_control(fd: c_int, enabled: bool) -> Result<()> {
 pub fn status(fd: c_int) -> Result<c_int> {
     let mut tiocm: c_int = 0;
 
-    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })?;
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })
+        .map_err(|e| Error::with_fd(e, fd))?;
 
     Ok(tiocm)
 }
@@ -384,7 +525,8 @@ pub fn status(fd: c_int) -> Result<c_int> {
 pub fn cts(fd: c_int) -> Result<bool> {
     let mut tiocm: c_int = 0;
 
-    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })?;
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })
+        .map_err(|e| Error::with_fd(e, fd))?;
 
     Ok(tiocm & TIOCM_CTS > 0)
 }
@@ -393,7 +535,8 @@ pub fn cts(fd: c_int) -> Result<bool> {
 pub fn rts(fd: c_int) -> Result<bool> {
     let mut tiocm: c_int = 0;
 
-    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })?;
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })
+        .map_err(|e| Error::with_fd(e, fd))?;
 
     Ok(tiocm & TIOCM_RTS > 0)
 }
@@ -402,7 +545,37 @@ pub fn rts(fd: c_int) -> Result<bool> {
 pub fn set_rts(fd: c_int, rts: bool) -> Result<()> {
     let tiocm: c_int = TIOCM_RTS;
 
-    parse_retval!(unsafe { libc::ioctl(fd, if rts { TIOCMBIS } else { TIOCMBIC }, &tiocm) })?;
+    parse_retval!(unsafe { libc::ioctl(fd, if rts { TIOCMBIS } else { TIOCMBIC }, &tiocm) })
+        .map_err(|e| Error::with_fd(e, fd))?;
+
+    Ok(())
+}
+
+// Return internal loopback state
+pub fn loopback(fd: c_int) -> Result<bool> {
+    let mut tiocm: c_int = 0;
+
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })
+        .map_err(|e| Error::with_fd(e, fd))?;
+
+    Ok(tiocm & TIOCM_LOOP > 0)
+}
+
+// Enable or disable internal loopback. Drivers that don't support loopback
+// either return an error, or silently ignore the request, in which case the
+// loopback bit won't be reported by TIOCMGET.
+pub fn set_loopback(fd: c_int, enabled: bool) -> Result<()> {
+    let tiocm: c_int = TIOCM_LOOP;
+
+    parse_retval!(unsafe { libc::ioctl(fd, if enabled { TIOCMBIS } else { TIOCMBIC }, &tiocm) })
+        .map_err(|e| match e.raw_os_error() {
+            Some(libc::ENOTTY) | Some(libc::EINVAL) => Error::FeatureNotSupported,
+            _ => Error::with_fd(e, fd),
+        })?;
+
+    if enabled && !loopback(fd)? {
+        return Err(Error::FeatureNotSupported);
+    }
 
     Ok(())
 }
@@ -411,7 +584,8 @@ pub fn set_rts(fd: c_int, rts: bool) -> Result<()> {
 pub fn dcd(fd: c_int) -> Result<bool> {
     let mut tiocm: c_int = 0;
 
-    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })?;
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })
+        .map_err(|e| Error::with_fd(e, fd))?;
 
     Ok(tiocm & TIOCM_CAR > 0)
 }
@@ -420,7 +594,8 @@ pub fn dcd(fd: c_int) -> Result<bool> {
 pub fn ri(fd: c_int) -> Result<bool> {
     let mut tiocm: c_int = 0;
 
-    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })?;
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })
+        .map_err(|e| Error::with_fd(e, fd))?;
 
     Ok(tiocm & TIOCM_RNG > 0)
 }
@@ -429,7 +604,8 @@ pub fn ri(fd: c_int) -> Result<bool> {
 pub fn dsr(fd: c_int) -> Result<bool> {
     let mut tiocm: c_int = 0;
 
-    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })?;
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })
+        .map_err(|e| Error::with_fd(e, fd))?;
 
     Ok(tiocm & TIOCM_DSR > 0)
 }
@@ -438,7 +614,8 @@ pub fn dsr(fd: c_int) -> Result<bool> {
 pub fn dtr(fd: c_int) -> Result<bool> {
     let mut tiocm: c_int = 0;
 
-    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })?;
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCMGET, &mut tiocm) })
+        .map_err(|e| Error::with_fd(e, fd))?;
 
     Ok(tiocm & TIOCM_DTR > 0)
 }
@@ -447,7 +624,8 @@ pub fn dtr(fd: c_int) -> Result<bool> {
 pub fn set_dtr(fd: c_int, dtr: bool) -> Result<()> {
     let tiocm: c_int = TIOCM_DTR;
 
-    parse_retval!(unsafe { libc::ioctl(fd, if dtr { TIOCMBIS } else { TIOCMBIC }, &tiocm) })?;
+    parse_retval!(unsafe { libc::ioctl(fd, if dtr { TIOCMBIS } else { TIOCMBIC }, &tiocm) })
+        .map_err(|e| Error::with_fd(e, fd))?;
 
     Ok(())
 }
@@ -523,7 +701,8 @@ pub fn drain(fd: c_int) -> Result<()> {
 pub fn input_len(fd: c_int) -> Result<usize> {
     let mut len: c_int = 0;
 
-    parse_retval!(unsafe { libc::ioctl(fd, TIOCINQ, &mut len) })?;
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCINQ, &mut len) })
+        .map_err(|e| Error::with_fd(e, fd))?;
 
     Ok(len as usize)
 }
@@ -532,7 +711,91 @@ pub fn input_len(fd: c_int) -> Result<usize> {
 pub fn output_len(fd: c_int) -> Result<usize> {
     let mut len: c_int = 0;
 
-    parse_retval!(unsafe { libc::ioctl(fd, TIOCOUTQ, &mut len) })?;
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCOUTQ, &mut len) })
+        .map_err(|e| Error::with_fd(e, fd))?;
 
     Ok(len as usize)
 }
+
+// Waits until the input queue contains data, or the timeout elapses. A
+// timeout of None waits indefinitely. Returns true if data is available.
+pub fn wait_readable(fd: c_int, timeout: Option<Duration>) -> Result<bool> {
+    let mut pfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+
+    let timeout_ms = timeout.map_or(-1, |timeout| {
+        timeout.as_millis().min(c_int::MAX as u128) as c_int
+    });
+
+    let ready = retry_interrupted!(parse_retval!(unsafe {
+        libc::poll(&mut pfd, 1, timeout_ms)
+    }))?;
+
+    Ok(ready > 0 && pfd.revents & libc::POLLIN != 0)
+}
+
+// Return the kernel's RS-485 settings
+pub fn rs485(fd: c_int) -> Result<SerialRs485> {
+    let mut rs485 = SerialRs485::default();
+
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCGRS485, &mut rs485) })
+        .map_err(|e| rs485_error(e, fd))?;
+
+    Ok(rs485)
+}
+
+// Enable or disable the kernel's RS-485 mode, where RTS is used as the
+// driver-enable signal. RTS is active during transmission, and inactive
+// after the last stop bit has been sent.
+pub fn set_rs485(
+    fd: c_int,
+    enabled: bool,
+    delay_before_send: Duration,
+    delay_after_send: Duration,
+) -> Result<SerialRs485> {
+    let mut config = SerialRs485::default();
+
+    if enabled {
+        config.flags = SER_RS485_ENABLED | SER_RS485_RTS_ON_SEND;
+        config.delay_rts_before_send =
+            delay_before_send.as_millis().min(u128::from(u32::MAX)) as u32;
+        config.delay_rts_after_send = delay_after_send.as_millis().min(u128::from(u32::MAX)) as u32;
+    }
+
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCSRS485, &mut config) })
+        .map_err(|e| rs485_error(e, fd))?;
+
+    // The driver may have clamped the delays, or cleared unsupported flags,
+    // so read back the actual values
+    let config = rs485(fd)?;
+    if enabled && !config.enabled() {
+        return Err(Error::FeatureNotSupported);
+    }
+
+    Ok(config)
+}
+
+// Drivers without RS-485 support return ENOTTY, or EINVAL on older kernels
+fn rs485_error(e: io::Error, fd: c_int) -> Error {
+    match e.raw_os_error() {
+        Some(libc::ENOTTY) | Some(libc::EINVAL) => Error::FeatureNotSupported,
+        _ => Error::with_fd(e, fd),
+    }
+}
+
+// Return the interrupt counters maintained by the serial driver
+pub fn icounter(fd: c_int) -> Result<SerialIcounter> {
+    let mut icount = SerialIcounter::default();
+
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCGICOUNT, &mut icount) }).map_err(|e| {
+        match e.raw_os_error() {
+            Some(libc::ENOTTY) | Some(libc::EINVAL) => Error::FeatureNotSupported,
+            _ => Error::with_fd(e, fd),
+        }
+    })?;
+
+    Ok(icount)
+}
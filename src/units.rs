@@ -0,0 +1,219 @@
+//! Validated duty cycle and frequency values.
+//!
+//! [`DutyCycle`] and [`Frequency`] are accepted by both the hardware PWM
+//! methods in the [`pwm`] module, and the software-based PWM methods on GPIO
+//! pins. Invalid values are rejected with a [`units::Error`], which converts
+//! into the `InvalidDutyCycle` and `InvalidFrequency` variants of the
+//! [`pwm::Error`] and [`gpio::Error`] types.
+//!
+//! [`DutyCycle`]: struct.DutyCycle.html
+//! [`Frequency`]: struct.Frequency.html
+//! [`units::Error`]: enum.Error.html
+//! [`pwm`]: ../pwm/index.html
+//! [`pwm::Error`]: ../pwm/enum.Error.html
+//! [`gpio::Error`]: ../gpio/enum.Error.html
+
+use std::convert::{Infallible, TryFrom};
+use std::error;
+use std::fmt;
+use std::result;
+
+/// Errors that can occur when constructing a [`DutyCycle`] or [`Frequency`].
+///
+/// ```
+/// use rppal::units::{DutyCycle, Error};
+///
+/// let err = DutyCycle::try_new(1.5).unwrap_err();
+/// assert_eq!(err, Error::InvalidDutyCycle(1.5));
+/// assert_eq!(err.to_string(), "Invalid duty cycle: 1.5");
+/// ```
+///
+/// [`DutyCycle`]: struct.DutyCycle.html
+/// [`Frequency`]: struct.Frequency.html
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[non_exhaustive]
+pub enum Error {
+    /// Invalid duty cycle.
+    ///
+    /// The duty cycle is NaN, or outside the range `0.0..=1.0`. Contains the
+    /// rejected value.
+    InvalidDutyCycle(f64),
+    /// Invalid frequency.
+    ///
+    /// The frequency is NaN, infinite, or not greater than `0.0`. Contains the
+    /// rejected value.
+    InvalidFrequency(f64),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::InvalidDutyCycle(value) => write!(f, "Invalid duty cycle: {}", value),
+            Error::InvalidFrequency(value) => write!(f, "Invalid frequency: {}", value),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+// Converting a DutyCycle or Frequency into itself can't fail.
+impl From<Infallible> for Error {
+    fn from(err: Infallible) -> Error {
+        match err {}
+    }
+}
+
+/// Result type returned from methods that can have `units::Error`s.
+pub type Result<T> = result::Result<T, Error>;
+
+/// A duty cycle between `0.0` (0%) and `1.0` (100%).
+///
+/// Methods that accept a duty cycle take either a `DutyCycle`, or a bare `f64`
+/// that's validated when the method is called. Constructing a `DutyCycle`
+/// up front moves the validation to a single place, and avoids having to
+/// handle the same error on every call.
+///
+/// ## Example
+///
+/// ```
+/// use rppal::units::DutyCycle;
+///
+/// # fn main() -> rppal::units::Result<()> {
+/// let duty_cycle = DutyCycle::try_new(0.25)?;
+/// assert_eq!(duty_cycle.value(), 0.25);
+///
+/// assert!(DutyCycle::try_new(1.5).is_err());
+/// assert!(DutyCycle::try_new(f64::NAN).is_err());
+/// assert_eq!(DutyCycle::clamped(1.5), DutyCycle::MAX);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, PartialOrd, Copy, Clone)]
+pub struct DutyCycle(f64);
+
+impl DutyCycle {
+    /// A duty cycle of 0%.
+    pub const MIN: DutyCycle = DutyCycle(0.0);
+    /// A duty cycle of 100%.
+    pub const MAX: DutyCycle = DutyCycle(1.0);
+
+    /// Constructs a new `DutyCycle`.
+    ///
+    /// Returns `Err(`[`Error::InvalidDutyCycle`]`)` if `value` is NaN, or
+    /// outside the range `0.0..=1.0`.
+    ///
+    /// [`Error::InvalidDutyCycle`]: enum.Error.html#variant.InvalidDutyCycle
+    pub fn try_new(value: f64) -> Result<DutyCycle> {
+        if (0.0..=1.0).contains(&value) {
+            Ok(DutyCycle(value))
+        } else {
+            Err(Error::InvalidDutyCycle(value))
+        }
+    }
+
+    /// Constructs a new `DutyCycle`, limiting `value` to the range `0.0..=1.0`.
+    ///
+    /// NaN is converted to `0.0`.
+    pub fn clamped(value: f64) -> DutyCycle {
+        if value.is_nan() {
+            DutyCycle::MIN
+        } else {
+            DutyCycle(value.clamp(0.0, 1.0))
+        }
+    }
+
+    /// Returns the duty cycle as a value between `0.0` and `1.0`.
+    pub const fn value(self) -> f64 {
+        self.0
+    }
+}
+
+impl TryFrom<f64> for DutyCycle {
+    type Error = Error;
+
+    fn try_from(value: f64) -> Result<DutyCycle> {
+        DutyCycle::try_new(value)
+    }
+}
+
+impl From<DutyCycle> for f64 {
+    fn from(duty_cycle: DutyCycle) -> f64 {
+        duty_cycle.0
+    }
+}
+
+impl fmt::Display for DutyCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.0 * 100.0)
+    }
+}
+
+/// A frequency in hertz (Hz).
+///
+/// A `Frequency` is always positive and finite. Methods that accept a
+/// frequency take either a `Frequency`, or a bare `f64` that's validated when
+/// the method is called.
+///
+/// ## Example
+///
+/// ```
+/// use rppal::units::Frequency;
+///
+/// # fn main() -> rppal::units::Result<()> {
+/// let frequency = Frequency::try_new(50.0)?;
+/// assert_eq!(frequency.hz(), 50.0);
+///
+/// assert!(Frequency::try_new(0.0).is_err());
+/// assert!(Frequency::try_new(f64::INFINITY).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, PartialOrd, Copy, Clone)]
+pub struct Frequency(f64);
+
+impl Frequency {
+    /// Constructs a new `Frequency`.
+    ///
+    /// Returns `Err(`[`Error::InvalidFrequency`]`)` if `hz` is NaN, infinite,
+    /// or not greater than `0.0`.
+    ///
+    /// [`Error::InvalidFrequency`]: enum.Error.html#variant.InvalidFrequency
+    pub fn try_new(hz: f64) -> Result<Frequency> {
+        if hz.is_finite() && hz > 0.0 {
+            Ok(Frequency(hz))
+        } else {
+            Err(Error::InvalidFrequency(hz))
+        }
+    }
+
+    /// Returns the frequency in hertz (Hz).
+    pub const fn hz(self) -> f64 {
+        self.0
+    }
+
+    // Returns the period in nanoseconds.
+    #[cfg(any(feature = "gpio", feature = "pwm"))]
+    pub(crate) fn period_nanos(self) -> f64 {
+        1_000_000_000.0 / self.0
+    }
+}
+
+impl TryFrom<f64> for Frequency {
+    type Error = Error;
+
+    fn try_from(hz: f64) -> Result<Frequency> {
+        Frequency::try_new(hz)
+    }
+}
+
+impl From<Frequency> for f64 {
+    fn from(frequency: Frequency) -> f64 {
+        frequency.0
+    }
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} Hz", self.0)
+    }
+}